@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use jaxp_rust::document::parse_untrusted_bytes;
+use jaxp_rust::writer::serialize;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(doc) = parse_untrusted_bytes(data) {
+        // whatever is accepted must be serializable and parse again
+        let xml = serialize(doc.document().root());
+        parse_untrusted_bytes(xml.as_bytes()).unwrap();
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use jaxp_rust::document::Document;
+use jaxp_rust::testutil::ArbitraryDocument;
+use jaxp_rust::writer::serialize;
+
+fuzz_target!(|generated: ArbitraryDocument| {
+    let xml = generated.to_xml();
+    let doc = Document::parse(&xml).unwrap();
+    assert_eq!(xml, serialize(doc.root()));
+});
@@ -0,0 +1,245 @@
+//! Derive macros for the `FromXml` and `ToXml` traits of `jaxp_rust::binding`, see its documentation.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, Ident, LitStr, PathArguments, Token, Type};
+
+#[proc_macro_derive(FromXml, attributes(xml))]
+pub fn derive_from_xml(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match fields(&input) {
+        Ok(fields) => from_xml(&input, &fields).into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(ToXml, attributes(xml))]
+pub fn derive_to_xml(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match fields(&input) {
+        Ok(fields) => to_xml(&input, &fields).into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+enum Kind {
+    Attribute(String),
+    Child(String),
+    Text,
+    Skip,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Multiplicity {
+    One,
+    Optional,
+    Many,
+}
+
+struct Field<'a> {
+    ident: &'a Ident,
+    kind: Kind,
+    multiplicity: Multiplicity,
+    /// The type of the value, without `Option` or `Vec`
+    ty: &'a Type,
+}
+
+fn fields(input: &DeriveInput) -> syn::Result<Vec<Field<'_>>> {
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "only structs with named fields can be bound to XML")),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "only structs with named fields can be bound to XML")),
+    };
+    let mut fields = vec![];
+    let mut has_text = false;
+    for field in &named.named {
+        let ident = field.ident.as_ref().unwrap();
+        let name = ident.to_string().trim_start_matches("r#").to_string();
+        let mut kind = Kind::Child(name.clone());
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("xml")) {
+            attr.parse_nested_meta(|meta| {
+                let explicit_name = |meta: &syn::meta::ParseNestedMeta| -> syn::Result<String> {
+                    if meta.input.peek(Token![=]) {
+                        Ok(meta.value()?.parse::<LitStr>()?.value())
+                    } else {
+                        Ok(name.clone())
+                    }
+                };
+                if meta.path.is_ident("attr") {
+                    kind = Kind::Attribute(explicit_name(&meta)?);
+                } else if meta.path.is_ident("child") {
+                    kind = Kind::Child(explicit_name(&meta)?);
+                } else if meta.path.is_ident("text") {
+                    kind = Kind::Text;
+                } else if meta.path.is_ident("skip") {
+                    kind = Kind::Skip;
+                } else {
+                    return Err(meta.error("expected attr, child, text or skip"));
+                }
+                Ok(())
+            })?;
+        }
+        let (multiplicity, ty) = match wrapped_type(&field.ty, "Option") {
+            Some(ty) => (Multiplicity::Optional, ty),
+            None => match wrapped_type(&field.ty, "Vec") {
+                Some(ty) => (Multiplicity::Many, ty),
+                None => (Multiplicity::One, &field.ty),
+            },
+        };
+        match kind {
+            Kind::Attribute(_) | Kind::Text if multiplicity == Multiplicity::Many => {
+                return Err(syn::Error::new_spanned(&field.ty, "attributes and text cannot be of type Vec"));
+            }
+            Kind::Text if has_text => return Err(syn::Error::new_spanned(ident, "only one field can hold the text")),
+            Kind::Text => has_text = true,
+            _ => {}
+        }
+        fields.push(Field { ident, kind, multiplicity, ty });
+    }
+    Ok(fields)
+}
+
+/// The type argument of `Option<T>` or `Vec<T>`, by the name of the wrapper.
+fn wrapped_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else { return None };
+    match arguments.args.first() {
+        Some(GenericArgument::Type(ty)) if arguments.args.len() == 1 => Some(ty),
+        _ => None,
+    }
+}
+
+fn from_xml(input: &DeriveInput, fields: &[Field]) -> TokenStream2 {
+    let binding = quote!(::jaxp_rust::binding);
+    let mut reads = vec![];
+    let mut child_arms = vec![];
+    let mut results = vec![];
+    for field in fields {
+        let ident = field.ident;
+        let ty = field.ty;
+        match (&field.kind, field.multiplicity) {
+            (Kind::Attribute(name), Multiplicity::Optional) => {
+                reads.push(quote!(let #ident = start.opt_attribute::<#ty>(#name)?;));
+            }
+            (Kind::Attribute(name), _) => {
+                reads.push(quote! {
+                    let #ident = start.opt_attribute::<#ty>(#name)?.ok_or_else(|| #binding::BindError::MissingAttribute {
+                        element: start.name.to_string(),
+                        name: #name.to_string(),
+                    })?;
+                });
+            }
+            (Kind::Child(name), Multiplicity::Many) => {
+                reads.push(quote!(let mut #ident: ::std::vec::Vec<#ty> = ::std::vec::Vec::new();));
+                child_arms.push(quote!(#name => #ident.push(<#ty as #binding::FromXml>::read_element(reader, &child)?),));
+            }
+            (Kind::Child(name), multiplicity) => {
+                reads.push(quote!(let mut #ident: ::std::option::Option<#ty> = ::std::option::Option::None;));
+                // the first child of the name, as in binding::child
+                child_arms.push(quote!(#name if #ident.is_none() => #ident = ::std::option::Option::Some(<#ty as #binding::FromXml>::read_element(reader, &child)?),));
+                if multiplicity == Multiplicity::One {
+                    results.push(quote! {
+                        let #ident = #ident.ok_or_else(|| #binding::BindError::MissingElement {
+                            parent: start.name.to_string(),
+                            name: #name.to_string(),
+                        })?;
+                    });
+                }
+            }
+            (Kind::Text, multiplicity) => {
+                let value = quote! {
+                    <#ty as #binding::XmlValue>::from_xml_value(&text).ok_or_else(|| #binding::BindError::InvalidValue {
+                        name: start.name.to_string(),
+                        value: text.clone(),
+                    })?
+                };
+                if multiplicity == Multiplicity::Optional {
+                    results.push(quote!(let #ident = if text.is_empty() { ::std::option::Option::None } else { ::std::option::Option::Some(#value) };));
+                } else {
+                    results.push(quote!(let #ident = #value;));
+                }
+            }
+            (Kind::Skip, _) => results.push(quote!(let #ident = ::std::default::Default::default();)),
+        }
+    }
+    let idents = fields.iter().map(|f| f.ident);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics #binding::FromXml for #name #ty_generics #where_clause {
+            fn read_element(reader: &mut #binding::XmlReader, start: &#binding::StartTag) -> ::std::result::Result<Self, #binding::BindError> {
+                #(#reads)*
+                let mut text = ::std::string::String::new();
+                while let ::std::option::Option::Some(child) = reader.next_child(&mut text)? {
+                    match child.local_name() {
+                        #(#child_arms)*
+                        _ => reader.skip()?,
+                    }
+                }
+                #(#results)*
+                ::std::result::Result::Ok(#name { #(#idents),* })
+            }
+        }
+    }
+}
+
+fn to_xml(input: &DeriveInput, fields: &[Field]) -> TokenStream2 {
+    let binding = quote!(::jaxp_rust::binding);
+    let mut attributes = vec![];
+    let mut text = vec![];
+    let mut children = vec![];
+    for field in fields {
+        let ident = field.ident;
+        let ty = field.ty;
+        match (&field.kind, field.multiplicity) {
+            (Kind::Attribute(name), Multiplicity::Optional) => attributes.push(quote! {
+                if let ::std::option::Option::Some(value) = &self.#ident {
+                    writer.attribute(#name, &<#ty as #binding::XmlValue>::to_xml_value(value))?;
+                }
+            }),
+            (Kind::Attribute(name), _) => {
+                attributes.push(quote!(writer.attribute(#name, &<#ty as #binding::XmlValue>::to_xml_value(&self.#ident))?;));
+            }
+            (Kind::Text, Multiplicity::Optional) => text.push(quote! {
+                if let ::std::option::Option::Some(value) = &self.#ident {
+                    writer.text(&<#ty as #binding::XmlValue>::to_xml_value(value))?;
+                }
+            }),
+            (Kind::Text, _) => text.push(quote!(writer.text(&<#ty as #binding::XmlValue>::to_xml_value(&self.#ident))?;)),
+            (Kind::Child(name), Multiplicity::One) => {
+                children.push(quote!(<#ty as #binding::ToXml>::write_element(&self.#ident, #name, writer)?;));
+            }
+            (Kind::Child(name), Multiplicity::Optional) => children.push(quote! {
+                if let ::std::option::Option::Some(value) = &self.#ident {
+                    <#ty as #binding::ToXml>::write_element(value, #name, writer)?;
+                }
+            }),
+            (Kind::Child(name), Multiplicity::Many) => children.push(quote! {
+                for value in &self.#ident {
+                    <#ty as #binding::ToXml>::write_element(value, #name, writer)?;
+                }
+            }),
+            (Kind::Skip, _) => {}
+        }
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics #binding::ToXml for #name #ty_generics #where_clause {
+            fn write_element<W: ::std::io::Write>(&self, name: &str, writer: &mut ::jaxp_rust::writer::XmlWriter<W>) -> ::std::io::Result<()> {
+                writer.start_element(name)?;
+                #(#attributes)*
+                #(#text)*
+                #(#children)*
+                writer.end_element()
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Asynchronous parsing on top of [tokio](https://docs.rs/tokio).
+//!
+//! The input is tokenized construct by construct while it is read, as far as the constructs are complete,
+//! without blocking the calling task. The tree is built once the reader is exhausted.
+//! A construct that fails to tokenize may still be completed by input that is not read yet,
+//! so errors are only reported then.
+
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::chariter::CharIter;
+use crate::document::{Document, OwnedDocument};
+use crate::error::XmlError;
+use crate::options::{ParseOptions, Strictness};
+use crate::textrange::TextRange;
+use crate::token::XmlToken;
+use crate::tokenize::XmlTokenizer;
+
+const CHUNK_LEN: usize = 8 * 1024;
+
+#[derive(Debug)]
+pub enum AsyncParseError {
+    /// Reading failed or the input is not valid UTF-8
+    Io(io::Error),
+    Xml(XmlError),
+}
+
+impl Display for AsyncParseError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            AsyncParseError::Io(e) => write!(f, "Error: {}", e),
+            AsyncParseError::Xml(e) => e.fmt(f),
+        }
+    }
+}
+
+/// Read everything from `reader` and parse it into a document.
+pub async fn parse_async<R: AsyncRead + Unpin>(reader: R) -> Result<OwnedDocument, AsyncParseError> {
+    parse_async_with_options(reader, &ParseOptions::default()).await
+}
+
+/// Read everything from `reader` and parse it into a document.
+/// Tag soup is not tokenized incrementally, it is parsed as a whole once the reader is exhausted.
+pub async fn parse_async_with_options<R: AsyncRead + Unpin>(mut reader: R, options: &ParseOptions) -> Result<OwnedDocument, AsyncParseError> {
+    let mut input = Input::default();
+    if options.strictness == Strictness::TagSoup {
+        while input.read_chunk(&mut reader).await? {}
+        return OwnedDocument::parse_with_options(input.text, options).map_err(AsyncParseError::Xml);
+    }
+    let mut tokenizer = IncrementalTokenizer::new(options);
+    loop {
+        let has_more = input.read_chunk(&mut reader).await?;
+        if !has_more || input.text.len() >= tokenizer.retry_len {
+            tokenizer.tokenize(&input.text, !has_more).map_err(AsyncParseError::Xml)?;
+        }
+        if !has_more {
+            break;
+        }
+    }
+    OwnedDocument::build(input.text, |text| {
+        let tokens = tokenizer.tokens.iter().map(|token| token.map_ranges(|range| TextRange { slice: &text[range.start..range.end], ..range })).collect();
+        Document::from_tokens(text, tokens, options)
+    })
+    .map_err(AsyncParseError::Xml)
+}
+
+/// The text read so far and the bytes of a character that is not read completely.
+#[derive(Default)]
+struct Input {
+    text: String,
+    incomplete_char: Vec<u8>,
+}
+
+impl Input {
+    /// Append the next chunk of the reader to the text. Returns false at the end of the input.
+    async fn read_chunk<R: AsyncRead + Unpin>(&mut self, reader: &mut R) -> Result<bool, AsyncParseError> {
+        let mut chunk = [0; CHUNK_LEN];
+        let len = reader.read(&mut chunk).await.map_err(AsyncParseError::Io)?;
+        if len == 0 {
+            if !self.incomplete_char.is_empty() {
+                return Err(invalid_utf8());
+            }
+            return Ok(false);
+        }
+        // only the first character of the chunk can continue one of the previous chunk
+        let mut bytes = std::mem::take(&mut self.incomplete_char);
+        bytes.extend_from_slice(&chunk[..len]);
+        match std::str::from_utf8(&bytes) {
+            Ok(text) => self.text.push_str(text),
+            Err(e) if e.error_len().is_none() => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                // checked by from_utf8
+                self.text.push_str(std::str::from_utf8(valid).unwrap());
+                self.incomplete_char = rest.to_vec();
+            }
+            Err(_) => return Err(invalid_utf8()),
+        }
+        Ok(true)
+    }
+}
+
+fn invalid_utf8() -> AsyncParseError {
+    AsyncParseError::Io(io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))
+}
+
+/// Tokenizes the constructs of a growing text that are complete.
+struct IncrementalTokenizer {
+    tokenizer: XmlTokenizer,
+    /// The tokens of the complete constructs. Their ranges are offsets in the text, the slices are empty
+    /// because the text may move while it grows.
+    tokens: Vec<XmlToken<'static>>,
+    /// Where the first construct that is not tokenized yet starts
+    pos: usize,
+    has_prolog: bool,
+    /// The text is tokenized again once it is at least this long, so that a long construct
+    /// that arrives in many chunks is not tokenized again for every one of them
+    retry_len: usize,
+}
+
+impl IncrementalTokenizer {
+    fn new(options: &ParseOptions) -> Self {
+        IncrementalTokenizer {
+            tokenizer: XmlTokenizer::with_options(options),
+            tokens: vec![],
+            pos: 0,
+            has_prolog: false,
+            retry_len: 0,
+        }
+    }
+
+    /// Tokenize the constructs that are complete. The last construct in the text may continue
+    /// in the input that is not read yet, unless `is_end`.
+    fn tokenize(&mut self, text: &str, is_end: bool) -> Result<(), XmlError> {
+        let mut buffer = vec![];
+        loop {
+            let mut ci = CharIter { pos: self.pos, text };
+            // the prolog ends before the markup that follows it, which is only known to be the root start tag
+            // once that is tokenized as well
+            let result = if self.has_prolog { Ok(()) } else { self.tokenizer.tokenize_start(&mut ci, &mut buffer) }
+                .and_then(|_| self.tokenizer.tokenize_content_step(&mut ci, &mut buffer).map_err(|e| e.in_construct("document")));
+            if !is_end && (result.is_err() || ci.pos >= text.len()) {
+                self.retry_len = self.pos + 2 * (text.len() - self.pos);
+                return Ok(());
+            }
+            let has_next = result?;
+            self.tokens.extend(buffer.drain(..).map(|token| token.map_ranges(|range| TextRange { slice: "", ..range })));
+            self.pos = ci.pos;
+            self.has_prolog = true;
+            if !has_next {
+                return Ok(());
+            }
+        }
+    }
+}
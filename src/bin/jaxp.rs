@@ -0,0 +1,382 @@
+//! Command line tool to check, format and inspect XML documents.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use jaxp_rust::diff::{diff, Change, DiffOptions};
+use jaxp_rust::document::{Document, Node};
+use jaxp_rust::dump::dump_tokens;
+use jaxp_rust::options::ParseOptions;
+use jaxp_rust::report::ValidationReport;
+use jaxp_rust::textrange::LineIndex;
+use jaxp_rust::stats::stats;
+use jaxp_rust::writer::{serialize, serialize_minified, serialize_pretty, LineEnding, PrettyPrintOptions};
+use jaxp_rust::xpath::XPath;
+
+const USAGE: &str = "\
+Usage: jaxp <command> [arguments] [file]
+
+Reads the document from the file or, if there is none or it is '-', from stdin.
+
+Commands:
+    check                 Check that the document is well-formed
+    check <dir>           Check the XML, DITA and DocBook files below a directory,
+                          exits with 1 if one is not well-formed
+    check --watch <dir> [--interval <ms>]
+                          Check these files whenever they change,
+                          polling every ms milliseconds (default 500), until interrupted
+    fmt [--indent <n>] [--tabs] [--crlf]
+                          Pretty print the document, indented by n spaces (default 2) or by tabs,
+                          with CRLF line breaks instead of LF
+    minify                Remove comments and whitespace between elements
+    stats                 Print element, attribute, text and namespace figures
+    diff [--ignore-whitespace] [--ignore-attr-order] [--format json] <old> <new>
+                          Print the differences between the trees of two documents, exits with 1 if they differ
+    grep <xpath> [file...]
+                          Print the nodes that an XPath expression selects as file:row:col: markup,
+                          exits with 1 if none matches and with 2 if a file is not well-formed
+    tokens [--format json|table] [file]
+                          Print the tokens of the document with their spans, up to the first error
+    get <path>            Print the text of the elements, or the values of the attributes, at a path
+                          like /root/*/item or /root/item/@id
+";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(message) => {
+            eprintln!("jaxp: {}", message);
+            process::exit(2);
+        }
+    }
+}
+
+fn read_input(path: Option<&String>) -> Result<String, String> {
+    match path.map(String::as_str) {
+        None | Some("-") => {
+            let mut xml = String::new();
+            io::stdin().read_to_string(&mut xml).map_err(|e| format!("cannot read stdin: {}", e))?;
+            Ok(xml)
+        }
+        Some(path) => fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e)),
+    }
+}
+
+/// Run a command, returns whether it succeeded. Usage and I/O problems are errors.
+fn run(args: &[String]) -> Result<bool, String> {
+    let command = match args.first() {
+        Some(command) => command.as_str(),
+        None => return Err(USAGE.to_string()),
+    };
+    let mut rest = &args[1..];
+    let mut out = io::stdout();
+    let output = match command {
+        "check" if rest.first().map(String::as_str) == Some("--watch") => return watch(&rest[1..], &mut out),
+        "check" if rest.first().is_some_and(|path| Path::new(path).is_dir()) => {
+            return scan(Path::new(&rest[0]), &mut HashMap::new(), &mut out);
+        }
+        "check" => {
+            let xml = read_input(rest.first())?;
+            Document::parse(&xml).map(|_| "ok\n".to_string())
+        }
+        "minify" => {
+            let xml = read_input(rest.first())?;
+            Document::parse(&xml).map(|doc| serialize_minified(doc.root()) + "\n")
+        }
+        "stats" => {
+            let xml = read_input(rest.first())?;
+            stats(&xml).map(|stats| format!(
+                "elements: {}\nmax depth: {}\nattributes: {}\ntext bytes: {}\nnamespaces: {}\n",
+                stats.element_count, stats.max_depth, stats.attribute_count, stats.text_bytes, stats.namespace_count
+            ))
+        }
+        "fmt" => {
+            let mut options = PrettyPrintOptions::default();
+            loop {
+                match rest.first().map(String::as_str) {
+                    Some("--indent") => {
+                        let width: usize = rest.get(1)
+                            .and_then(|n| n.parse().ok())
+                            .ok_or_else(|| "--indent expects a number".to_string())?;
+                        options.indent = " ".repeat(width);
+                        rest = &rest[2..];
+                    }
+                    Some("--tabs") => {
+                        options.indent = "\t".to_string();
+                        rest = &rest[1..];
+                    }
+                    Some("--crlf") => {
+                        options.line_ending = LineEnding::CrLf;
+                        rest = &rest[1..];
+                    }
+                    _ => break,
+                }
+            }
+            let xml = read_input(rest.first())?;
+            Document::parse(&xml).map(|doc| serialize_pretty(doc.root(), &options) + options.line_ending.as_str())
+        }
+        "tokens" => {
+            let mut is_json = false;
+            if rest.first().map(String::as_str) == Some("--format") {
+                is_json = match rest.get(1).map(String::as_str) {
+                    Some("json") => true,
+                    Some("table") => false,
+                    _ => return Err("--format expects json or table".to_string()),
+                };
+                rest = &rest[2..];
+            }
+            let xml = read_input(rest.first())?;
+            let dump = dump_tokens(&xml, &ParseOptions::default());
+            let output = if is_json { dump.to_json() + "\n" } else { dump.to_table() };
+            out.write_all(output.as_bytes()).map_err(|e| format!("cannot write output: {}", e))?;
+            return Ok(dump.opt_error.is_none());
+        }
+        "get" => {
+            let path = rest.first().ok_or_else(|| "get expects a path".to_string())?;
+            let xml = read_input(rest.get(1))?;
+            match Document::parse(&xml) {
+                Ok(doc) => {
+                    let values = select(doc.root(), path);
+                    if values.is_empty() {
+                        return Ok(false);
+                    }
+                    Ok(values.iter().map(|v| format!("{}\n", v)).collect())
+                }
+                Err(e) => Err(e),
+            }
+        }
+        "diff" => return run_diff(rest, &mut out),
+        "grep" => return run_grep(rest, &mut out),
+        "help" | "--help" | "-h" => Ok(USAGE.to_string()),
+        _ => return Err(format!("unknown command '{}'\n\n{}", command, USAGE)),
+    };
+    match output {
+        Ok(output) => {
+            out.write_all(output.as_bytes()).map_err(|e| format!("cannot write output: {}", e))?;
+            Ok(true)
+        }
+        // the document is not well-formed
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(false)
+        }
+    }
+}
+
+/// Search files with an XPath expression, returns whether any node matches.
+/// Documents that are not well-formed are reported on stderr and fail the command after the others are searched.
+fn run_grep(args: &[String], out: &mut impl Write) -> Result<bool, String> {
+    let expression = args.first().ok_or_else(|| "grep expects an XPath expression".to_string())?;
+    let xpath = XPath::compile(expression).map_err(|e| format!("invalid expression '{}': {}", expression, e))?;
+    let stdin = ["-".to_string()];
+    let paths = if args.len() > 1 { &args[1..] } else { &stdin[..] };
+    let mut has_match = false;
+    let mut has_error = false;
+    for path in paths {
+        let name = if path == "-" { "(standard input)" } else { path.as_str() };
+        let xml = read_input(Some(path))?;
+        let doc = match Document::parse(&xml) {
+            Ok(doc) => doc,
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                has_error = true;
+                continue;
+            }
+        };
+        let items = xpath.select(doc.root()).map_err(|e| format!("cannot evaluate '{}': {}", expression, e))?;
+        let lines = LineIndex::new(&xml);
+        let mut output = String::new();
+        for item in items {
+            has_match = true;
+            let (located, markup) = match item.attribute() {
+                Some(attribute) => (attribute.name(), format!("{}=\"{}\"", attribute.name(), attribute.value())),
+                None if item.node().is_element() => (item.node().tag_name(), serialize(item.node())),
+                None => (item.node().text().unwrap_or(""), serialize(item.node())),
+            };
+            let location = match offset_in(&xml, located) {
+                Some(offset) => {
+                    let pos = lines.pos(offset);
+                    format!("{}:{}:{}", name, pos.row, pos.col)
+                }
+                None => name.to_string(),
+            };
+            // one line per match
+            let first_line = markup.lines().next().unwrap_or("");
+            let ellipsis = if first_line.len() < markup.len() { "..." } else { "" };
+            output.push_str(&format!("{}: {}{}\n", location, first_line, ellipsis));
+        }
+        out.write_all(output.as_bytes()).map_err(|e| format!("cannot write output: {}", e))?;
+    }
+    if has_error {
+        return Err("not all documents are well-formed".to_string());
+    }
+    Ok(has_match)
+}
+
+/// The byte offset of a slice of the input, `None` for other text.
+fn offset_in(input: &str, text: &str) -> Option<usize> {
+    let start = (text.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    Some(start).filter(|start| start + text.len() <= input.len())
+}
+
+/// The extensions of the files that `check <dir>` and `check --watch` check
+const WATCHED_EXTENSIONS: [&str; 5] = ["xml", "dita", "ditamap", "dbk", "docbook"];
+
+/// The versions of files, by which [scan] finds the files that changed
+type Versions = HashMap<PathBuf, (SystemTime, u64)>;
+
+/// Check the files below a directory whenever their modification time or size changes, until interrupted.
+fn watch(args: &[String], out: &mut impl Write) -> Result<bool, String> {
+    let dir = args.first().ok_or_else(|| "--watch expects a directory".to_string())?;
+    let interval = match args.get(1).map(String::as_str) {
+        None => 500,
+        Some("--interval") => args.get(2)
+            .and_then(|ms| ms.parse().ok())
+            .ok_or_else(|| "--interval expects a number".to_string())?,
+        Some(arg) => return Err(format!("unexpected argument '{}'", arg)),
+    };
+    let mut versions = Versions::new();
+    loop {
+        scan(Path::new(dir), &mut versions, out)?;
+        thread::sleep(Duration::from_millis(interval));
+    }
+}
+
+/// Check the files below a directory whose modification time or size differs from their version, and forget the
+/// versions of files that disappeared. Prints `path: ok` or the diagnostics of a file when it is checked,
+/// and `path: removed` when it disappears. Returns whether all checked files are well-formed.
+fn scan(dir: &Path, versions: &mut Versions, out: &mut impl Write) -> Result<bool, String> {
+    let write_error = |e: io::Error| format!("cannot write output: {}", e);
+    let mut files = vec![];
+    collect_files(dir, &mut files).map_err(|e| format!("cannot read {}: {}", dir.display(), e))?;
+    files.sort();
+    let mut is_ok = true;
+    for path in &files {
+        // files may disappear while they are listed
+        let version = match fs::metadata(path).and_then(|m| Ok((m.modified()?, m.len()))) {
+            Ok(version) => version,
+            Err(_) => continue,
+        };
+        if versions.insert(path.clone(), version) == Some(version) {
+            continue;
+        }
+        let output = match fs::read_to_string(path) {
+            Ok(xml) => {
+                let report = ValidationReport::check_well_formedness(&xml, &ParseOptions::default());
+                is_ok &= report.is_valid();
+                if report.diagnostics.is_empty() {
+                    format!("{}: ok\n", path.display())
+                } else {
+                    report.diagnostics.iter()
+                        .map(|d| format!("{}:{}{}\n", path.display(), if d.span.is_some() { "" } else { " " }, d))
+                        .collect()
+                }
+            }
+            Err(e) => {
+                is_ok = false;
+                format!("{}: cannot read: {}\n", path.display(), e)
+            }
+        };
+        out.write_all(output.as_bytes()).map_err(write_error)?;
+    }
+    let mut removed: Vec<PathBuf> = versions.keys().filter(|path| !files.contains(path)).cloned().collect();
+    removed.sort();
+    for path in removed {
+        versions.remove(&path);
+        writeln!(out, "{}: removed", path.display()).map_err(write_error)?;
+    }
+    out.flush().map_err(write_error)?;
+    Ok(is_ok)
+}
+
+/// The files below a directory with one of the [WATCHED_EXTENSIONS].
+/// Symbolic links to directories are not followed, they could lead back up the tree.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(&path, files)?;
+        } else if !path.is_dir() && path.extension().and_then(|e| e.to_str()).is_some_and(|e| WATCHED_EXTENSIONS.contains(&e)) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Compare two documents, returns whether they are equal.
+fn run_diff(mut args: &[String], out: &mut impl Write) -> Result<bool, String> {
+    let mut options = DiffOptions::default();
+    let mut is_json = false;
+    loop {
+        match args.first().map(String::as_str) {
+            Some("--ignore-whitespace") => options.ignore_whitespace = true,
+            Some("--ignore-attr-order") => options.ignore_attribute_order = true,
+            Some("--format") => {
+                match args.get(1).map(String::as_str) {
+                    Some("json") => is_json = true,
+                    Some("text") => is_json = false,
+                    _ => return Err("--format expects json or text".to_string()),
+                }
+                args = &args[1..];
+            }
+            _ => break,
+        }
+        args = &args[1..];
+    }
+    let (old_path, new_path) = match args {
+        [old_path, new_path] => (old_path, new_path),
+        _ => return Err("diff expects two files".to_string()),
+    };
+    let (old_xml, new_xml) = (read_input(Some(old_path))?, read_input(Some(new_path))?);
+    let old = Document::parse(&old_xml).map_err(|e| format!("{}: {}", old_path, e))?;
+    let new = Document::parse(&new_xml).map_err(|e| format!("{}: {}", new_path, e))?;
+    let diff = diff(old.root(), new.root(), &options);
+
+    let output = if is_json {
+        diff.to_json() + "\n"
+    } else if io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none() {
+        diff.changes.iter()
+            .map(|change| {
+                let color = match change {
+                    Change::Added { .. } => "32",
+                    Change::Removed { .. } => "31",
+                    Change::Changed { .. } | Change::AttributesReordered { .. } => "33",
+                };
+                format!("\x1b[{}m{}\x1b[0m\n", color, change)
+            })
+            .collect()
+    } else {
+        diff.to_string()
+    };
+    out.write_all(output.as_bytes()).map_err(|e| format!("cannot write output: {}", e))?;
+    Ok(diff.is_empty())
+}
+
+/// Evaluate a path of element names, `*` matches any element and a last `@name` step selects an attribute.
+fn select<'a>(root: Node<'a, '_>, path: &str) -> Vec<&'a str> {
+    let mut steps: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let attribute = steps.last().and_then(|step| step.strip_prefix('@'));
+    if attribute.is_some() {
+        steps.pop();
+    }
+    let mut nodes = vec![root];
+    for step in steps {
+        nodes = nodes.iter()
+            .flat_map(|n| n.children())
+            .filter(|c| c.is_element() && (step == "*" || c.tag_name() == step))
+            .collect();
+    }
+    match attribute {
+        Some(name) => nodes.iter().filter_map(|n| n.attribute(name)).collect(),
+        None => nodes.iter().map(|n| n.text().unwrap_or("")).collect(),
+    }
+}
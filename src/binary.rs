@@ -0,0 +1,165 @@
+//! Decoding of binary data that is embedded in text as base64 or hex, like the `xs:base64Binary` and
+//! `xs:hexBinary` types of XML Schema. Whitespace between digits is ignored, as in line-wrapped data.
+//!
+//! The decoders accept the text in chunks, e.g. the events of a [PullParser](crate::pull::PullParser)
+//! with a [maximum chunk length](crate::pull::PullParser::set_max_chunk_len):
+//!
+//! ```
+//! use jaxp_rust::binary::Base64Decoder;
+//! use jaxp_rust::pull::{Event, PullParser};
+//!
+//! let mut parser = PullParser::new("<data>SGVsbG8s\n IFdvcmxkIQ==</data>").unwrap();
+//! parser.set_max_chunk_len(Some(4));
+//! let mut decoder = Base64Decoder::new();
+//! let mut bytes = Vec::new();
+//! while let Some(event) = parser.next_event().unwrap() {
+//!     if let Event::Text(chunk) = event {
+//!         decoder.push(chunk, &mut bytes).unwrap();
+//!     }
+//! }
+//! decoder.finish().unwrap();
+//! assert_eq!(b"Hello, World!".to_vec(), bytes);
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BinaryError {
+    /// A character that is not a digit of the encoding, by its byte offset in the whole text
+    InvalidDigit { offset: usize, digit: char },
+    /// The text ends inside of an encoded byte, or inside of a padded group of base64 digits
+    Incomplete,
+}
+
+impl Display for BinaryError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            BinaryError::InvalidDigit { offset, digit } => write!(f, "invalid digit {:?} at offset {}", digit, offset),
+            BinaryError::Incomplete => f.write_str("incomplete binary data"),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+/// [S](https://www.w3.org/TR/xml/#NT-S), which may separate the digits.
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+/// Incremental decoder of base64 with the standard alphabet. Padding with `=` is optional.
+#[derive(Clone, Debug, Default)]
+pub struct Base64Decoder {
+    buffer: u32,
+    bits: u32,
+    /// The number of digits so far, including padding
+    digits: usize,
+    is_padded: bool,
+    offset: usize,
+}
+
+impl Base64Decoder {
+    pub fn new() -> Base64Decoder {
+        Base64Decoder::default()
+    }
+
+    /// Decode the next chunk of text, appending the complete bytes to `out`.
+    pub fn push(&mut self, text: &str, out: &mut Vec<u8>) -> Result<(), BinaryError> {
+        for (i, c) in text.char_indices() {
+            if is_space(c) {
+                continue;
+            }
+            let value = match c {
+                'A'..='Z' => c as u32 - 'A' as u32,
+                'a'..='z' => c as u32 - 'a' as u32 + 26,
+                '0'..='9' => c as u32 - '0' as u32 + 52,
+                '+' => 62,
+                '/' => 63,
+                // padding fills the last group of four digits, after at least two digits
+                '=' if self.digits % 4 >= 2 => {
+                    self.is_padded = true;
+                    self.digits += 1;
+                    continue;
+                }
+                _ => return Err(BinaryError::InvalidDigit { offset: self.offset + i, digit: c }),
+            };
+            if self.is_padded {
+                return Err(BinaryError::InvalidDigit { offset: self.offset + i, digit: c });
+            }
+            self.digits += 1;
+            self.buffer = self.buffer << 6 | value;
+            self.bits += 6;
+            if self.bits >= 8 {
+                self.bits -= 8;
+                out.push((self.buffer >> self.bits) as u8);
+                self.buffer &= (1 << self.bits) - 1;
+            }
+        }
+        self.offset += text.len();
+        Ok(())
+    }
+
+    /// Check that the text does not end inside of a byte or a padded group.
+    pub fn finish(self) -> Result<(), BinaryError> {
+        // a single digit cannot encode a byte
+        if self.bits == 6 || self.is_padded && !self.digits.is_multiple_of(4) {
+            return Err(BinaryError::Incomplete);
+        }
+        Ok(())
+    }
+}
+
+/// Incremental decoder of hex digits in either case.
+#[derive(Clone, Debug, Default)]
+pub struct HexDecoder {
+    opt_high: Option<u8>,
+    offset: usize,
+}
+
+impl HexDecoder {
+    pub fn new() -> HexDecoder {
+        HexDecoder::default()
+    }
+
+    /// Decode the next chunk of text, appending the complete bytes to `out`.
+    pub fn push(&mut self, text: &str, out: &mut Vec<u8>) -> Result<(), BinaryError> {
+        for (i, c) in text.char_indices() {
+            if is_space(c) {
+                continue;
+            }
+            let value = c.to_digit(16).ok_or(BinaryError::InvalidDigit { offset: self.offset + i, digit: c })? as u8;
+            match self.opt_high.take() {
+                Some(high) => out.push(high << 4 | value),
+                None => self.opt_high = Some(value),
+            }
+        }
+        self.offset += text.len();
+        Ok(())
+    }
+
+    /// Check that the text does not end after half of a byte.
+    pub fn finish(self) -> Result<(), BinaryError> {
+        match self.opt_high {
+            Some(_) => Err(BinaryError::Incomplete),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Decode base64 text at once.
+pub fn decode_base64(text: &str) -> Result<Vec<u8>, BinaryError> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut decoder = Base64Decoder::new();
+    decoder.push(text, &mut bytes)?;
+    decoder.finish()?;
+    Ok(bytes)
+}
+
+/// Decode hex text at once.
+pub fn decode_hex(text: &str) -> Result<Vec<u8>, BinaryError> {
+    let mut bytes = Vec::with_capacity(text.len() / 2);
+    let mut decoder = HexDecoder::new();
+    decoder.push(text, &mut bytes)?;
+    decoder.finish()?;
+    Ok(bytes)
+}
@@ -0,0 +1,319 @@
+//! Support for typed data binding, used by the code that [codegen](crate::codegen) generates
+//! and by the [FromXml] and [ToXml] traits.
+//!
+//! Elements are matched by their local name, so bound types read documents with or without a default namespace.
+//!
+//! With the `derive` feature, both traits can be derived for structs with named fields.
+//! Fields are child elements named like the field by default, the `xml` attribute maps them explicitly:
+//! `#[xml(attr)]` and `#[xml(attr = "name")]` for attributes, `#[xml(child = "name")]` for child elements,
+//! `#[xml(text)]` for the text of the element itself and `#[xml(skip)]` for fields that are not mapped.
+//! Attributes and text are [XmlValue]s, child elements may also be types that implement the traits themselves.
+//! Fields of type `Option` are optional, fields of type `Vec` hold every child element of the name.
+//!
+//! ```
+//! # #[cfg(feature = "derive")] {
+//! use jaxp_rust::binding::{from_xml, to_xml, FromXml, ToXml};
+//!
+//! #[derive(Debug, PartialEq, FromXml, ToXml)]
+//! struct Book {
+//!     #[xml(attr)]
+//!     id: u32,
+//!     title: String,
+//!     #[xml(child = "author")]
+//!     authors: Vec<String>,
+//! }
+//!
+//! let book: Book = from_xml("<book id='7'><title>Dune</title><author>Frank Herbert</author></book>").unwrap();
+//! assert_eq!(7, book.id);
+//! assert_eq!("<book id=\"7\"><title>Dune</title><author>Frank Herbert</author></book>", to_xml(&book, "book"));
+//! # }
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::Write;
+
+use crate::document::{Attributes, Node};
+use crate::error::XmlError;
+use crate::namespace::split_qname;
+use crate::pull::{Event, PullParser};
+use crate::refs::decode_entities;
+use crate::types::{parse_boolean, Date, DateTime, Decimal, Duration};
+use crate::writer::XmlWriter;
+use crate::xpath::Item;
+
+#[derive(Debug)]
+pub enum BindError {
+    Xml(XmlError),
+    /// The root element does not have the expected name, but this one
+    UnexpectedRoot { expected: String, found: String },
+    MissingElement { parent: String, name: String },
+    MissingAttribute { element: String, name: String },
+    /// An element or attribute value that cannot be read as its type
+    InvalidValue { name: String, value: String },
+}
+
+impl Display for BindError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            BindError::Xml(e) => write!(f, "{}", e),
+            BindError::UnexpectedRoot { expected, found } => write!(f, "expected root element {}, found {}", expected, found),
+            BindError::MissingElement { parent, name } => write!(f, "missing element {} in {}", name, parent),
+            BindError::MissingAttribute { element, name } => write!(f, "missing attribute {} of {}", name, element),
+            BindError::InvalidValue { name, value } => write!(f, "invalid value {:?} of {}", value, name),
+        }
+    }
+}
+
+impl std::error::Error for BindError {}
+
+#[cfg(feature = "derive")]
+pub use jaxp_derive::{FromXml, ToXml};
+
+impl From<XmlError> for BindError {
+    fn from(e: XmlError) -> Self {
+        BindError::Xml(e)
+    }
+}
+
+/// A type of attribute values and of the content of elements with only text.
+pub trait XmlValue: Sized {
+    /// Read the value from its lexical representation, with references resolved.
+    fn from_xml_value(value: &str) -> Option<Self>;
+
+    fn to_xml_value(&self) -> String;
+}
+
+impl XmlValue for String {
+    fn from_xml_value(value: &str) -> Option<Self> {
+        Some(value.to_string())
+    }
+
+    fn to_xml_value(&self) -> String {
+        self.clone()
+    }
+}
+
+/// `true`, `false`, `1` and `0`, as in `xs:boolean`
+impl XmlValue for bool {
+    fn from_xml_value(value: &str) -> Option<Self> {
+        parse_boolean(value)
+    }
+
+    fn to_xml_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+macro_rules! impl_xml_value {
+    ($($t:ty),*) => {
+        $(
+            impl XmlValue for $t {
+                fn from_xml_value(value: &str) -> Option<Self> {
+                    value.trim().parse().ok()
+                }
+
+                fn to_xml_value(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+impl_xml_value!(i8, i16, i32, i64, u8, u16, u32, u64, f32, f64, char);
+
+macro_rules! impl_xml_value_of_type {
+    ($($t:ty),*) => {
+        $(
+            impl XmlValue for $t {
+                fn from_xml_value(value: &str) -> Option<Self> {
+                    <$t>::parse(value)
+                }
+
+                fn to_xml_value(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+impl_xml_value_of_type!(Decimal, Date, DateTime, Duration);
+
+/// Check the name of the root element of a document.
+pub fn root_element<'a, 'input>(root: Node<'a, 'input>, name: &str) -> Result<Node<'a, 'input>, BindError> {
+    if root.local_name() == name {
+        Ok(root)
+    } else {
+        Err(BindError::UnexpectedRoot { expected: name.to_string(), found: root.tag_name().to_string() })
+    }
+}
+
+/// The text content of an element, as a value.
+pub fn value<T: XmlValue>(node: Node) -> Result<T, BindError> {
+    let text = Item::Node(node).string_value();
+    T::from_xml_value(&text).ok_or_else(|| BindError::InvalidValue { name: node.tag_name().to_string(), value: text })
+}
+
+/// The text of an element without the text of its child elements, as a value.
+pub fn text<T: XmlValue>(node: Node) -> Result<T, BindError> {
+    let text: String = node.children().filter(|c| c.is_text()).map(|c| Item::Node(c).string_value()).collect();
+    T::from_xml_value(&text).ok_or_else(|| BindError::InvalidValue { name: node.tag_name().to_string(), value: text })
+}
+
+pub fn opt_attribute<T: XmlValue>(node: Node, name: &str) -> Result<Option<T>, BindError> {
+    match node.attributes().find(|a| a.name() == name) {
+        Some(attribute) => {
+            let value = decode_entities(attribute.value());
+            match T::from_xml_value(&value) {
+                Some(value) => Ok(Some(value)),
+                None => Err(BindError::InvalidValue { name: name.to_string(), value: value.into_owned() }),
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn attribute<T: XmlValue>(node: Node, name: &str) -> Result<T, BindError> {
+    opt_attribute(node, name)?.ok_or_else(|| BindError::MissingAttribute { element: node.tag_name().to_string(), name: name.to_string() })
+}
+
+/// Read every child element with the local name.
+pub fn children<'a, 'input: 'a, T>(node: Node<'a, 'input>, name: &str, read: impl Fn(Node<'a, 'input>) -> Result<T, BindError>) -> Result<Vec<T>, BindError> {
+    node.child_elements().filter(|c| c.local_name() == name).map(read).collect()
+}
+
+/// Read the first child element with the local name, if any.
+pub fn opt_child<'a, 'input: 'a, T>(node: Node<'a, 'input>, name: &str, read: impl Fn(Node<'a, 'input>) -> Result<T, BindError>) -> Result<Option<T>, BindError> {
+    node.child_elements().find(|c| c.local_name() == name).map(read).transpose()
+}
+
+pub fn child<'a, 'input: 'a, T>(node: Node<'a, 'input>, name: &str, read: impl Fn(Node<'a, 'input>) -> Result<T, BindError>) -> Result<T, BindError> {
+    opt_child(node, name, read)?.ok_or_else(|| BindError::MissingElement { parent: node.tag_name().to_string(), name: name.to_string() })
+}
+
+/// Write an element with a value as its text.
+pub fn write_value<W: Write, T: XmlValue>(writer: &mut XmlWriter<W>, name: &str, value: &T) -> io::Result<()> {
+    writer.start_element(name)?;
+    writer.text(&value.to_xml_value())?;
+    writer.end_element()
+}
+
+/// A start tag read by an [XmlReader].
+#[derive(Debug)]
+pub struct StartTag<'a> {
+    pub name: &'a str,
+    pub attributes: Attributes<'a>,
+}
+
+impl StartTag<'_> {
+    pub fn local_name(&self) -> &str {
+        split_qname(self.name).1
+    }
+
+    /// The value of the attribute with the name, matched by local name.
+    pub fn opt_attribute<T: XmlValue>(&self, name: &str) -> Result<Option<T>, BindError> {
+        match self.attributes.iter().find(|a| split_qname(a.name()).1 == name) {
+            Some(attribute) => {
+                let value = decode_entities(attribute.value());
+                match T::from_xml_value(&value) {
+                    Some(value) => Ok(Some(value)),
+                    None => Err(BindError::InvalidValue { name: name.to_string(), value: value.into_owned() }),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Reads elements with the [pull parser](crate::pull), for implementations of [FromXml].
+pub struct XmlReader<'a> {
+    parser: PullParser<'a>,
+}
+
+impl<'a> XmlReader<'a> {
+    pub fn new(xml: &'a str) -> Result<Self, BindError> {
+        Ok(XmlReader { parser: PullParser::new(xml)? })
+    }
+
+    /// The start tag of the root element.
+    pub fn root(&mut self) -> Result<StartTag<'a>, BindError> {
+        let mut text = String::new();
+        self.next_child(&mut text)?.ok_or_else(|| BindError::MissingElement { parent: "document".to_string(), name: "root element".to_string() })
+    }
+
+    /// The start tag of the next child element of the current element, `None` after its end tag.
+    /// The text before the child is appended to `text`.
+    /// Every child has to be read up to its end tag, by [FromXml::read_element] or [XmlReader::skip].
+    pub fn next_child(&mut self, text: &mut String) -> Result<Option<StartTag<'a>>, BindError> {
+        while let Some(event) = self.parser.next_event()? {
+            match event {
+                Event::StartElement { name, attributes, .. } => return Ok(Some(StartTag { name, attributes })),
+                Event::EndElement { .. } => return Ok(None),
+                Event::Text(t) => text.push_str(&decode_entities(t)),
+                Event::CdataSection(t) => text.push_str(t),
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// The text of the rest of the current element, including that of child elements.
+    pub fn text(&mut self) -> Result<String, BindError> {
+        let mut text = String::new();
+        while self.next_child(&mut text)?.is_some() {
+            let child_text = self.text()?;
+            text.push_str(&child_text);
+        }
+        Ok(text)
+    }
+
+    /// Skip the rest of the current element.
+    pub fn skip(&mut self) -> Result<(), BindError> {
+        Ok(self.parser.skip_to_end_of_current()?)
+    }
+}
+
+/// A type that is read from an element.
+pub trait FromXml: Sized {
+    /// Read the element whose start tag was just read, up to and including its end tag.
+    fn read_element(reader: &mut XmlReader, start: &StartTag) -> Result<Self, BindError>;
+}
+
+/// A type that is written as an element.
+pub trait ToXml {
+    fn write_element<W: Write>(&self, name: &str, writer: &mut XmlWriter<W>) -> io::Result<()>;
+}
+
+/// Values are read from the text of an element.
+impl<T: XmlValue> FromXml for T {
+    fn read_element(reader: &mut XmlReader, start: &StartTag) -> Result<Self, BindError> {
+        let text = reader.text()?;
+        T::from_xml_value(&text).ok_or_else(|| BindError::InvalidValue { name: start.name.to_string(), value: text })
+    }
+}
+
+impl<T: XmlValue> ToXml for T {
+    fn write_element<W: Write>(&self, name: &str, writer: &mut XmlWriter<W>) -> io::Result<()> {
+        write_value(writer, name, self)
+    }
+}
+
+/// Read a document, whatever the name of its root element.
+pub fn from_xml<T: FromXml>(xml: &str) -> Result<T, BindError> {
+    let mut reader = XmlReader::new(xml)?;
+    let root = reader.root()?;
+    let value = T::read_element(&mut reader, &root)?;
+    // read up to the end of the document, to report any errors
+    while reader.parser.next_event()?.is_some() {}
+    Ok(value)
+}
+
+/// Write a value as a document with the root element name.
+pub fn to_xml<T: ToXml>(value: &T, name: &str) -> String {
+    let mut writer = XmlWriter::unchecked(Vec::new());
+    // writing to a Vec cannot fail
+    value.write_element(name, &mut writer).unwrap();
+    String::from_utf8(writer.into_inner()).unwrap()
+}
@@ -2,16 +2,25 @@ use core::result::Result::*;
 use std::ops::Range;
 
 use crate::error::*;
-use crate::error::XmlError::{IllegalToken, UnexpectedEndOfFile};
-use crate::textrange::TextRange;
+use crate::error::XmlError::{IllegalToken, NonMatchingTags, UnexpectedEndOfFile};
+use crate::textrange::{is_continuation_byte, TextRange};
 use crate::xmlchar::{XmlByte, XmlChar};
 
+/// A position in a text that the tokenizer advances, reporting errors at the position.
+/// The byte methods do not check for char boundaries, the `str` and char methods do. Apart from [slice](CharIter::slice)
+/// with a range that is not on char boundaries, no method panics, also not at a position inside of a character,
+/// so that custom tokenizers can mix them.
 pub struct CharIter<'a> {
     pub(crate) pos: usize,
     pub(crate) text: &'a str,
 }
 
 impl<'a> CharIter<'a> {
+    /// An iterator at the start of the text
+    pub fn new(text: &'a str) -> Self {
+        CharIter { pos: 0, text }
+    }
+
     /// Get the underlying text as an owned String
     pub fn text(&self) -> String {
         self.text.to_string()
@@ -27,6 +36,11 @@ impl<'a> CharIter<'a> {
         self.pos < self.text.len()
     }
 
+    /// If the current position is the start of a character or the end of the text
+    pub fn is_char_boundary(&self) -> bool {
+        self.text.is_char_boundary(self.pos)
+    }
+
     /// Get the current character and advance the iterator by the length of that character-
     /// Throws an error if the character is not a valid XML char.
     pub fn next_xml_char(&mut self) -> Result<char, XmlError> {
@@ -36,22 +50,23 @@ impl<'a> CharIter<'a> {
     }
 
     /// Get the current character without advancing the iterator.
-    /// Throws an error if the character is not a valid XML char.
+    /// Throws an error if the character is not a valid XML char, or the position is inside of a character.
     pub fn peek_xml_char(&mut self) -> Result<char, XmlError> {
         let byte = self.peek_byte()?;
-        let c = if byte.is_ascii() {
-            char::from(byte)
+        let opt_c = if byte.is_ascii() {
+            Some(char::from(byte))
         } else {
-            self.text[self.pos..self.text.len()].chars().next().unwrap()
+            self.text.get(self.pos..).and_then(|rest| rest.chars().next())
         };
 
-        if !c.is_xml_char() {
-            return Err(IllegalToken {
+        match opt_c {
+            Some(c) if c.is_xml_char() => Ok(c),
+            _ => Err(IllegalToken {
                 pos: self.error_pos(),
                 expected: None,
-            });
+                fix: None,
+            }),
         }
-        Ok(c)
     }
 
     /// Get the current byte and advance the iterator by one.
@@ -66,20 +81,44 @@ impl<'a> CharIter<'a> {
     /// Does NOT check for char boundaries
     pub fn peek_byte(&self) -> Result<u8, XmlError> {
         if !self.has_next() {
-            return Err(UnexpectedEndOfFile);
+            return Err(self.unexpected_end_of_file());
         }
         Ok(self.text.as_bytes()[self.pos])
     }
 
-    /// Advance the iterator by n
+    /// Advance the iterator by n bytes, up to the end of the text.
+    /// Does NOT check for char boundaries
     pub fn advance_n(&mut self, n: usize) -> Result<(), XmlError> {
-        if !self.has_next() {
-            return Err(UnexpectedEndOfFile);
+        if !self.has_next() || self.pos + n > self.text.len() {
+            return Err(self.unexpected_end_of_file());
         }
         self.pos += n;
         Ok(())
     }
 
+    /// Like [advance_n](CharIter::advance_n), but throws an error instead of advancing into a character.
+    pub fn advance_checked(&mut self, n: usize) -> Result<(), XmlError> {
+        if self.pos + n <= self.text.len() && !self.text.is_char_boundary(self.pos + n) {
+            return Err(IllegalToken { pos: self.error_pos_of(self.pos + n), expected: None, fix: None });
+        }
+        self.advance_n(n)
+    }
+
+    /// Advance the iterator by n characters, which need not be XML chars.
+    pub fn advance_chars(&mut self, n: usize) -> Result<(), XmlError> {
+        let rest = match self.text.get(self.pos..) {
+            Some(rest) => rest,
+            None => return Err(IllegalToken { pos: self.error_pos(), expected: None, fix: None }),
+        };
+        match rest.char_indices().map(|(i, _)| i).chain(Some(rest.len())).nth(n) {
+            Some(len) => {
+                self.pos += len;
+                Ok(())
+            }
+            None => Err(self.unexpected_end_of_file()),
+        }
+    }
+
     /// Advance the iterator by the length of a byte slice
     pub fn skip_over(&mut self, expected: &[u8]) -> Result<(), XmlError> {
         self.advance_n(expected.len())
@@ -108,6 +147,11 @@ impl<'a> CharIter<'a> {
             &self.text.as_bytes()[self.pos..self.pos + test.len()] == test
     }
 
+    /// Test if a specified string starts at the current iterator position, which has to be the start of a character.
+    pub fn test_str(&self, test: &str) -> bool {
+        self.text.get(self.pos..).is_some_and(|rest| rest.starts_with(test))
+    }
+
     /// Test if the specified byte equals the current byte
     pub fn test_byte(&mut self, test: u8) -> bool {
         self.pos < self.text.len() &&
@@ -130,7 +174,8 @@ impl<'a> CharIter<'a> {
         if !self.test(expected) {
             return Err(IllegalToken {
                 pos: self.error_pos(),
-                expected: Some(String::from_utf8(Vec::from(expected)).unwrap()),
+                expected: Some(Expected::Literal(String::from_utf8(Vec::from(expected)).unwrap())),
+                fix: None,
             });
         }
 
@@ -138,12 +183,26 @@ impl<'a> CharIter<'a> {
         Ok(())
     }
 
+    /// Like [expect_bytes](CharIter::expect_bytes), but only at the start of a character, so that the iterator stays on char boundaries.
+    pub fn expect_str(&mut self, expected: &str) -> Result<(), XmlError> {
+        if !self.test_str(expected) {
+            return Err(IllegalToken {
+                pos: self.error_pos(),
+                expected: Some(Expected::Literal(expected.to_string())),
+                fix: None,
+            });
+        }
+        self.pos += expected.len();
+        Ok(())
+    }
+
     /// Test if the current byte equals the expected, return an error if it doesn't.
     pub fn expect_byte(&mut self, expected: u8) -> Result<(), XmlError> {
         if self.peek_byte()? != expected {
             return Err(IllegalToken {
                 pos: self.error_pos(),
-                expected: Some(char::from(expected).to_string()),
+                expected: Some(Expected::Literal(char::from(expected).to_string())),
+                fix: None,
             });
         }
         self.pos += 1;
@@ -156,7 +215,8 @@ impl<'a> CharIter<'a> {
         if !self.peek_byte()?.is_xml_whitespace() {
             return Err(IllegalToken {
                 pos: self.error_pos(),
-                expected: Some("Any space".to_string()),
+                expected: Some(Expected::Space),
+                fix: None,
             });
         }
         self.skip_spaces();
@@ -169,20 +229,15 @@ impl<'a> CharIter<'a> {
         TextRange { start: range.start, end: range.end, slice: &self.text[range] }
     }
 
-    /// Capture the text region that caused an error as an owned, heap-allocated string
+    /// The position of a byte offset in the text, see [XmlErrorPos].
     pub fn error_pos_of(&self, pos: usize) -> XmlErrorPos {
-        assert!(pos < self.text.len());
-        let mut row = 1;
-        let mut last_line_break_index = 0;
-        for i in 0..=pos {
-            if self.text.as_bytes()[i] == b'\n' {
-                row += 1;
-                last_line_break_index = i;
-            }
-        }
+        // the position may be the end of the text
+        let before = &self.text.as_bytes()[..pos.min(self.text.len())];
+        let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
         XmlErrorPos {
-            row,
-            col: pos - last_line_break_index,
+            row: before.iter().filter(|&&b| b == b'\n').count() + 1,
+            // the continuation bytes of a multibyte character do not start one
+            col: before[line_start..].iter().filter(|&&b| !is_continuation_byte(b)).count() + 1,
         }
     }
 
@@ -190,4 +245,51 @@ impl<'a> CharIter<'a> {
     pub fn error_pos(&self) -> XmlErrorPos {
         self.error_pos_of(self.pos)
     }
+
+    /// A non-matching end tag, with a fix for an end tag that differs in case only
+    /// or a start tag that should be an empty-element tag, as an element further out matches the end tag.
+    pub fn non_matching_tags(&self, start_range: TextRange, end_range: TextRange, outer_ranges: &[TextRange]) -> XmlError {
+        let opt_matching_range = outer_ranges.iter().rev().find(|r| r.name_eq(end_range.slice));
+        let fix = if start_range.slice.eq_ignore_ascii_case(end_range.slice) {
+            Some(Fix { start: end_range.start, end: end_range.end, replacement: start_range.slice.to_string() })
+        } else if opt_matching_range.is_some() {
+            let end = self.end_of_start_tag(start_range.end);
+            Some(Fix { start: end, end, replacement: "/".to_string() })
+        } else {
+            None
+        };
+        NonMatchingTags {
+            start_tag: self.error_pos_of(start_range.start),
+            end_tag: self.error_pos_of(end_range.start),
+            expected_name: start_range.slice.to_string(),
+            found_name: end_range.slice.to_string(),
+            matching_start_tag: opt_matching_range.map(|r| self.error_pos_of(r.start)),
+            fix: fix.map(Box::new),
+        }
+    }
+
+    /// The position of the closing `>` of a start tag, skipping quoted attribute values
+    fn end_of_start_tag(&self, from: usize) -> usize {
+        let mut opt_quote = None;
+        for (i, byte) in self.text.bytes().enumerate().skip(from) {
+            match opt_quote {
+                Some(quote) if byte == quote => opt_quote = None,
+                Some(_) => (),
+                None if byte.is_xml_quote() => opt_quote = Some(byte),
+                None if byte == b'>' => return i,
+                None => (),
+            }
+        }
+        self.text.len()
+    }
+
+    /// An end of file error at the end of the text, the construct is named by the caller, see [XmlError::in_construct].
+    pub fn unexpected_end_of_file(&self) -> XmlError {
+        UnexpectedEndOfFile { pos: self.error_pos_of(self.text.len()), construct: String::new(), start: None, name: None }
+    }
+
+    /// An end of file error in an element that has no end tag, with the range of the name of its start tag.
+    pub fn unclosed_element(&self, start_range: TextRange) -> XmlError {
+        self.unexpected_end_of_file().in_construct_at("element", self.error_pos_of(start_range.start - 1), Some(start_range.slice))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,649 @@
+//! Generation of typed Rust structs from a sample document or an XML Schema, for data binding like JAXB.
+//!
+//! The generated code uses the [binding](crate::binding) module: every struct gets `from_node`, `write_element` and `write_content`,
+//! the structs of root elements also `from_document` and `to_document`.
+//! Elements with only text become fields of their parent, other elements and attributes are named in snake case.
+//! The code is meant to be written from a build script and included, e.g.
+//!
+//! ```ignore
+//! // build.rs
+//! let xsd = std::fs::read_to_string("pom.xsd").unwrap();
+//! let code = jaxp_rust::codegen::generate_from_schema(&xsd).unwrap();
+//! std::fs::write(std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("pom.rs"), code).unwrap();
+//!
+//! // lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/pom.rs"));
+//! ```
+//!
+//! Of samples every value is a `String`, fields are optional unless every instance of their element has them
+//! and repeated if any instance has more than one.
+//! Of schemas the built-in types map onto `bool`, `i64`, `f64` and `String`, and sequences, choices and `all` groups,
+//! simple content and mixed content are supported. Type derivation by `complexContent`, groups and wildcards are not.
+//! The text of mixed content is that of the element itself, which is written after its child elements.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Write};
+
+use crate::document::{Document, Node};
+use crate::error::XmlError;
+use crate::namespace::{declared_prefix, split_qname};
+use crate::xpath::Item;
+
+const XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static", "struct", "trait",
+    "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+#[derive(Debug)]
+pub enum CodegenError {
+    Xml(XmlError),
+    /// A schema construct that cannot be bound
+    Unsupported(String),
+    InvalidSchema(String),
+}
+
+impl Display for CodegenError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            CodegenError::Xml(e) => write!(f, "{}", e),
+            CodegenError::Unsupported(construct) => write!(f, "unsupported schema construct: {}", construct),
+            CodegenError::InvalidSchema(reason) => write!(f, "invalid schema: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {}
+
+impl From<XmlError> for CodegenError {
+    fn from(e: XmlError) -> Self {
+        CodegenError::Xml(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FieldKind {
+    Attribute,
+    Element,
+    Text,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Multiplicity {
+    Required,
+    Optional,
+    Repeated,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FieldType {
+    /// A type implementing `XmlValue`
+    Value(&'static str),
+    /// The index of a struct
+    Struct(usize),
+}
+
+#[derive(Clone, Debug)]
+struct Field {
+    xml_name: String,
+    kind: FieldKind,
+    multiplicity: Multiplicity,
+    ty: FieldType,
+}
+
+#[derive(Clone, Debug, Default)]
+struct StructDef {
+    name: String,
+    fields: Vec<Field>,
+}
+
+/// An element that may be the root of a document.
+#[derive(Clone, Debug)]
+struct Root {
+    name: String,
+    opt_namespace: Option<String>,
+    ty: usize,
+}
+
+#[derive(Debug, Default)]
+struct Model {
+    structs: Vec<StructDef>,
+    roots: Vec<Root>,
+}
+
+impl Model {
+    /// Add a struct with a unique name derived from the name of its element or type.
+    fn add_struct(&mut self, xml_name: &str, parent: Option<usize>) -> usize {
+        let mut name = type_name(xml_name);
+        if self.structs.iter().any(|s| s.name == name) {
+            if let Some(parent) = parent {
+                name = format!("{}{}", self.structs[parent].name, name);
+            }
+        }
+        let base = name.clone();
+        let mut i = 2;
+        while self.structs.iter().any(|s| s.name == name) {
+            name = format!("{}{}", base, i);
+            i += 1;
+        }
+        self.structs.push(StructDef { name, fields: vec![] });
+        self.structs.len() - 1
+    }
+
+    /// Whether the struct `to` can be reached from `from` through fields that are not repeated,
+    /// in which case a field of type `from` in `to` needs a box.
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut visited = vec![false; self.structs.len()];
+        let mut stack = vec![from];
+        while let Some(i) = stack.pop() {
+            if i == to {
+                return true;
+            }
+            if std::mem::replace(&mut visited[i], true) {
+                continue;
+            }
+            for field in &self.structs[i].fields {
+                if let (FieldType::Struct(next), false) = (field.ty, field.multiplicity == Multiplicity::Repeated) {
+                    stack.push(next);
+                }
+            }
+        }
+        false
+    }
+}
+
+/// The name of a struct, in upper camel case.
+fn type_name(xml_name: &str) -> String {
+    let mut name = String::new();
+    for part in xml_name.split(|c: char| !c.is_ascii_alphanumeric()).filter(|p| !p.is_empty()) {
+        let mut chars = part.chars();
+        name.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        name.push_str(chars.as_str());
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert(0, 'T');
+    }
+    name
+}
+
+/// The name of a field, in snake case.
+fn field_name(xml_name: &str) -> String {
+    let chars: Vec<char> = xml_name.chars().collect();
+    let mut name = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !name.ends_with('_') {
+                name.push('_');
+            }
+            continue;
+        }
+        // a word starts at an upper case letter after a lower case one, or before one in an acronym like URLPath
+        let is_word_start = c.is_ascii_uppercase()
+            && i > 0
+            && (chars[i - 1].is_ascii_lowercase() || chars[i - 1].is_ascii_digit() || chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase()) && chars[i - 1].is_ascii_uppercase());
+        if is_word_start && !name.ends_with('_') {
+            name.push('_');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    let name = name.trim_matches('_').to_string();
+    match name {
+        n if n.is_empty() || n.starts_with(|c: char| c.is_ascii_digit()) => format!("_{}", n),
+        n if n == "self" || n == "super" || n == "crate" || n == "Self" => format!("{}_", n),
+        n if KEYWORDS.contains(&n.as_str()) => format!("r#{}", n),
+        n => n,
+    }
+}
+
+/// Infer the structs from a sample document.
+pub fn generate_from_sample(xml: &str) -> Result<String, CodegenError> {
+    let doc = Document::parse(xml)?;
+    Ok(generate(&infer_model(&doc)))
+}
+
+#[derive(Default)]
+struct ElementStats {
+    instances: usize,
+    /// attribute names with the number of instances that have them
+    attributes: Vec<(String, usize)>,
+    /// child names with the number of instances that have them and the largest number in an instance
+    children: Vec<(String, usize, usize)>,
+    has_text: bool,
+}
+
+fn collect_stats<'a>(node: Node<'a, '_>, stats: &mut Vec<(&'a str, ElementStats)>) {
+    let name = node.local_name();
+    let i = match stats.iter().position(|(n, _)| *n == name) {
+        Some(i) => i,
+        None => {
+            stats.push((name, ElementStats::default()));
+            stats.len() - 1
+        }
+    };
+    let mut child_counts: Vec<(&str, usize)> = vec![];
+    for child in node.child_elements() {
+        match child_counts.iter_mut().find(|(n, _)| *n == child.local_name()) {
+            Some((_, count)) => *count += 1,
+            None => child_counts.push((child.local_name(), 1)),
+        }
+    }
+    let element = &mut stats[i].1;
+    element.instances += 1;
+    for attribute in node.attributes().filter(|a| declared_prefix(a.name()).is_none()) {
+        match element.attributes.iter_mut().find(|(n, _)| n == attribute.name()) {
+            Some((_, count)) => *count += 1,
+            None => element.attributes.push((attribute.name().to_string(), 1)),
+        }
+    }
+    for (child, count) in child_counts {
+        match element.children.iter_mut().find(|(n, _, _)| n == child) {
+            Some((_, instances, max)) => {
+                *instances += 1;
+                *max = (*max).max(count);
+            }
+            None => element.children.push((child.to_string(), 1, count)),
+        }
+    }
+    element.has_text |= node.children().any(|c| c.is_text() && !Item::Node(c).string_value().trim().is_empty());
+    for child in node.child_elements() {
+        collect_stats(child, stats);
+    }
+}
+
+fn infer_model(doc: &Document) -> Model {
+    let mut stats = vec![];
+    let root = doc.root_element();
+    collect_stats(root, &mut stats);
+    let is_leaf = |s: &ElementStats| s.attributes.is_empty() && s.children.is_empty();
+    let mut model = Model::default();
+    // the root is a struct even if it only has text
+    let mut indices = HashMap::new();
+    for (name, element) in &stats {
+        if !is_leaf(element) || *name == root.local_name() {
+            indices.insert(*name, model.add_struct(name, None));
+        }
+    }
+    for (name, element) in &stats {
+        let i = match indices.get(name) {
+            Some(&i) => i,
+            None => continue,
+        };
+        let multiplicity = |instances: usize| if instances == element.instances { Multiplicity::Required } else { Multiplicity::Optional };
+        let mut fields: Vec<Field> = element
+            .attributes
+            .iter()
+            .map(|(attribute, count)| Field { xml_name: attribute.clone(), kind: FieldKind::Attribute, multiplicity: multiplicity(*count), ty: FieldType::Value("String") })
+            .collect();
+        for (child, instances, max) in &element.children {
+            let ty = indices.get(child.as_str()).map_or(FieldType::Value("String"), |&i| FieldType::Struct(i));
+            let multiplicity = if *max > 1 { Multiplicity::Repeated } else { multiplicity(*instances) };
+            fields.push(Field { xml_name: child.clone(), kind: FieldKind::Element, multiplicity, ty });
+        }
+        if element.has_text {
+            fields.push(Field { xml_name: "text".to_string(), kind: FieldKind::Text, multiplicity: Multiplicity::Required, ty: FieldType::Value("String") });
+        }
+        model.structs[i].fields = fields;
+    }
+    model.roots.push(Root { name: root.local_name().to_string(), opt_namespace: root.namespace_uri().map(str::to_string), ty: indices[root.local_name()] });
+    model
+}
+
+/// Generate structs for the global elements of an XML Schema that have complex types.
+pub fn generate_from_schema(xsd: &str) -> Result<String, CodegenError> {
+    let doc = Document::parse(xsd)?;
+    let schema = doc.root_element();
+    if !schema.has_tag_name_ns(XSD_NAMESPACE, "schema") {
+        return Err(CodegenError::InvalidSchema(format!("the root element {} is not a schema", schema.tag_name())));
+    }
+    let mut reader = SchemaReader { schema, model: Model::default(), named_types: HashMap::new(), global_elements: HashMap::new() };
+    for element in schema.children_named_ns(XSD_NAMESPACE, "element") {
+        let name = required_attribute(element, "name")?;
+        if let FieldType::Struct(ty) = reader.global_element_type(element)? {
+            let opt_namespace = schema.attribute("targetNamespace").map(str::to_string);
+            reader.model.roots.push(Root { name: name.to_string(), opt_namespace, ty });
+        }
+    }
+    Ok(generate(&reader.model))
+}
+
+fn required_attribute<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str, CodegenError> {
+    node.attribute(name).ok_or_else(|| CodegenError::InvalidSchema(format!("{} without {}", node.tag_name(), name)))
+}
+
+/// The Rust type of a built-in simple type.
+fn builtin_type(local_name: &str) -> &'static str {
+    match local_name {
+        "boolean" => "bool",
+        "byte" | "short" | "int" | "long" | "integer" | "negativeInteger" | "nonNegativeInteger" | "nonPositiveInteger"
+        | "positiveInteger" | "unsignedByte" | "unsignedShort" | "unsignedInt" | "unsignedLong" => "i64",
+        "float" | "double" | "decimal" => "f64",
+        _ => "String",
+    }
+}
+
+struct SchemaReader<'a, 'input> {
+    schema: Node<'a, 'input>,
+    model: Model,
+    /// the structs of named complex types
+    named_types: HashMap<&'a str, usize>,
+    /// the types of global elements, which may be referenced repeatedly
+    global_elements: HashMap<&'a str, FieldType>,
+}
+
+impl<'a, 'input> SchemaReader<'a, 'input> {
+    fn global(&self, kind: &str, name: &str) -> Option<Node<'a, 'input>> {
+        self.schema.children_named_ns(XSD_NAMESPACE, kind).find(|n| n.attribute("name") == Some(name))
+    }
+
+    /// Resolve a reference to a global definition, which is in the schema namespace for built-in types.
+    fn resolve(&self, node: Node<'a, 'input>, qname: &'a str) -> (Option<&'a str>, &'a str) {
+        let (opt_prefix, local_name) = split_qname(qname);
+        (node.lookup_namespace(opt_prefix), local_name)
+    }
+
+    fn global_element_type(&mut self, element: Node<'a, 'input>) -> Result<FieldType, CodegenError> {
+        let name = required_attribute(element, "name")?;
+        if let Some(&ty) = self.global_elements.get(name) {
+            return Ok(ty);
+        }
+        let opt_complex_type = element.child_element_ns(XSD_NAMESPACE, "complexType").filter(|_| !element.has_attribute("type"));
+        if let Some(complex_type) = opt_complex_type {
+            // registered before its fields are read, for recursive references
+            let i = self.model.add_struct(name, None);
+            self.global_elements.insert(name, FieldType::Struct(i));
+            self.model.structs[i].fields = self.complex_type(complex_type, i)?;
+            return Ok(FieldType::Struct(i));
+        }
+        let ty = self.element_type(element, None)?;
+        self.global_elements.insert(name, ty);
+        Ok(ty)
+    }
+
+    /// The type of an element: a struct for complex types, a value type for simple ones.
+    fn element_type(&mut self, element: Node<'a, 'input>, parent: Option<usize>) -> Result<FieldType, CodegenError> {
+        if let Some(type_name) = element.attribute("type") {
+            return match self.resolve(element, type_name) {
+                (Some(XSD_NAMESPACE), local_name) => Ok(FieldType::Value(builtin_type(local_name))),
+                (_, local_name) => self.named_type(local_name),
+            };
+        }
+        if let Some(complex_type) = element.child_element_ns(XSD_NAMESPACE, "complexType") {
+            let i = self.model.add_struct(required_attribute(element, "name")?, parent);
+            self.model.structs[i].fields = self.complex_type(complex_type, i)?;
+            return Ok(FieldType::Struct(i));
+        }
+        match element.child_element_ns(XSD_NAMESPACE, "simpleType") {
+            Some(simple_type) => Ok(FieldType::Value(self.simple_type(simple_type)?)),
+            None => Ok(FieldType::Value("String")),
+        }
+    }
+
+    fn named_type(&mut self, name: &'a str) -> Result<FieldType, CodegenError> {
+        if let Some(&i) = self.named_types.get(name) {
+            return Ok(FieldType::Struct(i));
+        }
+        if let Some(complex_type) = self.global("complexType", name) {
+            // registered before its fields are read, for recursive types
+            let i = self.model.add_struct(name, None);
+            self.named_types.insert(name, i);
+            self.model.structs[i].fields = self.complex_type(complex_type, i)?;
+            return Ok(FieldType::Struct(i));
+        }
+        match self.global("simpleType", name) {
+            Some(simple_type) => Ok(FieldType::Value(self.simple_type(simple_type)?)),
+            None => Err(CodegenError::InvalidSchema(format!("unknown type {}", name))),
+        }
+    }
+
+    /// The value type of a simple type, lists and unions are strings.
+    fn simple_type(&mut self, simple_type: Node<'a, 'input>) -> Result<&'static str, CodegenError> {
+        let restriction = match simple_type.child_element_ns(XSD_NAMESPACE, "restriction") {
+            Some(restriction) => restriction,
+            None => return Ok("String"),
+        };
+        match restriction.attribute("base").map(|base| self.resolve(restriction, base)) {
+            Some((Some(XSD_NAMESPACE), local_name)) => Ok(builtin_type(local_name)),
+            Some((_, local_name)) => match self.named_type(local_name)? {
+                FieldType::Value(ty) => Ok(ty),
+                FieldType::Struct(_) => Err(CodegenError::InvalidSchema(format!("simple type restricts complex type {}", local_name))),
+            },
+            None => match restriction.child_element_ns(XSD_NAMESPACE, "simpleType") {
+                Some(base) => self.simple_type(base),
+                None => Ok("String"),
+            },
+        }
+    }
+
+    fn attribute(&mut self, attribute: Node<'a, 'input>) -> Result<Field, CodegenError> {
+        if attribute.has_attribute("ref") {
+            return Err(CodegenError::Unsupported("attribute reference".to_string()));
+        }
+        let ty = match (attribute.attribute("type"), attribute.child_element_ns(XSD_NAMESPACE, "simpleType")) {
+            (Some(type_name), _) => match self.resolve(attribute, type_name) {
+                (Some(XSD_NAMESPACE), local_name) => FieldType::Value(builtin_type(local_name)),
+                (_, local_name) => self.named_type(local_name)?,
+            },
+            (None, Some(simple_type)) => FieldType::Value(self.simple_type(simple_type)?),
+            (None, None) => FieldType::Value("String"),
+        };
+        let multiplicity = if attribute.attribute("use") == Some("required") { Multiplicity::Required } else { Multiplicity::Optional };
+        Ok(Field { xml_name: required_attribute(attribute, "name")?.to_string(), kind: FieldKind::Attribute, multiplicity, ty })
+    }
+
+    fn complex_type(&mut self, complex_type: Node<'a, 'input>, i: usize) -> Result<Vec<Field>, CodegenError> {
+        let mut fields = vec![];
+        for child in complex_type.child_elements() {
+            match (child.namespace_uri(), child.local_name()) {
+                (Some(XSD_NAMESPACE), "sequence" | "choice" | "all") => self.particles(child, Multiplicity::Required, i, &mut fields)?,
+                (Some(XSD_NAMESPACE), "attribute") => fields.push(self.attribute(child)?),
+                (Some(XSD_NAMESPACE), "simpleContent") => {
+                    let extension = child
+                        .child_elements()
+                        .find(|c| c.has_tag_name_ns(XSD_NAMESPACE, "extension") || c.has_tag_name_ns(XSD_NAMESPACE, "restriction"))
+                        .ok_or_else(|| CodegenError::InvalidSchema("simpleContent without extension".to_string()))?;
+                    let ty = match self.resolve(extension, required_attribute(extension, "base")?) {
+                        (Some(XSD_NAMESPACE), local_name) => FieldType::Value(builtin_type(local_name)),
+                        (_, local_name) => self.named_type(local_name)?,
+                    };
+                    let ty = match ty {
+                        FieldType::Value(_) => ty,
+                        FieldType::Struct(_) => return Err(CodegenError::Unsupported("simpleContent of a complex type".to_string())),
+                    };
+                    for attribute in extension.children_named_ns(XSD_NAMESPACE, "attribute") {
+                        fields.push(self.attribute(attribute)?);
+                    }
+                    fields.push(Field { xml_name: "value".to_string(), kind: FieldKind::Text, multiplicity: Multiplicity::Required, ty });
+                }
+                (Some(XSD_NAMESPACE), "annotation" | "anyAttribute") => {}
+                _ => return Err(CodegenError::Unsupported(child.tag_name().to_string())),
+            }
+        }
+        if complex_type.attribute("mixed") == Some("true") {
+            fields.push(Field { xml_name: "text".to_string(), kind: FieldKind::Text, multiplicity: Multiplicity::Required, ty: FieldType::Value("String") });
+        }
+        Ok(fields)
+    }
+
+    /// Add the elements of a model group, whose own occurrence and kind make its elements optional or repeated.
+    fn particles(&mut self, group: Node<'a, 'input>, outer: Multiplicity, i: usize, fields: &mut Vec<Field>) -> Result<(), CodegenError> {
+        let group_multiplicity = combine(outer, occurrence(group)?);
+        let group_multiplicity = match (group.local_name(), group_multiplicity) {
+            ("choice", Multiplicity::Required) => Multiplicity::Optional,
+            (_, multiplicity) => multiplicity,
+        };
+        for particle in group.child_elements() {
+            match (particle.namespace_uri(), particle.local_name()) {
+                (Some(XSD_NAMESPACE), "element") => {
+                    let multiplicity = combine(group_multiplicity, occurrence(particle)?);
+                    let (xml_name, ty) = match particle.attribute("ref") {
+                        Some(reference) => {
+                            let (_, local_name) = self.resolve(particle, reference);
+                            let element = self.global("element", local_name).ok_or_else(|| CodegenError::InvalidSchema(format!("unknown element {}", local_name)))?;
+                            (local_name, self.global_element_type(element)?)
+                        }
+                        None => (required_attribute(particle, "name")?, self.element_type(particle, Some(i))?),
+                    };
+                    fields.push(Field { xml_name: xml_name.to_string(), kind: FieldKind::Element, multiplicity, ty });
+                }
+                (Some(XSD_NAMESPACE), "sequence" | "choice" | "all") => self.particles(particle, group_multiplicity, i, fields)?,
+                (Some(XSD_NAMESPACE), "annotation") => {}
+                _ => return Err(CodegenError::Unsupported(particle.tag_name().to_string())),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The multiplicity given by `minOccurs` and `maxOccurs`.
+fn occurrence(particle: Node) -> Result<Multiplicity, CodegenError> {
+    let invalid = |value: &str| CodegenError::InvalidSchema(format!("invalid occurrence {}", value));
+    let min_occurs = match particle.attribute("minOccurs") {
+        Some(value) => value.trim().parse::<u64>().map_err(|_| invalid(value))?,
+        None => 1,
+    };
+    let max_occurs = match particle.attribute("maxOccurs").map(str::trim) {
+        Some("unbounded") => u64::MAX,
+        Some(value) => value.parse::<u64>().map_err(|_| invalid(value))?,
+        None => 1,
+    };
+    Ok(match (min_occurs, max_occurs) {
+        (_, max) if max > 1 => Multiplicity::Repeated,
+        (0, _) => Multiplicity::Optional,
+        _ => Multiplicity::Required,
+    })
+}
+
+/// The multiplicity of a particle in a group with a multiplicity.
+fn combine(outer: Multiplicity, inner: Multiplicity) -> Multiplicity {
+    match (outer, inner) {
+        (Multiplicity::Repeated, _) | (_, Multiplicity::Repeated) => Multiplicity::Repeated,
+        (Multiplicity::Optional, _) | (_, Multiplicity::Optional) => Multiplicity::Optional,
+        _ => Multiplicity::Required,
+    }
+}
+
+/// Generate the source code of a model.
+fn generate(model: &Model) -> String {
+    let mut code = String::from("// Generated by jaxp_rust::codegen, do not edit.\n");
+    for (i, def) in model.structs.iter().enumerate() {
+        // field names are unique within a struct
+        let mut names: Vec<String> = vec![];
+        for field in &def.fields {
+            let base = field_name(&field.xml_name);
+            let mut name = base.clone();
+            let mut n = 2;
+            while names.contains(&name) {
+                name = format!("{}_{}", base.trim_start_matches("r#"), n);
+                n += 1;
+            }
+            names.push(name);
+        }
+        let type_of = |field: &Field| {
+            let (ty, boxed) = match field.ty {
+                FieldType::Value(ty) => (ty.to_string(), false),
+                FieldType::Struct(s) => (model.structs[s].name.clone(), field.multiplicity != Multiplicity::Repeated && model.reaches(s, i)),
+            };
+            let ty = if boxed { format!("Box<{}>", ty) } else { ty };
+            match field.multiplicity {
+                Multiplicity::Required => ty,
+                Multiplicity::Optional => format!("Option<{}>", ty),
+                Multiplicity::Repeated => format!("Vec<{}>", ty),
+            }
+        };
+        let read = |field: &Field| match field.ty {
+            FieldType::Value(_) => "jaxp_rust::binding::value".to_string(),
+            FieldType::Struct(s) if field.multiplicity != Multiplicity::Repeated && model.reaches(s, i) => {
+                format!("|n| {}::from_node(n).map(Box::new)", model.structs[s].name)
+            }
+            FieldType::Struct(s) => format!("{}::from_node", model.structs[s].name),
+        };
+
+        writeln!(code).unwrap();
+        writeln!(code, "#[derive(Clone, Debug, Default, PartialEq)]").unwrap();
+        writeln!(code, "pub struct {} {{", def.name).unwrap();
+        for (field, name) in def.fields.iter().zip(&names) {
+            writeln!(code, "    pub {}: {},", name, type_of(field)).unwrap();
+        }
+        writeln!(code, "}}").unwrap();
+
+        writeln!(code).unwrap();
+        writeln!(code, "impl {} {{", def.name).unwrap();
+        writeln!(code, "    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {{").unwrap();
+        writeln!(code, "        Ok({} {{", def.name).unwrap();
+        for (field, name) in def.fields.iter().zip(&names) {
+            let expression = match (field.kind, field.multiplicity) {
+                (FieldKind::Attribute, Multiplicity::Optional) => format!("jaxp_rust::binding::opt_attribute(node, {:?})?", field.xml_name),
+                (FieldKind::Attribute, _) => format!("jaxp_rust::binding::attribute(node, {:?})?", field.xml_name),
+                (FieldKind::Element, Multiplicity::Required) => format!("jaxp_rust::binding::child(node, {:?}, {})?", field.xml_name, read(field)),
+                (FieldKind::Element, Multiplicity::Optional) => format!("jaxp_rust::binding::opt_child(node, {:?}, {})?", field.xml_name, read(field)),
+                (FieldKind::Element, Multiplicity::Repeated) => format!("jaxp_rust::binding::children(node, {:?}, {})?", field.xml_name, read(field)),
+                (FieldKind::Text, _) => "jaxp_rust::binding::text(node)?".to_string(),
+            };
+            writeln!(code, "            {}: {},", name, expression).unwrap();
+        }
+        writeln!(code, "        }})").unwrap();
+        writeln!(code, "    }}").unwrap();
+
+        writeln!(code).unwrap();
+        writeln!(code, "    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {{").unwrap();
+        writeln!(code, "        writer.start_element(name)?;").unwrap();
+        writeln!(code, "        self.write_content(writer)?;").unwrap();
+        writeln!(code, "        writer.end_element()").unwrap();
+        writeln!(code, "    }}").unwrap();
+
+        writeln!(code).unwrap();
+        writeln!(code, "    /// Write the attributes and the content, right after the start tag.").unwrap();
+        writeln!(code, "    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {{").unwrap();
+        // attributes have to directly follow the start tag, text is written after the child elements
+        let mut fields: Vec<(&Field, &String)> = def.fields.iter().zip(&names).collect();
+        fields.sort_by_key(|(f, _)| match f.kind {
+            FieldKind::Attribute => 0,
+            FieldKind::Element => 1,
+            FieldKind::Text => 2,
+        });
+        for (field, name) in fields {
+            let write = |value: &str| match (field.kind, field.ty) {
+                (FieldKind::Attribute, _) => format!("writer.attribute({:?}, &jaxp_rust::binding::XmlValue::to_xml_value({}))?;", field.xml_name, value),
+                (FieldKind::Element, FieldType::Value(_)) => format!("jaxp_rust::binding::write_value(writer, {:?}, {})?;", field.xml_name, value),
+                (FieldKind::Element, FieldType::Struct(_)) => format!("{}.write_element({:?}, writer)?;", value.trim_start_matches('&'), field.xml_name),
+                (FieldKind::Text, _) => format!("writer.text(&jaxp_rust::binding::XmlValue::to_xml_value({}))?;", value),
+            };
+            match field.multiplicity {
+                Multiplicity::Required => writeln!(code, "        {}", write(&format!("&self.{}", name))).unwrap(),
+                Multiplicity::Optional => writeln!(code, "        if let Some(value) = &self.{} {{\n            {}\n        }}", name, write("value")).unwrap(),
+                Multiplicity::Repeated => writeln!(code, "        for value in &self.{} {{\n            {}\n        }}", name, write("value")).unwrap(),
+            }
+        }
+        writeln!(code, "        Ok(())").unwrap();
+        writeln!(code, "    }}").unwrap();
+
+        for root in model.roots.iter().filter(|r| r.ty == i) {
+            // a struct of several global elements reads and writes the first one as a document
+            if model.roots.iter().find(|r| r.ty == i).map(|r| &r.name) != Some(&root.name) {
+                continue;
+            }
+            writeln!(code).unwrap();
+            writeln!(code, "    pub fn from_document(doc: &jaxp_rust::document::Document) -> Result<Self, jaxp_rust::binding::BindError> {{").unwrap();
+            writeln!(code, "        Self::from_node(jaxp_rust::binding::root_element(doc.root_element(), {:?})?)", root.name).unwrap();
+            writeln!(code, "    }}").unwrap();
+            writeln!(code).unwrap();
+            writeln!(code, "    pub fn to_document(&self) -> jaxp_rust::document::OwnedDocument {{").unwrap();
+            writeln!(code, "        let mut writer = jaxp_rust::writer::XmlWriter::new(Vec::new());").unwrap();
+            writeln!(code, "        // writing to a Vec cannot fail").unwrap();
+            writeln!(code, "        writer.start_element({:?}).unwrap();", root.name).unwrap();
+            if let Some(namespace) = &root.opt_namespace {
+                writeln!(code, "        writer.attribute(\"xmlns\", {:?}).unwrap();", namespace).unwrap();
+            }
+            writeln!(code, "        self.write_content(&mut writer).unwrap();").unwrap();
+            writeln!(code, "        writer.end_element().unwrap();").unwrap();
+            writeln!(code, "        let xml = String::from_utf8(writer.into_inner()).unwrap();").unwrap();
+            writeln!(code, "        jaxp_rust::document::OwnedDocument::parse(xml).expect(\"the written document is well-formed\")").unwrap();
+            writeln!(code, "    }}").unwrap();
+        }
+        writeln!(code, "}}").unwrap();
+    }
+    code
+}
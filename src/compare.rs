@@ -0,0 +1,205 @@
+//! Comparison of jaxp with another parser, which reports the first event at which the two disagree about a document,
+//! e.g. to harden jaxp against a reference parser or to check that a migration between parsers preserves the content.
+//!
+//! Both parsers are reduced to a stream of [ComparedEvent]s that ignores what parsers commonly report differently:
+//! the prolog, text outside of the root element, how text is split into events and whether it is a CDATA section.
+//! Another parser is connected by mapping its events or its tree to [ComparedEvent]s, with the `quick-xml` feature
+//! an adapter for [quick-xml](https://docs.rs/quick-xml) is included.
+//!
+//! ```
+//! use jaxp_rust::compare::{compare, ComparedEvent};
+//! use jaxp_rust::options::ParseOptions;
+//!
+//! let reference = vec![
+//!     ComparedEvent::StartElement { name: "a".to_string(), attributes: vec![] },
+//!     ComparedEvent::Text("x & y".to_string()),
+//!     ComparedEvent::EndElement { name: "a".to_string() },
+//! ];
+//! assert!(compare("<a>x &amp; <![CDATA[y]]></a>", &ParseOptions::default(), reference).is_none());
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use crate::options::ParseOptions;
+use crate::pull::{Event, PullParser};
+use crate::refs::decode_entities_with;
+
+/// An event of a document as seen by a parser, with references resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ComparedEvent {
+    /// The attributes in the order of the start tag
+    StartElement { name: String, attributes: Vec<(String, String)> },
+    EndElement { name: String },
+    /// Text and CDATA sections between markup, merged
+    Text(String),
+    Comment(String),
+    ProcessingInstruction { target: String, opt_value: Option<String> },
+    /// The parser rejects the document, the message is not compared
+    Error(String),
+}
+
+/// The first event at which two parsers disagree, `None` where one stream ends earlier than the other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// The index of the events in the normalized streams
+    pub index: usize,
+    pub jaxp: Option<ComparedEvent>,
+    pub reference: Option<ComparedEvent>,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let describe = |opt_event: &Option<ComparedEvent>| match opt_event {
+            Some(event) => format!("{:?}", event),
+            None => "end of document".to_string(),
+        };
+        write!(f, "event {}: jaxp reports {}, the reference parser {}", self.index, describe(&self.jaxp), describe(&self.reference))
+    }
+}
+
+/// Bring the events of a parser into the form that is compared: text outside of the root element is dropped,
+/// adjacent text is merged, empty text is dropped and nothing follows an error.
+pub fn normalize(events: impl IntoIterator<Item=ComparedEvent>) -> Vec<ComparedEvent> {
+    let mut normalized: Vec<ComparedEvent> = vec![];
+    let mut depth = 0usize;
+    for event in events {
+        match event {
+            ComparedEvent::Text(text) if depth == 0 || text.is_empty() => continue,
+            ComparedEvent::Text(text) => {
+                if let Some(ComparedEvent::Text(previous)) = normalized.last_mut() {
+                    previous.push_str(&text);
+                    continue;
+                }
+                normalized.push(ComparedEvent::Text(text));
+            }
+            ComparedEvent::Error(message) => {
+                normalized.push(ComparedEvent::Error(message));
+                break;
+            }
+            event => {
+                match event {
+                    ComparedEvent::StartElement { .. } => depth += 1,
+                    ComparedEvent::EndElement { .. } => depth = depth.saturating_sub(1),
+                    _ => (),
+                }
+                normalized.push(event);
+            }
+        }
+    }
+    normalized
+}
+
+/// The events of jaxp's [pull parser](crate::pull), not yet normalized.
+pub fn jaxp_events(xml: &str, options: &ParseOptions) -> Vec<ComparedEvent> {
+    let decode = |text: &str| decode_entities_with(text, &options.entities).into_owned();
+    let mut parser = match PullParser::with_options(xml, options) {
+        Ok(parser) => parser,
+        Err(e) => return vec![ComparedEvent::Error(e.to_string())],
+    };
+    let mut events = vec![];
+    loop {
+        let event = match parser.next_event() {
+            Ok(Some(event)) => event,
+            Ok(None) => break,
+            Err(e) => {
+                events.push(ComparedEvent::Error(e.to_string()));
+                break;
+            }
+        };
+        events.push(match event {
+            Event::StartElement { name, attributes, .. } => ComparedEvent::StartElement {
+                name: name.to_string(),
+                attributes: attributes.iter().map(|a| (a.name().to_string(), decode(a.value()))).collect(),
+            },
+            Event::EndElement { name, .. } => ComparedEvent::EndElement { name: name.to_string() },
+            Event::Text(text) => ComparedEvent::Text(decode(text)),
+            Event::CdataSection(text) => ComparedEvent::Text(text.to_string()),
+            Event::Comment(text) => ComparedEvent::Comment(text.to_string()),
+            Event::ProcessingInstruction { target, opt_value } => ComparedEvent::ProcessingInstruction {
+                target: target.to_string(),
+                opt_value: opt_value.map(str::to_string),
+            },
+        });
+    }
+    events
+}
+
+/// Compare the normalized event streams of two parsers. Parsers that both reject a document agree,
+/// whatever they reported before their errors: parsers detect errors at different points, e.g. jaxp tokenizes
+/// text together with the markup after it.
+pub fn first_divergence(jaxp: &[ComparedEvent], reference: &[ComparedEvent]) -> Option<Divergence> {
+    if matches!((jaxp.last(), reference.last()), (Some(ComparedEvent::Error(_)), Some(ComparedEvent::Error(_)))) {
+        return None;
+    }
+    let len = jaxp.len().max(reference.len());
+    (0..len)
+        .find(|&i| match (jaxp.get(i), reference.get(i)) {
+            (Some(a), Some(b)) => a != b,
+            _ => true,
+        })
+        .map(|index| Divergence { index, jaxp: jaxp.get(index).cloned(), reference: reference.get(index).cloned() })
+}
+
+/// Parse a document with jaxp and compare it with the events of a reference parser.
+pub fn compare(xml: &str, options: &ParseOptions, reference: impl IntoIterator<Item=ComparedEvent>) -> Option<Divergence> {
+    first_divergence(&normalize(jaxp_events(xml, options)), &normalize(reference))
+}
+
+/// The events of [quick-xml](https://docs.rs/quick-xml), not yet normalized.
+/// quick-xml checks less of well-formedness than jaxp, e.g. it accepts several root elements.
+#[cfg(feature = "quick-xml")]
+pub fn quick_xml_events(xml: &str) -> Vec<ComparedEvent> {
+    use quick_xml::events::Event as QEvent;
+    use quick_xml::Reader;
+
+    fn utf8(bytes: &[u8]) -> String {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+
+    fn start_element(start: &quick_xml::events::BytesStart) -> Result<ComparedEvent, String> {
+        let mut attributes = vec![];
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|e| e.to_string())?;
+            let value = attribute.unescape_value().map_err(|e| e.to_string())?;
+            attributes.push((utf8(attribute.key.as_ref()), value.into_owned()));
+        }
+        Ok(ComparedEvent::StartElement { name: utf8(start.name().as_ref()), attributes })
+    }
+
+    let mut reader = Reader::from_str(xml);
+    let mut events = vec![];
+    loop {
+        let event = match reader.read_event() {
+            Ok(QEvent::Eof) => break,
+            Ok(event) => event,
+            Err(e) => {
+                events.push(ComparedEvent::Error(e.to_string()));
+                break;
+            }
+        };
+        let result = match event {
+            QEvent::Start(start) => start_element(&start),
+            QEvent::Empty(start) => start_element(&start).map(|event| {
+                events.push(event);
+                ComparedEvent::EndElement { name: utf8(start.name().as_ref()) }
+            }),
+            QEvent::End(end) => Ok(ComparedEvent::EndElement { name: utf8(end.name().as_ref()) }),
+            QEvent::Text(text) => text.unescape().map(|text| ComparedEvent::Text(text.into_owned())).map_err(|e| e.to_string()),
+            QEvent::CData(text) => Ok(ComparedEvent::Text(utf8(&text))),
+            QEvent::Comment(text) => Ok(ComparedEvent::Comment(utf8(&text))),
+            QEvent::PI(pi) => {
+                let opt_value = Some(utf8(pi.content()).trim_start().to_string()).filter(|value| !value.is_empty());
+                Ok(ComparedEvent::ProcessingInstruction { target: utf8(pi.target()), opt_value })
+            }
+            QEvent::Decl(_) | QEvent::DocType(_) | QEvent::Eof => continue,
+        };
+        match result {
+            Ok(event) => events.push(event),
+            Err(message) => {
+                events.push(ComparedEvent::Error(message));
+                break;
+            }
+        }
+    }
+    events
+}
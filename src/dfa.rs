@@ -0,0 +1,325 @@
+//! A table-driven tokenizer for the content of a document, see [TokenizerBackend::Dfa](crate::options::TokenizerBackend::Dfa).
+//!
+//! Tags, attributes and text are scanned by a state machine over classes of bytes, with one table lookup per byte.
+//! Each transition has an action, which emits a token at the end of a name, value or text, or checks what the table
+//! cannot: characters beyond ASCII, references and the `]]>` delimiter. Comments, CDATA sections and processing
+//! instructions are scanned by the recursive-descent tokenizer.
+//!
+//! The machine accepts only content that the recursive-descent tokenizer accepts, with the same tokens.
+//! It gives up on anything else, without an error, so that the recursive-descent tokenizer scans the input again
+//! and reports the error.
+
+use crate::chariter::CharIter;
+use crate::textrange::TextRange;
+use crate::token::XmlToken;
+use crate::token::XmlToken::*;
+use crate::tokenize::XmlTokenizer;
+use crate::xmlchar::{XmlByte, XmlChar};
+
+// classes of bytes
+const OTHER: u8 = 0;
+const SPACE: u8 = 1;
+const LESS_THAN: u8 = 2;
+const GREATER_THAN: u8 = 3;
+const SLASH: u8 = 4;
+const EQUALS: u8 = 5;
+const QUOTE: u8 = 6;
+const APOSTROPHE: u8 = 7;
+const AMPERSAND: u8 = 8;
+const BANG: u8 = 9;
+const QUESTION_MARK: u8 = 10;
+const RIGHT_BRACKET: u8 = 11;
+/// ASCII letters, `_` and `:`
+const NAME_START: u8 = 12;
+/// ASCII digits, `-` and `.`
+const NAME: u8 = 13;
+/// The first byte of a character beyond ASCII
+const NON_ASCII: u8 = 14;
+/// Control characters that are not XML Chars
+const INVALID: u8 = 15;
+const CLASS_COUNT: usize = 16;
+
+static CLASSES: [u8; 256] = byte_classes();
+
+const fn byte_classes() -> [u8; 256] {
+    let mut classes = [OTHER; 256];
+    let mut b = 0;
+    while b < 256 {
+        classes[b] = match b as u8 {
+            b' ' | b'\t' | b'\n' | b'\r' => SPACE,
+            b'<' => LESS_THAN,
+            b'>' => GREATER_THAN,
+            b'/' => SLASH,
+            b'=' => EQUALS,
+            b'"' => QUOTE,
+            b'\'' => APOSTROPHE,
+            b'&' => AMPERSAND,
+            b'!' => BANG,
+            b'?' => QUESTION_MARK,
+            b']' => RIGHT_BRACKET,
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' | b':' => NAME_START,
+            b'0'..=b'9' | b'-' | b'.' => NAME,
+            0x80..=0xff => NON_ASCII,
+            0..=0x1f => INVALID,
+            _ => OTHER,
+        };
+        b += 1;
+    }
+    classes
+}
+
+// states
+const TEXT: u8 = 0;
+/// After `<`
+const TAG_OPEN: u8 = 1;
+const START_NAME: u8 = 2;
+/// After an attribute value, where a space has to follow before the next attribute
+const IN_TAG: u8 = 3;
+/// After spaces in a start tag
+const TAG_SPACE: u8 = 4;
+const ATTRIBUTE_NAME: u8 = 5;
+/// After the name of an attribute and spaces
+const BEFORE_EQUALS: u8 = 6;
+const AFTER_EQUALS: u8 = 7;
+const DOUBLE_QUOTED_VALUE: u8 = 8;
+const SINGLE_QUOTED_VALUE: u8 = 9;
+/// After the `/` of an empty-element tag
+const EMPTY_TAG_END: u8 = 10;
+/// After `</`
+const END_TAG_OPEN: u8 = 11;
+const END_NAME: u8 = 12;
+/// After the name of an end tag and spaces
+const END_TAG_SPACE: u8 = 13;
+const FAIL: u8 = 15;
+const STATE_COUNT: usize = 16;
+
+// actions, in the upper half of a transition
+const NONE: u8 = 0;
+/// Emit the text before `<`
+const TEXT_END: u8 = 1;
+/// A name starts at the byte
+const NAME_BEGIN: u8 = 2;
+const START_NAME_END: u8 = 3;
+/// The start tag ends with `>` right after its name
+const START_NAME_AND_TAG_END: u8 = 4;
+/// The start tag ends with `>`, the text starts after it
+const TAG_END: u8 = 5;
+const ATTRIBUTE_NAME_END: u8 = 6;
+const VALUE_BEGIN: u8 = 7;
+const VALUE_END: u8 = 8;
+/// `>` of an empty-element tag, which is followed by its artificial end tag
+const EMPTY_TAG_CLOSE: u8 = 9;
+const END_NAME_END: u8 = 10;
+const END_NAME_AND_TAG_END: u8 = 11;
+/// A byte that the table cannot decide on, handled by the code for the current state and the class of the byte
+const SPECIAL: u8 = 12;
+
+static TRANSITIONS: [[u8; CLASS_COUNT]; STATE_COUNT] = transitions();
+
+const fn transition(next: u8, action: u8) -> u8 {
+    next | action << 4
+}
+
+const fn transitions() -> [[u8; CLASS_COUNT]; STATE_COUNT] {
+    let fail = transition(FAIL, NONE);
+    let mut table = [[fail; CLASS_COUNT]; STATE_COUNT];
+
+    let mut class = 0;
+    while class < CLASS_COUNT {
+        table[TEXT as usize][class] = transition(TEXT, NONE);
+        table[DOUBLE_QUOTED_VALUE as usize][class] = transition(DOUBLE_QUOTED_VALUE, NONE);
+        table[SINGLE_QUOTED_VALUE as usize][class] = transition(SINGLE_QUOTED_VALUE, NONE);
+        class += 1;
+    }
+    let mut state = 0;
+    while state < STATE_COUNT {
+        // characters beyond ASCII in text, values and names
+        if state != FAIL as usize {
+            table[state][NON_ASCII as usize] = transition(state as u8, SPECIAL);
+        }
+        state += 1;
+    }
+    let text = TEXT as usize;
+    table[text][LESS_THAN as usize] = transition(TAG_OPEN, TEXT_END);
+    table[text][AMPERSAND as usize] = transition(TEXT, SPECIAL);
+    table[text][RIGHT_BRACKET as usize] = transition(TEXT, SPECIAL);
+    table[text][INVALID as usize] = fail;
+
+    let tag_open = TAG_OPEN as usize;
+    table[tag_open][NAME_START as usize] = transition(START_NAME, NAME_BEGIN);
+    table[tag_open][SLASH as usize] = transition(END_TAG_OPEN, NONE);
+    table[tag_open][BANG as usize] = transition(TEXT, SPECIAL);
+    table[tag_open][QUESTION_MARK as usize] = transition(TEXT, SPECIAL);
+
+    let start_name = START_NAME as usize;
+    table[start_name][NAME_START as usize] = transition(START_NAME, NONE);
+    table[start_name][NAME as usize] = transition(START_NAME, NONE);
+    table[start_name][SPACE as usize] = transition(TAG_SPACE, START_NAME_END);
+    table[start_name][GREATER_THAN as usize] = transition(TEXT, START_NAME_AND_TAG_END);
+    table[start_name][SLASH as usize] = transition(EMPTY_TAG_END, START_NAME_END);
+
+    let in_tag = IN_TAG as usize;
+    table[in_tag][SPACE as usize] = transition(TAG_SPACE, NONE);
+    table[in_tag][GREATER_THAN as usize] = transition(TEXT, TAG_END);
+    table[in_tag][SLASH as usize] = transition(EMPTY_TAG_END, NONE);
+
+    let tag_space = TAG_SPACE as usize;
+    table[tag_space][SPACE as usize] = transition(TAG_SPACE, NONE);
+    table[tag_space][NAME_START as usize] = transition(ATTRIBUTE_NAME, NAME_BEGIN);
+    table[tag_space][GREATER_THAN as usize] = transition(TEXT, TAG_END);
+    table[tag_space][SLASH as usize] = transition(EMPTY_TAG_END, NONE);
+
+    let attribute_name = ATTRIBUTE_NAME as usize;
+    table[attribute_name][NAME_START as usize] = transition(ATTRIBUTE_NAME, NONE);
+    table[attribute_name][NAME as usize] = transition(ATTRIBUTE_NAME, NONE);
+    table[attribute_name][SPACE as usize] = transition(BEFORE_EQUALS, ATTRIBUTE_NAME_END);
+    table[attribute_name][EQUALS as usize] = transition(AFTER_EQUALS, ATTRIBUTE_NAME_END);
+
+    let before_equals = BEFORE_EQUALS as usize;
+    table[before_equals][SPACE as usize] = transition(BEFORE_EQUALS, NONE);
+    table[before_equals][EQUALS as usize] = transition(AFTER_EQUALS, NONE);
+
+    let after_equals = AFTER_EQUALS as usize;
+    table[after_equals][SPACE as usize] = transition(AFTER_EQUALS, NONE);
+    table[after_equals][QUOTE as usize] = transition(DOUBLE_QUOTED_VALUE, VALUE_BEGIN);
+    table[after_equals][APOSTROPHE as usize] = transition(SINGLE_QUOTED_VALUE, VALUE_BEGIN);
+
+    let mut value = DOUBLE_QUOTED_VALUE as usize;
+    while value <= SINGLE_QUOTED_VALUE as usize {
+        table[value][LESS_THAN as usize] = fail;
+        table[value][INVALID as usize] = fail;
+        table[value][AMPERSAND as usize] = transition(value as u8, SPECIAL);
+        table[value][RIGHT_BRACKET as usize] = transition(value as u8, SPECIAL);
+        value += 1;
+    }
+    table[DOUBLE_QUOTED_VALUE as usize][QUOTE as usize] = transition(IN_TAG, VALUE_END);
+    table[SINGLE_QUOTED_VALUE as usize][APOSTROPHE as usize] = transition(IN_TAG, VALUE_END);
+
+    table[EMPTY_TAG_END as usize][GREATER_THAN as usize] = transition(TEXT, EMPTY_TAG_CLOSE);
+
+    table[END_TAG_OPEN as usize][NAME_START as usize] = transition(END_NAME, NAME_BEGIN);
+
+    let end_name = END_NAME as usize;
+    table[end_name][NAME_START as usize] = transition(END_NAME, NONE);
+    table[end_name][NAME as usize] = transition(END_NAME, NONE);
+    table[end_name][SPACE as usize] = transition(END_TAG_SPACE, END_NAME_END);
+    table[end_name][GREATER_THAN as usize] = transition(TEXT, END_NAME_AND_TAG_END);
+
+    let end_tag_space = END_TAG_SPACE as usize;
+    table[end_tag_space][SPACE as usize] = transition(END_TAG_SPACE, NONE);
+    table[end_tag_space][GREATER_THAN as usize] = transition(TEXT, END_NAME_AND_TAG_END);
+    table
+}
+
+/// Tokenize the content of a document from the position of `ci` to the end, `None` if the machine gives up.
+pub(crate) fn tokenize_content<'a>(tokenizer: &XmlTokenizer, ci: &mut CharIter<'a>, tokens: &mut Vec<XmlToken<'a>>) -> Option<()> {
+    let text = ci.text;
+    let bytes = text.as_bytes();
+    let range = |start: usize, end: usize| TextRange { start, end, slice: &text[start..end] };
+    let mut state = TEXT;
+    let mut i = ci.pos();
+    // the start of the current text, name or value
+    let mut mark = i;
+    let mut name_range = range(i, i);
+    let mut start_name_range = name_range;
+    while i < bytes.len() {
+        let class = CLASSES[bytes[i] as usize];
+        let entry = TRANSITIONS[state as usize][class as usize];
+        let next = entry & 0xf;
+        match entry >> 4 {
+            NONE => (),
+            TEXT_END => {
+                if mark < i {
+                    tokens.push(Text(range(mark, i)));
+                }
+            }
+            NAME_BEGIN => mark = i,
+            START_NAME_END => {
+                start_name_range = range(mark, i);
+                tokens.push(StartTag(start_name_range));
+            }
+            START_NAME_AND_TAG_END => {
+                tokens.push(StartTag(range(mark, i)));
+                mark = i + 1;
+            }
+            TAG_END => mark = i + 1,
+            ATTRIBUTE_NAME_END => name_range = range(mark, i),
+            VALUE_BEGIN => mark = i + 1,
+            VALUE_END => tokens.push(Attribute { name_range, value_range: range(mark, i) }),
+            EMPTY_TAG_CLOSE => {
+                tokens.push(EndTag(start_name_range));
+                mark = i + 1;
+            }
+            END_NAME_END => name_range = range(mark, i),
+            END_NAME_AND_TAG_END => {
+                if state == END_NAME {
+                    name_range = range(mark, i);
+                }
+                tokens.push(EndTag(name_range));
+                mark = i + 1;
+            }
+            _ => {
+                // SPECIAL, the position and state are set here
+                match class {
+                    NON_ASCII => {
+                        let c = text[i..].chars().next()?;
+                        state = match state {
+                            TEXT | DOUBLE_QUOTED_VALUE | SINGLE_QUOTED_VALUE if c.is_xml_char() => state,
+                            START_NAME | ATTRIBUTE_NAME | END_NAME if c.is_xml_name_char() => state,
+                            TAG_OPEN | TAG_SPACE | END_TAG_OPEN if c.is_xml_name_start_char() => {
+                                mark = i;
+                                match state {
+                                    TAG_OPEN => START_NAME,
+                                    TAG_SPACE => ATTRIBUTE_NAME,
+                                    _ => END_NAME,
+                                }
+                            }
+                            _ => return None,
+                        };
+                        i += c.len_utf8();
+                    }
+                    AMPERSAND => {
+                        ci.pos = i;
+                        tokenizer.consume_character_reference(ci).ok()?;
+                        i = ci.pos();
+                    }
+                    RIGHT_BRACKET => {
+                        if bytes[i..].starts_with(b"]]>") {
+                            return None;
+                        }
+                        i += 1;
+                    }
+                    _ => {
+                        // `<!` or `<?`
+                        ci.pos = i - 1;
+                        let token = if ci.test(b"<!--") {
+                            tokenizer.tokenize_comment(ci)
+                        } else if ci.test(b"<![CDATA[") {
+                            tokenizer.tokenize_cdata_section(ci)
+                        } else if ci.test(b"<?") {
+                            tokenizer.tokenize_processing_instruction(ci)
+                        } else {
+                            return None;
+                        };
+                        tokens.push(token.ok()?);
+                        state = TEXT;
+                        i = ci.pos();
+                        mark = i;
+                    }
+                }
+                continue;
+            }
+        }
+        state = next;
+        if state == FAIL {
+            return None;
+        }
+        i += 1;
+    }
+    // only spaces may follow the last markup
+    if state != TEXT || !bytes[mark..].iter().all(|b| b.is_xml_whitespace()) {
+        return None;
+    }
+    ci.pos = bytes.len();
+    Some(())
+}
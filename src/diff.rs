@@ -0,0 +1,345 @@
+//! Semantic comparison of two trees, which reports how the nodes and attributes differ rather than the lines of the markup.
+//!
+//! Children are aligned by a longest common subsequence that prefers equal subtrees, so that an inserted or removed
+//! element is reported as such instead of as changes to its following siblings. Adjacent text and CDATA sections
+//! are compared as one text, references are resolved. Paths are those of [Node::path], of the old tree except for
+//! [added](Change::Added) nodes.
+//!
+//! ```
+//! use jaxp_rust::diff::{diff, Change, DiffOptions};
+//! use jaxp_rust::document::Document;
+//!
+//! let old = Document::parse("<list><item id='1'/><item id='2'>two</item></list>").unwrap();
+//! let new = Document::parse("<list><item id='2'>2</item></list>").unwrap();
+//! let diff = diff(old.root(), new.root(), &DiffOptions::default());
+//! assert_eq!(vec![
+//!     Change::Removed { path: "/list[1]/item[1]".to_string(), value: "<item id=\"1\"/>".to_string() },
+//!     Change::Changed { path: "/list[1]/item[2]/text()[1]".to_string(), old: "two".to_string(), new: "2".to_string() },
+//! ], diff.changes);
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use crate::document::{Node, NodeType};
+use crate::escape::{escape_text, write_json_string};
+use crate::refs::decode_entities;
+use crate::writer::serialize;
+
+#[derive(Clone, Debug, Default)]
+pub struct DiffOptions {
+    /// Skip whitespace-only text and collapse runs of whitespace in text and attribute values to a single space
+    pub ignore_whitespace: bool,
+    /// Compare the attributes of an element as a set, instead of reporting [reordered](Change::AttributesReordered) ones
+    pub ignore_attribute_order: bool,
+}
+
+/// A difference between two trees, nodes are given as markup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A node or attribute of the new tree only
+    Added { path: String, value: String },
+    /// A node or attribute of the old tree only
+    Removed { path: String, value: String },
+    /// Text, a comment, a processing instruction or an attribute with another value
+    Changed { path: String, old: String, new: String },
+    /// An element with the same attributes in another order
+    AttributesReordered { path: String },
+}
+
+impl Change {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } | Change::Changed { path, .. } | Change::AttributesReordered { path } => path,
+        }
+    }
+
+    fn write_json(&self, json: &mut String) {
+        let (kind, values): (&str, Vec<(&str, &str)>) = match self {
+            Change::Added { value, .. } => ("added", vec![("value", value)]),
+            Change::Removed { value, .. } => ("removed", vec![("value", value)]),
+            Change::Changed { old, new, .. } => ("changed", vec![("old", old), ("new", new)]),
+            Change::AttributesReordered { .. } => ("attributes-reordered", vec![]),
+        };
+        json.push_str("{\"kind\":");
+        write_json_string(kind, json);
+        json.push_str(",\"path\":");
+        write_json_string(self.path(), json);
+        for (name, value) in values {
+            json.push_str(&format!(",\"{}\":", name));
+            write_json_string(value, json);
+        }
+        json.push('}');
+    }
+}
+
+/// `+ path: value`, `- path: value`, `~ path: "old" -> "new"` or `~ path: attributes reordered`
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {}: {}", path, value),
+            Change::Removed { path, value } => write!(f, "- {}: {}", path, value),
+            Change::Changed { path, old, new } => write!(f, "~ {}: {:?} -> {:?}", path, old, new),
+            Change::AttributesReordered { path } => write!(f, "~ {}: attributes reordered", path),
+        }
+    }
+}
+
+/// The changes from an old to a new tree, in document order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Whether the trees are equal
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// A JSON array of the changes, each an object with the `kind`, the `path` and the `value` or the `old` and `new` values.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            change.write_json(&mut json);
+        }
+        json.push(']');
+        json
+    }
+}
+
+/// One change per line.
+impl Display for Diff {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare two subtrees, e.g. the roots of two documents.
+pub fn diff(old: Node, new: Node, options: &DiffOptions) -> Diff {
+    let mut differ = Differ { options, changes: vec![] };
+    let (old, new) = (differ.item(vec![old]), differ.item(vec![new]));
+    if differ.equal(&old, &new) {
+        return Diff::default();
+    }
+    if old.key == new.key {
+        differ.diff_items(&old, &new);
+    } else {
+        differ.removed(&old);
+        differ.added(&new);
+    }
+    Diff { changes: differ.changes }
+}
+
+/// A node as compared: an element or the root, merged text, a comment or a processing instruction.
+struct Item<'a, 'input> {
+    /// The first of the merged text nodes
+    node: Node<'a, 'input>,
+    /// Items only match if their keys are equal: the kind and the name
+    key: String,
+    /// Text, a comment or the value of a processing instruction
+    content: String,
+}
+
+/// The [weight](Differ::weight) of equal items
+const EQUAL: usize = 3;
+
+struct Differ<'o> {
+    options: &'o DiffOptions,
+    changes: Vec<Change>,
+}
+
+impl Differ<'_> {
+    fn collapse_whitespace(&self, text: String) -> String {
+        if self.options.ignore_whitespace {
+            text.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            text
+        }
+    }
+
+    /// An item of a single node or of adjacent text nodes.
+    fn item<'a, 'input>(&self, nodes: Vec<Node<'a, 'input>>) -> Item<'a, 'input> {
+        let node = nodes[0];
+        let (key, content) = match node.node_type() {
+            NodeType::Root => ("/".to_string(), String::new()),
+            NodeType::Element => (format!("<{}", node.tag_name()), String::new()),
+            NodeType::Text | NodeType::CdataSection => {
+                let text: String = nodes.iter()
+                    .map(|n| match n.node_type() {
+                        NodeType::Text => decode_entities(n.text().unwrap()).into_owned(),
+                        _ => n.text().unwrap().to_string(),
+                    })
+                    .collect();
+                ("text()".to_string(), self.collapse_whitespace(text))
+            }
+            NodeType::Comment => ("comment()".to_string(), node.text().unwrap().to_string()),
+            NodeType::ProcessingInstruction => (format!("<?{}", node.tag_name()), node.text().unwrap_or("").to_string()),
+        };
+        Item { node, key, content }
+    }
+
+    fn children<'a, 'input>(&self, node: Node<'a, 'input>) -> Vec<Item<'a, 'input>> {
+        let mut items = vec![];
+        let mut texts = vec![];
+        for child in node.children() {
+            if child.is_text() {
+                texts.push(child);
+                continue;
+            }
+            self.push_text(&mut items, &mut texts);
+            items.push(self.item(vec![child]));
+        }
+        self.push_text(&mut items, &mut texts);
+        items
+    }
+
+    fn push_text<'a, 'input>(&self, items: &mut Vec<Item<'a, 'input>>, texts: &mut Vec<Node<'a, 'input>>) {
+        if texts.is_empty() {
+            return;
+        }
+        let item = self.item(std::mem::take(texts));
+        if !(self.options.ignore_whitespace && item.content.is_empty()) {
+            items.push(item);
+        }
+    }
+
+    /// The attributes with normalized values
+    fn attributes(&self, node: Node) -> Vec<(String, String)> {
+        node.attributes().map(|a| (a.name().to_string(), self.collapse_whitespace(decode_entities(a.value()).into_owned()))).collect()
+    }
+
+    fn equal(&self, old: &Item, new: &Item) -> bool {
+        if old.key != new.key || old.content != new.content {
+            return false;
+        }
+        if !matches!(old.node.node_type(), NodeType::Element | NodeType::Root) {
+            return true;
+        }
+        if !self.equal_attributes(old.node, new.node) {
+            return false;
+        }
+        let (old_children, new_children) = (self.children(old.node), self.children(new.node));
+        old_children.len() == new_children.len() && old_children.iter().zip(&new_children).all(|(a, b)| self.equal(a, b))
+    }
+
+    fn equal_attributes(&self, old: Node, new: Node) -> bool {
+        let (mut old_attributes, mut new_attributes) = (self.attributes(old), self.attributes(new));
+        if self.options.ignore_attribute_order {
+            old_attributes.sort();
+            new_attributes.sort();
+        }
+        old_attributes == new_attributes
+    }
+
+    /// Report the changes between items with equal keys.
+    fn diff_items(&mut self, old: &Item, new: &Item) {
+        match old.node.node_type() {
+            NodeType::Element | NodeType::Root => {
+                self.diff_attributes(old.node, new.node);
+                self.diff_children(old.node, new.node);
+            }
+            _ if old.content != new.content => self.changes.push(Change::Changed {
+                path: old.node.path(),
+                old: old.content.clone(),
+                new: new.content.clone(),
+            }),
+            _ => (),
+        }
+    }
+
+    fn diff_attributes(&mut self, old: Node, new: Node) {
+        let (old_attributes, new_attributes) = (self.attributes(old), self.attributes(new));
+        let path = |node: Node, name: &str| format!("{}/@{}", node.path(), name);
+        for (name, value) in &old_attributes {
+            match new_attributes.iter().find(|(n, _)| n == name) {
+                Some((_, new_value)) if new_value != value => self.changes.push(Change::Changed {
+                    path: path(old, name),
+                    old: value.clone(),
+                    new: new_value.clone(),
+                }),
+                Some(_) => (),
+                None => self.changes.push(Change::Removed { path: path(old, name), value: value.clone() }),
+            }
+        }
+        for (name, value) in &new_attributes {
+            if !old_attributes.iter().any(|(n, _)| n == name) {
+                self.changes.push(Change::Added { path: path(new, name), value: value.clone() });
+            }
+        }
+        if !self.options.ignore_attribute_order {
+            let common = |attributes: &[(String, String)], others: &[(String, String)]| -> Vec<String> {
+                attributes.iter().filter(|(n, _)| others.iter().any(|(o, _)| o == n)).map(|(n, _)| n.clone()).collect()
+            };
+            if common(&old_attributes, &new_attributes) != common(&new_attributes, &old_attributes) {
+                self.changes.push(Change::AttributesReordered { path: old.path() });
+            }
+        }
+    }
+
+    fn diff_children(&mut self, old: Node, new: Node) {
+        let (old_children, new_children) = (self.children(old), self.children(new));
+        let (n, m) = (old_children.len(), new_children.len());
+        let weights: Vec<Vec<usize>> = old_children.iter()
+            .map(|a| new_children.iter().map(|b| self.weight(a, b)).collect())
+            .collect();
+        // the maximum weight of matching the children from i and j on
+        let mut best = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                let matched = if weights[i][j] > 0 { weights[i][j] + best[i + 1][j + 1] } else { 0 };
+                best[i][j] = matched.max(best[i + 1][j]).max(best[i][j + 1]);
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && weights[i][j] > 0 && best[i][j] == weights[i][j] + best[i + 1][j + 1] {
+                if weights[i][j] < EQUAL {
+                    self.diff_items(&old_children[i], &new_children[j]);
+                }
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || best[i][j] == best[i + 1][j]) {
+                self.removed(&old_children[i]);
+                i += 1;
+            } else {
+                self.added(&new_children[j]);
+                j += 1;
+            }
+        }
+    }
+
+    /// The weight of matching two items: equal subtrees are preferred over elements with the same attributes,
+    /// which are preferred over items with equal keys only.
+    fn weight(&self, old: &Item, new: &Item) -> usize {
+        if old.key != new.key {
+            0
+        } else if self.equal(old, new) {
+            EQUAL
+        } else if old.node.is_element() && self.equal_attributes(old.node, new.node) {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn markup(item: &Item) -> String {
+        match item.node.node_type() {
+            NodeType::Text | NodeType::CdataSection => escape_text(&item.content).into_owned(),
+            _ => serialize(item.node),
+        }
+    }
+
+    fn removed(&mut self, item: &Item) {
+        self.changes.push(Change::Removed { path: item.node.path(), value: Self::markup(item) });
+    }
+
+    fn added(&mut self, item: &Item) {
+        self.changes.push(Change::Added { path: item.node.path(), value: Self::markup(item) });
+    }
+}
@@ -0,0 +1,1944 @@
+//! A [Document] of arena nodes with a read API modeled on [roxmltree](https://docs.rs/roxmltree), edited through [NodeMut].
+//!
+//! Unlike roxmltree, text and attribute values are kept as they appear in the markup, with references unresolved:
+//! for `<a v='1 &lt; 2'>x &amp; y</a>` [Node::attribute] returns `1 &lt; 2` and [Node::text] returns `x &amp; y`.
+//! [Node::attribute_decoded] and [Node::text_decoded] return the values roxmltree returns, `1 < 2` and `x & y`.
+
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, Index, Range};
+use std::slice;
+use std::sync::Arc;
+
+use crate::binary::{Base64Decoder, BinaryError, HexDecoder};
+use crate::chariter::CharIter;
+use crate::error::{XmlError, XmlWarning};
+use crate::error::XmlError::{DisallowedConstruct, DuplicateAttribute, InvalidUtf8, LimitExceeded, NotNamespaceWellFormed, ReservedNamespace, UnexpectedXmlToken};
+use crate::escape::{escape_attribute, escape_text};
+use crate::node::truncate;
+use crate::namespace::{check_declaration, declared_prefix, split_qname, validate_qname, Namespace, XML_NAMESPACE, XMLNS_NAMESPACE};
+use crate::entities::EntityTable;
+use crate::options::{AttributeAction, AttributeFilter, CommentPolicy, ParseOptions, ProcessingInstructionPolicy, SampleOptions, Strictness};
+use crate::refs::{decode_entities, decode_entities_with};
+use crate::soup;
+use crate::stats::DocumentMetrics;
+use crate::textrange::{LineIndex, TextRange};
+use crate::token::XmlToken;
+use crate::token::XmlToken::*;
+use crate::tokenize::XmlTokenizer;
+use crate::xmlchar::{names_eq, XmlByte};
+use crate::xpointer;
+
+/// Index of a node inside the arena of a [Document].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(pub(crate) usize);
+
+impl NodeId {
+    /// The position of the node in document order, the document root is always 0.
+    pub fn get(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeType {
+    Root,
+    Element,
+    Text,
+    CdataSection,
+    Comment,
+    ProcessingInstruction,
+}
+
+/// What an element contains, comments and processing instructions aside,
+/// see [3.2.1 Element Content](https://www.w3.org/TR/xml/#sec-element-content) and [3.2.2 Mixed Content](https://www.w3.org/TR/xml/#sec-mixed-content).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentModel {
+    /// Neither elements nor text
+    Empty,
+    /// Text and CDATA sections, but no elements
+    TextOnly,
+    /// Elements, separated by nothing but whitespace
+    ElementOnly,
+    /// Elements and text other than whitespace
+    Mixed,
+}
+
+/// The version of XML a document is declared to conform to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XmlVersion {
+    V1_0,
+    V1_1,
+    /// A later 1.x version, read like XML 1.0, see [2.8 Prolog and Document Type Declaration](https://www.w3.org/TR/xml/#sec-prolog-dtd)
+    Other(String),
+}
+
+impl XmlVersion {
+    pub fn as_str(&self) -> &str {
+        match self {
+            XmlVersion::V1_0 => "1.0",
+            XmlVersion::V1_1 => "1.1",
+            XmlVersion::Other(version) => version,
+        }
+    }
+}
+
+/// The XML declaration at the beginning of a document.
+///
+/// [\[23\] XMLDecl](https://www.w3.org/TR/xml/#NT-XMLDecl)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XmlDecl {
+    pub version: XmlVersion,
+    /// The encoding name as declared, encoding names are case-insensitive
+    pub encoding: Option<String>,
+    pub standalone: Option<bool>,
+}
+
+/// The document type declaration of a document.
+///
+/// [\[28\] doctypedecl](https://www.w3.org/TR/xml/#NT-doctypedecl)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Doctype {
+    /// The name of the root element as declared
+    pub name: String,
+    pub public_id: Option<String>,
+    pub system_id: Option<String>,
+    /// The byte range of the text between the brackets of the internal subset in the [input](Document::input_text)
+    pub internal_subset_span: Option<Range<usize>>,
+}
+
+impl Doctype {
+    /// Whether this is the `<!DOCTYPE html>` of HTML5, also in its `about:legacy-compat` form.
+    pub fn is_html5(&self) -> bool {
+        self.name.eq_ignore_ascii_case("html")
+            && self.public_id.is_none()
+            && self.system_id.as_deref().is_none_or(|id| id == "about:legacy-compat")
+    }
+
+    /// Whether the public identifier starts with a prefix, e.g. `-//W3C//DTD XHTML 1.0`.
+    pub fn matches(&self, public_id_prefix: &str) -> bool {
+        self.public_id.as_deref().is_some_and(|id| id.starts_with(public_id_prefix))
+    }
+}
+
+/// An attribute of an element node.
+/// The value is stored as it appears in the markup, i.e. with references unresolved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute<'input> {
+    pub(crate) name: Cow<'input, str>,
+    pub(crate) value: Cow<'input, str>,
+}
+
+impl Attribute<'_> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// The prefix of the name, if any.
+    pub fn prefix(&self) -> Option<&str> {
+        split_qname(&self.name).0
+    }
+
+    /// The name without its prefix.
+    pub fn local_name(&self) -> &str {
+        split_qname(&self.name).1
+    }
+
+    /// Whether the attribute has the qualified name, with a cheap check of length and first and last byte first.
+    pub fn name_eq(&self, name: &str) -> bool {
+        names_eq(&self.name, name)
+    }
+}
+
+/// The attributes of an element, in document order.
+///
+/// Lookups are by qualified name. Of duplicate attributes, which only lenient parsing keeps, the first one is found.
+/// Edits keep the position of existing attributes, so a document round-trips with its attribute order intact.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Attributes<'input>(Vec<Attribute<'input>>);
+
+impl<'input> Attributes<'input> {
+    pub const fn new() -> Self {
+        Attributes(Vec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Attribute<'input>> {
+        self.0.iter()
+    }
+
+    /// The attribute with the specified name.
+    pub fn get(&self, name: &str) -> Option<&Attribute<'input>> {
+        self.0.iter().find(|a| a.name_eq(name))
+    }
+
+    /// The attribute at a position.
+    pub fn get_index(&self, index: usize) -> Option<&Attribute<'input>> {
+        self.0.get(index)
+    }
+
+    /// The position of the attribute with the specified name.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.0.iter().position(|a| a.name_eq(name))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.index_of(name).is_some()
+    }
+
+    /// Set the value of an attribute, the value is escaped.
+    /// An existing attribute keeps its position, a new one is appended.
+    /// Returns the previous value, with references unresolved.
+    pub fn insert(&mut self, name: &str, value: &str) -> Option<Cow<'input, str>> {
+        let value = Cow::Owned(escape_attribute(value, '"').into_owned());
+        match self.index_of(name) {
+            Some(index) => Some(std::mem::replace(&mut self.0[index].value, value)),
+            None => {
+                self.0.push(Attribute { name: Cow::Owned(name.to_string()), value });
+                None
+            }
+        }
+    }
+
+    /// Remove the attribute with the specified name and its duplicates, later attributes move up.
+    pub fn remove(&mut self, name: &str) -> Option<Attribute<'input>> {
+        let removed = self.0.remove(self.index_of(name)?);
+        self.0.retain(|a| a.name() != name);
+        Some(removed)
+    }
+
+    /// Remove the attribute at a position, later attributes move up by one.
+    pub fn remove_index(&mut self, index: usize) -> Option<Attribute<'input>> {
+        (index < self.0.len()).then(|| self.0.remove(index))
+    }
+
+    /// Namespace declarations must not bind or unbind the reserved prefixes and namespaces.
+    /// `name_ranges` are the names of the attributes in the markup, for error positions.
+    pub(crate) fn check_namespace_declarations(&self, name_ranges: &[TextRange], ci: &CharIter) -> Result<(), XmlError> {
+        for (attribute, name_range) in self.iter().zip(name_ranges) {
+            if let Some(prefix) = declared_prefix(attribute.name()) {
+                // a reference must not hide a reserved namespace
+                check_declaration(prefix, &decode_entities(attribute.value())).map_err(|reason| ReservedNamespace {
+                    pos: ci.error_pos_of(name_range.start),
+                    reason: reason.to_string(),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Attribute names must be unique within a start tag, and so must the combinations of
+    /// namespace and local name, see [6.3 Uniqueness of Attributes](https://www.w3.org/TR/xml-names/#uniqAttrs).
+    /// Prefixes are resolved in the scope of the element, including its own declarations.
+    pub(crate) fn check_unique<'v>(
+        &self,
+        name_ranges: &[TextRange],
+        ci: &CharIter,
+        resolve_prefix: impl Fn(&str) -> Option<&'v str>,
+    ) -> Result<(), XmlError> {
+        let namespace_uri = |attribute: &Attribute| match split_qname(attribute.name()) {
+            (Some(prefix), _) => resolve_prefix(prefix),
+            (None, "xmlns") => Some(XMLNS_NAMESPACE),
+            (None, _) => None,
+        };
+        for (i, attribute) in self.iter().enumerate() {
+            let uri = namespace_uri(attribute);
+            let is_duplicate = self.0[..i].iter().any(|other| {
+                if other.name_eq(attribute.name()) {
+                    return true;
+                }
+                // unprefixed attributes and those with an unbound prefix have no expanded name to compare
+                uri.is_some() && other.local_name() == attribute.local_name() && namespace_uri(other) == uri
+            });
+            if is_duplicate {
+                return Err(DuplicateAttribute { pos: ci.error_pos_of(name_ranges[i].start) });
+            }
+        }
+        Ok(())
+    }
+
+    /// The element name and the attribute names of a start tag must be QNames with declared prefixes,
+    /// see [5 Conformance of Documents](https://www.w3.org/TR/xml-names/#Conformance).
+    /// Prefixes are resolved in the scope of the element, including its own declarations.
+    pub(crate) fn check_qualified_names<'v>(
+        &self,
+        element_range: TextRange,
+        name_ranges: &[TextRange],
+        ci: &CharIter,
+        resolve_prefix: impl Fn(&str) -> Option<&'v str>,
+    ) -> Result<(), XmlError> {
+        for range in std::iter::once(&element_range).chain(name_ranges) {
+            let not_well_formed = |reason: String| NotNamespaceWellFormed { pos: ci.error_pos_of(range.start), reason };
+            match validate_qname(range.slice) {
+                Err(reason) => return Err(not_well_formed(format!("{} is not a QName: {}", range.slice, reason))),
+                // declarations bind the prefix instead of using it
+                Ok((Some("xmlns"), _)) => {}
+                Ok((Some(prefix), _)) if resolve_prefix(prefix).is_none() => {
+                    return Err(not_well_formed(format!("the prefix {} is not declared", prefix)));
+                }
+                Ok(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep, replace or drop every attribute as decided by a filter.
+    pub(crate) fn filter(&mut self, element_name: &str, filter: &AttributeFilter, entities: &EntityTable) {
+        self.0.retain_mut(|attribute| {
+            let value = decode_entities_with(&attribute.value, entities);
+            match filter.apply(&attribute.name, &value, element_name) {
+                AttributeAction::Keep => true,
+                AttributeAction::Replace(value) => {
+                    attribute.value = Cow::Owned(escape_attribute(&value, '"').into_owned());
+                    true
+                }
+                AttributeAction::Drop => false,
+            }
+        });
+    }
+
+    /// Append an attribute as it appears in the markup, without looking for duplicates.
+    pub(crate) fn push(&mut self, attribute: Attribute<'input>) {
+        self.0.push(attribute);
+    }
+}
+
+static NO_ATTRIBUTES: Attributes<'static> = Attributes::new();
+
+/// The attributes of an element for editing, see [NodeMut::attributes_mut]. Reads go through `Deref`.
+///
+/// Edits only store copies of the text they are passed. There is no `&mut Attributes`,
+/// which could swap in attributes that borrow from text that does not live as long as the document.
+pub struct AttributesMut<'a, 'input>(&'a mut Attributes<'input>);
+
+impl<'input> AttributesMut<'_, 'input> {
+    /// See [Attributes::insert].
+    pub fn insert(&mut self, name: &str, value: &str) -> Option<Cow<'input, str>> {
+        self.0.insert(name, value)
+    }
+
+    /// See [Attributes::remove].
+    pub fn remove(&mut self, name: &str) -> Option<Attribute<'input>> {
+        self.0.remove(name)
+    }
+
+    /// See [Attributes::remove_index].
+    pub fn remove_index(&mut self, index: usize) -> Option<Attribute<'input>> {
+        self.0.remove_index(index)
+    }
+}
+
+impl<'input> Deref for AttributesMut<'_, 'input> {
+    type Target = Attributes<'input>;
+
+    fn deref(&self) -> &Attributes<'input> {
+        self.0
+    }
+}
+
+impl<'input> Index<usize> for Attributes<'input> {
+    type Output = Attribute<'input>;
+
+    fn index(&self, index: usize) -> &Attribute<'input> {
+        &self.0[index]
+    }
+}
+
+impl<'a, 'input> IntoIterator for &'a Attributes<'input> {
+    type Item = &'a Attribute<'input>;
+    type IntoIter = slice::Iter<'a, Attribute<'input>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Names and values either borrow from the input or own text added by editing.
+/// Character data is stored as it appears in the markup, i.e. with references unresolved.
+#[derive(Clone, Debug)]
+pub(crate) enum NodeKind<'input> {
+    Root,
+    Element { name: Cow<'input, str>, attributes: Attributes<'input> },
+    Text(Cow<'input, str>),
+    CdataSection(Cow<'input, str>),
+    Comment(Cow<'input, str>),
+    ProcessingInstruction { target: Cow<'input, str>, opt_value: Option<Cow<'input, str>> },
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NodeData<'input> {
+    pub(crate) kind: NodeKind<'input>,
+    pub(crate) parent: Option<NodeId>,
+    pub(crate) children: Vec<NodeId>,
+    /// The position among the children of the parent, kept up to date by every edit of the children
+    pub(crate) index: usize,
+    /// The byte range of the markup of an element in the input, as long as neither it nor its subtree is edited
+    pub(crate) source: Option<Range<usize>>,
+}
+
+/// Why a subtree cannot be grafted into a document, see [NodeMut::insert_subtree].
+#[derive(Debug)]
+pub enum GraftError {
+    /// The fragment is not well-formed
+    Parse(XmlError),
+    /// Subtrees can only be grafted into elements
+    NotAnElement,
+    /// An `id` or `xml:id` value occurs in the document already, or twice in the subtree
+    DuplicateId(String),
+}
+
+impl Display for GraftError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            GraftError::Parse(e) => Display::fmt(e, f),
+            GraftError::NotAnElement => write!(f, "subtrees can only be grafted into elements"),
+            GraftError::DuplicateId(id) => write!(f, "duplicate id {}", id),
+        }
+    }
+}
+
+impl std::error::Error for GraftError {}
+
+/// What [NodeMut::sort_child_elements] orders sibling elements by.
+/// Keys are compared as strings, elements with equal keys keep their order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    /// The qualified name
+    TagName,
+    /// The value of an attribute with references resolved, elements without the attribute come first
+    Attribute(String),
+    /// The text of the first child element of the name with references resolved, elements without such a child come first
+    ChildText(String),
+}
+
+/// An XML document.
+///
+/// All nodes live in a single arena, which makes parent links and sibling navigation cheap.
+/// The read API mirrors the one of [roxmltree](https://docs.rs/roxmltree),
+/// edits go through [NodeMut].
+///
+/// Documents are `Send + Sync` and can be queried from multiple threads at once.
+#[derive(Clone)]
+pub struct Document<'input> {
+    pub(crate) text: &'input str,
+    pub(crate) nodes: Vec<NodeData<'input>>,
+    pub(crate) xml_declaration: Option<XmlDecl>,
+    pub(crate) opt_doctype: Option<Doctype>,
+}
+
+impl<'input> Document<'input> {
+    /// Parse a string slice into a document.
+    pub fn parse(xml: &'input str) -> Result<Document<'input>, XmlError> {
+        Self::parse_with_options(xml, &ParseOptions::default())
+    }
+
+    pub fn parse_with_options(xml: &'input str, options: &ParseOptions) -> Result<Document<'input>, XmlError> {
+        if options.strictness == Strictness::TagSoup {
+            return soup::parse(xml, options);
+        }
+        let tokens = XmlTokenizer::with_options(options).tokenize(xml)?;
+        let result = Self::from_tokens(xml, tokens, options);
+        #[cfg(feature = "tracing")]
+        if let Err(e) = &result {
+            tracing::debug!(error = %e, "document rejected");
+        }
+        result
+    }
+
+    /// Parse a string slice into a document and report non-fatal issues along the way.
+    pub fn parse_with_warnings(xml: &'input str, options: &ParseOptions) -> Result<(Document<'input>, Vec<XmlWarning>), XmlError> {
+        let tokens = XmlTokenizer::with_options(options).tokenize(xml)?;
+        let warnings = collect_warnings(xml, &tokens);
+        let doc = Self::from_tokens(xml, tokens, options)?;
+        Ok((doc, warnings))
+    }
+
+    /// Parse the beginning of a document up to `max_records` records, e.g. to preview a large feed.
+    /// If there are more records, parsing stops at the start tag of the next one, the elements that are still open
+    /// are closed and the result is flagged as truncated. The rest of the input is not checked then.
+    /// Tag soup is parsed like lenient XML.
+    pub fn parse_sample(xml: &'input str, options: &ParseOptions, sample: &SampleOptions) -> Result<(Document<'input>, bool), XmlError> {
+        let path: Vec<&str> = sample.record_path.split('/').filter(|step| !step.is_empty()).collect();
+        let mut open_ranges: Vec<TextRange> = vec![];
+        let mut record_count = 0;
+        // the tokens before the start tag of the first record that is not read
+        let mut cut = 0;
+        let mut tokens = vec![];
+        let is_truncated = XmlTokenizer::with_options(options).tokenize_until(xml, &mut tokens, |tokens| {
+            for (i, token) in tokens.iter().enumerate().skip(cut) {
+                match token {
+                    StartTag(name_range) => {
+                        open_ranges.push(*name_range);
+                        if open_ranges.len() == path.len() && open_ranges.iter().zip(&path).all(|(r, step)| r.name_eq(step)) {
+                            record_count += 1;
+                            if record_count > sample.max_records {
+                                open_ranges.pop();
+                                cut = i;
+                                return true;
+                            }
+                        }
+                    }
+                    EndTag(_) => {
+                        open_ranges.pop();
+                    }
+                    _ => (),
+                }
+            }
+            cut = tokens.len();
+            false
+        })?;
+        if !is_truncated {
+            let doc = Self::from_tokens(xml, tokens, options)?;
+            return Ok((doc, false));
+        }
+        tokens.truncate(cut);
+        // the end tags of empty-element tags have the range of their start tags, like these
+        tokens.extend(open_ranges.iter().rev().map(|&range| EndTag(range)));
+        let mut doc = Self::from_tokens(xml, tokens, options)?;
+        // the closed elements are the last ones on each level, their markup is incomplete
+        let mut deepest = doc.root_element();
+        for _ in 1..open_ranges.len() {
+            deepest = deepest.last_element_child().unwrap();
+        }
+        let id = deepest.id();
+        doc.invalidate_source(id);
+        Ok((doc, true))
+    }
+
+    pub(crate) fn from_tokens(xml: &'input str, tokens: Vec<XmlToken<'input>>, options: &ParseOptions) -> Result<Document<'input>, XmlError> {
+        // tag matching and namespace constraints are validated while building the tree
+        trace_span!("build_tree", token_count = tokens.len(), strictness = ?options.strictness);
+        let is_conformant = options.strictness == Strictness::Conformant;
+        let mut doc = Document {
+            text: xml,
+            nodes: Vec::with_capacity(tokens.len()),
+            xml_declaration: None,
+            opt_doctype: None,
+        };
+        doc.nodes.push(NodeData { kind: NodeKind::Root, parent: None, children: vec![], index: 0, source: None });
+
+        // only used for error positions
+        let ci = CharIter { pos: 0, text: xml };
+        let mut open_elements = vec![NodeId(0)];
+        let mut open_ranges: Vec<TextRange> = vec![];
+        // the name and attribute names of the latest start tag, checked once the start tag is complete
+        let mut opt_start_range: Option<TextRange> = None;
+        let mut attribute_ranges: Vec<TextRange> = vec![];
+        // filtered attributes no longer match the markup
+        let records_source = options.attribute_filter.is_none();
+        // where the closing `>` of the latest start tag is searched from
+        let mut start_tag_rest = 0;
+        for token in tokens {
+            let parent = *open_elements.last().unwrap();
+            // the start tag is complete
+            if opt_start_range.is_some() && !matches!(token, XmlToken::Attribute { .. }) {
+                let start_range = opt_start_range.take().unwrap();
+                if is_conformant {
+                    let element = doc.node(parent);
+                    let attributes = element.attribute_map();
+                    attributes.check_namespace_declarations(&attribute_ranges, &ci)?;
+                    attributes.check_unique(&attribute_ranges, &ci, |prefix| element.resolve_prefix(prefix))?;
+                    attributes.check_qualified_names(start_range, &attribute_ranges, &ci, |prefix| element.resolve_prefix(prefix))?;
+                }
+                if let (Some(filter), NodeKind::Element { name, attributes }) = (&options.attribute_filter, &mut doc.nodes[parent.0].kind) {
+                    attributes.filter(name, filter, &options.entities);
+                }
+                attribute_ranges.clear();
+            }
+            match token {
+                StartTag(name_range) => {
+                    if is_conformant && split_qname(name_range.slice).0 == Some("xmlns") {
+                        return Err(ReservedNamespace {
+                            pos: ci.error_pos_of(name_range.start),
+                            reason: "elements must not have the xmlns prefix".to_string(),
+                        });
+                    }
+                    // a document has exactly one root element
+                    if parent == NodeId(0) && doc.nodes[0].children.iter().any(|&c| matches!(doc.nodes[c.0].kind, NodeKind::Element { .. })) {
+                        return Err(UnexpectedXmlToken { pos: ci.error_pos_of(name_range.start) });
+                    }
+                    // the document root is open as well
+                    if let Some(max) = options.max_depth.filter(|&max| open_elements.len() > max) {
+                        return Err(LimitExceeded { limit: "nesting depth".to_string(), max });
+                    }
+                    let id = doc.append(parent, NodeKind::Element { name: Cow::Borrowed(name_range.slice), attributes: Attributes::new() });
+                    open_elements.push(id);
+                    open_ranges.push(name_range);
+                    opt_start_range = Some(name_range);
+                    start_tag_rest = name_range.end;
+                }
+                EndTag(name_range) => {
+                    let start_range = match open_ranges.pop() {
+                        Some(start_range) => start_range,
+                        None => return Err(UnexpectedXmlToken { pos: ci.error_pos_of(name_range.start) })
+                    };
+                    if !start_range.name_eq(name_range.slice) {
+                        return Err(ci.non_matching_tags(start_range, name_range, &open_ranges));
+                    }
+                    let id = open_elements.pop().unwrap();
+                    if records_source {
+                        // the end tag of an empty-element tag has the range of its start tag
+                        let rest = if name_range.start == start_range.start { start_tag_rest } else { name_range.end };
+                        let end = rest + xml.as_bytes()[rest..].iter().position(|&b| b == b'>').unwrap() + 1;
+                        doc.nodes[id.0].source = Some(start_range.start - 1..end);
+                    }
+                }
+                XmlToken::Attribute { name_range, value_range } => {
+                    if let NodeKind::Element { attributes, .. } = &mut doc.nodes[parent.0].kind {
+                        attribute_ranges.push(name_range);
+                        // after the closing quote
+                        start_tag_rest = value_range.end + 1;
+                        attributes.push(Attribute { name: Cow::Borrowed(name_range.slice), value: Cow::Borrowed(value_range.slice) });
+                    }
+                }
+                Text(range) => {
+                    if parent == NodeId(0) {
+                        // only spaces are allowed outside of the root element and they are not part of the tree
+                        if !range.slice.bytes().all(|b| b.is_xml_whitespace()) {
+                            return Err(UnexpectedXmlToken { pos: ci.error_pos_of(range.start) });
+                        }
+                        continue;
+                    }
+                    doc.append(parent, NodeKind::Text(Cow::Borrowed(range.slice)));
+                }
+                CdataSection(range) => {
+                    if parent == NodeId(0) {
+                        return Err(UnexpectedXmlToken { pos: ci.error_pos_of(range.start) });
+                    }
+                    doc.append(parent, NodeKind::CdataSection(Cow::Borrowed(range.slice)));
+                }
+                Comment(range) => {
+                    if options.comments == CommentPolicy::Deny {
+                        return Err(DisallowedConstruct { pos: ci.error_pos_of(range.start), construct: "comment".to_string() });
+                    }
+                    doc.append(parent, NodeKind::Comment(Cow::Borrowed(range.slice)));
+                }
+                ProcessingInstruction { target_range, opt_value_range } => {
+                    if options.processing_instructions == ProcessingInstructionPolicy::Deny {
+                        return Err(DisallowedConstruct {
+                            pos: ci.error_pos_of(target_range.start),
+                            construct: "processing instruction".to_string(),
+                        });
+                    }
+                    doc.append(parent, NodeKind::ProcessingInstruction {
+                        target: Cow::Borrowed(target_range.slice),
+                        opt_value: opt_value_range.map(|r| Cow::Borrowed(r.slice)),
+                    });
+                }
+                XmlDeclaration { version_range, opt_encoding_range, opt_standalone_range } => {
+                    let version = match version_range.slice {
+                        "1.0" => XmlVersion::V1_0,
+                        "1.1" => XmlVersion::V1_1,
+                        version => XmlVersion::Other(version.to_string()),
+                    };
+                    doc.xml_declaration = Some(XmlDecl {
+                        version,
+                        encoding: opt_encoding_range.map(|r| r.slice.to_string()),
+                        standalone: opt_standalone_range.map(|r| r.slice == "yes"),
+                    });
+                }
+                DocTypeDeclaration { name_range, opt_system_entity_range, opt_public_entity_range, opt_internal_subset_range } => {
+                    doc.opt_doctype = Some(Doctype {
+                        name: name_range.slice.to_string(),
+                        public_id: opt_public_entity_range.map(|r| r.slice.to_string()),
+                        system_id: opt_system_entity_range.map(|r| r.slice.to_string()),
+                        internal_subset_span: opt_internal_subset_range.map(|r| r.start..r.end),
+                    });
+                }
+                // Prolog tokens are not part of the tree
+                ParameterEntityReference(_) => (),
+            }
+        }
+        if let Some(&start_range) = open_ranges.last() {
+            return Err(ci.unclosed_element(start_range));
+        }
+        if !doc.nodes.iter().any(|n| matches!(n.kind, NodeKind::Element { .. })) {
+            return Err(ci.unexpected_end_of_file().in_construct("document"));
+        }
+        Ok(doc)
+    }
+
+    pub(crate) fn append(&mut self, parent: NodeId, kind: NodeKind<'input>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        let index = self.nodes[parent.0].children.len();
+        self.nodes.push(NodeData { kind, parent: Some(parent), children: vec![], index, source: None });
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+
+    /// Update the positions of the children of a node after they were inserted, removed or reordered.
+    pub(crate) fn reindex_children(&mut self, parent: NodeId) {
+        for (index, child) in self.nodes[parent.0].children.clone().into_iter().enumerate() {
+            self.nodes[child.0].index = index;
+        }
+    }
+
+    /// The XML declaration, if the document starts with one.
+    pub fn xml_declaration(&self) -> Option<&XmlDecl> {
+        self.xml_declaration.as_ref()
+    }
+
+    /// The document type declaration, if the document has one that is not [skipped](crate::options::DoctypePolicy::Ignore).
+    pub fn doctype(&self) -> Option<&Doctype> {
+        self.opt_doctype.as_ref()
+    }
+
+    /// The shadow root of the document. Its children are the top-level nodes.
+    pub fn root(&self) -> Node<'_, 'input> {
+        self.node(NodeId(0))
+    }
+
+    /// The first top-level element.
+    pub fn root_element(&self) -> Node<'_, 'input> {
+        self.root()
+            .children()
+            .find(|n| n.is_element())
+            .expect("a document always has a root element")
+    }
+
+    /// Get a node by its id, if it belongs to this document.
+    pub fn get_node(&self, id: NodeId) -> Option<Node<'_, 'input>> {
+        if id.0 < self.nodes.len() {
+            Some(self.node(id))
+        } else {
+            None
+        }
+    }
+
+    /// All nodes of the document in document order, starting at the root.
+    pub fn descendants(&self) -> Descendants<'_, 'input> {
+        self.root().descendants()
+    }
+
+    /// All elements with a qualified name, in document order.
+    pub fn elements_named<'n>(&self, name: &'n str) -> Named<'n, Descendants<'_, 'input>> {
+        Named { nodes: self.descendants(), test: NameTest::QName(name) }
+    }
+
+    /// All elements with a namespace and local name, in document order.
+    /// The empty namespace matches elements in no namespace.
+    pub fn elements_named_ns<'n>(&self, uri: &'n str, local_name: &'n str) -> Named<'n, Descendants<'_, 'input>> {
+        Named { nodes: self.descendants(), test: NameTest::Expanded { uri, local_name } }
+    }
+
+    /// The node at a path like `/root[1]/item[3]/text()[1]`, see [Node::path].
+    /// A missing position selects the first match, e.g. `/root/item`.
+    pub fn resolve_path(&self, path: &str) -> Option<Node<'_, 'input>> {
+        let mut node = self.root();
+        for step in path.strip_prefix('/')?.split('/').filter(|step| !step.is_empty()) {
+            let (name, position) = match step.strip_suffix(']').and_then(|s| s.split_once('[')) {
+                Some((name, position)) => (name, position.parse::<usize>().ok()?),
+                None => (step, 1),
+            };
+            node = node.children().filter(|c| c.path_step_name() == name).nth(position.checked_sub(1)?)?;
+        }
+        Some(node)
+    }
+
+    /// The element whose `xml:id` attribute has a value.
+    pub fn element_by_id(&self, id: &str) -> Option<Node<'_, 'input>> {
+        self.descendants().find(|n| n.attribute("xml:id") == Some(id))
+    }
+
+    /// The element that an [XPointer](https://www.w3.org/TR/xptr-framework/) identifies, e.g. the fragment identifier of a link.
+    /// Shorthand pointers like `intro` and the `element()` scheme, e.g. `element(intro/2)` or `element(/1/3)`, are supported.
+    pub fn resolve_xpointer(&self, pointer: &str) -> Option<Node<'_, 'input>> {
+        xpointer::resolve(self, pointer)
+    }
+
+    /// The text the document was parsed from.
+    pub fn input_text(&self) -> &'input str {
+        self.text
+    }
+
+    /// Figures about the shape of the document, like the number of elements per depth and per name.
+    pub fn metrics(&self) -> DocumentMetrics {
+        DocumentMetrics::of(self)
+    }
+
+    /// Total number of nodes in the arena, including the root and detached nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// A document always contains at least its root node.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Get a handle to edit a node, if it belongs to this document.
+    pub fn node_mut(&mut self, id: NodeId) -> Option<NodeMut<'_, 'input>> {
+        if id.0 < self.nodes.len() {
+            Some(NodeMut { id, doc: self })
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn node(&self, id: NodeId) -> Node<'_, 'input> {
+        Node { id, doc: self, data: &self.nodes[id.0] }
+    }
+
+    /// Forget the source ranges of a node and its ancestors, whose markup changes with an edit of the node.
+    fn invalidate_source(&mut self, id: NodeId) {
+        let mut next = Some(id);
+        while let Some(id) = next {
+            let data = &mut self.nodes[id.0];
+            data.source = None;
+            next = data.parent;
+        }
+    }
+}
+
+impl NodeKind<'_> {
+    fn to_owned_kind<'b>(&self) -> NodeKind<'b> {
+        let own = |value: &Cow<str>| Cow::Owned(value.to_string());
+        match self {
+            NodeKind::Root => NodeKind::Root,
+            NodeKind::Element { name, attributes } => NodeKind::Element {
+                name: own(name),
+                attributes: Attributes(attributes.iter().map(|a| Attribute { name: own(&a.name), value: own(&a.value) }).collect()),
+            },
+            NodeKind::Text(text) => NodeKind::Text(own(text)),
+            NodeKind::CdataSection(text) => NodeKind::CdataSection(own(text)),
+            NodeKind::Comment(text) => NodeKind::Comment(own(text)),
+            NodeKind::ProcessingInstruction { target, opt_value } => NodeKind::ProcessingInstruction {
+                target: own(target),
+                opt_value: opt_value.as_ref().map(own),
+            },
+        }
+    }
+}
+
+fn sort_key(element: Node, key: &SortKey) -> Option<String> {
+    match key {
+        SortKey::TagName => Some(element.tag_name().to_string()),
+        SortKey::Attribute(name) => element.attribute_decoded(name).map(Cow::into_owned),
+        SortKey::ChildText(name) => element.children_named(name).next().map(|child| child.text_decoded().unwrap_or_default().into_owned()),
+    }
+}
+
+/// The values of the `id` and `xml:id` attributes of a subtree.
+fn ids<'a>(node: Node<'a, '_>) -> impl Iterator<Item=&'a str> {
+    node.descendants()
+        .flat_map(|n| n.attributes())
+        .filter(|a| a.name() == "id" || a.name() == "xml:id")
+        .map(|a| a.value())
+}
+
+/// The alternate form `{:#?}` is the tree of [Display](#impl-Display-for-Document%3C'_%3E).
+impl Debug for Document<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            return Display::fmt(self, f);
+        }
+        f.debug_struct("Document").field("root", &self.root_element()).finish()
+    }
+}
+
+/// The tree of nodes, see [Node]'s `Display`.
+impl Display for Document<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.root(), f)
+    }
+}
+
+/// A [Document] that owns the text it was parsed from.
+///
+/// Useful wherever the input buffer cannot outlive the document on its own,
+/// e.g. across FFI boundaries. Cloning shares the input text and copies the node arena.
+#[derive(Clone)]
+pub struct OwnedDocument {
+    // drop order matters: the document borrows from text
+    doc: Document<'static>,
+    _text: Arc<str>,
+}
+
+impl OwnedDocument {
+    pub fn parse(xml: String) -> Result<OwnedDocument, XmlError> {
+        Self::parse_with_options(xml, &ParseOptions::default())
+    }
+
+    pub fn parse_with_options(xml: String, options: &ParseOptions) -> Result<OwnedDocument, XmlError> {
+        Self::build(xml, |text| Document::parse_with_options(text, options))
+    }
+
+    /// Build a document that borrows from `xml`, `parse` is generic over the lifetime so it cannot keep the text elsewhere.
+    pub(crate) fn build(xml: String, parse: impl for<'t> FnOnce(&'t str) -> Result<Document<'t>, XmlError>) -> Result<OwnedDocument, XmlError> {
+        let text: Arc<str> = Arc::from(xml);
+        // SAFETY: The shared str never moves and is never mutated, every clone of the document
+        // keeps it alive. The 'static lifetime never escapes: document() and node_mut() shorten it
+        // to the lifetime of the borrow of self.
+        let static_text: &'static str = unsafe { &*(text.as_ref() as *const str) };
+        let doc = parse(static_text)?;
+        Ok(OwnedDocument { doc, _text: text })
+    }
+
+    pub fn document(&self) -> &Document<'_> {
+        &self.doc
+    }
+
+    /// Get a handle to edit a node, see [Document::node_mut].
+    /// Values taken out of the document borrow from it, like those of [document](OwnedDocument::document):
+    ///
+    /// ```compile_fail
+    /// use jaxp_rust::document::OwnedDocument;
+    ///
+    /// let mut doc = OwnedDocument::parse("<a x='1'/>".to_string()).unwrap();
+    /// let id = doc.document().root_element().id();
+    /// let mut element = doc.node_mut(id).unwrap();
+    /// let removed = element.attributes_mut().unwrap().remove("x").unwrap();
+    /// drop(doc);
+    /// assert_eq!("1", removed.value());
+    /// ```
+    pub fn node_mut<'a>(&'a mut self, id: NodeId) -> Option<NodeMut<'a, 'a>> {
+        // SAFETY: The text outlives the borrow. NodeMut only accepts text that is copied into the document
+        // and has no &mut to values of the input lifetime, see AttributesMut, so nothing that lives
+        // shorter than the text can be stored through the shortened lifetime.
+        let doc = unsafe { &mut *(&mut self.doc as *mut Document<'static>).cast::<Document<'a>>() };
+        doc.node_mut(id)
+    }
+
+    /// Turn the document into a cheaply clonable, immutable handle.
+    pub fn into_shared(self) -> SharedDocument {
+        SharedDocument(Arc::new(self))
+    }
+}
+
+/// [\[2\] Char](https://www.w3.org/TR/xml/#NT-Char), the characters whose use is discouraged
+fn is_discouraged_char(c: char) -> bool {
+    matches!(c, '\u{7F}'..='\u{84}' | '\u{86}'..='\u{9F}' | '\u{FDD0}'..='\u{FDEF}')
+        // the last two code points of every plane
+        || (c as u32) & 0xFFFE == 0xFFFE
+}
+
+fn is_reserved_name(name: &str) -> bool {
+    let is_namespace_name = matches!(split_qname(name), (Some("xml"), _) | (Some("xmlns"), _) | (None, "xmlns"));
+    !is_namespace_name && name.as_bytes().get(..3).is_some_and(|prefix| prefix.eq_ignore_ascii_case(b"xml"))
+}
+
+fn check_chars(ci: &CharIter, range: &TextRange, warnings: &mut Vec<XmlWarning>) {
+    for (i, c) in range.slice.char_indices().filter(|(_, c)| is_discouraged_char(*c)) {
+        warnings.push(XmlWarning::DiscouragedCharacter { pos: ci.error_pos_of(range.start + i), character: c });
+    }
+}
+
+fn collect_warnings(xml: &str, tokens: &[XmlToken]) -> Vec<XmlWarning> {
+    // only used for positions
+    let ci = CharIter { pos: 0, text: xml };
+    let mut warnings = vec![];
+    let is_standalone = matches!(
+        tokens.first(),
+        Some(XmlDeclaration { opt_standalone_range: Some(range), .. }) if range.slice == "yes"
+    );
+    // reported once, for the first construct that may declare markup outside of the document
+    let mut is_standalone_unverified = false;
+    for token in tokens {
+        match token {
+            StartTag(name_range) if is_reserved_name(name_range.slice) => {
+                warnings.push(XmlWarning::ReservedName { pos: ci.error_pos_of(name_range.start), name: name_range.slice.to_string() });
+            }
+            XmlToken::Attribute { name_range, value_range } => {
+                if is_reserved_name(name_range.slice) {
+                    warnings.push(XmlWarning::ReservedName { pos: ci.error_pos_of(name_range.start), name: name_range.slice.to_string() });
+                }
+                if value_range.slice.is_empty() {
+                    warnings.push(XmlWarning::EmptyAttributeValue { pos: ci.error_pos_of(name_range.start), name: name_range.slice.to_string() });
+                }
+                check_chars(&ci, value_range, &mut warnings);
+            }
+            Text(range) | CdataSection(range) | Comment(range) => check_chars(&ci, range, &mut warnings),
+            DocTypeDeclaration { name_range, opt_system_entity_range, .. } => {
+                warnings.push(XmlWarning::DoctypeIgnored { pos: ci.error_pos_of(name_range.start) });
+                if is_standalone && opt_system_entity_range.is_some() {
+                    warnings.push(XmlWarning::StandaloneUnverified { pos: ci.error_pos_of(name_range.start) });
+                    is_standalone_unverified = true;
+                }
+            }
+            ParameterEntityReference(name_range) if is_standalone && !is_standalone_unverified => {
+                warnings.push(XmlWarning::StandaloneUnverified { pos: ci.error_pos_of(name_range.start) });
+                is_standalone_unverified = true;
+            }
+            _ => (),
+        }
+    }
+    warnings
+}
+
+/// The largest input accepted by [parse_untrusted_bytes].
+pub const MAX_UNTRUSTED_INPUT_LEN: usize = 64 * 1024 * 1024;
+
+/// The deepest nesting of elements accepted by [parse_untrusted_bytes] and [ParseOptions::untrusted].
+pub const MAX_UNTRUSTED_DEPTH: usize = 1024;
+
+/// Parse bytes from an untrusted source, e.g. a fuzzer.
+///
+/// Never panics: invalid UTF-8 and oversized input are reported as errors. Memory use is linear in the input size,
+/// which is capped at [MAX_UNTRUSTED_INPUT_LEN], and nesting is capped at [MAX_UNTRUSTED_DEPTH], so that
+/// the recursive tree walks of serializing and comparing do not overflow the stack.
+/// Document type declarations are rejected, see [ParseOptions::untrusted].
+pub fn parse_untrusted_bytes(bytes: &[u8]) -> Result<OwnedDocument, XmlError> {
+    if bytes.len() > MAX_UNTRUSTED_INPUT_LEN {
+        return Err(LimitExceeded { limit: "input length".to_string(), max: MAX_UNTRUSTED_INPUT_LEN });
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(xml) => OwnedDocument::parse_with_options(xml.to_string(), &ParseOptions::untrusted()),
+        Err(e) => {
+            // checked by from_utf8
+            let valid = std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap();
+            Err(InvalidUtf8 { pos: CharIter { pos: 0, text: valid }.error_pos_of(valid.len()) })
+        }
+    }
+}
+
+impl Debug for OwnedDocument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.doc, f)
+    }
+}
+
+impl Display for OwnedDocument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.doc, f)
+    }
+}
+
+/// An [OwnedDocument] behind an [Arc], cloning only increments the reference count.
+///
+/// Edits are copy-on-write: [make_mut](SharedDocument::make_mut) copies the node arena
+/// if the document is shared, other handles keep seeing the unmodified document.
+#[derive(Clone, Debug)]
+pub struct SharedDocument(Arc<OwnedDocument>);
+
+impl SharedDocument {
+    /// Get mutable access, copying the document first if other handles exist.
+    pub fn make_mut(&mut self) -> &mut OwnedDocument {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Whether other handles share the same document.
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.0) > 1
+    }
+}
+
+impl Deref for SharedDocument {
+    type Target = OwnedDocument;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<OwnedDocument> for SharedDocument {
+    fn from(doc: OwnedDocument) -> Self {
+        doc.into_shared()
+    }
+}
+
+/// A handle to a node of a [Document].
+#[derive(Clone, Copy)]
+pub struct Node<'a, 'input: 'a> {
+    id: NodeId,
+    doc: &'a Document<'input>,
+    data: &'a NodeData<'input>,
+}
+
+impl PartialEq for Node<'_, '_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && std::ptr::eq(self.doc, other.doc)
+    }
+}
+
+impl<'a, 'input: 'a> Node<'a, 'input> {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn document(&self) -> &'a Document<'input> {
+        self.doc
+    }
+
+    pub fn node_type(&self) -> NodeType {
+        match self.data.kind {
+            NodeKind::Root => NodeType::Root,
+            NodeKind::Element { .. } => NodeType::Element,
+            NodeKind::Text(_) => NodeType::Text,
+            NodeKind::CdataSection(_) => NodeType::CdataSection,
+            NodeKind::Comment(_) => NodeType::Comment,
+            NodeKind::ProcessingInstruction { .. } => NodeType::ProcessingInstruction,
+        }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.node_type() == NodeType::Root
+    }
+
+    pub fn is_element(&self) -> bool {
+        self.node_type() == NodeType::Element
+    }
+
+    /// True for text nodes and CDATA sections.
+    pub fn is_text(&self) -> bool {
+        matches!(self.node_type(), NodeType::Text | NodeType::CdataSection)
+    }
+
+    pub fn is_comment(&self) -> bool {
+        self.node_type() == NodeType::Comment
+    }
+
+    pub fn is_pi(&self) -> bool {
+        self.node_type() == NodeType::ProcessingInstruction
+    }
+
+    /// The name of an element or the target of a processing instruction, empty otherwise.
+    pub fn tag_name(&self) -> &'a str {
+        match &self.data.kind {
+            NodeKind::Element { name, .. } => name,
+            NodeKind::ProcessingInstruction { target, .. } => target,
+            _ => "",
+        }
+    }
+
+    /// The markup of an element as it appears in the input, from the start of its start tag to the end of its end tag.
+    /// `None` once the element or its subtree is edited, for elements added by editing and for tag soup.
+    pub fn original_markup(&self) -> Option<&'input str> {
+        self.data.source.clone().map(|range| &self.doc.text[range])
+    }
+
+    /// Whether the [tag name](Node::tag_name) is a name, with a cheap check of length and first and last byte first.
+    pub fn name_eq(&self, name: &str) -> bool {
+        names_eq(self.tag_name(), name)
+    }
+
+    /// The attributes of an element in document order, empty for all other nodes.
+    pub fn attributes(&self) -> slice::Iter<'a, Attribute<'input>> {
+        self.attribute_map().iter()
+    }
+
+    /// The attributes of an element with lookups by name and position, empty for all other nodes.
+    pub fn attribute_map(&self) -> &'a Attributes<'input> {
+        match &self.data.kind {
+            NodeKind::Element { attributes, .. } => attributes,
+            _ => &NO_ATTRIBUTES,
+        }
+    }
+
+    /// The value of the attribute with the specified name as it appears in the markup.
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.attribute_map().get(name).map(|a| a.value())
+    }
+
+    /// The value of the attribute with the specified name with references resolved.
+    pub fn attribute_decoded(&self, name: &str) -> Option<Cow<'a, str>> {
+        self.attribute(name).map(decode_entities)
+    }
+
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.attribute(name).is_some()
+    }
+
+    /// The value of the attribute with the specified namespace and local name.
+    /// The empty namespace selects unprefixed attributes.
+    pub fn attribute_ns(&self, uri: &str, local_name: &str) -> Option<&'a str> {
+        self.attributes()
+            .find(|a| a.local_name() == local_name && self.attribute_namespace_uri(a).unwrap_or("") == uri)
+            .map(|a| a.value())
+    }
+
+    pub fn has_attribute_ns(&self, uri: &str, local_name: &str) -> bool {
+        self.attribute_ns(uri, local_name).is_some()
+    }
+
+    /// Whether this is an element with the specified namespace and local name.
+    /// The empty namespace matches elements in no namespace.
+    pub fn has_tag_name_ns(&self, uri: &str, local_name: &str) -> bool {
+        self.is_element() && names_eq(self.local_name(), local_name) && self.namespace_uri().unwrap_or("") == uri
+    }
+
+    /// The first child element with the specified namespace and local name.
+    pub fn child_element_ns(&self, uri: &str, local_name: &str) -> Option<Node<'a, 'input>> {
+        self.children().find(|c| c.has_tag_name_ns(uri, local_name))
+    }
+
+    /// The child nodes that are elements.
+    pub fn child_elements(&self) -> ChildElements<'a, 'input> {
+        ChildElements { children: self.children() }
+    }
+
+    /// The child elements with a qualified name.
+    pub fn children_named<'n>(&self, name: &'n str) -> Named<'n, ChildElements<'a, 'input>> {
+        Named { nodes: self.child_elements(), test: NameTest::QName(name) }
+    }
+
+    /// The child elements with a namespace and local name, see [has_tag_name_ns](Node::has_tag_name_ns).
+    pub fn children_named_ns<'n>(&self, uri: &'n str, local_name: &'n str) -> Named<'n, ChildElements<'a, 'input>> {
+        Named { nodes: self.child_elements(), test: NameTest::Expanded { uri, local_name } }
+    }
+
+    /// The kind of content of an element, [ContentModel::Empty] for all other nodes.
+    /// Whitespace text next to elements is not significant, unlike whitespace in CDATA sections.
+    pub fn content_model(&self) -> ContentModel {
+        if !self.is_element() {
+            return ContentModel::Empty;
+        }
+        let mut has_elements = false;
+        let mut has_text = false;
+        let mut has_significant_text = false;
+        for child in self.children() {
+            match &child.data.kind {
+                NodeKind::Element { .. } => has_elements = true,
+                NodeKind::Text(text) => {
+                    has_text = true;
+                    has_significant_text |= !text.bytes().all(|b| b.is_xml_whitespace());
+                }
+                NodeKind::CdataSection(_) => {
+                    has_text = true;
+                    has_significant_text = true;
+                }
+                _ => (),
+            }
+        }
+        match (has_elements, has_significant_text) {
+            (true, true) => ContentModel::Mixed,
+            (true, false) => ContentModel::ElementOnly,
+            (false, _) if has_text => ContentModel::TextOnly,
+            (false, _) => ContentModel::Empty,
+        }
+    }
+
+    /// Whether this is an element that contains text, but no elements.
+    pub fn is_text_only(&self) -> bool {
+        self.content_model() == ContentModel::TextOnly
+    }
+
+    /// Whether this is an element that contains both elements and text other than whitespace.
+    pub fn has_mixed_content(&self) -> bool {
+        self.content_model() == ContentModel::Mixed
+    }
+
+    /// For text, comments and processing instructions the value of the node itself,
+    /// for elements the value of the first child if it is a text node.
+    pub fn text(&self) -> Option<&'a str> {
+        match &self.data.kind {
+            NodeKind::Element { .. } => self.first_child().filter(|c| c.is_text()).and_then(|c| c.text()),
+            NodeKind::Text(text) | NodeKind::CdataSection(text) | NodeKind::Comment(text) => Some(text),
+            NodeKind::ProcessingInstruction { opt_value, .. } => opt_value.as_deref(),
+            NodeKind::Root => None,
+        }
+    }
+
+    /// Like [Node::text], with the references of text resolved.
+    /// CDATA sections, comments and processing instructions contain no references and are returned as they are.
+    pub fn text_decoded(&self) -> Option<Cow<'a, str>> {
+        match &self.data.kind {
+            NodeKind::Element { .. } => self.first_child().filter(|c| c.is_text()).and_then(|c| c.text_decoded()),
+            NodeKind::Text(text) => Some(decode_entities(text)),
+            _ => self.text().map(Cow::Borrowed),
+        }
+    }
+
+    /// Decode the base64 content of a text node, or of all text and CDATA children of an element.
+    pub fn decode_base64(&self) -> Result<Vec<u8>, BinaryError> {
+        let mut bytes = Vec::new();
+        let mut decoder = Base64Decoder::new();
+        for text in self.binary_texts() {
+            decoder.push(&text, &mut bytes)?;
+        }
+        decoder.finish()?;
+        Ok(bytes)
+    }
+
+    /// Decode the hex content of a text node, or of all text and CDATA children of an element.
+    pub fn decode_hex(&self) -> Result<Vec<u8>, BinaryError> {
+        let mut bytes = Vec::new();
+        let mut decoder = HexDecoder::new();
+        for text in self.binary_texts() {
+            decoder.push(&text, &mut bytes)?;
+        }
+        decoder.finish()?;
+        Ok(bytes)
+    }
+
+    /// The text of this node if it is text, otherwise of its text children, with references resolved.
+    /// Encoders escape line breaks as references, e.g. `&#xD;`.
+    fn binary_texts(&self) -> impl Iterator<Item=Cow<'a, str>> {
+        self.children().chain(Some(*self)).filter(|n| n.is_text()).filter_map(|n| n.text_decoded())
+    }
+
+    /// The prefix of the name of an element, if any.
+    pub fn prefix(&self) -> Option<&'a str> {
+        split_qname(self.tag_name()).0
+    }
+
+    /// The name of an element without its prefix.
+    pub fn local_name(&self) -> &'a str {
+        split_qname(self.tag_name()).1
+    }
+
+    /// The namespace of an element, resolved from the declarations in scope.
+    pub fn namespace_uri(&self) -> Option<&'a str> {
+        if !self.is_element() {
+            return None;
+        }
+        self.resolve_prefix(self.prefix().unwrap_or(""))
+    }
+
+    /// The namespace bound to a prefix at this node, `None` looks up the default namespace.
+    pub fn lookup_namespace(&self, prefix: Option<&str>) -> Option<&'a str> {
+        self.resolve_prefix(prefix.unwrap_or(""))
+    }
+
+    /// The innermost prefix bound to a namespace at this node, the empty string for the default namespace.
+    pub fn lookup_prefix(&self, uri: &str) -> Option<&'a str> {
+        self.namespaces_in_scope()
+            .into_iter()
+            .find(|ns| ns.uri() == uri)
+            .map(|ns| ns.prefix().unwrap_or(""))
+    }
+
+    /// All namespace bindings in scope at this node, including the implicit `xml` binding.
+    /// Inner declarations shadow outer ones, bindings are ordered from the innermost declaration outwards.
+    pub fn namespaces_in_scope(&self) -> Vec<Namespace<'a>> {
+        let mut seen: Vec<&str> = vec![];
+        let mut namespaces = vec![];
+        for node in self.ancestors() {
+            for attribute in node.attributes() {
+                if let Some(prefix) = declared_prefix(attribute.name()) {
+                    if seen.contains(&prefix) {
+                        continue;
+                    }
+                    seen.push(prefix);
+                    // an empty value undeclares the default namespace
+                    if !attribute.value().is_empty() {
+                        let prefix = if prefix.is_empty() { None } else { Some(prefix) };
+                        namespaces.push(Namespace { prefix, uri: attribute.value() });
+                    }
+                }
+            }
+        }
+        namespaces.push(Namespace { prefix: Some("xml"), uri: XML_NAMESPACE });
+        namespaces
+    }
+
+    /// Resolve a prefix to the namespace bound to it at this node, the empty prefix resolves the default namespace.
+    pub(crate) fn resolve_prefix(&self, prefix: &str) -> Option<&'a str> {
+        if prefix == "xml" {
+            return Some(XML_NAMESPACE);
+        }
+        if prefix == "xmlns" {
+            return Some(XMLNS_NAMESPACE);
+        }
+        self.ancestors()
+            .find_map(|n| n.attributes().find(|a| declared_prefix(a.name()) == Some(prefix)))
+            .map(|a| a.value())
+            // an empty value undeclares the default namespace
+            .filter(|uri| !uri.is_empty())
+    }
+
+    /// The namespace of an attribute of this element. Unprefixed attributes are in no namespace.
+    pub fn attribute_namespace_uri(&self, attribute: &Attribute) -> Option<&'a str> {
+        match split_qname(attribute.name()) {
+            (Some(prefix), _) => self.resolve_prefix(prefix),
+            (None, "xmlns") => Some(XMLNS_NAMESPACE),
+            (None, _) => None,
+        }
+    }
+
+    /// The language in scope for this node as declared by the nearest `xml:lang` attribute,
+    /// see [2.12 Language Identification](https://www.w3.org/TR/xml/#sec-lang-tag).
+    /// An empty `xml:lang` value resets the language to unspecified.
+    pub fn language(&self) -> Option<&'a str> {
+        self.ancestors()
+            .find_map(|n| n.attribute("xml:lang"))
+            .filter(|lang| !lang.is_empty())
+    }
+
+    /// The text directly following an element, if any.
+    pub fn tail(&self) -> Option<&'a str> {
+        self.next_sibling().filter(|s| s.is_text()).and_then(|s| s.text())
+    }
+
+    pub fn parent(&self) -> Option<Node<'a, 'input>> {
+        self.data.parent.map(|id| self.doc.node(id))
+    }
+
+    pub fn parent_element(&self) -> Option<Node<'a, 'input>> {
+        self.ancestors().skip(1).find(|n| n.is_element())
+    }
+
+    pub fn has_children(&self) -> bool {
+        !self.data.children.is_empty()
+    }
+
+    pub fn children(&self) -> Children<'a, 'input> {
+        Children { doc: self.doc, iter: self.data.children.iter() }
+    }
+
+    pub fn first_child(&self) -> Option<Node<'a, 'input>> {
+        self.data.children.first().map(|id| self.doc.node(*id))
+    }
+
+    pub fn last_child(&self) -> Option<Node<'a, 'input>> {
+        self.data.children.last().map(|id| self.doc.node(*id))
+    }
+
+    /// The number of child nodes of any type.
+    pub fn child_count(&self) -> usize {
+        self.data.children.len()
+    }
+
+    pub fn first_element_child(&self) -> Option<Node<'a, 'input>> {
+        self.child_elements().next()
+    }
+
+    pub fn last_element_child(&self) -> Option<Node<'a, 'input>> {
+        self.child_elements().next_back()
+    }
+
+    /// The child element at an index among the child elements, starting at 0.
+    pub fn nth_element_child(&self, index: usize) -> Option<Node<'a, 'input>> {
+        self.child_elements().nth(index)
+    }
+
+    pub fn prev_sibling(&self) -> Option<Node<'a, 'input>> {
+        let siblings = &self.parent()?.data.children;
+        let index = self.index_in(siblings);
+        if index == 0 {
+            None
+        } else {
+            Some(self.doc.node(siblings[index - 1]))
+        }
+    }
+
+    pub fn next_sibling(&self) -> Option<Node<'a, 'input>> {
+        let siblings = &self.parent()?.data.children;
+        siblings.get(self.index_in(siblings) + 1).map(|id| self.doc.node(*id))
+    }
+
+    /// Iterate over this node and all its ancestors, up to the root.
+    pub fn ancestors(&self) -> Ancestors<'a, 'input> {
+        Ancestors { next: Some(*self) }
+    }
+
+    /// Iterate over this node and all its descendants in document order.
+    pub fn descendants(&self) -> Descendants<'a, 'input> {
+        Descendants { doc: self.doc, next: Some(self.id), stack: vec![] }
+    }
+
+    /// A path from the root to this node, e.g. `/root[1]/item[3]/name[1]`, to refer to the node across parses of the same document.
+    ///
+    /// Every step selects a child by its kind and its position among the siblings of the same kind, counting from 1.
+    /// Elements are selected by qualified name, other nodes by `text()`, which includes CDATA sections,
+    /// `comment()` and `processing-instruction()`. The root's path is `/`.
+    pub fn path(&self) -> String {
+        let mut steps: Vec<String> = self.ancestors()
+            .take_while(|n| !n.is_root())
+            .map(|n| {
+                let name = n.path_step_name();
+                let position = n.parent().map_or(1, |p| {
+                    p.children().take_while(|c| c != &n).filter(|c| c.path_step_name() == name).count() + 1
+                });
+                format!("{}[{}]", name, position)
+            })
+            .collect();
+        steps.reverse();
+        format!("/{}", steps.join("/"))
+    }
+
+    /// A hash of the subtree of this node, which is equal for subtrees with the same names, attributes in the same order
+    /// and content, as it appears in the markup. The hash is stable within one build of the library only.
+    pub fn subtree_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash_subtree(&mut hasher);
+        hasher.finish()
+    }
+
+    fn hash_subtree(&self, hasher: &mut DefaultHasher) {
+        self.node_type().hash(hasher);
+        self.tag_name().hash(hasher);
+        for attribute in self.attributes() {
+            attribute.name().hash(hasher);
+            attribute.value().hash(hasher);
+        }
+        if !self.is_element() {
+            self.text().hash(hasher);
+        }
+        self.data.children.len().hash(hasher);
+        for child in self.children() {
+            child.hash_subtree(hasher);
+        }
+    }
+
+    /// Whether the subtrees of this node and another node, possibly of another document, are equal
+    /// in the sense of [subtree_hash](Node::subtree_hash).
+    pub fn subtree_eq(&self, other: &Node) -> bool {
+        let is_equal = self.node_type() == other.node_type()
+            && self.tag_name() == other.tag_name()
+            && self.attributes().eq(other.attributes())
+            && (self.is_element() || self.text() == other.text())
+            && self.data.children.len() == other.data.children.len();
+        is_equal && self.children().zip(other.children()).all(|(a, b)| a.subtree_eq(&b))
+    }
+
+    /// The child elements that are equal to an earlier sibling, each with the first of its equals.
+    pub fn duplicate_child_elements(&self) -> Vec<(Node<'a, 'input>, Node<'a, 'input>)> {
+        let mut firsts: HashMap<u64, Vec<Node<'a, 'input>>> = HashMap::new();
+        let mut duplicates = vec![];
+        for child in self.child_elements() {
+            let candidates = firsts.entry(child.subtree_hash()).or_default();
+            match candidates.iter().find(|first| first.subtree_eq(&child)) {
+                Some(first) => duplicates.push((child, *first)),
+                None => candidates.push(child),
+            }
+        }
+        duplicates
+    }
+
+    fn path_step_name(&self) -> &'a str {
+        match self.node_type() {
+            NodeType::Element => self.tag_name(),
+            NodeType::Text | NodeType::CdataSection => "text()",
+            NodeType::Comment => "comment()",
+            NodeType::ProcessingInstruction => "processing-instruction()",
+            NodeType::Root => "",
+        }
+    }
+
+    fn index_in(&self, siblings: &[NodeId]) -> usize {
+        debug_assert_eq!(siblings.get(self.data.index), Some(&self.id));
+        self.data.index
+    }
+}
+
+/// The alternate form `{:#?}` is the tree of [Display](#impl-Display-for-Node%3C'_,+'_%3E).
+impl Debug for Node<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            return Display::fmt(self, f);
+        }
+        match self.node_type() {
+            NodeType::Element => f.debug_struct("Element")
+                .field("name", &self.tag_name())
+                .field("attributes", &self.attributes().map(|a| (a.name(), a.value())).collect::<Vec<_>>())
+                .field("children", &self.children().collect::<Vec<_>>())
+                .finish(),
+            NodeType::ProcessingInstruction => f.debug_struct("ProcessingInstruction")
+                .field("target", &self.tag_name())
+                .field("value", &self.text())
+                .finish(),
+            node_type => f.debug_tuple(&format!("{:?}", node_type)).field(&self.text()).finish(),
+        }
+    }
+}
+
+/// The subtree with one node per line, indented by depth, e.g. `Element a @1:1` followed by `  Text "x" @1:3`.
+/// Attributes are on lines of their own after their element, text is truncated to [TREE_TEXT_LEN] characters.
+/// The position is that of the name or the text in the input, see [LineIndex::pos], nodes that were edited have none.
+impl Display for Node<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let input = self.doc.input_text();
+        let lines = LineIndex::new(input);
+        let position = |text: &str| {
+            let start = (text.as_ptr() as usize).checked_sub(input.as_ptr() as usize)
+                .filter(|start| start + text.len() <= input.len() && !text.is_empty());
+            match start {
+                Some(start) => {
+                    let pos = lines.pos(start);
+                    format!(" @{}:{}", pos.row, pos.col)
+                }
+                None => String::new(),
+            }
+        };
+        let mut stack = vec![(*self, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            let indent = "  ".repeat(depth);
+            match node.node_type() {
+                NodeType::Root => writeln!(f, "{}Document", indent)?,
+                NodeType::Element => {
+                    writeln!(f, "{}Element {}{}", indent, node.tag_name(), position(node.tag_name()))?;
+                    for attribute in node.attributes() {
+                        writeln!(f, "{}  Attribute {}={}{}", indent, attribute.name(), truncate(attribute.value()), position(attribute.name()))?;
+                    }
+                }
+                NodeType::ProcessingInstruction => {
+                    let value = node.text().map(|value| format!(" {}", truncate(value))).unwrap_or_default();
+                    writeln!(f, "{}ProcessingInstruction {}{}{}", indent, node.tag_name(), value, position(node.tag_name()))?;
+                }
+                node_type => {
+                    let text = node.text().unwrap();
+                    writeln!(f, "{}{:?} {}{}", indent, node_type, truncate(text), position(text))?;
+                }
+            }
+            stack.extend(node.children().rev().map(|child| (child, depth + 1)));
+        }
+        Ok(())
+    }
+}
+
+/// The number of characters of text that the tree of a node shows
+pub const TREE_TEXT_LEN: usize = 40;
+
+pub struct Children<'a, 'input: 'a> {
+    doc: &'a Document<'input>,
+    iter: slice::Iter<'a, NodeId>,
+}
+
+impl<'a, 'input: 'a> Iterator for Children<'a, 'input> {
+    type Item = Node<'a, 'input>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|id| self.doc.node(*id))
+    }
+}
+
+impl<'a, 'input: 'a> DoubleEndedIterator for Children<'a, 'input> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|id| self.doc.node(*id))
+    }
+}
+
+pub struct ChildElements<'a, 'input: 'a> {
+    children: Children<'a, 'input>,
+}
+
+impl<'a, 'input: 'a> Iterator for ChildElements<'a, 'input> {
+    type Item = Node<'a, 'input>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.children.find(|c| c.is_element())
+    }
+}
+
+impl<'a, 'input: 'a> DoubleEndedIterator for ChildElements<'a, 'input> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.children.rfind(|c| c.is_element())
+    }
+}
+
+enum NameTest<'n> {
+    QName(&'n str),
+    Expanded { uri: &'n str, local_name: &'n str },
+}
+
+impl NameTest<'_> {
+    fn matches(&self, node: &Node) -> bool {
+        match *self {
+            NameTest::QName(name) => node.is_element() && node.name_eq(name),
+            NameTest::Expanded { uri, local_name } => node.has_tag_name_ns(uri, local_name),
+        }
+    }
+}
+
+/// The elements of another node iterator that have a name,
+/// see [Document::elements_named] and [Node::children_named].
+pub struct Named<'n, I> {
+    nodes: I,
+    test: NameTest<'n>,
+}
+
+impl<'a, 'input: 'a, I: Iterator<Item=Node<'a, 'input>>> Iterator for Named<'_, I> {
+    type Item = Node<'a, 'input>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let test = &self.test;
+        self.nodes.find(|n| test.matches(n))
+    }
+}
+
+pub struct Ancestors<'a, 'input: 'a> {
+    next: Option<Node<'a, 'input>>,
+}
+
+impl<'a, 'input: 'a> Iterator for Ancestors<'a, 'input> {
+    type Item = Node<'a, 'input>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = node.parent();
+        Some(node)
+    }
+}
+
+pub struct Descendants<'a, 'input: 'a> {
+    doc: &'a Document<'input>,
+    next: Option<NodeId>,
+    stack: Vec<slice::Iter<'a, NodeId>>,
+}
+
+impl<'a, 'input: 'a> Iterator for Descendants<'a, 'input> {
+    type Item = Node<'a, 'input>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.doc.node(self.next.take()?);
+        self.stack.push(node.data.children.iter());
+        // depth-first: descend if possible, otherwise continue with the next sibling of an ancestor
+        while let Some(siblings) = self.stack.last_mut() {
+            match siblings.next() {
+                Some(id) => {
+                    self.next = Some(*id);
+                    break;
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+        Some(node)
+    }
+}
+
+/// A handle to edit a node of a [Document].
+///
+/// Text and attribute values are passed unescaped and escaped when stored.
+/// Nodes that an edit removes from the tree, with [detach](NodeMut::detach) or by replacing the content
+/// of an element with [set_text](NodeMut::set_text), keep their slots in the arena until the document is dropped:
+/// their ids stay valid and [Document::len] counts them. A document that is edited over a long time
+/// can be compacted by serializing and parsing it again.
+pub struct NodeMut<'a, 'input: 'a> {
+    id: NodeId,
+    doc: &'a mut Document<'input>,
+}
+
+impl<'a, 'input: 'a> NodeMut<'a, 'input> {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// The data of the node for editing, which changes the markup of the node and its ancestors.
+    fn data(&mut self) -> &mut NodeData<'input> {
+        self.doc.invalidate_source(self.id);
+        &mut self.doc.nodes[self.id.0]
+    }
+
+    /// Rename an element or change the target of a processing instruction.
+    /// Returns false for all other nodes.
+    pub fn set_tag_name(&mut self, new_name: &str) -> bool {
+        match &mut self.data().kind {
+            NodeKind::Element { name, .. } => *name = Cow::Owned(new_name.to_string()),
+            NodeKind::ProcessingInstruction { target, .. } => *target = Cow::Owned(new_name.to_string()),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Set the value of an attribute, adding it if it does not exist yet.
+    /// Returns false if the node is not an element.
+    pub fn set_attribute(&mut self, name: &str, value: &str) -> bool {
+        match self.attributes_mut() {
+            Some(mut attributes) => {
+                attributes.insert(name, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove an attribute, returns whether it existed.
+    pub fn remove_attribute(&mut self, name: &str) -> bool {
+        self.attributes_mut().is_some_and(|mut attributes| attributes.remove(name).is_some())
+    }
+
+    /// The attributes of an element for editing, None for all other nodes.
+    pub fn attributes_mut(&mut self) -> Option<AttributesMut<'_, 'input>> {
+        match &mut self.data().kind {
+            NodeKind::Element { attributes, .. } => Some(AttributesMut(attributes)),
+            _ => None,
+        }
+    }
+
+    /// Set the value of a text node, CDATA section, comment or processing instruction.
+    /// The content of an element is replaced by a single text node, the old children are detached.
+    pub fn set_text(&mut self, text: &str) {
+        match &mut self.data().kind {
+            NodeKind::Text(value) => *value = Cow::Owned(escape_text(text).into_owned()),
+            NodeKind::CdataSection(value) | NodeKind::Comment(value) => *value = Cow::Owned(text.to_string()),
+            NodeKind::ProcessingInstruction { opt_value, .. } => *opt_value = Some(Cow::Owned(text.to_string())),
+            NodeKind::Root => (),
+            NodeKind::Element { .. } => {
+                for child in std::mem::take(&mut self.data().children) {
+                    self.doc.nodes[child.0].parent = None;
+                }
+                self.append_text(text);
+            }
+        }
+    }
+
+    /// Append a new element as the last child, returns its id.
+    pub fn append_element(&mut self, name: &str) -> NodeId {
+        self.doc.invalidate_source(self.id);
+        self.doc.append(self.id, NodeKind::Element { name: Cow::Owned(name.to_string()), attributes: Attributes::new() })
+    }
+
+    /// Append a new text node as the last child, returns its id.
+    pub fn append_text(&mut self, text: &str) -> NodeId {
+        self.doc.invalidate_source(self.id);
+        self.doc.append(self.id, NodeKind::Text(Cow::Owned(escape_text(text).into_owned())))
+    }
+
+    /// Copy a subtree of another document and insert it as a child at an index, returns the id of its root.
+    /// The root of a document stands for its root element.
+    ///
+    /// Namespaces that are in scope in the source, but not in the same way at the insertion point,
+    /// are declared on the root of the copy. IDs, the values of `id` and `xml:id` attributes,
+    /// must stay unique in the document, otherwise nothing is inserted.
+    ///
+    /// # Panics
+    /// If the index is greater than the number of children.
+    pub fn insert_subtree(&mut self, index: usize, source: Node) -> Result<NodeId, GraftError> {
+        if !self.doc.node(self.id).is_element() {
+            return Err(GraftError::NotAnElement);
+        }
+        let source = if source.is_root() { source.document().root_element() } else { source };
+        let mut known_ids: Vec<&str> = ids(self.doc.root()).collect();
+        for id in ids(source) {
+            if known_ids.contains(&id) {
+                return Err(GraftError::DuplicateId(id.to_string()));
+            }
+            known_ids.push(id);
+        }
+
+        let target = self.doc.node(self.id);
+        let mut declarations = vec![];
+        if source.is_element() {
+            for ns in source.namespaces_in_scope() {
+                let name = match ns.prefix() {
+                    Some("xml") => continue,
+                    Some(prefix) => format!("xmlns:{}", prefix),
+                    None => "xmlns".to_string(),
+                };
+                if !source.has_attribute(&name) && target.lookup_namespace(ns.prefix()) != Some(ns.uri()) {
+                    declarations.push(Attribute { name: Cow::Owned(name), value: Cow::Owned(ns.uri().to_string()) });
+                }
+            }
+            // the default namespace of the target does not apply to the copy
+            if source.lookup_namespace(None).is_none() && target.lookup_namespace(None).is_some() && !source.has_attribute("xmlns") {
+                declarations.push(Attribute { name: Cow::Borrowed("xmlns"), value: Cow::Borrowed("") });
+            }
+        }
+
+        // copy in document order, the parents of the copies are known before their children
+        let root = NodeId(self.doc.nodes.len());
+        let mut copies = HashMap::new();
+        for node in source.descendants() {
+            let copy = NodeId(self.doc.nodes.len());
+            let parent = if node == source { None } else { node.parent().map(|p| copies[&p.id()]) };
+            let index = if node == source { index } else { node.data.index };
+            self.doc.nodes.push(NodeData { kind: node.data.kind.to_owned_kind(), parent, children: vec![], index, source: None });
+            if let Some(parent) = parent {
+                self.doc.nodes[parent.0].children.push(copy);
+            }
+            copies.insert(node.id(), copy);
+        }
+        if let NodeKind::Element { attributes, .. } = &mut self.doc.nodes[root.0].kind {
+            for declaration in declarations {
+                attributes.push(declaration);
+            }
+        }
+        self.doc.nodes[root.0].parent = Some(self.id);
+        self.data().children.insert(index, root);
+        self.doc.reindex_children(self.id);
+        Ok(root)
+    }
+
+    /// Copy a subtree of another document and append it as the last child, see [insert_subtree](NodeMut::insert_subtree).
+    pub fn append_subtree(&mut self, source: Node) -> Result<NodeId, GraftError> {
+        let index = self.doc.nodes[self.id.0].children.len();
+        self.insert_subtree(index, source)
+    }
+
+    /// Parse a fragment with a single root element and append it as the last child, see [insert_subtree](NodeMut::insert_subtree).
+    pub fn append_fragment(&mut self, xml: &str) -> Result<NodeId, GraftError> {
+        let fragment = Document::parse(xml).map_err(GraftError::Parse)?;
+        self.append_subtree(fragment.root())
+    }
+
+    /// Remove the node and its subtree from the tree. The document root cannot be detached.
+    pub fn detach(&mut self) {
+        let id = self.id;
+        if let Some(parent) = self.data().parent.take() {
+            self.doc.nodes[parent.0].children.retain(|c| *c != id);
+            self.doc.reindex_children(parent);
+        }
+    }
+
+    /// Like [detach](NodeMut::detach), but an element keeps the namespaces that are in scope,
+    /// the declarations of its ancestors are copied onto it. The subtree can then be serialized on its own.
+    pub fn detach_with_namespaces(&mut self) {
+        let node = self.doc.node(self.id);
+        let declarations: Vec<(String, String)> = node.namespaces_in_scope().iter()
+            .filter(|ns| ns.prefix() != Some("xml"))
+            .map(|ns| match ns.prefix() {
+                Some(prefix) => (format!("xmlns:{}", prefix), ns.uri().to_string()),
+                None => ("xmlns".to_string(), ns.uri().to_string()),
+            })
+            .filter(|(name, _)| !node.has_attribute(name))
+            .collect();
+        if let Some(attributes) = self.attributes_mut() {
+            for (name, value) in declarations {
+                // the value is taken from the markup, it is escaped already
+                attributes.0.push(Attribute { name: Cow::Owned(name), value: Cow::Owned(value) });
+            }
+        }
+        self.detach();
+    }
+
+    /// Detach the child elements that are equal to an earlier sibling, see [Node::duplicate_child_elements],
+    /// each together with the whitespace-only text right before it. Returns the number of removed elements.
+    pub fn remove_duplicate_child_elements(&mut self) -> usize {
+        let node = self.doc.node(self.id);
+        let mut removed = vec![];
+        for (duplicate, _) in node.duplicate_child_elements() {
+            if let Some(space) = duplicate.prev_sibling().filter(|s| s.node_type() == NodeType::Text && s.text().unwrap().bytes().all(|b| b.is_xml_whitespace())) {
+                removed.push(space.id());
+            }
+            removed.push(duplicate.id());
+        }
+        let count = removed.iter().filter(|id| self.doc.node(**id).is_element()).count();
+        for id in removed {
+            NodeMut { id, doc: &mut *self.doc }.detach();
+        }
+        count
+    }
+
+    /// Reorder the child elements by a key, e.g. to normalize machine-generated documents before diffing them.
+    /// The elements take each other's positions, text, comments and processing instructions stay where they are.
+    pub fn sort_child_elements(&mut self, key: &SortKey) {
+        let doc = &*self.doc;
+        let children = &doc.nodes[self.id.0].children;
+        let positions: Vec<usize> = (0..children.len()).filter(|&i| doc.node(children[i]).is_element()).collect();
+        let mut elements: Vec<(Option<String>, NodeId)> = positions.iter().map(|&i| (sort_key(doc.node(children[i]), key), children[i])).collect();
+        elements.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if positions.iter().zip(&elements).all(|(&i, (_, id))| children[i] == *id) {
+            // the markup is unchanged
+            return;
+        }
+        let children = &mut self.data().children;
+        for (i, (_, id)) in positions.into_iter().zip(elements) {
+            children[i] = id;
+        }
+        self.doc.reindex_children(self.id);
+    }
+
+    /// Reorder the child elements of the node and of every element below it, see [sort_child_elements](NodeMut::sort_child_elements).
+    pub fn sort_descendant_elements(&mut self, key: &SortKey) {
+        let ids: Vec<NodeId> = self.doc.node(self.id).descendants().filter(|n| n.is_root() || n.is_element()).map(|n| n.id()).collect();
+        for id in ids {
+            NodeMut { id, doc: &mut *self.doc }.sort_child_elements(key);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Dumps of the token stream with the spans of the tokens, to share exactly what the tokenizer saw, e.g. in a bug report.
+//!
+//! ```
+//! use jaxp_rust::dump::dump_tokens;
+//! use jaxp_rust::options::ParseOptions;
+//!
+//! let dump = dump_tokens("<a x='1'/>", &ParseOptions::default());
+//! assert_eq!(3, dump.tokens.len());
+//! assert!(dump.to_json().starts_with("{\"tokens\":[{\"kind\":\"start-tag\",\"ranges\":[{\"field\":\"name\",\"start\":1,\"end\":2,"));
+//! ```
+
+use crate::error::XmlError;
+use crate::escape::write_json_string;
+use crate::options::ParseOptions;
+use crate::textrange::LineIndex;
+use crate::token::XmlToken;
+use crate::tokenize::XmlTokenizer;
+
+/// The tokens of a document, up to the error if the tokenizer rejects it.
+pub struct TokenDump<'a> {
+    pub tokens: Vec<XmlToken<'a>>,
+    pub opt_error: Option<XmlError>,
+    lines: LineIndex,
+}
+
+/// Tokenize a document, keeping the tokens before an error.
+pub fn dump_tokens<'a>(xml: &'a str, options: &ParseOptions) -> TokenDump<'a> {
+    let mut tokens = vec![];
+    let opt_error = XmlTokenizer::with_options(options).tokenize_into(xml, &mut tokens).err();
+    TokenDump { tokens, opt_error, lines: LineIndex::new(xml) }
+}
+
+impl TokenDump<'_> {
+    /// A JSON object with the `tokens` and the `error` message or `null`. Every token has a `kind` and `ranges`,
+    /// every range has the name of its `field`, its byte offsets `start` and `end`, the `row` and `col` of its start
+    /// and its `text`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"tokens\":[");
+        for (i, token) in self.tokens.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"kind\":");
+            write_json_string(token.kind(), &mut json);
+            json.push_str(",\"ranges\":[");
+            for (j, (field, range)) in token.ranges().into_iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                let pos = self.lines.pos(range.start());
+                json.push_str("{\"field\":");
+                write_json_string(field, &mut json);
+                json.push_str(&format!(",\"start\":{},\"end\":{},\"row\":{},\"col\":{},\"text\":", range.start(), range.end(), pos.row, pos.col));
+                write_json_string(range.slice(), &mut json);
+                json.push('}');
+            }
+            json.push_str("]}");
+        }
+        json.push_str("],\"error\":");
+        match &self.opt_error {
+            Some(e) => write_json_string(&e.to_string(), &mut json),
+            None => json.push_str("null"),
+        }
+        json.push('}');
+        json
+    }
+
+    /// A table with a line per range of a token, followed by a line with the error, if any.
+    pub fn to_table(&self) -> String {
+        let mut table = format!("{:<5} {:<26} {:<15} {:<11} {:<7} {}\n", "token", "kind", "field", "span", "row:col", "text");
+        for (i, token) in self.tokens.iter().enumerate() {
+            for (field, range) in token.ranges() {
+                let pos = self.lines.pos(range.start());
+                table.push_str(&format!(
+                    "{:<5} {:<26} {:<15} {:<11} {:<7} {:?}\n",
+                    i,
+                    token.kind(),
+                    field,
+                    format!("{}..{}", range.start(), range.end()),
+                    format!("{}:{}", pos.row, pos.col),
+                    range.slice(),
+                ));
+            }
+        }
+        if let Some(e) = &self.opt_error {
+            table.push_str(&format!("error: {}\n", e));
+        }
+        table
+    }
+}
@@ -0,0 +1,80 @@
+//! Named character references beyond the five predefined entities of XML.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The Latin-1 entities of XHTML 1.0, in the order of their code points starting at U+00A0.
+const LATIN_1: &[&str] = &[
+    "nbsp", "iexcl", "cent", "pound", "curren", "yen", "brvbar", "sect", "uml", "copy", "ordf", "laquo", "not", "shy",
+    "reg", "macr", "deg", "plusmn", "sup2", "sup3", "acute", "micro", "para", "middot", "cedil", "sup1", "ordm",
+    "raquo", "frac14", "frac12", "frac34", "iquest", "Agrave", "Aacute", "Acirc", "Atilde", "Auml", "Aring", "AElig",
+    "Ccedil", "Egrave", "Eacute", "Ecirc", "Euml", "Igrave", "Iacute", "Icirc", "Iuml", "ETH", "Ntilde", "Ograve",
+    "Oacute", "Ocirc", "Otilde", "Ouml", "times", "Oslash", "Ugrave", "Uacute", "Ucirc", "Uuml", "Yacute", "THORN",
+    "szlig", "agrave", "aacute", "acirc", "atilde", "auml", "aring", "aelig", "ccedil", "egrave", "eacute", "ecirc",
+    "euml", "igrave", "iacute", "icirc", "iuml", "eth", "ntilde", "ograve", "oacute", "ocirc", "otilde", "ouml",
+    "divide", "oslash", "ugrave", "uacute", "ucirc", "uuml", "yacute", "thorn", "yuml",
+];
+
+/// Frequently used entities of the special and symbol sets of XHTML 1.0.
+const SPECIAL: &[(&str, char)] = &[
+    ("OElig", '\u{152}'), ("oelig", '\u{153}'), ("Scaron", '\u{160}'), ("scaron", '\u{161}'), ("Yuml", '\u{178}'),
+    ("fnof", '\u{192}'), ("circ", '\u{2c6}'), ("tilde", '\u{2dc}'), ("ensp", '\u{2002}'), ("emsp", '\u{2003}'),
+    ("thinsp", '\u{2009}'), ("zwnj", '\u{200c}'), ("zwj", '\u{200d}'), ("lrm", '\u{200e}'), ("rlm", '\u{200f}'),
+    ("ndash", '\u{2013}'), ("mdash", '\u{2014}'), ("lsquo", '\u{2018}'), ("rsquo", '\u{2019}'), ("sbquo", '\u{201a}'),
+    ("ldquo", '\u{201c}'), ("rdquo", '\u{201d}'), ("bdquo", '\u{201e}'), ("dagger", '\u{2020}'), ("Dagger", '\u{2021}'),
+    ("bull", '\u{2022}'), ("hellip", '\u{2026}'), ("permil", '\u{2030}'), ("prime", '\u{2032}'), ("Prime", '\u{2033}'),
+    ("lsaquo", '\u{2039}'), ("rsaquo", '\u{203a}'), ("oline", '\u{203e}'), ("frasl", '\u{2044}'), ("euro", '\u{20ac}'),
+    ("trade", '\u{2122}'), ("larr", '\u{2190}'), ("uarr", '\u{2191}'), ("rarr", '\u{2192}'), ("darr", '\u{2193}'),
+    ("harr", '\u{2194}'), ("minus", '\u{2212}'), ("infin", '\u{221e}'), ("ne", '\u{2260}'), ("le", '\u{2264}'),
+    ("ge", '\u{2265}'),
+];
+
+/// Additional named entities that references like `&nbsp;` may refer to.
+///
+/// The table is empty by default, only the predefined entities `amp`, `lt`, `gt`, `apos` and `quot` are accepted then.
+/// Clones share their entries until one of them is modified.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EntityTable {
+    entries: Arc<HashMap<String, String>>,
+}
+
+impl EntityTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The Latin-1 set of XHTML 1.0, e.g. `&nbsp;` and `&eacute;`, and common typographic entities like `&mdash;`.
+    pub fn html() -> Self {
+        let mut table = Self::new();
+        for (i, name) in LATIN_1.iter().enumerate() {
+            let c = char::from_u32(0xa0 + i as u32).unwrap();
+            table.insert(name, &c.to_string());
+        }
+        for (name, c) in SPECIAL {
+            table.insert(name, &c.to_string());
+        }
+        table
+    }
+
+    /// Register an entity, replacing an earlier definition with the same name.
+    pub fn insert(&mut self, name: &str, replacement: &str) {
+        Arc::make_mut(&mut self.entries).insert(name.to_string(), replacement.to_string());
+    }
+
+    /// The replacement text of an entity.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.get(name).map(String::as_str)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
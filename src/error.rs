@@ -1,19 +1,219 @@
 use std::fmt::{Display, Formatter, Debug};
 
-#[derive(Debug)]
+use crate::refs::RefError;
+use crate::token::XmlToken::{EndTag, StartTag};
+use crate::tokenize::XmlTokenizer;
+
+/// A position in the input. Rows and columns count from 1 and columns count characters, not bytes.
+/// A line feed is the last character of its row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct XmlErrorPos {
     pub row: usize,
     pub col: usize
 }
 
+impl XmlErrorPos {
+    /// The byte offset of the position in the input that it was computed for.
+    fn offset_in(&self, xml: &str) -> usize {
+        let line_start = match self.row {
+            0 | 1 => 0,
+            row => xml.match_indices('\n').nth(row - 2).map_or(xml.len(), |(i, _)| i + 1),
+        };
+        xml[line_start..].char_indices().nth(self.col.saturating_sub(1)).map_or(xml.len(), |(i, _)| line_start + i)
+    }
+}
+
+/// A suggested edit of the input that resolves an error, e.g. for a quick-fix in an editor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fix {
+    /// Byte offset of the text to replace
+    pub start: usize,
+    /// Byte offset after the text to replace, equal to `start` for an insertion
+    pub end: usize,
+    pub replacement: String,
+}
+
+impl Fix {
+    /// Apply the fix to the input that the error was reported for.
+    pub fn apply(&self, xml: &str) -> String {
+        format!("{}{}{}", &xml[..self.start], self.replacement, &xml[self.end..])
+    }
+}
+
+/// What an [IllegalToken](XmlError::IllegalToken) error expected instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expected {
+    /// A fixed string, e.g. `>` or `?>`
+    Literal(String),
+    Space,
+    /// [\[27\] Misc](https://www.w3.org/TR/xml/#NT-Misc)
+    SpaceOrCommentOrProcessingInstruction,
+    /// [\[28b\] intSubset](https://www.w3.org/TR/xml/#NT-intSubset)
+    MarkupDeclarationOrSeparator,
+    /// `SYSTEM` or `PUBLIC`
+    ExternalId,
+    /// `yes` or `no`
+    YesOrNo,
+    /// A latin letter, which starts an encoding name
+    Letter,
+    Digit,
+    NameStartChar,
+    /// `"` or `'`
+    Quote,
+    /// A character other than a hyphen at the end of a comment
+    NoTrailingHyphen,
+    /// Anything but `--` inside of a comment
+    NoDoubleHyphen,
+    /// Anything but `]]>` in character data
+    NoCdataSectionEnd,
+    /// Anything but `<` in an attribute value
+    NoLessThan,
+    /// [\[17\] PITarget](https://www.w3.org/TR/xml/#NT-PITarget), a name other than `xml` in any case,
+    /// which is reserved for the XML declaration at the start of a document
+    ProcessingInstructionTarget,
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Expected::Literal(literal) => f.write_str(literal),
+            Expected::Space => f.write_str("Any space"),
+            Expected::SpaceOrCommentOrProcessingInstruction => f.write_str("Space or Start of Comment or Processing Instruction"),
+            Expected::MarkupDeclarationOrSeparator => f.write_str("Markup Declaration or Declaration Separator"),
+            Expected::ExternalId => f.write_str("'SYSTEM' or 'PUBLIC'"),
+            Expected::YesOrNo => f.write_str("yes or no"),
+            Expected::Letter => f.write_str("Any latin letter"),
+            Expected::Digit => f.write_str("Any digit"),
+            Expected::NameStartChar => f.write_str("Any Name start char"),
+            Expected::Quote => f.write_str("Either \" or '"),
+            Expected::NoTrailingHyphen => f.write_str("Not a hyphen as the last value character"),
+            Expected::NoDoubleHyphen => f.write_str("Not a double hyphen inside comments"),
+            Expected::NoCdataSectionEnd => f.write_str("Not the CDATA section-close delimiter"),
+            Expected::NoLessThan => f.write_str("Not the less-than character"),
+            Expected::ProcessingInstructionTarget => f.write_str("A processing instruction target other than xml"),
+        }
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum XmlError {
     //InternalError,
-    NonMatchingTags { start_tag: XmlErrorPos, end_tag: XmlErrorPos },
+    /// An end tag that does not match the start tag, e.g. because of a different case
+    /// or a start tag that should be an empty-element tag
+    NonMatchingTags {
+        start_tag: XmlErrorPos,
+        end_tag: XmlErrorPos,
+        /// The name of the start tag
+        expected_name: String,
+        /// The name of the end tag
+        found_name: String,
+        /// The innermost element further out that the end tag matches, whose end tag is likely missing inside of it
+        matching_start_tag: Option<XmlErrorPos>,
+        fix: Option<Box<Fix>>,
+    },
     UnexpectedXmlToken { pos: XmlErrorPos },
-    IllegalToken { pos: XmlErrorPos, expected: Option<String> },
-    UnknownReference { pos: XmlErrorPos },
-    UnexpectedEndOfFile,
+    IllegalToken { pos: XmlErrorPos, expected: Option<Expected>, fix: Option<Box<Fix>> },
+    /// A reference to an undeclared entity, or an ampersand that does not start a reference.
+    /// For a character reference, `reason` is why the character is not allowed.
+    UnknownReference { pos: XmlErrorPos, reason: Option<RefError>, fix: Option<Box<Fix>> },
+    /// An attribute name, or its namespace and local name, occurs twice in a start tag
+    DuplicateAttribute { pos: XmlErrorPos },
+    /// The `xml` or `xmlns` prefix or namespace is declared or used against the rules of Namespaces in XML
+    ReservedNamespace { pos: XmlErrorPos, reason: String },
+    /// An element or attribute name that is not a QName, or whose prefix is not declared,
+    /// see [5 Conformance of Documents](https://www.w3.org/TR/xml-names/#Conformance)
+    NotNamespaceWellFormed { pos: XmlErrorPos, reason: String },
+    /// The input ends inside of a construct, e.g. an element without an end tag.
+    /// The position is the end of the input, `start` that of the construct, e.g. the `<` of the start tag of the element.
+    /// `name` is that of the element, tag or attribute.
+    UnexpectedEndOfFile { pos: XmlErrorPos, construct: String, start: Option<XmlErrorPos>, name: Option<String> },
+    /// A construct that is well-formed, but rejected by the parse options
+    DisallowedConstruct { pos: XmlErrorPos, construct: String },
+    /// The input is not valid UTF-8
+    InvalidUtf8 { pos: XmlErrorPos },
+    /// Content before the document that is neither markup nor spaces, e.g. a misencoded byte order mark
+    /// or the rest of another response. It is `len` bytes long, up to the first `<`.
+    LeadingGarbage { pos: XmlErrorPos, len: usize },
+    /// The input exceeds a size limit
+    LimitExceeded { limit: String, max: usize },
+}
+
+impl XmlError {
+    /// The position of the error, for a non-matching end tag the position of the end tag.
+    pub fn pos(&self) -> Option<&XmlErrorPos> {
+        match self {
+            XmlError::NonMatchingTags { end_tag: pos, .. }
+            | XmlError::UnexpectedXmlToken { pos }
+            | XmlError::IllegalToken { pos, .. }
+            | XmlError::UnknownReference { pos, .. }
+            | XmlError::DuplicateAttribute { pos }
+            | XmlError::ReservedNamespace { pos, .. }
+            | XmlError::NotNamespaceWellFormed { pos, .. }
+            | XmlError::UnexpectedEndOfFile { pos, .. }
+            | XmlError::DisallowedConstruct { pos, .. }
+            | XmlError::InvalidUtf8 { pos }
+            | XmlError::LeadingGarbage { pos, .. } => Some(pos),
+            XmlError::LimitExceeded { .. } => None,
+        }
+    }
+
+    /// The names of the elements that are open at the position of the error, the root element first.
+    /// `xml` is the input that the error was reported for, it is tokenized again up to the error.
+    pub fn context(&self, xml: &str) -> Vec<String> {
+        let offset = match self.pos() {
+            Some(pos) => pos.offset_in(xml),
+            None => return vec![],
+        };
+        let mut tokens = vec![];
+        // the tokens before a tokenization error are kept
+        let _ = XmlTokenizer::default().tokenize_into(xml, &mut tokens);
+        let mut open_names = vec![];
+        for token in tokens.iter().take_while(|token| token.start() < offset) {
+            match token {
+                StartTag(name_range) => open_names.push(name_range.slice),
+                EndTag(_) => {
+                    open_names.pop();
+                }
+                _ => (),
+            }
+        }
+        open_names.into_iter().map(String::from).collect()
+    }
+
+    /// A suggested edit that resolves the error, available for some common mistakes.
+    pub fn fix(&self) -> Option<&Fix> {
+        match self {
+            XmlError::NonMatchingTags { fix, .. } | XmlError::IllegalToken { fix, .. } | XmlError::UnknownReference { fix, .. } => fix.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Name the construct in which the input ended, unless an inner construct is named already.
+    pub(crate) fn in_construct(mut self, name: &str) -> Self {
+        if let XmlError::UnexpectedEndOfFile { construct, .. } = &mut self {
+            if construct.is_empty() {
+                *construct = name.to_string();
+            }
+        }
+        self
+    }
+
+    /// Name the construct in which the input ended with its start and the name of its tag or attribute, if any,
+    /// unless an inner construct is named already.
+    pub(crate) fn in_construct_at(mut self, name: &str, start_pos: XmlErrorPos, opt_name: Option<&str>) -> Self {
+        if let XmlError::UnexpectedEndOfFile { construct, start, name: construct_name, .. } = &mut self {
+            if construct.is_empty() {
+                *construct = name.to_string();
+                *start = Some(start_pos);
+                *construct_name = opt_name.filter(|n| !n.is_empty()).map(String::from);
+            }
+        }
+        self
+    }
 }
 
 impl Display for XmlError {
@@ -21,3 +221,26 @@ impl Display for XmlError {
         write!(f, "Error: {:?}", self)
     }
 }
+
+/// A non-fatal issue in a well-formed document, see [Document::parse_with_warnings](crate::document::Document::parse_with_warnings).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XmlWarning {
+    /// Names beginning with `xml` are reserved for future standardization,
+    /// see [2.3 Common Syntactic Constructs](https://www.w3.org/TR/xml/#sec-common-syn)
+    ReservedName { pos: XmlErrorPos, name: String },
+    /// A control character or a Unicode noncharacter, see [2.2 Characters](https://www.w3.org/TR/xml/#charsets)
+    DiscouragedCharacter { pos: XmlErrorPos, character: char },
+    EmptyAttributeValue { pos: XmlErrorPos, name: String },
+    /// The document type declaration is parsed, but neither validated against nor used for entities
+    DoctypeIgnored { pos: XmlErrorPos },
+    /// The document is declared standalone, but has an external subset or parameter entity references,
+    /// whose declarations are not read. [VC: Standalone Document Declaration](https://www.w3.org/TR/xml/#vc-check-rmd) is not checked.
+    StandaloneUnverified { pos: XmlErrorPos },
+}
+
+impl Display for XmlWarning {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Warning: {:?}", self)
+    }
+}
@@ -0,0 +1,101 @@
+//! Escaping of character data and attribute values, for output that is written without an [XmlWriter](crate::writer::XmlWriter).
+
+use std::borrow::Cow;
+
+use crate::refs::decode_entities;
+
+/// How much of character data and attribute values is escaped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EscapeStyle {
+    /// Only what must be escaped: `>` only after `]]`, and in attribute values only the quote that delimits them
+    Minimal,
+    /// As [escape_text] and [escape_attribute]: `>` always, and in attribute values only the quote that delimits them
+    #[default]
+    Standard,
+    /// Like standard, and both quotes in character data and in attribute values
+    Aggressive,
+}
+
+/// Escape the characters for which `replacement` returns a reference, it receives the text before the character.
+fn escape_with(text: &str, replacement: impl Fn(&str, char) -> Option<&'static str>) -> Cow<'_, str> {
+    if !text.char_indices().any(|(i, c)| replacement(&text[..i], c).is_some()) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for (i, c) in text.char_indices() {
+        match replacement(&text[..i], c) {
+            Some(reference) => escaped.push_str(reference),
+            None => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Escape character data.
+///
+/// Besides the markup characters `&`, `<` and `>`, a carriage return is escaped,
+/// it would be turned into a line feed by [end-of-line handling](https://www.w3.org/TR/xml/#sec-line-ends).
+pub fn escape_text(text: &str) -> Cow<'_, str> {
+    escape_text_with(text, EscapeStyle::Standard)
+}
+
+/// Escape character data in a style, see [escape_text].
+pub fn escape_text_with(text: &str, style: EscapeStyle) -> Cow<'_, str> {
+    escape_with(text, |before, c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        // the end of a CDATA section is not allowed in character data
+        '>' if style != EscapeStyle::Minimal || before.ends_with("]]") => Some("&gt;"),
+        '\r' => Some("&#xD;"),
+        '"' if style == EscapeStyle::Aggressive => Some("&quot;"),
+        '\'' if style == EscapeStyle::Aggressive => Some("&apos;"),
+        _ => None,
+    })
+}
+
+/// Escape an attribute value that is delimited by `quote`, either `"` or `'`.
+///
+/// Tabs and line breaks are escaped as well,
+/// [attribute-value normalization](https://www.w3.org/TR/xml/#AVNormalize) would turn them into spaces.
+pub fn escape_attribute(value: &str, quote: char) -> Cow<'_, str> {
+    escape_attribute_with(value, quote, EscapeStyle::Standard)
+}
+
+/// Escape an attribute value that is delimited by `quote` in a style, see [escape_attribute].
+pub fn escape_attribute_with(value: &str, quote: char, style: EscapeStyle) -> Cow<'_, str> {
+    debug_assert!(quote == '"' || quote == '\'', "attribute values are delimited by \" or '");
+    escape_with(value, |_, c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' if style != EscapeStyle::Minimal => Some("&gt;"),
+        '"' if quote == '"' || style == EscapeStyle::Aggressive => Some("&quot;"),
+        '\'' if quote == '\'' || style == EscapeStyle::Aggressive => Some("&apos;"),
+        '\t' => Some("&#x9;"),
+        '\n' => Some("&#xA;"),
+        '\r' => Some("&#xD;"),
+        _ => None,
+    })
+}
+
+/// Resolve the predefined entities and character references, the inverse of [escape_text] and [escape_attribute].
+/// References that cannot be resolved are kept as they are, see [decode_entities].
+pub fn unescape(text: &str) -> Cow<'_, str> {
+    decode_entities(text)
+}
+
+/// Write a string literal of JSON.
+pub(crate) fn write_json_string(s: &str, json: &mut String) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
@@ -0,0 +1,941 @@
+//! Reading and writing [Fast Infoset](https://www.itu.int/rec/T-REC-X.891) documents, a binary encoding of XML.
+//!
+//! A document is decoded into markup, which can be read by any of the parsers, or into the tokens of the
+//! [tokenizer](crate::tokenize::XmlTokenizer). Markup and tokens are encoded the other way round.
+//! Names and short strings are added to the dynamic vocabulary tables, repetitions of them are encoded as indices.
+//!
+//! Not supported are initial vocabularies, restricted alphabets, encoding algorithms, additional data,
+//! notations, unparsed entities and unexpanded entity references.
+//! A document type declaration keeps its external identifier, the internal subset is not encoded.
+//!
+//! ```
+//! use jaxp_rust::fastinfoset::{decode, encode};
+//!
+//! let xml = "<a xmlns=\"urn:a\"><b n=\"1\">x &amp; y</b><b n=\"1\"/></a>";
+//! let fi = encode(xml).unwrap();
+//! assert_eq!(xml, decode(&fi).unwrap());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str;
+
+use crate::error::XmlError;
+use crate::escape::{escape_attribute, escape_text};
+use crate::namespace::{declared_prefix, is_ncname, split_qname, XML_NAMESPACE};
+use crate::refs::decode_entities;
+use crate::token::{XmlToken, XmlTokenOwned};
+use crate::tokenize::XmlTokenizer;
+
+/// The identification and version number that a document starts with.
+pub const HEADER: [u8; 4] = [0xE0, 0x00, 0x00, 0x01];
+
+/// The maximum number of entries of a vocabulary table, later strings are not added.
+const MAX_TABLE_SIZE: usize = 1 << 20;
+/// Attribute values and character chunks of at most this many octets are added to their tables.
+const MAX_INDEXED_LENGTH: usize = 32;
+
+const TERMINATOR: u8 = 0xF0;
+/// Two terminations in a row
+const DOUBLE_TERMINATOR: u8 = 0xFF;
+const PROCESSING_INSTRUCTION: u8 = 0xE1;
+const COMMENT: u8 = 0xE2;
+const DOCUMENT_TYPE_DECLARATION: u8 = 0xC4;
+const UNEXPANDED_ENTITY_REFERENCE: u8 = 0xC8;
+const ELEMENT_ATTRIBUTES_FLAG: u8 = 0x40;
+const ELEMENT_NAMESPACES: u8 = 0x38;
+const NAMESPACE_ATTRIBUTE: u8 = 0xCC;
+/// Flags of literal qualified names and namespace attributes
+const PREFIX_FLAG: u8 = 0x02;
+const NAMESPACE_NAME_FLAG: u8 = 0x01;
+const SYSTEM_IDENTIFIER_FLAG: u8 = 0x02;
+const PUBLIC_IDENTIFIER_FLAG: u8 = 0x01;
+/// Flags of the optional components of a document
+const UNSUPPORTED_COMPONENTS: u8 = 0x78;
+const CHARACTER_ENCODING_SCHEME_FLAG: u8 = 0x04;
+const STANDALONE_FLAG: u8 = 0x02;
+const VERSION_FLAG: u8 = 0x01;
+
+#[derive(Debug)]
+pub enum FastInfosetError {
+    /// The input does not start with the [HEADER], optionally preceded by an XML declaration
+    InvalidHeader,
+    UnexpectedEnd,
+    /// An octet that does not start an item or string allowed at its position
+    IllegalOctet { pos: usize, octet: u8 },
+    /// An index, counted from 1, that is not in its vocabulary table
+    InvalidIndex { pos: usize, index: usize },
+    /// A string that is not valid in its character encoding or not allowed in markup, e.g. a name with spaces
+    InvalidString { pos: usize },
+    Unsupported(String),
+    /// A prefix without a namespace declaration, when encoding
+    UndeclaredPrefix(String),
+    /// An end tag that does not match the open element or an element that is not closed, when encoding
+    UnbalancedTags(String),
+    Xml(XmlError),
+}
+
+impl Display for FastInfosetError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            FastInfosetError::InvalidHeader => write!(f, "the input is not a Fast Infoset document"),
+            FastInfosetError::UnexpectedEnd => write!(f, "unexpected end of the input"),
+            FastInfosetError::IllegalOctet { pos, octet } => write!(f, "illegal octet {:#04x} at {}", octet, pos),
+            FastInfosetError::InvalidIndex { pos, index } => write!(f, "index {} at {} is not in the vocabulary table", index, pos),
+            FastInfosetError::InvalidString { pos } => write!(f, "invalid string at {}", pos),
+            FastInfosetError::Unsupported(construct) => write!(f, "{} are not supported", construct),
+            FastInfosetError::UndeclaredPrefix(prefix) => write!(f, "the prefix {} is not declared", prefix),
+            FastInfosetError::UnbalancedTags(name) => write!(f, "the tags of element {} are not balanced", name),
+            FastInfosetError::Xml(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FastInfosetError {}
+
+impl From<XmlError> for FastInfosetError {
+    fn from(e: XmlError) -> Self {
+        FastInfosetError::Xml(e)
+    }
+}
+
+/// The vocabulary tables of strings, the tables of qualified names are kept apart.
+#[derive(Clone, Copy)]
+enum Table {
+    Prefixes,
+    NamespaceNames,
+    LocalNames,
+    /// Targets of processing instructions
+    OtherNcNames,
+    /// System and public identifiers
+    OtherUris,
+    AttributeValues,
+    CharacterChunks,
+    /// Comments, contents of processing instructions and the version
+    OtherStrings,
+}
+
+const TABLE_COUNT: usize = 8;
+
+/// Decode a document into markup.
+pub fn decode(fi: &[u8]) -> Result<String, FastInfosetError> {
+    Decoder::new(fi).decode()
+}
+
+/// Decode a document into the tokens of its markup.
+pub fn decode_tokens(fi: &[u8]) -> Result<Vec<XmlTokenOwned>, FastInfosetError> {
+    Ok(XmlTokenizer::default().tokenize_owned(&decode(fi)?)?)
+}
+
+/// Encode a document from markup.
+pub fn encode(xml: &str) -> Result<Vec<u8>, FastInfosetError> {
+    encode_tokens(&XmlTokenizer::default().tokenize(xml)?)
+}
+
+/// Encode a document from the tokens of its markup.
+/// References to entities other than the predefined ones are kept as text.
+pub fn encode_tokens(tokens: &[XmlToken]) -> Result<Vec<u8>, FastInfosetError> {
+    let mut encoder = Encoder::new();
+    match tokens.first() {
+        Some(XmlToken::XmlDeclaration { version_range, opt_standalone_range, .. }) => {
+            let standalone_flag = if opt_standalone_range.is_some() { STANDALONE_FLAG } else { 0 };
+            encoder.fi.push(standalone_flag | VERSION_FLAG);
+            if let Some(standalone) = opt_standalone_range {
+                encoder.fi.push((standalone.slice == "yes") as u8);
+            }
+            encoder.non_identifying_string(Table::OtherStrings, version_range.slice, false);
+        }
+        _ => encoder.fi.push(0),
+    }
+    let mut open = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            XmlToken::StartTag(name) => {
+                let mut attributes = vec![];
+                while let Some(XmlToken::Attribute { name_range, value_range }) = tokens.get(i + 1) {
+                    attributes.push((name_range.slice, decode_entities(value_range.slice).into_owned()));
+                    i += 1;
+                }
+                encoder.start_element(name.slice, &attributes)?;
+                open.push(name.slice);
+            }
+            XmlToken::EndTag(name) => {
+                if open.pop() != Some(name.slice) {
+                    return Err(FastInfosetError::UnbalancedTags(name.slice.to_string()));
+                }
+                encoder.end_element();
+            }
+            // white space around the root element
+            XmlToken::Text(_) if open.is_empty() => (),
+            XmlToken::Text(text) => encoder.character_chunk(&decode_entities(text.slice)),
+            XmlToken::CdataSection(text) => encoder.character_chunk(text.slice),
+            XmlToken::Comment(text) => encoder.comment(text.slice),
+            XmlToken::ProcessingInstruction { target_range, opt_value_range } => {
+                encoder.processing_instruction(target_range.slice, opt_value_range.map_or("", |v| v.slice))
+            }
+            XmlToken::DocTypeDeclaration { opt_system_entity_range, opt_public_entity_range, .. } => {
+                encoder.document_type_declaration(opt_system_entity_range.map(|s| s.slice), opt_public_entity_range.map(|p| p.slice))
+            }
+            XmlToken::XmlDeclaration { .. } | XmlToken::Attribute { .. } | XmlToken::ParameterEntityReference(_) => (),
+        }
+        i += 1;
+    }
+    if let Some(name) = open.pop() {
+        return Err(FastInfosetError::UnbalancedTags(name.to_string()));
+    }
+    encoder.terminate();
+    encoder.flush();
+    Ok(encoder.fi)
+}
+
+fn entry(entries: &[String], index: usize, pos: usize) -> Result<String, FastInfosetError> {
+    entries.get(index).cloned().ok_or(FastInfosetError::InvalidIndex { pos, index: index + 1 })
+}
+
+fn add(entries: &mut Vec<String>, string: &str) {
+    if entries.len() < MAX_TABLE_SIZE {
+        entries.push(string.to_string());
+    }
+}
+
+/// A system or public identifier in quotes that it does not contain.
+fn quoted(literal: &str, pos: usize) -> Result<String, FastInfosetError> {
+    match (literal.contains('"'), literal.contains('\'')) {
+        (false, _) => Ok(format!("\"{}\"", literal)),
+        (true, false) => Ok(format!("'{}'", literal)),
+        (true, true) => Err(FastInfosetError::InvalidString { pos }),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum CharacterEncoding {
+    Utf8,
+    Utf16,
+}
+
+/// The character encoding of an encoded character string, by its discriminant of two bits.
+fn character_encoding(discriminant: u8) -> Result<CharacterEncoding, FastInfosetError> {
+    match discriminant {
+        0 => Ok(CharacterEncoding::Utf8),
+        1 => Ok(CharacterEncoding::Utf16),
+        2 => Err(FastInfosetError::Unsupported("restricted alphabets".to_string())),
+        _ => Err(FastInfosetError::Unsupported("encoding algorithms".to_string())),
+    }
+}
+
+struct Decoder<'f> {
+    fi: &'f [u8],
+    pos: usize,
+    tables: [Vec<String>; TABLE_COUNT],
+    /// Qualified names as written in markup, i.e. prefix and local name
+    element_names: Vec<String>,
+    attribute_names: Vec<String>,
+    markup: String,
+}
+
+impl<'f> Decoder<'f> {
+    fn new(fi: &'f [u8]) -> Self {
+        let mut tables: [Vec<String>; TABLE_COUNT] = Default::default();
+        tables[Table::Prefixes as usize].push("xml".to_string());
+        tables[Table::NamespaceNames as usize].push(XML_NAMESPACE.to_string());
+        Decoder { fi, pos: 0, tables, element_names: vec![], attribute_names: vec![], markup: String::new() }
+    }
+
+    fn read(&mut self) -> Result<u8, FastInfosetError> {
+        let octet = *self.fi.get(self.pos).ok_or(FastInfosetError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(octet)
+    }
+
+    fn read_octets(&mut self, len: usize) -> Result<&'f [u8], FastInfosetError> {
+        let end = self.pos.checked_add(len).ok_or(FastInfosetError::UnexpectedEnd)?;
+        let octets = self.fi.get(self.pos..end).ok_or(FastInfosetError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(octets)
+    }
+
+    /// An unsigned big-endian integer of `len` octets.
+    fn read_integer(&mut self, len: usize) -> Result<usize, FastInfosetError> {
+        Ok(self.read_octets(len)?.iter().fold(0, |n, &octet| n << 8 | octet as usize))
+    }
+
+    fn read_string(&mut self, encoding: CharacterEncoding, len: usize) -> Result<String, FastInfosetError> {
+        let pos = self.pos;
+        let octets = self.read_octets(len)?;
+        let opt_string = match encoding {
+            CharacterEncoding::Utf8 => str::from_utf8(octets).ok().map(str::to_string),
+            CharacterEncoding::Utf16 if len.is_multiple_of(2) => {
+                let units = octets.chunks(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+                char::decode_utf16(units).collect::<Result<String, _>>().ok()
+            }
+            CharacterEncoding::Utf16 => None,
+        };
+        opt_string.ok_or(FastInfosetError::InvalidString { pos })
+    }
+
+    /// An index that starts on the second bit of an octet, counted from 0 instead of 1, see C.25.
+    fn index_on_second_bit(&mut self, pos: usize, octet: u8) -> Result<usize, FastInfosetError> {
+        match octet & 0x7F {
+            bits if bits & 0x40 == 0 => Ok(bits as usize),
+            bits if bits & 0x60 == 0x40 => Ok(((bits as usize & 0x1F) << 8 | self.read_integer(1)?) + 64),
+            bits if bits & 0x70 == 0x60 => Ok(((bits as usize & 0x0F) << 16 | self.read_integer(2)?) + 8256),
+            _ => Err(FastInfosetError::IllegalOctet { pos, octet }),
+        }
+    }
+
+    /// The length of a non-empty octet string that starts on the second bit of an octet, see C.22.
+    fn length_on_second_bit(&mut self, pos: usize, octet: u8) -> Result<usize, FastInfosetError> {
+        match octet {
+            0x00..=0x3F => Ok(octet as usize + 1),
+            0x40 => Ok(self.read_integer(1)? + 65),
+            0x60 => Ok(self.read_integer(4)? + 321),
+            _ => Err(FastInfosetError::IllegalOctet { pos, octet }),
+        }
+    }
+
+    /// An identifying string or index that starts on the first bit of an octet, see C.13.
+    /// Literal strings are added to the table.
+    fn identifying_string(&mut self, table: Table) -> Result<String, FastInfosetError> {
+        let pos = self.pos;
+        let octet = self.read()?;
+        if octet & 0x80 != 0 {
+            let index = self.index_on_second_bit(pos, octet)?;
+            return entry(&self.tables[table as usize], index, pos);
+        }
+        let len = self.length_on_second_bit(pos, octet)?;
+        let string = self.read_string(CharacterEncoding::Utf8, len)?;
+        if matches!(table, Table::Prefixes | Table::LocalNames | Table::OtherNcNames) && !is_ncname(&string) {
+            return Err(FastInfosetError::InvalidString { pos });
+        }
+        add(&mut self.tables[table as usize], &string);
+        Ok(string)
+    }
+
+    /// A non-identifying string or index that starts on the first bit of an octet, see C.14.
+    fn non_identifying_string(&mut self, table: Table) -> Result<String, FastInfosetError> {
+        let pos = self.pos;
+        let octet = self.read()?;
+        if octet == DOUBLE_TERMINATOR {
+            return Ok(String::new());
+        }
+        if octet & 0x80 != 0 {
+            let index = self.index_on_second_bit(pos, octet)?;
+            return entry(&self.tables[table as usize], index, pos);
+        }
+        let encoding = character_encoding(octet >> 4 & 0x03)?;
+        // the length starts on the fifth bit, see C.23
+        let len = match octet & 0x0F {
+            bits if bits & 0x08 == 0 => bits as usize + 1,
+            0x08 => self.read_integer(1)? + 9,
+            0x0C => self.read_integer(4)? + 265,
+            _ => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+        };
+        let string = self.read_string(encoding, len)?;
+        if octet & 0x40 != 0 {
+            add(&mut self.tables[table as usize], &string);
+        }
+        Ok(string)
+    }
+
+    /// A character chunk, whose string or index starts on the third bit of its first octet, see C.15.
+    fn character_chunk(&mut self, pos: usize, octet: u8) -> Result<String, FastInfosetError> {
+        if octet & 0x20 != 0 {
+            // the index starts on the fourth bit, see C.28
+            let index = match octet & 0x1F {
+                bits if bits & 0x10 == 0 => bits as usize,
+                bits if bits & 0x1C == 0x10 => ((bits as usize & 0x03) << 8 | self.read_integer(1)?) + 16,
+                bits if bits & 0x1C == 0x14 => ((bits as usize & 0x03) << 16 | self.read_integer(2)?) + 1040,
+                0x18 => (self.read_integer(3)? & 0xFFFFF) + 263184,
+                _ => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+            };
+            return entry(&self.tables[Table::CharacterChunks as usize], index, pos);
+        }
+        let encoding = character_encoding(octet >> 2 & 0x03)?;
+        // the length starts on the seventh bit, see C.24
+        let len = match octet & 0x03 {
+            bits @ (0 | 1) => bits as usize + 1,
+            2 => self.read_integer(1)? + 3,
+            _ => self.read_integer(4)? + 259,
+        };
+        let string = self.read_string(encoding, len)?;
+        if octet & 0x10 != 0 {
+            add(&mut self.tables[Table::CharacterChunks as usize], &string);
+        }
+        Ok(string)
+    }
+
+    /// A qualified name whose prefix, namespace name and local name are flagged in an octet.
+    fn literal_qualified_name(&mut self, pos: usize, octet: u8) -> Result<String, FastInfosetError> {
+        let opt_prefix = if octet & PREFIX_FLAG != 0 { Some(self.identifying_string(Table::Prefixes)?) } else { None };
+        if octet & NAMESPACE_NAME_FLAG != 0 {
+            // the prefix is declared by a namespace attribute, which is written as such
+            self.identifying_string(Table::NamespaceNames)?;
+        } else if opt_prefix.is_some() {
+            return Err(FastInfosetError::IllegalOctet { pos, octet });
+        }
+        let local_name = self.identifying_string(Table::LocalNames)?;
+        Ok(match opt_prefix {
+            Some(prefix) => format!("{}:{}", prefix, local_name),
+            None => local_name,
+        })
+    }
+
+    /// The name of an element that starts on the third bit of an octet, see C.18.
+    fn element_name(&mut self, pos: usize, octet: u8) -> Result<String, FastInfosetError> {
+        // the index starts on the third bit, see C.27
+        let index = match octet & 0x3F {
+            bits if bits & 0x20 == 0 => bits as usize,
+            bits if bits & 0x38 == 0x20 => ((bits as usize & 0x07) << 8 | self.read_integer(1)?) + 32,
+            bits if bits & 0x3C == 0x28 => ((bits as usize & 0x03) << 16 | self.read_integer(2)?) + 2080,
+            0x30 => (self.read_integer(3)? & 0xFFFFF) + 264224,
+            bits if bits & 0x3C == 0x3C => {
+                let name = self.literal_qualified_name(pos, octet)?;
+                add(&mut self.element_names, &name);
+                return Ok(name);
+            }
+            _ => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+        };
+        entry(&self.element_names, index, pos)
+    }
+
+    fn decode(mut self) -> Result<String, FastInfosetError> {
+        // an XML declaration may precede the header, e.g. `<?xml encoding='finf'?>`
+        if self.fi.starts_with(b"<?xml") {
+            self.pos = self.fi.windows(2).position(|w| w == b"?>").ok_or(FastInfosetError::InvalidHeader)? + 2;
+        }
+        if !self.fi[self.pos..].starts_with(&HEADER) {
+            return Err(FastInfosetError::InvalidHeader);
+        }
+        self.pos += HEADER.len();
+        self.optional_components()?;
+        self.children()?;
+        Ok(self.markup)
+    }
+
+    /// The optional components before the children of the document, of which the version and standalone
+    /// make up an XML declaration.
+    fn optional_components(&mut self) -> Result<(), FastInfosetError> {
+        let pos = self.pos;
+        let components = self.read()?;
+        if components & 0x80 != 0 {
+            return Err(FastInfosetError::IllegalOctet { pos, octet: components });
+        }
+        if components & UNSUPPORTED_COMPONENTS != 0 {
+            return Err(FastInfosetError::Unsupported("additional data, initial vocabularies, notations and unparsed entities".to_string()));
+        }
+        if components & CHARACTER_ENCODING_SCHEME_FLAG != 0 {
+            // the encoding of the document as XML, the markup is a string regardless
+            let pos = self.pos;
+            let octet = self.read()?;
+            let len = self.length_on_second_bit(pos, octet)?;
+            self.read_octets(len)?;
+        }
+        let mut opt_standalone = None;
+        if components & STANDALONE_FLAG != 0 {
+            let pos = self.pos;
+            opt_standalone = Some(match self.read()? {
+                0 => "no",
+                1 => "yes",
+                octet => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+            });
+        }
+        let mut version = "1.0".to_string();
+        if components & VERSION_FLAG != 0 {
+            let pos = self.pos;
+            version = self.non_identifying_string(Table::OtherStrings)?;
+            let is_valid = version.strip_prefix("1.").is_some_and(|minor| !minor.is_empty() && minor.bytes().all(|b| b.is_ascii_digit()));
+            if !is_valid {
+                return Err(FastInfosetError::InvalidString { pos });
+            }
+        }
+        if components & (STANDALONE_FLAG | VERSION_FLAG) != 0 {
+            self.markup.push_str(&format!("<?xml version=\"{}\"", version));
+            if let Some(standalone) = opt_standalone {
+                self.markup.push_str(&format!(" standalone=\"{}\"", standalone));
+            }
+            self.markup.push_str("?>");
+        }
+        Ok(())
+    }
+
+    /// The children of the document and the elements in it, up to the termination of the document.
+    fn children(&mut self) -> Result<(), FastInfosetError> {
+        let mut open: Vec<String> = vec![];
+        let mut has_root = false;
+        // the external identifier of a document type declaration and where it is inserted, once the name of the root is known
+        let mut opt_doctype: Option<(usize, String)> = None;
+        loop {
+            let pos = self.pos;
+            let octet = self.read()?;
+            match octet {
+                TERMINATOR | DOUBLE_TERMINATOR => {
+                    let count = if octet == DOUBLE_TERMINATOR { 2 } else { 1 };
+                    for i in 0..count {
+                        match open.pop() {
+                            Some(name) => self.markup.push_str(&format!("</{}>", name)),
+                            None if i + 1 == count => return self.end(),
+                            None => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+                        }
+                    }
+                }
+                0x00..=0x7F if open.is_empty() && has_root => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+                0x00..=0x7F => {
+                    has_root = true;
+                    let (name, is_empty) = self.element(pos, octet, opt_doctype.take())?;
+                    if !is_empty {
+                        open.push(name);
+                    }
+                }
+                0x80..=0xBF if !open.is_empty() => {
+                    let chunk = self.character_chunk(pos, octet)?;
+                    self.markup.push_str(&escape_text(&chunk));
+                }
+                PROCESSING_INSTRUCTION => {
+                    let processing_instruction = self.processing_instruction()?;
+                    self.markup.push_str(&processing_instruction);
+                }
+                COMMENT => {
+                    let pos = self.pos;
+                    let comment = self.non_identifying_string(Table::OtherStrings)?;
+                    if comment.contains("--") || comment.ends_with('-') {
+                        return Err(FastInfosetError::InvalidString { pos });
+                    }
+                    self.markup.push_str(&format!("<!--{}-->", comment));
+                }
+                octet if octet & 0xFC == DOCUMENT_TYPE_DECLARATION && !has_root && opt_doctype.is_none() => {
+                    opt_doctype = Some((self.markup.len(), self.document_type_declaration(pos, octet)?));
+                }
+                octet if octet & 0xFC == UNEXPANDED_ENTITY_REFERENCE => {
+                    return Err(FastInfosetError::Unsupported("unexpanded entity references".to_string()));
+                }
+                _ => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+            }
+        }
+    }
+
+    /// Check that the termination of the document is the end of the input.
+    fn end(&self) -> Result<(), FastInfosetError> {
+        match self.fi.get(self.pos) {
+            Some(&octet) => Err(FastInfosetError::IllegalOctet { pos: self.pos, octet }),
+            None => Ok(()),
+        }
+    }
+
+    /// Write the start tag of an element, get its name and whether it is empty, i.e. terminated right after its attributes.
+    fn element(&mut self, pos: usize, octet: u8, opt_doctype: Option<(usize, String)>) -> Result<(String, bool), FastInfosetError> {
+        let has_attributes = octet & ELEMENT_ATTRIBUTES_FLAG != 0;
+        let mut declarations = String::new();
+        let (name_pos, name_octet) = if octet & 0x3F == ELEMENT_NAMESPACES {
+            loop {
+                let pos = self.pos;
+                let octet = self.read()?;
+                if octet == TERMINATOR {
+                    break;
+                }
+                if octet & 0xFC != NAMESPACE_ATTRIBUTE {
+                    return Err(FastInfosetError::IllegalOctet { pos, octet });
+                }
+                let opt_prefix = if octet & PREFIX_FLAG != 0 { Some(self.identifying_string(Table::Prefixes)?) } else { None };
+                let namespace_name = if octet & NAMESPACE_NAME_FLAG != 0 { self.identifying_string(Table::NamespaceNames)? } else { String::new() };
+                let name = opt_prefix.map_or("xmlns".to_string(), |prefix| format!("xmlns:{}", prefix));
+                declarations.push_str(&format!(" {}=\"{}\"", name, escape_attribute(&namespace_name, '"')));
+            }
+            // the name starts on the third bit of the octet after the namespace attributes
+            let pos = self.pos;
+            match self.read()? {
+                octet @ 0x00..=0x3F => (pos, octet),
+                octet => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+            }
+        } else {
+            (pos, octet)
+        };
+        let name = self.element_name(name_pos, name_octet)?;
+        if let Some((doctype_pos, external_id)) = opt_doctype {
+            self.markup.insert_str(doctype_pos, &format!("<!DOCTYPE {}{}>", name, external_id));
+        }
+        self.markup.push_str(&format!("<{}{}", name, declarations));
+        let is_empty = has_attributes && self.attributes()?;
+        self.markup.push_str(if is_empty { "/>" } else { ">" });
+        Ok((name, is_empty))
+    }
+
+    /// Write attributes up to their termination, get whether the element is terminated as well.
+    fn attributes(&mut self) -> Result<bool, FastInfosetError> {
+        loop {
+            let pos = self.pos;
+            let octet = self.read()?;
+            let name = match octet {
+                TERMINATOR => return Ok(false),
+                DOUBLE_TERMINATOR => return Ok(true),
+                // the name starts on the second bit, see C.17
+                octet if octet & 0xFC == 0x78 => {
+                    let name = self.literal_qualified_name(pos, octet)?;
+                    add(&mut self.attribute_names, &name);
+                    name
+                }
+                0x00..=0x7F => {
+                    let index = self.index_on_second_bit(pos, octet)?;
+                    entry(&self.attribute_names, index, pos)?
+                }
+                _ => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+            };
+            let value = self.non_identifying_string(Table::AttributeValues)?;
+            self.markup.push_str(&format!(" {}=\"{}\"", name, escape_attribute(&value, '"')));
+        }
+    }
+
+    /// The markup of a processing instruction, after its first octet.
+    fn processing_instruction(&mut self) -> Result<String, FastInfosetError> {
+        let pos = self.pos;
+        let target = self.identifying_string(Table::OtherNcNames)?;
+        if target.eq_ignore_ascii_case("xml") {
+            return Err(FastInfosetError::InvalidString { pos });
+        }
+        let pos = self.pos;
+        let content = self.non_identifying_string(Table::OtherStrings)?;
+        if content.contains("?>") {
+            return Err(FastInfosetError::InvalidString { pos });
+        }
+        Ok(if content.is_empty() { format!("<?{}?>", target) } else { format!("<?{} {}?>", target, content) })
+    }
+
+    /// The external identifier of a document type declaration, its processing instructions are dropped like those of
+    /// an internal subset.
+    fn document_type_declaration(&mut self, pos: usize, octet: u8) -> Result<String, FastInfosetError> {
+        let mut opt_system_id = None;
+        if octet & SYSTEM_IDENTIFIER_FLAG != 0 {
+            let pos = self.pos;
+            opt_system_id = Some(quoted(&self.identifying_string(Table::OtherUris)?, pos)?);
+        }
+        let mut opt_public_id = None;
+        if octet & PUBLIC_IDENTIFIER_FLAG != 0 {
+            let pos = self.pos;
+            opt_public_id = Some(quoted(&self.identifying_string(Table::OtherUris)?, pos)?);
+        }
+        loop {
+            let pos = self.pos;
+            match self.read()? {
+                TERMINATOR => break,
+                PROCESSING_INSTRUCTION => {
+                    self.processing_instruction()?;
+                }
+                octet => return Err(FastInfosetError::IllegalOctet { pos, octet }),
+            }
+        }
+        match (opt_system_id, opt_public_id) {
+            (None, None) => Ok(String::new()),
+            (Some(system_id), None) => Ok(format!(" SYSTEM {}", system_id)),
+            (Some(system_id), Some(public_id)) => Ok(format!(" PUBLIC {} {}", public_id, system_id)),
+            (None, Some(_)) => Err(FastInfosetError::IllegalOctet { pos, octet }),
+        }
+    }
+}
+
+/// A qualified name by prefix, namespace name and local name, which are empty if absent.
+type QualifiedName = (String, String, String);
+
+/// The flags of a literal qualified name.
+fn qualified_name_flags((prefix, namespace_name, _): &QualifiedName) -> u8 {
+    (if prefix.is_empty() { 0 } else { PREFIX_FLAG }) | (if namespace_name.is_empty() { 0 } else { NAMESPACE_NAME_FLAG })
+}
+
+struct Encoder {
+    fi: Vec<u8>,
+    tables: [HashMap<String, usize>; TABLE_COUNT],
+    element_names: HashMap<QualifiedName, usize>,
+    attribute_names: HashMap<QualifiedName, usize>,
+    /// A termination that is not written yet, two in a row share an octet
+    has_pending_termination: bool,
+    /// The namespace declarations of the open elements by prefix, the empty one for the default namespace
+    scopes: Vec<Vec<(String, String)>>,
+}
+
+impl Encoder {
+    fn new() -> Self {
+        let mut tables: [HashMap<String, usize>; TABLE_COUNT] = Default::default();
+        tables[Table::Prefixes as usize].insert("xml".to_string(), 0);
+        tables[Table::NamespaceNames as usize].insert(XML_NAMESPACE.to_string(), 0);
+        Encoder {
+            fi: HEADER.to_vec(),
+            tables,
+            element_names: HashMap::new(),
+            attribute_names: HashMap::new(),
+            has_pending_termination: false,
+            scopes: vec![],
+        }
+    }
+
+    fn terminate(&mut self) {
+        if self.has_pending_termination {
+            self.fi.push(DOUBLE_TERMINATOR);
+        }
+        self.has_pending_termination = !self.has_pending_termination;
+    }
+
+    /// Write a pending termination, before an item that starts on the first bit of an octet.
+    fn flush(&mut self) {
+        if self.has_pending_termination {
+            self.fi.push(TERMINATOR);
+            self.has_pending_termination = false;
+        }
+    }
+
+    /// Write the lowest `len` octets of an integer in big-endian order.
+    fn integer(&mut self, n: usize, len: usize) {
+        self.fi.extend((0..len).rev().map(|i| (n >> (8 * i)) as u8));
+    }
+
+    /// Write an index counted from 0 on the second bit of an octet, the first bit is set in `first_bit`.
+    fn index_on_second_bit(&mut self, first_bit: u8, index: usize) {
+        if index < 64 {
+            self.fi.push(first_bit | index as u8);
+        } else if index < 8256 {
+            self.fi.push(first_bit | 0x40 | ((index - 64) >> 8) as u8);
+            self.integer(index - 64, 1);
+        } else {
+            self.fi.push(first_bit | 0x60 | ((index - 8256) >> 16) as u8);
+            self.integer(index - 8256, 2);
+        }
+    }
+
+    /// Write an identifying string, which must not be empty, by index if it is in the table.
+    fn identifying_string(&mut self, table: Table, string: &str) {
+        if let Some(&index) = self.tables[table as usize].get(string) {
+            self.index_on_second_bit(0x80, index);
+            return;
+        }
+        let len = string.len();
+        if len <= 64 {
+            self.fi.push((len - 1) as u8);
+        } else if len <= 320 {
+            self.fi.push(0x40);
+            self.integer(len - 65, 1);
+        } else {
+            self.fi.push(0x60);
+            self.integer(len - 321, 4);
+        }
+        self.fi.extend_from_slice(string.as_bytes());
+        let entries = &mut self.tables[table as usize];
+        if entries.len() < MAX_TABLE_SIZE {
+            entries.insert(string.to_string(), entries.len());
+        }
+    }
+
+    /// Write a non-identifying string, by index if it is in the table. Short ones are added if `is_indexed`.
+    fn non_identifying_string(&mut self, table: Table, string: &str, is_indexed: bool) {
+        if string.is_empty() {
+            self.fi.push(DOUBLE_TERMINATOR);
+            return;
+        }
+        if let Some(&index) = self.tables[table as usize].get(string) {
+            self.index_on_second_bit(0x80, index);
+            return;
+        }
+        let len = string.len();
+        let is_added = is_indexed && len <= MAX_INDEXED_LENGTH && self.tables[table as usize].len() < MAX_TABLE_SIZE;
+        let add_flag = if is_added { 0x40 } else { 0 };
+        if len <= 8 {
+            self.fi.push(add_flag | (len - 1) as u8);
+        } else if len <= 264 {
+            self.fi.push(add_flag | 0x08);
+            self.integer(len - 9, 1);
+        } else {
+            self.fi.push(add_flag | 0x0C);
+            self.integer(len - 265, 4);
+        }
+        self.fi.extend_from_slice(string.as_bytes());
+        if is_added {
+            let entries = &mut self.tables[table as usize];
+            entries.insert(string.to_string(), entries.len());
+        }
+    }
+
+    /// The namespace that a prefix is bound to, if any.
+    fn namespace_name(&self, prefix: &str) -> Option<String> {
+        if prefix == "xml" {
+            return Some(XML_NAMESPACE.to_string());
+        }
+        let mut declarations = self.scopes.iter().rev().flat_map(|scope| scope.iter());
+        declarations.find(|(p, _)| p == prefix).map(|(_, uri)| uri.clone()).filter(|uri| !uri.is_empty())
+    }
+
+    /// Resolve the prefix of a name. Unprefixed element names are in the default namespace, attribute names are not.
+    fn qualified_name(&self, name: &str, is_element: bool) -> Result<QualifiedName, FastInfosetError> {
+        let (opt_prefix, local_name) = split_qname(name);
+        let namespace_name = match opt_prefix {
+            Some(prefix) => self.namespace_name(prefix).ok_or_else(|| FastInfosetError::UndeclaredPrefix(prefix.to_string()))?,
+            None if is_element => self.namespace_name("").unwrap_or_default(),
+            None => String::new(),
+        };
+        Ok((opt_prefix.unwrap_or_default().to_string(), namespace_name, local_name.to_string()))
+    }
+
+    fn literal_qualified_name_strings(&mut self, (prefix, namespace_name, local_name): &QualifiedName) {
+        if !prefix.is_empty() {
+            self.identifying_string(Table::Prefixes, prefix);
+        }
+        if !namespace_name.is_empty() {
+            self.identifying_string(Table::NamespaceNames, namespace_name);
+        }
+        self.identifying_string(Table::LocalNames, local_name);
+    }
+
+    /// Write the name of an element on the third bit of an octet, whose first two bits are set in `first_bits`.
+    fn element_name(&mut self, first_bits: u8, name: QualifiedName) {
+        let index = match self.element_names.get(&name) {
+            Some(&index) => index,
+            None => {
+                let flags = qualified_name_flags(&name);
+                self.fi.push(first_bits | 0x3C | flags);
+                self.literal_qualified_name_strings(&name);
+                if self.element_names.len() < MAX_TABLE_SIZE {
+                    self.element_names.insert(name, self.element_names.len());
+                }
+                return;
+            }
+        };
+        if index < 32 {
+            self.fi.push(first_bits | index as u8);
+        } else if index < 2080 {
+            self.fi.push(first_bits | 0x20 | ((index - 32) >> 8) as u8);
+            self.integer(index - 32, 1);
+        } else if index < 264224 {
+            self.fi.push(first_bits | 0x28 | ((index - 2080) >> 16) as u8);
+            self.integer(index - 2080, 2);
+        } else {
+            self.fi.push(first_bits | 0x30);
+            self.integer(index - 264224, 3);
+        }
+    }
+
+    fn attribute_name(&mut self, name: QualifiedName) {
+        match self.attribute_names.get(&name) {
+            Some(&index) => self.index_on_second_bit(0, index),
+            None => {
+                let flags = qualified_name_flags(&name);
+                self.fi.push(0x78 | flags);
+                self.literal_qualified_name_strings(&name);
+                if self.attribute_names.len() < MAX_TABLE_SIZE {
+                    self.attribute_names.insert(name, self.attribute_names.len());
+                }
+            }
+        }
+    }
+
+    /// Write the start of an element, with the decoded values of its attributes.
+    fn start_element(&mut self, name: &str, attributes: &[(&str, String)]) -> Result<(), FastInfosetError> {
+        self.flush();
+        let (declarations, attributes): (Vec<_>, Vec<_>) = attributes.iter().partition(|(name, _)| declared_prefix(name).is_some());
+        let scope: Vec<(String, String)> =
+            declarations.iter().map(|(name, uri)| (declared_prefix(name).unwrap_or_default().to_string(), uri.clone())).collect();
+        self.scopes.push(scope.clone());
+        let element_name = self.qualified_name(name, true)?;
+        let attribute_names = attributes.iter().map(|(name, _)| self.qualified_name(name, false)).collect::<Result<Vec<_>, _>>()?;
+
+        let mut first_bits = if attributes.is_empty() { 0 } else { ELEMENT_ATTRIBUTES_FLAG };
+        if !scope.is_empty() {
+            self.fi.push(first_bits | ELEMENT_NAMESPACES);
+            for (prefix, uri) in &scope {
+                let prefix_flag = if prefix.is_empty() { 0 } else { PREFIX_FLAG };
+                let namespace_name_flag = if uri.is_empty() { 0 } else { NAMESPACE_NAME_FLAG };
+                self.fi.push(NAMESPACE_ATTRIBUTE | prefix_flag | namespace_name_flag);
+                if !prefix.is_empty() {
+                    self.identifying_string(Table::Prefixes, prefix);
+                }
+                if !uri.is_empty() {
+                    self.identifying_string(Table::NamespaceNames, uri);
+                }
+            }
+            self.fi.push(TERMINATOR);
+            first_bits = 0;
+        }
+        self.element_name(first_bits, element_name);
+        for (name, (_, value)) in attribute_names.into_iter().zip(attributes.iter().copied()) {
+            self.attribute_name(name);
+            self.non_identifying_string(Table::AttributeValues, value, true);
+        }
+        if !attributes.is_empty() {
+            self.terminate();
+        }
+        Ok(())
+    }
+
+    fn end_element(&mut self) {
+        self.scopes.pop();
+        self.terminate();
+    }
+
+    fn character_chunk(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.flush();
+        // the index starts on the fourth bit, see C.28
+        if let Some(&index) = self.tables[Table::CharacterChunks as usize].get(text) {
+            if index < 16 {
+                self.fi.push(0xA0 | index as u8);
+            } else if index < 1040 {
+                self.fi.push(0xB0 | ((index - 16) >> 8) as u8);
+                self.integer(index - 16, 1);
+            } else if index < 263184 {
+                self.fi.push(0xB4 | ((index - 1040) >> 16) as u8);
+                self.integer(index - 1040, 2);
+            } else {
+                self.fi.push(0xB8);
+                self.integer(index - 263184, 3);
+            }
+            return;
+        }
+        let len = text.len();
+        let entries = &self.tables[Table::CharacterChunks as usize];
+        let is_added = len <= MAX_INDEXED_LENGTH && entries.len() < MAX_TABLE_SIZE;
+        let add_flag = if is_added { 0x10 } else { 0 };
+        // the length starts on the seventh bit, see C.24
+        if len <= 2 {
+            self.fi.push(0x80 | add_flag | (len - 1) as u8);
+        } else if len <= 258 {
+            self.fi.push(0x80 | add_flag | 0x02);
+            self.integer(len - 3, 1);
+        } else {
+            self.fi.push(0x80 | add_flag | 0x03);
+            self.integer(len - 259, 4);
+        }
+        self.fi.extend_from_slice(text.as_bytes());
+        if is_added {
+            let entries = &mut self.tables[Table::CharacterChunks as usize];
+            entries.insert(text.to_string(), entries.len());
+        }
+    }
+
+    fn comment(&mut self, text: &str) {
+        self.flush();
+        self.fi.push(COMMENT);
+        self.non_identifying_string(Table::OtherStrings, text, false);
+    }
+
+    fn processing_instruction(&mut self, target: &str, content: &str) {
+        self.flush();
+        self.fi.push(PROCESSING_INSTRUCTION);
+        self.identifying_string(Table::OtherNcNames, target);
+        self.non_identifying_string(Table::OtherStrings, content, false);
+    }
+
+    /// Write a document type declaration, empty identifiers cannot be encoded and are left out.
+    fn document_type_declaration(&mut self, opt_system_id: Option<&str>, opt_public_id: Option<&str>) {
+        let opt_system_id = opt_system_id.filter(|s| !s.is_empty());
+        // a public identifier is only allowed with a system identifier
+        let opt_public_id = opt_public_id.filter(|p| !p.is_empty() && opt_system_id.is_some());
+        self.flush();
+        let system_flag = if opt_system_id.is_some() { SYSTEM_IDENTIFIER_FLAG } else { 0 };
+        let public_flag = if opt_public_id.is_some() { PUBLIC_IDENTIFIER_FLAG } else { 0 };
+        self.fi.push(DOCUMENT_TYPE_DECLARATION | system_flag | public_flag);
+        if let Some(system_id) = opt_system_id {
+            self.identifying_string(Table::OtherUris, system_id);
+        }
+        if let Some(public_id) = opt_public_id {
+            self.identifying_string(Table::OtherUris, public_id);
+        }
+        self.terminate();
+    }
+}
@@ -0,0 +1,531 @@
+//! Reading [RSS 2.0](https://www.rssboard.org/rss-specification) and [Atom](https://www.rfc-editor.org/rfc/rfc4287) feeds
+//! into typed structs, using the [pull parser](crate::pull).
+//!
+//! Both formats are mapped onto the same [Feed] and [Entry] structs, e.g. the RSS `description` of an item is its summary.
+//! Dates that cannot be parsed and unknown elements are ignored.
+//! The text of Atom text constructs of type `xhtml` is the character data of their markup.
+//!
+//! ```
+//! use jaxp_rust::feeds::{parse, FeedKind};
+//!
+//! let xml = "<rss version=\"2.0\"><channel><title>News</title><item><title>First</title>\
+//!            <link>https://example.org/1</link><pubDate>Tue, 10 Jun 2003 04:00:00 GMT</pubDate></item></channel></rss>";
+//! let feed = parse(xml).unwrap();
+//! assert_eq!(FeedKind::Rss, feed.kind);
+//! assert_eq!(Some("First"), feed.entries[0].title.as_deref());
+//! assert_eq!(Some("https://example.org/1"), feed.entries[0].link());
+//! assert_eq!(Some(1055217600), feed.entries[0].published.map(|d| d.unix_timestamp()));
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use crate::document::Attributes;
+use crate::error::XmlError;
+use crate::namespace::{declared_prefix, split_qname, XML_NAMESPACE};
+use crate::pull::{Event, PullParser};
+use crate::refs::decode_entities;
+
+pub const ATOM_NAMESPACE: &str = "http://www.w3.org/2005/Atom";
+/// The namespace of the RSS content module, for `content:encoded`
+pub const CONTENT_NAMESPACE: &str = "http://purl.org/rss/1.0/modules/content/";
+/// The namespace of the Dublin Core elements, for `dc:creator` and `dc:date`
+pub const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+
+#[derive(Debug)]
+pub enum FeedError {
+    Xml(XmlError),
+    /// The root element is neither an RSS `rss` nor an Atom `feed`, but this one
+    UnknownFormat(String),
+    /// A required element is missing
+    InvalidFeed(String),
+}
+
+impl Display for FeedError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            FeedError::Xml(e) => write!(f, "{}", e),
+            FeedError::UnknownFormat(name) => write!(f, "the root element {} is not a feed", name),
+            FeedError::InvalidFeed(reason) => write!(f, "invalid feed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for FeedError {}
+
+impl From<XmlError> for FeedError {
+    fn from(e: XmlError) -> Self {
+        FeedError::Xml(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeedKind {
+    Rss,
+    Atom,
+}
+
+/// A point in time with the offset from UTC that it was given in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub offset_minutes: i16,
+}
+
+impl DateTime {
+    fn new(year: i32, month: u8, day: u8, hour: u8, minute: u8, second: u8, offset_minutes: i16) -> Option<Self> {
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if day == 0 || day > days_in_month || hour > 23 || minute > 59 || second > 60 || offset_minutes.abs() >= 24 * 60 {
+            return None;
+        }
+        Some(DateTime { year, month, day, hour, minute, second, offset_minutes })
+    }
+
+    /// Parse a timestamp like `2003-12-13T18:30:02.25+01:00`, as in Atom and Dublin Core dates.
+    /// Fractions of seconds are dropped.
+    pub fn parse_rfc3339(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let number = |range: std::ops::Range<usize>| s.get(range).filter(|n| n.bytes().all(|b| b.is_ascii_digit())).and_then(|n| n.parse::<u16>().ok());
+        let b = s.as_bytes();
+        if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || !b"Tt ".contains(&b[10]) || b[13] != b':' || b[16] != b':' {
+            return None;
+        }
+        let mut rest = &s[19..];
+        if let Some(fraction) = rest.strip_prefix('.') {
+            rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+        }
+        let offset_minutes = match rest.as_bytes() {
+            [b'Z' | b'z'] => 0,
+            [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] if [h1, h2, m1, m2].iter().all(|b| b.is_ascii_digit()) => {
+                let minutes = ((h1 - b'0') * 10 + h2 - b'0') as i16 * 60 + ((m1 - b'0') * 10 + m2 - b'0') as i16;
+                if *sign == b'-' {
+                    -minutes
+                } else {
+                    minutes
+                }
+            }
+            _ => return None,
+        };
+        // the two-digit fields fit into a byte
+        let field = |range| number(range).map(|n| n as u8);
+        DateTime::new(number(0..4)? as i32, field(5..7)?, field(8..10)?, field(11..13)?, field(14..16)?, field(17..19)?, offset_minutes)
+    }
+
+    /// Parse a timestamp like `Sat, 07 Sep 2002 09:42:31 GMT`, as in RSS.
+    /// The day of the week and the seconds are optional, two-digit years are in 1950 to 2049.
+    pub fn parse_rfc822(s: &str) -> Option<Self> {
+        let mut s = s.trim();
+        if let Some(i) = s.find(',') {
+            s = &s[i + 1..];
+        }
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let [day, month, year, time, zone] = parts[..] else {
+            return None;
+        };
+        const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+        let month = MONTHS.iter().position(|m| month.get(..3).is_some_and(|p| p.eq_ignore_ascii_case(m)))? as u8 + 1;
+        let year: i32 = match year.parse().ok()? {
+            y if year.len() == 2 && y < 50 => y + 2000,
+            y if year.len() == 2 => y + 1900,
+            y => y,
+        };
+        let mut time = time.split(':').map(|n| n.parse::<u8>().ok());
+        let (hour, minute, second) = (time.next()??, time.next()??, time.next().unwrap_or(Some(0))?);
+        if time.next().is_some() {
+            return None;
+        }
+        let offset_minutes = match zone.to_ascii_uppercase().as_str() {
+            "GMT" | "UT" | "UTC" | "Z" => 0,
+            "EDT" => -4 * 60,
+            "EST" | "CDT" => -5 * 60,
+            "CST" | "MDT" => -6 * 60,
+            "MST" | "PDT" => -7 * 60,
+            "PST" => -8 * 60,
+            z if z.len() == 5 && (z.starts_with('+') || z.starts_with('-')) => {
+                let hhmm: i16 = z[1..].parse().ok()?;
+                let minutes = hhmm / 100 * 60 + hhmm % 100;
+                if z.starts_with('-') {
+                    -minutes
+                } else {
+                    minutes
+                }
+            }
+            // military zones are not reliable, see RFC 2822
+            z if z.len() == 1 && z.bytes().all(|b| b.is_ascii_alphabetic()) => 0,
+            _ => return None,
+        };
+        DateTime::new(year, month, day.parse().ok()?, hour, minute, second, offset_minutes)
+    }
+
+    /// The number of seconds since 1970-01-01T00:00:00Z, ignoring leap seconds.
+    pub fn unix_timestamp(&self) -> i64 {
+        // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = self.month as i64;
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+        days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64 - self.offset_minutes as i64 * 60
+    }
+}
+
+/// A link of a feed or entry. RSS enclosures are links with the relation `enclosure`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Link {
+    pub href: String,
+    /// `None` for the alternate representation
+    pub rel: Option<String>,
+    pub media_type: Option<String>,
+    pub title: Option<String>,
+    pub length: Option<u64>,
+}
+
+impl Link {
+    fn is_alternate(&self) -> bool {
+        matches!(self.rel.as_deref(), None | Some("alternate"))
+    }
+}
+
+/// The first alternate link of a list.
+fn alternate_link(links: &[Link]) -> Option<&str> {
+    links.iter().find(|l| l.is_alternate()).map(|l| l.href.as_str())
+}
+
+/// An RSS item or Atom entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Entry {
+    /// The RSS `guid` or Atom `id`
+    pub id: Option<String>,
+    pub title: Option<String>,
+    /// The RSS `description` or Atom `summary`
+    pub summary: Option<String>,
+    /// The RSS `content:encoded` or Atom `content`
+    pub content: Option<String>,
+    pub links: Vec<Link>,
+    /// The names of Atom authors, the RSS `author` and `dc:creator`
+    pub authors: Vec<String>,
+    pub categories: Vec<String>,
+    /// The RSS `pubDate` and `dc:date` or Atom `published`
+    pub published: Option<DateTime>,
+    pub updated: Option<DateTime>,
+}
+
+impl Entry {
+    /// The address of the alternate representation, the RSS `link`.
+    pub fn link(&self) -> Option<&str> {
+        alternate_link(&self.links)
+    }
+}
+
+/// An RSS channel or Atom feed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Feed {
+    pub kind: FeedKind,
+    pub id: Option<String>,
+    pub title: Option<String>,
+    /// The RSS `description` or Atom `subtitle`
+    pub description: Option<String>,
+    pub links: Vec<Link>,
+    pub authors: Vec<String>,
+    pub categories: Vec<String>,
+    /// The RSS `lastBuildDate` or `pubDate` or Atom `updated`
+    pub updated: Option<DateTime>,
+    pub entries: Vec<Entry>,
+}
+
+impl Feed {
+    fn new(kind: FeedKind) -> Self {
+        Feed { kind, id: None, title: None, description: None, links: vec![], authors: vec![], categories: vec![], updated: None, entries: vec![] }
+    }
+
+    /// The address of the alternate representation, e.g. the website of the feed.
+    pub fn link(&self) -> Option<&str> {
+        alternate_link(&self.links)
+    }
+}
+
+/// A start tag with its name resolved against the namespace declarations in scope.
+struct Start<'a> {
+    namespace: String,
+    local_name: &'a str,
+    attributes: Attributes<'a>,
+}
+
+impl Start<'_> {
+    fn is(&self, namespace: &str, local_name: &str) -> bool {
+        self.namespace == namespace && self.local_name == local_name
+    }
+
+    /// The value of an unprefixed attribute, with references resolved.
+    fn attribute(&self, name: &str) -> Option<String> {
+        self.attributes.get(name).map(|a| decode_entities(a.value()).into_owned())
+    }
+}
+
+/// Reads the child elements of the current element, keeping track of the namespace declarations.
+struct Reader<'a> {
+    parser: PullParser<'a>,
+    /// the prefixes and namespaces declared by the open elements, by their depth
+    scopes: Vec<Vec<(String, String)>>,
+}
+
+impl<'a> Reader<'a> {
+    fn namespace_of(&self, opt_prefix: Option<&str>) -> String {
+        let prefix = opt_prefix.unwrap_or("");
+        if prefix == "xml" {
+            return XML_NAMESPACE.to_string();
+        }
+        self.scopes.iter().rev().flatten().find(|(p, _)| p == prefix).map(|(_, uri)| uri.clone()).unwrap_or_default()
+    }
+
+    /// The next child element of the current element, `None` after the end tag of the current element.
+    /// Every child has to be read with [Reader::text], [Reader::skip] or further calls up to its end.
+    fn next_child(&mut self) -> Result<Option<Start<'a>>, FeedError> {
+        while let Some(event) = self.parser.next_event()? {
+            match event {
+                Event::StartElement { name, attributes, depth } => {
+                    self.scopes.truncate(depth);
+                    let declarations = attributes
+                        .iter()
+                        .filter_map(|a| declared_prefix(a.name()).map(|p| (p.to_string(), decode_entities(a.value()).into_owned())))
+                        .collect();
+                    self.scopes.push(declarations);
+                    let (opt_prefix, local_name) = split_qname(name);
+                    return Ok(Some(Start { namespace: self.namespace_of(opt_prefix), local_name, attributes }));
+                }
+                Event::EndElement { .. } => return Ok(None),
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Skip the rest of the current element.
+    fn skip(&mut self) -> Result<(), FeedError> {
+        Ok(self.parser.skip_to_end_of_current()?)
+    }
+
+    /// The trimmed character data of the rest of the current element, with references resolved.
+    fn text(&mut self) -> Result<String, FeedError> {
+        let mut text = String::new();
+        let mut nesting = 0;
+        while let Some(event) = self.parser.next_event()? {
+            match event {
+                Event::Text(t) => text.push_str(&decode_entities(t)),
+                Event::CdataSection(t) => text.push_str(t),
+                Event::StartElement { .. } => nesting += 1,
+                Event::EndElement { .. } if nesting == 0 => break,
+                Event::EndElement { .. } => nesting -= 1,
+                _ => {}
+            }
+        }
+        Ok(text.trim().to_string())
+    }
+
+    fn opt_text(&mut self) -> Result<Option<String>, FeedError> {
+        Ok(Some(self.text()?).filter(|t| !t.is_empty()))
+    }
+
+    /// The name of an Atom person construct.
+    fn atom_person(&mut self) -> Result<Option<String>, FeedError> {
+        let mut opt_name = None;
+        while let Some(child) = self.next_child()? {
+            if child.is(ATOM_NAMESPACE, "name") {
+                opt_name = self.opt_text()?;
+            } else {
+                self.skip()?;
+            }
+        }
+        Ok(opt_name)
+    }
+
+    /// Read the Atom elements that feeds and entries have in common.
+    fn atom_common(&mut self, start: &Start, entry: &mut Entry) -> Result<bool, FeedError> {
+        match start.local_name {
+            "id" => entry.id = self.opt_text()?,
+            "title" => entry.title = self.opt_text()?,
+            "updated" => entry.updated = DateTime::parse_rfc3339(&self.text()?),
+            "link" => {
+                self.skip()?;
+                entry.links.push(atom_link(start));
+            }
+            "author" => entry.authors.extend(self.atom_person()?),
+            "category" => {
+                self.skip()?;
+                entry.categories.extend(start.attribute("term"));
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn atom_entry(&mut self) -> Result<Entry, FeedError> {
+        let mut entry = Entry::default();
+        while let Some(child) = self.next_child()? {
+            if child.namespace != ATOM_NAMESPACE {
+                self.skip()?;
+                continue;
+            }
+            if self.atom_common(&child, &mut entry)? {
+                continue;
+            }
+            match child.local_name {
+                "summary" => entry.summary = self.opt_text()?,
+                "content" => entry.content = self.opt_text()?,
+                "published" => entry.published = DateTime::parse_rfc3339(&self.text()?),
+                _ => self.skip()?,
+            }
+        }
+        Ok(entry)
+    }
+
+    fn atom_feed(&mut self) -> Result<Feed, FeedError> {
+        let mut feed = Feed::new(FeedKind::Atom);
+        // the metadata of the feed is read like that of an entry
+        let mut metadata = Entry::default();
+        while let Some(child) = self.next_child()? {
+            if child.namespace != ATOM_NAMESPACE {
+                self.skip()?;
+                continue;
+            }
+            if self.atom_common(&child, &mut metadata)? {
+                continue;
+            }
+            match child.local_name {
+                "subtitle" => feed.description = self.opt_text()?,
+                "entry" => feed.entries.push(self.atom_entry()?),
+                _ => self.skip()?,
+            }
+        }
+        feed.id = metadata.id;
+        feed.title = metadata.title;
+        feed.links = metadata.links;
+        feed.authors = metadata.authors;
+        feed.categories = metadata.categories;
+        feed.updated = metadata.updated;
+        Ok(feed)
+    }
+
+    /// Read the RSS elements that channels and items have in common, and the extensions of both.
+    fn rss_common(&mut self, start: &Start, entry: &mut Entry) -> Result<bool, FeedError> {
+        match (start.namespace.as_str(), start.local_name) {
+            ("", "title") => entry.title = self.opt_text()?,
+            ("", "link") => entry.links.extend(self.opt_text()?.map(|href| Link { href, ..Link::default() })),
+            ("", "description") => entry.summary = self.opt_text()?,
+            ("", "category") => entry.categories.extend(self.opt_text()?),
+            ("", "pubDate") => entry.published = DateTime::parse_rfc822(&self.text()?),
+            (ATOM_NAMESPACE, "link") => {
+                self.skip()?;
+                entry.links.push(atom_link(start));
+            }
+            (DC_NAMESPACE, "creator") => entry.authors.extend(self.opt_text()?),
+            (DC_NAMESPACE, "date") => entry.published = DateTime::parse_rfc3339(&self.text()?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn rss_item(&mut self) -> Result<Entry, FeedError> {
+        let mut entry = Entry::default();
+        while let Some(child) = self.next_child()? {
+            if self.rss_common(&child, &mut entry)? {
+                continue;
+            }
+            match (child.namespace.as_str(), child.local_name) {
+                ("", "guid") => entry.id = self.opt_text()?,
+                ("", "author") => entry.authors.extend(self.opt_text()?),
+                ("", "enclosure") => {
+                    self.skip()?;
+                    entry.links.push(Link {
+                        href: child.attribute("url").unwrap_or_default(),
+                        rel: Some("enclosure".to_string()),
+                        media_type: child.attribute("type"),
+                        title: None,
+                        length: child.attribute("length").and_then(|l| l.trim().parse().ok()),
+                    });
+                }
+                (CONTENT_NAMESPACE, "encoded") => entry.content = self.opt_text()?,
+                _ => self.skip()?,
+            }
+        }
+        Ok(entry)
+    }
+
+    fn rss_channel(&mut self) -> Result<Feed, FeedError> {
+        let mut feed = Feed::new(FeedKind::Rss);
+        // the metadata of the channel is read like that of an item
+        let mut metadata = Entry::default();
+        while let Some(child) = self.next_child()? {
+            if self.rss_common(&child, &mut metadata)? {
+                continue;
+            }
+            match (child.namespace.as_str(), child.local_name) {
+                ("", "lastBuildDate") => metadata.updated = DateTime::parse_rfc822(&self.text()?),
+                ("", "managingEditor") => metadata.authors.extend(self.opt_text()?),
+                ("", "item") => feed.entries.push(self.rss_item()?),
+                _ => self.skip()?,
+            }
+        }
+        feed.title = metadata.title;
+        feed.description = metadata.summary;
+        feed.links = metadata.links;
+        feed.authors = metadata.authors;
+        feed.categories = metadata.categories;
+        feed.updated = metadata.updated.or(metadata.published);
+        Ok(feed)
+    }
+}
+
+/// The link described by the attributes of an Atom `link`.
+fn atom_link(start: &Start) -> Link {
+    Link {
+        href: start.attribute("href").unwrap_or_default(),
+        rel: start.attribute("rel"),
+        media_type: start.attribute("type"),
+        title: start.attribute("title"),
+        length: start.attribute("length").and_then(|l| l.trim().parse().ok()),
+    }
+}
+
+/// Read an RSS 2.0 or Atom feed, RSS 0.91 and 0.92 are read like RSS 2.0.
+pub fn parse(xml: &str) -> Result<Feed, FeedError> {
+    let mut reader = Reader { parser: PullParser::new(xml)?, scopes: vec![] };
+    let root = match reader.next_child()? {
+        Some(root) => root,
+        None => return Err(FeedError::InvalidFeed("missing root element".to_string())),
+    };
+    let feed = if root.is("", "rss") {
+        let mut opt_feed = None;
+        while let Some(child) = reader.next_child()? {
+            if child.is("", "channel") && opt_feed.is_none() {
+                opt_feed = Some(reader.rss_channel()?);
+            } else {
+                reader.skip()?;
+            }
+        }
+        opt_feed.ok_or_else(|| FeedError::InvalidFeed("missing channel".to_string()))?
+    } else if root.is(ATOM_NAMESPACE, "feed") {
+        reader.atom_feed()?
+    } else {
+        let name = match root.namespace.as_str() {
+            "" => root.local_name.to_string(),
+            namespace => format!("{{{}}}{}", namespace, root.local_name),
+        };
+        return Err(FeedError::UnknownFormat(name));
+    };
+    // read up to the end of the document, to report any errors
+    while reader.parser.next_event()?.is_some() {}
+    Ok(feed)
+}
@@ -0,0 +1,230 @@
+//! C ABI for the [Document](crate::document::Document) API.
+//!
+//! Documents are passed around as opaque `JaxpDocument` pointers, nodes as indices into a document.
+//! Strings are exchanged as pointer and length pairs of UTF-8 bytes and are NOT null-terminated,
+//! except for error messages.
+//! Returned strings borrow from the document and are valid until it is freed.
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::{ptr, slice, str};
+
+use crate::document::{Node, NodeId, NodeType, OwnedDocument};
+
+/// Returned instead of a node index if there is no such node.
+pub const JAXP_NO_NODE: usize = usize::MAX;
+
+/// Opaque handle to a parsed document.
+pub struct JaxpDocument(OwnedDocument);
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JaxpNodeType {
+    Root = 0,
+    Element = 1,
+    Text = 2,
+    CdataSection = 3,
+    Comment = 4,
+    ProcessingInstruction = 5,
+    /// The node index is not valid for the document
+    Invalid = -1,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // error messages never contain null bytes, but stay on the safe side
+    let message = CString::new(message.replace('\0', "")).unwrap();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Parse `len` bytes of UTF-8 encoded XML.
+/// Returns null on failure, the reason can be retrieved with [jaxp_last_error].
+/// The document must be released with [jaxp_document_free].
+///
+/// # Safety
+/// `xml` must point to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_parse(xml: *const c_char, len: usize) -> *mut JaxpDocument {
+    if xml.is_null() {
+        set_last_error("Input is a null pointer".to_string());
+        return ptr::null_mut();
+    }
+    let bytes = slice::from_raw_parts(xml as *const u8, len);
+    let text = match str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => {
+            set_last_error(format!("Input is not valid UTF-8: {}", e));
+            return ptr::null_mut();
+        }
+    };
+    match OwnedDocument::parse(text.to_string()) {
+        Ok(doc) => Box::into_raw(Box::new(JaxpDocument(doc))),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a document returned by [jaxp_parse]. Passing null is a no-op.
+///
+/// # Safety
+/// `doc` must be null or a pointer returned by [jaxp_parse] that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_document_free(doc: *mut JaxpDocument) {
+    if !doc.is_null() {
+        drop(Box::from_raw(doc));
+    }
+}
+
+/// The message of the last error that occurred on the calling thread as a null-terminated string,
+/// or null if there was none. The pointer is valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn jaxp_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| match e.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+unsafe fn get_node<'a>(doc: *const JaxpDocument, node: usize) -> Option<Node<'a, 'a>> {
+    doc.as_ref()?.0.document().get_node(NodeId(node))
+}
+
+fn node_index(node: Option<Node>) -> usize {
+    node.map_or(JAXP_NO_NODE, |n| n.id().get())
+}
+
+unsafe fn write_str(s: Option<&str>, out_len: *mut usize) -> *const c_char {
+    let (ptr, len) = match s {
+        Some(s) => (s.as_ptr() as *const c_char, s.len()),
+        None => (ptr::null(), 0),
+    };
+    if !out_len.is_null() {
+        *out_len = len;
+    }
+    ptr
+}
+
+/// The index of the root element.
+///
+/// # Safety
+/// `doc` must be a valid document handle.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_root_element(doc: *const JaxpDocument) -> usize {
+    match doc.as_ref() {
+        Some(doc) => doc.0.document().root_element().id().get(),
+        None => JAXP_NO_NODE,
+    }
+}
+
+/// # Safety
+/// `doc` must be a valid document handle.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_type(doc: *const JaxpDocument, node: usize) -> JaxpNodeType {
+    match get_node(doc, node).map(|n| n.node_type()) {
+        Some(NodeType::Root) => JaxpNodeType::Root,
+        Some(NodeType::Element) => JaxpNodeType::Element,
+        Some(NodeType::Text) => JaxpNodeType::Text,
+        Some(NodeType::CdataSection) => JaxpNodeType::CdataSection,
+        Some(NodeType::Comment) => JaxpNodeType::Comment,
+        Some(NodeType::ProcessingInstruction) => JaxpNodeType::ProcessingInstruction,
+        None => JaxpNodeType::Invalid,
+    }
+}
+
+/// # Safety
+/// `doc` must be a valid document handle.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_parent(doc: *const JaxpDocument, node: usize) -> usize {
+    node_index(get_node(doc, node).and_then(|n| n.parent()))
+}
+
+/// # Safety
+/// `doc` must be a valid document handle.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_child_count(doc: *const JaxpDocument, node: usize) -> usize {
+    get_node(doc, node).map_or(0, |n| n.children().count())
+}
+
+/// The child at position `index`.
+///
+/// # Safety
+/// `doc` must be a valid document handle.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_child(doc: *const JaxpDocument, node: usize, index: usize) -> usize {
+    node_index(get_node(doc, node).and_then(|n| n.children().nth(index)))
+}
+
+/// # Safety
+/// `doc` must be a valid document handle.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_next_sibling(doc: *const JaxpDocument, node: usize) -> usize {
+    node_index(get_node(doc, node).and_then(|n| n.next_sibling()))
+}
+
+/// The tag name of an element or the target of a processing instruction.
+/// The length is written to `out_len`.
+///
+/// # Safety
+/// `doc` must be a valid document handle, `out_len` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_name(doc: *const JaxpDocument, node: usize, out_len: *mut usize) -> *const c_char {
+    write_str(get_node(doc, node).map(|n| n.tag_name()), out_len)
+}
+
+/// The text of a node as defined by [Node::text](crate::document::Node::text), null if there is none.
+/// The length is written to `out_len`.
+///
+/// # Safety
+/// `doc` must be a valid document handle, `out_len` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_text(doc: *const JaxpDocument, node: usize, out_len: *mut usize) -> *const c_char {
+    write_str(get_node(doc, node).and_then(|n| n.text()), out_len)
+}
+
+/// # Safety
+/// `doc` must be a valid document handle.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_attribute_count(doc: *const JaxpDocument, node: usize) -> usize {
+    get_node(doc, node).map_or(0, |n| n.attributes().count())
+}
+
+/// The name of the attribute at position `index`, null if there is none.
+///
+/// # Safety
+/// `doc` must be a valid document handle, `out_len` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_attribute_name(doc: *const JaxpDocument, node: usize, index: usize, out_len: *mut usize) -> *const c_char {
+    write_str(get_node(doc, node).and_then(|n| n.attributes().nth(index)).map(|a| a.name()), out_len)
+}
+
+/// The value of the attribute at position `index`, null if there is none.
+///
+/// # Safety
+/// `doc` must be a valid document handle, `out_len` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_attribute_value(doc: *const JaxpDocument, node: usize, index: usize, out_len: *mut usize) -> *const c_char {
+    write_str(get_node(doc, node).and_then(|n| n.attributes().nth(index)).map(|a| a.value()), out_len)
+}
+
+/// The value of the attribute named by `name_len` bytes at `name`, null if there is none.
+///
+/// # Safety
+/// `doc` must be a valid document handle, `name` must point to at least `name_len` readable bytes
+/// and `out_len` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn jaxp_node_attribute(doc: *const JaxpDocument, node: usize, name: *const c_char, name_len: usize, out_len: *mut usize) -> *const c_char {
+    if name.is_null() {
+        return write_str(None, out_len);
+    }
+    let name = match str::from_utf8(slice::from_raw_parts(name as *const u8, name_len)) {
+        Ok(name) => name,
+        Err(_) => return write_str(None, out_len),
+    };
+    write_str(get_node(doc, node).and_then(|n| n.attribute(name)), out_len)
+}
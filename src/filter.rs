@@ -0,0 +1,338 @@
+//! Streaming copies of documents with modifications, as a chain of filters between a [PullParser] and a writer.
+//!
+//! Like a SAX filter, a [Filter] receives the events of a document one by one and forwards any number of them,
+//! modified or not, to the next stage of the [Pipeline], which ends in an [EventSink] like an [XmlWriter].
+//! Closures are filters, [RenameElement], [DropNamespace], [StripNamespaces] and [AddAttribute] are building blocks for common edits.
+//!
+//! ```
+//! use jaxp_rust::filter::{AddAttribute, EventSink, Pipeline, RenameElement};
+//! use jaxp_rust::pull::Event;
+//!
+//! let mut pipeline = Pipeline::new();
+//! pipeline
+//!     .add(RenameElement::new("item", "entry"))
+//!     .add(AddAttribute::new("entry", "checked", "yes"))
+//!     .add(|event: Event, next: &mut dyn EventSink| match event {
+//!         Event::Comment(_) => Ok(()),
+//!         event => next.event(event),
+//!     });
+//! let xml = pipeline.transform("<list><item/><!-- two --><item>b</item></list>").unwrap();
+//! assert_eq!("<list><entry checked=\"yes\"/><entry checked=\"yes\">b</entry></list>", xml);
+//! ```
+
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::Write;
+
+use crate::document::{Attribute, Attributes};
+use crate::error::XmlError;
+use crate::namespace::{declared_prefix, split_qname};
+use crate::pull::{Event, PullParser};
+use crate::writer::XmlWriter;
+use crate::xmlchar::names_eq;
+
+#[derive(Debug)]
+pub enum FilterError {
+    /// The input is not well-formed
+    Xml(XmlError),
+    /// A stage could not handle an event, e.g. the writer rejected a name
+    Io(io::Error),
+}
+
+impl Display for FilterError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            FilterError::Xml(e) => Display::fmt(e, f),
+            FilterError::Io(e) => write!(f, "cannot write the result: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+impl From<XmlError> for FilterError {
+    fn from(e: XmlError) -> Self {
+        FilterError::Xml(e)
+    }
+}
+
+impl From<io::Error> for FilterError {
+    fn from(e: io::Error) -> Self {
+        FilterError::Io(e)
+    }
+}
+
+/// The end of a [Pipeline], or the rest of it as seen by a filter.
+pub trait EventSink {
+    fn event(&mut self, event: Event) -> io::Result<()>;
+}
+
+/// Writes the events as markup. Text and attribute values are written as they are, i.e. escaped.
+impl<W: Write> EventSink for XmlWriter<W> {
+    fn event(&mut self, event: Event) -> io::Result<()> {
+        match event {
+            Event::StartElement { name, attributes, .. } => {
+                self.start_element(name)?;
+                for attribute in &attributes {
+                    self.attribute_raw(attribute.name(), attribute.value())?;
+                }
+                Ok(())
+            }
+            Event::EndElement { .. } => self.end_element(),
+            Event::Text(text) => self.text_raw(text),
+            Event::CdataSection(text) => self.cdata_section(text),
+            Event::Comment(text) => self.comment(text),
+            Event::ProcessingInstruction { target, opt_value } => self.processing_instruction(target, opt_value),
+        }
+    }
+}
+
+/// A stage of a [Pipeline]. Events that are not forwarded to `next` are dropped.
+pub trait Filter {
+    fn event(&mut self, event: Event, next: &mut dyn EventSink) -> io::Result<()>;
+}
+
+impl<F: FnMut(Event, &mut dyn EventSink) -> io::Result<()>> Filter for F {
+    fn event(&mut self, event: Event, next: &mut dyn EventSink) -> io::Result<()> {
+        self(event, next)
+    }
+}
+
+/// Filters that events pass in the order they were added.
+#[derive(Default)]
+pub struct Pipeline {
+    filters: Vec<Box<dyn Filter>>,
+}
+
+/// The filters from one stage on.
+struct Stages<'p> {
+    filters: &'p mut [Box<dyn Filter>],
+    sink: &'p mut dyn EventSink,
+}
+
+impl EventSink for Stages<'_> {
+    fn event(&mut self, event: Event) -> io::Result<()> {
+        match self.filters.split_first_mut() {
+            Some((filter, rest)) => filter.event(event, &mut Stages { filters: rest, sink: &mut *self.sink }),
+            None => self.sink.event(event),
+        }
+    }
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, filter: impl Filter + 'static) -> &mut Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Pass every event of the parser through the filters into the sink.
+    pub fn run(&mut self, mut parser: PullParser, sink: &mut dyn EventSink) -> Result<(), FilterError> {
+        let mut stages = Stages { filters: &mut self.filters, sink };
+        while let Some(event) = parser.next_event()? {
+            stages.event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Filter a document into a string, with a checked [XmlWriter].
+    pub fn transform(&mut self, xml: &str) -> Result<String, FilterError> {
+        let mut writer = XmlWriter::new(Vec::new());
+        self.run(PullParser::new(xml)?, &mut writer)?;
+        Ok(String::from_utf8(writer.finish()?).expect("only strings are written"))
+    }
+}
+
+/// Renames elements with a qualified name, at their start and end.
+pub struct RenameElement {
+    from: String,
+    to: String,
+}
+
+impl RenameElement {
+    pub fn new(from: &str, to: &str) -> Self {
+        RenameElement { from: from.to_string(), to: to.to_string() }
+    }
+}
+
+impl Filter for RenameElement {
+    fn event(&mut self, event: Event, next: &mut dyn EventSink) -> io::Result<()> {
+        match event {
+            Event::StartElement { name, attributes, depth } if names_eq(name, &self.from) => {
+                next.event(Event::StartElement { name: &self.to, attributes, depth })
+            }
+            Event::EndElement { name, depth } if names_eq(name, &self.from) => next.event(Event::EndElement { name: &self.to, depth }),
+            event => next.event(event),
+        }
+    }
+}
+
+/// Sets an attribute of the elements with a qualified name, the value is escaped.
+/// An existing attribute of the name gets the value.
+pub struct AddAttribute {
+    element: String,
+    name: String,
+    value: String,
+}
+
+impl AddAttribute {
+    pub fn new(element: &str, name: &str, value: &str) -> Self {
+        AddAttribute { element: element.to_string(), name: name.to_string(), value: value.to_string() }
+    }
+}
+
+impl Filter for AddAttribute {
+    fn event(&mut self, event: Event, next: &mut dyn EventSink) -> io::Result<()> {
+        match event {
+            Event::StartElement { name, mut attributes, depth } if names_eq(name, &self.element) => {
+                attributes.insert(&self.name, &self.value);
+                next.event(Event::StartElement { name, attributes, depth })
+            }
+            event => next.event(event),
+        }
+    }
+}
+
+/// The namespace declarations of the open elements, by prefix, the empty prefix for the default namespace.
+#[derive(Default)]
+pub(crate) struct NamespaceScopes {
+    scopes: Vec<Vec<(String, String)>>,
+}
+
+impl NamespaceScopes {
+    /// Open the scope of an element with its attributes.
+    pub(crate) fn push(&mut self, attributes: &Attributes) {
+        let declarations = attributes.iter()
+            .filter_map(|a| declared_prefix(a.name()).map(|prefix| (prefix.to_string(), a.value().to_string())))
+            .collect();
+        self.scopes.push(declarations);
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// The namespace bound to a prefix, `None` if it is not bound or the default namespace is undeclared.
+    pub(crate) fn uri(&self, prefix: &str) -> Option<&str> {
+        let uri = self.scopes.iter().flatten().rev().find(|(p, _)| p == prefix).map(|(_, uri)| uri.as_str());
+        uri.filter(|uri| !uri.is_empty())
+    }
+
+    /// The namespace bound to the prefix of an element name, with the default namespace for names without a prefix.
+    pub(crate) fn element_uri(&self, name: &str) -> Option<&str> {
+        self.uri(split_qname(name).0.unwrap_or(""))
+    }
+}
+
+/// Moves the elements and attributes of a namespace out of it, by removing the prefixes of their names
+/// and the declarations of the namespace. Elements without a prefix that would end up in another default namespace
+/// get a declaration of the empty default namespace. Removing prefixes can make attribute names collide, which the writer rejects.
+pub struct DropNamespace {
+    uri: String,
+    scopes: NamespaceScopes,
+    /// The default namespace of the output in the open elements
+    output_defaults: Vec<String>,
+}
+
+impl DropNamespace {
+    pub fn new(uri: &str) -> Self {
+        DropNamespace { uri: uri.to_string(), scopes: NamespaceScopes::default(), output_defaults: vec![] }
+    }
+
+    fn is_dropped(&self, element_name: &str) -> bool {
+        self.scopes.element_uri(element_name) == Some(self.uri.as_str())
+    }
+}
+
+impl Filter for DropNamespace {
+    fn event(&mut self, event: Event, next: &mut dyn EventSink) -> io::Result<()> {
+        match event {
+            Event::StartElement { name, attributes, depth } => {
+                self.scopes.push(&attributes);
+                let is_dropped = self.is_dropped(name);
+                let mut filtered = Attributes::new();
+                for attribute in &attributes {
+                    match (declared_prefix(attribute.name()), attribute.prefix()) {
+                        (Some(_), _) if attribute.value() == self.uri => {}
+                        (None, Some(prefix)) if self.scopes.uri(prefix) == Some(self.uri.as_str()) => {
+                            filtered.push(Attribute { name: Cow::Borrowed(attribute.local_name()), value: Cow::Borrowed(attribute.value()) });
+                        }
+                        _ => filtered.push(Attribute { name: Cow::Borrowed(attribute.name()), value: Cow::Borrowed(attribute.value()) }),
+                    }
+                }
+                let name = if is_dropped { split_qname(name).1 } else { name };
+                let mut output_default = match filtered.get("xmlns") {
+                    Some(declaration) => declaration.value().to_string(),
+                    None => self.output_defaults.last().cloned().unwrap_or_default(),
+                };
+                if !name.contains(':') {
+                    let uri = if is_dropped { "" } else { self.scopes.uri("").unwrap_or("") };
+                    if output_default != uri {
+                        filtered.insert("xmlns", uri);
+                        output_default = uri.to_string();
+                    }
+                }
+                self.output_defaults.push(output_default);
+                next.event(Event::StartElement { name, attributes: filtered, depth })
+            }
+            Event::EndElement { name, depth } => {
+                let name = if self.is_dropped(name) { split_qname(name).1 } else { name };
+                self.scopes.pop();
+                self.output_defaults.pop();
+                next.event(Event::EndElement { name, depth })
+            }
+            event => next.event(event),
+        }
+    }
+}
+
+/// Removes all namespaces, by removing the declarations and the prefixes of element and attribute names,
+/// or moves all elements into one default namespace. Attributes with the `xml` prefix keep it.
+/// Of attributes whose names collide without their prefixes, the first one is kept.
+pub struct StripNamespaces {
+    opt_default: Option<String>,
+}
+
+impl StripNamespaces {
+    /// Remove all namespaces.
+    pub fn new() -> Self {
+        StripNamespaces { opt_default: None }
+    }
+
+    /// Move all elements into the namespace, which is declared as the default namespace of the outermost elements.
+    pub fn into_default(uri: &str) -> Self {
+        StripNamespaces { opt_default: Some(uri.to_string()) }
+    }
+}
+
+impl Default for StripNamespaces {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Filter for StripNamespaces {
+    fn event(&mut self, event: Event, next: &mut dyn EventSink) -> io::Result<()> {
+        match event {
+            Event::StartElement { name, attributes, depth } => {
+                let mut stripped = Attributes::new();
+                if let Some(uri) = self.opt_default.as_deref().filter(|_| depth == 0) {
+                    stripped.insert("xmlns", uri);
+                }
+                for attribute in attributes.iter().filter(|a| declared_prefix(a.name()).is_none()) {
+                    let name = if attribute.prefix() == Some("xml") { attribute.name() } else { attribute.local_name() };
+                    if !stripped.contains(name) {
+                        stripped.push(Attribute { name: Cow::Borrowed(name), value: Cow::Borrowed(attribute.value()) });
+                    }
+                }
+                next.event(Event::StartElement { name: split_qname(name).1, attributes: stripped, depth })
+            }
+            Event::EndElement { name, depth } => next.event(Event::EndElement { name: split_qname(name).1, depth }),
+            event => next.event(event),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Synthetic documents for benchmarks and tests.
+//!
+//! The output only depends on the [GeneratorOptions], the same seed always yields the same document.
+
+use crate::writer::XmlWriter;
+
+const WORDS: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do",
+    "eiusmod", "tempor", "incididunt", "ut", "labore", "et", "dolore", "magna", "aliqua", "enim",
+];
+
+const NAMES: &[&str] = &["item", "entry", "record", "value", "node", "group", "field", "data"];
+
+const REFERENCES: &[&str] = &["&amp;", "&lt;", "&gt;", "&quot;", "&apos;", "&#169;", "&#x20AC;"];
+
+/// Shape of a generated document.
+#[derive(Clone, Debug)]
+pub struct GeneratorOptions {
+    pub seed: u64,
+    /// Nesting depth of elements, the root element has depth 0
+    pub max_depth: usize,
+    /// Number of children of every element above the maximum depth
+    pub fan_out: usize,
+    /// Average number of attributes per element
+    pub attribute_density: f64,
+    /// Probability of a child being text instead of an element
+    pub text_ratio: f64,
+    /// Probability of a word in text or attribute values being a reference instead
+    pub entity_ratio: f64,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            seed: 0,
+            max_depth: 4,
+            fan_out: 4,
+            attribute_density: 1.0,
+            text_ratio: 0.25,
+            entity_ratio: 0.05,
+        }
+    }
+}
+
+/// [SplitMix64](https://prng.di.unimi.it/splitmix64.c), small and good enough for workloads
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn choose<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.next() as usize % items.len()]
+    }
+}
+
+struct Generator<'o> {
+    options: &'o GeneratorOptions,
+    rng: Rng,
+}
+
+impl Generator<'_> {
+    /// A few words, escaped and with references mixed in
+    fn words(&mut self, count: usize) -> String {
+        let mut text = String::new();
+        for i in 0..count {
+            if i > 0 {
+                text.push(' ');
+            }
+            if self.rng.next_f64() < self.options.entity_ratio {
+                text.push_str(self.rng.choose(REFERENCES));
+            } else {
+                text.push_str(self.rng.choose(WORDS));
+            }
+        }
+        text
+    }
+
+    fn element<W: std::io::Write>(&mut self, writer: &mut XmlWriter<W>, depth: usize) -> std::io::Result<()> {
+        writer.start_element(self.rng.choose(NAMES))?;
+        // the fractional part of the density is the probability of one more attribute
+        let density = self.options.attribute_density.max(0.0);
+        let mut attribute_count = density as usize;
+        if self.rng.next_f64() < density.fract() {
+            attribute_count += 1;
+        }
+        for i in 0..attribute_count {
+            let value_len = 1 + self.rng.next() as usize % 3;
+            let value = self.words(value_len);
+            writer.attribute_raw(&format!("a{}", i), &value)?;
+        }
+        if depth >= self.options.max_depth {
+            let text_len = 1 + self.rng.next() as usize % 8;
+            let text = self.words(text_len);
+            writer.text_raw(&text)?;
+        } else {
+            for _ in 0..self.options.fan_out {
+                if self.rng.next_f64() < self.options.text_ratio {
+                    let text_len = 1 + self.rng.next() as usize % 8;
+                    let text = self.words(text_len);
+                    writer.text_raw(&text)?;
+                } else {
+                    self.element(writer, depth + 1)?;
+                }
+            }
+        }
+        writer.end_element()
+    }
+}
+
+/// Write a generated document.
+pub fn write_generated<W: std::io::Write>(options: &GeneratorOptions, writer: &mut XmlWriter<W>) -> std::io::Result<()> {
+    let mut generator = Generator { options, rng: Rng(options.seed) };
+    generator.element(writer, 0)
+}
+
+/// Generate a document into a string.
+pub fn generate(options: &GeneratorOptions) -> String {
+    let mut writer = XmlWriter::new(Vec::new());
+    write_generated(options, &mut writer).unwrap();
+    String::from_utf8(writer.into_inner()).unwrap()
+}
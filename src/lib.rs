@@ -1,11 +1,61 @@
+#[macro_use]
+mod trace;
+
 pub mod tokenize;
 pub mod parse;
 pub mod node;
 pub mod token;
+pub mod textrange;
+pub mod xmlchar;
 pub mod error;
+pub mod document;
+pub mod writer;
+pub mod namespace;
+pub mod options;
+pub mod entities;
+pub mod profile;
+pub mod refs;
+pub mod escape;
+pub mod generator;
+pub mod stats;
+pub mod pull;
+pub mod redact;
+pub mod xpath;
+pub mod xslt;
+pub mod query;
+pub mod streaming;
+pub mod soap;
+pub mod plist;
+pub mod binding;
+pub mod codegen;
+pub mod report;
+pub mod lint;
+pub mod shard;
+pub mod scan;
+pub mod filter;
+pub mod binary;
+pub mod types;
+pub mod compare;
+pub mod diff;
+pub mod dump;
+#[cfg(feature = "quick-xml")]
+pub mod quickxml;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "tokio")]
+pub mod asyncread;
+pub mod testutil;
+#[cfg(feature = "fastinfoset")]
+pub mod fastinfoset;
+#[cfg(feature = "wbxml")]
+pub mod wbxml;
+#[cfg(feature = "feeds")]
+pub mod feeds;
 
 mod tokenstream;
 mod chariter;
-mod xmlchar;
-mod util;
-mod textrange;
\ No newline at end of file
+mod dfa;
+mod soup;
+mod xpointer;
\ No newline at end of file
@@ -0,0 +1,188 @@
+//! Custom rules that check documents in a single traversal, for linters built on this crate.
+//!
+//! A [Rule] is called for every node of a document in document order and reports its findings to a [ReportSink],
+//! which collects them in a [ValidationReport] with the rule's code. Closures are rules,
+//! [built-in rules](Linter::with_builtin_rules) check ids, empty attributes, the nesting depth and deprecated elements.
+//!
+//! ```
+//! use jaxp_rust::document::{Document, Node};
+//! use jaxp_rust::lint::{Linter, ReportSink};
+//!
+//! let doc = Document::parse("<html><img src=\"a.png\"/><img src=\"b.png\" alt=\"B\"/></html>").unwrap();
+//! let mut linter = Linter::new();
+//! linter.add_rule("img-alt", |node: &Node, sink: &mut ReportSink| {
+//!     if node.tag_name() == "img" && node.attribute("alt").is_none() {
+//!         sink.warning(node, "image without alt text");
+//!     }
+//! });
+//! let report = linter.run(&doc);
+//! assert_eq!("1:8: warning[img-alt]: image without alt text\n", report.to_string());
+//! ```
+
+use std::collections::HashMap;
+
+use crate::document::{Document, Node};
+use crate::report::{Diagnostic, Severity, Source, Span, ValidationReport};
+use crate::textrange::LineIndex;
+
+/// A check of single nodes, which may keep state across the nodes of a document.
+pub trait Rule {
+    fn check(&mut self, node: &Node, sink: &mut ReportSink);
+}
+
+impl<F: FnMut(&Node, &mut ReportSink)> Rule for F {
+    fn check(&mut self, node: &Node, sink: &mut ReportSink) {
+        self(node, sink)
+    }
+}
+
+/// Collects the findings of the rules of a [Linter].
+pub struct ReportSink<'input> {
+    text: &'input str,
+    lines: LineIndex,
+    code: String,
+    report: ValidationReport,
+}
+
+impl ReportSink<'_> {
+    /// Report a finding of the current rule.
+    pub fn report(&mut self, severity: Severity, span: Option<Span>, message: impl Into<String>) {
+        let diagnostic = Diagnostic::new(severity, Source::Rule, &self.code, message);
+        self.report.push(match span {
+            Some(span) => diagnostic.with_span(span),
+            None => diagnostic,
+        });
+    }
+
+    /// Report an error at a node.
+    pub fn error(&mut self, node: &Node, message: impl Into<String>) {
+        let span = self.span_of_node(node);
+        self.report(Severity::Error, span, message)
+    }
+
+    pub fn warning(&mut self, node: &Node, message: impl Into<String>) {
+        let span = self.span_of_node(node);
+        self.report(Severity::Warning, span, message)
+    }
+
+    pub fn info(&mut self, node: &Node, message: impl Into<String>) {
+        let span = self.span_of_node(node);
+        self.report(Severity::Info, span, message)
+    }
+
+    /// The span of a part of the input, e.g. an attribute value of the document.
+    /// `None` for text that is not a slice of the input, e.g. a name that was changed after parsing.
+    pub fn span_of(&self, text: &str) -> Option<Span> {
+        let start = (text.as_ptr() as usize).checked_sub(self.text.as_ptr() as usize)?;
+        if start + text.len() > self.text.len() {
+            return None;
+        }
+        Some(Span { start: self.lines.pos(start), end: self.lines.pos(start + text.len()) })
+    }
+
+    /// The span of the name of an element, `None` for other nodes.
+    pub fn span_of_node(&self, node: &Node) -> Option<Span> {
+        if node.is_element() {
+            self.span_of(node.tag_name())
+        } else {
+            None
+        }
+    }
+}
+
+/// Runs [Rule]s over documents.
+#[derive(Default)]
+pub struct Linter {
+    rules: Vec<(String, Box<dyn Rule>)>,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A linter with the rules `duplicate-id`, `empty-attribute`, `max-depth` with the maximum depth and
+    /// `deprecated-element` with the names of deprecated elements.
+    pub fn with_builtin_rules(max_depth: usize, deprecated_elements: &[&str]) -> Self {
+        let mut linter = Linter::new();
+        linter
+            .add_rule("duplicate-id", NoDuplicateIds::default())
+            .add_rule("empty-attribute", no_empty_attributes)
+            .add_rule("max-depth", MaxDepth(max_depth))
+            .add_rule("deprecated-element", DeprecatedElements(deprecated_elements.iter().map(|s| s.to_string()).collect()));
+        linter
+    }
+
+    /// Add a rule, whose findings are reported with the code.
+    pub fn add_rule(&mut self, code: &str, rule: impl Rule + 'static) -> &mut Self {
+        self.rules.push((code.to_string(), Box::new(rule)));
+        self
+    }
+
+    /// Check every node of the document with every rule, in the order they were added.
+    pub fn run(&mut self, doc: &Document) -> ValidationReport {
+        let mut sink = ReportSink { text: doc.input_text(), lines: LineIndex::new(doc.input_text()), code: String::new(), report: ValidationReport::new() };
+        for node in doc.descendants() {
+            for (code, rule) in &mut self.rules {
+                sink.code.clone_from(code);
+                rule.check(&node, &mut sink);
+            }
+        }
+        sink.report
+    }
+}
+
+/// An error for every element whose `id` or `xml:id` value was used by an earlier element.
+#[derive(Default)]
+pub struct NoDuplicateIds {
+    /// The ids with the name of the element that uses them first
+    seen: HashMap<String, String>,
+}
+
+impl Rule for NoDuplicateIds {
+    fn check(&mut self, node: &Node, sink: &mut ReportSink) {
+        for attribute in node.attributes().filter(|a| a.name() == "id" || a.name() == "xml:id") {
+            match self.seen.get(attribute.value()) {
+                Some(first) => {
+                    let span = sink.span_of(attribute.value());
+                    sink.report(Severity::Error, span, format!("duplicate id {}, first used by {}", attribute.value(), first));
+                }
+                None => {
+                    self.seen.insert(attribute.value().to_string(), node.tag_name().to_string());
+                }
+            }
+        }
+    }
+}
+
+/// A warning for every attribute with an empty value.
+pub fn no_empty_attributes(node: &Node, sink: &mut ReportSink) {
+    for attribute in node.attributes().filter(|a| a.value().is_empty()) {
+        let span = sink.span_of(attribute.name());
+        sink.report(Severity::Warning, span, format!("attribute {} of {} is empty", attribute.name(), node.tag_name()));
+    }
+}
+
+/// An error for every element that is nested deeper than the maximum, but not for its descendants.
+/// A root element has depth 1.
+pub struct MaxDepth(pub usize);
+
+impl Rule for MaxDepth {
+    fn check(&mut self, node: &Node, sink: &mut ReportSink) {
+        // the ancestors include the node and the document root
+        if node.is_element() && node.ancestors().count() == self.0 + 2 {
+            sink.error(node, format!("element {} is nested deeper than {} levels", node.tag_name(), self.0));
+        }
+    }
+}
+
+/// A warning for every element with one of the names.
+pub struct DeprecatedElements(pub Vec<String>);
+
+impl Rule for DeprecatedElements {
+    fn check(&mut self, node: &Node, sink: &mut ReportSink) {
+        if node.is_element() && self.0.iter().any(|name| name == node.tag_name()) {
+            sink.warning(node, format!("element {} is deprecated", node.tag_name()));
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Constants and helpers for [Namespaces in XML 1.0](https://www.w3.org/TR/xml-names/).
+
+use crate::xmlchar::is_valid_xml_name;
+
+/// The namespace bound to the `xml` prefix.
+pub const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// The namespace of `xmlns` attributes.
+pub const XMLNS_NAMESPACE: &str = "http://www.w3.org/2000/xmlns/";
+
+/// Split a qualified name into prefix and local part.
+///
+/// [\[7\] QName](https://www.w3.org/TR/xml-names/#NT-QName)
+pub fn split_qname(name: &str) -> (Option<&str>, &str) {
+    match name.find(':') {
+        Some(i) => (Some(&name[..i]), &name[i + 1..]),
+        None => (None, name),
+    }
+}
+
+/// Whether the text is a name without a colon, like prefixes and local parts.
+///
+/// [\[4\] NCName](https://www.w3.org/TR/xml-names/#NT-NCName)
+pub fn is_ncname(name: &str) -> bool {
+    !name.contains(':') && is_valid_xml_name(name)
+}
+
+/// Check that a name is a qualified name, which namespace-aware processing requires of the names
+/// of elements and attributes, and split it into prefix and local part.
+///
+/// [\[7\] QName](https://www.w3.org/TR/xml-names/#NT-QName)
+pub fn validate_qname(name: &str) -> Result<(Option<&str>, &str), &'static str> {
+    let (opt_prefix, local_name) = split_qname(name);
+    if opt_prefix.is_some_and(|prefix| !is_ncname(prefix)) {
+        return Err("the prefix is not an NCName");
+    }
+    if !is_ncname(local_name) {
+        return Err("the local part is not an NCName");
+    }
+    Ok((opt_prefix, local_name))
+}
+
+/// If an attribute name declares a namespace, get the declared prefix.
+/// The default namespace is declared for the empty prefix.
+pub fn declared_prefix(attribute_name: &str) -> Option<&str> {
+    match split_qname(attribute_name) {
+        (None, "xmlns") => Some(""),
+        (Some("xmlns"), prefix) => Some(prefix),
+        _ => None,
+    }
+}
+
+/// Check a namespace declaration against the reserved prefixes and namespaces,
+/// see [3 Declaring Namespaces](https://www.w3.org/TR/xml-names/#ns-decl).
+/// The empty prefix declares the default namespace.
+pub fn check_declaration(prefix: &str, uri: &str) -> Result<(), &'static str> {
+    match prefix {
+        "xml" if uri != XML_NAMESPACE => Err("the xml prefix must be bound to the XML namespace"),
+        "xml" => Ok(()),
+        "xmlns" => Err("the xmlns prefix must not be declared"),
+        _ if uri == XML_NAMESPACE => Err("the XML namespace must not be bound to a prefix other than xml"),
+        _ if uri == XMLNS_NAMESPACE => Err("the xmlns namespace must not be declared"),
+        "" => Ok(()),
+        _ if uri.is_empty() => Err("a prefix must not be undeclared"),
+        _ => Ok(()),
+    }
+}
+
+/// A prefix to namespace binding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Namespace<'a> {
+    pub(crate) prefix: Option<&'a str>,
+    pub(crate) uri: &'a str,
+}
+
+impl<'a> Namespace<'a> {
+    /// The bound prefix, `None` for the default namespace.
+    pub fn prefix(&self) -> Option<&'a str> {
+        self.prefix
+    }
+
+    pub fn uri(&self) -> &'a str {
+        self.uri
+    }
+}
@@ -1,4 +1,10 @@
-#[derive(Debug, PartialEq)]
+use std::borrow::Cow;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::refs::decode_entities;
+
+#[derive(PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum XmlNode<'a> {
     TextNode(&'a str),
     CommentNode(&'a str),
@@ -6,4 +12,179 @@ pub enum XmlNode<'a> {
     AttributeNode { name: &'a str, value: &'a str },
     CdataSectionNode(&'a str),
     ProcessingInstructionNode(&'a str, Option<&'a str>),
-}
\ No newline at end of file
+}
+
+impl XmlNode<'_> {
+    fn write_tree(&self, f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+        let indent = "  ".repeat(depth);
+        match self {
+            XmlNode::TextNode(text) => writeln!(f, "{}Text {}", indent, truncate(text)),
+            XmlNode::CommentNode(text) => writeln!(f, "{}Comment {}", indent, truncate(text)),
+            XmlNode::CdataSectionNode(text) => writeln!(f, "{}CdataSection {}", indent, truncate(text)),
+            XmlNode::AttributeNode { name, value } => writeln!(f, "{}Attribute {}={}", indent, name, truncate(value)),
+            XmlNode::ProcessingInstructionNode(target, opt_value) => match opt_value {
+                Some(value) => writeln!(f, "{}ProcessingInstruction {} {}", indent, target, truncate(value)),
+                None => writeln!(f, "{}ProcessingInstruction {}", indent, target),
+            },
+            XmlNode::ElementNode { name, children } => {
+                writeln!(f, "{}Element {}", indent, name)?;
+                children.iter().try_for_each(|child| child.write_tree(f, depth + 1))
+            }
+        }
+    }
+}
+
+/// A comparison of [XmlNode]s that is less strict than `PartialEq`, which compares every child and name as it is.
+/// Text and attribute values are compared with their references resolved, like [assert_xml_eq](crate::assert_xml_eq) does.
+///
+/// ```
+/// use jaxp_rust::node::NodeEq;
+/// use jaxp_rust::parse::XmlParser;
+///
+/// let a = XmlParser::default().parse("<a x='1' y='2'>\n  <b>text</b>\n</a>").unwrap();
+/// let b = XmlParser::default().parse("<A y='2' x='1'><!--c--><B>text</B></A>").unwrap();
+/// assert_ne!(a, b);
+/// let eq = NodeEq { ignore_attribute_order: true, ignore_comments: true, normalize_whitespace: true, case_insensitive_names: true };
+/// assert!(eq.equal(&a, &b));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NodeEq {
+    /// Compare the attributes of an element as a set
+    pub ignore_attribute_order: bool,
+    /// Skip comments, the text around them is compared as one
+    pub ignore_comments: bool,
+    /// Skip whitespace-only text, trim text and attribute values and collapse their runs of whitespace to a single space
+    pub normalize_whitespace: bool,
+    /// Compare the names of elements and attributes and the targets of processing instructions ignoring case
+    pub case_insensitive_names: bool,
+}
+
+impl NodeEq {
+    /// Whether two nodes and their descendants are equal under the flags.
+    pub fn equal(&self, a: &XmlNode, b: &XmlNode) -> bool {
+        match (a, b) {
+            (XmlNode::ElementNode { name: a_name, children: a_children }, XmlNode::ElementNode { name: b_name, children: b_children }) => {
+                if !self.names_eq(a_name, b_name) {
+                    return false;
+                }
+                let (mut a_attributes, a_content) = self.split_children(a_children);
+                let (mut b_attributes, b_content) = self.split_children(b_children);
+                if self.ignore_attribute_order {
+                    a_attributes.sort_by_key(|(name, _)| self.name_key(name));
+                    b_attributes.sort_by_key(|(name, _)| self.name_key(name));
+                }
+                a_attributes.len() == b_attributes.len()
+                    && a_attributes.iter().zip(&b_attributes).all(|((a_name, a_value), (b_name, b_value))| {
+                        self.names_eq(a_name, b_name) && self.value(a_value) == self.value(b_value)
+                    })
+                    && a_content.len() == b_content.len()
+                    && a_content.iter().zip(&b_content).all(|(a, b)| match (a, b) {
+                        (Content::Text(a), Content::Text(b)) => a == b,
+                        (Content::Node(a), Content::Node(b)) => self.equal(a, b),
+                        _ => false,
+                    })
+            }
+            (XmlNode::AttributeNode { name: a_name, value: a_value }, XmlNode::AttributeNode { name: b_name, value: b_value }) => {
+                self.names_eq(a_name, b_name) && self.value(a_value) == self.value(b_value)
+            }
+            (XmlNode::ProcessingInstructionNode(a_target, a_value), XmlNode::ProcessingInstructionNode(b_target, b_value)) => {
+                self.names_eq(a_target, b_target) && a_value.map(|v| self.normalize(v)) == b_value.map(|v| self.normalize(v))
+            }
+            (XmlNode::TextNode(a), XmlNode::TextNode(b)) => self.value(a) == self.value(b),
+            (XmlNode::CdataSectionNode(a), XmlNode::CdataSectionNode(b)) | (XmlNode::CommentNode(a), XmlNode::CommentNode(b)) => {
+                self.normalize(a) == self.normalize(b)
+            }
+            _ => false,
+        }
+    }
+
+    fn names_eq(&self, a: &str, b: &str) -> bool {
+        a == b || self.case_insensitive_names && a.to_lowercase() == b.to_lowercase()
+    }
+
+    fn name_key<'n>(&self, name: &'n str) -> Cow<'n, str> {
+        if self.case_insensitive_names { Cow::Owned(name.to_lowercase()) } else { Cow::Borrowed(name) }
+    }
+
+    fn normalize<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if self.normalize_whitespace {
+            Cow::Owned(text.split_whitespace().collect::<Vec<_>>().join(" "))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Text or an attribute value with its references resolved, normalized.
+    fn value<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        match decode_entities(text) {
+            Cow::Borrowed(text) => self.normalize(text),
+            Cow::Owned(text) => Cow::Owned(self.normalize(&text).into_owned()),
+        }
+    }
+
+    /// The attributes of an element, and its content with adjacent text merged as the flags skip nodes in between.
+    fn split_children<'n>(&self, children: &'n [XmlNode<'n>]) -> (Vec<(&'n str, &'n str)>, Vec<Content<'n>>) {
+        let mut attributes = vec![];
+        let mut content: Vec<Content> = vec![];
+        for child in children {
+            match child {
+                XmlNode::AttributeNode { name, value } => attributes.push((*name, *value)),
+                XmlNode::CommentNode(_) if self.ignore_comments => (),
+                XmlNode::TextNode(text) => match content.last_mut() {
+                    Some(Content::Text(previous)) => previous.push_str(&decode_entities(text)),
+                    _ => content.push(Content::Text(decode_entities(text).into_owned())),
+                },
+                node => content.push(Content::Node(node)),
+            }
+        }
+        for item in &mut content {
+            if let Content::Text(text) = item {
+                *text = self.normalize(text).into_owned();
+            }
+        }
+        if self.normalize_whitespace {
+            content.retain(|item| !matches!(item, Content::Text(text) if text.is_empty()));
+        }
+        (attributes, content)
+    }
+}
+
+/// The content of an element as [NodeEq] compares it
+enum Content<'n> {
+    Text(String),
+    Node(&'n XmlNode<'n>),
+}
+
+/// Quote text, truncated to [TREE_TEXT_LEN](crate::document::TREE_TEXT_LEN) characters.
+pub(crate) fn truncate(text: &str) -> String {
+    match text.char_indices().nth(crate::document::TREE_TEXT_LEN) {
+        Some((end, _)) => format!("{:?}...", &text[..end]),
+        None => format!("{:?}", text),
+    }
+}
+
+/// The tree with one node per line, indented by depth, like that of a [Node](crate::document::Node) without positions.
+impl Display for XmlNode<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+/// The alternate form `{:#?}` is the tree of `Display`, the nested vectors of the derived form are hard to read.
+impl Debug for XmlNode<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            return self.write_tree(f, 0);
+        }
+        match self {
+            XmlNode::TextNode(text) => f.debug_tuple("TextNode").field(text).finish(),
+            XmlNode::CommentNode(text) => f.debug_tuple("CommentNode").field(text).finish(),
+            XmlNode::ElementNode { name, children } => f.debug_struct("ElementNode").field("name", name).field("children", children).finish(),
+            XmlNode::AttributeNode { name, value } => f.debug_struct("AttributeNode").field("name", name).field("value", value).finish(),
+            XmlNode::CdataSectionNode(text) => f.debug_tuple("CdataSectionNode").field(text).finish(),
+            XmlNode::ProcessingInstructionNode(target, opt_value) => {
+                f.debug_tuple("ProcessingInstructionNode").field(target).field(opt_value).finish()
+            }
+        }
+    }
+}
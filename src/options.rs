@@ -0,0 +1,163 @@
+//! Options that control how a [Document](crate::document::Document) is parsed.
+
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use crate::document::MAX_UNTRUSTED_DEPTH;
+use crate::entities::EntityTable;
+use crate::profile::Profile;
+use crate::refs::CharRefPolicy;
+
+/// How strictly the constraints of the specifications are enforced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Reject every document that is not well-formed, and start tags that violate
+    /// [Namespaces in XML](https://www.w3.org/TR/xml-names/): names that are not QNames, undeclared prefixes,
+    /// duplicate expanded attribute names and misuse of the reserved `xml` and `xmlns` prefixes.
+    #[default]
+    Conformant,
+    /// Accept documents that violate constraints which do not affect the structure of the tree:
+    /// duplicate attributes, names that are not QNames or have undeclared prefixes
+    /// and misuse of the reserved `xml` and `xmlns` prefixes.
+    /// Of duplicate attributes the first one is found by lookups.
+    Lenient,
+    /// Read HTML-like markup that is not well-formed into a best-effort tree, the parse never fails on markup.
+    /// Attribute values may be unquoted or missing, end tags match start tags ignoring case,
+    /// void elements like `br` need no end tag and implied end tags, e.g. of `p` or `li`, are inserted.
+    /// These rules of HTML can be replaced by a [Profile] for other dialects.
+    /// Stray end tags are dropped and elements still open at the end of the input are closed.
+    TagSoup,
+}
+
+/// Which comments are accepted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommentPolicy {
+    /// Comments as defined by [\[15\] Comment](https://www.w3.org/TR/xml/#NT-Comment)
+    #[default]
+    Strict,
+    /// Also accept `--` inside of comments and a hyphen right before the closing `-->`,
+    /// as emitted by some generators
+    AllowDoubleHyphen,
+    /// Reject documents containing comments
+    Deny,
+}
+
+/// Whether processing instructions are accepted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProcessingInstructionPolicy {
+    #[default]
+    Allow,
+    /// Reject documents containing processing instructions
+    Deny,
+}
+
+/// How a document type declaration is treated.
+///
+/// The declaration is never used to expand entities or fetch external resources,
+/// still, services parsing untrusted input should not accept it at all, see [ParseOptions::untrusted].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DoctypePolicy {
+    /// Reject documents with a document type declaration
+    Error,
+    /// Skip the declaration without looking into its internal subset.
+    /// The declaration is scanned as raw bytes for its end, which is much faster than processing a large internal subset.
+    Ignore,
+    /// Check the declaration and its internal subset for well-formedness
+    #[default]
+    Process,
+}
+
+/// How the tokenizer scans the content of a document, both accept the same documents with the same tokens and errors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TokenizerBackend {
+    /// A parser with a function per rule of the grammar
+    #[default]
+    RecursiveDescent,
+    /// A table-driven state machine for tags, attributes and text, whose branches are easier to predict,
+    /// which pays off for documents with many attributes. Documents that it rejects are scanned again with recursive
+    /// descent for the error, so with [ParseOptions::lazy_char_validation], whose checks it does not skip,
+    /// documents with characters that are not XML Chars are scanned twice.
+    Dfa,
+}
+
+/// What an [AttributeFilter] does with an attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttributeAction {
+    Keep,
+    /// Replace the value, which is escaped when stored
+    Replace(String),
+    Drop,
+}
+
+/// A callback that is invoked on every attribute while a document is parsed,
+/// e.g. to redact secrets or to normalize values at ingest.
+///
+/// It receives the name of the attribute, its value with references resolved, and the name of its element.
+/// Well-formedness checks see the attributes as they appear in the input.
+#[derive(Clone)]
+pub struct AttributeFilter(Arc<FilterFn>);
+
+type FilterFn = dyn Fn(&str, &str, &str) -> AttributeAction + Send + Sync;
+
+impl AttributeFilter {
+    pub fn new(filter: impl Fn(&str, &str, &str) -> AttributeAction + Send + Sync + 'static) -> Self {
+        AttributeFilter(Arc::new(filter))
+    }
+
+    pub(crate) fn apply(&self, name: &str, value: &str, element_name: &str) -> AttributeAction {
+        (self.0)(name, value, element_name)
+    }
+}
+
+impl Debug for AttributeFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AttributeFilter")
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    pub strictness: Strictness,
+    pub comments: CommentPolicy,
+    pub processing_instructions: ProcessingInstructionPolicy,
+    /// Named entities that references may refer to besides the predefined ones, e.g. [EntityTable::html]
+    pub entities: EntityTable,
+    pub doctype: DoctypePolicy,
+    pub attribute_filter: Option<AttributeFilter>,
+    /// The dialect of tag soup, HTML-flavored XHTML if `None`, see [Profile::parse_options]
+    pub profile: Option<Profile>,
+    /// The maximum length in bytes that the tokenizer scans ahead for the end of a comment, processing instruction,
+    /// CDATA section or [skipped](DoctypePolicy::Ignore) document type declaration.
+    /// Longer constructs fail with [LimitExceeded](crate::error::XmlError::LimitExceeded), so that input without
+    /// a closing delimiter is rejected early. Unlimited if `None`.
+    pub max_lookahead: Option<usize>,
+    /// The maximum nesting depth of elements, the root element is at depth 1. Deeper elements fail with
+    /// [LimitExceeded](crate::error::XmlError::LimitExceeded). Serializing and comparing documents recurse on nesting,
+    /// so this bounds their stack use. Unlimited if `None`, tag soup is never limited.
+    pub max_depth: Option<usize>,
+    /// Scan text, attribute values, comments, CDATA sections and processing instructions by the bytes that end them,
+    /// without checking that their characters are XML Chars, which is faster for inputs that are mostly content.
+    /// Names and references are still checked. The characters of a token can be checked on access with
+    /// [TextRange::as_str](crate::textrange::TextRange::as_str), documents do not check them at all.
+    pub lazy_char_validation: bool,
+    pub tokenizer_backend: TokenizerBackend,
+    /// The characters that character references may refer to
+    pub char_refs: CharRefPolicy,
+}
+
+impl ParseOptions {
+    /// Options for input from untrusted sources, which rejects document type declarations
+    /// and elements nested deeper than [MAX_UNTRUSTED_DEPTH].
+    pub fn untrusted() -> Self {
+        ParseOptions { doctype: DoctypePolicy::Error, max_depth: Some(MAX_UNTRUSTED_DEPTH), ..Default::default() }
+    }
+}
+
+/// Which part of a document [Document::parse_sample](crate::document::Document::parse_sample) reads.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SampleOptions {
+    /// The qualified names of the elements from the root element down to the records, separated by slashes, e.g. `/feed/entry`
+    pub record_path: String,
+    /// The number of records to read, parsing stops at the start tag of the next one
+    pub max_records: usize,
+}
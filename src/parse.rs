@@ -1,27 +1,52 @@
+use crate::chariter::CharIter;
 use crate::error::*;
 use crate::error::XmlError::UnexpectedXmlToken;
 use crate::node::XmlNode;
 use crate::node::XmlNode::*;
+use crate::options::ParseOptions;
+use crate::textrange::TextRange;
+use crate::token::XmlToken;
 use crate::token::XmlToken::*;
-use crate::tokenize::XmlTokenizer;
+use crate::tokenize::{recycle_tokens, XmlTokenizer};
 use crate::tokenstream::TokenStream;
 
-pub struct XmlParser {}
-
-impl Default for XmlParser {
-    fn default() -> Self {
-        XmlParser {}
-    }
+/// A parser that can be reused for many documents, keeping its configuration and buffers.
+#[derive(Default)]
+pub struct XmlParser {
+    tokenizer: XmlTokenizer,
+    /// Emptied token buffer of the previous document, kept for its allocation
+    tokens: Vec<XmlToken<'static>>,
 }
 
 impl<'a> XmlParser {
+    pub fn with_options(options: &ParseOptions) -> Self {
+        XmlParser { tokenizer: XmlTokenizer::with_options(options), tokens: vec![] }
+    }
+
+    /// Forget the state of the previous document, the configuration and buffers are kept.
+    /// Parsing a document starts with a reset, so this is only needed to release state early.
+    pub fn reset(&mut self) {
+        self.tokenizer.reset();
+        self.tokens.clear();
+    }
+
     pub fn parse(&mut self, xml: &'a str) -> Result<XmlNode<'a>, XmlError> {
-        // tokenize
-        let tokens = XmlTokenizer::default().tokenize(xml)?;
+        self.reset();
+        let mut tokens = recycle_tokens(std::mem::take(&mut self.tokens));
+        let result = self.tokenizer.tokenize_into(xml, &mut tokens);
         let ts = &mut TokenStream::from(tokens);
+        let result = result.and_then(|_| Self::parse_tokens(xml, ts));
+        self.tokens = recycle_tokens(ts.take_tokens());
+        result
+    }
+
+    fn parse_tokens(xml: &'a str, ts: &mut TokenStream<'a>) -> Result<XmlNode<'a>, XmlError> {
+        // only used for error positions
+        let ci = CharIter { pos: 0, text: xml };
 
         // 10 is a reasonable max depth
         let mut depth_stack = Vec::with_capacity(20);
+        let mut tag_stack: Vec<TextRange> = Vec::with_capacity(20);
         // shadow document root
         depth_stack.push(Vec::with_capacity(1));
 
@@ -29,16 +54,21 @@ impl<'a> XmlParser {
             let active_child_list = depth_stack.last_mut().unwrap();
             match ts.next() {
                 EndTag(name_range) => {
-                    //TODO verify name equality
                     let tag_name = name_range.slice;
+                    let start_range = match tag_stack.pop() {
+                        Some(start_range) => start_range,
+                        None => return Err(UnexpectedXmlToken { pos: ci.error_pos_of(name_range.start) })
+                    };
+                    if !start_range.name_eq(tag_name) {
+                        return Err(ci.non_matching_tags(start_range, *name_range, &tag_stack));
+                    }
                     // Currently active child list belongs to this element node
                     let node = ElementNode { name: tag_name, children: depth_stack.pop().unwrap() };
                     // Add element node to parent element
                     depth_stack.last_mut().unwrap().push(node);
                 }
-                StartTag(_) => {
-                    // TODO remember start tag
-                    // let tag_name = name_range.slice;
+                StartTag(name_range) => {
+                    tag_stack.push(*name_range);
                     // Change active child list
                     let child_list = Vec::with_capacity(5);
                     depth_stack.push(child_list);
@@ -54,12 +84,12 @@ impl<'a> XmlParser {
                     active_child_list.push(CdataSectionNode(value_range.slice)),
                 ProcessingInstruction { target_range, opt_value_range } =>
                     active_child_list.push(ProcessingInstructionNode(target_range.slice, opt_value_range.map(|ovr| ovr.slice))),
-                unexpected_token => {
-                    // TODO position of unexpected token
-                    return Err(UnexpectedXmlToken { pos: XmlErrorPos { row: 0, col: 0 } });
-                }
+                token => return Err(UnexpectedXmlToken { pos: ci.error_pos_of(token.start()) }),
             }
         }
+        if let Some(&start_range) = tag_stack.last() {
+            return Err(ci.unclosed_element(start_range));
+        }
         Ok(depth_stack.pop().unwrap().pop().unwrap())
     }
-}
\ No newline at end of file
+}
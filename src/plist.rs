@@ -0,0 +1,267 @@
+//! Apple XML property lists, see the [PropertyList-1.0 DTD](http://www.apple.com/DTDs/PropertyList-1.0.dtd).
+//!
+//! ```
+//! use jaxp_rust::plist::{from_xml, to_xml, PlistValue};
+//!
+//! let xml = "<plist version=\"1.0\"><dict><key>CFBundleName</key><string>Demo</string>\
+//!            <key>LSRequiresIPhoneOS</key><true/></dict></plist>";
+//! let value = from_xml(xml).unwrap();
+//! assert_eq!(Some(&PlistValue::String("Demo".to_string())), value.get("CFBundleName"));
+//! assert_eq!(value, from_xml(&to_xml(&value)).unwrap());
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+use crate::binary::decode_base64;
+use crate::document::{Document, Node, NodeType};
+use crate::error::XmlError;
+use crate::writer::XmlWriter;
+use crate::xpath::Item;
+
+const DOCTYPE: &str = "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">";
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug)]
+pub enum PlistError {
+    Xml(XmlError),
+    /// An element that is not a value or not allowed at its position, by its name
+    UnexpectedElement(String),
+    /// A `dict` with a key without value, or with a value without key
+    MissingKey(String),
+    DuplicateKey(String),
+    /// The content of a value element cannot be read as its type
+    InvalidValue { element: String, value: String },
+}
+
+impl Display for PlistError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PlistError::Xml(e) => write!(f, "{}", e),
+            PlistError::UnexpectedElement(name) => write!(f, "unexpected element {}", name),
+            PlistError::MissingKey(context) => write!(f, "missing key or value in dict: {}", context),
+            PlistError::DuplicateKey(key) => write!(f, "duplicate key {} in dict", key),
+            PlistError::InvalidValue { element, value } => write!(f, "invalid {} value {:?}", element, value),
+        }
+    }
+}
+
+impl std::error::Error for PlistError {}
+
+impl From<XmlError> for PlistError {
+    fn from(e: XmlError) -> Self {
+        PlistError::Xml(e)
+    }
+}
+
+/// A value of a property list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlistValue {
+    Array(Vec<PlistValue>),
+    /// The entries in document order
+    Dictionary(Vec<(String, PlistValue)>),
+    Boolean(bool),
+    /// Binary data, written in base64
+    Data(Vec<u8>),
+    /// An ISO 8601 timestamp like `2003-02-11T23:52:55Z`, kept as written
+    Date(String),
+    Integer(i64),
+    Real(f64),
+    String(String),
+}
+
+impl PlistValue {
+    /// The value of a key, if this is a dictionary.
+    pub fn get(&self, key: &str) -> Option<&PlistValue> {
+        match self {
+            PlistValue::Dictionary(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PlistValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[PlistValue]> {
+        match self {
+            PlistValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Read a property list from the `plist` root element of a document, or from a single value element.
+pub fn from_xml(xml: &str) -> Result<PlistValue, PlistError> {
+    let doc = Document::parse(xml)?;
+    let root = doc.root_element();
+    if root.tag_name() != "plist" {
+        return from_node(root);
+    }
+    let mut values = child_elements(root)?;
+    match (values.next(), values.next()) {
+        (Some(value), None) => from_node(value),
+        (Some(_), Some(extra)) => Err(PlistError::UnexpectedElement(extra.tag_name().to_string())),
+        (None, _) => Err(PlistError::UnexpectedElement("plist".to_string())),
+    }
+}
+
+/// The child elements of a node, which must not contain text other than spaces.
+fn child_elements<'a, 'input>(node: Node<'a, 'input>) -> Result<impl Iterator<Item = Node<'a, 'input>>, PlistError> {
+    let has_text = node
+        .children()
+        .any(|c| matches!(c.node_type(), NodeType::Text | NodeType::CdataSection) && !Item::Node(c).string_value().trim().is_empty());
+    if has_text {
+        return Err(PlistError::InvalidValue { element: node.tag_name().to_string(), value: Item::Node(node).string_value() });
+    }
+    Ok(node.child_elements())
+}
+
+/// Read a value element like `dict` or `string`.
+pub fn from_node(node: Node) -> Result<PlistValue, PlistError> {
+    let name = node.tag_name();
+    let invalid = |value: &str| PlistError::InvalidValue { element: name.to_string(), value: value.to_string() };
+    let text = Item::Node(node).string_value();
+    let value = match name {
+        "array" => PlistValue::Array(child_elements(node)?.map(from_node).collect::<Result<_, _>>()?),
+        "dict" => {
+            let mut entries: Vec<(String, PlistValue)> = vec![];
+            let mut children = child_elements(node)?;
+            while let Some(key) = children.next() {
+                if key.tag_name() != "key" {
+                    return Err(PlistError::MissingKey(format!("{} without key", key.tag_name())));
+                }
+                let key = Item::Node(key).string_value();
+                let value = match children.next() {
+                    Some(value) => from_node(value)?,
+                    None => return Err(PlistError::MissingKey(format!("key {} without value", key))),
+                };
+                if entries.iter().any(|(k, _)| *k == key) {
+                    return Err(PlistError::DuplicateKey(key));
+                }
+                entries.push((key, value));
+            }
+            PlistValue::Dictionary(entries)
+        }
+        "true" | "false" if node.first_element_child().is_some() || !text.trim().is_empty() => return Err(invalid(&text)),
+        "true" | "false" => PlistValue::Boolean(name == "true"),
+        "data" => PlistValue::Data(decode_base64(&text).map_err(|_| invalid(&text))?),
+        "date" => PlistValue::Date(text.trim().to_string()),
+        "integer" => PlistValue::Integer(parse_integer(text.trim()).ok_or_else(|| invalid(&text))?),
+        "real" => PlistValue::Real(parse_real(text.trim()).ok_or_else(|| invalid(&text))?),
+        "string" => PlistValue::String(text),
+        _ => return Err(PlistError::UnexpectedElement(name.to_string())),
+    };
+    Ok(value)
+}
+
+/// Integers are decimal or, with a `0x` prefix, hexadecimal.
+fn parse_integer(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let magnitude = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i128>().ok()?,
+    };
+    i64::try_from(if negative { -magnitude } else { magnitude }).ok()
+}
+
+fn parse_real(text: &str) -> Option<f64> {
+    match text.to_ascii_lowercase().as_str() {
+        "nan" => Some(f64::NAN),
+        "inf" | "+inf" | "infinity" | "+infinity" => Some(f64::INFINITY),
+        "-inf" | "-infinity" => Some(f64::NEG_INFINITY),
+        _ => text.parse().ok(),
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = chunk.iter().enumerate().fold(0u32, |buffer, (i, b)| buffer | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(BASE64_ALPHABET[(buffer >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Write a property list document in the format of Apple's tools, indented with tabs.
+pub fn to_xml(value: &PlistValue) -> String {
+    let mut writer = XmlWriter::unchecked(Vec::new());
+    // writing to a Vec cannot fail
+    write_plist(value, &mut writer).unwrap();
+    String::from_utf8(writer.into_inner()).unwrap()
+}
+
+fn write_plist(value: &PlistValue, writer: &mut XmlWriter<Vec<u8>>) -> std::io::Result<()> {
+    writer.markup_raw("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+    writer.markup_raw(DOCTYPE)?;
+    writer.text_raw("\n")?;
+    writer.start_element("plist")?;
+    writer.attribute("version", "1.0")?;
+    writer.text_raw("\n")?;
+    write_value(value, writer, 0)?;
+    writer.text_raw("\n")?;
+    writer.end_element()?;
+    writer.text_raw("\n")
+}
+
+fn write_value(value: &PlistValue, writer: &mut XmlWriter<Vec<u8>>, depth: usize) -> std::io::Result<()> {
+    let leaf = |writer: &mut XmlWriter<Vec<u8>>, name: &str, text: &str| {
+        writer.start_element(name)?;
+        writer.text(text)?;
+        writer.end_element()
+    };
+    let indent = |writer: &mut XmlWriter<Vec<u8>>, depth: usize| writer.text_raw(&format!("\n{}", "\t".repeat(depth)));
+    match value {
+        PlistValue::Array(values) => {
+            writer.start_element("array")?;
+            for value in values {
+                indent(writer, depth + 1)?;
+                write_value(value, writer, depth + 1)?;
+            }
+            if !values.is_empty() {
+                indent(writer, depth)?;
+            }
+            writer.end_element()
+        }
+        PlistValue::Dictionary(entries) => {
+            writer.start_element("dict")?;
+            for (key, value) in entries {
+                indent(writer, depth + 1)?;
+                leaf(writer, "key", key)?;
+                indent(writer, depth + 1)?;
+                write_value(value, writer, depth + 1)?;
+            }
+            if !entries.is_empty() {
+                indent(writer, depth)?;
+            }
+            writer.end_element()
+        }
+        PlistValue::Boolean(b) => {
+            writer.start_element(if *b { "true" } else { "false" })?;
+            writer.end_element()
+        }
+        PlistValue::Data(bytes) => leaf(writer, "data", &encode_base64(bytes)),
+        PlistValue::Date(date) => leaf(writer, "date", date),
+        PlistValue::Integer(i) => leaf(writer, "integer", &i.to_string()),
+        PlistValue::Real(r) if r.is_nan() => leaf(writer, "real", "nan"),
+        PlistValue::Real(r) if r.is_infinite() => leaf(writer, "real", if *r > 0.0 { "+infinity" } else { "-infinity" }),
+        PlistValue::Real(r) => leaf(writer, "real", &r.to_string()),
+        PlistValue::String(s) => leaf(writer, "string", s),
+    }
+}
@@ -0,0 +1,103 @@
+//! Dialect profiles for [tag soup](crate::options::Strictness::TagSoup) parsing of XHTML, SVG and similar markup.
+//!
+//! A profile describes the elements that are void, the start tags that imply end tags,
+//! whether names are case-sensitive and which entities are known.
+//!
+//! ```
+//! use jaxp_rust::document::Document;
+//! use jaxp_rust::profile::Profile;
+//!
+//! let svg = "<svg><linearGradient id=g><stop offset=0></linearGradient><LinearGradient></svg>";
+//! let doc = Document::parse_with_options(svg, &Profile::svg().parse_options()).unwrap();
+//! assert_eq!(2, doc.root_element().child_elements().count());
+//! ```
+
+use crate::entities::EntityTable;
+use crate::options::{ParseOptions, Strictness};
+
+/// Elements that never have content, see the [HTML standard](https://html.spec.whatwg.org/multipage/syntax.html#void-elements)
+const HTML_VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose start tag implies the end of an open `p`
+const CLOSES_PARAGRAPH: &[&str] = &[
+    "address", "blockquote", "div", "dl", "fieldset", "form", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "ol", "p",
+    "pre", "section", "table", "ul",
+];
+
+/// The open elements of HTML that are ended by the start tags of other elements
+const HTML_IMPLIED_END_TAGS: &[(&str, &[&str])] = &[
+    ("p", CLOSES_PARAGRAPH),
+    ("li", &["li"]),
+    ("option", &["option", "optgroup"]),
+    ("dt", &["dt", "dd"]),
+    ("dd", &["dt", "dd"]),
+    ("td", &["td", "th", "tr"]),
+    ("th", &["td", "th", "tr"]),
+    ("tr", &["tr"]),
+];
+
+/// The rules of a markup dialect, see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    /// Elements that need no end tag and never have content
+    pub void_elements: Vec<String>,
+    /// Open elements with the start tags that imply their end, e.g. a `p` is ended by a `div`
+    pub implied_end_tags: Vec<(String, Vec<String>)>,
+    /// Whether end tags only match start tags of the same case, element names of the rules match in any case otherwise
+    pub case_sensitive: bool,
+    /// Named entities that references may refer to besides the predefined ones
+    pub entities: EntityTable,
+}
+
+impl Profile {
+    /// HTML-flavored XHTML: the void elements and implied end tags of HTML, names in any case and the entities of [EntityTable::html].
+    /// This is what tag soup parsing uses without a profile, except for the entities.
+    pub fn xhtml() -> Self {
+        Profile { entities: EntityTable::html(), ..Self::html_structure() }
+    }
+
+    /// SVG: no void elements or implied end tags, case-sensitive names like `linearGradient` and only the predefined entities.
+    pub fn svg() -> Self {
+        Profile { void_elements: vec![], implied_end_tags: vec![], case_sensitive: true, entities: EntityTable::new() }
+    }
+
+    /// The structure of XHTML without its entities.
+    pub(crate) fn html_structure() -> Self {
+        Profile {
+            void_elements: HTML_VOID_ELEMENTS.iter().map(|s| s.to_string()).collect(),
+            implied_end_tags: HTML_IMPLIED_END_TAGS
+                .iter()
+                .map(|(open, new)| (open.to_string(), new.iter().map(|s| s.to_string()).collect()))
+                .collect(),
+            case_sensitive: false,
+            entities: EntityTable::new(),
+        }
+    }
+
+    /// Options for tag soup parsing with this profile and its entities.
+    pub fn parse_options(self) -> ParseOptions {
+        ParseOptions { strictness: Strictness::TagSoup, entities: self.entities.clone(), profile: Some(self), ..Default::default() }
+    }
+
+    /// Whether two element names are the same in this dialect.
+    pub fn names_match(&self, a: &str, b: &str) -> bool {
+        if self.case_sensitive {
+            a == b
+        } else {
+            a.eq_ignore_ascii_case(b)
+        }
+    }
+
+    pub fn is_void(&self, name: &str) -> bool {
+        self.void_elements.iter().any(|v| self.names_match(v, name))
+    }
+
+    /// Whether the start tag of `new` implies the end tag of the open element `open`.
+    pub fn implies_end_of(&self, open: &str, new: &str) -> bool {
+        self.implied_end_tags
+            .iter()
+            .any(|(o, ends)| self.names_match(o, open) && ends.iter().any(|n| self.names_match(n, new)))
+    }
+}
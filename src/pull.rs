@@ -0,0 +1,288 @@
+//! A pull parser that reports a document as a sequence of events, e.g. for hand-written deserializers.
+//!
+//! The prolog is tokenized when the parser is created, the content one construct at a time as the events are read:
+//! besides the input, memory grows with the depth of the document and the size of the largest tag, not the size of the document.
+//! Errors are reported when they are reached, after the events before them.
+//! Start tags are checked like [Document](crate::document::Document) checks them, including the attribute
+//! and namespace constraints of [Strictness::Conformant].
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use crate::chariter::CharIter;
+use crate::document::{Attribute, Attributes};
+use crate::error::XmlError;
+use crate::error::XmlError::{DisallowedConstruct, ReservedNamespace, UnexpectedXmlToken};
+use crate::namespace::{declared_prefix, split_qname, XML_NAMESPACE, XMLNS_NAMESPACE};
+use crate::options::{CommentPolicy, ParseOptions, ProcessingInstructionPolicy, Strictness};
+use crate::textrange::TextRange;
+use crate::token::XmlToken;
+use crate::tokenize::XmlTokenizer;
+use crate::xmlchar::XmlByte;
+
+/// Content of a document, in document order. The prolog is not reported.
+/// Text and attribute values are reported as they appear in the markup, i.e. with references unresolved.
+#[derive(Debug, PartialEq)]
+pub enum Event<'a> {
+    /// `depth` is the number of enclosing elements, 0 for the root element
+    StartElement { name: &'a str, attributes: Attributes<'a>, depth: usize },
+    /// Also reported for empty-element tags, right after their start
+    EndElement { name: &'a str, depth: usize },
+    Text(&'a str),
+    CdataSection(&'a str),
+    Comment(&'a str),
+    ProcessingInstruction { target: &'a str, opt_value: Option<&'a str> },
+}
+
+pub struct PullParser<'a> {
+    ci: CharIter<'a>,
+    tokenizer: XmlTokenizer,
+    /// The tokens that are not read yet, those of the prolog and then of the latest construct of the content
+    tokens: VecDeque<XmlToken<'a>>,
+    /// Reused to tokenize the next construct
+    buffer: Vec<XmlToken<'a>>,
+    comments: CommentPolicy,
+    processing_instructions: ProcessingInstructionPolicy,
+    open_ranges: Vec<TextRange<'a>>,
+    /// Whether the namespace constraints are checked, as [Strictness::Conformant] documents do
+    is_conformant: bool,
+    /// The namespace declarations in scope with the depth of their element, the innermost last
+    namespaces: Vec<(usize, &'a str, &'a str)>,
+    has_root: bool,
+    has_failed: bool,
+    /// See [PullParser::set_max_chunk_len]
+    opt_max_chunk_len: Option<usize>,
+    /// The rest of a text or CDATA section that is delivered in chunks, and whether it is a CDATA section
+    opt_pending_chunks: Option<(&'a str, bool)>,
+}
+
+impl<'a> PullParser<'a> {
+    pub fn new(xml: &'a str) -> Result<Self, XmlError> {
+        Self::with_options(xml, &ParseOptions::default())
+    }
+
+    pub fn with_options(xml: &'a str, options: &ParseOptions) -> Result<Self, XmlError> {
+        let mut tokenizer = XmlTokenizer::with_options(options);
+        let mut ci = CharIter { pos: 0, text: xml };
+        let mut buffer = vec![];
+        tokenizer.tokenize_start(&mut ci, &mut buffer)?;
+        Ok(PullParser {
+            ci,
+            tokenizer,
+            tokens: buffer.drain(..).collect(),
+            buffer,
+            comments: options.comments,
+            processing_instructions: options.processing_instructions,
+            open_ranges: vec![],
+            is_conformant: options.strictness == Strictness::Conformant,
+            namespaces: vec![],
+            has_root: false,
+            has_failed: false,
+            opt_max_chunk_len: None,
+            opt_pending_chunks: None,
+        })
+    }
+
+    /// Deliver text and CDATA sections that are longer than `max_len` bytes as consecutive events of at most that length,
+    /// e.g. for large embedded binary data. Chunks of text do not split references, a reference or character
+    /// that is longer than `max_len` is a chunk of its own. With `None`, the default, every text is a single event.
+    pub fn set_max_chunk_len(&mut self, opt_max_len: Option<usize>) {
+        self.opt_max_chunk_len = opt_max_len;
+    }
+
+    /// The number of open elements, the depth of the next start tag.
+    pub fn depth(&self) -> usize {
+        self.open_ranges.len()
+    }
+
+    /// The next event, or `None` at the end of the document and after an error.
+    pub fn next_event(&mut self) -> Result<Option<Event<'a>>, XmlError> {
+        if self.has_failed {
+            return Ok(None);
+        }
+        let result = self.read_event();
+        self.has_failed = result.is_err();
+        result
+    }
+
+    /// Skip the rest of the innermost open element, up to and including its end tag.
+    /// After a [StartElement](Event::StartElement), this skips the element with all of its content.
+    /// Outside of the root element nothing is skipped.
+    pub fn skip_to_end_of_current(&mut self) -> Result<(), XmlError> {
+        let depth = self.depth();
+        while self.depth() >= depth && depth > 0 {
+            if self.next_event()?.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_event(&mut self) -> Result<Option<Event<'a>>, XmlError> {
+        if let Some((rest, is_cdata)) = self.opt_pending_chunks.take() {
+            return Ok(Some(self.chunk(rest, is_cdata)));
+        }
+        while let Some(token) = self.next_token()? {
+            let event = match token {
+                XmlToken::StartTag(name_range) => {
+                    // a document has exactly one root element
+                    if self.open_ranges.is_empty() && self.has_root {
+                        return Err(UnexpectedXmlToken { pos: self.ci.error_pos_of(name_range.start) });
+                    }
+                    self.has_root = true;
+                    let depth = self.depth();
+                    let mut attributes = Attributes::new();
+                    let mut attribute_ranges = vec![];
+                    // the attributes are tokenized with their start tag
+                    while let Some(&XmlToken::Attribute { name_range, value_range }) = self.tokens.front() {
+                        attributes.push(Attribute { name: Cow::Borrowed(name_range.slice), value: Cow::Borrowed(value_range.slice) });
+                        attribute_ranges.push(name_range);
+                        if let Some(prefix) = declared_prefix(name_range.slice) {
+                            self.namespaces.push((depth, prefix, value_range.slice));
+                        }
+                        self.tokens.pop_front();
+                    }
+                    if self.is_conformant {
+                        self.check_start_tag(name_range, &attributes, &attribute_ranges)?;
+                    }
+                    self.open_ranges.push(name_range);
+                    Event::StartElement { name: name_range.slice, attributes, depth }
+                }
+                XmlToken::EndTag(name_range) => {
+                    let start_range = match self.open_ranges.pop() {
+                        Some(start_range) => start_range,
+                        None => return Err(UnexpectedXmlToken { pos: self.ci.error_pos_of(name_range.start) }),
+                    };
+                    if !start_range.name_eq(name_range.slice) {
+                        return Err(self.ci.non_matching_tags(start_range, name_range, &self.open_ranges));
+                    }
+                    let depth = self.depth();
+                    while self.namespaces.last().is_some_and(|&(declared_at, _, _)| declared_at >= depth) {
+                        self.namespaces.pop();
+                    }
+                    Event::EndElement { name: name_range.slice, depth }
+                }
+                XmlToken::Text(range) if self.open_ranges.is_empty() => {
+                    // only spaces are allowed outside of the root element and they are not reported
+                    if !range.slice.bytes().all(|b| b.is_xml_whitespace()) {
+                        return Err(UnexpectedXmlToken { pos: self.ci.error_pos_of(range.start) });
+                    }
+                    continue;
+                }
+                XmlToken::Text(range) => self.chunk(range.slice, false),
+                XmlToken::CdataSection(range) => {
+                    if self.open_ranges.is_empty() {
+                        return Err(UnexpectedXmlToken { pos: self.ci.error_pos_of(range.start) });
+                    }
+                    self.chunk(range.slice, true)
+                }
+                XmlToken::Comment(range) => {
+                    if self.comments == CommentPolicy::Deny {
+                        return Err(DisallowedConstruct { pos: self.ci.error_pos_of(range.start), construct: "comment".to_string() });
+                    }
+                    Event::Comment(range.slice)
+                }
+                XmlToken::ProcessingInstruction { target_range, opt_value_range } => {
+                    if self.processing_instructions == ProcessingInstructionPolicy::Deny {
+                        return Err(DisallowedConstruct {
+                            pos: self.ci.error_pos_of(target_range.start),
+                            construct: "processing instruction".to_string(),
+                        });
+                    }
+                    Event::ProcessingInstruction { target: target_range.slice, opt_value: opt_value_range.map(|r| r.slice) }
+                }
+                // attributes are read with their start tag, prolog tokens are not reported
+                XmlToken::Attribute { .. }
+                | XmlToken::XmlDeclaration { .. }
+                | XmlToken::DocTypeDeclaration { .. }
+                | XmlToken::ParameterEntityReference(_) => continue,
+            };
+            return Ok(Some(event));
+        }
+        if let Some(&start_range) = self.open_ranges.last() {
+            return Err(self.ci.unclosed_element(start_range));
+        }
+        if !self.has_root {
+            return Err(self.ci.unexpected_end_of_file().in_construct("document"));
+        }
+        Ok(None)
+    }
+
+    /// The namespace constraints on a start tag that [Document](crate::document::Document) checks as well.
+    fn check_start_tag(&self, name_range: TextRange, attributes: &Attributes, attribute_ranges: &[TextRange]) -> Result<(), XmlError> {
+        if split_qname(name_range.slice).0 == Some("xmlns") {
+            return Err(ReservedNamespace {
+                pos: self.ci.error_pos_of(name_range.start),
+                reason: "elements must not have the xmlns prefix".to_string(),
+            });
+        }
+        attributes.check_namespace_declarations(attribute_ranges, &self.ci)?;
+        attributes.check_unique(attribute_ranges, &self.ci, |prefix| self.resolve_prefix(prefix))?;
+        attributes.check_qualified_names(name_range, attribute_ranges, &self.ci, |prefix| self.resolve_prefix(prefix))
+    }
+
+    /// The namespace bound to a prefix in the scope of the latest start tag.
+    fn resolve_prefix(&self, prefix: &str) -> Option<&'a str> {
+        match prefix {
+            "xml" => Some(XML_NAMESPACE),
+            "xmlns" => Some(XMLNS_NAMESPACE),
+            // an empty value undeclares the default namespace
+            _ => self.namespaces.iter().rev().find(|&&(_, declared, _)| declared == prefix).map(|&(_, _, uri)| uri).filter(|uri| !uri.is_empty()),
+        }
+    }
+
+    /// The next token, the tokens of the next construct are read once all earlier ones are.
+    fn next_token(&mut self) -> Result<Option<XmlToken<'a>>, XmlError> {
+        while self.tokens.is_empty() {
+            let has_next = self.tokenizer.tokenize_content_step(&mut self.ci, &mut self.buffer).map_err(|e| e.in_construct("document"))?;
+            if !has_next {
+                return Ok(None);
+            }
+            self.tokens.extend(self.buffer.drain(..));
+        }
+        Ok(self.tokens.pop_front())
+    }
+
+    /// The event of the first chunk of a text or CDATA section, the rest is pending.
+    fn chunk(&mut self, text: &'a str, is_cdata: bool) -> Event<'a> {
+        let len = match self.opt_max_chunk_len {
+            Some(max_len) if text.len() > max_len => chunk_len(text, max_len, is_cdata),
+            _ => text.len(),
+        };
+        let (chunk, rest) = text.split_at(len);
+        if !rest.is_empty() {
+            self.opt_pending_chunks = Some((rest, is_cdata));
+        }
+        if is_cdata { Event::CdataSection(chunk) } else { Event::Text(chunk) }
+    }
+}
+
+/// The length of the first chunk of a text that is longer than `max_len`: at most `max_len`, at a char boundary
+/// and, in text, not inside of a reference, unless the first character or reference alone is longer.
+fn chunk_len(text: &str, max_len: usize, is_cdata: bool) -> usize {
+    let mut len = max_len;
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    if !is_cdata {
+        if let Some(reference_start) = text[..len].rfind('&').filter(|&i| !text[i..len].contains(';')) {
+            len = reference_start;
+        }
+    }
+    if len > 0 {
+        return len;
+    }
+    // a reference or character alone
+    match text.strip_prefix('&') {
+        Some(reference) if !is_cdata => reference.find(';').map_or(text.len(), |i| i + 2),
+        _ => text.chars().next().map_or(0, char::len_utf8),
+    }
+}
+
+impl<'a> Iterator for PullParser<'a> {
+    type Item = Result<Event<'a>, XmlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
@@ -0,0 +1,121 @@
+//! A small query DSL in the spirit of XQuery's FLWOR expressions, with Rust closures instead of a query language.
+//!
+//! A query iterates over the nodes that a [path](crate::xpath) selects, keeps those that pass its conditions,
+//! orders them and maps each one to a result.
+//!
+//! ```
+//! use jaxp_rust::document::Document;
+//! use jaxp_rust::query::for_each;
+//!
+//! let doc = Document::parse("<orders><order id='1' status='open'/><order id='2' status='closed'/><order id='3' status='open'/></orders>").unwrap();
+//! let open: Vec<&str> = for_each("//order")
+//!     .where_attr("status", "open")
+//!     .order_by(|order| std::cmp::Reverse(order.attribute("id")))
+//!     .select(|order| order.attribute("id").unwrap())
+//!     .run(doc.root())
+//!     .unwrap()
+//!     .collect();
+//! assert_eq!(vec!["3", "1"], open);
+//! ```
+
+use std::cmp::Ordering;
+use std::vec;
+
+use crate::document::Node;
+use crate::refs::decode_entities;
+use crate::xpath::{Item, XPath, XPathError};
+
+type Condition<'a, 'input> = dyn Fn(Node<'a, 'input>) -> bool + 'a;
+type Comparator<'a, 'input> = dyn Fn(&Node<'a, 'input>, &Node<'a, 'input>) -> Ordering + 'a;
+
+/// Start a query over the nodes that a path selects, e.g. `//order`.
+/// Attributes that the path selects are skipped, conditions and the selection read them from their elements.
+pub fn for_each<'a, 'input: 'a>(path: &str) -> Query<'a, 'input> {
+    Query { path: XPath::compile(path), conditions: vec![], opt_order: None }
+}
+
+/// A query without a selection yet, see [for_each].
+///
+/// A path that does not compile is reported when the query is run.
+pub struct Query<'a, 'input: 'a> {
+    path: Result<XPath, XPathError>,
+    conditions: Vec<Box<Condition<'a, 'input>>>,
+    opt_order: Option<Box<Comparator<'a, 'input>>>,
+}
+
+impl<'a, 'input: 'a> Query<'a, 'input> {
+    /// Keep the nodes that pass a condition.
+    pub fn filter(mut self, condition: impl Fn(Node<'a, 'input>) -> bool + 'a) -> Self {
+        self.conditions.push(Box::new(condition));
+        self
+    }
+
+    /// Keep the elements with an attribute of a value, references in the attribute value are resolved.
+    pub fn where_attr(self, name: &str, value: &str) -> Self {
+        let (name, value) = (name.to_string(), value.to_string());
+        self.filter(move |node| node.attribute(&name).is_some_and(|v| decode_entities(v) == value))
+    }
+
+    /// Keep the elements with a child element whose text, including the text of its descendants, is a value.
+    pub fn where_child_text(self, name: &str, text: &str) -> Self {
+        let (name, text) = (name.to_string(), text.to_string());
+        self.filter(move |node| node.children_named(&name).any(|child| Item::Node(child).string_value() == text))
+    }
+
+    /// Order the nodes by a key, nodes with equal keys stay in document order.
+    /// A later call replaces the order of an earlier one.
+    pub fn order_by<K: Ord>(mut self, key: impl Fn(Node<'a, 'input>) -> K + 'a) -> Self {
+        self.opt_order = Some(Box::new(move |a, b| key(*a).cmp(&key(*b))));
+        self
+    }
+
+    /// Map every node to a result.
+    pub fn select<T, F: FnMut(Node<'a, 'input>) -> T>(self, select: F) -> Selection<'a, 'input, F> {
+        Selection { query: self, select }
+    }
+}
+
+/// A complete query, which is [run](Selection::run) from a context node.
+pub struct Selection<'a, 'input: 'a, F> {
+    query: Query<'a, 'input>,
+    select: F,
+}
+
+impl<'a, 'input: 'a, T, F: FnMut(Node<'a, 'input>) -> T> Selection<'a, 'input, F> {
+    /// Run the query, with a relative path starting from the context node.
+    /// The nodes are selected and filtered right away, the results are mapped as they are iterated.
+    pub fn run(self, context: Node<'a, 'input>) -> Result<Results<'a, 'input, F>, XPathError> {
+        let Query { path, conditions, opt_order } = self.query;
+        let mut nodes: Vec<Node<'a, 'input>> = path?
+            .select(context)?
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Node(node) => Some(node),
+                Item::Attribute(..) => None,
+            })
+            .filter(|node| conditions.iter().all(|condition| condition(*node)))
+            .collect();
+        if let Some(order) = opt_order {
+            nodes.sort_by(|a, b| order(a, b));
+        }
+        Ok(Results { nodes: nodes.into_iter(), select: self.select })
+    }
+}
+
+/// The results of a query, in the order of the query.
+pub struct Results<'a, 'input: 'a, F> {
+    nodes: vec::IntoIter<Node<'a, 'input>>,
+    select: F,
+}
+
+impl<'a, 'input: 'a, T, F: FnMut(Node<'a, 'input>) -> T> Iterator for Results<'a, 'input, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.nodes.next().map(&mut self.select)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
@@ -0,0 +1,112 @@
+use std::borrow::Cow;
+use std::io::Write;
+use std::iter::Peekable;
+
+use quick_xml::events::{BytesCData, BytesDecl, BytesEnd, BytesPI, BytesStart, BytesText, Event};
+use quick_xml::name::QName;
+use quick_xml::Writer;
+
+use crate::token::XmlToken;
+use crate::token::XmlToken::*;
+
+/// Adapter that maps jaxp tokens to [quick-xml](https://docs.rs/quick-xml) events.
+///
+/// A start tag and its attribute tokens are merged into a single `Start` event,
+/// empty element tags are reported as `Empty` events. Event contents are borrowed
+/// from the input wherever quick-xml allows it.
+///
+/// The reverse direction is not offered: tokens refer to positions in the parsed text,
+/// which quick-xml events do not carry.
+pub struct QuickXmlEvents<'a, I: Iterator<Item=XmlToken<'a>>> {
+    tokens: Peekable<I>,
+    eof_emitted: bool,
+}
+
+/// Map a token sequence, e.g. the result of [XmlTokenizer::tokenize](crate::tokenize::XmlTokenizer::tokenize), to quick-xml events.
+/// The last event is always `Event::Eof`.
+pub fn to_events<'a, I: IntoIterator<Item=XmlToken<'a>>>(tokens: I) -> QuickXmlEvents<'a, I::IntoIter> {
+    QuickXmlEvents { tokens: tokens.into_iter().peekable(), eof_emitted: false }
+}
+
+/// Write a token sequence with a quick-xml writer.
+pub fn write_tokens<'a, W: Write, I: IntoIterator<Item=XmlToken<'a>>>(tokens: I, writer: &mut Writer<W>) -> std::io::Result<()> {
+    for event in to_events(tokens) {
+        if let Event::Eof = event {
+            break;
+        }
+        writer.write_event(event)?;
+    }
+    Ok(())
+}
+
+impl<'a, I: Iterator<Item=XmlToken<'a>>> QuickXmlEvents<'a, I> {
+    fn start_event(&mut self, name: &'a str, name_start: usize) -> Event<'a> {
+        let mut start = BytesStart::new(name);
+        while let Some(Attribute { .. }) = self.tokens.peek() {
+            if let Some(Attribute { name_range, value_range }) = self.tokens.next() {
+                // Attribute values are kept escaped, just like quick-xml expects them
+                start.push_attribute(quick_xml::events::attributes::Attribute {
+                    key: QName(name_range.slice.as_bytes()),
+                    value: Cow::Borrowed(value_range.slice.as_bytes()),
+                });
+            }
+        }
+        // The tokenizer creates the end tag of an empty element tag from the very same name range
+        match self.tokens.peek() {
+            Some(EndTag(end_range)) if end_range.start == name_start => {
+                self.tokens.next();
+                Event::Empty(start)
+            }
+            _ => Event::Start(start)
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item=XmlToken<'a>>> Iterator for QuickXmlEvents<'a, I> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let token = match self.tokens.next() {
+                Some(token) => token,
+                None => {
+                    if self.eof_emitted {
+                        return None;
+                    }
+                    self.eof_emitted = true;
+                    return Some(Event::Eof);
+                }
+            };
+            let event = match token {
+                StartTag(name_range) => self.start_event(name_range.slice, name_range.start),
+                EndTag(name_range) => Event::End(BytesEnd::new(name_range.slice)),
+                Text(range) => Event::Text(BytesText::from_escaped(range.slice)),
+                CdataSection(range) => Event::CData(BytesCData::new(range.slice)),
+                Comment(range) => Event::Comment(BytesText::from_escaped(range.slice)),
+                ProcessingInstruction { target_range, opt_value_range } => Event::PI(match opt_value_range {
+                    Some(value_range) => BytesPI::new(format!("{} {}", target_range.slice, value_range.slice)),
+                    None => BytesPI::new(target_range.slice),
+                }),
+                XmlDeclaration { version_range, opt_encoding_range, opt_standalone_range } => Event::Decl(BytesDecl::new(
+                    version_range.slice,
+                    opt_encoding_range.map(|r| r.slice),
+                    opt_standalone_range.map(|r| r.slice),
+                )),
+                DocTypeDeclaration { name_range, opt_system_entity_range, opt_public_entity_range, .. } => {
+                    let content = match (opt_public_entity_range, opt_system_entity_range) {
+                        (Some(public_range), Some(system_range)) =>
+                            format!("{} PUBLIC \"{}\" \"{}\"", name_range.slice, public_range.slice, system_range.slice),
+                        (None, Some(system_range)) =>
+                            format!("{} SYSTEM \"{}\"", name_range.slice, system_range.slice),
+                        _ => name_range.slice.to_string(),
+                    };
+                    Event::DocType(BytesText::from_escaped(content))
+                }
+                // attributes are consumed together with their start tag,
+                // parameter entity references have no quick-xml counterpart
+                Attribute { .. } | ParameterEntityReference(_) => continue,
+            };
+            return Some(event);
+        }
+    }
+}
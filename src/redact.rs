@@ -0,0 +1,90 @@
+//! Masking of sensitive text and attribute values, e.g. to scrub XML payloads before they are logged.
+//!
+//! The document is only tokenized, not built into a tree. Everything but the masked values is copied
+//! from the input as it is, so the output differs from the input in exactly the redacted places.
+//! The input is tokenized as a whole.
+
+use crate::error::XmlError;
+use crate::escape::{escape_attribute, escape_text};
+use crate::token::XmlToken::*;
+use crate::tokenize::XmlTokenizer;
+use crate::xmlchar::XmlByte;
+
+/// What to mask.
+///
+/// Elements are selected by name, e.g. `password`, or by an absolute path of names, e.g. `/order/card/number`.
+/// Attributes are selected by name, e.g. `token`, or by an element selector and a name, e.g. `user@token` or `/config/db@password`.
+#[derive(Clone, Debug)]
+pub struct RedactionRules {
+    /// Elements whose text is masked, including the text of their descendants
+    pub elements: Vec<String>,
+    /// Attributes whose values are masked
+    pub attributes: Vec<String>,
+    /// Replacement of every masked text and attribute value, escaped where necessary
+    pub mask: String,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        RedactionRules { elements: vec![], attributes: vec![], mask: "***".to_string() }
+    }
+}
+
+/// Whether an element selector matches the innermost element of a path of names.
+fn matches_element(selector: &str, path: &[&str]) -> bool {
+    match selector.strip_prefix('/') {
+        Some(absolute) => absolute.split('/').eq(path.iter().copied()),
+        None => path.last() == Some(&selector),
+    }
+}
+
+fn matches_attribute(selector: &str, path: &[&str], name: &str) -> bool {
+    match selector.rsplit_once('@') {
+        Some((element, attribute)) => attribute == name && matches_element(element, path),
+        None => selector == name,
+    }
+}
+
+/// Mask the selected text and attribute values of a document.
+/// Text that consists of spaces only is kept, so the layout of the document stays intact.
+pub fn redact(xml: &str, rules: &RedactionRules) -> Result<String, XmlError> {
+    let tokens = XmlTokenizer::default().tokenize(xml)?;
+    let mut redacted = String::with_capacity(xml.len());
+    // the end of the input that has been copied
+    let mut copied = 0;
+    let mut replace = |redacted: &mut String, start: usize, end: usize, mask: &str| {
+        redacted.push_str(&xml[copied..start]);
+        redacted.push_str(mask);
+        copied = end;
+    };
+    let text_mask = escape_text(&rules.mask);
+    let mut path = vec![];
+    // the depth of the outermost open element whose text is masked
+    let mut opt_masked_depth = None;
+    for token in &tokens {
+        match token {
+            StartTag(name_range) => {
+                path.push(name_range.slice);
+                if opt_masked_depth.is_none() && rules.elements.iter().any(|s| matches_element(s, &path)) {
+                    opt_masked_depth = Some(path.len());
+                }
+            }
+            EndTag(_) => {
+                if opt_masked_depth == Some(path.len()) {
+                    opt_masked_depth = None;
+                }
+                path.pop();
+            }
+            Attribute { name_range, value_range } if rules.attributes.iter().any(|s| matches_attribute(s, &path, name_range.slice)) => {
+                let quote = char::from(xml.as_bytes()[value_range.start - 1]);
+                replace(&mut redacted, value_range.start, value_range.end, &escape_attribute(&rules.mask, quote));
+            }
+            Text(range) | CdataSection(range) if opt_masked_depth.is_some() && !range.slice.bytes().all(|b| b.is_xml_whitespace()) => {
+                replace(&mut redacted, range.start, range.end, &text_mask);
+            }
+            _ => (),
+        }
+    }
+    redacted.push_str(&xml[copied..]);
+    Ok(redacted)
+}
@@ -0,0 +1,151 @@
+//! Decoding of character and entity references.
+
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+
+use crate::entities::EntityTable;
+use crate::xmlchar::XmlChar;
+
+/// Why a character reference cannot be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RefError {
+    /// Not of the form `&#digits;` or `&#xhexdigits;`
+    Malformed,
+    /// The code point is a surrogate or beyond U+10FFFF
+    OutOfRange,
+    /// The character is not a [Char](https://www.w3.org/TR/xml/#NT-Char), e.g. U+0000
+    NotAnXmlChar(char),
+    /// The character is an XML Char, but the [CharRefPolicy] does not allow references to it
+    Disallowed(char),
+}
+
+impl Display for RefError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            RefError::Malformed => write!(f, "malformed character reference"),
+            RefError::OutOfRange => write!(f, "code point out of range"),
+            RefError::NotAnXmlChar(c) => write!(f, "U+{:04X} is not an XML character", *c as u32),
+            RefError::Disallowed(c) => write!(f, "U+{:04X} is not allowed in character references", *c as u32),
+        }
+    }
+}
+
+impl std::error::Error for RefError {}
+
+/// Which characters character references may refer to. Characters written literally are always
+/// [\[2\] Char](https://www.w3.org/TR/xml/#NT-Char)s, whatever the policy.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum CharRefPolicy {
+    /// [\[2\] Char](https://www.w3.org/TR/xml/#NT-Char) of XML 1.0, fifth edition
+    #[default]
+    Xml10,
+    /// [\[2\] Char](https://www.w3.org/TR/xml11/#NT-Char) of XML 1.1, which includes the control characters
+    /// U+0001 to U+001F, that are [restricted](https://www.w3.org/TR/xml11/#NT-RestrictedChar) to references
+    Xml11,
+    /// The XML 1.0 Chars of the Basic Multilingual Plane, as accepted by parsers that
+    /// decode references into a single UTF-16 code unit
+    Bmp,
+    /// Exactly the code points for which the function returns true, e.g. to match the acceptance of another parser.
+    /// Surrogates and code points beyond U+10FFFF are never allowed.
+    Custom(fn(char) -> bool),
+}
+
+impl CharRefPolicy {
+    /// Whether a reference may refer to the character.
+    pub fn allows(&self, c: char) -> bool {
+        match self {
+            CharRefPolicy::Xml10 => c.is_xml_char(),
+            CharRefPolicy::Xml11 => c != '\0' && c != '\u{FFFE}' && c != '\u{FFFF}',
+            CharRefPolicy::Bmp => c.is_xml_char() && (c as u32) < 0x10000,
+            CharRefPolicy::Custom(allows) => allows(c),
+        }
+    }
+}
+
+/// Decode a character reference like `&#233;` or `&#xE9;`.
+///
+/// [\[66\] CharRef](https://www.w3.org/TR/xml/#NT-CharRef)
+pub fn decode_char_ref(reference: &str) -> Result<char, RefError> {
+    decode_char_ref_with(reference, &CharRefPolicy::Xml10)
+}
+
+/// Like [decode_char_ref], allowing the characters of a policy.
+pub fn decode_char_ref_with(reference: &str, policy: &CharRefPolicy) -> Result<char, RefError> {
+    let body = reference
+        .strip_prefix("&#")
+        .and_then(|r| r.strip_suffix(';'))
+        .ok_or(RefError::Malformed)?;
+    let (digits, radix) = match body.strip_prefix('x') {
+        Some(hex) => (hex, 16),
+        None => (body, 10),
+    };
+    // from_str_radix would accept a leading sign
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(RefError::Malformed);
+    }
+    let c = u32::from_str_radix(digits, radix)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or(RefError::OutOfRange)?;
+    match (policy.allows(c), c.is_xml_char()) {
+        (true, _) => Ok(c),
+        (false, true) => Err(RefError::Disallowed(c)),
+        (false, false) => Err(RefError::NotAnXmlChar(c)),
+    }
+}
+
+/// Resolve the predefined entities and character references in text or an attribute value,
+/// as stored in a [Document](crate::document::Document).
+/// References that cannot be resolved are kept as they are, as are references to characters
+/// that only a [CharRefPolicy] other than the default allows.
+pub fn decode_entities(text: &str) -> Cow<'_, str> {
+    decode_entities_with(text, &EntityTable::default())
+}
+
+/// Like [decode_entities], additionally resolving the entities of a table.
+pub fn decode_entities_with<'t>(text: &'t str, entities: &EntityTable) -> Cow<'t, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let reference = &rest[..=end];
+        let resolved = match &reference[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "apos" => Some('\''),
+            "quot" => Some('"'),
+            name if name.starts_with('#') => decode_char_ref(reference).ok(),
+            name => match entities.get(name) {
+                Some(replacement) => {
+                    decoded.push_str(replacement);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+                None => None,
+            },
+        };
+        match resolved {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            // not a reference, the next one may start before the semicolon
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
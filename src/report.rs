@@ -0,0 +1,266 @@
+//! A unified report of the issues found in a document, for tools that check documents in bulk, e.g. in CI.
+//!
+//! A [ValidationReport] collects [Diagnostic]s from well-formedness checks, DTD and schema validation and custom rules,
+//! each with a severity, a stable code, a span in the input and a message, and serializes them to JSON.
+//!
+//! ```
+//! use jaxp_rust::options::ParseOptions;
+//! use jaxp_rust::report::{Severity, ValidationReport};
+//!
+//! let report = ValidationReport::check_well_formedness("<a b=\"\"><xml-c/></a>", &ParseOptions::default());
+//! assert!(report.is_valid());
+//! assert_eq!(2, report.count(Severity::Warning));
+//! assert!(report.to_json().starts_with("{\"valid\":true,\"error_count\":0,\"warning_count\":2,"));
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use crate::document::Document;
+use crate::error::{XmlError, XmlErrorPos, XmlWarning};
+use crate::escape::write_json_string;
+use crate::options::ParseOptions;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    Info,
+    Warning,
+    /// The document is not well-formed or not valid
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// The kind of check that reported a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Source {
+    WellFormedness,
+    Dtd,
+    Schema,
+    /// A custom rule, like a lint
+    Rule,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::WellFormedness => "well-formedness",
+            Source::Dtd => "dtd",
+            Source::Schema => "schema",
+            Source::Rule => "rule",
+        }
+    }
+}
+
+/// The part of the input that a diagnostic refers to, `start` and `end` are equal for a position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: XmlErrorPos,
+    pub end: XmlErrorPos,
+}
+
+impl Span {
+    pub fn at(pos: XmlErrorPos) -> Self {
+        Span { start: pos.clone(), end: pos }
+    }
+}
+
+/// A single issue of a [ValidationReport].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub source: Source,
+    /// A stable, kebab-case identifier of the kind of issue, e.g. `non-matching-tags`
+    pub code: String,
+    /// `None` if the issue is not tied to a part of the input, e.g. an exceeded size limit
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, source: Source, code: &str, message: impl Into<String>) -> Self {
+        Diagnostic { severity, source, code: code.to_string(), span: None, message: message.into() }
+    }
+
+    pub fn with_span(self, span: Span) -> Self {
+        Diagnostic { span: Some(span), ..self }
+    }
+
+    fn write_json(&self, json: &mut String) {
+        json.push_str("{\"severity\":");
+        write_json_string(self.severity.as_str(), json);
+        json.push_str(",\"source\":");
+        write_json_string(self.source.as_str(), json);
+        json.push_str(",\"code\":");
+        write_json_string(&self.code, json);
+        json.push_str(",\"span\":");
+        match &self.span {
+            Some(span) => json.push_str(&format!(
+                "{{\"start\":{{\"row\":{},\"col\":{}}},\"end\":{{\"row\":{},\"col\":{}}}}}",
+                span.start.row, span.start.col, span.end.row, span.end.col
+            )),
+            None => json.push_str("null"),
+        }
+        json.push_str(",\"message\":");
+        write_json_string(&self.message, json);
+        json.push('}');
+    }
+}
+
+/// `row:col: severity[code]: message`
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{}:{}: ", span.start.row, span.start.col)?;
+        }
+        write!(f, "{}[{}]: {}", self.severity.as_str(), self.code, self.message)
+    }
+}
+
+impl From<&XmlError> for Diagnostic {
+    fn from(e: &XmlError) -> Self {
+        let (code, message) = match e {
+            XmlError::NonMatchingTags { expected_name, found_name, .. } => {
+                ("non-matching-tags", format!("end tag {} does not match start tag {}", found_name, expected_name))
+            }
+            XmlError::UnexpectedXmlToken { .. } => ("unexpected-token", "unexpected token".to_string()),
+            XmlError::IllegalToken { expected: Some(expected), .. } => ("illegal-token", format!("illegal token, expected: {}", expected)),
+            XmlError::IllegalToken { expected: None, .. } => ("illegal-token", "illegal token".to_string()),
+            XmlError::UnknownReference { reason: Some(reason), .. } => ("unknown-reference", format!("invalid character reference: {}", reason)),
+            XmlError::UnknownReference { .. } => ("unknown-reference", "reference to an undeclared entity".to_string()),
+            XmlError::DuplicateAttribute { .. } => ("duplicate-attribute", "duplicate attribute".to_string()),
+            XmlError::ReservedNamespace { reason, .. } => ("reserved-namespace", reason.clone()),
+            XmlError::NotNamespaceWellFormed { reason, .. } => ("not-namespace-well-formed", reason.clone()),
+            XmlError::UnexpectedEndOfFile { construct, .. } if construct.is_empty() => ("unexpected-end-of-file", "unexpected end of input".to_string()),
+            XmlError::UnexpectedEndOfFile { construct, start, name, .. } => {
+                let mut message = format!("unexpected end of input in {}", construct);
+                if let Some(name) = name {
+                    message.push_str(&format!(" {}", name));
+                }
+                if let Some(start) = start {
+                    message.push_str(&format!(" started at line {}, column {}", start.row, start.col));
+                }
+                ("unexpected-end-of-file", message)
+            }
+            XmlError::DisallowedConstruct { construct, .. } => ("disallowed-construct", format!("{} is not allowed", construct)),
+            XmlError::InvalidUtf8 { .. } => ("invalid-utf8", "invalid UTF-8".to_string()),
+            XmlError::LeadingGarbage { len, .. } => ("leading-garbage", format!("{} bytes of content before the document", len)),
+            XmlError::LimitExceeded { limit, max } => ("limit-exceeded", format!("{} exceeds the limit of {}", limit, max)),
+        };
+        let diagnostic = Diagnostic::new(Severity::Error, Source::WellFormedness, code, message);
+        match e.pos() {
+            Some(pos) => diagnostic.with_span(Span::at(pos.clone())),
+            None => diagnostic,
+        }
+    }
+}
+
+impl From<&XmlWarning> for Diagnostic {
+    fn from(w: &XmlWarning) -> Self {
+        let (pos, code, message) = match w {
+            XmlWarning::ReservedName { pos, name } => (pos, "reserved-name", format!("names beginning with xml are reserved: {}", name)),
+            XmlWarning::DiscouragedCharacter { pos, character } => (pos, "discouraged-character", format!("discouraged character {:?}", character)),
+            XmlWarning::EmptyAttributeValue { pos, name } => (pos, "empty-attribute-value", format!("attribute {} is empty", name)),
+            XmlWarning::DoctypeIgnored { pos } => (pos, "doctype-ignored", "the document type declaration is not validated against".to_string()),
+            XmlWarning::StandaloneUnverified { pos } => {
+                (pos, "standalone-unverified", "the standalone declaration is not verified, external declarations are not read".to_string())
+            }
+        };
+        Diagnostic::new(Severity::Warning, Source::WellFormedness, code, message).with_span(Span::at(pos.clone()))
+    }
+}
+
+/// The diagnostics of one or more checks of a document, in the order they were reported.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidationReport {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a document and report its well-formedness error or its [warnings](Document::parse_with_warnings).
+    pub fn check_well_formedness(xml: &str, options: &ParseOptions) -> Self {
+        let mut report = ValidationReport::new();
+        match Document::parse_with_warnings(xml, options) {
+            Ok((_, warnings)) => report.diagnostics.extend(warnings.iter().map(Diagnostic::from)),
+            Err(e) => report.push(Diagnostic::from(&e)),
+        }
+        report
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Append the diagnostics of another check.
+    pub fn merge(&mut self, other: ValidationReport) {
+        self.diagnostics.extend(other.diagnostics);
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Warning)
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity == severity).count()
+    }
+
+    /// The highest severity of any diagnostic, `None` for an empty report.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.diagnostics.iter().map(|d| d.severity).max()
+    }
+
+    /// Whether no diagnostic is an error.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Serialize the report to a JSON object with the fields `valid`, `error_count`, `warning_count` and `diagnostics`.
+    /// Each diagnostic has the fields `severity`, `source`, `code`, `span` and `message`,
+    /// a span has the fields `start` and `end` with a `row` and `col` each, or is `null`.
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"valid\":{},\"error_count\":{},\"warning_count\":{},\"diagnostics\":[",
+            self.is_valid(),
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        );
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            diagnostic.write_json(&mut json);
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+/// One diagnostic per line.
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
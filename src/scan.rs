@@ -0,0 +1,147 @@
+//! A scanner for formats that embed XML constructs, e.g. templates with XML-like tags or the payload
+//! of a processing instruction, with the checks of the tokenizer for names, spaces, literals and references.
+//!
+//! Positions are byte offsets in the input, errors are those of the parser, at the position of the scanner.
+//!
+//! ```
+//! use jaxp_rust::scan::Scanner;
+//!
+//! // the pseudo-attributes of <?xml-stylesheet href="style.css" type="text/css"?>
+//! let mut scanner = Scanner::new(r#"href="style.css" type="text/css""#);
+//! let mut pseudo_attributes = vec![];
+//! while !scanner.is_at_end() {
+//!     let (name, value) = scanner.attribute().unwrap();
+//!     pseudo_attributes.push((name.slice(), value.slice()));
+//!     scanner.skip_spaces();
+//! }
+//! assert_eq!(vec![("href", "style.css"), ("type", "text/css")], pseudo_attributes);
+//! ```
+
+use crate::chariter::CharIter;
+use crate::error::{Expected, XmlError, XmlErrorPos};
+use crate::error::XmlError::IllegalToken;
+use crate::textrange::TextRange;
+use crate::token::XmlToken;
+use crate::tokenize::XmlTokenizer;
+use crate::xmlchar::XmlByte;
+
+/// Scans a text from its start. A method that fails may leave the scanner inside of the construct it scanned, at the error.
+pub struct Scanner<'a> {
+    ci: CharIter<'a>,
+    /// The predefined entities only, in the character data of literals
+    tokenizer: XmlTokenizer,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Scanner { ci: CharIter::new(text), tokenizer: XmlTokenizer::default() }
+    }
+
+    /// The current byte offset
+    pub fn pos(&self) -> usize {
+        self.ci.pos()
+    }
+
+    /// The text that is not scanned yet
+    pub fn rest(&self) -> &'a str {
+        &self.ci.text[self.ci.pos()..]
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        !self.ci.has_next()
+    }
+
+    /// An [IllegalToken] error at the current position, for the syntax errors of the scanned format.
+    pub fn error(&self, opt_expected: Option<Expected>) -> XmlError {
+        IllegalToken { pos: self.error_pos(), expected: opt_expected, fix: None }
+    }
+
+    pub fn error_pos(&self) -> XmlErrorPos {
+        self.ci.error_pos()
+    }
+
+    /// The next character, which has to be an XML Char, without consuming it.
+    pub fn peek(&mut self) -> Result<char, XmlError> {
+        self.ci.peek_xml_char()
+    }
+
+    /// Consume the next character, which has to be an XML Char.
+    pub fn next_char(&mut self) -> Result<char, XmlError> {
+        self.ci.next_xml_char()
+    }
+
+    /// If the rest of the text starts with a string
+    pub fn test(&self, test: &str) -> bool {
+        self.ci.test_str(test)
+    }
+
+    /// Consume a string, if the rest of the text starts with it.
+    pub fn skip(&mut self, test: &str) -> bool {
+        self.ci.expect_str(test).is_ok()
+    }
+
+    /// Consume a string, which the rest of the text has to start with.
+    pub fn expect(&mut self, expected: &str) -> Result<(), XmlError> {
+        self.ci.expect_str(expected)
+    }
+
+    /// Consume [\[3\] S](https://www.w3.org/TR/xml/#NT-S), if any, and return whether there were spaces.
+    pub fn skip_spaces(&mut self) -> bool {
+        let start_pos = self.ci.pos();
+        self.ci.skip_spaces();
+        self.ci.pos() > start_pos
+    }
+
+    /// Consume [\[3\] S](https://www.w3.org/TR/xml/#NT-S), at least one space.
+    pub fn expect_spaces(&mut self) -> Result<(), XmlError> {
+        self.ci.expect_spaces()
+    }
+
+    /// Consume a [\[5\] Name](https://www.w3.org/TR/xml/#NT-Name).
+    pub fn name(&mut self) -> Result<TextRange<'a>, XmlError> {
+        let start_pos = self.ci.pos();
+        XmlTokenizer::consume_name(&mut self.ci).inspect_err(|_| self.ci.pos = start_pos)
+    }
+
+    /// Consume a literal in single or double quotes, whose characters have to be XML Chars, and return its content.
+    pub fn quoted(&mut self) -> Result<TextRange<'a>, XmlError> {
+        let quote = self.quote()?;
+        let value_range = self.tokenizer.consume_xml_chars_until(&mut self.ci, &[quote])?;
+        self.ci.advance_n(1)?;
+        Ok(value_range)
+    }
+
+    /// Consume an attribute value in single or double quotes, [\[10\] AttValue](https://www.w3.org/TR/xml/#NT-AttValue),
+    /// and return its content, which is escaped. References have to refer to the predefined entities.
+    pub fn attribute_value(&mut self) -> Result<TextRange<'a>, XmlError> {
+        let quote = self.quote()?;
+        let value_range = self.tokenizer.consume_character_data_until(&mut self.ci, char::from(quote))?;
+        self.ci.advance_n(1)?;
+        Ok(value_range)
+    }
+
+    /// Consume an attribute, [\[41\] Attribute](https://www.w3.org/TR/xml/#NT-Attribute), and return its name and escaped value.
+    pub fn attribute(&mut self) -> Result<(TextRange<'a>, TextRange<'a>), XmlError> {
+        match self.tokenizer.tokenize_attribute(&mut self.ci)? {
+            XmlToken::Attribute { name_range, value_range } => Ok((name_range, value_range)),
+            _ => unreachable!("the tokenizer returns an attribute"),
+        }
+    }
+
+    /// Consume XML Chars up to a delimiter, which is not consumed, and return them.
+    /// Fails if the text does not contain the delimiter.
+    pub fn until(&mut self, delimiter: &str) -> Result<TextRange<'a>, XmlError> {
+        self.tokenizer.consume_xml_chars_until(&mut self.ci, delimiter.as_bytes())
+    }
+
+    /// A single or double quote, without a fix for an unquoted value
+    fn quote(&mut self) -> Result<u8, XmlError> {
+        match self.ci.peek_byte()? {
+            quote if quote.is_xml_quote() => {
+                self.ci.advance_n(1)?;
+                Ok(quote)
+            }
+            _ => Err(self.error(Some(Expected::Quote))),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Splitting a document into smaller documents, e.g. to distribute the records of a large feed across workers,
+//! and merging documents into one.
+//!
+//! The records are the child elements of the root element. Every shard wraps a run of consecutive records
+//! in a copy of the start tag of the root element, so that the records keep the namespace declarations in scope.
+//! The text, comments and processing instructions between two records go with the shard of the record after them,
+//! those after the last record with the last shard, so no content of the root element is lost.
+//! Everything else of the document is not copied.
+//!
+//! ```
+//! use jaxp_rust::shard::split;
+//!
+//! let xml = "<feed xmlns:a='urn:a'><a:entry/><a:entry/><a:entry/></feed>";
+//! let shards = split(xml, 2).unwrap();
+//! assert_eq!(vec!["<feed xmlns:a='urn:a'><a:entry/><a:entry/></feed>", "<feed xmlns:a='urn:a'><a:entry/></feed>"], shards);
+//! ```
+//!
+//! [merge] streams the records of several documents under one new root element, one document at a time.
+
+use std::io::Write;
+use std::ops::Range;
+
+use crate::chariter::CharIter;
+use crate::error::XmlError;
+use crate::error::XmlError::UnexpectedXmlToken;
+use crate::filter::{EventSink, FilterError};
+use crate::namespace::declared_prefix;
+use crate::pull::{Event, PullParser};
+use crate::textrange::TextRange;
+use crate::token::XmlToken::*;
+use crate::tokenize::XmlTokenizer;
+use crate::writer::XmlWriter;
+
+/// Split a document into at most `shard_count` documents with about the same number of records each.
+/// A document without records is its only shard. The records are not checked beyond the matching of tags.
+///
+/// # Panics
+/// If `shard_count` is 0.
+pub fn split(xml: &str, shard_count: usize) -> Result<Vec<String>, XmlError> {
+    assert!(shard_count > 0, "a document is split into at least one shard");
+    let tokens = XmlTokenizer::default().tokenize(xml)?;
+    // only used for error positions
+    let ci = CharIter { pos: 0, text: xml };
+    let mut open_ranges: Vec<TextRange> = vec![];
+    let mut opt_root_start_tag: Option<Range<usize>> = None;
+    let mut root = 0..0;
+    // the start of the end tag of the root element
+    let mut root_content_end = 0;
+    let mut root_name = "";
+    let mut records: Vec<Range<usize>> = vec![];
+    // where the closing `>` of the latest start tag is searched from
+    let mut start_tag_rest = 0;
+    // the end of a tag, found from a position after its name or last attribute
+    let tag_end = |rest: usize| rest + xml.as_bytes()[rest..].iter().position(|&b| b == b'>').unwrap() + 1;
+    for token in &tokens {
+        if open_ranges.len() == 1 && opt_root_start_tag.is_none() && !matches!(token, Attribute { .. }) {
+            opt_root_start_tag = Some(root.start..tag_end(start_tag_rest));
+        }
+        match *token {
+            StartTag(name_range) => {
+                if open_ranges.is_empty() {
+                    root.start = name_range.start - 1;
+                    root_name = name_range.slice;
+                }
+                open_ranges.push(name_range);
+                start_tag_rest = name_range.end;
+            }
+            EndTag(name_range) => {
+                let start_range = match open_ranges.pop() {
+                    Some(start_range) => start_range,
+                    None => return Err(UnexpectedXmlToken { pos: ci.error_pos_of(name_range.start) })
+                };
+                if !start_range.name_eq(name_range.slice) {
+                    return Err(ci.non_matching_tags(start_range, name_range, &open_ranges));
+                }
+                // the end tag of an empty-element tag has the range of its start tag
+                let rest = if name_range.start == start_range.start { start_tag_rest } else { name_range.end };
+                match open_ranges.len() {
+                    0 => {
+                        root.end = tag_end(rest);
+                        // before the `</` of the end tag, an empty-element tag has no content
+                        root_content_end = if name_range.start == start_range.start { root.end } else { name_range.start - 2 };
+                    }
+                    1 => records.push(start_range.start - 1..tag_end(rest)),
+                    _ => (),
+                }
+            }
+            Attribute { value_range, .. } => start_tag_rest = value_range.end + 1,
+            _ => (),
+        }
+    }
+    if let Some(&start_range) = open_ranges.last() {
+        return Err(ci.unclosed_element(start_range));
+    }
+    if root.is_empty() {
+        return Err(ci.unexpected_end_of_file().in_construct("document"));
+    }
+    let root_start_tag = match opt_root_start_tag {
+        Some(root_start_tag) if !records.is_empty() => root_start_tag,
+        _ => return Ok(vec![xml[root].to_string()]),
+    };
+    let shard_count = shard_count.min(records.len());
+    let mut shards = Vec::with_capacity(shard_count);
+    let mut first = 0;
+    let mut content_start = root_start_tag.end;
+    for shard in 0..shard_count {
+        // the first shards get one more record if they cannot all have the same number
+        let last = first + records.len() / shard_count + usize::from(shard < records.len() % shard_count) - 1;
+        let content_end = if shard + 1 == shard_count { root_content_end } else { records[last].end };
+        shards.push(format!("{}{}</{}>", &xml[root_start_tag.clone()], &xml[content_start..content_end], root_name));
+        first = last + 1;
+        content_start = content_end;
+    }
+    Ok(shards)
+}
+
+/// Write the records of the documents, with the text and comments between them, as the content of a new root element.
+/// The new root element declares the namespaces of the first root element, records of other documents
+/// declare those namespaces of their root element that differ.
+/// Only one document is parsed at a time and its events are written as they are read.
+pub fn merge<'a, W: Write>(inputs: impl IntoIterator<Item = &'a str>, root_name: &str, writer: &mut XmlWriter<W>) -> Result<(), FilterError> {
+    let mut opt_root_declarations: Option<Vec<(String, String)>> = None;
+    for xml in inputs {
+        let mut parser = PullParser::new(xml)?;
+        let mut declarations: Vec<(String, String)> = vec![];
+        let mut is_in_root = false;
+        while let Some(event) = parser.next_event()? {
+            match event {
+                Event::StartElement { attributes, depth: 0, .. } => {
+                    declarations = attributes.iter()
+                        .filter(|a| declared_prefix(a.name()).is_some())
+                        .map(|a| (a.name().to_string(), a.value().to_string()))
+                        .collect();
+                    if opt_root_declarations.is_none() {
+                        writer.start_element(root_name)?;
+                        for (name, value) in &declarations {
+                            writer.attribute_raw(name, value)?;
+                        }
+                        opt_root_declarations = Some(declarations.clone());
+                    }
+                    is_in_root = true;
+                }
+                Event::StartElement { name, mut attributes, depth: 1 } => {
+                    let root_declarations = opt_root_declarations.as_deref().unwrap_or_default();
+                    // the default namespace of the new root element may have to be undeclared
+                    let names = declarations.iter().chain(root_declarations.iter().filter(|(n, _)| n == "xmlns")).map(|(n, _)| n);
+                    for declared in names {
+                        let uri = declared_uri(&declarations, declared);
+                        if uri != declared_uri(root_declarations, declared) && !attributes.contains(declared) {
+                            attributes.insert(declared, uri);
+                        }
+                    }
+                    writer.event(Event::StartElement { name, attributes, depth: 1 })?;
+                }
+                Event::EndElement { depth: 0, .. } => is_in_root = false,
+                event if is_in_root => writer.event(event)?,
+                _ => (),
+            }
+        }
+    }
+    if opt_root_declarations.is_none() {
+        writer.start_element(root_name)?;
+    }
+    Ok(writer.end_element()?)
+}
+
+/// The namespace of a declaration by its attribute name, empty if it is not declared.
+fn declared_uri<'d>(declarations: &'d [(String, String)], name: &str) -> &'d str {
+    declarations.iter().find(|(n, _)| n == name).map_or("", |(_, uri)| uri)
+}
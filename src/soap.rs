@@ -0,0 +1,443 @@
+//! Access to and construction of SOAP 1.1 and 1.2 envelopes.
+//!
+//! An [Envelope] checks the structure of a parsed message and gives access to its header blocks, body and fault.
+//! Header blocks that must be understood are checked with [Envelope::check_must_understand].
+//! An [EnvelopeBuilder] writes a message from header blocks and body content of other documents.
+//!
+//! ```
+//! use jaxp_rust::document::Document;
+//! use jaxp_rust::soap::{Envelope, EnvelopeBuilder, SoapVersion};
+//!
+//! let request = Document::parse("<m:GetPrice xmlns:m=\"urn:shop\"><m:Item>Apples</m:Item></m:GetPrice>").unwrap();
+//! let message = EnvelopeBuilder::new(SoapVersion::Soap12).body_content(request.root_element()).build();
+//!
+//! let doc = Document::parse(&message).unwrap();
+//! let envelope = Envelope::from_document(&doc).unwrap();
+//! assert_eq!(Some("GetPrice"), envelope.body_content().map(|c| c.local_name()));
+//! assert!(envelope.fault().is_none());
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use crate::document::{ChildElements, Document, Node};
+use crate::namespace::split_qname;
+use crate::writer::{serialize_with_options, NamespaceOptions, SerializeOptions, XmlWriter};
+use crate::xpath::Item;
+
+pub const SOAP_11_NAMESPACE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+pub const SOAP_12_NAMESPACE: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+const SOAP_11_NEXT: &str = "http://schemas.xmlsoap.org/soap/actor/next";
+const SOAP_12_NEXT: &str = "http://www.w3.org/2003/05/soap-envelope/role/next";
+const SOAP_12_NONE: &str = "http://www.w3.org/2003/05/soap-envelope/role/none";
+const SOAP_12_ULTIMATE_RECEIVER: &str = "http://www.w3.org/2003/05/soap-envelope/role/ultimateReceiver";
+
+/// The prefix of the envelope namespace in built messages
+const PREFIX: &str = "env";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoapVersion {
+    Soap11,
+    Soap12,
+}
+
+impl SoapVersion {
+    pub fn namespace(&self) -> &'static str {
+        match self {
+            SoapVersion::Soap11 => SOAP_11_NAMESPACE,
+            SoapVersion::Soap12 => SOAP_12_NAMESPACE,
+        }
+    }
+
+    /// The media type of messages, SOAP 1.1 messages also need a `SOAPAction` HTTP header.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SoapVersion::Soap11 => "text/xml",
+            SoapVersion::Soap12 => "application/soap+xml",
+        }
+    }
+
+    fn from_namespace(uri: &str) -> Option<SoapVersion> {
+        match uri {
+            SOAP_11_NAMESPACE => Some(SoapVersion::Soap11),
+            SOAP_12_NAMESPACE => Some(SoapVersion::Soap12),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SoapError {
+    /// The root element is an `Envelope` in neither of the SOAP namespaces, but in this one, if any
+    VersionMismatch(Option<String>),
+    /// The root element is not an `Envelope`
+    NotAnEnvelope(String),
+    /// The envelope lacks its `Body` or has elements in places where they are not allowed
+    InvalidEnvelope(String),
+    /// Header blocks that must be understood but are not, by their names in the form `{namespace}local-name`
+    NotUnderstood(Vec<String>),
+}
+
+impl Display for SoapError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            SoapError::VersionMismatch(Some(uri)) => write!(f, "the envelope namespace {} is not supported", uri),
+            SoapError::VersionMismatch(None) => write!(f, "the envelope is in no namespace"),
+            SoapError::NotAnEnvelope(name) => write!(f, "the root element {} is not an envelope", name),
+            SoapError::InvalidEnvelope(reason) => write!(f, "invalid envelope: {}", reason),
+            SoapError::NotUnderstood(names) => write!(f, "header blocks not understood: {}", names.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for SoapError {}
+
+/// The code of a fault, SOAP 1.1 calls senders clients and receivers servers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FaultCode {
+    VersionMismatch,
+    MustUnderstand,
+    /// Only defined by SOAP 1.2
+    DataEncodingUnknown,
+    Sender,
+    Receiver,
+    /// A code of another namespace, which SOAP 1.1 allows
+    Other { namespace: Option<String>, local_name: String },
+}
+
+impl FaultCode {
+    fn local_name(&self, version: SoapVersion) -> &str {
+        match (self, version) {
+            (FaultCode::VersionMismatch, _) => "VersionMismatch",
+            (FaultCode::MustUnderstand, _) => "MustUnderstand",
+            (FaultCode::DataEncodingUnknown, _) => "DataEncodingUnknown",
+            (FaultCode::Sender, SoapVersion::Soap11) => "Client",
+            (FaultCode::Sender, SoapVersion::Soap12) => "Sender",
+            (FaultCode::Receiver, SoapVersion::Soap11) => "Server",
+            (FaultCode::Receiver, SoapVersion::Soap12) => "Receiver",
+            (FaultCode::Other { local_name, .. }, _) => local_name,
+        }
+    }
+}
+
+/// The character data of an element, with references resolved.
+fn text(node: Node) -> String {
+    Item::Node(node).string_value()
+}
+
+/// The SOAP envelope of a document.
+#[derive(Clone, Copy, Debug)]
+pub struct Envelope<'a, 'input: 'a> {
+    version: SoapVersion,
+    node: Node<'a, 'input>,
+    opt_header: Option<Node<'a, 'input>>,
+    body: Node<'a, 'input>,
+}
+
+impl<'a, 'input: 'a> Envelope<'a, 'input> {
+    /// Check that the root element is an envelope with an optional `Header` and a `Body`.
+    /// SOAP 1.1 allows further elements after the body, SOAP 1.2 does not.
+    pub fn from_document(doc: &'a Document<'input>) -> Result<Envelope<'a, 'input>, SoapError> {
+        let node = doc.root_element();
+        if node.local_name() != "Envelope" {
+            return Err(SoapError::NotAnEnvelope(node.tag_name().to_string()));
+        }
+        let version = node
+            .namespace_uri()
+            .and_then(SoapVersion::from_namespace)
+            .ok_or_else(|| SoapError::VersionMismatch(node.namespace_uri().map(str::to_string)))?;
+        let namespace = version.namespace();
+        let mut children = node.child_elements().peekable();
+        let opt_header = children.next_if(|c| c.has_tag_name_ns(namespace, "Header"));
+        let body = children
+            .next()
+            .filter(|c| c.has_tag_name_ns(namespace, "Body"))
+            .ok_or_else(|| SoapError::InvalidEnvelope("expected a Body after the optional Header".to_string()))?;
+        if let (SoapVersion::Soap12, Some(extra)) = (version, children.next()) {
+            return Err(SoapError::InvalidEnvelope(format!("unexpected element {} after the Body", extra.tag_name())));
+        }
+        Ok(Envelope { version, node, opt_header, body })
+    }
+
+    pub fn version(&self) -> SoapVersion {
+        self.version
+    }
+
+    pub fn node(&self) -> Node<'a, 'input> {
+        self.node
+    }
+
+    pub fn header(&self) -> Option<Node<'a, 'input>> {
+        self.opt_header
+    }
+
+    /// The child elements of the header.
+    pub fn header_blocks(&self) -> HeaderBlocks<'a, 'input> {
+        HeaderBlocks { version: self.version, opt_children: self.opt_header.map(|h| h.child_elements()) }
+    }
+
+    pub fn body(&self) -> Node<'a, 'input> {
+        self.body
+    }
+
+    /// The first child element of the body, i.e. the request, response or fault.
+    pub fn body_content(&self) -> Option<Node<'a, 'input>> {
+        self.body.first_element_child()
+    }
+
+    /// The fault that the body consists of, if any.
+    pub fn fault(&self) -> Option<Fault<'a, 'input>> {
+        self.body_content().filter(|c| c.has_tag_name_ns(self.version.namespace(), "Fault")).map(|node| Fault { version: self.version, node })
+    }
+
+    /// Check that every header block that is targeted at one of the roles and must be understood is understood.
+    /// The receiver acts in the roles next and, in SOAP 1.2, ultimate receiver, besides the ones given.
+    pub fn check_must_understand(&self, roles: &[&str], is_understood: impl Fn(&HeaderBlock<'a, 'input>) -> bool) -> Result<(), SoapError> {
+        let not_understood: Vec<String> = self
+            .header_blocks()
+            .filter(|block| block.must_understand() && block.is_targeted_at(roles) && !is_understood(block))
+            .map(|block| format!("{{{}}}{}", block.node.namespace_uri().unwrap_or(""), block.node.local_name()))
+            .collect();
+        if not_understood.is_empty() {
+            Ok(())
+        } else {
+            Err(SoapError::NotUnderstood(not_understood))
+        }
+    }
+}
+
+/// A child element of the header of an envelope.
+#[derive(Clone, Copy, Debug)]
+pub struct HeaderBlock<'a, 'input: 'a> {
+    version: SoapVersion,
+    node: Node<'a, 'input>,
+}
+
+impl<'a, 'input: 'a> HeaderBlock<'a, 'input> {
+    pub fn node(&self) -> Node<'a, 'input> {
+        self.node
+    }
+
+    /// Whether the `mustUnderstand` attribute is `1` or, in SOAP 1.2, `true`.
+    pub fn must_understand(&self) -> bool {
+        matches!(
+            (self.version, self.node.attribute_ns(self.version.namespace(), "mustUnderstand").map(str::trim)),
+            (_, Some("1")) | (SoapVersion::Soap12, Some("true"))
+        )
+    }
+
+    /// The role that the block is targeted at, the `actor` attribute of SOAP 1.1.
+    /// The ultimate receiver if absent.
+    pub fn role(&self) -> Option<&'a str> {
+        let name = match self.version {
+            SoapVersion::Soap11 => "actor",
+            SoapVersion::Soap12 => "role",
+        };
+        self.node.attribute_ns(self.version.namespace(), name)
+    }
+
+    /// Whether the block is relayed if it is not processed, only defined by SOAP 1.2.
+    pub fn relay(&self) -> bool {
+        matches!(self.node.attribute_ns(SOAP_12_NAMESPACE, "relay").map(str::trim), Some("1" | "true")) && self.version == SoapVersion::Soap12
+    }
+
+    /// Whether the block is targeted at one of the roles, or a role that every receiver acts in.
+    pub fn is_targeted_at(&self, roles: &[&str]) -> bool {
+        match self.role() {
+            None | Some(SOAP_11_NEXT | SOAP_12_NEXT | SOAP_12_ULTIMATE_RECEIVER) => true,
+            Some(SOAP_12_NONE) => false,
+            Some(role) => roles.contains(&role),
+        }
+    }
+}
+
+/// The [HeaderBlock]s of an envelope.
+pub struct HeaderBlocks<'a, 'input: 'a> {
+    version: SoapVersion,
+    opt_children: Option<ChildElements<'a, 'input>>,
+}
+
+impl<'a, 'input: 'a> Iterator for HeaderBlocks<'a, 'input> {
+    type Item = HeaderBlock<'a, 'input>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.opt_children.as_mut()?.next()?;
+        Some(HeaderBlock { version: self.version, node })
+    }
+}
+
+/// The fault in the body of an envelope, SOAP 1.1 and 1.2 structure them differently.
+#[derive(Clone, Copy, Debug)]
+pub struct Fault<'a, 'input: 'a> {
+    version: SoapVersion,
+    node: Node<'a, 'input>,
+}
+
+impl<'a, 'input: 'a> Fault<'a, 'input> {
+    pub fn node(&self) -> Node<'a, 'input> {
+        self.node
+    }
+
+    /// A child of the fault, which is in no namespace in SOAP 1.1 and in the envelope namespace in SOAP 1.2.
+    fn child(&self, soap_11_name: &str, soap_12_name: &str) -> Option<Node<'a, 'input>> {
+        match self.version {
+            SoapVersion::Soap11 => self.node.child_element_ns("", soap_11_name),
+            SoapVersion::Soap12 => self.node.child_element_ns(SOAP_12_NAMESPACE, soap_12_name),
+        }
+    }
+
+    /// The code, whose qualified name is resolved against the namespace declarations in scope.
+    pub fn code(&self) -> Option<FaultCode> {
+        let value = match self.version {
+            SoapVersion::Soap11 => self.child("faultcode", "")?,
+            SoapVersion::Soap12 => self.child("", "Code")?.child_element_ns(SOAP_12_NAMESPACE, "Value")?,
+        };
+        let qname = text(value);
+        let (opt_prefix, local_name) = split_qname(qname.trim());
+        let opt_namespace = value.lookup_namespace(opt_prefix);
+        if opt_namespace == Some(self.version.namespace()) {
+            let code = match (local_name, self.version) {
+                ("VersionMismatch", _) => Some(FaultCode::VersionMismatch),
+                ("MustUnderstand", _) => Some(FaultCode::MustUnderstand),
+                ("DataEncodingUnknown", SoapVersion::Soap12) => Some(FaultCode::DataEncodingUnknown),
+                ("Client", SoapVersion::Soap11) | ("Sender", SoapVersion::Soap12) => Some(FaultCode::Sender),
+                ("Server", SoapVersion::Soap11) | ("Receiver", SoapVersion::Soap12) => Some(FaultCode::Receiver),
+                _ => None,
+            };
+            if code.is_some() {
+                return code;
+            }
+        }
+        Some(FaultCode::Other { namespace: opt_namespace.map(str::to_string), local_name: local_name.to_string() })
+    }
+
+    /// The human-readable explanation, the first `Text` of the reason in SOAP 1.2.
+    pub fn reason(&self) -> Option<String> {
+        let reason = match self.version {
+            SoapVersion::Soap11 => self.child("faultstring", "")?,
+            SoapVersion::Soap12 => self.child("", "Reason")?.child_element_ns(SOAP_12_NAMESPACE, "Text")?,
+        };
+        Some(text(reason))
+    }
+
+    /// The role of the node that caused the fault, the `faultactor` of SOAP 1.1.
+    pub fn role(&self) -> Option<String> {
+        self.child("faultactor", "Role").map(text)
+    }
+
+    /// The application-specific details.
+    pub fn detail(&self) -> Option<Node<'a, 'input>> {
+        self.child("detail", "Detail")
+    }
+}
+
+/// Writes an envelope from copies of header blocks and body content.
+/// The copies declare the namespaces they use, the envelope namespace is bound to the prefix `env`.
+#[derive(Clone, Debug)]
+pub struct EnvelopeBuilder {
+    version: SoapVersion,
+    header_blocks: Vec<String>,
+    body_content: Vec<String>,
+}
+
+/// Serialize a copy of a node that is placed into an envelope.
+fn copy(node: Node) -> String {
+    serialize_with_options(node, &SerializeOptions { namespaces: Some(NamespaceOptions::default()), ..SerializeOptions::default() })
+}
+
+impl EnvelopeBuilder {
+    pub fn new(version: SoapVersion) -> Self {
+        EnvelopeBuilder { version, header_blocks: vec![], body_content: vec![] }
+    }
+
+    pub fn header_block(mut self, block: Node) -> Self {
+        self.header_blocks.push(copy(block));
+        self
+    }
+
+    pub fn body_content(mut self, content: Node) -> Self {
+        self.body_content.push(copy(content));
+        self
+    }
+
+    /// Add a fault to the body, which should be its only content.
+    pub fn fault(mut self, code: &FaultCode, reason: &str, opt_detail: Option<Node>) -> Self {
+        let mut writer = XmlWriter::unchecked(Vec::new());
+        // writing to a Vec cannot fail
+        self.write_fault(&mut writer, code, reason, opt_detail).unwrap();
+        self.body_content.push(String::from_utf8(writer.into_inner()).unwrap());
+        self
+    }
+
+    fn write_fault(&self, writer: &mut XmlWriter<Vec<u8>>, code: &FaultCode, reason: &str, opt_detail: Option<Node>) -> std::io::Result<()> {
+        let element = |local_name: &str| format!("{}:{}", PREFIX, local_name);
+        writer.start_element(&element("Fault"))?;
+        match self.version {
+            SoapVersion::Soap11 => writer.start_element("faultcode")?,
+            SoapVersion::Soap12 => {
+                writer.start_element(&element("Code"))?;
+                writer.start_element(&element("Value"))?;
+            }
+        }
+        let local_name = code.local_name(self.version);
+        match code {
+            FaultCode::Other { namespace: Some(namespace), .. } => {
+                writer.attribute("xmlns:c", namespace)?;
+                writer.text(&format!("c:{}", local_name))?;
+            }
+            FaultCode::Other { namespace: None, .. } => writer.text(local_name)?,
+            _ => writer.text(&element(local_name))?,
+        }
+        writer.end_element()?;
+        match self.version {
+            SoapVersion::Soap11 => {
+                writer.start_element("faultstring")?;
+                writer.text(reason)?;
+                writer.end_element()?;
+            }
+            SoapVersion::Soap12 => {
+                writer.end_element()?;
+                writer.start_element(&element("Reason"))?;
+                writer.start_element(&element("Text"))?;
+                writer.attribute("xml:lang", "en")?;
+                writer.text(reason)?;
+                writer.end_element()?;
+                writer.end_element()?;
+            }
+        }
+        if let Some(detail) = opt_detail {
+            match self.version {
+                SoapVersion::Soap11 => writer.start_element("detail")?,
+                SoapVersion::Soap12 => writer.start_element(&element("Detail"))?,
+            }
+            writer.markup_raw(&copy(detail))?;
+            writer.end_element()?;
+        }
+        writer.end_element()
+    }
+
+    pub fn build(self) -> String {
+        let mut writer = XmlWriter::unchecked(Vec::new());
+        // writing to a Vec cannot fail
+        self.write(&mut writer).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    fn write(&self, writer: &mut XmlWriter<Vec<u8>>) -> std::io::Result<()> {
+        writer.start_element(&format!("{}:Envelope", PREFIX))?;
+        writer.attribute(&format!("xmlns:{}", PREFIX), self.version.namespace())?;
+        if !self.header_blocks.is_empty() {
+            writer.start_element(&format!("{}:Header", PREFIX))?;
+            for block in &self.header_blocks {
+                writer.markup_raw(block)?;
+            }
+            writer.end_element()?;
+        }
+        writer.start_element(&format!("{}:Body", PREFIX))?;
+        for content in &self.body_content {
+            writer.markup_raw(content)?;
+        }
+        writer.end_element()?;
+        writer.end_element()
+    }
+}
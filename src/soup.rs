@@ -0,0 +1,343 @@
+//! Best-effort parsing of "tag soup", XHTML-flavored HTML that is not well-formed XML.
+//!
+//! The scanner never fails, anything that cannot be read as markup is kept as text.
+//! Text and attribute values are escaped where necessary, so the tree serializes to well-formed XML.
+//! The first top-level element is the root element, text and elements around it are moved into it.
+
+use std::borrow::Cow;
+
+use crate::chariter::CharIter;
+use crate::document::{Attribute, Attributes, Document, NodeData, NodeId, NodeKind};
+use crate::entities::EntityTable;
+use crate::error::XmlError;
+use crate::escape::{escape_attribute, escape_text};
+use crate::options::{CommentPolicy, ParseOptions, ProcessingInstructionPolicy};
+use crate::profile::Profile;
+use crate::refs::decode_char_ref;
+use crate::tokenize::BYTE_ORDER_MARK;
+use crate::xmlchar::{XmlByte, XmlChar};
+
+/// Keep character references and the predefined entities, escape every other markup character.
+/// References to registered entities are replaced by their text.
+fn normalize<'t>(text: &'t str, is_attribute: bool, entities: &EntityTable) -> Cow<'t, str> {
+    let needs_escaping = |i: usize, c: char| match c {
+        '<' | '>' => true,
+        '"' => is_attribute,
+        '&' => !is_reference(&text[i..]),
+        _ => false,
+    };
+    if !text.char_indices().any(|(i, c)| needs_escaping(i, c)) {
+        return Cow::Borrowed(text);
+    }
+    let escape = |text: &str| if is_attribute { escape_attribute(text, '"').into_owned() } else { escape_text(text).into_owned() };
+    let mut normalized = String::with_capacity(text.len());
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if !needs_escaping(i, c) {
+            normalized.push(c);
+            continue;
+        }
+        let replacement = (c == '&')
+            .then(|| text[i + 1..].split_once(';'))
+            .flatten()
+            .and_then(|(name, _)| Some((name, entities.get(name)?)));
+        match replacement {
+            Some((name, replacement)) => {
+                normalized.push_str(&escape(replacement));
+                // skip the name and the semicolon
+                chars.nth(name.len());
+            }
+            None => normalized.push_str(&escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    Cow::Owned(normalized)
+}
+
+/// Whether a text starts with a reference that the strict parser accepts.
+fn is_reference(text: &str) -> bool {
+    let end = match text.find(';') {
+        Some(end) => end,
+        None => return false,
+    };
+    match &text[1..end] {
+        "amp" | "lt" | "gt" | "quot" | "apos" => true,
+        name if name.starts_with('#') => decode_char_ref(&text[..=end]).is_ok(),
+        _ => false,
+    }
+}
+
+struct SoupParser<'input, 'o> {
+    text: &'input str,
+    pos: usize,
+    doc: Document<'input>,
+    /// open elements, the document root at the bottom
+    open: Vec<(NodeId, &'input str)>,
+    options: &'o ParseOptions,
+    profile: Cow<'o, Profile>,
+}
+
+impl<'input> SoupParser<'input, '_> {
+    fn rest(&self) -> &'input str {
+        &self.text[self.pos..]
+    }
+
+    fn parent(&self) -> NodeId {
+        self.open.last().unwrap().0
+    }
+
+    /// Consume up to and including a delimiter, or the rest of the input.
+    /// Returns the consumed text before the delimiter.
+    fn consume_until(&mut self, delimiter: &str) -> &'input str {
+        let rest = self.rest();
+        match rest.find(delimiter) {
+            Some(i) => {
+                self.pos += i + delimiter.len();
+                &rest[..i]
+            }
+            None => {
+                self.pos = self.text.len();
+                rest
+            }
+        }
+    }
+
+    fn consume_name(&mut self) -> &'input str {
+        let rest = self.rest();
+        let mut len = 0;
+        for (i, c) in rest.char_indices() {
+            let is_valid = if i == 0 { c.is_xml_name_start_char() } else { c.is_xml_name_char() };
+            if !is_valid {
+                break;
+            }
+            len = i + c.len_utf8();
+        }
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.pos < self.text.len() && self.text.as_bytes()[self.pos].is_xml_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn append(&mut self, kind: NodeKind<'input>) -> NodeId {
+        let parent = self.parent();
+        self.doc.append(parent, kind)
+    }
+
+    fn append_text(&mut self, text: &'input str) {
+        if text.is_empty() {
+            return;
+        }
+        // spaces outside of elements are not part of the tree
+        if self.open.len() == 1 && text.bytes().all(|b| b.is_xml_whitespace()) {
+            return;
+        }
+        let text = normalize(text, false, &self.options.entities);
+        let parent = self.parent();
+        // text next to text that was interrupted by a stray '<' or an ignored construct
+        if let Some(&last) = self.doc.nodes[parent.0].children.last() {
+            if let NodeKind::Text(previous) = &mut self.doc.nodes[last.0].kind {
+                *previous = Cow::Owned(format!("{}{}", previous, text));
+                return;
+            }
+        }
+        self.append(NodeKind::Text(text));
+    }
+
+    fn parse(mut self) -> Document<'input> {
+        let mut text_start = self.pos;
+        while self.pos < self.text.len() {
+            let rest = self.rest();
+            let is_markup = rest.starts_with("<!") || rest.starts_with("<?") || rest.starts_with("</")
+                || (rest.starts_with('<') && rest[1..].chars().next().is_some_and(|c| c.is_xml_name_start_char()));
+            if !is_markup {
+                self.pos += rest.chars().next().unwrap().len_utf8();
+                continue;
+            }
+            let text = &self.text[text_start..self.pos];
+            self.append_text(text);
+            self.parse_markup();
+            text_start = self.pos;
+        }
+        let text = &self.text[text_start..];
+        self.append_text(text);
+        if let Some(root_element) = self.doc.nodes[0].children.iter().copied().find(|c| matches!(self.doc.nodes[c.0].kind, NodeKind::Element { .. })) {
+            self.adopt_top_level_content(root_element);
+        }
+        self.doc
+    }
+
+    /// Move text before the root element to its start, and text and elements after it to its end,
+    /// together with the comments and processing instructions in between, so that the document has a single root element.
+    fn adopt_top_level_content(&mut self, root_element: NodeId) {
+        let top_level = std::mem::take(&mut self.doc.nodes[0].children);
+        let is_content = |id: &NodeId| matches!(self.doc.nodes[id.0].kind, NodeKind::Element { .. } | NodeKind::Text(_) | NodeKind::CdataSection(_));
+        let position = top_level.iter().position(|c| *c == root_element).unwrap();
+        let end = top_level.iter().rposition(is_content).unwrap() + 1;
+        let (leading, trailing): (Vec<NodeId>, Vec<NodeId>) = top_level[..position].iter().partition(|c| !is_content(c));
+        let mut children = trailing;
+        children.append(&mut std::mem::take(&mut self.doc.nodes[root_element.0].children));
+        children.extend_from_slice(&top_level[position + 1..end]);
+
+        let mut adopted: Vec<NodeId> = Vec::with_capacity(children.len());
+        for child in children {
+            // text next to text, which was separated by the boundary of the root element
+            let previous = adopted.last().map(|p| &self.doc.nodes[p.0].kind);
+            if let (Some(NodeKind::Text(previous)), NodeKind::Text(text)) = (previous, &self.doc.nodes[child.0].kind) {
+                let merged = NodeKind::Text(Cow::Owned(format!("{}{}", previous, text)));
+                let last = *adopted.last().unwrap();
+                self.doc.nodes[last.0].kind = merged;
+                self.doc.nodes[child.0].parent = None;
+            } else {
+                self.doc.nodes[child.0].parent = Some(root_element);
+                adopted.push(child);
+            }
+        }
+        self.doc.nodes[root_element.0].children = adopted;
+        self.doc.reindex_children(root_element);
+
+        let mut top_level_children = leading;
+        top_level_children.push(root_element);
+        top_level_children.extend_from_slice(&top_level[end..]);
+        self.doc.nodes[0].children = top_level_children;
+        self.doc.reindex_children(NodeId(0));
+    }
+
+    fn parse_markup(&mut self) {
+        let rest = self.rest();
+        if rest.starts_with("<!--") {
+            self.pos += 4;
+            let comment = self.consume_until("-->");
+            if self.options.comments != CommentPolicy::Deny {
+                // hyphens are kept, but must not form the end of the comment
+                let comment = if comment.contains("--") || comment.ends_with('-') {
+                    let mut comment = comment.to_string();
+                    while comment.contains("--") {
+                        comment = comment.replace("--", "- -");
+                    }
+                    Cow::Owned(comment.trim_end_matches('-').to_string())
+                } else {
+                    Cow::Borrowed(comment)
+                };
+                self.append(NodeKind::Comment(comment));
+            }
+        } else if rest.starts_with("<![CDATA[") {
+            self.pos += 9;
+            let cdata = self.consume_until("]]>");
+            self.append(NodeKind::CdataSection(Cow::Borrowed(cdata)));
+        } else if rest.starts_with("<!") {
+            // document type declarations and the like are not part of the tree
+            self.consume_until(">");
+        } else if rest.starts_with("<?") {
+            self.pos += 2;
+            let target = self.consume_name();
+            self.skip_spaces();
+            let value = self.consume_until(">");
+            let value = value.strip_suffix('?').unwrap_or(value).trim_end();
+            let is_declaration = target.eq_ignore_ascii_case("xml");
+            if !target.is_empty() && !is_declaration && self.options.processing_instructions != ProcessingInstructionPolicy::Deny {
+                self.append(NodeKind::ProcessingInstruction {
+                    target: Cow::Borrowed(target),
+                    opt_value: if value.is_empty() || value.contains("?>") { None } else { Some(Cow::Borrowed(value)) },
+                });
+            }
+        } else if rest.starts_with("</") {
+            self.pos += 2;
+            let name = self.consume_name();
+            self.consume_until(">");
+            // close the innermost matching element and everything opened inside of it, ignore stray end tags
+            if let Some(i) = self.open.iter().skip(1).rposition(|(_, open)| self.profile.names_match(open, name)) {
+                self.open.truncate(i + 1);
+            }
+        } else {
+            self.parse_start_tag();
+        }
+    }
+
+    fn parse_start_tag(&mut self) {
+        self.pos += 1;
+        let name = self.consume_name();
+        while self.open.len() > 1 && self.profile.implies_end_of(self.open.last().unwrap().1, name) {
+            self.open.pop();
+        }
+        let mut attributes = Attributes::new();
+        let mut is_empty_element_tag = false;
+        loop {
+            self.skip_spaces();
+            let rest = self.rest();
+            if rest.is_empty() {
+                break;
+            } else if rest.starts_with("/>") {
+                self.pos += 2;
+                is_empty_element_tag = true;
+                break;
+            } else if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let attribute_name = self.consume_name();
+            if attribute_name.is_empty() {
+                // skip garbage
+                self.pos += rest.chars().next().unwrap().len_utf8();
+                continue;
+            }
+            self.skip_spaces();
+            let value = if self.rest().starts_with('=') {
+                self.pos += 1;
+                self.skip_spaces();
+                self.consume_attribute_value()
+            } else {
+                // a boolean attribute
+                Cow::Borrowed("")
+            };
+            if !attributes.contains(attribute_name) {
+                attributes.push(Attribute { name: Cow::Borrowed(attribute_name), value });
+            }
+        }
+        if let Some(filter) = &self.options.attribute_filter {
+            attributes.filter(name, filter, &self.options.entities);
+        }
+        let id = self.append(NodeKind::Element { name: Cow::Borrowed(name), attributes });
+        if !is_empty_element_tag && !self.profile.is_void(name) {
+            self.open.push((id, name));
+        }
+    }
+
+    fn consume_attribute_value(&mut self) -> Cow<'input, str> {
+        let rest = self.rest();
+        match rest.bytes().next() {
+            Some(quote @ (b'"' | b'\'')) => {
+                self.pos += 1;
+                let value = self.consume_until(if quote == b'"' { "\"" } else { "'" });
+                normalize(value, true, &self.options.entities)
+            }
+            _ => {
+                let len = rest.find(|c: char| c.is_ascii_whitespace() || c == '>').unwrap_or(rest.len());
+                // a slash right before the end of the tag belongs to the tag
+                let len = if rest[..len].ends_with('/') && rest[len..].starts_with('>') { len - 1 } else { len };
+                self.pos += len;
+                normalize(&rest[..len], true, &self.options.entities)
+            }
+        }
+    }
+}
+
+/// Parse tag soup into a document, see [Strictness::TagSoup](crate::options::Strictness::TagSoup).
+pub(crate) fn parse<'input>(xml: &'input str, options: &ParseOptions) -> Result<Document<'input>, XmlError> {
+    let mut doc = Document { text: xml, nodes: vec![], xml_declaration: None, opt_doctype: None };
+    doc.nodes.push(NodeData { kind: NodeKind::Root, parent: None, children: vec![], index: 0, source: None });
+    let profile = match &options.profile {
+        Some(profile) => Cow::Borrowed(profile),
+        None => Cow::Owned(Profile::html_structure()),
+    };
+    // skip the byte order mark
+    let pos = if xml.starts_with(BYTE_ORDER_MARK) { BYTE_ORDER_MARK.len_utf8() } else { 0 };
+    let parser = SoupParser { text: xml, pos, doc, open: vec![(NodeId(0), "")], options, profile };
+    let doc = parser.parse();
+    if !doc.nodes.iter().any(|n| matches!(n.kind, NodeKind::Element { .. })) {
+        return Err(CharIter { pos: 0, text: xml }.unexpected_end_of_file().in_construct("document"));
+    }
+    Ok(doc)
+}
@@ -0,0 +1,145 @@
+//! Quick figures about a document, computed without building a tree, and detailed ones about the shape of a [Document].
+
+use std::collections::BTreeMap;
+
+use crate::chariter::CharIter;
+use crate::document::Document;
+use crate::error::XmlError;
+use crate::error::XmlError::UnexpectedXmlToken;
+use crate::namespace::declared_prefix;
+use crate::textrange::TextRange;
+use crate::token::XmlToken::*;
+use crate::tokenize::XmlTokenizer;
+use crate::writer::serialize;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentStats {
+    pub element_count: usize,
+    /// Nesting depth of elements, a document with only a root element has depth 1
+    pub max_depth: usize,
+    /// Attributes of all elements, including namespace declarations
+    pub attribute_count: usize,
+    /// Length of text and CDATA sections as they appear in the input
+    pub text_bytes: usize,
+    /// Number of distinct namespaces that are declared, the empty default namespace does not count
+    pub namespace_count: usize,
+}
+
+/// Compute the [DocumentStats] of a document in a single pass over its tokens.
+pub fn stats(xml: &str) -> Result<DocumentStats, XmlError> {
+    let tokens = XmlTokenizer::default().tokenize(xml)?;
+    // only used for error positions
+    let ci = CharIter { pos: 0, text: xml };
+    let mut stats = DocumentStats::default();
+    let mut open_ranges: Vec<TextRange> = vec![];
+    let mut namespaces: Vec<&str> = vec![];
+    for token in tokens {
+        match token {
+            StartTag(name_range) => {
+                open_ranges.push(name_range);
+                stats.element_count += 1;
+                stats.max_depth = stats.max_depth.max(open_ranges.len());
+            }
+            EndTag(name_range) => {
+                let start_range = match open_ranges.pop() {
+                    Some(start_range) => start_range,
+                    None => return Err(UnexpectedXmlToken { pos: ci.error_pos_of(name_range.start) })
+                };
+                if !start_range.name_eq(name_range.slice) {
+                    return Err(ci.non_matching_tags(start_range, name_range, &open_ranges));
+                }
+            }
+            Attribute { name_range, value_range } => {
+                stats.attribute_count += 1;
+                let is_declaration = declared_prefix(name_range.slice).is_some();
+                if is_declaration && !value_range.slice.is_empty() && !namespaces.contains(&value_range.slice) {
+                    namespaces.push(value_range.slice);
+                }
+            }
+            // spaces outside of the root element are not text
+            Text(range) | CdataSection(range) if !open_ranges.is_empty() => {
+                stats.text_bytes += range.slice.len();
+            }
+            _ => (),
+        }
+    }
+    if let Some(&start_range) = open_ranges.last() {
+        return Err(ci.unclosed_element(start_range));
+    }
+    stats.namespace_count = namespaces.len();
+    Ok(stats)
+}
+
+/// The shape of a document, e.g. to design a schema or to choose the limits of [ParseOptions](crate::options::ParseOptions),
+/// see [Document::metrics].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentMetrics {
+    /// The number of elements per depth, `depth_histogram[d - 1]` for depth `d`. The root element has depth 1.
+    pub depth_histogram: Vec<usize>,
+    /// The number of elements per qualified name
+    pub tag_counts: BTreeMap<String, usize>,
+    /// The number of elements per number of attributes, `attribute_histogram[n]` for `n` attributes,
+    /// including namespace declarations
+    pub attribute_histogram: Vec<usize>,
+    /// The number of elements with an attribute, per qualified name of the attribute
+    pub attribute_counts: BTreeMap<String, usize>,
+    /// Length of text and CDATA sections as they are serialized
+    pub text_bytes: usize,
+    /// Length of everything else of the serialized document: tags, comments, processing instructions and delimiters
+    pub markup_bytes: usize,
+}
+
+impl DocumentMetrics {
+    pub(crate) fn of(doc: &Document) -> Self {
+        let mut metrics = DocumentMetrics::default();
+        let mut text_bytes = 0;
+        for node in doc.descendants() {
+            if node.is_text() {
+                text_bytes += node.text().unwrap().len();
+            }
+            if !node.is_element() {
+                continue;
+            }
+            // the ancestors include the node and the document root
+            let depth = node.ancestors().count() - 1;
+            if metrics.depth_histogram.len() < depth {
+                metrics.depth_histogram.resize(depth, 0);
+            }
+            metrics.depth_histogram[depth - 1] += 1;
+            *metrics.tag_counts.entry(node.tag_name().to_string()).or_default() += 1;
+            let attribute_count = node.attributes().count();
+            if metrics.attribute_histogram.len() <= attribute_count {
+                metrics.attribute_histogram.resize(attribute_count + 1, 0);
+            }
+            metrics.attribute_histogram[attribute_count] += 1;
+            for attribute in node.attributes() {
+                *metrics.attribute_counts.entry(attribute.name().to_string()).or_default() += 1;
+            }
+        }
+        metrics.text_bytes = text_bytes;
+        metrics.markup_bytes = serialize(doc.root()).len() - text_bytes;
+        metrics
+    }
+
+    pub fn element_count(&self) -> usize {
+        self.depth_histogram.iter().sum()
+    }
+
+    /// The depth of the most deeply nested element, 0 without elements.
+    pub fn max_depth(&self) -> usize {
+        self.depth_histogram.len()
+    }
+
+    /// The highest number of attributes of an element.
+    pub fn max_attributes(&self) -> usize {
+        self.attribute_histogram.len().saturating_sub(1)
+    }
+
+    /// The share of text in the serialized document, between 0 and 1.
+    pub fn text_ratio(&self) -> f64 {
+        match self.text_bytes + self.markup_bytes {
+            0 => 0.0,
+            total => self.text_bytes as f64 / total as f64,
+        }
+    }
+}
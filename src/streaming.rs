@@ -0,0 +1,283 @@
+//! Evaluation of a restricted XPath subset over a [PullParser], without building a tree.
+//!
+//! A path is a sequence of steps on the forward axes `child` and `descendant`, including the abbreviation `//`,
+//! that may end with a step on the `attribute` axis or a `text()` step. Predicates are positions, e.g. `[2]`, on child steps,
+//! and tests of attributes, e.g. `[@id]` or `[@status = 'open']`, combined with `and`, `or` and `not()`.
+//! Relative paths are evaluated from the document root, like absolute ones.
+//!
+//! The state of the evaluation is one set of partially matched steps per open element, and the pull parser tokenizes
+//! one construct at a time, so besides the input, which is borrowed as a whole, e.g. from a memory-mapped file,
+//! memory grows with the depth of the document, not its size. Matches are reported before any error after them.
+//!
+//! ```
+//! use jaxp_rust::pull::PullParser;
+//! use jaxp_rust::streaming::{Match, StreamingPath};
+//!
+//! let path = StreamingPath::compile("//order[@status = 'open']/total/text()").unwrap();
+//! let xml = "<orders><order status='open'><total>20</total></order><order><total>5</total></order></orders>";
+//! let totals: Vec<Match> = path.matches(PullParser::new(xml).unwrap()).collect::<Result<_, _>>().unwrap();
+//! assert_eq!(vec![Match::Text("20")], totals);
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::document::{Attribute, Attributes};
+use crate::error::XmlError;
+use crate::namespace::declared_prefix;
+use crate::pull::{Event, PullParser};
+use crate::refs::decode_entities;
+use crate::xpath::{Axis, Expr, Function, NodeTest, Operator, PathStart, Step, XPath, XPathError};
+
+/// What a path selects.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Match<'a> {
+    /// An element, reported at its start tag. Attributes and text are reported as they appear in the markup.
+    Element { name: &'a str, attributes: Attributes<'a>, depth: usize },
+    Attribute(Attribute<'a>),
+    /// Character data or a CDATA section
+    Text(&'a str),
+}
+
+/// A condition of a predicate on the attributes of an element.
+#[derive(Clone, Debug)]
+enum Condition {
+    HasAttribute(String),
+    /// The attribute exists, and its value equals or differs from the value
+    AttributeEquals { name: String, value: String, is_equal: bool },
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    fn holds(&self, attributes: &Attributes) -> bool {
+        match self {
+            Condition::HasAttribute(name) => attributes.contains(name),
+            Condition::AttributeEquals { name, value, is_equal } => {
+                attributes.get(name).is_some_and(|a| (decode_entities(a.value()) == value.as_str()) == *is_equal)
+            }
+            Condition::Not(condition) => !condition.holds(attributes),
+            Condition::And(left, right) => left.holds(attributes) && right.holds(attributes),
+            Condition::Or(left, right) => left.holds(attributes) || right.holds(attributes),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Predicate {
+    /// The position among the siblings that pass the node test and the predicates before
+    Position(f64),
+    Condition(Condition),
+}
+
+#[derive(Clone, Debug)]
+enum StepKind {
+    Element { test: NodeTest, predicates: Vec<Predicate> },
+    Attribute(NodeTest),
+    Text,
+}
+
+#[derive(Clone, Debug)]
+struct StreamingStep {
+    /// The step selects descendants instead of children
+    is_descendant: bool,
+    kind: StepKind,
+}
+
+/// A path compiled for streaming evaluation.
+#[derive(Clone, Debug)]
+pub struct StreamingPath {
+    steps: Vec<StreamingStep>,
+}
+
+fn unsupported(construct: &str) -> XPathError {
+    XPathError::Unsupported(format!("{} in streaming evaluation", construct))
+}
+
+/// The name of an attribute that an expression selects with `@name`.
+fn attribute_name(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Path(PathStart::Context, steps) => match steps.as_slice() {
+            [Step { axis: Axis::Attribute, test: NodeTest::Name(name), predicates }] if predicates.is_empty() => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn compile_condition(expr: &Expr) -> Result<Condition, XPathError> {
+    if let Some(name) = attribute_name(expr) {
+        return Ok(Condition::HasAttribute(name.to_string()));
+    }
+    match expr {
+        Expr::Binary(operator @ (Operator::Equal | Operator::NotEqual), left, right) => {
+            let (name, value) = match (left.as_ref(), right.as_ref()) {
+                (attribute, Expr::Literal(value)) | (Expr::Literal(value), attribute) => (attribute_name(attribute), value),
+                _ => (None, &String::new()),
+            };
+            let name = name.ok_or_else(|| unsupported("a comparison other than of an attribute and a literal"))?;
+            Ok(Condition::AttributeEquals { name: name.to_string(), value: value.clone(), is_equal: *operator == Operator::Equal })
+        }
+        Expr::Binary(Operator::And, left, right) => Ok(Condition::And(Box::new(compile_condition(left)?), Box::new(compile_condition(right)?))),
+        Expr::Binary(Operator::Or, left, right) => Ok(Condition::Or(Box::new(compile_condition(left)?), Box::new(compile_condition(right)?))),
+        Expr::Function(Function::Not, args) => Ok(Condition::Not(Box::new(compile_condition(&args[0])?))),
+        _ => Err(unsupported("a predicate other than a position or a test of attributes")),
+    }
+}
+
+impl StreamingPath {
+    pub fn compile(path: &str) -> Result<StreamingPath, XPathError> {
+        let xpath = XPath::compile(path)?;
+        let steps = match xpath.expr() {
+            Expr::Path(PathStart::Root | PathStart::Context, steps) if !steps.is_empty() => steps,
+            _ => return Err(unsupported("an expression other than a location path")),
+        };
+        let mut streaming_steps = vec![];
+        // set by `//`, which applies to the next step
+        let mut is_descendant = false;
+        for step in steps {
+            let kind = match (step.axis, &step.test) {
+                (Axis::DescendantOrSelf, NodeTest::Node) if step.predicates.is_empty() => {
+                    is_descendant = true;
+                    continue;
+                }
+                (Axis::Child | Axis::Descendant, NodeTest::Any | NodeTest::Prefix(_) | NodeTest::Name(_)) => {
+                    let mut predicates = vec![];
+                    for predicate in &step.predicates {
+                        predicates.push(match predicate {
+                            Expr::Number(_) if step.axis == Axis::Descendant => return Err(unsupported("a position on a descendant step")),
+                            Expr::Number(position) => Predicate::Position(*position),
+                            condition => Predicate::Condition(compile_condition(condition)?),
+                        });
+                    }
+                    StepKind::Element { test: step.test.clone(), predicates }
+                }
+                (Axis::Child | Axis::Descendant, NodeTest::Text) if step.predicates.is_empty() => StepKind::Text,
+                (Axis::Attribute, NodeTest::Any | NodeTest::Prefix(_) | NodeTest::Name(_)) if step.predicates.is_empty() => {
+                    StepKind::Attribute(step.test.clone())
+                }
+                (Axis::Child | Axis::Descendant | Axis::Attribute, _) => return Err(unsupported("a node test or predicate other than on elements")),
+                _ => return Err(unsupported("an axis other than child, descendant and attribute")),
+            };
+            if !matches!(streaming_steps.last(), None | Some(StreamingStep { kind: StepKind::Element { .. }, .. })) {
+                return Err(unsupported("a step after an attribute or text() step"));
+            }
+            streaming_steps.push(StreamingStep { is_descendant: is_descendant || step.axis == Axis::Descendant, kind });
+            is_descendant = false;
+        }
+        Ok(StreamingPath { steps: streaming_steps })
+    }
+
+    /// Evaluate the path over the events of a parser, which should not have been read from yet.
+    pub fn matches<'p, 'a>(&'p self, parser: PullParser<'a>) -> Matches<'p, 'a> {
+        Matches { path: self, parser, frames: vec![Frame { states: vec![0], counters: HashMap::new() }], pending: VecDeque::new() }
+    }
+}
+
+/// The evaluation state of an open element, or the document root.
+struct Frame {
+    /// The number of steps matched by the element or an ancestor, whose next step applies to the children
+    /// or, for descendant steps, the descendants of the element
+    states: Vec<usize>,
+    /// How many children passed a node test and the predicates before, by step and predicate
+    counters: HashMap<(usize, usize), usize>,
+}
+
+/// The matches of a [StreamingPath] in document order.
+pub struct Matches<'p, 'a> {
+    path: &'p StreamingPath,
+    parser: PullParser<'a>,
+    frames: Vec<Frame>,
+    pending: VecDeque<Match<'a>>,
+}
+
+impl<'a> Matches<'_, 'a> {
+    /// The next match, or `None` at the end of the document and after an error.
+    pub fn next_match(&mut self) -> Result<Option<Match<'a>>, XmlError> {
+        loop {
+            if let Some(m) = self.pending.pop_front() {
+                return Ok(Some(m));
+            }
+            match self.parser.next_event()? {
+                None => return Ok(None),
+                Some(Event::StartElement { name, attributes, depth }) => self.start_element(name, attributes, depth),
+                Some(Event::EndElement { .. }) => {
+                    self.frames.pop();
+                }
+                Some(Event::Text(text) | Event::CdataSection(text)) => {
+                    let steps = &self.path.steps;
+                    let frame = self.frames.last().expect("text is inside of the root element");
+                    if frame.states.iter().any(|k| matches!(steps.get(*k), Some(StreamingStep { kind: StepKind::Text, .. }))) {
+                        self.pending.push_back(Match::Text(text));
+                    }
+                }
+                Some(_) => (),
+            }
+        }
+    }
+
+    fn start_element(&mut self, name: &'a str, attributes: Attributes<'a>, depth: usize) {
+        let steps = &self.path.steps;
+        let parent = self.frames.last_mut().expect("the document root is never closed");
+        let mut states = vec![];
+        let mut is_match = false;
+        for &k in &parent.states {
+            let step = &steps[k];
+            if step.is_descendant {
+                states.push(k);
+            }
+            let (test, predicates) = match &step.kind {
+                StepKind::Element { test, predicates } => (test, predicates),
+                _ => continue,
+            };
+            if !test.matches_name(name) || !passes(predicates, k, &attributes, &mut parent.counters) {
+                continue;
+            }
+            if k + 1 == steps.len() {
+                is_match = true;
+            } else {
+                states.push(k + 1);
+            }
+        }
+        states.sort_unstable();
+        states.dedup();
+        if is_match {
+            self.pending.push_back(Match::Element { name, attributes: attributes.clone(), depth });
+        }
+        // an attribute step is the last one
+        if let Some(StreamingStep { kind: StepKind::Attribute(test), .. }) = steps.last() {
+            if states.contains(&(steps.len() - 1)) {
+                // namespace declarations are not attributes in XPath
+                let selected = attributes.iter().filter(|a| declared_prefix(a.name()).is_none() && test.matches_name(a.name()));
+                self.pending.extend(selected.cloned().map(Match::Attribute));
+            }
+        }
+        self.frames.push(Frame { states, counters: HashMap::new() });
+    }
+}
+
+/// Whether an element passes the predicates of step `k`, the positions are counted in the frame of its parent.
+fn passes(predicates: &[Predicate], k: usize, attributes: &Attributes, counters: &mut HashMap<(usize, usize), usize>) -> bool {
+    for (i, predicate) in predicates.iter().enumerate() {
+        let holds = match predicate {
+            Predicate::Position(position) => {
+                let count = counters.entry((k, i)).or_insert(0);
+                *count += 1;
+                *count as f64 == *position
+            }
+            Predicate::Condition(condition) => condition.holds(attributes),
+        };
+        if !holds {
+            return false;
+        }
+    }
+    true
+}
+
+impl<'a> Iterator for Matches<'_, 'a> {
+    type Item = Result<Match<'a>, XmlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_match().transpose()
+    }
+}
@@ -0,0 +1,216 @@
+//! Helpers for testing code that consumes or produces XML.
+//!
+//! [assert_xml_eq!](crate::assert_xml_eq) compares documents by their trees and shows a [diff](crate::diff) on failure,
+//! [assert_golden] compares a document with a golden file. With the `arbitrary` feature, `ArbitraryDocument`
+//! generates random well-formed documents from unstructured bytes, e.g. to drive fuzz targets or property tests of round-tripping.
+//!
+//! ```
+//! jaxp_rust::assert_xml_eq!("<a y='2' x='1'>\n  <b/>\n</a>", "<a x=\"1\" y=\"2\"><b/></a>");
+//! ```
+
+use std::path::Path;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::diff::{diff, DiffOptions};
+use crate::document::Document;
+#[cfg(feature = "arbitrary")]
+use crate::writer::XmlWriter;
+use crate::writer::{serialize_pretty, PrettyPrintOptions};
+
+/// Set to `1` to let [assert_golden] write the actual document to the golden file instead of comparing.
+pub const UPDATE_GOLDEN_VAR: &str = "JAXP_UPDATE_GOLDEN";
+
+/// Assert that two documents have equal trees, ignoring whitespace between elements, runs of whitespace in text
+/// and the order of attributes, see [assert_xml_eq_with]. Takes anything that is `AsRef<str>`.
+#[macro_export]
+macro_rules! assert_xml_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::testutil::assert_xml_eq_with(
+            ::std::convert::AsRef::<str>::as_ref(&$actual),
+            ::std::convert::AsRef::<str>::as_ref(&$expected),
+            &$crate::testutil::lenient_diff_options(),
+        )
+    };
+}
+
+/// The options of [assert_xml_eq!](crate::assert_xml_eq): whitespace and the order of attributes are ignored.
+pub fn lenient_diff_options() -> DiffOptions {
+    DiffOptions { ignore_whitespace: true, ignore_attribute_order: true }
+}
+
+/// Assert that two documents have equal trees under the options. Panics with the differences,
+/// `-` for nodes that only the expected document has, `+` for those that only the actual one has and `~` for changed values,
+/// and both documents pretty printed. Also panics if a document is not well-formed.
+#[track_caller]
+pub fn assert_xml_eq_with(actual: &str, expected: &str, options: &DiffOptions) {
+    let parse = |xml, what| Document::parse(xml).unwrap_or_else(|e| panic!("the {} XML is not well-formed: {}\n{}", what, e, xml));
+    let (actual_doc, expected_doc) = (parse(actual, "actual"), parse(expected, "expected"));
+    let diff = diff(expected_doc.root(), actual_doc.root(), options);
+    if !diff.is_empty() {
+        let pretty = |doc: &Document| serialize_pretty(doc.root(), &PrettyPrintOptions::default());
+        panic!(
+            "XML documents differ (- expected, + actual):\n{}\nactual:\n{}\n\nexpected:\n{}\n",
+            diff,
+            pretty(&actual_doc),
+            pretty(&expected_doc),
+        );
+    }
+}
+
+/// Assert that a document is equal to the one in a golden file, like [assert_xml_eq!](crate::assert_xml_eq).
+/// If the environment variable [UPDATE_GOLDEN_VAR] is `1`, the actual document is written to the file
+/// instead, so that golden files are created and updated by running the tests with it.
+/// A missing golden file fails the assertion otherwise.
+#[track_caller]
+pub fn assert_golden(actual: &str, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    if std::env::var(UPDATE_GOLDEN_VAR).as_deref() == Ok("1") {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).unwrap_or_else(|e| panic!("cannot create {}: {}", dir.display(), e));
+        }
+        std::fs::write(path, actual).unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+        return;
+    }
+    if !path.exists() {
+        panic!("the golden file {} does not exist, run with {}=1 to create it", path.display(), UPDATE_GOLDEN_VAR);
+    }
+    let expected = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e));
+    assert_xml_eq_with(actual, &expected, &lenient_diff_options());
+}
+
+#[cfg(feature = "arbitrary")]
+/// Characters to build text from, chosen to exercise escaping, white space and multi-byte encodings.
+const TEXT_CHARS: &[char] = &['a', 'b', 'z', '0', ' ', '\n', '\t', '<', '>', '&', '"', '\'', ']', '-', '?', 'é', '€', '𝄞'];
+
+#[cfg(feature = "arbitrary")]
+const NAME_START_CHARS: &[char] = &['a', 'b', 'x', 'Z', '_', 'é'];
+
+#[cfg(feature = "arbitrary")]
+const NAME_CHARS: &[char] = &['a', 'b', 'x', 'Z', '_', 'é', '0', '9', '-', '.'];
+
+#[cfg(feature = "arbitrary")]
+/// Nesting depth of generated elements, keeps generation and recursive consumers bounded.
+const MAX_DEPTH: usize = 16;
+
+#[cfg(feature = "arbitrary")]
+/// A node of an [ArbitraryDocument]. Text is unescaped.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArbitraryNode {
+    Element { name: String, attributes: Vec<(String, String)>, children: Vec<ArbitraryNode> },
+    Text(String),
+    CdataSection(String),
+    Comment(String),
+    ProcessingInstruction { target: String, value: Option<String> },
+}
+
+#[cfg(feature = "arbitrary")]
+/// A random well-formed document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArbitraryDocument {
+    /// Always an element
+    pub root: ArbitraryNode,
+}
+
+#[cfg(feature = "arbitrary")]
+impl ArbitraryDocument {
+    /// Serialize the document with [XmlWriter].
+    pub fn to_xml(&self) -> String {
+        let mut writer = XmlWriter::new(Vec::new());
+        write_node(&self.root, &mut writer).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+fn write_node(node: &ArbitraryNode, writer: &mut XmlWriter<Vec<u8>>) -> std::io::Result<()> {
+    match node {
+        ArbitraryNode::Element { name, attributes, children } => {
+            writer.start_element(name)?;
+            for (name, value) in attributes {
+                writer.attribute(name, value)?;
+            }
+            for child in children {
+                write_node(child, writer)?;
+            }
+            writer.end_element()
+        }
+        ArbitraryNode::Text(text) => writer.text(text),
+        ArbitraryNode::CdataSection(text) => writer.cdata_section(text),
+        ArbitraryNode::Comment(text) => writer.comment(text),
+        ArbitraryNode::ProcessingInstruction { target, value } => writer.processing_instruction(target, value.as_deref()),
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_string(u: &mut Unstructured, chars: &[char], max_len: usize) -> Result<String> {
+    let len = u.int_in_range(0..=max_len)?;
+    (0..len).map(|_| u.choose(chars).copied()).collect()
+}
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_name(u: &mut Unstructured) -> Result<String> {
+    let mut name = u.choose(NAME_START_CHARS)?.to_string();
+    name.push_str(&arbitrary_string(u, NAME_CHARS, 7)?);
+    Ok(name)
+}
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_element(u: &mut Unstructured, depth: usize) -> Result<ArbitraryNode> {
+    let name = arbitrary_name(u)?;
+    let mut attributes: Vec<(String, String)> = vec![];
+    for _ in 0..u.int_in_range(0..=4)? {
+        let attribute_name = arbitrary_name(u)?;
+        if !attributes.iter().any(|(n, _)| *n == attribute_name) {
+            attributes.push((attribute_name, arbitrary_string(u, TEXT_CHARS, 16)?));
+        }
+    }
+    let mut children = vec![];
+    if depth < MAX_DEPTH {
+        for _ in 0..u.int_in_range(0..=6)? {
+            let child = match u.int_in_range(0..=9)? {
+                0..=3 => arbitrary_element(u, depth + 1)?,
+                4..=6 => ArbitraryNode::Text(arbitrary_string(u, TEXT_CHARS, 32)?),
+                7 => ArbitraryNode::CdataSection(arbitrary_string(u, TEXT_CHARS, 32)?.replace('>', "")),
+                8 => {
+                    let mut text = arbitrary_string(u, TEXT_CHARS, 32)?.replace('-', "_");
+                    if u.arbitrary()? {
+                        text.push_str("-a");
+                    }
+                    ArbitraryNode::Comment(text)
+                }
+                _ => {
+                    let mut target = arbitrary_name(u)?;
+                    if target.to_ascii_lowercase().starts_with("xml") {
+                        target.insert(0, '_');
+                    }
+                    let value = if u.arbitrary()? {
+                        // the value starts after the separating space
+                        Some(format!("v{}", arbitrary_string(u, TEXT_CHARS, 32)?.replace("?>", "? >")))
+                    } else {
+                        None
+                    };
+                    ArbitraryNode::ProcessingInstruction { target, value }
+                }
+            };
+            // adjacent text nodes are indistinguishable from a single one after parsing
+            let is_mergeable_text = match (&child, children.last()) {
+                (ArbitraryNode::Text(_), Some(ArbitraryNode::Text(_))) => true,
+                (ArbitraryNode::Text(text), _) => text.is_empty(),
+                _ => false,
+            };
+            if !is_mergeable_text {
+                children.push(child);
+            }
+        }
+    }
+    Ok(ArbitraryNode::Element { name, attributes, children })
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for ArbitraryDocument {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(ArbitraryDocument { root: arbitrary_element(u, 0)? })
+    }
+}
@@ -1,12 +1,151 @@
+use std::fmt::{Display, Formatter};
+
+use crate::chariter::CharIter;
+use crate::error::{XmlError, XmlErrorPos};
+use crate::xmlchar::{names_eq, XmlByte, XmlChar};
+
+/// A part of the input that a token refers to, by its byte offsets.
+/// Displayed as its text, serialized as its byte offsets into the input.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TextRange<'a> {
     pub(crate) start: usize,
     pub(crate) end: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) slice: &'a str
 }
 
-impl TextRange<'_> {
+impl<'a> TextRange<'a> {
+    /// The byte offset of the start of the range in the input.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The byte offset after the end of the range in the input.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The text of the range, without the check of [as_str](TextRange::as_str).
+    pub fn slice(&self) -> &'a str {
+        self.slice
+    }
+
+    /// The length in bytes.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
     pub fn is_empty(&self) -> bool {
         self.start >= self.end
     }
-}
\ No newline at end of file
+
+    /// The range without leading and trailing XML whitespace.
+    pub fn trim(&self) -> TextRange<'a> {
+        let slice = self.slice.trim_start_matches(|c: char| (c as u32) < 0x80 && (c as u8).is_xml_whitespace());
+        let start = self.end - slice.len();
+        let slice = slice.trim_end_matches(|c: char| (c as u32) < 0x80 && (c as u8).is_xml_whitespace());
+        TextRange { start, end: start + slice.len(), slice }
+    }
+
+    /// The positions of the start and the end of the range, see [LineIndex::pos].
+    pub fn line_col(&self, index: &LineIndex) -> (XmlErrorPos, XmlErrorPos) {
+        (index.pos(self.start), index.pos(self.end))
+    }
+
+    /// The text of the range, after checking that its characters are XML Chars.
+    /// Tokenizing with [lazy_char_validation](crate::options::ParseOptions::lazy_char_validation) leaves that check to this method.
+    pub fn as_str(&self) -> Result<&'a str, InvalidChar> {
+        match self.slice.char_indices().find(|(_, c)| !c.is_xml_char()) {
+            Some((i, character)) => Err(InvalidChar { offset: self.start + i, character }),
+            None => Ok(self.slice),
+        }
+    }
+
+    /// Whether the text of the range is a name, with a cheap check of length and first and last byte first.
+    pub fn name_eq(&self, name: &str) -> bool {
+        names_eq(self.slice, name)
+    }
+}
+
+impl Display for TextRange<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str(self.slice)
+    }
+}
+
+/// The line breaks of an input, to turn byte offsets into positions like those of errors in logarithmic time.
+#[derive(Clone, Debug)]
+pub struct LineIndex {
+    /// The byte offsets of the line feeds
+    line_breaks: Vec<usize>,
+    /// The byte offsets of the continuation bytes of multibyte characters, to count characters instead of bytes
+    continuation_bytes: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let offsets_of = |is_match: fn(u8) -> bool| text.bytes().enumerate().filter(|&(_, b)| is_match(b)).map(|(i, _)| i).collect();
+        LineIndex { line_breaks: offsets_of(|b| b == b'\n'), continuation_bytes: offsets_of(is_continuation_byte), len: text.len() }
+    }
+
+    /// The position of a byte offset, the same as that of an [XmlError] at the offset, see [XmlErrorPos].
+    /// Offsets past the end of the input are clamped to it.
+    pub fn pos(&self, offset: usize) -> XmlErrorPos {
+        let offset = offset.min(self.len);
+        let row = self.line_breaks.partition_point(|&i| i < offset) + 1;
+        let line_start = if row == 1 { 0 } else { self.line_breaks[row - 2] + 1 };
+        let continuation_count = self.continuation_bytes.partition_point(|&i| i < offset) - self.continuation_bytes.partition_point(|&i| i < line_start);
+        XmlErrorPos { row, col: offset - line_start - continuation_count + 1 }
+    }
+}
+
+/// Whether a byte of UTF-8 continues a multibyte character instead of starting a character.
+pub(crate) fn is_continuation_byte(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// A character that is not allowed in XML, found by [TextRange::as_str].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidChar {
+    /// The byte offset of the character in the input
+    pub offset: usize,
+    pub character: char,
+}
+
+impl InvalidChar {
+    /// The error that tokenizing without lazy validation reports, with the position in the input.
+    pub fn to_xml_error(&self, xml: &str) -> XmlError {
+        XmlError::IllegalToken { pos: CharIter { pos: 0, text: xml }.error_pos_of(self.offset), expected: None, fix: None }
+    }
+}
+
+impl Display for InvalidChar {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "invalid character {:?} at offset {}", self.character, self.offset)
+    }
+}
+
+impl std::error::Error for InvalidChar {}
+
+/// A [TextRange] that owns a copy of its text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedTextRange {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) text: String,
+}
+
+impl OwnedTextRange {
+    pub fn borrowed(&self) -> TextRange<'_> {
+        TextRange { start: self.start, end: self.end, slice: &self.text }
+    }
+}
+
+impl From<TextRange<'_>> for OwnedTextRange {
+    fn from(range: TextRange<'_>) -> Self {
+        OwnedTextRange { start: range.start, end: range.end, text: range.slice.to_string() }
+    }
+}
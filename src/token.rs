@@ -1,6 +1,7 @@
-use crate::textrange::TextRange;
+use crate::textrange::{OwnedTextRange, TextRange};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum XmlToken<'a> {
     Text(TextRange<'a>),
     StartTag(TextRange<'a>),
@@ -26,6 +27,209 @@ pub enum XmlToken<'a> {
         name_range: TextRange<'a>,
         opt_system_entity_range: Option<TextRange<'a>>,
         opt_public_entity_range: Option<TextRange<'a>>,
+        /// The text between the brackets of the internal subset
+        opt_internal_subset_range: Option<TextRange<'a>>,
     },
     ParameterEntityReference(TextRange<'a>),
-}
\ No newline at end of file
+}
+
+/// An [XmlToken] that owns its text, so it can outlive the input, e.g. to be sent to another thread.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XmlTokenOwned {
+    Text(OwnedTextRange),
+    StartTag(OwnedTextRange),
+    EndTag(OwnedTextRange),
+    CdataSection(OwnedTextRange),
+    Comment(OwnedTextRange),
+    ProcessingInstruction {
+        target_range: OwnedTextRange,
+        opt_value_range: Option<OwnedTextRange>,
+    },
+    Attribute {
+        name_range: OwnedTextRange,
+        value_range: OwnedTextRange,
+    },
+
+    // Prolog tokens
+    XmlDeclaration {
+        version_range: OwnedTextRange,
+        opt_encoding_range: Option<OwnedTextRange>,
+        opt_standalone_range: Option<OwnedTextRange>,
+    },
+    DocTypeDeclaration {
+        name_range: OwnedTextRange,
+        opt_system_entity_range: Option<OwnedTextRange>,
+        opt_public_entity_range: Option<OwnedTextRange>,
+        opt_internal_subset_range: Option<OwnedTextRange>,
+    },
+    ParameterEntityReference(OwnedTextRange),
+}
+
+impl<'a> XmlToken<'a> {
+    /// The position of the first range of the token in the input.
+    pub(crate) fn start(&self) -> usize {
+        match self {
+            XmlToken::Text(range)
+            | XmlToken::StartTag(range)
+            | XmlToken::EndTag(range)
+            | XmlToken::CdataSection(range)
+            | XmlToken::Comment(range)
+            | XmlToken::ParameterEntityReference(range)
+            | XmlToken::ProcessingInstruction { target_range: range, .. }
+            | XmlToken::Attribute { name_range: range, .. }
+            | XmlToken::XmlDeclaration { version_range: range, .. }
+            | XmlToken::DocTypeDeclaration { name_range: range, .. } => range.start,
+        }
+    }
+
+    /// The kind of the token in kebab case, e.g. `start-tag`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            XmlToken::Text(_) => "text",
+            XmlToken::StartTag(_) => "start-tag",
+            XmlToken::EndTag(_) => "end-tag",
+            XmlToken::CdataSection(_) => "cdata-section",
+            XmlToken::Comment(_) => "comment",
+            XmlToken::ProcessingInstruction { .. } => "processing-instruction",
+            XmlToken::Attribute { .. } => "attribute",
+            XmlToken::XmlDeclaration { .. } => "xml-declaration",
+            XmlToken::DocTypeDeclaration { .. } => "doctype-declaration",
+            XmlToken::ParameterEntityReference(_) => "parameter-entity-reference",
+        }
+    }
+
+    /// The ranges of the token by the names of its fields without `_range` and `opt_`, the ones that are present.
+    pub fn ranges(&self) -> Vec<(&'static str, TextRange<'a>)> {
+        let mut ranges = vec![];
+        let mut add = |name, range: &Option<TextRange<'a>>| ranges.extend(range.map(|range| (name, range)));
+        match self {
+            XmlToken::Text(range) | XmlToken::CdataSection(range) | XmlToken::Comment(range) => add("text", &Some(*range)),
+            XmlToken::StartTag(range) | XmlToken::EndTag(range) | XmlToken::ParameterEntityReference(range) => add("name", &Some(*range)),
+            XmlToken::ProcessingInstruction { target_range, opt_value_range } => {
+                add("target", &Some(*target_range));
+                add("value", opt_value_range);
+            }
+            XmlToken::Attribute { name_range, value_range } => {
+                add("name", &Some(*name_range));
+                add("value", &Some(*value_range));
+            }
+            XmlToken::XmlDeclaration { version_range, opt_encoding_range, opt_standalone_range } => {
+                add("version", &Some(*version_range));
+                add("encoding", opt_encoding_range);
+                add("standalone", opt_standalone_range);
+            }
+            XmlToken::DocTypeDeclaration { name_range, opt_system_entity_range, opt_public_entity_range, opt_internal_subset_range } => {
+                add("name", &Some(*name_range));
+                add("system_entity", opt_system_entity_range);
+                add("public_entity", opt_public_entity_range);
+                add("internal_subset", opt_internal_subset_range);
+            }
+        }
+        ranges
+    }
+
+    /// The same token with every range replaced, e.g. to point into another copy of the input.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn map_ranges<'b>(&self, f: impl Fn(TextRange<'a>) -> TextRange<'b>) -> XmlToken<'b> {
+        match self {
+            XmlToken::Text(range) => XmlToken::Text(f(*range)),
+            XmlToken::StartTag(range) => XmlToken::StartTag(f(*range)),
+            XmlToken::EndTag(range) => XmlToken::EndTag(f(*range)),
+            XmlToken::CdataSection(range) => XmlToken::CdataSection(f(*range)),
+            XmlToken::Comment(range) => XmlToken::Comment(f(*range)),
+            XmlToken::ProcessingInstruction { target_range, opt_value_range } => XmlToken::ProcessingInstruction {
+                target_range: f(*target_range),
+                opt_value_range: opt_value_range.map(&f),
+            },
+            XmlToken::Attribute { name_range, value_range } => XmlToken::Attribute {
+                name_range: f(*name_range),
+                value_range: f(*value_range),
+            },
+            XmlToken::XmlDeclaration { version_range, opt_encoding_range, opt_standalone_range } => XmlToken::XmlDeclaration {
+                version_range: f(*version_range),
+                opt_encoding_range: opt_encoding_range.map(&f),
+                opt_standalone_range: opt_standalone_range.map(&f),
+            },
+            XmlToken::DocTypeDeclaration { name_range, opt_system_entity_range, opt_public_entity_range, opt_internal_subset_range } => XmlToken::DocTypeDeclaration {
+                name_range: f(*name_range),
+                opt_system_entity_range: opt_system_entity_range.map(&f),
+                opt_public_entity_range: opt_public_entity_range.map(&f),
+                opt_internal_subset_range: opt_internal_subset_range.map(&f),
+            },
+            XmlToken::ParameterEntityReference(range) => XmlToken::ParameterEntityReference(f(*range)),
+        }
+    }
+
+    /// Copy the text of the token.
+    pub fn to_owned(&self) -> XmlTokenOwned {
+        let own = |range: &TextRange| OwnedTextRange::from(*range);
+        let own_opt = |range: &Option<TextRange>| range.map(OwnedTextRange::from);
+        match self {
+            XmlToken::Text(range) => XmlTokenOwned::Text(own(range)),
+            XmlToken::StartTag(range) => XmlTokenOwned::StartTag(own(range)),
+            XmlToken::EndTag(range) => XmlTokenOwned::EndTag(own(range)),
+            XmlToken::CdataSection(range) => XmlTokenOwned::CdataSection(own(range)),
+            XmlToken::Comment(range) => XmlTokenOwned::Comment(own(range)),
+            XmlToken::ProcessingInstruction { target_range, opt_value_range } => XmlTokenOwned::ProcessingInstruction {
+                target_range: own(target_range),
+                opt_value_range: own_opt(opt_value_range),
+            },
+            XmlToken::Attribute { name_range, value_range } => XmlTokenOwned::Attribute {
+                name_range: own(name_range),
+                value_range: own(value_range),
+            },
+            XmlToken::XmlDeclaration { version_range, opt_encoding_range, opt_standalone_range } => XmlTokenOwned::XmlDeclaration {
+                version_range: own(version_range),
+                opt_encoding_range: own_opt(opt_encoding_range),
+                opt_standalone_range: own_opt(opt_standalone_range),
+            },
+            XmlToken::DocTypeDeclaration { name_range, opt_system_entity_range, opt_public_entity_range, opt_internal_subset_range } => XmlTokenOwned::DocTypeDeclaration {
+                name_range: own(name_range),
+                opt_system_entity_range: own_opt(opt_system_entity_range),
+                opt_public_entity_range: own_opt(opt_public_entity_range),
+                opt_internal_subset_range: own_opt(opt_internal_subset_range),
+            },
+            XmlToken::ParameterEntityReference(range) => XmlTokenOwned::ParameterEntityReference(own(range)),
+        }
+    }
+}
+
+impl XmlTokenOwned {
+    /// A token that borrows the text of this one.
+    pub fn borrowed(&self) -> XmlToken<'_> {
+        fn borrow(range: &OwnedTextRange) -> TextRange<'_> {
+            range.borrowed()
+        }
+        fn borrow_opt(range: &Option<OwnedTextRange>) -> Option<TextRange<'_>> {
+            range.as_ref().map(OwnedTextRange::borrowed)
+        }
+        match self {
+            XmlTokenOwned::Text(range) => XmlToken::Text(borrow(range)),
+            XmlTokenOwned::StartTag(range) => XmlToken::StartTag(borrow(range)),
+            XmlTokenOwned::EndTag(range) => XmlToken::EndTag(borrow(range)),
+            XmlTokenOwned::CdataSection(range) => XmlToken::CdataSection(borrow(range)),
+            XmlTokenOwned::Comment(range) => XmlToken::Comment(borrow(range)),
+            XmlTokenOwned::ProcessingInstruction { target_range, opt_value_range } => XmlToken::ProcessingInstruction {
+                target_range: borrow(target_range),
+                opt_value_range: borrow_opt(opt_value_range),
+            },
+            XmlTokenOwned::Attribute { name_range, value_range } => XmlToken::Attribute {
+                name_range: borrow(name_range),
+                value_range: borrow(value_range),
+            },
+            XmlTokenOwned::XmlDeclaration { version_range, opt_encoding_range, opt_standalone_range } => XmlToken::XmlDeclaration {
+                version_range: borrow(version_range),
+                opt_encoding_range: borrow_opt(opt_encoding_range),
+                opt_standalone_range: borrow_opt(opt_standalone_range),
+            },
+            XmlTokenOwned::DocTypeDeclaration { name_range, opt_system_entity_range, opt_public_entity_range, opt_internal_subset_range } => XmlToken::DocTypeDeclaration {
+                name_range: borrow(name_range),
+                opt_system_entity_range: borrow_opt(opt_system_entity_range),
+                opt_public_entity_range: borrow_opt(opt_public_entity_range),
+                opt_internal_subset_range: borrow_opt(opt_internal_subset_range),
+            },
+            XmlTokenOwned::ParameterEntityReference(range) => XmlToken::ParameterEntityReference(borrow(range)),
+        }
+    }
+}
@@ -1,96 +1,288 @@
-use std::str::FromStr;
-
-use crate::chariter::CharIter;
-use crate::error::XmlError;
-use crate::error::XmlError::{IllegalToken, UnknownReference};
+use crate::entities::EntityTable;
+use crate::error::{Expected, Fix, XmlError};
+use crate::error::XmlError::{DisallowedConstruct, IllegalToken, LeadingGarbage, LimitExceeded, UnknownReference};
+use crate::dfa;
+use crate::options::{CommentPolicy, DoctypePolicy, ParseOptions, TokenizerBackend};
+use crate::refs::{self, CharRefPolicy};
 use crate::textrange::TextRange;
-use crate::token::XmlToken;
+use crate::token::{XmlToken, XmlTokenOwned};
 use crate::token::XmlToken::*;
-use crate::util;
 use crate::xmlchar::{XmlByte, XmlChar};
 
-pub struct XmlTokenizer {}
+pub use crate::chariter::CharIter;
 
-impl Default for XmlTokenizer {
-    fn default() -> Self {
-        XmlTokenizer {}
-    }
+/// U+FEFF, which may start a document to mark its encoding
+pub(crate) const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// Empty a token buffer for use with another input, keeping its allocation.
+pub fn recycle_tokens<'b>(mut tokens: Vec<XmlToken<'_>>) -> Vec<XmlToken<'b>> {
+    tokens.clear();
+    // collecting from the emptied vector reuses its allocation, as the layout of tokens does not depend on the lifetime
+    tokens.into_iter().map(|_| unreachable!()).collect()
+}
+
+#[derive(Default)]
+pub struct XmlTokenizer {
+    /// Accept `--` inside of comments and a hyphen as their last character
+    relaxed_comments: bool,
+    /// Named entities accepted in references besides the predefined ones
+    entities: EntityTable,
+    doctype: DoctypePolicy,
+    /// Whether the current document is declared standalone, registered entities count as external then
+    is_standalone: bool,
+    /// The maximum length of comments, processing instructions, CDATA sections and skipped document type declarations
+    max_lookahead: Option<usize>,
+    /// Scan content by its delimiters only, see [ParseOptions::lazy_char_validation]
+    lazy_char_validation: bool,
+    backend: TokenizerBackend,
+    /// The characters that character references may refer to
+    char_refs: CharRefPolicy,
 }
 
 
 impl<'a> XmlTokenizer {
+    pub fn with_options(options: &ParseOptions) -> Self {
+        XmlTokenizer {
+            relaxed_comments: options.comments == CommentPolicy::AllowDoubleHyphen,
+            entities: options.entities.clone(),
+            doctype: options.doctype,
+            is_standalone: false,
+            max_lookahead: options.max_lookahead,
+            lazy_char_validation: options.lazy_char_validation,
+            backend: options.tokenizer_backend,
+            char_refs: options.char_refs,
+        }
+    }
+
     pub fn tokenize(&mut self, xml: &'a str) -> Result<Vec<XmlToken<'a>>, XmlError> {
+        let mut tokens = vec![];
+        self.tokenize_into(xml, &mut tokens)?;
+        Ok(tokens)
+    }
+
+    /// Tokenize into tokens that own their text and do not borrow the input.
+    pub fn tokenize_owned(&mut self, xml: &str) -> Result<Vec<XmlTokenOwned>, XmlError> {
+        Ok(self.tokenize(xml)?.iter().map(XmlToken::to_owned).collect())
+    }
+
+    /// Tokenize into a buffer, which is cleared first. Reusing a buffer across documents saves
+    /// allocations, see [recycle_tokens] for buffers of inputs that do not live long enough.
+    pub fn tokenize_into(&mut self, xml: &'a str, tokens: &mut Vec<XmlToken<'a>>) -> Result<(), XmlError> {
+        trace_span!("tokenize", input_len = xml.len());
+        let mut ci = CharIter { pos: 0, text: xml };
+
+        self.reset();
+        tokens.clear();
+        // average token length of ~20 bytes
+        tokens.reserve(xml.len() / 20);
+        let result = match self.backend {
+            TokenizerBackend::RecursiveDescent => self.tokenize_document(&mut ci, tokens, &mut |_| false).map(|_| ()),
+            TokenizerBackend::Dfa => self.tokenize_document_dfa(&mut ci, tokens),
+        };
+        let result = result.map_err(|e| e.in_construct("document"));
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(_) => tracing::debug!(token_count = tokens.len(), "tokenized"),
+            Err(e) => tracing::debug!(error = %e, pos = ci.pos(), "tokenization failed"),
+        }
+        result
+    }
+
+    /// Tokenize a document up to the first markup after which `is_end` returns true for the tokens so far,
+    /// or up to its end. Returns whether tokenizing ended early, the rest of the input is not checked then.
+    pub(crate) fn tokenize_until(&mut self, xml: &'a str, tokens: &mut Vec<XmlToken<'a>>, mut is_end: impl FnMut(&[XmlToken<'a>]) -> bool) -> Result<bool, XmlError> {
         let mut ci = CharIter { pos: 0, text: xml };
+        self.reset();
+        tokens.clear();
+        self.tokenize_document(&mut ci, tokens, &mut is_end).map_err(|e| e.in_construct("document"))
+    }
+
+    /// Start tokenizing a document incrementally: the byte order mark and the prolog, the content follows
+    /// construct by construct with [tokenize_content_step](XmlTokenizer::tokenize_content_step).
+    /// Only recursive descent tokenizes incrementally, the configured backend is not used.
+    pub(crate) fn tokenize_start(&mut self, ci: &mut CharIter<'a>, tokens: &mut Vec<XmlToken<'a>>) -> Result<(), XmlError> {
+        self.reset();
+        self.tokenize_document_start(ci, tokens).map_err(|e| e.in_construct("document"))
+    }
 
-        return Self::tokenize_document(&mut ci);
+    /// Forget the state of the previous document, the configuration is kept.
+    /// Tokenizing a document starts with a reset, so this is only needed to release state early.
+    pub fn reset(&mut self) {
+        self.is_standalone = false;
     }
 
     /// [\[1\] document](https://www.w3.org/TR/xml/#NT-document)
-    fn tokenize_document(ci: &mut CharIter<'a>) -> Result<Vec<XmlToken<'a>>, XmlError> {
-        let mut tokens = Self::tokenize_prolog(ci)?;
-        tokens.append(&mut Self::tokenize_content(ci)?);
-        return Ok(tokens);
+    fn tokenize_document(&mut self, ci: &mut CharIter<'a>, tokens: &mut Vec<XmlToken<'a>>, is_end: &mut impl FnMut(&[XmlToken<'a>]) -> bool) -> Result<bool, XmlError> {
+        self.tokenize_document_start(ci, tokens)?;
+        self.tokenize_content(ci, tokens, is_end)
+    }
+
+    /// Tokenize the content with the state machine of [dfa], and the whole document again with recursive descent
+    /// for the error if the machine gives up.
+    fn tokenize_document_dfa(&mut self, ci: &mut CharIter<'a>, tokens: &mut Vec<XmlToken<'a>>) -> Result<(), XmlError> {
+        if self.tokenize_document_start(ci, tokens).is_ok() && dfa::tokenize_content(self, ci, tokens).is_some() {
+            return Ok(());
+        }
+        trace_event!(pos = ci.pos(), "falling back to recursive descent");
+        ci.pos = 0;
+        tokens.clear();
+        self.tokenize_document(ci, tokens, &mut |_| false).map(|_| ())
+    }
+
+    /// The document up to its content: a byte order mark and the prolog.
+    fn tokenize_document_start(&mut self, ci: &mut CharIter<'a>, tokens: &mut Vec<XmlToken<'a>>) -> Result<(), XmlError> {
+        // the UTF-8 byte order mark is not part of the document, the ranges of the tokens are still offsets in the input
+        if ci.text.starts_with(BYTE_ORDER_MARK) {
+            ci.pos = BYTE_ORDER_MARK.len_utf8();
+        }
+        let rest = &ci.text.as_bytes()[ci.pos..];
+        if let Some(start) = rest.iter().position(|b| !b.is_xml_whitespace()).filter(|&i| rest[i] != b'<') {
+            let len = rest[start..].iter().position(|&b| b == b'<').unwrap_or(rest.len() - start);
+            return Err(LeadingGarbage { pos: ci.error_pos_of(ci.pos + start), len });
+        }
+        self.tokenize_prolog(ci, tokens)?;
+        self.is_standalone = matches!(
+            tokens.first(),
+            Some(XmlDeclaration { opt_standalone_range: Some(range), .. }) if range.slice == "yes"
+        );
+        Ok(())
     }
 
     /// [\[22\] prolog](https://www.w3.org/TR/xml/#NT-prolog)
-    fn tokenize_prolog(ci: &mut CharIter<'a>) -> Result<Vec<XmlToken<'a>>, XmlError> {
-        let mut tokens = vec![];
-        if ci.test(b"<?xml") {
-            tokens.push(Self::tokenize_xml_declaration(ci)?);
+    fn tokenize_prolog(&self, ci: &mut CharIter<'a>, tokens: &mut Vec<XmlToken<'a>>) -> Result<(), XmlError> {
+        trace_span!("prolog");
+        // not a processing instruction like `<?xml-stylesheet ...?>`
+        if ci.test(b"<?xml") && ci.text.as_bytes().get(ci.pos() + 5).is_some_and(|b| b.is_xml_whitespace()) {
+            tokens.push(Self::tokenize_construct(ci, "XML declaration", None, Self::tokenize_xml_declaration)?);
         }
         while ci.peek_byte()?.is_xml_whitespace() || ci.test(b"<!--") || ci.test(b"<?") {
             // TODO lift space here for performance
-            match Self::tokenize_misc(ci)? {
-                Some(token) => tokens.push(token),
-                None => ()
+            if let Some(token) = self.tokenize_misc(ci)? {
+                tokens.push(token);
             }
         }
         if ci.test(b"<!DOCTYPE") {
-            tokens.append(&mut Self::tokenize_doctype_declaration(ci)?);
+            match self.doctype {
+                DoctypePolicy::Error => return Err(DisallowedConstruct {
+                    pos: ci.error_pos(),
+                    construct: "document type declaration".to_string(),
+                }),
+                DoctypePolicy::Ignore => Self::tokenize_construct(ci, "document type declaration", None, |ci| self.skip_doctype_declaration(ci))?,
+                DoctypePolicy::Process => tokens.append(&mut Self::tokenize_construct(ci, "document type declaration", None, |ci| self.tokenize_doctype_declaration(ci))?),
+            }
 
             while ci.peek_byte()?.is_xml_whitespace() || ci.test(b"<!--") || ci.test(b"<?") {
                 // TODO lift space here for performance
-                match Self::tokenize_misc(ci)? {
-                    Some(token) => tokens.push(token),
-                    None => ()
+                if let Some(token) = self.tokenize_misc(ci)? {
+                    tokens.push(token);
                 }
             }
         }
-        Ok(tokens)
+        Ok(())
+    }
+
+    /// Tokenize a construct, naming it in end of file errors with its start
+    /// and the name that follows its start by `opt_name_offset` bytes, e.g. that of a start tag.
+    fn tokenize_construct<T>(ci: &mut CharIter<'a>, construct: &str, opt_name_offset: Option<usize>, tokenize: impl FnOnce(&mut CharIter<'a>) -> Result<T, XmlError>) -> Result<T, XmlError> {
+        let start = ci.pos();
+        tokenize(ci).map_err(|e| {
+            let opt_name = opt_name_offset.map(|offset| {
+                let rest = ci.text.get(start + offset..).unwrap_or_default();
+                &rest[..rest.find(|c: char| !c.is_xml_name_char()).unwrap_or(rest.len())]
+            });
+            e.in_construct_at(construct, ci.error_pos_of(start), opt_name)
+        })
     }
 
     /// [\[27\] Misc](https://www.w3.org/TR/xml/#NT-Misc)
-    fn tokenize_misc(ci: &mut CharIter<'a>) -> Result<Option<XmlToken<'a>>, XmlError> {
-        return if ci.peek_byte()?.is_xml_whitespace() {
+    fn tokenize_misc(&self, ci: &mut CharIter<'a>) -> Result<Option<XmlToken<'a>>, XmlError> {
+        if ci.peek_byte()?.is_xml_whitespace() {
             ci.advance_n(1)?;
             Ok(None)
         } else if ci.test(b"<!--") {
-            Ok(Some(Self::tokenize_comment(ci)?))
+            Ok(Some(Self::tokenize_construct(ci, "comment", None, |ci| self.tokenize_comment(ci))?))
         } else if ci.test(b"<?") {
-            Ok(Some(Self::tokenize_processing_instruction(ci)?))
+            Ok(Some(Self::tokenize_construct(ci, "processing instruction", Some(2), |ci| self.tokenize_processing_instruction(ci))?))
         } else {
             Err(IllegalToken {
                 pos: ci.error_pos(),
-                expected: Some("Space or Start of Comment or Processing Instruction".to_string()),
+                expected: Some(Expected::SpaceOrCommentOrProcessingInstruction),
+                fix: None,
             })
-        };
+        }
     }
 
     /// [\[28b\] intSubset](https://www.w3.org/TR/xml/#NT-intSubset)
-    fn tokenize_internal_subset(ci: &mut CharIter<'a>) -> Result<Vec<XmlToken<'a>>, XmlError> {
+    fn tokenize_internal_subset(&self, ci: &mut CharIter<'a>) -> Result<Vec<XmlToken<'a>>, XmlError> {
+        trace_span!("internal_subset", start = ci.pos());
         let mut tokens = vec![];
         while !ci.test_byte(b']') {
             // [\[28a\] DeclSep](https://www.w3.org/TR/xml/#NT-DeclSep)
             ci.skip_spaces();
             if ci.test_byte(b'%') {
                 tokens.push(ParameterEntityReference(Self::consume_parameter_entity_reference(ci)?));
-            } else {
-                // TODO test for markup declarations
+            } else if ci.test(b"<!--") {
+                // comments and processing instructions of the DTD are not part of the document
+                Self::tokenize_construct(ci, "comment", None, |ci| self.tokenize_comment(ci))?;
+            } else if ci.test(b"<?") {
+                Self::tokenize_construct(ci, "processing instruction", Some(2), |ci| self.tokenize_processing_instruction(ci))?;
+            } else if ci.test(b"<!") {
+                // TODO tokenize markup declarations
+                trace_event!(pos = ci.pos(), "skipping markup declaration");
+                Self::tokenize_construct(ci, "markup declaration", None, Self::skip_markup_declaration)?;
+            } else if ci.peek_byte()? != b']' {
+                return Err(IllegalToken {
+                    pos: ci.error_pos(),
+                    expected: Some(Expected::MarkupDeclarationOrSeparator),
+                    fix: None,
+                });
             }
         }
         Ok(tokens)
     }
 
+    /// [\[29\] markupdecl](https://www.w3.org/TR/xml/#NT-markupdecl)
+    /// Skip a declaration up to its closing `>`, which may also occur inside of quoted literals.
+    fn skip_markup_declaration(ci: &mut CharIter<'a>) -> Result<(), XmlError> {
+        ci.skip_over(b"<!")?;
+        loop {
+            match ci.next_byte()? {
+                b'>' => return Ok(()),
+                quote @ (b'"' | b'\'') => {
+                    while ci.next_byte()? != quote {}
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Skip a document type declaration up to its closing `>`, which may also occur inside of
+    /// quoted literals, comments, processing instructions and the internal subset.
+    /// The input is scanned as raw bytes, neither characters nor markup declarations are checked.
+    fn skip_doctype_declaration(&self, ci: &mut CharIter<'a>) -> Result<(), XmlError> {
+        trace_event!(pos = ci.pos(), "skipping document type declaration");
+        let rest = &ci.text.as_bytes()[ci.pos()..];
+        let window = &rest[..rest.len().min(self.max_lookahead.unwrap_or(usize::MAX))];
+        match doctype_declaration_len(window) {
+            Some(len) => ci.advance_n(len),
+            None => match self.max_lookahead {
+                Some(max) if window.len() < rest.len() => Err(LimitExceeded { limit: "lookahead".to_string(), max }),
+                _ => {
+                    ci.pos = ci.text.len();
+                    Err(ci.unexpected_end_of_file())
+                }
+            },
+        }
+    }
+
+    /// Fail if a construct that started at `start_pos` is longer than [ParseOptions::max_lookahead].
+    fn check_lookahead(&self, ci: &CharIter<'a>, start_pos: usize) -> Result<(), XmlError> {
+        match self.max_lookahead {
+            Some(max) if ci.pos() - start_pos >= max => Err(LimitExceeded { limit: "lookahead".to_string(), max }),
+            _ => Ok(()),
+        }
+    }
+
     /// [\[69\] PEReference](https://www.w3.org/TR/xml/#NT-PEReference)
     fn consume_parameter_entity_reference(ci: &mut CharIter<'a>) -> Result<TextRange<'a>, XmlError> {
         ci.expect_byte(b'%')?;
@@ -100,7 +292,8 @@ impl<'a> XmlTokenizer {
     }
 
     /// [\[28\] doctypedecl](https://www.w3.org/TR/xml/#NT-doctypedecl)
-    fn tokenize_doctype_declaration(ci: &mut CharIter<'a>) -> Result<Vec<XmlToken<'a>>, XmlError> {
+    fn tokenize_doctype_declaration(&self, ci: &mut CharIter<'a>) -> Result<Vec<XmlToken<'a>>, XmlError> {
+        trace_span!("doctype", start = ci.pos());
         let mut tokens = vec![];
         ci.expect_bytes(b"<!DOCTYPE")?;
         ci.expect_spaces()?;
@@ -110,52 +303,58 @@ impl<'a> XmlTokenizer {
         // externalID ?
         if ci.test_after_spaces(b"PUBLIC") || ci.test_after_spaces(b"SYSTEM") {
             ci.expect_spaces()?;
-            (opt_system_entity_range, opt_public_entity_range) = Self::consume_external_id(ci)?;
+            (opt_system_entity_range, opt_public_entity_range) = self.consume_external_id(ci)?;
         }
-        tokens.push(DocTypeDeclaration {
-            name_range,
-            opt_system_entity_range,
-            opt_public_entity_range,
-        });
         ci.skip_spaces();
+        let mut opt_internal_subset_range = None;
         if ci.test_byte(b'[') {
             ci.advance_n(1)?;
-            tokens.append(&mut Self::tokenize_internal_subset(ci)?);
+            let start_pos = ci.pos();
+            tokens.append(&mut self.tokenize_internal_subset(ci)?);
+            opt_internal_subset_range = Some(ci.slice(start_pos..ci.pos()));
             ci.expect_byte(b']')?;
         }
+        // the declaration precedes the parameter entity references of its internal subset
+        tokens.insert(0, DocTypeDeclaration {
+            name_range,
+            opt_system_entity_range,
+            opt_public_entity_range,
+            opt_internal_subset_range,
+        });
         ci.skip_spaces();
         ci.expect_byte(b'>')?;
         Ok(tokens)
     }
 
     /// [\[75\] ExternalID](https://www.w3.org/TR/xml/#NT-ExternalID)
-    fn consume_external_id(ci: &mut CharIter<'a>) -> Result<(Option<TextRange<'a>>, Option<TextRange<'a>>), XmlError> {
+    fn consume_external_id(&self, ci: &mut CharIter<'a>) -> Result<(Option<TextRange<'a>>, Option<TextRange<'a>>), XmlError> {
         let system_start_delimiter = b"SYSTEM";
         let public_start_delimiter = b"PUBLIC";
-        return if ci.test(system_start_delimiter) {
+        if ci.test(system_start_delimiter) {
             ci.skip_over(system_start_delimiter)?;
             ci.expect_spaces()?;
-            let system_literal_range = Self::consume_system_literal(ci)?;
+            let system_literal_range = self.consume_system_literal(ci)?;
             Ok((Some(system_literal_range), None))
         } else if ci.test(public_start_delimiter) {
             ci.skip_over(public_start_delimiter)?;
             ci.expect_spaces()?;
             let pubid_literal_range = Self::consume_pubid_literal(ci)?;
             ci.expect_spaces()?;
-            let system_literal_range = Self::consume_system_literal(ci)?;
+            let system_literal_range = self.consume_system_literal(ci)?;
             Ok((Some(system_literal_range), Some(pubid_literal_range)))
         } else {
             Err(IllegalToken {
                 pos: ci.error_pos(),
-                expected: Some("'SYSTEM' or 'PUBLIC'".to_string()),
+                expected: Some(Expected::ExternalId),
+                fix: None,
             })
-        };
+        }
     }
 
     /// [\[11\] SystemLiteral](https://www.w3.org/TR/xml/#NT-SystemLiteral)
-    fn consume_system_literal(ci: &mut CharIter<'a>) -> Result<TextRange<'a>, XmlError> {
+    fn consume_system_literal(&self, ci: &mut CharIter<'a>) -> Result<TextRange<'a>, XmlError> {
         let used_quote = Self::consume_quote(ci)?;
-        let literal_range = Self::consume_xml_chars_until(ci, &[used_quote])?;
+        let literal_range = self.consume_xml_chars_until(ci, &[used_quote])?;
         ci.expect_byte(used_quote)?;
         Ok(literal_range)
     }
@@ -212,13 +411,14 @@ impl<'a> XmlTokenizer {
             return Err(
                 IllegalToken {
                     pos: ci.error_pos(),
-                    expected: Some("yes or no".to_string()),
+                    expected: Some(Expected::YesOrNo),
+                    fix: None,
                 }
             );
         }
         let end_pos = ci.pos();
         ci.expect_byte(used_quote)?;
-        return Ok(ci.slice(start_pos..end_pos));
+        Ok(ci.slice(start_pos..end_pos))
     }
 
 
@@ -231,7 +431,7 @@ impl<'a> XmlTokenizer {
 
         let range = Self::consume_encoding_name(ci)?;
         ci.expect_byte(used_quote)?;
-        return Ok(range);
+        Ok(range)
     }
 
     /// [\[81\] EncName](https://www.w3.org/TR/xml/#NT-VersionNum)
@@ -242,14 +442,12 @@ impl<'a> XmlTokenizer {
         if !byte.is_ascii_alphabetic() {
             return Err(IllegalToken {
                 pos: ci.error_pos(),
-                expected: Some("Any latin letter".to_string()),
+                expected: Some(Expected::Letter),
+                fix: None,
             });
         }
         // maybe move this to xmlchar
-        while ci.peek_byte()?.is_ascii_alphanumeric() || match ci.peek_byte()? {
-            b'.' | b'_' | b'-' => true,
-            _ => false
-        } {
+        while ci.peek_byte()?.is_ascii_alphanumeric() || matches!(ci.peek_byte()?, b'.' | b'_' | b'-') {
             ci.advance_n(1)?;
         }
         Ok(ci.slice(start_pos..ci.pos()))
@@ -265,56 +463,76 @@ impl<'a> XmlTokenizer {
 
         let range = Self::consume_version_num(ci)?;
         ci.expect_byte(used_quote)?;
-        return Ok(range);
+        Ok(range)
     }
 
     /// [\[26\] VersionNUm](https://www.w3.org/TR/xml/#NT-VersionNum)
     fn consume_version_num(ci: &mut CharIter<'a>) -> Result<TextRange<'a>, XmlError> {
         let start_pos = ci.pos();
         ci.expect_bytes(b"1.")?;
-        // TODO: remove redundant xml_char check
-        while ci.peek_xml_char()?.is_ascii_digit() {
-            ci.next_xml_char()?;
+        if !ci.peek_byte()?.is_ascii_digit() {
+            return Err(IllegalToken {
+                pos: ci.error_pos(),
+                expected: Some(Expected::Digit),
+                fix: None,
+            });
+        }
+        while ci.peek_byte()?.is_ascii_digit() {
+            ci.advance_n(1)?;
         }
         Ok(ci.slice(start_pos..ci.pos()))
     }
 
     /// [\[43\] content](https://www.w3.org/TR/xml/#NT-content)
-    fn tokenize_content(ci: &mut CharIter<'a>) -> Result<Vec<XmlToken<'a>>, XmlError> {
-        // average token length of ~20 bytes
-        let mut tokens = Vec::with_capacity(ci.text.len() / 20);
-        while ci.has_next() {
-            let text_range = Self::consume_character_data_until(ci, '<')?;
-            if !text_range.is_empty() {
-                tokens.push(Text(text_range));
-            }
-            if ci.test(b"</") {
-                tokens.push(Self::tokenize_end_tag(ci)?);
-            } else if ci.test(b"<!--") {
-                tokens.push(Self::tokenize_comment(ci)?);
-            } else if ci.test(b"<![CDATA[") {
-                tokens.push(Self::tokenize_cdata_section(ci)?);
-            } else if ci.test(b"<?") {
-                tokens.push(Self::tokenize_processing_instruction(ci)?)
-            } else {
-                tokens.append(Self::tokenize_start_tag(ci)?.as_mut());
+    fn tokenize_content(&self, ci: &mut CharIter<'a>, tokens: &mut Vec<XmlToken<'a>>, is_end: &mut impl FnMut(&[XmlToken<'a>]) -> bool) -> Result<bool, XmlError> {
+        trace_span!("content", start = ci.pos());
+        while self.tokenize_content_step(ci, tokens)? {
+            if is_end(tokens) {
+                return Ok(true);
             }
         }
-        Ok(tokens)
+        Ok(false)
+    }
+
+    /// Tokenize the text up to the next markup and the markup, i.e. a tag with its attributes, a comment,
+    /// a CDATA section or a processing instruction. Returns false at the end of the input.
+    pub(crate) fn tokenize_content_step(&self, ci: &mut CharIter<'a>, tokens: &mut Vec<XmlToken<'a>>) -> Result<bool, XmlError> {
+        // trailing spaces after the root element
+        let start_pos = ci.pos;
+        ci.skip_spaces();
+        if !ci.has_next() {
+            return Ok(false);
+        }
+        ci.pos = start_pos;
+        let text_range = self.consume_character_data_until(ci, '<')?;
+        if !text_range.is_empty() {
+            tokens.push(Text(text_range));
+        }
+        if ci.test(b"</") {
+            tokens.push(Self::tokenize_construct(ci, "end tag", Some(2), Self::tokenize_end_tag)?);
+        } else if ci.test(b"<!--") {
+            tokens.push(Self::tokenize_construct(ci, "comment", None, |ci| self.tokenize_comment(ci))?);
+        } else if ci.test(b"<![CDATA[") {
+            tokens.push(Self::tokenize_construct(ci, "CDATA section", None, |ci| self.tokenize_cdata_section(ci))?);
+        } else if ci.test(b"<?") {
+            tokens.push(Self::tokenize_construct(ci, "processing instruction", Some(2), |ci| self.tokenize_processing_instruction(ci))?)
+        } else {
+            Self::tokenize_construct(ci, "start tag", Some(1), |ci| self.tokenize_start_tag(ci, tokens))?;
+        }
+        Ok(true)
     }
 
 
     /// [\[40\] STag](https://www.w3.org/TR/xml/#NT-STag)
-    fn tokenize_start_tag(ci: &mut CharIter<'a>) -> Result<Vec<XmlToken<'a>>, XmlError> {
-        let mut tokens = vec![];
-
+    fn tokenize_start_tag(&self, ci: &mut CharIter<'a>, tokens: &mut Vec<XmlToken<'a>>) -> Result<(), XmlError> {
         //tag start has already been identified
         ci.skip_over(b"<")?;
         let name_range = Self::consume_name(ci)?;
+        tokens.push(StartTag(name_range));
 
         while !ci.test_after_spaces(b"/>") && !ci.test_after_spaces(b">") {
             ci.expect_spaces()?;
-            tokens.push(Self::tokenize_attribute(ci)?);
+            tokens.push(Self::tokenize_construct(ci, "attribute", Some(0), |ci| self.tokenize_attribute(ci))?);
         }
 
         ci.skip_spaces();
@@ -326,12 +544,11 @@ impl<'a> XmlTokenizer {
             ci.expect_byte(b'>')?;
         }
 
-        tokens.insert(0, StartTag(name_range));
         if is_empty_element_tag {
             // Create artificial end tag
             tokens.push(EndTag(name_range));
         }
-        Ok(tokens)
+        Ok(())
     }
 
     /// [\[42\] ETag](https://www.w3.org/TR/xml/#NT-ETag)
@@ -344,48 +561,57 @@ impl<'a> XmlTokenizer {
     }
 
     /// [\[41\] Attribute](https://www.w3.org/TR/xml/#NT-Attribute)
-    fn tokenize_attribute(ci: &mut CharIter<'a>) -> Result<XmlToken<'a>, XmlError> {
+    pub(crate) fn tokenize_attribute(&self, ci: &mut CharIter<'a>) -> Result<XmlToken<'a>, XmlError> {
         // spaces have already been skipped
         let name_range = Self::consume_name(ci)?;
         Self::expect_eq(ci)?;
         let used_quote = Self::consume_quote(ci)?;
         // TODO consider references in Attributes
         // [\[10\] AttValue](https://www.w3.org/TR/xml/#NT-AttValue)
-        let value_range = Self::consume_character_data_until(ci, char::from(used_quote))?;
+        let value_range = self.consume_character_data_until(ci, char::from(used_quote))?;
         ci.advance_n(1)?;
         Ok(Attribute { name_range, value_range })
     }
 
     /// [\[18\] CDSect](https://www.w3.org/TR/xml/#NT-CDSect)
-    fn tokenize_cdata_section(ci: &mut CharIter<'a>) -> Result<XmlToken<'a>, XmlError> {
+    pub(crate) fn tokenize_cdata_section(&self, ci: &mut CharIter<'a>) -> Result<XmlToken<'a>, XmlError> {
         ci.skip_over(b"<![CDATA[")?;
-        let value_range = Self::consume_xml_chars_until(ci, b"]]>")?;
+        let value_range = self.consume_xml_chars_until(ci, b"]]>")?;
         ci.skip_over(b"]]>")?;
         Ok(CdataSection(value_range))
     }
 
     /// [\[15\] Comment](https://www.w3.org/TR/xml/#NT-Comment)
-    fn tokenize_comment(ci: &mut CharIter<'a>) -> Result<XmlToken<'a>, XmlError> {
+    pub(crate) fn tokenize_comment(&self, ci: &mut CharIter<'a>) -> Result<XmlToken<'a>, XmlError> {
         ci.skip_over(b"<!--")?;
         let start_pos = ci.pos();
         loop {
+            if self.lazy_char_validation {
+                Self::skip_unchecked_chars(ci, |b| b == b'-');
+            }
             if ci.test(b"--") {
                 if ci.test(b"-->") {
                     break;
+                } else if self.relaxed_comments {
+                    ci.advance_n(1)?;
+                    continue;
                 } else if ci.test(b"--->") {
                     // Last character cannot be a hyphen
                     return Err(IllegalToken {
                         pos: ci.error_pos(),
-                        expected: Some("Not a hyphen as the last value character".to_string()),
+                        expected: Some(Expected::NoTrailingHyphen),
+                        fix: None,
                     });
                 } else {
                     // Double hypen is not allowed inside comments
                     return Err(IllegalToken {
                         pos: ci.error_pos(),
-                        expected: Some("Not a double hyphen inside comments".to_string()),
+                        expected: Some(Expected::NoDoubleHyphen),
+                        fix: None,
                     });
                 }
             }
+            self.check_lookahead(ci, start_pos)?;
             ci.next_xml_char()?;
         }
         let value_range = ci.slice(start_pos..ci.pos());
@@ -394,15 +620,22 @@ impl<'a> XmlTokenizer {
     }
 
     /// [\[16\] PI](https://www.w3.org/TR/xml/#NT-PI)
-    fn tokenize_processing_instruction(ci: &mut CharIter<'a>) -> Result<XmlToken<'a>, XmlError> {
+    pub(crate) fn tokenize_processing_instruction(&self, ci: &mut CharIter<'a>) -> Result<XmlToken<'a>, XmlError> {
         ci.skip_over(b"<?")?;
         let target_range = Self::consume_name(ci)?;
+        // [17] PITarget: the name xml is reserved for the XML declaration, which may only start a document
+        if target_range.slice.eq_ignore_ascii_case("xml") {
+            return Err(IllegalToken {
+                pos: ci.error_pos_of(target_range.start),
+                expected: Some(Expected::ProcessingInstructionTarget),
+                fix: None,
+            });
+        }
         ci.skip_spaces();
 
-        // TODO forbid literal "XML" in processing instruction
         let mut opt_value_range = None;
         if !ci.test(b"?>") {
-            opt_value_range = Some(Self::consume_xml_chars_until(ci, b"?>")?);
+            opt_value_range = Some(self.consume_xml_chars_until(ci, b"?>")?);
         }
 
         ci.skip_over(b"?>")?;
@@ -416,7 +649,8 @@ impl<'a> XmlTokenizer {
         if !c.is_xml_name_start_char() {
             return Err(IllegalToken {
                 pos: ci.error_pos(),
-                expected: Some("Any Name start char".to_string()),
+                expected: Some(Expected::NameStartChar),
+                fix: None,
             });
         }
         loop {
@@ -437,27 +671,36 @@ impl<'a> XmlTokenizer {
     ///
     /// CharData ::= \[^<&\]* - (\[^<&\]* ']]>' \[^<&\]*)
     /// [\[14\] CharData](https://www.w3.org/TR/xml/#NT-CharData)
-    fn consume_character_data_until(ci: &mut CharIter<'a>, delimiter: char) -> Result<TextRange<'a>, XmlError> {
+    pub(crate) fn consume_character_data_until(&self, ci: &mut CharIter<'a>, delimiter: char) -> Result<TextRange<'a>, XmlError> {
         let start_pos = ci.pos();
         let cdata_close_delimiter = b"]]>";
+        let delimiter_byte = delimiter as u8;
         loop {
+            if self.lazy_char_validation {
+                Self::skip_unchecked_chars(ci, |b| b == delimiter_byte || matches!(b, b']' | b'&' | b'<'));
+            }
             match ci.peek_xml_char()? {
                 c if c == delimiter => break,
-                ']' => if ci.test(cdata_close_delimiter) {
-                    return Err(IllegalToken {
-                        pos: ci.error_pos(),
-                        expected: Some("Not the CDATA section-close delimiter".to_string()),
-                    });
-                },
+                ']' => {
+                    if ci.test(cdata_close_delimiter) {
+                        return Err(IllegalToken {
+                            pos: ci.error_pos(),
+                            expected: Some(Expected::NoCdataSectionEnd),
+                            fix: None,
+                        });
+                    }
+                    ci.advance_n(1)?;
+                }
                 '&' => {
                     // TODO handle returned range
-                    Self::consume_character_reference(ci)?;
+                    self.consume_character_reference(ci)?;
                     continue;
                 }
                 '<' => {
                     return Err(IllegalToken {
                         pos: ci.error_pos(),
-                        expected: Some("Not the less-than character".to_string()),
+                        expected: Some(Expected::NoLessThan),
+                        fix: None,
                     });
                 }
                 c => { ci.advance_n(c.len_utf8())?; }
@@ -468,14 +711,29 @@ impl<'a> XmlTokenizer {
 
 
     /// Consume any XML char until a specified byte slice is found
-    fn consume_xml_chars_until(ci: &mut CharIter<'a>, delimiter: &[u8]) -> Result<TextRange<'a>, XmlError> {
+    pub(crate) fn consume_xml_chars_until(&self, ci: &mut CharIter<'a>, delimiter: &[u8]) -> Result<TextRange<'a>, XmlError> {
         let start_pos = ci.pos();
         while !ci.test(delimiter) {
+            if self.lazy_char_validation {
+                Self::skip_unchecked_chars(ci, |b| b == delimiter[0]);
+                if ci.test(delimiter) {
+                    break;
+                }
+            }
+            self.check_lookahead(ci, start_pos)?;
             ci.next_xml_char()?; // checks for valid XML char
         }
         Ok(ci.slice(start_pos..ci.pos()))
     }
 
+    /// Advance to the next byte that may end the current construct, without checking the characters in between.
+    /// The bytes looked for are ASCII, so the position stays at a character boundary. Without such a byte,
+    /// the position is the end of the input.
+    fn skip_unchecked_chars(ci: &mut CharIter<'a>, is_end: impl Fn(u8) -> bool) {
+        let rest = &ci.text.as_bytes()[ci.pos..];
+        ci.pos += rest.iter().position(|&b| is_end(b)).unwrap_or(rest.len());
+    }
+
     /// Consume a character reference.
     /// Apart from valid unicode character references, the short-hand definitions
     /// "&amp;" = &
@@ -483,60 +741,44 @@ impl<'a> XmlTokenizer {
     /// "&gt;"= >
     /// "&apos;" = '
     /// and "&quot;" = "
-    /// are supported.
+    /// are supported, as well as the entities registered in [ParseOptions::entities].
     ///
     /// [\[66\] CharRef](https://www.w3.org/TR/xml/#NT-CharRef)
-    fn consume_character_reference(ci: &mut CharIter<'a>) -> Result<TextRange<'a>, XmlError> {
+    pub(crate) fn consume_character_reference(&self, ci: &mut CharIter<'a>) -> Result<TextRange<'a>, XmlError> {
         let start_pos = ci.pos();
         ci.expect_byte(b'&')?;
-        if ci.test(b"#x") {
-            ci.skip_over(b"#x")?;
-
-            // unicode char reference
-            let char_hex_range = Self::consume_xml_chars_until(ci, b";")?;
-
-            // decode character reference
-            match util::decode_hex(char_hex_range.slice) {
-                Some(_) => (),
-                None => return Err(UnknownReference {
-                    pos: ci.error_pos()
-                })
-            };
-        } else if ci.test(b"#") {
-            ci.skip_over(b"#")?;
-
-            // unicode char reference
-            let code_point_range = Self::consume_xml_chars_until(ci, b";")?;
-            let err = Err(UnknownReference {
-                pos: ci.error_pos()
+        // an ampersand that is meant literally
+        let rest = &ci.text[ci.pos()..];
+        let body_len = rest.find(|c: char| c != '#' && !c.is_xml_name_char()).unwrap_or(rest.len());
+        if body_len == 0 || !rest[body_len..].starts_with(';') {
+            return Err(UnknownReference {
+                pos: ci.error_pos_of(start_pos),
+                reason: None,
+                fix: Some(Box::new(Fix { start: start_pos, end: start_pos + 1, replacement: "&amp;".to_string() })),
+            });
+        }
+        let body_range = self.consume_xml_chars_until(ci, b";")?;
+        let known = match body_range.slice {
+            "amp" | "lt" | "gt" | "apos" | "quot" => Ok(()),
+            // unicode char reference, including the semicolon
+            body if body.starts_with('#') => refs::decode_char_ref_with(&ci.text[start_pos..=ci.pos()], &self.char_refs).map(|_| ()).map_err(Some),
+            // [WFC: Entity Declared](https://www.w3.org/TR/xml/#wf-entdeclared), registered entities are not declared in the document
+            name if !self.is_standalone && self.entities.contains(name) => Ok(()),
+            _ => Err(None),
+        };
+        if let Err(reason) = known {
+            return Err(UnknownReference {
+                pos: ci.error_pos(),
+                reason,
+                fix: None,
             });
-            match u32::from_str(code_point_range.slice) {
-                Ok(codepoint) => {
-                    match char::from_u32(codepoint) {
-                        Some(c) => if !c.is_xml_char() {
-                            return err;
-                        },
-                        None => return err
-                    }
-                }
-                Err(_) => return err
-            };
-        } else {
-            // short hand syntax
-            let short_range = Self::consume_xml_chars_until(ci, b";")?;
-            match short_range.slice {
-                "amp" | "lt" | "gt" | "apos" | "quot" => (), // all good
-                _ => return Err(UnknownReference {
-                    pos: ci.error_pos()
-                })
-            }
         }
         ci.skip_over(b";")?;
         Ok(ci.slice(start_pos..ci.pos()))
     }
 
     /// [\[25\] Eq](https://www.w3.org/TR/xml/#NT-Eq)
-    fn expect_eq(ci: &mut CharIter<'a>) -> Result<(), XmlError> {
+    pub(crate) fn expect_eq(ci: &mut CharIter<'a>) -> Result<(), XmlError> {
         ci.skip_spaces();
         ci.expect_byte(b'=')?;
         ci.skip_spaces();
@@ -544,14 +786,52 @@ impl<'a> XmlTokenizer {
     }
 
     /// ' or "
-    fn consume_quote(ci: &mut CharIter<'a>) -> Result<u8, XmlError> {
+    pub(crate) fn consume_quote(ci: &mut CharIter<'a>) -> Result<u8, XmlError> {
         let quote = ci.next_byte()?;
         if !quote.is_xml_quote() {
+            // quote an unquoted value
+            let start = ci.pos() - 1;
+            let rest = &ci.text[start..];
+            let len = rest.find(|c: char| c.is_ascii() && (c as u8).is_xml_whitespace() || c == '>').unwrap_or(rest.len());
+            let len = if rest[..len].ends_with('/') && rest[len..].starts_with('>') { len - 1 } else { len };
+            let value = &rest[..len];
+            let quote = if value.contains('"') { '\'' } else { '"' };
             return Err(IllegalToken {
                 pos: ci.error_pos(),
-                expected: Some("Either \" or '".to_string()),
+                expected: Some(Expected::Quote),
+                fix: Some(Box::new(Fix { start, end: start + len, replacement: format!("{}{}{}", quote, value, quote) })),
             });
         }
         Ok(quote)
     }
-}
\ No newline at end of file
+}
+
+/// The length of the document type declaration at the start of the bytes, `None` if it does not end in them.
+/// Only the bytes that may end a construct are looked at, which is what makes skipping a large internal subset fast.
+fn doctype_declaration_len(bytes: &[u8]) -> Option<usize> {
+    let mut i = b"<!DOCTYPE".len();
+    let mut is_in_internal_subset = false;
+    loop {
+        i += bytes.get(i..)?.iter().position(|b| matches!(b, b'<' | b'>' | b'[' | b']' | b'"' | b'\''))?;
+        let rest = &bytes[i..];
+        if rest.starts_with(b"<!--") {
+            i += 4 + find(&rest[4..], b"-->")? + 3;
+            continue;
+        } else if rest.starts_with(b"<?") {
+            i += 2 + find(&rest[2..], b"?>")? + 2;
+            continue;
+        }
+        match rest[0] {
+            b'>' if !is_in_internal_subset => return Some(i + 1),
+            b'[' => is_in_internal_subset = true,
+            b']' => is_in_internal_subset = false,
+            quote @ (b'"' | b'\'') => i += 1 + rest[1..].iter().position(|b| *b == quote)?,
+            _ => (),
+        }
+        i += 1;
+    }
+}
+
+fn find(bytes: &[u8], delimiter: &[u8]) -> Option<usize> {
+    bytes.windows(delimiter.len()).position(|w| w == delimiter)
+}
@@ -1,16 +1,11 @@
 use crate::token::XmlToken;
 
+#[derive(Default)]
 pub struct TokenStream<'a> {
     pos: usize,
     tokens: Vec<XmlToken<'a>>,
 }
 
-impl Default for TokenStream<'_> {
-    fn default() -> Self {
-        TokenStream { pos: 0, tokens: vec![] }
-    }
-}
-
 impl<'a> From<Vec<XmlToken<'a>>> for TokenStream<'a> {
     fn from(tokens: Vec<XmlToken<'a>>) -> Self {
         TokenStream { pos: 0, tokens }
@@ -29,4 +24,10 @@ impl<'a> TokenStream<'a> {
     pub fn has_next(&self) -> bool {
         self.pos < self.tokens.len()
     }
+
+    /// Take the tokens, e.g. to reuse their buffer.
+    pub fn take_tokens(&mut self) -> Vec<XmlToken<'a>> {
+        self.pos = 0;
+        std::mem::take(&mut self.tokens)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,17 @@
+//! Instrumentation with [tracing](https://docs.rs/tracing), compiled away without the `tracing` feature.
+
+/// Enter a debug span until the end of the enclosing block.
+macro_rules! trace_span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($arg)*).entered();
+    };
+}
+
+/// Emit a debug event.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
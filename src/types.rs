@@ -0,0 +1,415 @@
+//! Values of the built-in simple types of [XML Schema](https://www.w3.org/TR/xmlschema11-2/) that have no
+//! Rust counterpart: `xs:decimal`, `xs:date`, `xs:dateTime` and `xs:duration`, and the parser of `xs:boolean`.
+//!
+//! The parsers accept the lexical space of the types after whitespace collapsing, so surrounding spaces are ignored,
+//! and the [Display] implementations write the canonical representation. All of the types are
+//! [XmlValue](crate::binding::XmlValue)s for data binding.
+//!
+//! ```
+//! use jaxp_rust::types::{DateTime, Decimal};
+//!
+//! let price = Decimal::parse(" 012.50 ").unwrap();
+//! assert_eq!("12.5", price.to_string());
+//! assert_eq!((3, 1), (price.total_digits(), price.fraction_digits()));
+//! let midnight = DateTime::parse("2024-02-29T24:00:00+01:00").unwrap();
+//! assert_eq!("2024-03-01T00:00:00+01:00", midnight.to_string());
+//! assert!(DateTime::parse("2023-02-29T12:00:00").is_none());
+//! ```
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+/// `true`, `false`, `1` and `0`.
+pub fn parse_boolean(text: &str) -> Option<bool> {
+    match text.trim() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// A decimal number of up to 38 digits, `unscaled / 10^scale`, without trailing zeros in the fraction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    unscaled: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn new(unscaled: i128, scale: u32) -> Decimal {
+        let mut decimal = Decimal { unscaled, scale };
+        while decimal.scale > 0 && decimal.unscaled % 10 == 0 {
+            decimal.unscaled /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+
+    /// An optional sign followed by digits with an optional decimal point, e.g. `-1.23`, `+.5` or `7.`
+    pub fn parse(text: &str) -> Option<Decimal> {
+        let text = text.trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer.is_empty() && fraction.is_empty() || !integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let fraction = fraction.trim_end_matches('0');
+        let mut unscaled: i128 = 0;
+        for b in integer.bytes().chain(fraction.bytes()) {
+            unscaled = unscaled.checked_mul(10)?.checked_add((b - b'0') as i128)?;
+        }
+        Some(Decimal { unscaled: if negative { -unscaled } else { unscaled }, scale: fraction.len() as u32 })
+    }
+
+    pub fn unscaled(&self) -> i128 {
+        self.unscaled
+    }
+
+    /// The number of digits after the decimal point.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// The number of significant digits, as restricted by the `totalDigits` facet.
+    pub fn total_digits(&self) -> u32 {
+        let digits = self.unscaled.unsigned_abs().checked_ilog10().map_or(1, |log| log + 1);
+        digits.max(self.scale)
+    }
+
+    /// The number of digits of the fraction, as restricted by the `fractionDigits` facet.
+    pub fn fraction_digits(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        let align = |d: &Decimal| 10i128.checked_pow(scale - d.scale).and_then(|factor| d.unscaled.checked_mul(factor));
+        match (align(self), align(other)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            // a number that overflows when aligned has the larger magnitude
+            (None, _) => if self.unscaled < 0 { Ordering::Less } else { Ordering::Greater },
+            (_, None) => if other.unscaled < 0 { Ordering::Greater } else { Ordering::Less },
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let digits = self.unscaled.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        if self.unscaled < 0 {
+            f.write_str("-")?;
+        }
+        if scale == 0 {
+            return f.write_str(&digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}.{}", integer, fraction)
+    }
+}
+
+/// A calendar date with an optional timezone. Year 0 is 1 BCE, as in XML Schema 1.1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    /// The offset from UTC in minutes
+    pub opt_timezone: Option<i16>,
+}
+
+impl Date {
+    /// `yyyy-mm-dd` followed by an optional timezone, `Z` or `+hh:mm`
+    pub fn parse(text: &str) -> Option<Date> {
+        let (date, opt_timezone) = split_timezone(text.trim())?;
+        let (year, month, day) = parse_date(date)?;
+        Some(Date { year, month, day, opt_timezone })
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write_date(f, self.year, self.month, self.day)?;
+        write_timezone(f, self.opt_timezone)
+    }
+}
+
+/// A date and time of day with an optional timezone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+    /// The offset from UTC in minutes
+    pub opt_timezone: Option<i16>,
+}
+
+impl DateTime {
+    /// `yyyy-mm-ddThh:mm:ss` with optional fractional seconds, followed by an optional timezone.
+    /// Midnight may be written as `24:00:00` of the previous day.
+    pub fn parse(text: &str) -> Option<DateTime> {
+        let (text, opt_timezone) = split_timezone(text.trim())?;
+        let (date, time) = text.split_once('T')?;
+        let (year, month, day) = parse_date(date)?;
+        let (hour, minute, second, nanosecond) = parse_time(time)?;
+        let date_time = DateTime { year, month, day, hour, minute, second, nanosecond, opt_timezone };
+        if hour == 24 {
+            return date_time.next_day();
+        }
+        Some(date_time)
+    }
+
+    pub fn date(&self) -> Date {
+        Date { year: self.year, month: self.month, day: self.day, opt_timezone: self.opt_timezone }
+    }
+
+    /// Midnight at the start of the following day.
+    fn next_day(self) -> Option<DateTime> {
+        let (year, month, day) = if self.day < days_in_month(self.year, self.month) {
+            (self.year, self.month, self.day + 1)
+        } else if self.month < 12 {
+            (self.year, self.month + 1, 1)
+        } else {
+            (self.year.checked_add(1)?, 1, 1)
+        };
+        Some(DateTime { year, month, day, hour: 0, ..self })
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write_date(f, self.year, self.month, self.day)?;
+        write!(f, "T{:02}:{:02}:{:02}", self.hour, self.minute, self.second)?;
+        write_nanoseconds(f, self.nanosecond)?;
+        write_timezone(f, self.opt_timezone)
+    }
+}
+
+/// A duration of months and of seconds, which are not convertible into each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Duration {
+    pub negative: bool,
+    pub years: u64,
+    pub months: u64,
+    pub days: u64,
+    pub hours: u64,
+    pub minutes: u64,
+    pub seconds: u64,
+    pub nanoseconds: u32,
+}
+
+impl Duration {
+    /// `PnYnMnDTnHnMnS` with an optional leading `-`, where each part is optional, but at least one is required,
+    /// and only the seconds may have a fraction.
+    pub fn parse(text: &str) -> Option<Duration> {
+        let text = text.trim();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(text) => (true, text),
+            None => (false, text),
+        };
+        let text = text.strip_prefix('P')?;
+        let (date, opt_time) = match text.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (text, None),
+        };
+        let mut duration = Duration { negative, years: 0, months: 0, days: 0, hours: 0, minutes: 0, seconds: 0, nanoseconds: 0 };
+        let mut has_part = false;
+        let mut rest = date;
+        for (designator, field) in [('Y', &mut duration.years), ('M', &mut duration.months), ('D', &mut duration.days)] {
+            if let Some((value, after)) = rest.split_once(designator) {
+                *field = parse_digits(value)?;
+                rest = after;
+                has_part = true;
+            }
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+        if let Some(time) = opt_time {
+            let mut rest = time;
+            for (designator, field) in [('H', &mut duration.hours), ('M', &mut duration.minutes)] {
+                if let Some((value, after)) = rest.split_once(designator) {
+                    *field = parse_digits(value)?;
+                    rest = after;
+                }
+            }
+            if let Some(seconds) = rest.strip_suffix('S') {
+                let (whole, fraction) = seconds.split_once('.').map_or((seconds, None), |(w, f)| (w, Some(f)));
+                duration.seconds = parse_digits(whole)?;
+                duration.nanoseconds = fraction.map_or(Some(0), parse_nanoseconds)?;
+                rest = "";
+            }
+            // a time designator without parts
+            if !rest.is_empty() || time.is_empty() {
+                return None;
+            }
+            has_part = true;
+        }
+        has_part.then_some(duration)
+    }
+}
+
+impl Display for Duration {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if self.negative {
+            f.write_str("-")?;
+        }
+        f.write_str("P")?;
+        for (value, designator) in [(self.years, 'Y'), (self.months, 'M'), (self.days, 'D')] {
+            if value > 0 {
+                write!(f, "{}{}", value, designator)?;
+            }
+        }
+        let has_seconds = self.seconds > 0 || self.nanoseconds > 0;
+        if self.hours > 0 || self.minutes > 0 || has_seconds {
+            f.write_str("T")?;
+            for (value, designator) in [(self.hours, 'H'), (self.minutes, 'M')] {
+                if value > 0 {
+                    write!(f, "{}{}", value, designator)?;
+                }
+            }
+            if has_seconds {
+                write!(f, "{}", self.seconds)?;
+                write_nanoseconds(f, self.nanoseconds)?;
+                f.write_str("S")?;
+            }
+        } else if self.years == 0 && self.months == 0 && self.days == 0 {
+            f.write_str("T0S")?;
+        }
+        Ok(())
+    }
+}
+
+/// Split an optional trailing timezone off a date or time.
+fn split_timezone(text: &str) -> Option<(&str, Option<i16>)> {
+    if let Some(rest) = text.strip_suffix('Z') {
+        return Some((rest, Some(0)));
+    }
+    let bytes = text.as_bytes();
+    match bytes.len().checked_sub(6) {
+        Some(i) if matches!(bytes[i], b'+' | b'-') && bytes[i + 3] == b':' => {
+            let (rest, timezone) = text.split_at(i);
+            let (hours, minutes) = (parse_fixed(&timezone[1..3], 2)? as i16, parse_fixed(&timezone[4..], 2)? as i16);
+            if minutes > 59 || hours > 14 || hours == 14 && minutes > 0 {
+                return None;
+            }
+            let offset = hours * 60 + minutes;
+            Some((rest, Some(if timezone.starts_with('-') { -offset } else { offset })))
+        }
+        _ => Some((text, None)),
+    }
+}
+
+fn parse_date(text: &str) -> Option<(i32, u8, u8)> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let mut parts = text.splitn(3, '-');
+    let year = parts.next()?;
+    // years of more than four digits have no leading zeros
+    if year.len() < 4 || year.len() > 4 && year.starts_with('0') {
+        return None;
+    }
+    let year = i32::try_from(parse_digits(year)?).ok()?;
+    let month = parse_fixed(parts.next()?, 2)? as u8;
+    let day = parse_fixed(parts.next()?, 2)? as u8;
+    let year = if negative { -year } else { year };
+    if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some((year, month, day))
+}
+
+fn parse_time(text: &str) -> Option<(u8, u8, u8, u32)> {
+    let mut parts = text.splitn(3, ':');
+    let hour = parse_fixed(parts.next()?, 2)? as u8;
+    let minute = parse_fixed(parts.next()?, 2)? as u8;
+    let seconds = parts.next()?;
+    let (whole, fraction) = seconds.split_once('.').map_or((seconds, None), |(w, f)| (w, Some(f)));
+    let second = parse_fixed(whole, 2)? as u8;
+    let nanosecond = fraction.map_or(Some(0), parse_nanoseconds)?;
+    if hour == 24 && (minute, second, nanosecond) != (0, 0, 0) || hour > 24 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some((hour, minute, second, nanosecond))
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// One or more ASCII digits.
+fn parse_digits(text: &str) -> Option<u64> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Exactly `len` ASCII digits.
+fn parse_fixed(text: &str, len: usize) -> Option<u64> {
+    if text.len() != len {
+        return None;
+    }
+    parse_digits(text)
+}
+
+/// The digits of a fraction of a second, of which only nanoseconds are kept.
+fn parse_nanoseconds(fraction: &str) -> Option<u32> {
+    parse_digits(fraction)?;
+    let digits = format!("{:0<9}", &fraction[..fraction.len().min(9)]);
+    digits.parse().ok()
+}
+
+fn write_date(f: &mut Formatter, year: i32, month: u8, day: u8) -> std::fmt::Result {
+    if year < 0 {
+        f.write_str("-")?;
+    }
+    write!(f, "{:04}-{:02}-{:02}", year.unsigned_abs(), month, day)
+}
+
+fn write_nanoseconds(f: &mut Formatter, nanoseconds: u32) -> std::fmt::Result {
+    if nanoseconds == 0 {
+        return Ok(());
+    }
+    let digits = format!("{:09}", nanoseconds);
+    write!(f, ".{}", digits.trim_end_matches('0'))
+}
+
+fn write_timezone(f: &mut Formatter, opt_timezone: Option<i16>) -> std::fmt::Result {
+    match opt_timezone {
+        Some(0) => f.write_str("Z"),
+        Some(offset) => {
+            let sign = if offset < 0 { '-' } else { '+' };
+            write!(f, "{}{:02}:{:02}", sign, offset.unsigned_abs() / 60, offset.unsigned_abs() % 60)
+        }
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,226 @@
+//! [wasm-bindgen](https://docs.rs/wasm-bindgen) bindings for browser-side use.
+//!
+//! Nodes are referred to by their index in the document, see [NodeId].
+
+use wasm_bindgen::prelude::*;
+
+use crate::document::{Node, NodeId, NodeType, OwnedDocument};
+use crate::escape::write_json_string;
+use crate::writer;
+
+#[wasm_bindgen]
+pub struct WasmDocument {
+    doc: OwnedDocument,
+}
+
+/// Parse a string into a document, throws the error message on failure.
+#[wasm_bindgen]
+pub fn parse(xml: String) -> Result<WasmDocument, JsValue> {
+    parse_document(xml).map_err(|message| JsValue::from_str(&message))
+}
+
+/// [parse] with the error message as a string, [JsValue]s need a JavaScript host.
+fn parse_document(xml: String) -> Result<WasmDocument, String> {
+    OwnedDocument::parse(xml).map(|doc| WasmDocument { doc }).map_err(|e| e.to_string())
+}
+
+#[wasm_bindgen]
+impl WasmDocument {
+    fn node(&self, node: u32) -> Option<Node<'_, '_>> {
+        self.doc.document().get_node(NodeId(node as usize))
+    }
+
+    #[wasm_bindgen(js_name = rootElement)]
+    pub fn root_element(&self) -> u32 {
+        self.doc.document().root_element().id().get() as u32
+    }
+
+    /// One of "root", "element", "text", "cdata", "comment" and "pi".
+    #[wasm_bindgen(js_name = nodeType)]
+    pub fn node_type(&self, node: u32) -> Option<String> {
+        self.node(node).map(|n| node_type_name(n.node_type()).to_string())
+    }
+
+    #[wasm_bindgen(js_name = tagName)]
+    pub fn tag_name(&self, node: u32) -> Option<String> {
+        self.node(node).map(|n| n.tag_name().to_string())
+    }
+
+    pub fn text(&self, node: u32) -> Option<String> {
+        self.node(node).and_then(|n| n.text()).map(str::to_string)
+    }
+
+    pub fn attribute(&self, node: u32, name: &str) -> Option<String> {
+        self.node(node).and_then(|n| n.attribute(name)).map(str::to_string)
+    }
+
+    pub fn parent(&self, node: u32) -> Option<u32> {
+        self.node(node).and_then(|n| n.parent()).map(|p| p.id().get() as u32)
+    }
+
+    pub fn children(&self, node: u32) -> Vec<u32> {
+        self.node(node)
+            .map(|n| n.children().map(|c| c.id().get() as u32).collect())
+            .unwrap_or_default()
+    }
+
+    /// All elements with the specified tag name, in document order.
+    #[wasm_bindgen(js_name = elementsByTagName)]
+    pub fn elements_by_tag_name(&self, name: &str) -> Vec<u32> {
+        self.doc.document()
+            .descendants()
+            .filter(|n| n.is_element() && n.tag_name() == name)
+            .map(|n| n.id().get() as u32)
+            .collect()
+    }
+
+    /// Serialize a node and its subtree.
+    pub fn serialize(&self, node: u32) -> Option<String> {
+        self.node(node).map(writer::serialize)
+    }
+
+    /// The subtree of a node as JSON, suitable for `JSON.parse()`.
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self, node: u32) -> Option<String> {
+        self.node(node).map(|n| {
+            let mut json = String::new();
+            write_json(n, &mut json);
+            json
+        })
+    }
+}
+
+fn node_type_name(node_type: NodeType) -> &'static str {
+    match node_type {
+        NodeType::Root => "root",
+        NodeType::Element => "element",
+        NodeType::Text => "text",
+        NodeType::CdataSection => "cdata",
+        NodeType::Comment => "comment",
+        NodeType::ProcessingInstruction => "pi",
+    }
+}
+
+fn write_json(node: Node, json: &mut String) {
+    json.push_str("{\"type\":");
+    write_json_string(node_type_name(node.node_type()), json);
+    match node.node_type() {
+        NodeType::Root | NodeType::Element => {
+            if node.is_element() {
+                json.push_str(",\"name\":");
+                write_json_string(node.tag_name(), json);
+                json.push_str(",\"attributes\":{");
+                for (i, attribute) in node.attributes().enumerate() {
+                    if i > 0 {
+                        json.push(',');
+                    }
+                    write_json_string(attribute.name(), json);
+                    json.push(':');
+                    write_json_string(attribute.value(), json);
+                }
+                json.push('}');
+            }
+            json.push_str(",\"children\":[");
+            for (i, child) in node.children().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write_json(child, json);
+            }
+            json.push(']');
+        }
+        NodeType::ProcessingInstruction => {
+            json.push_str(",\"target\":");
+            write_json_string(node.tag_name(), json);
+            if let Some(value) = node.text() {
+                json.push_str(",\"value\":");
+                write_json_string(value, json);
+            }
+        }
+        _ => {
+            json.push_str(",\"value\":");
+            write_json_string(node.text().unwrap_or(""), json);
+        }
+    }
+    json.push('}');
+}
+
+// the exports are tested natively, JsValue is only used by the error path of parse
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_ok(xml: &str) -> WasmDocument {
+        parse_document(xml.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let doc = parse_ok("<a/>");
+        assert_eq!(Some("a".to_string()), doc.tag_name(doc.root_element()));
+        let message = parse_document("<a></b>".to_string()).err().unwrap();
+        assert!(message.starts_with("Error: NonMatchingTags"), "{}", message);
+        assert!(parse_document(String::new()).is_err());
+    }
+
+    #[test]
+    fn test_tree_queries() {
+        let doc = parse_ok("<a x='1'>t<b/><!--c--><b y='&amp;'/></a>");
+        let root = doc.root_element();
+        assert_eq!(1, root);
+        assert_eq!(Some("element".to_string()), doc.node_type(root));
+        assert_eq!(Some(0), doc.parent(root));
+        assert_eq!(Some("root".to_string()), doc.node_type(0));
+        assert_eq!(None, doc.parent(0));
+
+        let children = doc.children(root);
+        assert_eq!(4, children.len());
+        let types: Vec<_> = children.iter().filter_map(|&c| doc.node_type(c)).collect();
+        assert_eq!(vec!["text", "element", "comment", "element"], types);
+        assert_eq!(Some("t".to_string()), doc.text(children[0]));
+        assert_eq!(Some("c".to_string()), doc.text(children[2]));
+        assert_eq!(Some(root), doc.parent(children[1]));
+
+        assert_eq!(Some("1".to_string()), doc.attribute(root, "x"));
+        assert_eq!(None, doc.attribute(root, "y"));
+        // values as they appear in the markup
+        assert_eq!(Some("&amp;".to_string()), doc.attribute(children[3], "y"));
+
+        assert_eq!(vec![children[1], children[3]], doc.elements_by_tag_name("b"));
+        assert_eq!(vec![root], doc.elements_by_tag_name("a"));
+        assert!(doc.elements_by_tag_name("c").is_empty());
+
+        // unknown nodes
+        assert_eq!(None, doc.node_type(100));
+        assert_eq!(None, doc.tag_name(100));
+        assert_eq!(None, doc.attribute(100, "x"));
+        assert!(doc.children(100).is_empty());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let doc = parse_ok("<a x='1'><b>t</b></a>");
+        let b = doc.elements_by_tag_name("b")[0];
+        assert_eq!(Some("<b>t</b>".to_string()), doc.serialize(b));
+        assert_eq!(Some("<a x=\"1\"><b>t</b></a>".to_string()), doc.serialize(doc.root_element()));
+        assert_eq!(None, doc.serialize(100));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let doc = parse_ok("<a x='\"'>t<![CDATA[c]]><!--n--><?pi v?><?empty?></a>");
+        assert_eq!(
+            Some(concat!(
+                r#"{"type":"element","name":"a","attributes":{"x":"\""},"children":["#,
+                r#"{"type":"text","value":"t"},{"type":"cdata","value":"c"},{"type":"comment","value":"n"},"#,
+                r#"{"type":"pi","target":"pi","value":"v"},{"type":"pi","target":"empty"}]}"#,
+            ).to_string()),
+            doc.to_json(doc.root_element()),
+        );
+        assert_eq!(
+            Some(r#"{"type":"root","children":[{"type":"element","name":"a","attributes":{},"children":[]}]}"#.to_string()),
+            parse_ok("<a/>").to_json(0),
+        );
+        assert_eq!(None, doc.to_json(100));
+    }
+}
@@ -0,0 +1,375 @@
+//! Decoding of [WAP Binary XML](https://www.w3.org/TR/wbxml/) documents, versions 1.0 to 1.3.
+//!
+//! Tags and attributes are encoded as tokens of code pages, which are defined by the application of a document,
+//! e.g. Exchange ActiveSync or OMA provisioning, and registered by its public identifier in a [CodePageRegistry].
+//! A document is decoded into markup, which can be read by any of the parsers, or into the tokens of the
+//! [tokenizer](crate::tokenize::XmlTokenizer).
+//!
+//! Documents in UTF-8, US-ASCII and ISO-8859-1 are supported. Opaque data must be UTF-8 text,
+//! the application-specific extension tokens are not supported.
+//!
+//! ```
+//! use jaxp_rust::wbxml::{decode, CodePageRegistry, CodePages};
+//!
+//! let mut code_pages = CodePages::default();
+//! code_pages.add_tags(0, &[(0x05, "Sync"), (0x06, "Status")]);
+//! let mut registry = CodePageRegistry::default();
+//! registry.by_id.insert(0x01, code_pages);
+//! // version 1.3, public identifier 1, UTF-8, an empty string table
+//! let wbxml = [0x03, 0x01, 0x6A, 0x00, 0x45, 0x46, 0x03, b'1', 0x00, 0x01, 0x01];
+//! assert_eq!("<Sync><Status>1</Status></Sync>", decode(&wbxml, &registry).unwrap());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str;
+
+use crate::error::XmlError;
+use crate::escape::{escape_attribute, escape_text};
+use crate::token::XmlTokenOwned;
+use crate::tokenize::XmlTokenizer;
+use crate::xmlchar::{is_valid_xml_name, XmlChar};
+
+const SWITCH_PAGE: u8 = 0x00;
+const END: u8 = 0x01;
+const ENTITY: u8 = 0x02;
+const STR_I: u8 = 0x03;
+const LITERAL: u8 = 0x04;
+const PI: u8 = 0x43;
+const STR_T: u8 = 0x83;
+const OPAQUE: u8 = 0xC3;
+/// Flags of a tag token
+const ATTRIBUTES_FLAG: u8 = 0x80;
+const CONTENT_FLAG: u8 = 0x40;
+
+/// [IANA MIBenum](https://www.iana.org/assignments/character-sets) values of the supported character sets
+const MIB_UNKNOWN: u32 = 0;
+const MIB_US_ASCII: u32 = 3;
+const MIB_ISO_8859_1: u32 = 4;
+const MIB_UTF_8: u32 = 106;
+
+#[derive(Debug)]
+pub enum WbxmlError {
+    UnexpectedEnd,
+    UnsupportedVersion(u8),
+    /// A token that is not allowed at its position
+    IllegalToken { pos: usize, token: u8 },
+    /// A token that is not in the code page
+    UnknownToken { pos: usize, page: u8, token: u8 },
+    /// An offset beyond the string table
+    InvalidStringTableIndex { pos: usize, index: u32 },
+    /// A string that is not valid in the character set of the document or not allowed in markup, e.g. a name with spaces
+    InvalidString { pos: usize },
+    Unsupported(String),
+    Xml(XmlError),
+}
+
+impl Display for WbxmlError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            WbxmlError::UnexpectedEnd => write!(f, "unexpected end of the input"),
+            WbxmlError::UnsupportedVersion(version) => write!(f, "unsupported version {}.{}", 1 + (version >> 4), version & 0x0F),
+            WbxmlError::IllegalToken { pos, token } => write!(f, "illegal token {:#04x} at {}", token, pos),
+            WbxmlError::UnknownToken { pos, page, token } => write!(f, "unknown token {:#04x} of code page {} at {}", token, page, pos),
+            WbxmlError::InvalidStringTableIndex { pos, index } => write!(f, "index {} at {} is beyond the string table", index, pos),
+            WbxmlError::InvalidString { pos } => write!(f, "invalid string at {}", pos),
+            WbxmlError::Unsupported(construct) => write!(f, "{} are not supported", construct),
+            WbxmlError::Xml(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for WbxmlError {}
+
+impl From<XmlError> for WbxmlError {
+    fn from(e: XmlError) -> Self {
+        WbxmlError::Xml(e)
+    }
+}
+
+/// The public identifier of a document, which determines its code pages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PublicId {
+    /// A well-known identifier, e.g. 0x01 for an unknown one, which Exchange ActiveSync uses
+    Known(u32),
+    /// A formal public identifier, e.g. `-//WAPFORUM//DTD WML 1.1//EN`
+    Name(String),
+}
+
+/// The tokens of tags and attributes by code page.
+/// Tag tokens are given without the flags for attributes and content, i.e. in `0x05..=0x3F`.
+#[derive(Clone, Debug, Default)]
+pub struct CodePages {
+    pub tags: HashMap<(u8, u8), String>,
+    /// Attribute names and the start of their values, which is often empty, e.g. `("href", "http://")`
+    pub attribute_starts: HashMap<(u8, u8), (String, String)>,
+    /// Parts of attribute values, e.g. `.com/`, for tokens in `0x80..=0xFF`
+    pub attribute_values: HashMap<(u8, u8), String>,
+}
+
+impl CodePages {
+    /// Add the tags of a code page.
+    pub fn add_tags(&mut self, page: u8, tags: &[(u8, &str)]) {
+        self.tags.extend(tags.iter().map(|(token, name)| ((page, *token), name.to_string())));
+    }
+}
+
+/// Code pages by the public identifiers of the documents that use them.
+#[derive(Clone, Debug, Default)]
+pub struct CodePageRegistry {
+    pub by_id: HashMap<u32, CodePages>,
+    pub by_name: HashMap<String, CodePages>,
+}
+
+impl CodePageRegistry {
+    pub fn code_pages(&self, public_id: &PublicId) -> Option<&CodePages> {
+        match public_id {
+            PublicId::Known(id) => self.by_id.get(id),
+            PublicId::Name(name) => self.by_name.get(name),
+        }
+    }
+}
+
+/// Decode a document into markup.
+/// A document whose public identifier is not registered can only use literal tags and attributes.
+pub fn decode(wbxml: &[u8], registry: &CodePageRegistry) -> Result<String, WbxmlError> {
+    let default_code_pages = CodePages::default();
+    let mut decoder = Decoder { wbxml, pos: 0, string_table: &[], is_latin1: false, code_pages: &default_code_pages, tag_page: 0, attribute_page: 0 };
+    let public_id = decoder.header()?;
+    if let Some(code_pages) = registry.code_pages(&public_id) {
+        decoder.code_pages = code_pages;
+    }
+    decoder.body()
+}
+
+/// Decode a document into the tokens of its markup.
+pub fn decode_tokens(wbxml: &[u8], registry: &CodePageRegistry) -> Result<Vec<XmlTokenOwned>, WbxmlError> {
+    Ok(XmlTokenizer::default().tokenize_owned(&decode(wbxml, registry)?)?)
+}
+
+struct Decoder<'w, 'c> {
+    wbxml: &'w [u8],
+    pos: usize,
+    string_table: &'w [u8],
+    /// The character set is ISO-8859-1 instead of UTF-8 or its subset US-ASCII
+    is_latin1: bool,
+    code_pages: &'c CodePages,
+    /// Tags and attributes switch their code pages independently
+    tag_page: u8,
+    attribute_page: u8,
+}
+
+impl<'w> Decoder<'w, '_> {
+    fn read(&mut self) -> Result<u8, WbxmlError> {
+        let octet = *self.wbxml.get(self.pos).ok_or(WbxmlError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(octet)
+    }
+
+    fn read_octets(&mut self, len: usize) -> Result<&'w [u8], WbxmlError> {
+        let end = self.pos.checked_add(len).ok_or(WbxmlError::UnexpectedEnd)?;
+        let octets = self.wbxml.get(self.pos..end).ok_or(WbxmlError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(octets)
+    }
+
+    /// A multi-byte integer of up to 32 bits, seven bits per octet with the highest bit set in all but the last one.
+    fn read_integer(&mut self) -> Result<u32, WbxmlError> {
+        let mut n: u32 = 0;
+        loop {
+            let pos = self.pos;
+            let octet = self.read()?;
+            if n > u32::MAX >> 7 {
+                return Err(WbxmlError::IllegalToken { pos, token: octet });
+            }
+            n = n << 7 | (octet & 0x7F) as u32;
+            if octet & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+    }
+
+    fn decode_string(&self, octets: &[u8], pos: usize) -> Result<String, WbxmlError> {
+        if self.is_latin1 {
+            return Ok(octets.iter().map(|&octet| octet as char).collect());
+        }
+        str::from_utf8(octets).map(str::to_string).map_err(|_| WbxmlError::InvalidString { pos })
+    }
+
+    /// A string terminated by a null octet.
+    fn inline_string(&mut self) -> Result<String, WbxmlError> {
+        let pos = self.pos;
+        let len = self.wbxml[pos..].iter().position(|&octet| octet == 0).ok_or(WbxmlError::UnexpectedEnd)?;
+        let octets = self.read_octets(len + 1)?;
+        self.decode_string(&octets[..len], pos)
+    }
+
+    /// A string of the string table, by the offset that is read.
+    fn table_string(&mut self) -> Result<String, WbxmlError> {
+        let pos = self.pos;
+        let index = self.read_integer()?;
+        let rest = self.string_table.get(index as usize..).filter(|rest| !rest.is_empty()).ok_or(WbxmlError::InvalidStringTableIndex { pos, index })?;
+        let len = rest.iter().position(|&octet| octet == 0).unwrap_or(rest.len());
+        self.decode_string(&rest[..len], pos)
+    }
+
+    /// A name of the string table, of a literal tag or attribute.
+    fn table_name(&mut self) -> Result<String, WbxmlError> {
+        let pos = self.pos;
+        let name = self.table_string()?;
+        if !is_valid_xml_name(&name) {
+            return Err(WbxmlError::InvalidString { pos });
+        }
+        Ok(name)
+    }
+
+    /// The character that an entity token stands for.
+    fn entity(&mut self) -> Result<char, WbxmlError> {
+        let pos = self.pos;
+        char::from_u32(self.read_integer()?).filter(|c| c.is_xml_char()).ok_or(WbxmlError::InvalidString { pos })
+    }
+
+    fn opaque(&mut self) -> Result<String, WbxmlError> {
+        let len = self.read_integer()? as usize;
+        let pos = self.pos;
+        let octets = self.read_octets(len)?;
+        str::from_utf8(octets).map(str::to_string).map_err(|_| WbxmlError::InvalidString { pos })
+    }
+
+    /// Read the version, public identifier, character set and string table.
+    fn header(&mut self) -> Result<PublicId, WbxmlError> {
+        let version = self.read()?;
+        if version > 0x03 {
+            return Err(WbxmlError::UnsupportedVersion(version));
+        }
+        let id = self.read_integer()?;
+        // a formal public identifier is an offset into the string table, which follows
+        let name_pos = self.pos;
+        if id == 0 {
+            self.read_integer()?;
+        }
+        // version 1.0 has no character set
+        let charset = if version == 0x00 { MIB_UTF_8 } else { self.read_integer()? };
+        self.is_latin1 = match charset {
+            MIB_UNKNOWN | MIB_US_ASCII | MIB_UTF_8 => false,
+            MIB_ISO_8859_1 => true,
+            _ => return Err(WbxmlError::Unsupported(format!("character sets like MIBenum {}", charset))),
+        };
+        let len = self.read_integer()? as usize;
+        self.string_table = self.read_octets(len)?;
+        if id != 0 {
+            return Ok(PublicId::Known(id));
+        }
+        let pos = self.pos;
+        self.pos = name_pos;
+        let name = self.table_string()?;
+        self.pos = pos;
+        Ok(PublicId::Name(name))
+    }
+
+    /// The processing instructions and the root element, up to the end of the input.
+    fn body(&mut self) -> Result<String, WbxmlError> {
+        let mut markup = String::new();
+        let mut open: Vec<String> = vec![];
+        let mut has_root = false;
+        loop {
+            if self.pos == self.wbxml.len() && open.is_empty() && has_root {
+                return Ok(markup);
+            }
+            let pos = self.pos;
+            let token = self.read()?;
+            match token {
+                SWITCH_PAGE => self.tag_page = self.read()?,
+                END => match open.pop() {
+                    Some(name) => markup.push_str(&format!("</{}>", name)),
+                    None => return Err(WbxmlError::IllegalToken { pos, token }),
+                },
+                PI => markup.push_str(&self.processing_instruction(pos)?),
+                _ if open.is_empty() && (has_root || token & 0x3F < LITERAL) => return Err(WbxmlError::IllegalToken { pos, token }),
+                ENTITY => markup.push_str(&escape_text(&self.entity()?.to_string())),
+                STR_I => markup.push_str(&escape_text(&self.inline_string()?)),
+                STR_T => markup.push_str(&escape_text(&self.table_string()?)),
+                OPAQUE => markup.push_str(&escape_text(&self.opaque()?)),
+                _ if token & 0x3F < LITERAL => return Err(WbxmlError::Unsupported("extension tokens".to_string())),
+                _ => {
+                    has_root = true;
+                    let name = self.tag_name(pos, token)?;
+                    markup.push('<');
+                    markup.push_str(&name);
+                    if token & ATTRIBUTES_FLAG != 0 {
+                        for (name, value) in self.attributes()? {
+                            markup.push_str(&format!(" {}=\"{}\"", name, escape_attribute(&value, '"')));
+                        }
+                    }
+                    if token & CONTENT_FLAG != 0 {
+                        markup.push('>');
+                        open.push(name);
+                    } else {
+                        markup.push_str("/>");
+                    }
+                }
+            }
+        }
+    }
+
+    fn tag_name(&mut self, pos: usize, token: u8) -> Result<String, WbxmlError> {
+        match token & 0x3F {
+            LITERAL => self.table_name(),
+            identity => {
+                let key = (self.tag_page, identity);
+                self.code_pages.tags.get(&key).cloned().ok_or(WbxmlError::UnknownToken { pos, page: self.tag_page, token })
+            }
+        }
+    }
+
+    /// Attribute names and values up to the END token.
+    fn attributes(&mut self) -> Result<Vec<(String, String)>, WbxmlError> {
+        let mut attributes: Vec<(String, String)> = vec![];
+        loop {
+            let pos = self.pos;
+            let token = self.read()?;
+            let value = match token {
+                SWITCH_PAGE => {
+                    self.attribute_page = self.read()?;
+                    continue;
+                }
+                END => return Ok(attributes),
+                LITERAL => {
+                    attributes.push((self.table_name()?, String::new()));
+                    continue;
+                }
+                0x40..=0x42 | 0x80..=0x82 | 0xC0..=0xC2 => return Err(WbxmlError::Unsupported("extension tokens".to_string())),
+                0x44 | 0x84 | 0xC4 | PI => return Err(WbxmlError::IllegalToken { pos, token }),
+                0x05..=0x7F => {
+                    let key = (self.attribute_page, token);
+                    let (name, value) = self.code_pages.attribute_starts.get(&key).ok_or(WbxmlError::UnknownToken { pos, page: self.attribute_page, token })?;
+                    attributes.push((name.clone(), value.clone()));
+                    continue;
+                }
+                // the remaining tokens are parts of the value of the last attribute
+                _ if attributes.is_empty() => return Err(WbxmlError::IllegalToken { pos, token }),
+                ENTITY => self.entity()?.to_string(),
+                STR_I => self.inline_string()?,
+                STR_T => self.table_string()?,
+                OPAQUE => self.opaque()?,
+                _ => {
+                    let key = (self.attribute_page, token);
+                    self.code_pages.attribute_values.get(&key).cloned().ok_or(WbxmlError::UnknownToken { pos, page: self.attribute_page, token })?
+                }
+            };
+            if let Some((_, attribute_value)) = attributes.last_mut() {
+                attribute_value.push_str(&value);
+            }
+        }
+    }
+
+    /// The markup of a processing instruction, whose target and value are encoded like an attribute.
+    fn processing_instruction(&mut self, pos: usize) -> Result<String, WbxmlError> {
+        let attributes = self.attributes()?;
+        let (target, value) = match attributes.as_slice() {
+            [(target, value)] if !target.eq_ignore_ascii_case("xml") && !target.contains(':') && !value.contains("?>") => (target, value),
+            _ => return Err(WbxmlError::IllegalToken { pos, token: PI }),
+        };
+        Ok(if value.is_empty() { format!("<?{}?>", target) } else { format!("<?{} {}?>", target, value) })
+    }
+}
@@ -0,0 +1,948 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io;
+use std::io::{ErrorKind, Write};
+
+use crate::document::{ContentModel, Node, NodeType, XmlVersion};
+use crate::namespace::{declared_prefix, split_qname, XML_NAMESPACE};
+use crate::refs::{decode_char_ref, decode_entities};
+use crate::escape::{escape_attribute_with, escape_text_with, EscapeStyle};
+use crate::xmlchar::{is_valid_xml_name, XmlByte, XmlChar};
+
+/// Which quotes delimit attribute values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    #[default]
+    Double,
+    Single,
+    /// The quotes of the input for the attributes of a parsed document, see [SerializeOptions::quote_style].
+    /// Double quotes for other attributes and for the values that an [XmlWriter] escapes itself.
+    Preserve,
+}
+
+impl QuoteStyle {
+    fn quote(self) -> char {
+        match self {
+            QuoteStyle::Double | QuoteStyle::Preserve => '"',
+            QuoteStyle::Single => '\'',
+        }
+    }
+}
+
+/// The character encoding of the output of an [XmlWriter].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputEncoding {
+    #[default]
+    Utf8,
+    /// Little endian, starting with a byte order mark
+    Utf16Le,
+    /// Big endian, starting with a byte order mark
+    Utf16Be,
+    /// ISO-8859-1. Other characters of character data and attribute values are written as character references,
+    /// anywhere else they are rejected with an error of kind [InvalidData](ErrorKind::InvalidData).
+    Latin1,
+}
+
+impl OutputEncoding {
+    /// The name in the XML declaration
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputEncoding::Utf8 => "UTF-8",
+            OutputEncoding::Utf16Le | OutputEncoding::Utf16Be => "UTF-16",
+            OutputEncoding::Latin1 => "ISO-8859-1",
+        }
+    }
+
+    /// Replace the characters that cannot be encoded by character references.
+    fn escape_unencodable(self, text: &str) -> Cow<'_, str> {
+        if self != OutputEncoding::Latin1 || text.chars().all(|c| (c as u32) <= 0xFF) {
+            return Cow::Borrowed(text);
+        }
+        let mut escaped = String::with_capacity(text.len() + 8);
+        for c in text.chars() {
+            if (c as u32) <= 0xFF {
+                escaped.push(c);
+            } else {
+                escaped.push_str(&format!("&#x{:X};", c as u32));
+            }
+        }
+        Cow::Owned(escaped)
+    }
+}
+
+/// Transcodes the UTF-8 written by an [XmlWriter], which always writes whole strings.
+struct Encoder<W: Write> {
+    inner: W,
+    encoding: OutputEncoding,
+    has_written: bool,
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let text = std::str::from_utf8(buf).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        let bom = !self.has_written;
+        let bytes = match self.encoding {
+            OutputEncoding::Utf8 => Cow::Borrowed(buf),
+            OutputEncoding::Utf16Le | OutputEncoding::Utf16Be => {
+                let is_le = self.encoding == OutputEncoding::Utf16Le;
+                let mut bytes = Vec::with_capacity(2 * buf.len() + 2);
+                for unit in bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16()) {
+                    bytes.extend_from_slice(&if is_le { unit.to_le_bytes() } else { unit.to_be_bytes() });
+                }
+                Cow::Owned(bytes)
+            }
+            OutputEncoding::Latin1 => Cow::Owned(text.chars()
+                .map(|c| u8::try_from(c as u32).map_err(|_| io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Character {:?} cannot be encoded in ISO-8859-1", c),
+                )))
+                .collect::<io::Result<Vec<u8>>>()?),
+        };
+        self.has_written |= !buf.is_empty();
+        self.inner.write_all(&bytes)
+    }
+
+    /// Format first, so that nothing is written if a character cannot be encoded
+    fn write_fmt(&mut self, args: std::fmt::Arguments) -> io::Result<()> {
+        self.write_all(args.to_string().as_bytes())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Streaming XML serializer.
+///
+/// Start tags are kept open until the first content is written,
+/// elements without content are written as empty element tags.
+///
+/// Names, attributes, character data and the nesting of elements are checked before they are written,
+/// so that the output parses as well-formed XML. Anything that would not is rejected with an error of kind
+/// [InvalidInput](ErrorKind::InvalidInput) and not written. A writer created with [XmlWriter::unchecked] skips the checks.
+///
+/// The output is UTF-8 unless another [OutputEncoding] is set.
+pub struct XmlWriter<W: Write> {
+    out: Encoder<W>,
+    open_elements: Vec<String>,
+    start_tag_open: bool,
+    is_checked: bool,
+    /// The names of the attributes of the open start tag, only kept if checked
+    attribute_names: Vec<String>,
+    quote_style: QuoteStyle,
+    escape_style: EscapeStyle,
+}
+
+impl<W: Write> XmlWriter<W> {
+    pub fn new(out: W) -> Self {
+        XmlWriter {
+            out: Encoder { inner: out, encoding: OutputEncoding::default(), has_written: false },
+            open_elements: vec![],
+            start_tag_open: false,
+            is_checked: true,
+            attribute_names: vec![],
+            quote_style: QuoteStyle::default(),
+            escape_style: EscapeStyle::default(),
+        }
+    }
+
+    /// A writer without checks for output that is known to be well-formed, e.g. nodes of a parsed document.
+    /// Only attributes outside of start tags and end tags without an open element are still rejected.
+    pub fn unchecked(out: W) -> Self {
+        XmlWriter { is_checked: false, ..XmlWriter::new(out) }
+    }
+
+    /// The quotes of attribute values, double quotes by default.
+    pub fn set_quote_style(&mut self, quote_style: QuoteStyle) {
+        self.quote_style = quote_style;
+    }
+
+    /// How [attribute](XmlWriter::attribute) and [text](XmlWriter::text) escape their input, [EscapeStyle::Standard] by default.
+    pub fn set_escape_style(&mut self, escape_style: EscapeStyle) {
+        self.escape_style = escape_style;
+    }
+
+    /// The encoding of the output, UTF-8 by default. Set it before writing anything.
+    pub fn set_encoding(&mut self, encoding: OutputEncoding) {
+        self.out.encoding = encoding;
+    }
+
+    /// Get back the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out.inner
+    }
+
+    /// Get back the underlying writer after checking that all elements were closed.
+    pub fn finish(self) -> io::Result<W> {
+        match self.open_elements.last() {
+            Some(name) => Err(invalid(format!("Element {} is not closed", name))),
+            None => Ok(self.out.inner),
+        }
+    }
+
+    /// Write the XML declaration with the name of the [OutputEncoding], it must come first.
+    pub fn xml_declaration(&mut self, version: &XmlVersion, opt_standalone: Option<bool>) -> io::Result<()> {
+        if self.is_checked {
+            if self.out.has_written {
+                return Err(invalid("The XML declaration must be at the start of the output".to_string()));
+            }
+            let is_valid_version = version.as_str().strip_prefix("1.")
+                .is_some_and(|minor| !minor.is_empty() && minor.bytes().all(|b| b.is_ascii_digit()));
+            if !is_valid_version {
+                return Err(invalid(format!("Invalid XML version {:?}", version.as_str())));
+            }
+        }
+        write!(self.out, "<?xml version=\"{}\" encoding=\"{}\"", version.as_str(), self.out.encoding.name())?;
+        match opt_standalone {
+            Some(true) => self.out.write_all(b" standalone=\"yes\"?>"),
+            Some(false) => self.out.write_all(b" standalone=\"no\"?>"),
+            None => self.out.write_all(b"?>"),
+        }
+    }
+
+    pub fn start_element(&mut self, name: &str) -> io::Result<()> {
+        if self.is_checked {
+            check_name(name)?;
+            self.attribute_names.clear();
+        }
+        self.close_start_tag()?;
+        write!(self.out, "<{}", name)?;
+        self.open_elements.push(name.to_string());
+        self.start_tag_open = true;
+        Ok(())
+    }
+
+    /// Write an attribute of the current start tag, the value is escaped.
+    pub fn attribute(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.attribute_quoted(name, value, self.quote_style.quote())
+    }
+
+    /// Write an attribute of the current start tag delimited by `quote`, either `"` or `'`, the value is escaped.
+    pub fn attribute_quoted(&mut self, name: &str, value: &str, quote: char) -> io::Result<()> {
+        if quote != '"' && quote != '\'' {
+            return Err(invalid(format!("Invalid quote {:?}", quote)));
+        }
+        self.attribute_raw_quoted(name, &escape_attribute_with(value, quote, self.escape_style), quote)
+    }
+
+    /// Write an attribute of the current start tag, the value must already be escaped.
+    /// Values containing the quote of the [QuoteStyle] are enclosed in the other quote.
+    pub fn attribute_raw(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.attribute_raw_quoted(name, value, self.quote_style.quote())
+    }
+
+    /// Write an attribute of the current start tag, the value must already be escaped.
+    /// Values containing `quote` are enclosed in the other quote.
+    pub fn attribute_raw_quoted(&mut self, name: &str, value: &str, quote: char) -> io::Result<()> {
+        if !self.start_tag_open {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Attributes must directly follow a start tag"));
+        }
+        if self.is_checked {
+            check_name(name)?;
+            if self.attribute_names.iter().any(|n| n == name) {
+                return Err(invalid(format!("Duplicate attribute {}", name)));
+            }
+            check_escaped(value)?;
+            if value.contains('"') && value.contains('\'') {
+                return Err(invalid(format!("Value of attribute {} contains both quotes", name)));
+            }
+            self.attribute_names.push(name.to_string());
+        }
+        let quote = match quote {
+            '"' if value.contains('"') => '\'',
+            '\'' if value.contains('\'') => '"',
+            quote => quote,
+        };
+        let value = self.out.encoding.escape_unencodable(value);
+        write!(self.out, " {}={}{}{}", name, quote, value, quote)
+    }
+
+    pub fn end_element(&mut self) -> io::Result<()> {
+        let name = match self.open_elements.pop() {
+            Some(name) => name,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, "No element to close")),
+        };
+        if self.start_tag_open {
+            self.start_tag_open = false;
+            self.out.write_all(b"/>")
+        } else {
+            write!(self.out, "</{}>", name)
+        }
+    }
+
+    /// Write character data, the text is escaped.
+    pub fn text(&mut self, text: &str) -> io::Result<()> {
+        self.text_raw(&escape_text_with(text, self.escape_style))
+    }
+
+    /// Write character data, the text must already be escaped.
+    pub fn text_raw(&mut self, text: &str) -> io::Result<()> {
+        if self.is_checked {
+            check_escaped(text)?;
+            if text.contains("]]>") {
+                return Err(invalid("Character data must not contain ]]>".to_string()));
+            }
+        }
+        self.close_start_tag()?;
+        let text = self.out.encoding.escape_unencodable(text);
+        self.out.write_all(text.as_bytes())
+    }
+
+    /// Write markup as it is, without any checks, e.g. a document type declaration or a copied element.
+    pub fn markup_raw(&mut self, markup: &str) -> io::Result<()> {
+        self.close_start_tag()?;
+        self.out.write_all(markup.as_bytes())
+    }
+
+    pub fn cdata_section(&mut self, text: &str) -> io::Result<()> {
+        if self.is_checked {
+            check_chars(text)?;
+            if text.contains("]]>") {
+                return Err(invalid("CDATA sections must not contain ]]>".to_string()));
+            }
+        }
+        self.close_start_tag()?;
+        write!(self.out, "<![CDATA[{}]]>", text)
+    }
+
+    pub fn comment(&mut self, text: &str) -> io::Result<()> {
+        if self.is_checked {
+            check_chars(text)?;
+            if text.contains("--") || text.ends_with('-') {
+                return Err(invalid("Comments must not contain -- or end with -".to_string()));
+            }
+        }
+        self.close_start_tag()?;
+        write!(self.out, "<!--{}-->", text)
+    }
+
+    pub fn processing_instruction(&mut self, target: &str, opt_value: Option<&str>) -> io::Result<()> {
+        if self.is_checked {
+            check_name(target)?;
+            if target.eq_ignore_ascii_case("xml") {
+                return Err(invalid("The processing instruction target xml is reserved".to_string()));
+            }
+            if let Some(value) = opt_value {
+                check_chars(value)?;
+                if value.contains("?>") {
+                    return Err(invalid("Processing instructions must not contain ?>".to_string()));
+                }
+            }
+        }
+        self.close_start_tag()?;
+        match opt_value {
+            Some(value) => write!(self.out, "<?{} {}?>", target, value),
+            None => write!(self.out, "<?{}?>", target),
+        }
+    }
+
+    fn close_start_tag(&mut self) -> io::Result<()> {
+        if self.start_tag_open {
+            self.start_tag_open = false;
+            self.out.write_all(b">")?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, message)
+}
+
+fn check_name(name: &str) -> io::Result<()> {
+    if is_valid_xml_name(name) {
+        Ok(())
+    } else {
+        Err(invalid(format!("Invalid name {:?}", name)))
+    }
+}
+
+fn check_chars(text: &str) -> io::Result<()> {
+    match text.chars().find(|c| !c.is_xml_char()) {
+        Some(c) => Err(invalid(format!("Character {:?} is not allowed in XML", c))),
+        None => Ok(()),
+    }
+}
+
+/// Check escaped text or an attribute value: `<` must be escaped and references must be well-formed.
+fn check_escaped(text: &str) -> io::Result<()> {
+    check_chars(text)?;
+    if text.contains('<') {
+        return Err(invalid("Unescaped <".to_string()));
+    }
+    for (i, _) in text.match_indices('&') {
+        let reference = match text[i..].find(';') {
+            Some(end) => &text[i..=i + end],
+            None => &text[i..],
+        };
+        let is_valid = match reference.strip_suffix(';') {
+            Some(_) if reference.starts_with("&#") => decode_char_ref(reference).is_ok(),
+            Some(name) => is_valid_xml_name(&name[1..]),
+            None => false,
+        };
+        if !is_valid {
+            return Err(invalid(format!("Malformed reference {:?}", reference)));
+        }
+    }
+    Ok(())
+}
+
+/// Write a node and its subtree. Text and attribute values are copied as they appear in the input.
+pub fn write_node<W: Write>(node: Node, writer: &mut XmlWriter<W>) -> io::Result<()> {
+    write_node_with_options(node, writer, &SerializeOptions::default())
+}
+
+/// Serialize a node and its subtree into a string.
+pub fn serialize(node: Node) -> String {
+    serialize_with_options(node, &SerializeOptions::default())
+}
+
+/// The line breaks that [write_node_pretty] inserts. Line breaks in the content are kept as they are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// The elements that [write_node_pretty] keeps on one line with the surrounding content, like `<b>` or `<a>` of HTML.
+/// Neither the content of an inline element nor content that contains one is indented,
+/// even if it is element-only: the whitespace would become part of the text.
+#[derive(Clone, Debug, Default)]
+pub enum InlineElements {
+    /// Only the elements in mixed content are inline
+    #[default]
+    None,
+    /// The elements with one of the tag names
+    Names(HashSet<String>),
+    Predicate(fn(Node) -> bool),
+}
+
+impl InlineElements {
+    pub fn is_inline(&self, node: Node) -> bool {
+        node.is_element() && match self {
+            InlineElements::None => false,
+            InlineElements::Names(names) => names.contains(node.tag_name()),
+            InlineElements::Predicate(is_inline) => is_inline(node),
+        }
+    }
+}
+
+/// Where [write_node_pretty] puts a trailing comment, which follows its previous sibling on the same line in the input,
+/// like `<port>80</port> <!-- default -->`. Other comments are written on a line of their own, before the node they precede.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrailingComments {
+    /// On a line of their own, like other comments
+    #[default]
+    OwnLine,
+    /// After the previous sibling, separated by a space
+    Keep,
+    /// After the previous sibling, the trailing comments of the same parent start in the same column
+    Align,
+}
+
+/// Formatting of [write_node_pretty].
+#[derive(Clone, Debug)]
+pub struct PrettyPrintOptions {
+    /// Inserted once per nesting level
+    pub indent: String,
+    pub line_ending: LineEnding,
+    pub inline_elements: InlineElements,
+    pub trailing_comments: TrailingComments,
+}
+
+impl PrettyPrintOptions {
+    /// Indent by `width` spaces per level.
+    pub fn spaces(width: usize) -> Self {
+        PrettyPrintOptions { indent: " ".repeat(width), ..PrettyPrintOptions::default() }
+    }
+
+    /// Indent by a tab per level.
+    pub fn tabs() -> Self {
+        PrettyPrintOptions { indent: "\t".to_string(), ..PrettyPrintOptions::default() }
+    }
+}
+
+impl Default for PrettyPrintOptions {
+    fn default() -> Self {
+        PrettyPrintOptions {
+            indent: "  ".to_string(),
+            line_ending: LineEnding::default(),
+            inline_elements: InlineElements::default(),
+            trailing_comments: TrailingComments::default(),
+        }
+    }
+}
+
+/// Write a node and its subtree, putting every child of element-only content on its own, indented line.
+/// Content with [inline elements](InlineElements) is written as is.
+///
+/// Whitespace is significant in mixed content and inside `xml:space="preserve"`
+/// (see [2.10 White Space Handling](https://www.w3.org/TR/xml/#sec-white-space)),
+/// such content is written as is.
+pub fn write_node_pretty<W: Write>(node: Node, writer: &mut XmlWriter<W>, options: &PrettyPrintOptions) -> io::Result<()> {
+    let options = SerializeOptions { pretty: Some(options.clone()), ..SerializeOptions::default() };
+    write_node_with_options(node, writer, &options)
+}
+
+/// Serialize a node and its subtree into a string, see [write_node_pretty].
+pub fn serialize_pretty(node: Node, options: &PrettyPrintOptions) -> String {
+    let options = SerializeOptions { pretty: Some(options.clone()), ..SerializeOptions::default() };
+    serialize_with_options(node, &options)
+}
+
+/// Serialize a node and its subtree into a string without comments and without
+/// the whitespace between the children of element-only content.
+/// Just like for [write_node_pretty], whitespace of mixed content and inside `xml:space="preserve"` is kept.
+pub fn serialize_minified(node: Node) -> String {
+    let options = SerializeOptions { minify: true, ..SerializeOptions::default() };
+    serialize_with_options(node, &options)
+}
+
+/// Namespace normalization of [write_node_with_options].
+///
+/// Every namespace gets a single prefix throughout the output, clashes are resolved
+/// by generating `ns1`, `ns2`, ... prefixes. Declarations are written on the first element
+/// that needs them, unless they are hoisted.
+#[derive(Clone, Debug, Default)]
+pub struct NamespaceOptions {
+    /// Preferred prefix per namespace URI, the empty prefix selects the default namespace.
+    pub preferred_prefixes: HashMap<String, String>,
+    /// Declare all namespaces on the outermost element.
+    pub hoist_declarations: bool,
+    /// Skip declarations of the input that are unused or already in scope.
+    pub drop_redundant_declarations: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SerializeOptions {
+    /// Indent element-only content, see [write_node_pretty]
+    pub pretty: Option<PrettyPrintOptions>,
+    /// Rewrite namespace prefixes and declarations
+    pub namespaces: Option<NamespaceOptions>,
+    /// Drop comments and the whitespace of element-only content, see [serialize_minified]
+    pub minify: bool,
+    /// Write namespace declarations first, ordered by prefix, followed by the other attributes
+    /// ordered by namespace URI and local name, like [Canonical XML](https://www.w3.org/TR/xml-c14n/#DocumentOrder).
+    /// Attributes in no namespace come first.
+    pub sort_attributes: bool,
+    /// Copy elements that are unchanged since parsing from the input as they are, instead of writing them node by node,
+    /// see [Node::original_markup](crate::document::Node::original_markup). Their formatting is kept, e.g. quotes,
+    /// spaces inside of tags and empty-element tags. Has no effect together with the options above or a
+    /// [quote style](SerializeOptions::quote_style), which rewrite the markup, and when writing [OutputEncoding::Latin1],
+    /// which escapes characters that the markup may contain.
+    pub copy_unchanged: bool,
+    /// The quotes of attribute values, those of the writer if `None`. Values that contain the quote are enclosed in the other one.
+    pub quote_style: Option<QuoteStyle>,
+    /// Start the serialization of a document with an XML declaration, see [XmlWriter::xml_declaration].
+    /// The version and standalone declaration are those of the document, the encoding is that of the writer.
+    pub xml_declaration: bool,
+}
+
+/// Write a node and its subtree as configured by the options.
+pub fn write_node_with_options<W: Write>(node: Node, writer: &mut XmlWriter<W>, options: &SerializeOptions) -> io::Result<()> {
+    let preserve = node.ancestors().skip(1).find_map(|n| n.attribute("xml:space")).map(decode_entities).as_deref() == Some("preserve");
+    let prefixes = options.namespaces.as_ref().map(|ns_options| PrefixMap::new(node, ns_options));
+    let mut serializer = Serializer { options, prefixes, scopes: vec![] };
+    serializer.write(node, writer, 0, preserve)
+}
+
+/// Serialize a node and its subtree into a string, see [write_node_with_options].
+pub fn serialize_with_options(node: Node, options: &SerializeOptions) -> String {
+    let mut writer = XmlWriter::unchecked(Vec::new());
+    // writing to a Vec cannot fail
+    write_node_with_options(node, &mut writer, options).unwrap();
+    String::from_utf8(writer.into_inner()).unwrap()
+}
+
+/// The quote that delimits an attribute value in the input, `None` if the value is not part of the input.
+fn original_quote(node: Node, value: &str) -> Option<char> {
+    let input = node.document().input_text();
+    let start = (value.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    if start == 0 || start + value.len() > input.len() {
+        return None;
+    }
+    match input.as_bytes()[start - 1] {
+        quote @ (b'"' | b'\'') => Some(quote as char),
+        _ => None,
+    }
+}
+
+/// A comment on the line of its previous sibling in the input.
+fn is_trailing_comment(node: Node) -> bool {
+    if node.node_type() != NodeType::Comment {
+        return false;
+    }
+    let mut opt_previous = node.prev_sibling();
+    if let Some(previous) = opt_previous.filter(is_whitespace_text) {
+        if previous.text().unwrap().contains('\n') {
+            return false;
+        }
+        opt_previous = previous.prev_sibling();
+    }
+    matches!(
+        opt_previous.map(|n| n.node_type()),
+        Some(NodeType::Element | NodeType::ProcessingInstruction | NodeType::CdataSection)
+    )
+}
+
+/// The width in characters of the last line of serialized output that starts after `indent`.
+fn last_line_width(indent: &str, rendered: &str) -> usize {
+    match rendered.rfind('\n') {
+        Some(i) => rendered[i + 1..].chars().count(),
+        None => indent.chars().count() + rendered.chars().count(),
+    }
+}
+
+fn is_whitespace_text(node: &Node) -> bool {
+    node.node_type() == NodeType::Text && node.text().unwrap().bytes().all(|b| b.is_xml_whitespace())
+}
+
+fn qualify(prefix: &str, local_name: &str) -> String {
+    if prefix.is_empty() {
+        local_name.to_string()
+    } else {
+        format!("{}:{}", prefix, local_name)
+    }
+}
+
+/// The output prefix of every namespace used in a subtree.
+struct PrefixMap {
+    /// namespace URI to element prefix
+    prefixes: HashMap<String, String>,
+    /// namespace URI to attribute prefix, if the element prefix is empty
+    attribute_prefixes: HashMap<String, String>,
+    taken: HashSet<String>,
+}
+
+impl PrefixMap {
+    fn new(subtree: Node, options: &NamespaceOptions) -> PrefixMap {
+        let mut map = PrefixMap { prefixes: HashMap::new(), attribute_prefixes: HashMap::new(), taken: HashSet::new() };
+        map.taken.insert("xml".to_string());
+        map.taken.insert("xmlns".to_string());
+        for node in subtree.descendants().filter(|n| n.is_element()) {
+            if let Some(uri) = node.namespace_uri() {
+                map.assign(uri, node.prefix().unwrap_or(""), options);
+            }
+            for attribute in node.attributes() {
+                match (declared_prefix(attribute.name()), node.attribute_namespace_uri(attribute)) {
+                    (Some(prefix), _) if !options.drop_redundant_declarations && !attribute.value().is_empty() =>
+                        map.assign(attribute.value(), prefix, options),
+                    (None, Some(uri)) => {
+                        map.assign(uri, split_qname(attribute.name()).0.unwrap_or(""), options);
+                        map.attribute_prefix(uri);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        map
+    }
+
+    fn assign(&mut self, uri: &str, source_prefix: &str, options: &NamespaceOptions) {
+        if uri == XML_NAMESPACE || self.prefixes.contains_key(uri) {
+            return;
+        }
+        let candidate = options.preferred_prefixes.get(uri).map(String::as_str).unwrap_or(source_prefix);
+        let prefix = if self.taken.contains(candidate) {
+            self.generate()
+        } else {
+            candidate.to_string()
+        };
+        self.taken.insert(prefix.clone());
+        self.prefixes.insert(uri.to_string(), prefix);
+    }
+
+    fn generate(&mut self) -> String {
+        (1..).map(|i| format!("ns{}", i)).find(|p| !self.taken.contains(p)).unwrap()
+    }
+
+    fn element_prefix(&self, uri: &str) -> &str {
+        if uri == XML_NAMESPACE { "xml" } else { &self.prefixes[uri] }
+    }
+
+    /// Attributes cannot use the default namespace
+    fn attribute_prefix(&mut self, uri: &str) -> String {
+        let prefix = self.element_prefix(uri).to_string();
+        if !prefix.is_empty() {
+            return prefix;
+        }
+        if let Some(prefix) = self.attribute_prefixes.get(uri) {
+            return prefix.clone();
+        }
+        let prefix = self.generate();
+        self.taken.insert(prefix.clone());
+        self.attribute_prefixes.insert(uri.to_string(), prefix.clone());
+        prefix
+    }
+
+    /// All bindings, for hoisting
+    fn bindings(&self) -> Vec<(String, String)> {
+        let mut bindings: Vec<_> = self.prefixes.iter()
+            .chain(self.attribute_prefixes.iter())
+            .map(|(uri, prefix)| (prefix.clone(), uri.clone()))
+            .collect();
+        bindings.sort();
+        bindings
+    }
+}
+
+struct Serializer<'o> {
+    options: &'o SerializeOptions,
+    prefixes: Option<PrefixMap>,
+    /// Namespace bindings (prefix, uri) declared in the output per open element
+    scopes: Vec<Vec<(String, String)>>,
+}
+
+impl Serializer<'_> {
+    fn write<W: Write>(&mut self, node: Node, writer: &mut XmlWriter<W>, depth: usize, preserve: bool) -> io::Result<()> {
+        match node.node_type() {
+            NodeType::Root => {
+                let mut has_declaration = false;
+                if self.options.xml_declaration {
+                    let opt_decl = node.document().xml_declaration();
+                    let version = opt_decl.map_or(XmlVersion::V1_0, |decl| decl.version.clone());
+                    writer.xml_declaration(&version, opt_decl.and_then(|decl| decl.standalone))?;
+                    has_declaration = true;
+                }
+                if self.options.pretty.is_some() {
+                    let children: Vec<Node> = node.children().filter(|c| !is_whitespace_text(c)).collect();
+                    self.write_lines(&children, writer, depth, preserve, has_declaration)?;
+                } else {
+                    for child in node.children() {
+                        self.write(child, writer, depth, preserve)?;
+                    }
+                }
+            }
+            NodeType::Element if self.copies_unchanged(writer) && node.original_markup().is_some() => {
+                writer.markup_raw(node.original_markup().unwrap())?;
+            }
+            NodeType::Element => {
+                let preserve = match node.attribute("xml:space").map(decode_entities).as_deref() {
+                    Some("preserve") => true,
+                    Some("default") => false,
+                    _ => preserve,
+                };
+                self.write_start_tag(node, writer)?;
+                let is_element_only = matches!(node.content_model(), ContentModel::Empty | ContentModel::ElementOnly);
+                match &self.options.pretty {
+                    Some(pretty) if !preserve && is_element_only && !Self::has_inline_content(node, pretty) => {
+                        let children: Vec<Node> = node.children().filter(|c| !is_whitespace_text(c)).collect();
+                        self.write_lines(&children, writer, depth + 1, preserve, true)?;
+                        if !children.is_empty() {
+                            writer.text_raw(&format!("{}{}", pretty.line_ending.as_str(), pretty.indent.repeat(depth)))?;
+                        }
+                    }
+                    _ if self.options.minify && !preserve && is_element_only => {
+                        for child in node.children().filter(|c| !is_whitespace_text(c)) {
+                            self.write(child, writer, depth + 1, preserve)?;
+                        }
+                    }
+                    _ => {
+                        for child in node.children() {
+                            self.write(child, writer, depth + 1, preserve)?;
+                        }
+                    }
+                }
+                self.scopes.pop();
+                writer.end_element()?;
+            }
+            NodeType::Text => writer.text_raw(node.text().unwrap())?,
+            NodeType::CdataSection => writer.cdata_section(node.text().unwrap())?,
+            NodeType::Comment if self.options.minify => (),
+            NodeType::Comment => writer.comment(node.text().unwrap())?,
+            NodeType::ProcessingInstruction => writer.processing_instruction(node.tag_name(), node.text())?,
+        }
+        Ok(())
+    }
+
+    /// Write the children of element-only content or of the root on lines of their own, indented to `depth`.
+    /// Trailing comments stay on the line of their previous sibling, if configured, see [TrailingComments].
+    fn write_lines<W: Write>(&mut self, children: &[Node], writer: &mut XmlWriter<W>, depth: usize, preserve: bool, breaks_first: bool) -> io::Result<()> {
+        let options = self.options;
+        let pretty = options.pretty.as_ref().unwrap();
+        let indent = pretty.indent.repeat(depth);
+        let is_trailing: Vec<bool> = children.iter()
+            .map(|&child| pretty.trailing_comments != TrailingComments::OwnLine && is_trailing_comment(child))
+            .collect();
+        // aligning needs the width of the lines before the comments
+        let opt_rendered = match pretty.trailing_comments {
+            TrailingComments::Align if is_trailing.contains(&true) => Some(children.iter()
+                .map(|&child| self.render(child, writer, depth, preserve))
+                .collect::<io::Result<Vec<String>>>()?),
+            _ => None,
+        };
+        let widths: Vec<usize> = match &opt_rendered {
+            Some(rendered) => rendered.iter().map(|r| last_line_width(&indent, r)).collect(),
+            None => vec![],
+        };
+        let column = (1..children.len()).filter(|&i| is_trailing[i]).filter_map(|i| widths.get(i - 1)).max().copied().unwrap_or(0);
+        for (i, &child) in children.iter().enumerate() {
+            if is_trailing[i] && i > 0 {
+                let padding = if widths.is_empty() { 1 } else { column + 1 - widths[i - 1] };
+                writer.text_raw(&" ".repeat(padding))?;
+            } else if i > 0 || breaks_first {
+                writer.text_raw(&format!("{}{}", pretty.line_ending.as_str(), indent))?;
+            }
+            match &opt_rendered {
+                Some(rendered) => writer.markup_raw(&rendered[i])?,
+                None => self.write(child, writer, depth, preserve)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize a node like `writer` would.
+    fn render<W: Write>(&mut self, node: Node, writer: &XmlWriter<W>, depth: usize, preserve: bool) -> io::Result<String> {
+        let mut rendered = XmlWriter {
+            is_checked: writer.is_checked,
+            quote_style: writer.quote_style,
+            escape_style: writer.escape_style,
+            ..XmlWriter::new(Vec::new())
+        };
+        // escape what ISO-8859-1 cannot encode, as `writer` would, then read the bytes back
+        if writer.out.encoding == OutputEncoding::Latin1 {
+            rendered.set_encoding(OutputEncoding::Latin1);
+            self.write(node, &mut rendered, depth, preserve)?;
+            return Ok(rendered.into_inner().into_iter().map(char::from).collect());
+        }
+        self.write(node, &mut rendered, depth, preserve)?;
+        // the writer writes UTF-8 by default
+        Ok(String::from_utf8(rendered.into_inner()).unwrap())
+    }
+
+    fn has_inline_content(node: Node, pretty: &PrettyPrintOptions) -> bool {
+        let inline = &pretty.inline_elements;
+        inline.is_inline(node) || node.children().any(|child| inline.is_inline(child))
+    }
+
+    /// Copied markup is written as it is, so the output encoding must be able to encode every character.
+    fn copies_unchanged<W: Write>(&self, writer: &XmlWriter<W>) -> bool {
+        let options = self.options;
+        writer.out.encoding != OutputEncoding::Latin1
+            && options.copy_unchanged
+            && options.pretty.is_none()
+            && options.namespaces.is_none()
+            && !options.minify
+            && !options.sort_attributes
+            && options.quote_style.is_none()
+    }
+
+    fn write_start_tag<W: Write>(&mut self, node: Node, writer: &mut XmlWriter<W>) -> io::Result<()> {
+        let prefixes = match &mut self.prefixes {
+            Some(prefixes) => prefixes,
+            None => {
+                self.scopes.push(vec![]);
+                writer.start_element(node.tag_name())?;
+                let mut attributes: Vec<_> = node.attributes().collect();
+                if self.options.sort_attributes {
+                    // declarations first, the default namespace has the empty prefix
+                    attributes.sort_by_cached_key(|a| match declared_prefix(a.name()) {
+                        Some(prefix) => (false, prefix, ""),
+                        None => (true, node.attribute_namespace_uri(a).unwrap_or(""), a.local_name()),
+                    });
+                }
+                for attribute in attributes {
+                    self.write_attribute(node, writer, attribute.name(), attribute.value())?;
+                }
+                return Ok(());
+            }
+        };
+        let ns_options = self.options.namespaces.as_ref().unwrap();
+
+        // (prefix, uri, whether to declare even if already in scope)
+        let mut declarations: Vec<(String, String, bool)> = vec![];
+        if ns_options.hoist_declarations && self.scopes.is_empty() {
+            declarations.extend(prefixes.bindings().into_iter().map(|(prefix, uri)| (prefix, uri, false)));
+        }
+        let name = match node.namespace_uri() {
+            Some(uri) => {
+                let prefix = prefixes.element_prefix(uri).to_string();
+                declarations.push((prefix.clone(), uri.to_string(), false));
+                qualify(&prefix, node.local_name())
+            }
+            // keep names with unbound prefixes as they are
+            None if node.prefix().is_some() => node.tag_name().to_string(),
+            None => {
+                // undeclare a default namespace of an ancestor
+                declarations.push((String::new(), String::new(), false));
+                node.local_name().to_string()
+            }
+        };
+        let mut attributes = vec![];
+        for attribute in node.attributes() {
+            match (declared_prefix(attribute.name()), node.attribute_namespace_uri(attribute)) {
+                (Some(_), _) => {
+                    if !ns_options.drop_redundant_declarations && !attribute.value().is_empty() {
+                        let uri = attribute.value();
+                        let prefix = prefixes.element_prefix(uri).to_string();
+                        declarations.push((prefix, uri.to_string(), true));
+                    }
+                }
+                (None, Some(uri)) => {
+                    let prefix = prefixes.attribute_prefix(uri);
+                    if uri != XML_NAMESPACE {
+                        declarations.push((prefix.clone(), uri.to_string(), false));
+                    }
+                    attributes.push((uri, qualify(&prefix, split_qname(attribute.name()).1), attribute.value()));
+                }
+                (None, None) => attributes.push(("", attribute.name().to_string(), attribute.value())),
+            }
+        }
+
+        let mut scope: Vec<(String, String)> = vec![];
+        for (prefix, uri, is_forced) in declarations {
+            if scope.iter().any(|(p, _)| *p == prefix) {
+                continue;
+            }
+            let in_scope = self.scopes.iter().flatten().rev()
+                .find(|(p, _)| *p == prefix)
+                .map(|(_, u)| u.as_str())
+                .unwrap_or("");
+            if is_forced || in_scope != uri {
+                scope.push((prefix, uri));
+            }
+        }
+        if self.options.sort_attributes {
+            scope.sort();
+            attributes.sort_by(|(uri, name, _), (other_uri, other_name, _)| {
+                (uri, split_qname(name).1).cmp(&(other_uri, split_qname(other_name).1))
+            });
+        }
+        self.scopes.push(scope);
+
+        writer.start_element(&name)?;
+        for (prefix, uri) in self.scopes.last().unwrap() {
+            let name = if prefix.is_empty() { "xmlns".to_string() } else { format!("xmlns:{}", prefix) };
+            self.write_attribute(node, writer, &name, uri)?;
+        }
+        for (_, name, value) in attributes {
+            self.write_attribute(node, writer, &name, value)?;
+        }
+        Ok(())
+    }
+
+    fn write_attribute<W: Write>(&self, node: Node, writer: &mut XmlWriter<W>, name: &str, value: &str) -> io::Result<()> {
+        let quote = match self.options.quote_style {
+            None => return writer.attribute_raw(name, value),
+            Some(QuoteStyle::Preserve) => original_quote(node, value).unwrap_or('"'),
+            Some(quote_style) => quote_style.quote(),
+        };
+        writer.attribute_raw_quoted(name, value, quote)
+    }
+}
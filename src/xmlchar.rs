@@ -1,41 +1,82 @@
+//! The character classes of the XML specification, used by the tokenizer and available to code that checks
+//! names or text itself.
+//!
+//! Characters below U+0100 are classified by a lookup table, all others by a binary search of tables of ranges.
+//!
+//! ```
+//! use jaxp_rust::xmlchar::{XmlByte, XmlChar};
+//!
+//! assert!('é'.is_xml_name_start_char());
+//! assert!(!'-'.is_xml_name_start_char() && '-'.is_xml_name_char());
+//! assert!(!'\u{FFFE}'.is_xml_char());
+//! assert!(b'\t'.is_xml_whitespace());
+//! ```
+
+/// The classes of characters of the XML specification.
 pub trait XmlChar {
     fn is_xml_char(&self) -> bool;
 
     fn is_xml_name_start_char(&self) -> bool;
 
     fn is_xml_name_char(&self) -> bool;
+}
+
 
-    fn is_xml_character_data_char(&self) -> bool;
+/// Compare two names, most names that differ are told apart by their length or their first or last byte
+/// without comparing all bytes. Names of siblings often share a prefix, so the last byte is checked first.
+#[inline]
+pub(crate) fn names_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    a.len() == b.len() && a.last() == b.last() && a.first() == b.first() && a == b
 }
 
+/// Whether the text is a [\[5\] Name](https://www.w3.org/TR/xml/#NT-Name), as the tokenizer accepts for
+/// the names of elements, attributes, processing instructions and entities.
+pub fn is_valid_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_xml_name_start_char()) && chars.all(|c| c.is_xml_name_char())
+}
 
+/// The classes of bytes of the syntax of XML, which only consists of ASCII characters.
 pub trait XmlByte {
     fn is_xml_whitespace(&self) -> bool;
     fn is_xml_quote(&self) -> bool;
     fn is_xml_pubid_char(&self) -> bool;
 }
 
-impl XmlByte for u8 {
-    /// [\[3\] S](https://www.w3.org/TR/xml/#NT-S)
-    fn is_xml_whitespace(&self) -> bool {
-        match self {
-            b' ' | b'\n' | b'\t' | b'\r' => true,
-            _ => false
-        }
-    }
+const CHAR: u8 = 1;
+const NAME_START_CHAR: u8 = 1 << 1;
+const NAME_CHAR: u8 = 1 << 2;
+const WHITESPACE: u8 = 1 << 3;
+const QUOTE: u8 = 1 << 4;
+const PUBID_CHAR: u8 = 1 << 5;
 
-    /// Deduced from  [\[10\] AttValue](https://www.w3.org/TR/xml/#NT-AttValue)
-    fn is_xml_quote(&self) -> bool {
-        match self {
-            b'"' | b'\'' => true,
-            _ => false
-        }
-    }
+/// The classes of the characters U+0000 to U+00FF, which are also those of the bytes of the same value
+/// for the classes of [XmlByte]
+static LATIN_1: [u8; 256] = latin_1_classes();
 
-    /// PubidChar ::= #x20 | #xD | #xA | \[a-zA-Z0-9\] | \[-'()+,./:=?;!*#@$_%\]
-    /// [\[13\] PubidChar](https://www.w3.org/TR/xml/#NT-PubidChar)
-    fn is_xml_pubid_char(&self) -> bool {
-        self.is_ascii_alphanumeric() || match self {
+const fn latin_1_classes() -> [u8; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let b = i as u8;
+        let mut classes = 0;
+        if matches!(b, 0x9 | 0xA | 0xD) || b >= 0x20 {
+            classes |= CHAR;
+        }
+        if matches!(b, b':' | b'A'..=b'Z' | b'_' | b'a'..=b'z' | 0xC0..=0xD6 | 0xD8..=0xF6 | 0xF8..=0xFF) {
+            classes |= NAME_START_CHAR | NAME_CHAR;
+        }
+        if matches!(b, b'-' | b'.' | b'0'..=b'9' | 0xB7) {
+            classes |= NAME_CHAR;
+        }
+        if matches!(b, b' ' | b'\n' | b'\t' | b'\r') {
+            classes |= WHITESPACE;
+        }
+        if matches!(b, b'"' | b'\'') {
+            classes |= QUOTE;
+        }
+        if b.is_ascii_alphanumeric() || matches!(b,
             0x20 |
             0xA |
             0xD |
@@ -45,9 +86,77 @@ impl XmlByte for u8 {
             b'=' | b'?' | b';' |
             b'!' | b'*' | b'#' |
             b'@' | b'$' | b'_' |
-            b'%' => true,
-            _ => false
+            b'%'
+        ) {
+            classes |= PUBID_CHAR;
         }
+        table[i] = classes;
+        i += 1;
+    }
+    table
+}
+
+/// [\[2\] Char](https://www.w3.org/TR/xml/#NT-Char) from U+0100
+static CHAR_RANGES: [(u32, u32); 3] = [
+    (0x100, 0xD7FF),
+    (0xE000, 0xFFFD),
+    (0x10000, 0x10FFFF),
+];
+
+/// [\[4\] NameStartChar](https://www.w3.org/TR/xml/#NT-NameStartChar) from U+0100
+static NAME_START_CHAR_RANGES: [(u32, u32); 10] = [
+    (0x100, 0x2FF),
+    (0x370, 0x37D),
+    (0x37F, 0x1FFF),
+    (0x200C, 0x200D),
+    (0x2070, 0x218F),
+    (0x2C00, 0x2FEF),
+    (0x3001, 0xD7FF),
+    (0xF900, 0xFDCF),
+    (0xFDF0, 0xFFFD),
+    (0x10000, 0xEFFFF),
+];
+
+/// [\[4a\] NameChar](https://www.w3.org/TR/xml/#NT-NameChar) from U+0100, the NameStartChar ranges
+/// merged with U+0300 to U+036F and U+203F to U+2040
+static NAME_CHAR_RANGES: [(u32, u32); 10] = [
+    (0x100, 0x37D),
+    (0x37F, 0x1FFF),
+    (0x200C, 0x200D),
+    (0x203F, 0x2040),
+    (0x2070, 0x218F),
+    (0x2C00, 0x2FEF),
+    (0x3001, 0xD7FF),
+    (0xF900, 0xFDCF),
+    (0xFDF0, 0xFFFD),
+    (0x10000, 0xEFFFF),
+];
+
+/// Whether the character is in one of the sorted, disjoint ranges.
+#[inline]
+fn in_ranges(c: u32, ranges: &[(u32, u32)]) -> bool {
+    let i = ranges.partition_point(|&(_, last)| last < c);
+    i < ranges.len() && ranges[i].0 <= c
+}
+
+impl XmlByte for u8 {
+    /// [\[3\] S](https://www.w3.org/TR/xml/#NT-S)
+    #[inline]
+    fn is_xml_whitespace(&self) -> bool {
+        LATIN_1[*self as usize] & WHITESPACE != 0
+    }
+
+    /// Deduced from  [\[10\] AttValue](https://www.w3.org/TR/xml/#NT-AttValue)
+    #[inline]
+    fn is_xml_quote(&self) -> bool {
+        LATIN_1[*self as usize] & QUOTE != 0
+    }
+
+    /// PubidChar ::= #x20 | #xD | #xA | \[a-zA-Z0-9\] | \[-'()+,./:=?;!*#@$_%\]
+    /// [\[13\] PubidChar](https://www.w3.org/TR/xml/#NT-PubidChar)
+    #[inline]
+    fn is_xml_pubid_char(&self) -> bool {
+        LATIN_1[*self as usize] & PUBID_CHAR != 0
     }
 }
 
@@ -55,54 +164,29 @@ impl XmlByte for u8 {
 impl XmlChar for char {
 
     /// [\[2\] Char](https://www.w3.org/TR/xml/#NT-Char)
+    #[inline]
     fn is_xml_char(&self) -> bool {
-        match self {
-            '\u{9}' |
-            '\u{A}' |
-            '\u{D}' |
-            '\u{20}'..='\u{D7FF}' |
-            '\u{E000}'..='\u{FFFD}' |
-            '\u{10000}'..='\u{10FFFF}' => true,
-            _ => false
+        match *self as u32 {
+            c @ 0..=0xFF => LATIN_1[c as usize] & CHAR != 0,
+            c => in_ranges(c, &CHAR_RANGES),
         }
     }
 
     /// [\[4\] NameStartChar](https://www.w3.org/TR/xml/#NT-NameStartChar)
+    #[inline]
     fn is_xml_name_start_char(&self) -> bool {
-        match self {
-            ':' | 'A'..='Z' | '_' | 'a'..='z' |
-            '\u{C0}'..='\u{D6}' |
-            '\u{D8}'..='\u{F6}' |
-            '\u{F8}'..='\u{2FF}' |
-            '\u{370}'..='\u{37D}' |
-            '\u{37F}'..='\u{1FFF}' |
-            '\u{200C}'..='\u{200D}' |
-            '\u{2070}'..='\u{218F}' |
-            '\u{2C00}'..='\u{2FEF}' |
-            '\u{3001}'..='\u{D7FF}' |
-            '\u{F900}'..='\u{FDCF}' |
-            '\u{FDF0}'..='\u{FFFD}' |
-            '\u{10000}'..='\u{EFFFF}' => true,
-            _ => false
+        match *self as u32 {
+            c @ 0..=0xFF => LATIN_1[c as usize] & NAME_START_CHAR != 0,
+            c => in_ranges(c, &NAME_START_CHAR_RANGES),
         }
     }
 
     /// [\[4a\] NameChar](https://www.w3.org/TR/xml/#NT-NameChar)
+    #[inline]
     fn is_xml_name_char(&self) -> bool {
-        self.is_xml_name_start_char() || match self {
-            '-' | '.' | '0'..='9' |
-            '\u{B7}' |
-            '\u{0300}'..='\u{036F}' |
-            '\u{203F}'..='\u{2040}' => true,
-            _ => false
+        match *self as u32 {
+            c @ 0..=0xFF => LATIN_1[c as usize] & NAME_CHAR != 0,
+            c => in_ranges(c, &NAME_CHAR_RANGES),
         }
     }
-
-    /// [\[14\] CharData](https://www.w3.org/TR/xml/#NT-CharData)
-    fn is_xml_character_data_char(&self) -> bool {
-        match self {
-            '<' | '&' => false,
-            _ => true
-        }
-    }
-}
\ No newline at end of file
+}
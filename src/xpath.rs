@@ -0,0 +1,1093 @@
+//! A subset of [XPath 1.0](https://www.w3.org/TR/xpath-10/) to select nodes of a [Document](crate::document::Document).
+//!
+//! Supported are location paths with all axes but `following`, `preceding` and `namespace`, predicates,
+//! the operators and the core functions on node-sets, strings, booleans and numbers. Variables are not supported.
+//! Names are compared as qualified names, their prefixes are not resolved to namespaces.
+
+use std::fmt::{Display, Formatter};
+use std::iter::{once, successors};
+
+use crate::document::{Attribute, Node, NodeId, NodeType};
+use crate::namespace::{declared_prefix, split_qname};
+use crate::refs::decode_entities;
+use crate::xmlchar::{names_eq, XmlByte, XmlChar};
+
+/// Why an expression cannot be compiled or evaluated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XPathError {
+    /// The expression is malformed, `pos` is the byte offset of the offending token
+    Syntax { pos: usize, reason: String },
+    /// A function that is not supported, or a call with the wrong number of arguments
+    UnknownFunction(String),
+    /// A value of the wrong type, e.g. a path step applied to a string
+    Type(String),
+    /// A construct that is valid XPath, but not supported where it is used, e.g. a reverse axis in streaming evaluation
+    Unsupported(String),
+}
+
+impl Display for XPathError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            XPathError::Syntax { pos, reason } => write!(f, "syntax error at {}: {}", pos, reason),
+            XPathError::UnknownFunction(name) => write!(f, "unknown function {}", name),
+            XPathError::Type(reason) => write!(f, "type error: {}", reason),
+            XPathError::Unsupported(construct) => write!(f, "{} is not supported", construct),
+        }
+    }
+}
+
+impl std::error::Error for XPathError {}
+
+/// A node that an expression selects. Unlike in a [Document](crate::document::Document), attributes are nodes in XPath.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Item<'a, 'input: 'a> {
+    Node(Node<'a, 'input>),
+    /// An element and the index of one of its attributes
+    Attribute(Node<'a, 'input>, usize),
+}
+
+impl<'a, 'input: 'a> Item<'a, 'input> {
+    /// The node itself, or the element of an attribute.
+    pub fn node(&self) -> Node<'a, 'input> {
+        match *self {
+            Item::Node(node) | Item::Attribute(node, _) => node,
+        }
+    }
+
+    pub fn attribute(&self) -> Option<&'a Attribute<'input>> {
+        match *self {
+            Item::Node(_) => None,
+            Item::Attribute(element, index) => element.attribute_map().get_index(index),
+        }
+    }
+
+    /// The qualified name of an element or attribute, or the target of a processing instruction, empty otherwise.
+    pub fn name(&self) -> &'a str {
+        match self.attribute() {
+            Some(attribute) => attribute.name(),
+            None => self.node().tag_name(),
+        }
+    }
+
+    pub fn local_name(&self) -> &'a str {
+        split_qname(self.name()).1
+    }
+
+    /// The [string-value](https://www.w3.org/TR/xpath-10/#data-model), with references resolved.
+    /// For the root and elements this is the text of all descendants.
+    pub fn string_value(&self) -> String {
+        if let Some(attribute) = self.attribute() {
+            return decode_entities(attribute.value()).into_owned();
+        }
+        let node = self.node();
+        match node.node_type() {
+            NodeType::Root | NodeType::Element => node
+                .descendants()
+                .filter_map(|n| match n.node_type() {
+                    NodeType::Text => n.text().map(decode_entities),
+                    NodeType::CdataSection => n.text().map(Into::into),
+                    _ => None,
+                })
+                .collect(),
+            NodeType::Text => decode_entities(node.text().unwrap_or("")).into_owned(),
+            _ => node.text().unwrap_or("").to_string(),
+        }
+    }
+
+    /// Sorts in document order, an element before its attributes.
+    fn order_key(&self) -> (NodeId, usize) {
+        match *self {
+            Item::Node(node) => (node.id(), 0),
+            Item::Attribute(element, index) => (element.id(), index + 1),
+        }
+    }
+}
+
+/// The result of an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a, 'input: 'a> {
+    /// A node-set, in document order
+    Nodes(Vec<Item<'a, 'input>>),
+    String(String),
+    Number(f64),
+    Boolean(bool),
+}
+
+impl<'a, 'input: 'a> Value<'a, 'input> {
+    /// [boolean()](https://www.w3.org/TR/xpath-10/#function-boolean)
+    pub fn boolean(&self) -> bool {
+        match self {
+            Value::Nodes(items) => !items.is_empty(),
+            Value::String(s) => !s.is_empty(),
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::Boolean(b) => *b,
+        }
+    }
+
+    /// [number()](https://www.w3.org/TR/xpath-10/#function-number)
+    pub fn number(&self) -> f64 {
+        match self {
+            Value::Number(n) => *n,
+            Value::Boolean(b) => f64::from(u8::from(*b)),
+            _ => parse_number(&self.string()),
+        }
+    }
+
+    /// [string()](https://www.w3.org/TR/xpath-10/#function-string), of a node-set the string-value of its first node
+    pub fn string(&self) -> String {
+        match self {
+            Value::Nodes(items) => items.first().map(Item::string_value).unwrap_or_default(),
+            Value::String(s) => s.clone(),
+            Value::Number(n) => format_number(*n),
+            Value::Boolean(b) => b.to_string(),
+        }
+    }
+
+    pub fn into_nodes(self) -> Result<Vec<Item<'a, 'input>>, XPathError> {
+        match self {
+            Value::Nodes(items) => Ok(items),
+            other => Err(XPathError::Type(format!("expected a node-set, found {:?}", other))),
+        }
+    }
+}
+
+fn is_space(c: char) -> bool {
+    c.is_ascii() && (c as u8).is_xml_whitespace()
+}
+
+fn parse_number(s: &str) -> f64 {
+    let trimmed = s.trim_matches(is_space);
+    let unsigned = trimmed.strip_prefix('-').unwrap_or(trimmed);
+    let is_number = unsigned.bytes().any(|b| b.is_ascii_digit())
+        && unsigned.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+        && unsigned.matches('.').count() <= 1;
+    if is_number { trimmed.parse().unwrap_or(f64::NAN) } else { f64::NAN }
+}
+
+fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        "NaN".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if n == n.trunc() && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        format!("{}", n)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Slash,
+    DoubleSlash,
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    At,
+    Comma,
+    Pipe,
+    Dot,
+    DoubleDot,
+    DoubleColon,
+    Star,
+    Plus,
+    Minus,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Literal(String),
+    Number(f64),
+    /// A name, qualified name or `prefix:*`
+    Name(String),
+}
+
+/// Split an expression into tokens with their byte offsets.
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>, XPathError> {
+    let mut tokens = vec![];
+    let mut pos = 0;
+    let char_at = |pos: usize| expression[pos..].chars().next();
+    while let Some(c) = char_at(pos) {
+        let start = pos;
+        pos += c.len_utf8();
+        let next = char_at(pos);
+        let mut pair = |token: Token, second: char, pair_token: Token| {
+            if next == Some(second) {
+                pos += 1;
+                pair_token
+            } else {
+                token
+            }
+        };
+        let token = match c {
+            c if is_space(c) => continue,
+            '/' => pair(Token::Slash, '/', Token::DoubleSlash),
+            '<' => pair(Token::Less, '=', Token::LessOrEqual),
+            '>' => pair(Token::Greater, '=', Token::GreaterOrEqual),
+            '!' if next == Some('=') => pair(Token::NotEqual, '=', Token::NotEqual),
+            ':' if next == Some(':') => pair(Token::DoubleColon, ':', Token::DoubleColon),
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '@' => Token::At,
+            ',' => Token::Comma,
+            '|' => Token::Pipe,
+            '*' => Token::Star,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '=' => Token::Equal,
+            '.' if next == Some('.') => pair(Token::Dot, '.', Token::DoubleDot),
+            '"' | '\'' => {
+                let length = expression[pos..].find(c).ok_or(XPathError::Syntax { pos: start, reason: "unterminated literal".to_string() })?;
+                let literal = expression[pos..pos + length].to_string();
+                pos += length + 1;
+                Token::Literal(literal)
+            }
+            '.' | '0'..='9' => {
+                let length = expression[pos..].find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(expression.len() - pos);
+                pos += length;
+                match &expression[start..pos] {
+                    "." => Token::Dot,
+                    number => Token::Number(number.parse().map_err(|_| XPathError::Syntax { pos: start, reason: format!("malformed number {}", number) })?),
+                }
+            }
+            c if c != ':' && c.is_xml_name_start_char() => {
+                let ncname_length = |pos: usize| expression[pos..].find(|c: char| c == ':' || !c.is_xml_name_char()).unwrap_or(expression.len() - pos);
+                pos += ncname_length(pos);
+                // a prefix, unless the colon starts an axis separator
+                if char_at(pos) == Some(':') && char_at(pos + 1) != Some(':') {
+                    pos += 1;
+                    match char_at(pos) {
+                        Some('*') => pos += 1,
+                        Some(c) if c.is_xml_name_start_char() => pos += ncname_length(pos),
+                        _ => return Err(XPathError::Syntax { pos: start, reason: "malformed qualified name".to_string() }),
+                    }
+                }
+                Token::Name(expression[start..pos].to_string())
+            }
+            c => return Err(XPathError::Syntax { pos: start, reason: format!("unexpected character {:?}", c) }),
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Axis {
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Parent,
+    Ancestor,
+    AncestorOrSelf,
+    FollowingSibling,
+    PrecedingSibling,
+    Attribute,
+    SelfNode,
+}
+
+impl Axis {
+    fn from_name(name: &str) -> Option<Axis> {
+        Some(match name {
+            "child" => Axis::Child,
+            "descendant" => Axis::Descendant,
+            "descendant-or-self" => Axis::DescendantOrSelf,
+            "parent" => Axis::Parent,
+            "ancestor" => Axis::Ancestor,
+            "ancestor-or-self" => Axis::AncestorOrSelf,
+            "following-sibling" => Axis::FollowingSibling,
+            "preceding-sibling" => Axis::PrecedingSibling,
+            "attribute" => Axis::Attribute,
+            "self" => Axis::SelfNode,
+            _ => return None,
+        })
+    }
+
+    /// The items on the axis, reverse axes start with the nearest item.
+    fn items<'a, 'input: 'a>(self, item: Item<'a, 'input>) -> Vec<Item<'a, 'input>> {
+        let node = match item {
+            Item::Node(node) => node,
+            Item::Attribute(element, _) => {
+                return match self {
+                    Axis::Parent => vec![Item::Node(element)],
+                    Axis::Ancestor => element.ancestors().map(Item::Node).collect(),
+                    Axis::AncestorOrSelf => once(item).chain(element.ancestors().map(Item::Node)).collect(),
+                    Axis::SelfNode | Axis::DescendantOrSelf => vec![item],
+                    _ => vec![],
+                };
+            }
+        };
+        match self {
+            Axis::Child => node.children().map(Item::Node).collect(),
+            Axis::Descendant => node.descendants().skip(1).map(Item::Node).collect(),
+            Axis::DescendantOrSelf => node.descendants().map(Item::Node).collect(),
+            Axis::Parent => node.parent().map(Item::Node).into_iter().collect(),
+            Axis::Ancestor => node.ancestors().skip(1).map(Item::Node).collect(),
+            Axis::AncestorOrSelf => node.ancestors().map(Item::Node).collect(),
+            Axis::FollowingSibling => successors(node.next_sibling(), |n| n.next_sibling()).map(Item::Node).collect(),
+            Axis::PrecedingSibling => successors(node.prev_sibling(), |n| n.prev_sibling()).map(Item::Node).collect(),
+            // namespace declarations are not attributes in XPath
+            Axis::Attribute => node
+                .attributes()
+                .enumerate()
+                .filter(|(_, a)| declared_prefix(a.name()).is_none())
+                .map(|(index, _)| Item::Attribute(node, index))
+                .collect(),
+            Axis::SelfNode => vec![item],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum NodeTest {
+    /// `*`
+    Any,
+    /// `prefix:*`
+    Prefix(String),
+    Name(String),
+    /// `node()`
+    Node,
+    /// `text()`, which includes CDATA sections
+    Text,
+    Comment,
+    /// `processing-instruction()`, optionally with a target
+    ProcessingInstruction(Option<String>),
+}
+
+impl NodeTest {
+    /// Whether a name test matches an element or attribute name, always false for node type tests.
+    pub(crate) fn matches_name(&self, name: &str) -> bool {
+        match self {
+            NodeTest::Any => true,
+            NodeTest::Prefix(prefix) => split_qname(name).0 == Some(prefix.as_str()),
+            NodeTest::Name(test) => names_eq(test, name),
+            _ => false,
+        }
+    }
+
+    fn matches(&self, item: Item, axis: Axis) -> bool {
+        // name tests select the principal node type of the axis
+        let is_principal = match item {
+            Item::Attribute(..) => axis == Axis::Attribute,
+            Item::Node(node) => axis != Axis::Attribute && node.is_element(),
+        };
+        match (self, item) {
+            (NodeTest::Node, _) => true,
+            (NodeTest::Any | NodeTest::Prefix(_) | NodeTest::Name(_), _) => is_principal && self.matches_name(item.name()),
+            (NodeTest::Text, Item::Node(node)) => matches!(node.node_type(), NodeType::Text | NodeType::CdataSection),
+            (NodeTest::Comment, Item::Node(node)) => node.is_comment(),
+            (NodeTest::ProcessingInstruction(opt_target), Item::Node(node)) => {
+                node.is_pi() && opt_target.as_ref().is_none_or(|target| target == node.tag_name())
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Step {
+    pub(crate) axis: Axis,
+    pub(crate) test: NodeTest,
+    pub(crate) predicates: Vec<Expr>,
+}
+
+impl Step {
+    /// `descendant-or-self::node()`, which `//` abbreviates
+    fn any_descendant_or_self() -> Step {
+        Step { axis: Axis::DescendantOrSelf, test: NodeTest::Node, predicates: vec![] }
+    }
+
+    fn select<'a, 'input: 'a>(&self, item: Item<'a, 'input>) -> Result<Vec<Item<'a, 'input>>, XPathError> {
+        let mut items: Vec<Item> = self.axis.items(item).into_iter().filter(|i| self.test.matches(*i, self.axis)).collect();
+        for predicate in &self.predicates {
+            items = filter(items, predicate)?;
+        }
+        Ok(items)
+    }
+}
+
+/// Keep the items for which a predicate holds, a number is compared to the position of the item.
+fn filter<'a, 'input: 'a>(items: Vec<Item<'a, 'input>>, predicate: &Expr) -> Result<Vec<Item<'a, 'input>>, XPathError> {
+    let size = items.len();
+    let mut kept = vec![];
+    for (index, item) in items.into_iter().enumerate() {
+        let context = Context { item, position: index + 1, size };
+        let holds = match predicate.evaluate(&context)? {
+            Value::Number(n) => n == (index + 1) as f64,
+            value => value.boolean(),
+        };
+        if holds {
+            kept.push(item);
+        }
+    }
+    Ok(kept)
+}
+
+fn sort_in_document_order(items: &mut Vec<Item>) {
+    items.sort_by_key(Item::order_key);
+    items.dedup();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Function {
+    Last,
+    Position,
+    Count,
+    LocalName,
+    Name,
+    String,
+    Concat,
+    StartsWith,
+    Contains,
+    SubstringBefore,
+    SubstringAfter,
+    Substring,
+    StringLength,
+    NormalizeSpace,
+    Translate,
+    Boolean,
+    Not,
+    True,
+    False,
+    Number,
+    Sum,
+    Floor,
+    Ceiling,
+    Round,
+}
+
+impl Function {
+    /// The function and its minimum and maximum number of arguments.
+    fn from_name(name: &str) -> Option<(Function, usize, usize)> {
+        Some(match name {
+            "last" => (Function::Last, 0, 0),
+            "position" => (Function::Position, 0, 0),
+            "count" => (Function::Count, 1, 1),
+            "local-name" => (Function::LocalName, 0, 1),
+            "name" => (Function::Name, 0, 1),
+            "string" => (Function::String, 0, 1),
+            "concat" => (Function::Concat, 2, usize::MAX),
+            "starts-with" => (Function::StartsWith, 2, 2),
+            "contains" => (Function::Contains, 2, 2),
+            "substring-before" => (Function::SubstringBefore, 2, 2),
+            "substring-after" => (Function::SubstringAfter, 2, 2),
+            "substring" => (Function::Substring, 2, 3),
+            "string-length" => (Function::StringLength, 0, 1),
+            "normalize-space" => (Function::NormalizeSpace, 0, 1),
+            "translate" => (Function::Translate, 3, 3),
+            "boolean" => (Function::Boolean, 1, 1),
+            "not" => (Function::Not, 1, 1),
+            "true" => (Function::True, 0, 0),
+            "false" => (Function::False, 0, 0),
+            "number" => (Function::Number, 0, 1),
+            "sum" => (Function::Sum, 1, 1),
+            "floor" => (Function::Floor, 1, 1),
+            "ceiling" => (Function::Ceiling, 1, 1),
+            "round" => (Function::Round, 1, 1),
+            _ => return None,
+        })
+    }
+
+    fn call<'a, 'input: 'a>(self, args: &[Expr], context: &Context<'a, 'input>) -> Result<Value<'a, 'input>, XPathError> {
+        // string arguments default to the string-value of the context node
+        let string = |index: usize| -> Result<String, XPathError> {
+            match args.get(index) {
+                Some(arg) => Ok(arg.evaluate(context)?.string()),
+                None => Ok(context.item.string_value()),
+            }
+        };
+        let number = |index: usize| -> Result<f64, XPathError> { Ok(args[index].evaluate(context)?.number()) };
+        Ok(match self {
+            Function::Last => Value::Number(context.size as f64),
+            Function::Position => Value::Number(context.position as f64),
+            Function::Count => Value::Number(args[0].evaluate(context)?.into_nodes()?.len() as f64),
+            Function::LocalName | Function::Name => {
+                let opt_item = match args.first() {
+                    Some(arg) => arg.evaluate(context)?.into_nodes()?.first().copied(),
+                    None => Some(context.item),
+                };
+                let name = match opt_item {
+                    Some(item) if self == Function::Name => item.name(),
+                    Some(item) => item.local_name(),
+                    None => "",
+                };
+                Value::String(name.to_string())
+            }
+            Function::String => Value::String(string(0)?),
+            Function::Concat => Value::String(args.iter().map(|arg| arg.evaluate(context).map(|v| v.string())).collect::<Result<_, _>>()?),
+            Function::StartsWith => Value::Boolean(string(0)?.starts_with(&string(1)?)),
+            Function::Contains => Value::Boolean(string(0)?.contains(&string(1)?)),
+            Function::SubstringBefore => {
+                let (s, pattern) = (string(0)?, string(1)?);
+                Value::String(s.find(&pattern).map_or("", |i| &s[..i]).to_string())
+            }
+            Function::SubstringAfter => {
+                let (s, pattern) = (string(0)?, string(1)?);
+                Value::String(s.find(&pattern).map_or("", |i| &s[i + pattern.len()..]).to_string())
+            }
+            Function::Substring => {
+                // characters are counted from 1, the bounds are rounded
+                let s = string(0)?;
+                let start = round(number(1)?);
+                let end = if args.len() == 3 { start + round(number(2)?) } else { f64::INFINITY };
+                Value::String(s.chars().enumerate().filter(|(i, _)| (i + 1) as f64 >= start && ((i + 1) as f64) < end).map(|(_, c)| c).collect())
+            }
+            Function::StringLength => Value::Number(string(0)?.chars().count() as f64),
+            Function::NormalizeSpace => Value::String(string(0)?.split(is_space).filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ")),
+            Function::Translate => {
+                let (s, from, to) = (string(0)?, string(1)?, string(2)?);
+                let to: Vec<char> = to.chars().collect();
+                Value::String(s.chars().filter_map(|c| match from.chars().position(|f| f == c) {
+                    Some(index) => to.get(index).copied(),
+                    None => Some(c),
+                }).collect())
+            }
+            Function::Boolean => Value::Boolean(args[0].evaluate(context)?.boolean()),
+            Function::Not => Value::Boolean(!args[0].evaluate(context)?.boolean()),
+            Function::True => Value::Boolean(true),
+            Function::False => Value::Boolean(false),
+            Function::Number => Value::Number(match args.first() {
+                Some(arg) => arg.evaluate(context)?.number(),
+                None => parse_number(&context.item.string_value()),
+            }),
+            Function::Sum => Value::Number(args[0].evaluate(context)?.into_nodes()?.iter().map(|i| parse_number(&i.string_value())).sum()),
+            Function::Floor => Value::Number(number(0)?.floor()),
+            Function::Ceiling => Value::Number(number(0)?.ceil()),
+            Function::Round => Value::Number(round(number(0)?)),
+        })
+    }
+}
+
+/// [round()](https://www.w3.org/TR/xpath-10/#function-round), which rounds halves up.
+fn round(n: f64) -> f64 {
+    if n.is_finite() { (n + 0.5).floor() } else { n }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Operator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+    Union,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum PathStart {
+    Root,
+    Context,
+    /// A filter expression, e.g. `(a | b)[1]/c`
+    Expr(Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Expr {
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Negate(Box<Expr>),
+    Literal(String),
+    Number(f64),
+    Function(Function, Vec<Expr>),
+    /// A primary expression with predicates
+    Filter(Box<Expr>, Vec<Expr>),
+    Path(PathStart, Vec<Step>),
+}
+
+/// The context of an evaluation, positions count from 1.
+#[derive(Clone, Copy)]
+struct Context<'a, 'input: 'a> {
+    item: Item<'a, 'input>,
+    position: usize,
+    size: usize,
+}
+
+impl Expr {
+    fn evaluate<'a, 'input: 'a>(&self, context: &Context<'a, 'input>) -> Result<Value<'a, 'input>, XPathError> {
+        Ok(match self {
+            Expr::Binary(Operator::Or, left, right) => Value::Boolean(left.evaluate(context)?.boolean() || right.evaluate(context)?.boolean()),
+            Expr::Binary(Operator::And, left, right) => Value::Boolean(left.evaluate(context)?.boolean() && right.evaluate(context)?.boolean()),
+            Expr::Binary(Operator::Union, left, right) => {
+                let mut items = left.evaluate(context)?.into_nodes()?;
+                items.extend(right.evaluate(context)?.into_nodes()?);
+                sort_in_document_order(&mut items);
+                Value::Nodes(items)
+            }
+            Expr::Binary(operator, left, right) => {
+                let (left, right) = (left.evaluate(context)?, right.evaluate(context)?);
+                match operator {
+                    Operator::Plus => Value::Number(left.number() + right.number()),
+                    Operator::Minus => Value::Number(left.number() - right.number()),
+                    Operator::Multiply => Value::Number(left.number() * right.number()),
+                    Operator::Divide => Value::Number(left.number() / right.number()),
+                    Operator::Modulo => Value::Number(left.number() % right.number()),
+                    comparison => Value::Boolean(compare(*comparison, &left, &right)),
+                }
+            }
+            Expr::Negate(expr) => Value::Number(-expr.evaluate(context)?.number()),
+            Expr::Literal(literal) => Value::String(literal.clone()),
+            Expr::Number(n) => Value::Number(*n),
+            Expr::Function(function, args) => function.call(args, context)?,
+            Expr::Filter(primary, predicates) => {
+                let mut items = primary.evaluate(context)?.into_nodes()?;
+                for predicate in predicates {
+                    items = filter(items, predicate)?;
+                }
+                Value::Nodes(items)
+            }
+            Expr::Path(start, steps) => {
+                let mut items = match start {
+                    PathStart::Root => vec![Item::Node(context.item.node().document().root())],
+                    PathStart::Context => vec![context.item],
+                    PathStart::Expr(expr) => expr.evaluate(context)?.into_nodes()?,
+                };
+                for step in steps {
+                    let mut selected = vec![];
+                    for item in items {
+                        selected.extend(step.select(item)?);
+                    }
+                    sort_in_document_order(&mut selected);
+                    items = selected;
+                }
+                Value::Nodes(items)
+            }
+        })
+    }
+}
+
+/// [3.4 Booleans](https://www.w3.org/TR/xpath-10/#booleans): node-sets are compared by the string-values of their nodes,
+/// a comparison holds if it holds for any of them.
+fn compare(operator: Operator, left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Nodes(items), Value::Boolean(_)) => compare_atomic(operator, &Value::Boolean(!items.is_empty()), right),
+        (Value::Boolean(_), Value::Nodes(items)) => compare_atomic(operator, left, &Value::Boolean(!items.is_empty())),
+        (Value::Nodes(left_items), Value::Nodes(right_items)) => left_items.iter().any(|l| {
+            let l = Value::String(l.string_value());
+            right_items.iter().any(|r| compare_atomic(operator, &l, &Value::String(r.string_value())))
+        }),
+        (Value::Nodes(items), _) => items.iter().any(|i| compare_atomic(operator, &Value::String(i.string_value()), right)),
+        (_, Value::Nodes(items)) => items.iter().any(|i| compare_atomic(operator, left, &Value::String(i.string_value()))),
+        _ => compare_atomic(operator, left, right),
+    }
+}
+
+fn compare_atomic(operator: Operator, left: &Value, right: &Value) -> bool {
+    let is_equal = || match (left, right) {
+        (Value::Boolean(_), _) | (_, Value::Boolean(_)) => left.boolean() == right.boolean(),
+        (Value::Number(_), _) | (_, Value::Number(_)) => left.number() == right.number(),
+        _ => left.string() == right.string(),
+    };
+    match operator {
+        Operator::Equal => is_equal(),
+        Operator::NotEqual => !is_equal(),
+        Operator::Less => left.number() < right.number(),
+        Operator::LessOrEqual => left.number() <= right.number(),
+        Operator::Greater => left.number() > right.number(),
+        Operator::GreaterOrEqual => left.number() >= right.number(),
+        _ => unreachable!("not a comparison"),
+    }
+}
+
+/// Recursive descent parser, [3.7 Lexical Structure](https://www.w3.org/TR/xpath-10/#exprlex) decides
+/// whether a name is an operator: it is in the position of one.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn peek_second(&self) -> Option<&Token> {
+        self.tokens.get(self.index + 1).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(_, token)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let is_next = self.peek() == Some(token);
+        if is_next {
+            self.index += 1;
+        }
+        is_next
+    }
+
+    fn eat_name(&mut self, name: &str) -> bool {
+        let is_next = matches!(self.peek(), Some(Token::Name(n)) if n == name);
+        if is_next {
+            self.index += 1;
+        }
+        is_next
+    }
+
+    fn error(&self, reason: &str) -> XPathError {
+        let pos = self.tokens.get(self.index).map_or(self.end, |(pos, _)| *pos);
+        XPathError::Syntax { pos, reason: reason.to_string() }
+    }
+
+    fn expect(&mut self, token: Token, reason: &str) -> Result<(), XPathError> {
+        if self.eat(&token) { Ok(()) } else { Err(self.error(reason)) }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, XPathError> {
+        let mut expr = self.parse_and()?;
+        while self.eat_name("or") {
+            expr = Expr::Binary(Operator::Or, Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, XPathError> {
+        let mut expr = self.parse_equality()?;
+        while self.eat_name("and") {
+            expr = Expr::Binary(Operator::And, Box::new(expr), Box::new(self.parse_equality()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_equality(&mut self) -> Result<Expr, XPathError> {
+        let mut expr = self.parse_relational()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Equal) => Operator::Equal,
+                Some(Token::NotEqual) => Operator::NotEqual,
+                _ => return Ok(expr),
+            };
+            self.index += 1;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.parse_relational()?));
+        }
+    }
+
+    fn parse_relational(&mut self) -> Result<Expr, XPathError> {
+        let mut expr = self.parse_additive()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Less) => Operator::Less,
+                Some(Token::LessOrEqual) => Operator::LessOrEqual,
+                Some(Token::Greater) => Operator::Greater,
+                Some(Token::GreaterOrEqual) => Operator::GreaterOrEqual,
+                _ => return Ok(expr),
+            };
+            self.index += 1;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.parse_additive()?));
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, XPathError> {
+        let mut expr = self.parse_multiplicative()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Plus) => Operator::Plus,
+                Some(Token::Minus) => Operator::Minus,
+                _ => return Ok(expr),
+            };
+            self.index += 1;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.parse_multiplicative()?));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, XPathError> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Star) => Operator::Multiply,
+                Some(Token::Name(name)) if name == "div" => Operator::Divide,
+                Some(Token::Name(name)) if name == "mod" => Operator::Modulo,
+                _ => return Ok(expr),
+            };
+            self.index += 1;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.parse_unary()?));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, XPathError> {
+        if self.eat(&Token::Minus) {
+            return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+        }
+        let mut expr = self.parse_path()?;
+        while self.eat(&Token::Pipe) {
+            expr = Expr::Binary(Operator::Union, Box::new(expr), Box::new(self.parse_path()?));
+        }
+        Ok(expr)
+    }
+
+    fn starts_step(&self) -> bool {
+        matches!(self.peek(), Some(Token::Dot | Token::DoubleDot | Token::At | Token::Star | Token::Name(_)))
+    }
+
+    fn parse_path(&mut self) -> Result<Expr, XPathError> {
+        if self.eat(&Token::Slash) {
+            let steps = if self.starts_step() { self.parse_relative_path()? } else { vec![] };
+            return Ok(Expr::Path(PathStart::Root, steps));
+        }
+        if self.eat(&Token::DoubleSlash) {
+            let mut steps = vec![Step::any_descendant_or_self()];
+            steps.extend(self.parse_relative_path()?);
+            return Ok(Expr::Path(PathStart::Root, steps));
+        }
+        let is_primary = match (self.peek(), self.peek_second()) {
+            (Some(Token::Literal(_) | Token::Number(_) | Token::LeftParen), _) => true,
+            (Some(Token::Name(name)), Some(Token::LeftParen)) => !is_node_type(name),
+            _ => false,
+        };
+        if !is_primary {
+            return Ok(Expr::Path(PathStart::Context, self.parse_relative_path()?));
+        }
+        let primary = self.parse_primary()?;
+        let predicates = self.parse_predicates()?;
+        let filter = if predicates.is_empty() { primary } else { Expr::Filter(Box::new(primary), predicates) };
+        let mut steps = vec![];
+        if self.eat(&Token::DoubleSlash) {
+            steps.push(Step::any_descendant_or_self());
+        } else if !self.eat(&Token::Slash) {
+            return Ok(filter);
+        }
+        steps.extend(self.parse_relative_path()?);
+        Ok(Expr::Path(PathStart::Expr(Box::new(filter)), steps))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, XPathError> {
+        match self.next() {
+            Some(Token::Literal(literal)) => Ok(Expr::Literal(literal)),
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::LeftParen) => {
+                let expr = self.parse_expr()?;
+                self.expect(Token::RightParen, "expected )")?;
+                Ok(expr)
+            }
+            Some(Token::Name(name)) => {
+                self.expect(Token::LeftParen, "expected (")?;
+                let mut args = vec![];
+                if !self.eat(&Token::RightParen) {
+                    loop {
+                        args.push(self.parse_expr()?);
+                        if self.eat(&Token::RightParen) {
+                            break;
+                        }
+                        self.expect(Token::Comma, "expected , or )")?;
+                    }
+                }
+                match Function::from_name(&name) {
+                    Some((function, min, max)) if (min..=max).contains(&args.len()) => Ok(Expr::Function(function, args)),
+                    _ => Err(XPathError::UnknownFunction(format!("{}#{}", name, args.len()))),
+                }
+            }
+            _ => {
+                self.index -= 1;
+                Err(self.error("expected an expression"))
+            }
+        }
+    }
+
+    fn parse_predicates(&mut self) -> Result<Vec<Expr>, XPathError> {
+        let mut predicates = vec![];
+        while self.eat(&Token::LeftBracket) {
+            predicates.push(self.parse_expr()?);
+            self.expect(Token::RightBracket, "expected ]")?;
+        }
+        Ok(predicates)
+    }
+
+    fn parse_relative_path(&mut self) -> Result<Vec<Step>, XPathError> {
+        let mut steps = vec![self.parse_step()?];
+        loop {
+            if self.eat(&Token::DoubleSlash) {
+                steps.push(Step::any_descendant_or_self());
+            } else if !self.eat(&Token::Slash) {
+                return Ok(steps);
+            }
+            steps.push(self.parse_step()?);
+        }
+    }
+
+    fn parse_step(&mut self) -> Result<Step, XPathError> {
+        if self.eat(&Token::Dot) {
+            return Ok(Step { axis: Axis::SelfNode, test: NodeTest::Node, predicates: vec![] });
+        }
+        if self.eat(&Token::DoubleDot) {
+            return Ok(Step { axis: Axis::Parent, test: NodeTest::Node, predicates: vec![] });
+        }
+        let axis = if self.eat(&Token::At) {
+            Axis::Attribute
+        } else if let (Some(Token::Name(name)), Some(Token::DoubleColon)) = (self.peek(), self.peek_second()) {
+            let axis = Axis::from_name(name).ok_or_else(|| self.error("unsupported axis"))?;
+            self.index += 2;
+            axis
+        } else {
+            Axis::Child
+        };
+        let test = match self.next() {
+            Some(Token::Star) => NodeTest::Any,
+            Some(Token::Name(name)) if is_node_type(&name) && self.eat(&Token::LeftParen) => {
+                let test = match (name.as_str(), self.peek()) {
+                    ("processing-instruction", Some(Token::Literal(target))) => {
+                        let target = target.clone();
+                        self.index += 1;
+                        NodeTest::ProcessingInstruction(Some(target))
+                    }
+                    ("processing-instruction", _) => NodeTest::ProcessingInstruction(None),
+                    ("text", _) => NodeTest::Text,
+                    ("comment", _) => NodeTest::Comment,
+                    _ => NodeTest::Node,
+                };
+                self.expect(Token::RightParen, "expected )")?;
+                test
+            }
+            Some(Token::Name(name)) => match name.strip_suffix(":*") {
+                Some(prefix) => NodeTest::Prefix(prefix.to_string()),
+                None => NodeTest::Name(name),
+            },
+            _ => {
+                self.index -= 1;
+                return Err(self.error("expected a node test"));
+            }
+        };
+        Ok(Step { axis, test, predicates: self.parse_predicates()? })
+    }
+}
+
+fn is_node_type(name: &str) -> bool {
+    matches!(name, "node" | "text" | "comment" | "processing-instruction")
+}
+
+/// A compiled expression.
+///
+/// ```
+/// use jaxp_rust::document::Document;
+/// use jaxp_rust::xpath::XPath;
+///
+/// let doc = Document::parse("<list><item n='1'>a</item><item n='2'>b</item></list>").unwrap();
+/// let xpath = XPath::compile("/list/item[@n = 2]").unwrap();
+/// assert_eq!("b", xpath.evaluate(doc.root()).unwrap().string());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct XPath {
+    expr: Expr,
+}
+
+impl XPath {
+    pub fn compile(expression: &str) -> Result<XPath, XPathError> {
+        let mut parser = Parser { tokens: tokenize(expression)?, index: 0, end: expression.len() };
+        let expr = parser.parse_expr()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("unexpected token"));
+        }
+        Ok(XPath { expr })
+    }
+
+    /// Evaluate the expression with a node as the context node.
+    pub fn evaluate<'a, 'input: 'a>(&self, node: Node<'a, 'input>) -> Result<Value<'a, 'input>, XPathError> {
+        self.evaluate_item(Item::Node(node), 1, 1)
+    }
+
+    /// The nodes that the expression selects in document order, an error if it is not a node-set.
+    pub fn select<'a, 'input: 'a>(&self, node: Node<'a, 'input>) -> Result<Vec<Item<'a, 'input>>, XPathError> {
+        self.evaluate(node)?.into_nodes()
+    }
+
+    /// Evaluate the expression with an item at a position of the current node list as the context.
+    pub(crate) fn evaluate_item<'a, 'input: 'a>(&self, item: Item<'a, 'input>, position: usize, size: usize) -> Result<Value<'a, 'input>, XPathError> {
+        self.expr.evaluate(&Context { item, position, size })
+    }
+
+    pub(crate) fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// The alternatives of a union, each on its own.
+    pub(crate) fn alternatives(self) -> Vec<XPath> {
+        match self.expr {
+            Expr::Binary(Operator::Union, left, right) => {
+                let mut alternatives = XPath { expr: *left }.alternatives();
+                alternatives.extend(XPath { expr: *right }.alternatives());
+                alternatives
+            }
+            expr => vec![XPath { expr }],
+        }
+    }
+
+    /// Whether the expression is a [pattern](https://www.w3.org/TR/xslt-10/#patterns) without a union:
+    /// a location path with steps on the child and attribute axes, separated by `/` or `//`.
+    pub(crate) fn is_pattern(&self) -> bool {
+        match &self.expr {
+            Expr::Path(PathStart::Root, steps) => steps.iter().all(Step::is_pattern_step),
+            Expr::Path(PathStart::Context, steps) => !steps.is_empty() && steps.iter().all(Step::is_pattern_step),
+            _ => false,
+        }
+    }
+
+    /// The [default priority](https://www.w3.org/TR/xslt-10/#conflict) of a pattern.
+    pub(crate) fn default_priority(&self) -> f64 {
+        match &self.expr {
+            Expr::Path(PathStart::Context, steps) if steps.len() == 1 && steps[0].predicates.is_empty() => match &steps[0].test {
+                NodeTest::Name(_) | NodeTest::ProcessingInstruction(Some(_)) => 0.0,
+                NodeTest::Prefix(_) => -0.25,
+                _ => -0.5,
+            },
+            _ => 0.5,
+        }
+    }
+
+    /// Whether a pattern matches an item. The steps are matched from the last to the first,
+    /// each one by selecting it from the parent of the item that the step after it matched.
+    pub(crate) fn matches(&self, item: Item) -> Result<bool, XPathError> {
+        match &self.expr {
+            Expr::Path(start, steps) => matches_steps(*start == PathStart::Root, steps, item),
+            _ => Ok(false),
+        }
+    }
+}
+
+impl Step {
+    fn is_pattern_step(&self) -> bool {
+        match self.axis {
+            Axis::Child | Axis::Attribute => true,
+            Axis::DescendantOrSelf => *self == Step::any_descendant_or_self(),
+            _ => false,
+        }
+    }
+}
+
+fn matches_steps(is_absolute: bool, steps: &[Step], item: Item) -> Result<bool, XPathError> {
+    let (last, rest) = match steps.split_last() {
+        Some(split) => split,
+        None => return Ok(!is_absolute || item == Item::Node(item.node().document().root())),
+    };
+    if last.axis == Axis::DescendantOrSelf {
+        for ancestor in item.node().ancestors() {
+            if matches_steps(is_absolute, rest, Item::Node(ancestor))? {
+                return Ok(true);
+            }
+        }
+        return Ok(false);
+    }
+    let parent = match item {
+        Item::Node(node) => match node.parent() {
+            Some(parent) => parent,
+            None => return Ok(false),
+        },
+        Item::Attribute(element, _) => element,
+    };
+    if !last.test.matches(item, last.axis) || !last.select(Item::Node(parent))?.contains(&item) {
+        return Ok(false);
+    }
+    matches_steps(is_absolute, rest, Item::Node(parent))
+}
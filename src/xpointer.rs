@@ -0,0 +1,67 @@
+//! Resolution of [XPointer](https://www.w3.org/TR/xptr-framework/) fragment identifiers.
+//!
+//! Shorthand pointers refer to an element by its `xml:id`, of the schemes only
+//! [element()](https://www.w3.org/TR/xptr-element/) is supported. Parts with other schemes are skipped.
+
+use crate::document::{Document, Node};
+
+/// Split a scheme-based pointer into its parts, with the escapes of their data resolved.
+fn parts(pointer: &str) -> Option<Vec<(&str, String)>> {
+    let mut parts = vec![];
+    let mut rest = pointer.trim_start();
+    while !rest.is_empty() {
+        let (scheme, after) = rest.split_once('(')?;
+        let mut data = String::new();
+        // data may contain balanced parentheses, others are escaped by a circumflex
+        let mut depth = 1;
+        let mut chars = after.char_indices();
+        let end = loop {
+            let (i, c) = chars.next()?;
+            match c {
+                '^' => match chars.next()? {
+                    (_, escaped @ ('^' | '(' | ')')) => data.push(escaped),
+                    _ => return None,
+                },
+                '(' => {
+                    depth += 1;
+                    data.push(c);
+                }
+                ')' if depth == 1 => break i,
+                ')' => {
+                    depth -= 1;
+                    data.push(c);
+                }
+                _ => data.push(c),
+            }
+        };
+        parts.push((scheme.trim(), data));
+        rest = after[end + 1..].trim_start();
+    }
+    Some(parts)
+}
+
+/// [element() scheme](https://www.w3.org/TR/xptr-element/#NT-ElementSchemeData):
+/// a shorthand or `/1`, followed by the positions of child elements, e.g. `intro/2/1` or `/1/3`.
+fn resolve_element_scheme<'a, 'input>(doc: &'a Document<'input>, data: &str) -> Option<Node<'a, 'input>> {
+    let mut steps = data.split('/');
+    let mut node = match steps.next()? {
+        "" => doc.root(),
+        id => doc.element_by_id(id)?,
+    };
+    for step in steps {
+        let position: usize = step.parse().ok()?;
+        node = node.nth_element_child(position.checked_sub(1)?)?;
+    }
+    (!node.is_root()).then_some(node)
+}
+
+pub(crate) fn resolve<'a, 'input>(doc: &'a Document<'input>, pointer: &str) -> Option<Node<'a, 'input>> {
+    if !pointer.contains('(') {
+        return doc.element_by_id(pointer.trim());
+    }
+    // the first part that identifies a node wins
+    parts(pointer)?.into_iter().find_map(|(scheme, data)| match scheme {
+        "element" => resolve_element_scheme(doc, &data),
+        _ => None,
+    })
+}
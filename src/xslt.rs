@@ -0,0 +1,632 @@
+//! A subset of [XSLT 1.0](https://www.w3.org/TR/xslt-10/), with expressions and patterns in the [XPath subset](crate::xpath).
+//!
+//! Supported are template rules with modes and priorities, named templates, literal result elements with
+//! attribute value templates and the instructions `apply-templates`, `call-template`, `for-each`, `value-of`,
+//! `if`, `choose`, `text`, `element`, `attribute`, `comment`, `copy` and `copy-of`.
+//! Variables, parameters, sorting, keys and imports are not, a stylesheet that uses them is rejected.
+//!
+//! The result is serialized as XML without an XML declaration, `xsl:output` and the whitespace stripping of the
+//! input by `xsl:strip-space` are ignored.
+//!
+//! ```
+//! use jaxp_rust::document::Document;
+//! use jaxp_rust::xslt::Stylesheet;
+//!
+//! let stylesheet = Stylesheet::parse(r#"<xsl:stylesheet version="1.0" xmlns:xsl="http://www.w3.org/1999/XSL/Transform">
+//!     <xsl:template match="/list"><ul><xsl:apply-templates/></ul></xsl:template>
+//!     <xsl:template match="item"><li id="{@n}"><xsl:value-of select="."/></li></xsl:template>
+//! </xsl:stylesheet>"#).unwrap();
+//! let doc = Document::parse("<list><item n='1'>a</item><item n='2'>b</item></list>").unwrap();
+//! assert_eq!(r#"<ul><li id="1">a</li><li id="2">b</li></ul>"#, stylesheet.transform(&doc).unwrap());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::document::{Document, Node, NodeType};
+use crate::error::XmlError;
+use crate::namespace::declared_prefix;
+use crate::refs::decode_entities;
+use crate::writer::XmlWriter;
+use crate::xmlchar::XmlByte;
+use crate::xpath::{Item, Value, XPath, XPathError};
+
+pub const XSLT_NAMESPACE: &str = "http://www.w3.org/1999/XSL/Transform";
+
+/// The maximum nesting of template instantiations, deeper nesting is likely an endless recursion.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug)]
+pub enum XsltError {
+    /// The stylesheet is not well-formed
+    Parse(XmlError),
+    /// An expression or pattern is malformed, or cannot be evaluated
+    XPath(XPathError),
+    /// The stylesheet breaks the rules of XSLT, e.g. an instruction lacks a required attribute
+    InvalidStylesheet(String),
+    /// An instruction or top-level element that is not supported, by its qualified name
+    Unsupported(String),
+    /// The result cannot be written, e.g. an attribute is added to an element after its content
+    Output(String),
+    /// Templates are instantiated inside of each other too deeply, likely in an endless recursion
+    RecursionLimitExceeded,
+}
+
+impl Display for XsltError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            XsltError::Parse(e) => Display::fmt(e, f),
+            XsltError::XPath(e) => Display::fmt(e, f),
+            XsltError::InvalidStylesheet(reason) => write!(f, "invalid stylesheet: {}", reason),
+            XsltError::Unsupported(name) => write!(f, "{} is not supported", name),
+            XsltError::Output(reason) => write!(f, "cannot write the result: {}", reason),
+            XsltError::RecursionLimitExceeded => write!(f, "templates are nested deeper than {}", MAX_DEPTH),
+        }
+    }
+}
+
+impl std::error::Error for XsltError {}
+
+impl From<XmlError> for XsltError {
+    fn from(e: XmlError) -> Self {
+        XsltError::Parse(e)
+    }
+}
+
+impl From<XPathError> for XsltError {
+    fn from(e: XPathError) -> Self {
+        XsltError::XPath(e)
+    }
+}
+
+/// An [attribute value template](https://www.w3.org/TR/xslt-10/#attribute-value-templates), e.g. `item-{@id}`.
+#[derive(Clone, Debug)]
+enum AvtPart {
+    Literal(String),
+    Expr(XPath),
+}
+
+fn compile_avt(value: &str) -> Result<Vec<AvtPart>, XsltError> {
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut rest = value;
+    while let Some(i) = rest.find(['{', '}']) {
+        literal.push_str(&rest[..i]);
+        let (brace, after) = (&rest[i..i + 1], &rest[i + 1..]);
+        // doubled braces stand for themselves
+        if after.starts_with(brace) {
+            literal.push_str(brace);
+            rest = &after[1..];
+            continue;
+        }
+        if brace == "}" {
+            return Err(XsltError::InvalidStylesheet(format!("unmatched }} in {}", value)));
+        }
+        let end = after.find('}').ok_or_else(|| XsltError::InvalidStylesheet(format!("unmatched {{ in {}", value)))?;
+        if !literal.is_empty() {
+            parts.push(AvtPart::Literal(std::mem::take(&mut literal)));
+        }
+        parts.push(AvtPart::Expr(XPath::compile(&after[..end])?));
+        rest = &after[end + 1..];
+    }
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        parts.push(AvtPart::Literal(literal));
+    }
+    Ok(parts)
+}
+
+#[derive(Clone, Debug)]
+enum Instruction {
+    Text(String),
+    LiteralElement { name: String, attributes: Vec<(String, Vec<AvtPart>)>, body: Vec<Instruction> },
+    Element { name: Vec<AvtPart>, body: Vec<Instruction> },
+    Attribute { name: Vec<AvtPart>, body: Vec<Instruction> },
+    Comment(Vec<Instruction>),
+    ValueOf(XPath),
+    ApplyTemplates { opt_select: Option<XPath>, opt_mode: Option<String> },
+    CallTemplate(String),
+    ForEach { select: XPath, body: Vec<Instruction> },
+    If { test: XPath, body: Vec<Instruction> },
+    Choose { branches: Vec<(XPath, Vec<Instruction>)>, otherwise: Vec<Instruction> },
+    Copy(Vec<Instruction>),
+    CopyOf(XPath),
+}
+
+#[derive(Clone, Debug)]
+struct TemplateRule {
+    /// A single alternative of the pattern of the template
+    pattern: XPath,
+    opt_mode: Option<String>,
+    priority: f64,
+    template: usize,
+}
+
+/// A compiled stylesheet, which can transform any number of documents.
+#[derive(Clone, Debug)]
+pub struct Stylesheet {
+    templates: Vec<Vec<Instruction>>,
+    rules: Vec<TemplateRule>,
+    named_templates: HashMap<String, usize>,
+}
+
+fn is_xsl(node: Node) -> bool {
+    node.namespace_uri() == Some(XSLT_NAMESPACE)
+}
+
+/// The value of an attribute with references resolved.
+fn attribute(element: Node, name: &str) -> Option<String> {
+    element.attribute(name).map(|value| decode_entities(value).into_owned())
+}
+
+fn required_attribute(element: Node, name: &str) -> Result<String, XsltError> {
+    attribute(element, name).ok_or_else(|| XsltError::InvalidStylesheet(format!("{} needs a {} attribute", element.tag_name(), name)))
+}
+
+fn is_space(text: &str) -> bool {
+    text.bytes().all(|b| b.is_xml_whitespace())
+}
+
+fn text_of(node: Node) -> String {
+    Item::Node(node).string_value()
+}
+
+impl Stylesheet {
+    pub fn parse(xsl: &str) -> Result<Stylesheet, XsltError> {
+        let doc = Document::parse(xsl)?;
+        let mut stylesheet = Stylesheet { templates: vec![], rules: vec![], named_templates: HashMap::new() };
+        let root = doc.root_element();
+        if !is_xsl(root) {
+            // a literal result element as the stylesheet, see [2.3](https://www.w3.org/TR/xslt-10/#result-element-stylesheet)
+            stylesheet.templates.push(vec![compile_instruction(root, false)?]);
+            stylesheet.rules.push(TemplateRule { pattern: XPath::compile("/")?, opt_mode: None, priority: 0.5, template: 0 });
+            return Ok(stylesheet);
+        }
+        if !matches!(root.local_name(), "stylesheet" | "transform") {
+            return Err(XsltError::InvalidStylesheet(format!("{} is not a stylesheet", root.tag_name())));
+        }
+        // top-level elements of other namespaces are ignored
+        for child in root.child_elements().filter(|c| is_xsl(*c)) {
+            match child.local_name() {
+                "template" => stylesheet.add_template(child)?,
+                "output" | "strip-space" | "preserve-space" => (),
+                _ => return Err(XsltError::Unsupported(child.tag_name().to_string())),
+            }
+        }
+        Ok(stylesheet)
+    }
+
+    fn add_template(&mut self, element: Node) -> Result<(), XsltError> {
+        let template = self.templates.len();
+        self.templates.push(compile_body(element)?);
+        let opt_name = attribute(element, "name");
+        let opt_pattern = attribute(element, "match");
+        if opt_name.is_none() && opt_pattern.is_none() {
+            return Err(XsltError::InvalidStylesheet("a template needs a match or name attribute".to_string()));
+        }
+        if let Some(name) = opt_name {
+            self.named_templates.insert(name, template);
+        }
+        if let Some(pattern) = opt_pattern {
+            let opt_priority = match attribute(element, "priority") {
+                Some(priority) => Some(priority.trim().parse::<f64>().map_err(|_| XsltError::InvalidStylesheet(format!("invalid priority {}", priority)))?),
+                None => None,
+            };
+            for alternative in XPath::compile(&pattern)?.alternatives() {
+                if !alternative.is_pattern() {
+                    return Err(XsltError::InvalidStylesheet(format!("{} is not a pattern", pattern)));
+                }
+                let priority = opt_priority.unwrap_or_else(|| alternative.default_priority());
+                self.rules.push(TemplateRule { pattern: alternative, opt_mode: attribute(element, "mode"), priority, template });
+            }
+        }
+        Ok(())
+    }
+
+    /// Transform a document, the result is serialized.
+    pub fn transform(&self, doc: &Document) -> Result<String, XsltError> {
+        let mut output = Output::Markup(XmlWriter::new(vec![]));
+        Transformer { stylesheet: self, depth: 0 }.apply_templates(vec![Item::Node(doc.root())], None, &mut output)?;
+        Ok(output.into_string())
+    }
+}
+
+/// The instructions of a template or instruction. Text that consists of spaces only is stripped, as in the stylesheet it is layout.
+fn compile_body(parent: Node) -> Result<Vec<Instruction>, XsltError> {
+    let is_in_literal = !is_xsl(parent);
+    let mut body = vec![];
+    for child in parent.children() {
+        match child.node_type() {
+            NodeType::Element => body.push(compile_instruction(child, is_in_literal)?),
+            NodeType::Text | NodeType::CdataSection => {
+                let text = text_of(child);
+                if !is_space(&text) {
+                    body.push(Instruction::Text(text));
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(body)
+}
+
+/// Reject the content of an instruction that must be empty, e.g. `xsl:sort` in `xsl:apply-templates`.
+fn expect_empty(element: Node) -> Result<(), XsltError> {
+    match element.child_elements().next() {
+        Some(child) if is_xsl(child) => Err(XsltError::Unsupported(child.tag_name().to_string())),
+        Some(child) => Err(XsltError::InvalidStylesheet(format!("{} must not contain {}", element.tag_name(), child.tag_name()))),
+        None => Ok(()),
+    }
+}
+
+fn compile_instruction(element: Node, is_in_literal: bool) -> Result<Instruction, XsltError> {
+    if !is_xsl(element) {
+        return compile_literal_element(element, is_in_literal);
+    }
+    let select = || Ok::<_, XsltError>(XPath::compile(&required_attribute(element, "select")?)?);
+    let test = || Ok::<_, XsltError>(XPath::compile(&required_attribute(element, "test")?)?);
+    let name = || compile_avt(&required_attribute(element, "name")?);
+    Ok(match element.local_name() {
+        "apply-templates" => {
+            expect_empty(element)?;
+            let opt_select = attribute(element, "select").map(|s| XPath::compile(&s)).transpose()?;
+            Instruction::ApplyTemplates { opt_select, opt_mode: attribute(element, "mode") }
+        }
+        "call-template" => {
+            expect_empty(element)?;
+            Instruction::CallTemplate(required_attribute(element, "name")?)
+        }
+        "for-each" => Instruction::ForEach { select: select()?, body: compile_body(element)? },
+        "value-of" => Instruction::ValueOf(select()?),
+        "copy-of" => Instruction::CopyOf(select()?),
+        "if" => Instruction::If { test: test()?, body: compile_body(element)? },
+        "choose" => {
+            let mut branches = vec![];
+            let mut otherwise = vec![];
+            for child in element.child_elements() {
+                match child.local_name() {
+                    "when" if is_xsl(child) => branches.push((XPath::compile(&required_attribute(child, "test")?)?, compile_body(child)?)),
+                    "otherwise" if is_xsl(child) => otherwise = compile_body(child)?,
+                    _ => return Err(XsltError::InvalidStylesheet(format!("xsl:choose must not contain {}", child.tag_name()))),
+                }
+            }
+            Instruction::Choose { branches, otherwise }
+        }
+        "text" => Instruction::Text(element.children().filter(|c| matches!(c.node_type(), NodeType::Text | NodeType::CdataSection)).map(text_of).collect()),
+        "element" => Instruction::Element { name: name()?, body: compile_body(element)? },
+        "attribute" => Instruction::Attribute { name: name()?, body: compile_body(element)? },
+        "comment" => Instruction::Comment(compile_body(element)?),
+        "copy" => Instruction::Copy(compile_body(element)?),
+        _ => return Err(XsltError::Unsupported(element.tag_name().to_string())),
+    })
+}
+
+/// A [literal result element](https://www.w3.org/TR/xslt-10/#literal-result-element). The outermost one of a template
+/// declares the namespaces in scope in the stylesheet, except the XSLT namespace.
+fn compile_literal_element(element: Node, is_in_literal: bool) -> Result<Instruction, XsltError> {
+    let mut attributes = vec![];
+    if !is_in_literal {
+        for namespace in element.namespaces_in_scope() {
+            let uri = decode_entities(namespace.uri());
+            let name = match namespace.prefix() {
+                Some("xml") => continue,
+                Some(prefix) => format!("xmlns:{}", prefix),
+                None => "xmlns".to_string(),
+            };
+            if uri != XSLT_NAMESPACE {
+                attributes.push((name, vec![AvtPart::Literal(uri.into_owned())]));
+            }
+        }
+    }
+    for a in element.attributes() {
+        let is_declaration = declared_prefix(a.name()).is_some();
+        // the declarations of the outermost element are copied with the others in scope
+        if (is_declaration && (!is_in_literal || decode_entities(a.value()) == XSLT_NAMESPACE))
+            || element.attribute_namespace_uri(a) == Some(XSLT_NAMESPACE)
+        {
+            continue;
+        }
+        let value = decode_entities(a.value());
+        let value = if is_declaration { vec![AvtPart::Literal(value.into_owned())] } else { compile_avt(&value)? };
+        attributes.push((a.name().to_string(), value));
+    }
+    Ok(Instruction::LiteralElement { name: element.tag_name().to_string(), attributes, body: compile_body(element)? })
+}
+
+/// Where the result goes: serialized markup, or only the text for the value of an attribute or comment.
+enum Output {
+    Markup(XmlWriter<Vec<u8>>),
+    Text(String),
+}
+
+impl Output {
+    fn start_element(&mut self, name: &str) -> Result<(), XsltError> {
+        match self {
+            Output::Markup(writer) => writer.start_element(name).map_err(|e| XsltError::Output(e.to_string())),
+            Output::Text(_) => Ok(()),
+        }
+    }
+
+    fn attribute(&mut self, name: &str, value: &str) -> Result<(), XsltError> {
+        match self {
+            Output::Markup(writer) => writer.attribute(name, value).map_err(|e| XsltError::Output(e.to_string())),
+            Output::Text(_) => Ok(()),
+        }
+    }
+
+    fn end_element(&mut self) -> Result<(), XsltError> {
+        match self {
+            Output::Markup(writer) => writer.end_element().map_err(|e| XsltError::Output(e.to_string())),
+            Output::Text(_) => Ok(()),
+        }
+    }
+
+    fn text(&mut self, text: &str) -> Result<(), XsltError> {
+        // empty text would close a start tag that attributes may still be added to
+        if text.is_empty() {
+            return Ok(());
+        }
+        match self {
+            Output::Markup(writer) => writer.text(text).map_err(|e| XsltError::Output(e.to_string())),
+            Output::Text(s) => {
+                s.push_str(text);
+                Ok(())
+            }
+        }
+    }
+
+    fn comment(&mut self, text: &str) -> Result<(), XsltError> {
+        match self {
+            Output::Markup(writer) => writer.comment(&comment_text(text)).map_err(|e| XsltError::Output(e.to_string())),
+            Output::Text(_) => Ok(()),
+        }
+    }
+
+    fn processing_instruction(&mut self, target: &str, opt_value: Option<&str>) -> Result<(), XsltError> {
+        match self {
+            Output::Markup(writer) => writer.processing_instruction(target, opt_value).map_err(|e| XsltError::Output(e.to_string())),
+            Output::Text(_) => Ok(()),
+        }
+    }
+
+    fn into_string(self) -> String {
+        match self {
+            Output::Markup(writer) => String::from_utf8(writer.into_inner()).expect("only strings are written"),
+            Output::Text(s) => s,
+        }
+    }
+}
+
+/// A comment must neither contain `--` nor end with `-`, a space is inserted after such hyphens.
+fn comment_text(text: &str) -> String {
+    let mut comment = text.replace("--", "- -");
+    if comment.ends_with('-') {
+        comment.push(' ');
+    }
+    comment
+}
+
+/// The current node and its position in the current node list, counting from 1.
+#[derive(Clone, Copy)]
+struct Current<'a, 'input: 'a> {
+    item: Item<'a, 'input>,
+    position: usize,
+    size: usize,
+}
+
+struct Transformer<'s> {
+    stylesheet: &'s Stylesheet,
+    depth: usize,
+}
+
+impl Transformer<'_> {
+    fn evaluate<'a, 'input: 'a>(&self, xpath: &XPath, current: Current<'a, 'input>) -> Result<Value<'a, 'input>, XsltError> {
+        Ok(xpath.evaluate_item(current.item, current.position, current.size)?)
+    }
+
+    fn evaluate_avt(&self, avt: &[AvtPart], current: Current) -> Result<String, XsltError> {
+        let mut value = String::new();
+        for part in avt {
+            match part {
+                AvtPart::Literal(literal) => value.push_str(literal),
+                AvtPart::Expr(xpath) => value.push_str(&self.evaluate(xpath, current)?.string()),
+            }
+        }
+        Ok(value)
+    }
+
+    /// Instantiate a body for its text only.
+    fn text_of(&mut self, body: &[Instruction], current: Current) -> Result<String, XsltError> {
+        let mut output = Output::Text(String::new());
+        self.execute(body, current, &mut output)?;
+        Ok(output.into_string())
+    }
+
+    fn apply_templates(&mut self, items: Vec<Item>, opt_mode: Option<&str>, output: &mut Output) -> Result<(), XsltError> {
+        let size = items.len();
+        for (index, item) in items.into_iter().enumerate() {
+            let current = Current { item, position: index + 1, size };
+            self.apply_template(current, opt_mode, output)?;
+        }
+        Ok(())
+    }
+
+    /// Instantiate the template rule that matches the current node best, or the built-in one.
+    /// Of rules with the same priority the last one wins.
+    fn apply_template(&mut self, current: Current, opt_mode: Option<&str>, output: &mut Output) -> Result<(), XsltError> {
+        let mut opt_best: Option<&TemplateRule> = None;
+        for rule in self.stylesheet.rules.iter().filter(|r| r.opt_mode.as_deref() == opt_mode) {
+            if opt_best.is_none_or(|best| rule.priority >= best.priority) && rule.pattern.matches(current.item)? {
+                opt_best = Some(rule);
+            }
+        }
+        if let Some(rule) = opt_best {
+            return self.instantiate(rule.template, current, output);
+        }
+        // the built-in rules, see [5.8](https://www.w3.org/TR/xslt-10/#built-in-rule)
+        match current.item {
+            Item::Attribute(..) => output.text(&current.item.string_value()),
+            Item::Node(node) => match node.node_type() {
+                NodeType::Root | NodeType::Element => self.apply_templates(node.children().map(Item::Node).collect(), opt_mode, output),
+                NodeType::Text | NodeType::CdataSection => output.text(&current.item.string_value()),
+                NodeType::Comment | NodeType::ProcessingInstruction => Ok(()),
+            },
+        }
+    }
+
+    fn instantiate(&mut self, template: usize, current: Current, output: &mut Output) -> Result<(), XsltError> {
+        if self.depth == MAX_DEPTH {
+            return Err(XsltError::RecursionLimitExceeded);
+        }
+        self.depth += 1;
+        let result = self.execute(&self.stylesheet.templates[template], current, output);
+        self.depth -= 1;
+        result
+    }
+
+    fn execute(&mut self, body: &[Instruction], current: Current, output: &mut Output) -> Result<(), XsltError> {
+        for instruction in body {
+            self.execute_instruction(instruction, current, output)?;
+        }
+        Ok(())
+    }
+
+    // the instructions are executed by methods of their own, which keeps the stack frames of recursive templates small
+    fn execute_instruction(&mut self, instruction: &Instruction, current: Current, output: &mut Output) -> Result<(), XsltError> {
+        match instruction {
+            Instruction::Text(text) => output.text(text),
+            Instruction::LiteralElement { name, attributes, body } => self.literal_element(name, attributes, body, current, output),
+            Instruction::Element { name, body } => self.element(name, body, current, output),
+            Instruction::Attribute { name, body } => self.attribute(name, body, current, output),
+            Instruction::Comment(body) => self.comment(body, current, output),
+            Instruction::ValueOf(select) => self.value_of(select, current, output),
+            Instruction::ApplyTemplates { opt_select, opt_mode } => self.apply_templates_instruction(opt_select.as_ref(), opt_mode.as_deref(), current, output),
+            Instruction::CallTemplate(name) => self.call_template(name, current, output),
+            Instruction::ForEach { select, body } => self.for_each(select, body, current, output),
+            Instruction::If { test, body } => self.if_instruction(test, body, current, output),
+            Instruction::Choose { branches, otherwise } => self.choose(branches, otherwise, current, output),
+            Instruction::Copy(body) => self.copy(body, current, output),
+            Instruction::CopyOf(select) => self.copy_of(select, current, output),
+        }
+    }
+
+    fn literal_element(&mut self, name: &str, attributes: &[(String, Vec<AvtPart>)], body: &[Instruction], current: Current, output: &mut Output) -> Result<(), XsltError> {
+        output.start_element(name)?;
+        for (name, value) in attributes {
+            output.attribute(name, &self.evaluate_avt(value, current)?)?;
+        }
+        self.execute(body, current, output)?;
+        output.end_element()
+    }
+
+    fn element(&mut self, name: &[AvtPart], body: &[Instruction], current: Current, output: &mut Output) -> Result<(), XsltError> {
+        output.start_element(&self.evaluate_avt(name, current)?)?;
+        self.execute(body, current, output)?;
+        output.end_element()
+    }
+
+    fn attribute(&mut self, name: &[AvtPart], body: &[Instruction], current: Current, output: &mut Output) -> Result<(), XsltError> {
+        let value = self.text_of(body, current)?;
+        output.attribute(&self.evaluate_avt(name, current)?, &value)
+    }
+
+    fn comment(&mut self, body: &[Instruction], current: Current, output: &mut Output) -> Result<(), XsltError> {
+        let text = self.text_of(body, current)?;
+        output.comment(&text)
+    }
+
+    fn value_of(&mut self, select: &XPath, current: Current, output: &mut Output) -> Result<(), XsltError> {
+        output.text(&self.evaluate(select, current)?.string())
+    }
+
+    fn apply_templates_instruction(&mut self, opt_select: Option<&XPath>, opt_mode: Option<&str>, current: Current, output: &mut Output) -> Result<(), XsltError> {
+        let items = match opt_select {
+            Some(select) => self.evaluate(select, current)?.into_nodes()?,
+            // attributes have no children
+            None => match current.item {
+                Item::Node(node) => node.children().map(Item::Node).collect(),
+                Item::Attribute(..) => vec![],
+            },
+        };
+        self.apply_templates(items, opt_mode, output)
+    }
+
+    fn call_template(&mut self, name: &str, current: Current, output: &mut Output) -> Result<(), XsltError> {
+        let template = *self.stylesheet.named_templates.get(name)
+            .ok_or_else(|| XsltError::InvalidStylesheet(format!("no template is named {}", name)))?;
+        self.instantiate(template, current, output)
+    }
+
+    fn for_each(&mut self, select: &XPath, body: &[Instruction], current: Current, output: &mut Output) -> Result<(), XsltError> {
+        let items = self.evaluate(select, current)?.into_nodes()?;
+        let size = items.len();
+        for (index, item) in items.into_iter().enumerate() {
+            self.execute(body, Current { item, position: index + 1, size }, output)?;
+        }
+        Ok(())
+    }
+
+    fn if_instruction(&mut self, test: &XPath, body: &[Instruction], current: Current, output: &mut Output) -> Result<(), XsltError> {
+        if self.evaluate(test, current)?.boolean() {
+            self.execute(body, current, output)?;
+        }
+        Ok(())
+    }
+
+    fn choose(&mut self, branches: &[(XPath, Vec<Instruction>)], otherwise: &[Instruction], current: Current, output: &mut Output) -> Result<(), XsltError> {
+        for (test, body) in branches {
+            if self.evaluate(test, current)?.boolean() {
+                return self.execute(body, current, output);
+            }
+        }
+        self.execute(otherwise, current, output)
+    }
+
+    fn copy(&mut self, body: &[Instruction], current: Current, output: &mut Output) -> Result<(), XsltError> {
+        match current.item {
+            Item::Node(node) if node.is_element() => {
+                output.start_element(node.tag_name())?;
+                self.execute(body, current, output)?;
+                output.end_element()
+            }
+            Item::Node(node) if node.is_root() => self.execute(body, current, output),
+            item => copy(item, output),
+        }
+    }
+
+    fn copy_of(&mut self, select: &XPath, current: Current, output: &mut Output) -> Result<(), XsltError> {
+        match self.evaluate(select, current)? {
+            Value::Nodes(items) => items.into_iter().try_for_each(|item| copy(item, output)),
+            value => output.text(&value.string()),
+        }
+    }
+}
+
+/// Copy a node with its attributes and descendants.
+fn copy(item: Item, output: &mut Output) -> Result<(), XsltError> {
+    let node = match item {
+        Item::Attribute(..) => return output.attribute(item.name(), &item.string_value()),
+        Item::Node(node) => node,
+    };
+    match node.node_type() {
+        NodeType::Root => {
+            for child in node.children() {
+                copy(Item::Node(child), output)?;
+            }
+            Ok(())
+        }
+        NodeType::Element => {
+            output.start_element(node.tag_name())?;
+            for a in node.attributes() {
+                output.attribute(a.name(), &decode_entities(a.value()))?;
+            }
+            for child in node.children() {
+                copy(Item::Node(child), output)?;
+            }
+            output.end_element()
+        }
+        NodeType::Text | NodeType::CdataSection => output.text(&item.string_value()),
+        NodeType::Comment => output.comment(node.text().unwrap_or("")),
+        NodeType::ProcessingInstruction => output.processing_instruction(node.tag_name(), node.text()),
+    }
+}
@@ -0,0 +1,148 @@
+// Generated by jaxp_rust::codegen, do not edit.
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Catalog {
+    pub version: String,
+    pub book: Vec<Book>,
+}
+
+impl Catalog {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Catalog {
+            version: jaxp_rust::binding::attribute(node, "version")?,
+            book: jaxp_rust::binding::children(node, "book", Book::from_node)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.attribute("version", &jaxp_rust::binding::XmlValue::to_xml_value(&self.version))?;
+        for value in &self.book {
+            value.write_element("book", writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn from_document(doc: &jaxp_rust::document::Document) -> Result<Self, jaxp_rust::binding::BindError> {
+        Self::from_node(jaxp_rust::binding::root_element(doc.root_element(), "catalog")?)
+    }
+
+    pub fn to_document(&self) -> jaxp_rust::document::OwnedDocument {
+        let mut writer = jaxp_rust::writer::XmlWriter::new(Vec::new());
+        // writing to a Vec cannot fail
+        writer.start_element("catalog").unwrap();
+        writer.attribute("xmlns", "urn:catalog").unwrap();
+        self.write_content(&mut writer).unwrap();
+        writer.end_element().unwrap();
+        let xml = String::from_utf8(writer.into_inner()).unwrap();
+        jaxp_rust::document::OwnedDocument::parse(xml).expect("the written document is well-formed")
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Book {
+    pub id: String,
+    pub lang: Option<String>,
+    pub title: String,
+    pub author: Vec<String>,
+    pub price: Price,
+    pub note: Option<Note>,
+}
+
+impl Book {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Book {
+            id: jaxp_rust::binding::attribute(node, "id")?,
+            lang: jaxp_rust::binding::opt_attribute(node, "lang")?,
+            title: jaxp_rust::binding::child(node, "title", jaxp_rust::binding::value)?,
+            author: jaxp_rust::binding::children(node, "author", jaxp_rust::binding::value)?,
+            price: jaxp_rust::binding::child(node, "price", Price::from_node)?,
+            note: jaxp_rust::binding::opt_child(node, "note", Note::from_node)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.attribute("id", &jaxp_rust::binding::XmlValue::to_xml_value(&self.id))?;
+        if let Some(value) = &self.lang {
+            writer.attribute("lang", &jaxp_rust::binding::XmlValue::to_xml_value(value))?;
+        }
+        jaxp_rust::binding::write_value(writer, "title", &self.title)?;
+        for value in &self.author {
+            jaxp_rust::binding::write_value(writer, "author", value)?;
+        }
+        self.price.write_element("price", writer)?;
+        if let Some(value) = &self.note {
+            value.write_element("note", writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Price {
+    pub currency: String,
+    pub text: String,
+}
+
+impl Price {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Price {
+            currency: jaxp_rust::binding::attribute(node, "currency")?,
+            text: jaxp_rust::binding::text(node)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.attribute("currency", &jaxp_rust::binding::XmlValue::to_xml_value(&self.currency))?;
+        writer.text(&jaxp_rust::binding::XmlValue::to_xml_value(&self.text))?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Note {
+    pub em: String,
+    pub text: String,
+}
+
+impl Note {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Note {
+            em: jaxp_rust::binding::child(node, "em", jaxp_rust::binding::value)?,
+            text: jaxp_rust::binding::text(node)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        jaxp_rust::binding::write_value(writer, "em", &self.em)?;
+        writer.text(&jaxp_rust::binding::XmlValue::to_xml_value(&self.text))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,364 @@
+// Generated by jaxp_rust::codegen, do not edit.
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Model {
+    pub model_version: Option<String>,
+    pub parent: Option<Parent>,
+    pub group_id: Option<String>,
+    pub artifact_id: String,
+    pub version: Option<String>,
+    pub packaging: Option<String>,
+    pub modules: Option<Modules>,
+    pub properties: Option<Properties>,
+    pub dependencies: Option<Dependencies>,
+    pub child_project_url_inherit_append_path: Option<String>,
+}
+
+impl Model {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Model {
+            model_version: jaxp_rust::binding::opt_child(node, "modelVersion", jaxp_rust::binding::value)?,
+            parent: jaxp_rust::binding::opt_child(node, "parent", Parent::from_node)?,
+            group_id: jaxp_rust::binding::opt_child(node, "groupId", jaxp_rust::binding::value)?,
+            artifact_id: jaxp_rust::binding::child(node, "artifactId", jaxp_rust::binding::value)?,
+            version: jaxp_rust::binding::opt_child(node, "version", jaxp_rust::binding::value)?,
+            packaging: jaxp_rust::binding::opt_child(node, "packaging", jaxp_rust::binding::value)?,
+            modules: jaxp_rust::binding::opt_child(node, "modules", Modules::from_node)?,
+            properties: jaxp_rust::binding::opt_child(node, "properties", Properties::from_node)?,
+            dependencies: jaxp_rust::binding::opt_child(node, "dependencies", Dependencies::from_node)?,
+            child_project_url_inherit_append_path: jaxp_rust::binding::opt_attribute(node, "child.project.url.inherit.append.path")?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        if let Some(value) = &self.child_project_url_inherit_append_path {
+            writer.attribute("child.project.url.inherit.append.path", &jaxp_rust::binding::XmlValue::to_xml_value(value))?;
+        }
+        if let Some(value) = &self.model_version {
+            jaxp_rust::binding::write_value(writer, "modelVersion", value)?;
+        }
+        if let Some(value) = &self.parent {
+            value.write_element("parent", writer)?;
+        }
+        if let Some(value) = &self.group_id {
+            jaxp_rust::binding::write_value(writer, "groupId", value)?;
+        }
+        jaxp_rust::binding::write_value(writer, "artifactId", &self.artifact_id)?;
+        if let Some(value) = &self.version {
+            jaxp_rust::binding::write_value(writer, "version", value)?;
+        }
+        if let Some(value) = &self.packaging {
+            jaxp_rust::binding::write_value(writer, "packaging", value)?;
+        }
+        if let Some(value) = &self.modules {
+            value.write_element("modules", writer)?;
+        }
+        if let Some(value) = &self.properties {
+            value.write_element("properties", writer)?;
+        }
+        if let Some(value) = &self.dependencies {
+            value.write_element("dependencies", writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn from_document(doc: &jaxp_rust::document::Document) -> Result<Self, jaxp_rust::binding::BindError> {
+        Self::from_node(jaxp_rust::binding::root_element(doc.root_element(), "project")?)
+    }
+
+    pub fn to_document(&self) -> jaxp_rust::document::OwnedDocument {
+        let mut writer = jaxp_rust::writer::XmlWriter::new(Vec::new());
+        // writing to a Vec cannot fail
+        writer.start_element("project").unwrap();
+        writer.attribute("xmlns", "http://maven.apache.org/POM/4.0.0").unwrap();
+        self.write_content(&mut writer).unwrap();
+        writer.end_element().unwrap();
+        let xml = String::from_utf8(writer.into_inner()).unwrap();
+        jaxp_rust::document::OwnedDocument::parse(xml).expect("the written document is well-formed")
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Parent {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: String,
+    pub relative_path: Option<String>,
+}
+
+impl Parent {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Parent {
+            group_id: jaxp_rust::binding::child(node, "groupId", jaxp_rust::binding::value)?,
+            artifact_id: jaxp_rust::binding::child(node, "artifactId", jaxp_rust::binding::value)?,
+            version: jaxp_rust::binding::child(node, "version", jaxp_rust::binding::value)?,
+            relative_path: jaxp_rust::binding::opt_child(node, "relativePath", jaxp_rust::binding::value)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        jaxp_rust::binding::write_value(writer, "groupId", &self.group_id)?;
+        jaxp_rust::binding::write_value(writer, "artifactId", &self.artifact_id)?;
+        jaxp_rust::binding::write_value(writer, "version", &self.version)?;
+        if let Some(value) = &self.relative_path {
+            jaxp_rust::binding::write_value(writer, "relativePath", value)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Modules {
+    pub module: Vec<String>,
+}
+
+impl Modules {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Modules {
+            module: jaxp_rust::binding::children(node, "module", jaxp_rust::binding::value)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        for value in &self.module {
+            jaxp_rust::binding::write_value(writer, "module", value)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Properties {
+    pub property: Vec<Property>,
+}
+
+impl Properties {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Properties {
+            property: jaxp_rust::binding::children(node, "property", Property::from_node)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        for value in &self.property {
+            value.write_element("property", writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub value: String,
+}
+
+impl Property {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Property {
+            name: jaxp_rust::binding::attribute(node, "name")?,
+            value: jaxp_rust::binding::text(node)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.attribute("name", &jaxp_rust::binding::XmlValue::to_xml_value(&self.name))?;
+        writer.text(&jaxp_rust::binding::XmlValue::to_xml_value(&self.value))?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dependencies {
+    pub dependency: Vec<Dependency>,
+}
+
+impl Dependencies {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Dependencies {
+            dependency: jaxp_rust::binding::children(node, "dependency", Dependency::from_node)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        for value in &self.dependency {
+            value.write_element("dependency", writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dependency {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: Option<String>,
+    pub scope: Option<String>,
+    pub optional: Option<bool>,
+    pub exclusions: Option<Exclusions>,
+    pub r#type: Option<String>,
+    pub priority: Option<i64>,
+}
+
+impl Dependency {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Dependency {
+            group_id: jaxp_rust::binding::child(node, "groupId", jaxp_rust::binding::value)?,
+            artifact_id: jaxp_rust::binding::child(node, "artifactId", jaxp_rust::binding::value)?,
+            version: jaxp_rust::binding::opt_child(node, "version", jaxp_rust::binding::value)?,
+            scope: jaxp_rust::binding::opt_child(node, "scope", jaxp_rust::binding::value)?,
+            optional: jaxp_rust::binding::opt_child(node, "optional", jaxp_rust::binding::value)?,
+            exclusions: jaxp_rust::binding::opt_child(node, "exclusions", Exclusions::from_node)?,
+            r#type: jaxp_rust::binding::opt_attribute(node, "type")?,
+            priority: jaxp_rust::binding::opt_attribute(node, "priority")?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        if let Some(value) = &self.r#type {
+            writer.attribute("type", &jaxp_rust::binding::XmlValue::to_xml_value(value))?;
+        }
+        if let Some(value) = &self.priority {
+            writer.attribute("priority", &jaxp_rust::binding::XmlValue::to_xml_value(value))?;
+        }
+        jaxp_rust::binding::write_value(writer, "groupId", &self.group_id)?;
+        jaxp_rust::binding::write_value(writer, "artifactId", &self.artifact_id)?;
+        if let Some(value) = &self.version {
+            jaxp_rust::binding::write_value(writer, "version", value)?;
+        }
+        if let Some(value) = &self.scope {
+            jaxp_rust::binding::write_value(writer, "scope", value)?;
+        }
+        if let Some(value) = &self.optional {
+            jaxp_rust::binding::write_value(writer, "optional", value)?;
+        }
+        if let Some(value) = &self.exclusions {
+            value.write_element("exclusions", writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn from_document(doc: &jaxp_rust::document::Document) -> Result<Self, jaxp_rust::binding::BindError> {
+        Self::from_node(jaxp_rust::binding::root_element(doc.root_element(), "dependency")?)
+    }
+
+    pub fn to_document(&self) -> jaxp_rust::document::OwnedDocument {
+        let mut writer = jaxp_rust::writer::XmlWriter::new(Vec::new());
+        // writing to a Vec cannot fail
+        writer.start_element("dependency").unwrap();
+        writer.attribute("xmlns", "http://maven.apache.org/POM/4.0.0").unwrap();
+        self.write_content(&mut writer).unwrap();
+        writer.end_element().unwrap();
+        let xml = String::from_utf8(writer.into_inner()).unwrap();
+        jaxp_rust::document::OwnedDocument::parse(xml).expect("the written document is well-formed")
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Exclusions {
+    pub exclusion: Vec<Exclusion>,
+}
+
+impl Exclusions {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Exclusions {
+            exclusion: jaxp_rust::binding::children(node, "exclusion", Exclusion::from_node)?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        for value in &self.exclusion {
+            value.write_element("exclusion", writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Exclusion {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub exclusion: Option<Box<Exclusion>>,
+}
+
+impl Exclusion {
+    pub fn from_node(node: jaxp_rust::document::Node) -> Result<Self, jaxp_rust::binding::BindError> {
+        Ok(Exclusion {
+            group_id: jaxp_rust::binding::child(node, "groupId", jaxp_rust::binding::value)?,
+            artifact_id: jaxp_rust::binding::child(node, "artifactId", jaxp_rust::binding::value)?,
+            exclusion: jaxp_rust::binding::opt_child(node, "exclusion", |n| Exclusion::from_node(n).map(Box::new))?,
+        })
+    }
+
+    pub fn write_element<W: std::io::Write>(&self, name: &str, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        writer.start_element(name)?;
+        self.write_content(writer)?;
+        writer.end_element()
+    }
+
+    /// Write the attributes and the content, right after the start tag.
+    pub fn write_content<W: std::io::Write>(&self, writer: &mut jaxp_rust::writer::XmlWriter<W>) -> std::io::Result<()> {
+        jaxp_rust::binding::write_value(writer, "groupId", &self.group_id)?;
+        jaxp_rust::binding::write_value(writer, "artifactId", &self.artifact_id)?;
+        if let Some(value) = &self.exclusion {
+            value.write_element("exclusion", writer)?;
+        }
+        Ok(())
+    }
+}
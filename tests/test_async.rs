@@ -0,0 +1,75 @@
+#![cfg(feature = "tokio")]
+
+use jaxp_rust::asyncread::{parse_async, parse_async_with_options, AsyncParseError};
+use jaxp_rust::document::Document;
+use jaxp_rust::options::{ParseOptions, Strictness};
+use jaxp_rust::writer::serialize;
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+}
+
+#[test]
+pub fn test_parse_async() {
+    let xml: &[u8] = b"<root><a attr=\"value\">text</a></root>";
+    let owned = block_on(parse_async(xml)).unwrap();
+    let doc = owned.document();
+    assert_eq!("root", doc.root_element().tag_name());
+    assert_eq!(Some("value"), doc.root_element().first_child().unwrap().attribute("attr"));
+}
+
+#[test]
+pub fn test_parse_async_errors() {
+    let invalid_utf8: &[u8] = b"<root>\xFF</root>";
+    assert!(matches!(block_on(parse_async(invalid_utf8)), Err(AsyncParseError::Io(_))));
+
+    let invalid_xml: &[u8] = b"<root></a>";
+    assert!(matches!(block_on(parse_async(invalid_xml)), Err(AsyncParseError::Xml(_))));
+}
+
+#[test]
+pub fn test_parse_async_chunks() {
+    use tokio::io::AsyncReadExt;
+
+    let documents = [
+        "\u{feff}<?xml version='1.0'?>\n<!DOCTYPE r [<!ENTITY e 'ü'>]>\n<!--c--><r a='1 &amp; 2'>t &#xfc; €<![CDATA[<>]]><?pi v?><b/></r>\n<!--end-->\n",
+        "<r>&amp;</r>",
+        "<r><a></b></r>",
+        "<r x='1' x='2'/>",
+        "<r>&bogus;</r>",
+        "<r><!-- open",
+        "  <r/>  ",
+        "",
+    ];
+    for xml in documents {
+        let expected = Document::parse(xml).map(|doc| serialize(doc.root())).map_err(|e| e.to_string());
+        // a cut at every byte, inside of every construct and character
+        for cut in 0..=xml.len() {
+            let (head, tail) = xml.as_bytes().split_at(cut);
+            let actual = match block_on(parse_async(head.chain(tail))) {
+                Ok(owned) => Ok(serialize(owned.document().root())),
+                Err(AsyncParseError::Xml(e)) => Err(e.to_string()),
+                Err(AsyncParseError::Io(e)) => panic!("{}", e),
+            };
+            assert_eq!(expected, actual, "{:?} cut at {}", xml, cut);
+        }
+    }
+
+    // a text that is read in many chunks
+    let xml = format!("<r>{}</r>", "x".repeat(100_000));
+    let owned = block_on(parse_async(xml.as_bytes())).unwrap();
+    assert_eq!(Some(100_000), owned.document().root_element().text().map(str::len));
+
+    // a character cut off at the end of the input
+    let incomplete: &[u8] = b"<r/>\xE2\x82";
+    assert!(matches!(block_on(parse_async(incomplete)), Err(AsyncParseError::Io(_))));
+}
+
+#[test]
+pub fn test_parse_async_with_options() {
+    let xml: &[u8] = b"<r x='1' x='2'><p>soup</r>";
+    assert!(block_on(parse_async(xml)).is_err());
+    let soup = ParseOptions { strictness: Strictness::TagSoup, ..Default::default() };
+    let owned = block_on(parse_async_with_options(xml, &soup)).unwrap();
+    assert_eq!("<r x=\"1\"><p>soup</p></r>", serialize(owned.document().root_element()));
+}
@@ -0,0 +1,38 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::options::{AttributeAction, AttributeFilter, ParseOptions, Strictness};
+use jaxp_rust::writer::serialize;
+
+fn redacting_options() -> ParseOptions {
+    ParseOptions {
+        attribute_filter: Some(AttributeFilter::new(|name, value, element_name| match (element_name, name) {
+            (_, "password") => AttributeAction::Drop,
+            ("user", "token") => AttributeAction::Replace("*".repeat(value.len())),
+            (_, "class") => AttributeAction::Replace(value.split_whitespace().collect::<Vec<_>>().join(" & ")),
+            _ => AttributeAction::Keep,
+        })),
+        ..Default::default()
+    }
+}
+
+#[test]
+pub fn test_attribute_filter() {
+    let xml = "<users><user name=\"a\" password=\"secret\" token=\"&#x41;bc\"/><user class=\" x  y \" password=\"\"/><group token=\"t\"/></users>";
+    let doc = Document::parse_with_options(xml, &redacting_options()).unwrap();
+    assert_eq!(
+        "<users><user name=\"a\" token=\"***\"/><user class=\"x &amp; y\"/><group token=\"t\"/></users>",
+        serialize(doc.root())
+    );
+}
+
+#[test]
+pub fn test_attribute_filter_after_checks() {
+    // duplicates are reported even if the filter drops them
+    let xml = "<a password=\"1\" password=\"2\"/>";
+    assert!(Document::parse_with_options(xml, &redacting_options()).is_err());
+    let options = ParseOptions { strictness: Strictness::Lenient, ..redacting_options() };
+    assert_eq!("<a/>", serialize(Document::parse_with_options(xml, &options).unwrap().root()));
+
+    let options = ParseOptions { strictness: Strictness::TagSoup, ..redacting_options() };
+    let doc = Document::parse_with_options("<p password=secret token=x>", &options).unwrap();
+    assert_eq!("<p token=\"x\"/>", serialize(doc.root()));
+}
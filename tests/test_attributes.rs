@@ -1,3 +1,4 @@
+use jaxp_rust::error::Expected;
 use jaxp_rust::error::XmlError::*;
 use jaxp_rust::node::XmlNode;
 use jaxp_rust::parse::XmlParser;
@@ -62,16 +63,21 @@ pub fn test_random_spaces() {
 #[test]
 pub fn test_missing_spaces() {
     let xml = "<root  \t\r\t \n  attr1=\"value1\"attr2=\"value2\"  \n\r \n \n \n \n    ></root    >";
-    let actual_err = XmlParser::default().parse(&xml).unwrap_err();
-    assert!(matches!(actual_err, IllegalToken{..})); // assert error type
+    let actual_err = XmlParser::default().parse(xml).unwrap_err();
+    assert!(matches!(actual_err, IllegalToken{ expected: Some(Expected::Space), .. })); // assert error type
 }
 
 #[test]
 pub fn test_no_equality_sign() {
     let xml = "<root attr\"value\"></root>";
-    let expected_err_target = "\"".to_string();
-    let actual_err = XmlParser::default().parse(&xml).unwrap_err();
-    assert!(matches!(actual_err, IllegalToken{..})); // assert error type
+    let actual_err = XmlParser::default().parse(xml).unwrap_err();
+    match actual_err {
+        IllegalToken { expected: Some(expected), .. } => {
+            assert_eq!(Expected::Literal("=".to_string()), expected);
+            assert_eq!("=", expected.to_string());
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
 }
 
 /// Valid names as defined in the standard. For more information, see
@@ -102,9 +108,9 @@ pub fn test_valid_unicode_names() {
 
 #[test]
 pub fn test_invalid_unicode_names() {
-    let mut start_chars_to_test = vec!["-", ".", "$", "\u{200E}"];
+    let start_chars_to_test = vec!["-", ".", "$", "\u{200E}"];
     // name chars
-    let mut name_chars_to_test = vec!["\u{B8}"];
+    let name_chars_to_test = vec!["\u{B8}"];
 
     for start_char in start_chars_to_test {
         let name = format!("{}abc", start_char);
@@ -146,9 +152,9 @@ pub fn test_single_quotes() {
             },
         ],
     };
-    assert_eq!(root_elem, XmlParser::default().parse(&single_qoutes).unwrap());
+    assert_eq!(root_elem, XmlParser::default().parse(single_qoutes).unwrap());
 
     let invalid_quotes = "<root attr=`value`></root>";
-    let actual_err = XmlParser::default().parse(&invalid_quotes).unwrap_err();
+    let actual_err = XmlParser::default().parse(invalid_quotes).unwrap_err();
     assert!(matches!(actual_err, IllegalToken{..})); // assert error type
 }
\ No newline at end of file
@@ -0,0 +1,63 @@
+use jaxp_rust::binary::{decode_base64, decode_hex, Base64Decoder, BinaryError, HexDecoder};
+use jaxp_rust::document::Document;
+
+#[test]
+pub fn test_base64() {
+    assert_eq!(b"Hello".to_vec(), decode_base64("SGVsbG8=").unwrap());
+    assert_eq!(b"Hello".to_vec(), decode_base64("SGVsbG8").unwrap());
+    assert_eq!(b"Hi!?".to_vec(), decode_base64(" SGkh\r\n Pw== ").unwrap());
+    assert_eq!(Vec::<u8>::new(), decode_base64("").unwrap());
+
+    assert_eq!(Err(BinaryError::InvalidDigit { offset: 4, digit: '-' }), decode_base64("SGVs-G8="));
+    assert_eq!(Err(BinaryError::InvalidDigit { offset: 4, digit: 'b' }), decode_base64("SG==bG8="));
+    assert_eq!(Err(BinaryError::InvalidDigit { offset: 1, digit: '=' }), decode_base64("S="));
+    assert_eq!(Err(BinaryError::Incomplete), decode_base64("SGVsb"));
+    assert_eq!(Err(BinaryError::Incomplete), decode_base64("SG="));
+}
+
+#[test]
+pub fn test_hex() {
+    assert_eq!(vec![0x0f, 0xa0, 0xff], decode_hex("0fA0 fF").unwrap());
+    assert_eq!(Err(BinaryError::InvalidDigit { offset: 2, digit: 'g' }), decode_hex("0fg0"));
+    assert_eq!(Err(BinaryError::Incomplete), decode_hex("0fa"));
+}
+
+#[test]
+pub fn test_chunks() {
+    let mut bytes = Vec::new();
+    let mut decoder = Base64Decoder::new();
+    for chunk in ["SG", "Vsb", "G8", "="] {
+        decoder.push(chunk, &mut bytes).unwrap();
+    }
+    decoder.finish().unwrap();
+    assert_eq!(b"Hello".to_vec(), bytes);
+
+    let mut bytes = Vec::new();
+    let mut decoder = HexDecoder::new();
+    decoder.push("a", &mut bytes).unwrap();
+    assert!(bytes.is_empty());
+    decoder.push("bc", &mut bytes).unwrap();
+    assert_eq!(vec![0xab], bytes);
+    // offsets count from the start of the first chunk
+    assert_eq!(Err(BinaryError::InvalidDigit { offset: 3, digit: 'x' }), decoder.push("x", &mut bytes));
+}
+
+#[test]
+pub fn test_nodes() {
+    let doc = Document::parse("<a><b>SGVs<!--c--><![CDATA[bG8=]]></b><h>cafe</h><e/></a>").unwrap();
+    let root = doc.root_element();
+    let b = root.first_element_child().unwrap();
+    assert_eq!(b"Hello".to_vec(), b.decode_base64().unwrap());
+    assert_eq!(b"Hel".to_vec(), b.first_child().unwrap().decode_base64().unwrap());
+    let h = root.children_named("h").next().unwrap();
+    assert_eq!(vec![0xca, 0xfe], h.decode_hex().unwrap());
+    assert!(root.children_named("e").next().unwrap().decode_hex().unwrap().is_empty());
+    assert!(h.decode_base64().is_ok());
+    assert!(b.decode_hex().is_err());
+
+    // references in text are resolved, in CDATA they are not
+    let doc = Document::parse("<d>SGVs&#xD;\nbG8=</d>").unwrap();
+    assert_eq!(b"Hello".to_vec(), doc.root_element().decode_base64().unwrap());
+    let doc = Document::parse("<d>&#x63;afe<![CDATA[&#x30;]]></d>").unwrap();
+    assert!(matches!(doc.root_element().decode_hex(), Err(BinaryError::InvalidDigit { digit: '&', .. })));
+}
@@ -0,0 +1,47 @@
+use jaxp_rust::error::XmlError::*;
+use jaxp_rust::tokenize::{CharIter, XmlTokenizer};
+
+#[test]
+pub fn test_char_boundaries() {
+    let mut ci = CharIter::new("ä<b>€");
+    assert!(ci.is_char_boundary());
+    // inside of ä
+    ci.advance_n(1).unwrap();
+    assert!(!ci.is_char_boundary());
+    assert!(matches!(ci.peek_xml_char(), Err(IllegalToken { .. })));
+    assert!(matches!(ci.next_xml_char(), Err(IllegalToken { .. })));
+    assert!(!ci.test_str("<b>"));
+    assert!(ci.expect_str("\u{a4}").is_err());
+    assert!(matches!(ci.advance_chars(1), Err(IllegalToken { .. })));
+    ci.advance_n(1).unwrap();
+
+    assert!(ci.test_str("<b>"));
+    ci.expect_str("<b>").unwrap();
+    assert_eq!(5, ci.pos());
+    assert!(matches!(ci.advance_checked(1), Err(IllegalToken { .. })));
+    assert_eq!(5, ci.pos());
+    ci.advance_checked(3).unwrap();
+    assert!(!ci.has_next());
+    assert!(matches!(ci.advance_checked(1), Err(UnexpectedEndOfFile { .. })));
+
+    let mut ci = CharIter::new("€€b");
+    ci.advance_chars(2).unwrap();
+    assert_eq!('b', ci.next_xml_char().unwrap());
+    let mut ci = CharIter::new("€b");
+    ci.advance_chars(2).unwrap();
+    assert!(!ci.has_next());
+    assert!(matches!(CharIter::new("€b").advance_chars(3), Err(UnexpectedEndOfFile { .. })));
+
+    // advancing beyond the end
+    let mut ci = CharIter::new("ab");
+    assert!(matches!(ci.advance_n(3), Err(UnexpectedEndOfFile { .. })));
+    assert_eq!(0, ci.pos());
+}
+
+#[test]
+pub fn test_consume_name() {
+    let mut ci = CharIter::new("näme rest");
+    assert_eq!("näme", XmlTokenizer::consume_name(&mut ci).unwrap().slice());
+    assert!(ci.test_str(" rest"));
+    assert!(XmlTokenizer::consume_name(&mut CharIter::new("1a")).is_err());
+}
@@ -0,0 +1,142 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Output, Stdio};
+
+fn jaxp(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jaxp"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // commands that fail early do not read their input
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+pub fn test_commands() {
+    let xml = "<r><a id=\"1\">x</a><!--c--><a id=\"2\">y</a></r>";
+    assert_eq!("ok\n", stdout(&jaxp(&["check"], xml)));
+    assert_eq!("<r>\n    <a id=\"1\">x</a>\n    <!--c-->\n    <a id=\"2\">y</a>\n</r>\n", stdout(&jaxp(&["fmt", "--indent", "4", "-"], xml)));
+    assert_eq!("<r>\r\n\t<a id=\"1\">x</a>\r\n\t<!--c-->\r\n\t<a id=\"2\">y</a>\r\n</r>\r\n", stdout(&jaxp(&["fmt", "--tabs", "--crlf"], xml)));
+    assert_eq!("<r><a id=\"1\">x</a><a id=\"2\">y</a></r>\n", stdout(&jaxp(&["minify"], xml)));
+    assert!(stdout(&jaxp(&["stats"], xml)).starts_with("elements: 3\n"));
+    assert_eq!("1\n2\n", stdout(&jaxp(&["get", "/r/a/@id"], xml)));
+    assert_eq!("x\ny\n", stdout(&jaxp(&["get", "r/*"], xml)));
+    assert!(stdout(&jaxp(&["tokens", "--format", "json"], "<r/>")).starts_with("{\"tokens\":[{\"kind\":\"start-tag\","));
+    let output = jaxp(&["tokens"], "<r><!-- -- --></r>");
+    assert_eq!(Some(1), output.status.code());
+    assert!(stdout(&output).lines().last().unwrap().starts_with("error: "));
+}
+
+#[test]
+pub fn test_exit_codes() {
+    assert_eq!(Some(1), jaxp(&["check"], "<a></b>").status.code());
+    assert_eq!(Some(1), jaxp(&["get", "/a/b"], "<a/>").status.code());
+    assert_eq!(Some(2), jaxp(&["unknown"], "<a/>").status.code());
+    assert_eq!(Some(2), jaxp(&["check", "/does/not/exist.xml"], "").status.code());
+}
+
+#[test]
+pub fn test_diff() {
+    let dir = env!("CARGO_TARGET_TMPDIR");
+    let old = format!("{}/diff_old.xml", dir);
+    std::fs::write(&old, "<r a='1' b='2'>\n  <x/>\n</r>").unwrap();
+    let output = jaxp(&["diff", &old, "-"], "<r b='2' a='1'><x/><y/></r>");
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("~ /r[1]: attributes reordered\n- /r[1]/text()[1]: \n  \n- /r[1]/text()[2]: \n\n+ /r[1]/y[1]: <y/>\n", stdout(&output));
+    let output = jaxp(&["diff", "--ignore-whitespace", "--ignore-attr-order", "--format", "json", &old, "-"], "<r b='2' a='1'><x/><y/></r>");
+    assert_eq!("[{\"kind\":\"added\",\"path\":\"/r[1]/y[1]\",\"value\":\"<y/>\"}]\n", stdout(&output));
+    assert_eq!(Some(0), jaxp(&["diff", "--ignore-whitespace", "--ignore-attr-order", &old, "-"], "<r b='2' a='1'><x/></r>").status.code());
+    assert_eq!(Some(2), jaxp(&["diff", &old], "").status.code());
+    assert_eq!(Some(2), jaxp(&["diff", &old, "-"], "<r>").status.code());
+}
+
+/// Stops a command that runs until interrupted, also if a test fails.
+struct KillOnDrop(Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+pub fn test_check_watch() {
+    let dir = format!("{}/watch", env!("CARGO_TARGET_TMPDIR"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(format!("{}/topics", dir)).unwrap();
+    std::fs::write(format!("{}/book.xml", dir), "<book/>").unwrap();
+    std::fs::write(format!("{}/topics/intro.dita", dir), "<topic>").unwrap();
+    std::fs::write(format!("{}/notes.txt", dir), "<not checked").unwrap();
+
+    let mut child = KillOnDrop(Command::new(env!("CARGO_BIN_EXE_jaxp"))
+        .args(["check", "--watch", &dir, "--interval", "20"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap());
+    let mut lines = BufReader::new(child.0.stdout.take().unwrap()).lines().map(Result::unwrap);
+    assert_eq!(format!("{}/book.xml: ok", dir), lines.next().unwrap());
+    assert!(lines.next().unwrap().starts_with(&format!("{}/topics/intro.dita:", dir)));
+
+    std::fs::write(format!("{}/topics/intro.dita", dir), "<topic></topic>").unwrap();
+    assert_eq!(format!("{}/topics/intro.dita: ok", dir), lines.next().unwrap());
+    std::fs::remove_file(format!("{}/book.xml", dir)).unwrap();
+    assert_eq!(format!("{}/book.xml: removed", dir), lines.next().unwrap());
+}
+
+#[test]
+pub fn test_check_dir() {
+    let dir = format!("{}/check_dir", env!("CARGO_TARGET_TMPDIR"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(format!("{}/topics", dir)).unwrap();
+    std::fs::write(format!("{}/book.xml", dir), "<book/>").unwrap();
+    std::fs::write(format!("{}/topics/intro.dita", dir), "<topic>").unwrap();
+    std::fs::write(format!("{}/notes.txt", dir), "<not checked").unwrap();
+    // links to directories are not followed, this one would lead around in circles
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("..", format!("{}/topics/loop", dir)).unwrap();
+
+    let output = jaxp(&["check", &dir], "");
+    assert_eq!(Some(1), output.status.code());
+    let lines: Vec<_> = stdout(&output).lines().collect();
+    assert_eq!(2, lines.len(), "{:?}", lines);
+    assert_eq!(format!("{}/book.xml: ok", dir), lines[0]);
+    assert!(lines[1].starts_with(&format!("{}/topics/intro.dita:", dir)));
+
+    std::fs::write(format!("{}/topics/intro.dita", dir), "<topic></topic>").unwrap();
+    assert_eq!(Some(0), jaxp(&["check", &dir], "").status.code());
+
+    // warnings are reported, but fail neither a directory nor a file
+    let warned = format!("{}/topics/warned.xml", dir);
+    std::fs::write(&warned, "<!DOCTYPE topic>\n<topic id=''/>").unwrap();
+    let output = jaxp(&["check", &dir], "");
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(2, stdout(&output).lines().filter(|line| line.starts_with(&warned)).count(), "{}", stdout(&output));
+    assert_eq!(Some(0), jaxp(&["check", &warned], "").status.code());
+}
+
+#[test]
+pub fn test_grep() {
+    let dir = env!("CARGO_TARGET_TMPDIR");
+    let (a, b) = (format!("{}/grep_a.xml", dir), format!("{}/grep_b.xml", dir));
+    std::fs::write(&a, "<r>\n  <item id='1'>one</item>\n  <item id='2'>\n    two\n  </item>\n</r>").unwrap();
+    std::fs::write(&b, "<r><item id='3'/><!--item--></r>").unwrap();
+    let output = jaxp(&["grep", "//item", &a, &b], "");
+    assert_eq!(Some(0), output.status.code());
+    let expected = format!("{a}:2:4: <item id=\"1\">one</item>\n{a}:3:4: <item id=\"2\">...\n{b}:1:5: <item id=\"3\"/>\n", a = a, b = b);
+    assert_eq!(expected, stdout(&output));
+    assert_eq!("(standard input):1:4: id=\"7\"\n(standard input):1:16: <!--c-->\n", stdout(&jaxp(&["grep", "//@id | //comment()"], "<a id='7'/><!--c-->")));
+
+    assert_eq!(Some(1), jaxp(&["grep", "//none", &a], "").status.code());
+    assert_eq!(Some(2), jaxp(&["grep", "//item[", &a], "").status.code());
+    let output = jaxp(&["grep", "//item", "-", &a], "<broken>");
+    assert_eq!(Some(2), output.status.code());
+    assert_eq!(2, stdout(&output).lines().count());
+}
@@ -0,0 +1,98 @@
+use jaxp_rust::binding::BindError;
+use jaxp_rust::codegen::{generate_from_sample, generate_from_schema, CodegenError};
+use jaxp_rust::document::Document;
+
+// the generated sources are checked in, so that the tests compile them
+#[allow(dead_code)]
+mod pom {
+    include!("codegen/pom.rs");
+}
+
+#[allow(dead_code)]
+mod catalog {
+    include!("codegen/catalog.rs");
+}
+
+const POM: &str = r#"<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <modelVersion>4.0.0</modelVersion>
+  <parent><groupId>org.example</groupId><artifactId>parent</artifactId><version>1</version></parent>
+  <artifactId>demo</artifactId>
+  <modules><module>core</module><module>cli</module></modules>
+  <properties><property name="java.version">17</property></properties>
+  <dependencies>
+    <dependency type="jar" priority="2">
+      <groupId>junit</groupId><artifactId>junit</artifactId><version>4.13</version><scope>test</scope>
+      <exclusions>
+        <exclusion><groupId>org.hamcrest</groupId><artifactId>core</artifactId></exclusion>
+      </exclusions>
+    </dependency>
+    <dependency><groupId>serde</groupId><artifactId>json</artifactId><optional>1</optional></dependency>
+  </dependencies>
+</project>"#;
+
+#[test]
+pub fn test_generated_sources_are_current() {
+    assert_eq!(include_str!("codegen/pom.rs"), generate_from_schema(include_str!("codegen/pom.xsd")).unwrap());
+    assert_eq!(include_str!("codegen/catalog.rs"), generate_from_sample(include_str!("codegen/catalog.xml")).unwrap());
+}
+
+#[test]
+pub fn test_schema_binding() {
+    let doc = Document::parse(POM).unwrap();
+    let project = pom::Model::from_document(&doc).unwrap();
+    assert_eq!(Some("4.0.0"), project.model_version.as_deref());
+    assert_eq!("parent", project.parent.as_ref().unwrap().artifact_id);
+    assert_eq!(vec!["core", "cli"], project.modules.as_ref().unwrap().module);
+    assert_eq!("17", project.properties.as_ref().unwrap().property[0].value);
+    let dependencies = &project.dependencies.as_ref().unwrap().dependency;
+    assert_eq!((Some("jar"), Some(2)), (dependencies[0].r#type.as_deref(), dependencies[0].priority));
+    assert_eq!(Some("test"), dependencies[0].scope.as_deref());
+    assert_eq!("org.hamcrest", dependencies[0].exclusions.as_ref().unwrap().exclusion[0].group_id);
+    assert_eq!(Some(true), dependencies[1].optional);
+
+    let written = project.to_document();
+    assert_eq!(Some("http://maven.apache.org/POM/4.0.0"), written.document().root_element().namespace_uri());
+    assert_eq!(project, pom::Model::from_document(written.document()).unwrap());
+}
+
+#[test]
+pub fn test_sample_binding() {
+    let doc = Document::parse(include_str!("codegen/catalog.xml")).unwrap();
+    let catalog = catalog::Catalog::from_document(&doc).unwrap();
+    assert_eq!(2, catalog.book.len());
+    assert_eq!(vec!["Elliotte Rusty Harold", "W. Scott Means"], catalog.book[0].author);
+    assert_eq!(("USD", "39.95"), (catalog.book[0].price.currency.as_str(), catalog.book[0].price.text.as_str()));
+    assert_eq!(None, catalog.book[1].lang);
+    assert_eq!(Some("Second  pending"), catalog.book[1].note.as_ref().map(|n| n.text.as_str()));
+    assert_eq!(catalog, catalog::Catalog::from_document(catalog.to_document().document()).unwrap());
+}
+
+#[test]
+pub fn test_binding_errors() {
+    let parse = |xml: &str| pom::Model::from_document(&Document::parse(xml).unwrap());
+    assert!(matches!(parse("<pom/>"), Err(BindError::UnexpectedRoot { .. })));
+    assert!(matches!(parse("<project/>"), Err(BindError::MissingElement { name, .. }) if name == "artifactId"));
+    assert!(matches!(
+        parse("<project><artifactId>a</artifactId><dependencies><dependency priority='high'><groupId>g</groupId><artifactId>a</artifactId></dependency></dependencies></project>"),
+        Err(BindError::InvalidValue { name, value }) if name == "priority" && value == "high"
+    ));
+}
+
+#[test]
+pub fn test_names() {
+    let code = generate_from_sample("<root-element URLPath='' self='' type='' x-2=''><item.list/><item.list/><HTTPServer2Config/></root-element>").unwrap();
+    for line in ["pub struct RootElement {", "pub url_path: String,", "pub self_: String,", "pub r#type: String,", "pub x_2: String,", "pub item_list: Vec<String>,", "pub http_server2_config: String,"].iter() {
+        assert!(code.contains(line), "{} in {}", line, code);
+    }
+}
+
+#[test]
+pub fn test_schema_errors() {
+    let schema = |content: &str| generate_from_schema(&format!("<xs:schema xmlns:xs=\"http://www.w3.org/2001/XMLSchema\">{}</xs:schema>", content));
+    assert!(matches!(generate_from_schema("<schema/>"), Err(CodegenError::InvalidSchema(_))));
+    assert!(matches!(schema("<xs:element name='a' type='Missing'/>"), Err(CodegenError::InvalidSchema(_))));
+    assert!(matches!(schema("<xs:element name='a'><xs:complexType><xs:sequence><xs:any/></xs:sequence></xs:complexType></xs:element>"), Err(CodegenError::Unsupported(_))));
+    assert!(matches!(schema("<xs:element name='a'><xs:complexType><xs:complexContent/></xs:complexType></xs:element>"), Err(CodegenError::Unsupported(_))));
+    // simple global elements have no structs
+    assert_eq!("// Generated by jaxp_rust::codegen, do not edit.\n", schema("<xs:element name='a' type='xs:int'/>").unwrap());
+}
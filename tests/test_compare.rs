@@ -0,0 +1,86 @@
+use jaxp_rust::compare::{compare, first_divergence, jaxp_events, normalize, ComparedEvent, Divergence};
+use jaxp_rust::options::ParseOptions;
+
+fn start(name: &str, attributes: &[(&str, &str)]) -> ComparedEvent {
+    let attributes = attributes.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect();
+    ComparedEvent::StartElement { name: name.to_string(), attributes }
+}
+
+fn end(name: &str) -> ComparedEvent {
+    ComparedEvent::EndElement { name: name.to_string() }
+}
+
+fn text(text: &str) -> ComparedEvent {
+    ComparedEvent::Text(text.to_string())
+}
+
+#[test]
+pub fn test_jaxp_events() {
+    let xml = "<?xml version='1.0'?>\n<a x='&lt;1'>a&amp;b<![CDATA[<c>]]><b/><!--d--><?pi v?></a>\n";
+    let expected = vec![
+        start("a", &[("x", "<1")]),
+        text("a&b<c>"),
+        start("b", &[]),
+        end("b"),
+        ComparedEvent::Comment("d".to_string()),
+        ComparedEvent::ProcessingInstruction { target: "pi".to_string(), opt_value: Some("v".to_string()) },
+        end("a"),
+    ];
+    assert_eq!(expected, normalize(jaxp_events(xml, &ParseOptions::default())));
+
+    let events = normalize(jaxp_events("<a><b></a>", &ParseOptions::default()));
+    assert!(matches!(events.last(), Some(ComparedEvent::Error(_))));
+}
+
+#[test]
+pub fn test_normalize() {
+    let events = vec![text("\n"), start("a", &[]), text("x"), text(""), text("y"), end("a"), text(" "),
+                      ComparedEvent::Error("bad".to_string()), start("b", &[])];
+    assert_eq!(vec![start("a", &[]), text("xy"), end("a"), ComparedEvent::Error("bad".to_string())], normalize(events));
+}
+
+#[test]
+pub fn test_divergence() {
+    let options = ParseOptions::default();
+    assert_eq!(None, compare("<a>x</a>", &options, vec![start("a", &[]), text("x"), end("a")]));
+    assert_eq!(
+        Some(Divergence { index: 1, jaxp: Some(text("x")), reference: Some(text("y")) }),
+        compare("<a>x</a>", &options, vec![start("a", &[]), text("y"), end("a")]),
+    );
+    assert_eq!(
+        Some(Divergence { index: 2, jaxp: Some(end("a")), reference: None }),
+        compare("<a>x</a>", &options, vec![start("a", &[]), text("x")]),
+    );
+    // both parsers reject the document, whatever they report before
+    assert_eq!(None, compare("<a>&bogus;</a>", &options, vec![start("a", &[]), ComparedEvent::Error("undefined entity".to_string())]));
+    let divergence = compare("<a>&bogus;</a>", &options, vec![start("a", &[]), end("a")]).unwrap();
+    assert!(matches!(divergence.jaxp, Some(ComparedEvent::Error(_))));
+
+    let divergence = first_divergence(&[start("a", &[])], &[start("a", &[("x", "1")])]).unwrap();
+    assert_eq!(0, divergence.index);
+    assert!(divergence.to_string().starts_with("event 0: jaxp reports StartElement"));
+}
+
+#[cfg(feature = "quick-xml")]
+#[test]
+pub fn test_quick_xml() {
+    use jaxp_rust::compare::quick_xml_events;
+    use jaxp_rust::options::Strictness;
+
+    for entry in std::fs::read_dir("tests/conformance/wf").unwrap() {
+        let path = entry.unwrap().path();
+        let xml = std::fs::read_to_string(&path).unwrap();
+        // quick-xml does not check namespaces, the catalog marks these cases as not namespace-well-formed
+        let options = if path.ends_with("reserved-prefix.xml") || path.ends_with("undeclared-prefix.xml") {
+            ParseOptions { strictness: Strictness::Lenient, ..Default::default() }
+        } else {
+            ParseOptions::default()
+        };
+        assert_eq!(None, compare(&xml, &options, quick_xml_events(&xml)), "{}", xml);
+    }
+    let xml = "<a x='1' y=\"&amp;\">t&#x41;<![CDATA[<>]]><b/><?pi  v?></a>";
+    assert_eq!(None, compare(xml, &ParseOptions::default(), quick_xml_events(xml)));
+    // quick-xml accepts a second root element
+    let divergence = compare("<a/><b/>", &ParseOptions::default(), quick_xml_events("<a/><b/>")).unwrap();
+    assert_eq!((2, Some(start("b", &[]))), (divergence.index, divergence.reference));
+}
@@ -0,0 +1,137 @@
+//! Runs well-formedness cases from catalogs in the format of the
+//! [W3C XML Conformance Test Suite](https://www.w3.org/XML/Test/).
+//!
+//! The bundled sample catalog always runs. To run the full suite, unpack it and point
+//! `XMLCONF_DIR` to the `xmlconf` directory, every `TESTCASES` catalog below it is run
+//! and the results are reported without failing the test.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use jaxp_rust::document::{Document, Node};
+use jaxp_rust::options::{ParseOptions, Strictness};
+
+#[derive(Default)]
+struct Report {
+    passed: usize,
+    skipped: usize,
+    failed: Vec<String>,
+}
+
+impl Report {
+    fn merge(&mut self, other: Report) {
+        self.passed += other.passed;
+        self.skipped += other.skipped;
+        self.failed.extend(other.failed);
+    }
+}
+
+/// The directory a test URI is relative to, taking xml:base attributes into account.
+fn base_dir(catalog_dir: &Path, test: Node) -> PathBuf {
+    let mut bases: Vec<_> = test.ancestors().filter_map(|n| n.attribute("xml:base")).collect();
+    bases.reverse();
+    bases.iter().fold(catalog_dir.to_path_buf(), |dir, base| dir.join(base))
+}
+
+fn run_catalog(catalog: &Path) -> Report {
+    let mut report = Report::default();
+    let text = match fs::read_to_string(catalog) {
+        Ok(text) => text,
+        Err(_) => return report,
+    };
+    let doc = match Document::parse(&text) {
+        Ok(doc) if doc.root_element().tag_name() == "TESTCASES" => doc,
+        _ => return report,
+    };
+    let catalog_dir = catalog.parent().unwrap();
+
+    for test in doc.descendants().filter(|n| n.tag_name() == "TEST") {
+        let id = test.attribute("ID").unwrap_or("?");
+        let expect_wf = match test.attribute("TYPE") {
+            Some("valid") | Some("invalid") => true,
+            Some("not-wf") => false,
+            // errors are optional for processors
+            _ => {
+                report.skipped += 1;
+                continue;
+            }
+        };
+        // external entities are not loaded, XML 1.1 is not supported
+        let needs_entities = test.attribute("ENTITIES").is_some_and(|e| e != "none");
+        let is_xml_1_1 = test.attribute("VERSION") == Some("1.1")
+            || test.attribute("RECOMMENDATION").is_some_and(|r| r.contains("1.1"));
+        if needs_entities || is_xml_1_1 {
+            report.skipped += 1;
+            continue;
+        }
+        let path = base_dir(catalog_dir, test).join(test.attribute("URI").unwrap_or(""));
+        // only UTF-8 input is supported
+        let xml = match fs::read(&path).ok().and_then(|bytes| String::from_utf8(bytes).ok()) {
+            Some(xml) => xml,
+            None => {
+                report.skipped += 1;
+                continue;
+            }
+        };
+        let strictness = match test.attribute("NAMESPACE") {
+            Some("no") => Strictness::Lenient,
+            _ => Strictness::Conformant,
+        };
+        let result = Document::parse_with_options(&xml, &ParseOptions { strictness, ..Default::default() });
+        if result.is_ok() == expect_wf {
+            report.passed += 1;
+        } else {
+            let outcome = match result {
+                Ok(_) => "accepted".to_string(),
+                Err(e) => format!("rejected: {}", e),
+            };
+            report.failed.push(format!("{} ({}): {}", id, path.display(), outcome));
+        }
+    }
+    report
+}
+
+fn collect_catalogs(dir: &Path, catalogs: &mut Vec<PathBuf>) {
+    let mut entries: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(_) => return,
+    };
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_catalogs(&path, catalogs);
+        } else if path.extension().is_some_and(|e| e == "xml") {
+            catalogs.push(path);
+        }
+    }
+}
+
+#[test]
+pub fn test_sample_catalog() {
+    let catalog = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance/catalog.xml");
+    let report = run_catalog(&catalog);
+    assert_eq!(Vec::<String>::new(), report.failed);
+    assert_eq!(23, report.passed);
+}
+
+#[test]
+pub fn test_w3c_suite() {
+    let dir = match env::var_os("XMLCONF_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            println!("XMLCONF_DIR is not set, skipping the W3C XML Conformance Test Suite");
+            return;
+        }
+    };
+    let mut catalogs = vec![];
+    collect_catalogs(&dir, &mut catalogs);
+    let mut report = Report::default();
+    for catalog in catalogs {
+        report.merge(run_catalog(&catalog));
+    }
+    for failure in &report.failed {
+        println!("FAILED {}", failure);
+    }
+    println!("passed: {}, failed: {}, skipped: {}", report.passed, report.failed.len(), report.skipped);
+}
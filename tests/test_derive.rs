@@ -0,0 +1,112 @@
+#![cfg(feature = "derive")]
+
+use jaxp_rust::binding::{from_xml, to_xml, BindError, FromXml, ToXml};
+
+#[derive(Debug, Default, PartialEq, FromXml, ToXml)]
+struct Library {
+    #[xml(attr)]
+    name: String,
+    #[xml(attr = "opened")]
+    year: Option<u16>,
+    #[xml(child = "book")]
+    books: Vec<Book>,
+    address: Option<Address>,
+    #[xml(skip)]
+    visits: u32,
+}
+
+#[derive(Debug, Default, PartialEq, FromXml, ToXml)]
+struct Book {
+    #[xml(attr)]
+    id: u32,
+    title: String,
+    #[xml(child = "author")]
+    authors: Vec<String>,
+    available: bool,
+}
+
+#[derive(Debug, Default, PartialEq, FromXml, ToXml)]
+struct Address {
+    #[xml(text)]
+    street: String,
+    #[xml(attr)]
+    r#type: Option<String>,
+    note: Option<Note>,
+}
+
+#[derive(Debug, Default, PartialEq, FromXml, ToXml)]
+struct Note {
+    #[xml(attr)]
+    lang: Option<String>,
+    #[xml(text)]
+    text: Option<String>,
+}
+
+const LIBRARY: &str = r#"<library xmlns="urn:lib" name="City &amp; County" opened="1901">
+  <book id="1">
+    <title>Dune</title>
+    <author>Frank Herbert</author>
+    <publisher>Chilton</publisher>
+    <available>true</available>
+  </book>
+  <book id="2"><title><![CDATA[<Untitled>]]></title><available>0</available></book>
+  <address type="postal">Main Street <note lang="en"/>1</address>
+</library>"#;
+
+#[test]
+pub fn test_from_xml() {
+    let library: Library = from_xml(LIBRARY).unwrap();
+    assert_eq!("City & County", library.name);
+    assert_eq!(Some(1901), library.year);
+    assert_eq!(0, library.visits);
+    assert_eq!(
+        vec![
+            Book { id: 1, title: "Dune".to_string(), authors: vec!["Frank Herbert".to_string()], available: true },
+            Book { id: 2, title: "<Untitled>".to_string(), authors: vec![], available: false },
+        ],
+        library.books
+    );
+    let address = library.address.unwrap();
+    assert_eq!("Main Street 1", address.street);
+    assert_eq!(Some("postal"), address.r#type.as_deref());
+    assert_eq!(Some(Note { lang: Some("en".to_string()), text: None }), address.note);
+}
+
+#[test]
+pub fn test_to_xml() {
+    let library = Library {
+        name: "<Central>".to_string(),
+        year: None,
+        books: vec![Book { id: 3, title: "A & B".to_string(), authors: vec!["X".to_string(), "Y".to_string()], available: true }],
+        address: Some(Address { street: "Elm".to_string(), r#type: None, note: None }),
+        visits: 10,
+    };
+    let xml = to_xml(&library, "library");
+    assert_eq!(
+        "<library name=\"&lt;Central&gt;\"><book id=\"3\"><title>A &amp; B</title><author>X</author><author>Y</author>\
+         <available>true</available></book><address>Elm</address></library>",
+        xml
+    );
+    assert_eq!(Library { visits: 0, ..library }, from_xml(&xml).unwrap());
+}
+
+#[test]
+pub fn test_errors() {
+    assert!(matches!(
+        from_xml::<Book>("<book><title>t</title><available>true</available></book>"),
+        Err(BindError::MissingAttribute { element, name }) if element == "book" && name == "id"
+    ));
+    assert!(matches!(
+        from_xml::<Book>("<book id=\"1\"><available>true</available></book>"),
+        Err(BindError::MissingElement { parent, name }) if parent == "book" && name == "title"
+    ));
+    assert!(matches!(
+        from_xml::<Book>("<book id=\"x\"/>"),
+        Err(BindError::InvalidValue { name, value }) if name == "id" && value == "x"
+    ));
+    assert!(matches!(
+        from_xml::<Book>("<book id=\"1\"><title>t</title><available>maybe</available></book>"),
+        Err(BindError::InvalidValue { name, value }) if name == "available" && value == "maybe"
+    ));
+    assert!(matches!(from_xml::<Book>("<book id=\"1\"><title>t</title>"), Err(BindError::Xml(_))));
+}
@@ -0,0 +1,69 @@
+use std::fs;
+
+use jaxp_rust::document::Document;
+use jaxp_rust::options::{CommentPolicy, ParseOptions, TokenizerBackend};
+use jaxp_rust::tokenize::XmlTokenizer;
+
+/// The tokens or the error of both backends, which have to be the same.
+fn assert_same_tokens(xml: &str, options: &ParseOptions) {
+    let dfa = ParseOptions { tokenizer_backend: TokenizerBackend::Dfa, ..options.clone() };
+    let expected = format!("{:?}", XmlTokenizer::with_options(options).tokenize_owned(xml));
+    let actual = format!("{:?}", XmlTokenizer::with_options(&dfa).tokenize_owned(xml));
+    assert_eq!(expected, actual, "tokens of {:?}", xml);
+}
+
+#[test]
+pub fn test_dfa_backend() {
+    let inputs = [
+        "<a/>",
+        "<a></a>",
+        "<?xml version='1.0' standalone='yes'?>\n<!DOCTYPE a [<!ENTITY e 'x'>]>\n<!-- c --><a>&e;</a>\n<?pi?>\n",
+        "<root x=\"1\" y = '2'\tz=\"&amp;&#60;'\"><b/><c d='e' /><f g='h'/>text &lt; ]] > &#x1F600;</root >  \n",
+        "<ns:root xmlns:ns='urn:ns' ns:a='1'><ns:child>é</ns:child></ns:root>",
+        "<日本 語='値'><ä.b-c_d:e/></日本>",
+        "<a><!-- comment --><![CDATA[<x> ]] & ]]><?target value?>tail</a>",
+        "<a>\r\n\t</a>\n\n",
+        "\u{feff}<a/>",
+        // errors
+        "<a x='1'y='2'/>",
+        "<a x=1/>",
+        "<a x='<'/>",
+        "<a>]]></a>",
+        "<a>&unknown;</a>",
+        "<a>&</a>",
+        "<a>\u{1}</a>",
+        "<a x='\u{1}'/>",
+        "<a></a>text",
+        "<a/ >",
+        "< a/>",
+        "<1a/>",
+        "<a><!DOCTYPE></a>",
+        "<a></ a>",
+        "<a></a",
+        "<a x='1",
+        "<a x",
+        "<a><!-- -- --></a>",
+        "<a><![CDATA[x</a>",
+        "<a>\u{fffe}</a>",
+        "<a\u{fffe}/>",
+        "<a \u{b7}='1'/>",
+        "<a>",
+        "",
+    ];
+    for xml in inputs {
+        assert_same_tokens(xml, &ParseOptions::default());
+    }
+    let relaxed = ParseOptions { comments: CommentPolicy::AllowDoubleHyphen, lazy_char_validation: true, ..Default::default() };
+    for xml in inputs {
+        assert_same_tokens(xml, &relaxed);
+    }
+    for dir in ["tests/conformance/wf", "tests/conformance/not-wf"] {
+        for entry in fs::read_dir(dir).unwrap() {
+            assert_same_tokens(&fs::read_to_string(entry.unwrap().path()).unwrap(), &ParseOptions::default());
+        }
+    }
+
+    let options = ParseOptions { tokenizer_backend: TokenizerBackend::Dfa, ..Default::default() };
+    let doc = Document::parse_with_options("<a x='1'><b>text</b><c/></a>", &options).unwrap();
+    assert_eq!(Some("<a x='1'><b>text</b><c/></a>"), doc.root_element().original_markup());
+}
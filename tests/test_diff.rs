@@ -0,0 +1,73 @@
+use jaxp_rust::diff::{diff, Change, DiffOptions};
+use jaxp_rust::document::Document;
+
+fn changes(old: &str, new: &str, options: &DiffOptions) -> Vec<Change> {
+    let (old, new) = (Document::parse(old).unwrap(), Document::parse(new).unwrap());
+    diff(old.root(), new.root(), options).changes
+}
+
+fn added(path: &str, value: &str) -> Change {
+    Change::Added { path: path.to_string(), value: value.to_string() }
+}
+
+fn removed(path: &str, value: &str) -> Change {
+    Change::Removed { path: path.to_string(), value: value.to_string() }
+}
+
+fn changed(path: &str, old: &str, new: &str) -> Change {
+    Change::Changed { path: path.to_string(), old: old.to_string(), new: new.to_string() }
+}
+
+#[test]
+pub fn test_diff_nodes() {
+    let options = DiffOptions::default();
+    assert!(changes("<a>x &amp; <![CDATA[y]]></a>", "<a>x &#38; y</a>", &options).is_empty());
+    assert_eq!(
+        vec![removed("/a[1]/comment()[1]", "<!--d-->"), added("/a[1]/c[1]", "<c/>")],
+        changes("<a><b/><!--d--></a>", "<a><b/><c/></a>", &options),
+    );
+    // an inserted element is not reported as changes to its following siblings
+    assert_eq!(
+        vec![added("/r[1]/i[1]", "<i>0</i>")],
+        changes("<r><i>1</i><i>2</i></r>", "<r><i>0</i><i>1</i><i>2</i></r>", &options),
+    );
+    assert_eq!(
+        vec![changed("/r[1]/processing-instruction()[1]", "v", "w"), changed("/r[1]/i[1]/text()[1]", "1", "one")],
+        changes("<r><?pi v?><i>1</i></r>", "<r><?pi w?><i>one</i></r>", &options),
+    );
+    assert_eq!(vec![removed("/a[1]", "<a/>"), added("/b[1]", "<b/>")], changes("<a/>", "<b/>", &options));
+}
+
+#[test]
+pub fn test_diff_attributes() {
+    let options = DiffOptions::default();
+    assert_eq!(
+        vec![changed("/a[1]/@x", "1", "2"), removed("/a[1]/@y", "<"), added("/a[1]/@z", "3")],
+        changes("<a x='1' y='&lt;'/>", "<a x='2' z='3'/>", &options),
+    );
+    let reordered = changes("<a x='1' y='2'/>", "<a y='2' x='1'/>", &options);
+    assert_eq!(vec![Change::AttributesReordered { path: "/a[1]".to_string() }], reordered);
+    let options = DiffOptions { ignore_attribute_order: true, ..DiffOptions::default() };
+    assert!(changes("<a x='1' y='2'/>", "<a y='2' x='1'/>", &options).is_empty());
+}
+
+#[test]
+pub fn test_diff_whitespace() {
+    let (old, new) = ("<a>\n  <b c='x  y'>one  two</b>\n</a>", "<a><b c=' x y'>one\ntwo </b></a>");
+    assert_eq!(4, changes(old, new, &DiffOptions::default()).len());
+    let options = DiffOptions { ignore_whitespace: true, ..DiffOptions::default() };
+    assert!(changes(old, new, &options).is_empty());
+}
+
+#[test]
+pub fn test_diff_output() {
+    let (old, new) = (Document::parse("<a x='1'>\"t\"</a>").unwrap(), Document::parse("<a>u<b/></a>").unwrap());
+    let diff = diff(old.root(), new.root(), &DiffOptions::default());
+    assert_eq!("- /a[1]/@x: 1\n~ /a[1]/text()[1]: \"\\\"t\\\"\" -> \"u\"\n+ /a[1]/b[1]: <b/>\n", diff.to_string());
+    assert_eq!(
+        "[{\"kind\":\"removed\",\"path\":\"/a[1]/@x\",\"value\":\"1\"},\
+         {\"kind\":\"changed\",\"path\":\"/a[1]/text()[1]\",\"old\":\"\\\"t\\\"\",\"new\":\"u\"},\
+         {\"kind\":\"added\",\"path\":\"/a[1]/b[1]\",\"value\":\"<b/>\"}]",
+        diff.to_json(),
+    );
+}
@@ -0,0 +1,607 @@
+use jaxp_rust::document::{Document, Node, NodeType, SortKey, XmlDecl, XmlVersion};
+use jaxp_rust::document::ContentModel::*;
+use jaxp_rust::entities::EntityTable;
+use jaxp_rust::error::XmlError::*;
+use jaxp_rust::error::{XmlErrorPos, XmlWarning};
+use jaxp_rust::options::{CommentPolicy, DoctypePolicy, ParseOptions, ProcessingInstructionPolicy, SampleOptions, Strictness};
+use jaxp_rust::token::XmlToken;
+use jaxp_rust::tokenize::XmlTokenizer;
+use jaxp_rust::writer::serialize;
+
+#[test]
+pub fn test_navigation() {
+    let xml = "<root><a x=\"1\">first</a><!--comment--><b><c/></b>tail</root>";
+    let doc = Document::parse(xml).unwrap();
+    let root = doc.root_element();
+    assert_eq!("root", root.tag_name());
+    assert_eq!(NodeType::Root, root.parent().unwrap().node_type());
+
+    let children: Vec<_> = root.children().collect();
+    assert_eq!(4, children.len());
+    assert_eq!("a", children[0].tag_name());
+    assert!(children[1].is_comment());
+    assert_eq!(Some(children[2]), children[1].next_sibling());
+    assert_eq!(Some(children[0]), children[1].prev_sibling());
+    assert_eq!(None, children[0].prev_sibling());
+    assert_eq!(None, children[3].next_sibling());
+    assert_eq!(Some(root), children[2].parent_element());
+}
+
+#[test]
+pub fn test_descendants_and_ancestors() {
+    let xml = "<root><a><b/></a><c><d/></c></root>";
+    let doc = Document::parse(xml).unwrap();
+    let names: Vec<_> = doc.descendants().filter(|n| n.is_element()).map(|n| n.tag_name()).collect();
+    assert_eq!(vec!["root", "a", "b", "c", "d"], names);
+
+    let a = doc.root_element().first_child().unwrap();
+    let names: Vec<_> = a.descendants().map(|n| n.tag_name()).collect();
+    assert_eq!(vec!["a", "b"], names);
+
+    let d = doc.descendants().find(|n| n.tag_name() == "d").unwrap();
+    let names: Vec<_> = d.ancestors().map(|n| n.tag_name()).collect();
+    assert_eq!(vec!["d", "c", "root", ""], names);
+}
+
+#[test]
+pub fn test_attributes_and_text() {
+    let xml = "<root attr1=\"value1\" attr2='value2'>text<a/>tail<?target value?></root>";
+    let doc = Document::parse(xml).unwrap();
+    let root = doc.root_element();
+    assert_eq!(Some("value1"), root.attribute("attr1"));
+    assert_eq!(Some("value2"), root.attribute("attr2"));
+    assert_eq!(None, root.attribute("attr3"));
+    assert_eq!(2, root.attributes().count());
+    assert_eq!(Some("text"), root.text());
+
+    let a = root.children().find(|n| n.is_element()).unwrap();
+    assert_eq!(None, a.text());
+    assert_eq!(Some("tail"), a.tail());
+
+    let pi = root.last_child().unwrap();
+    assert!(pi.is_pi());
+    assert_eq!("target", pi.tag_name());
+    assert_eq!(Some("value"), pi.text());
+}
+
+#[test]
+pub fn test_decoded_attributes_and_text() {
+    let doc = Document::parse("<a v='1 &lt; 2'>x &amp; y&#65;<b><![CDATA[&amp;]]></b><!--&amp;--></a>").unwrap();
+    let a = doc.root_element();
+    assert_eq!(Some("1 &lt; 2"), a.attribute("v"));
+    assert_eq!(Some("1 < 2"), a.attribute_decoded("v").as_deref());
+    assert_eq!(None, a.attribute_decoded("w"));
+    assert_eq!(Some("x &amp; y&#65;"), a.text());
+    assert_eq!(Some("x & yA"), a.text_decoded().as_deref());
+    let b = a.first_element_child().unwrap();
+    assert_eq!(Some("&amp;"), b.text_decoded().as_deref());
+    assert_eq!(Some("&amp;"), a.last_child().unwrap().text_decoded().as_deref());
+}
+
+#[test]
+pub fn test_non_matching_tags() {
+    let xml = "<root><a></b></root>";
+    let actual_err = Document::parse(xml).unwrap_err();
+    assert!(matches!(actual_err, NonMatchingTags{ .. })); // assert error type
+    match actual_err {
+        NonMatchingTags { expected_name, found_name, matching_start_tag, .. } => {
+            assert_eq!(("a", "b", None), (expected_name.as_str(), found_name.as_str(), matching_start_tag));
+        }
+        _ => unreachable!(),
+    }
+
+    // the end tag of b is missing
+    match Document::parse("<root>\n<a>\n<b>\n</a></root>").unwrap_err() {
+        NonMatchingTags { expected_name, found_name, matching_start_tag, .. } => {
+            assert_eq!(("b", "a"), (expected_name.as_str(), found_name.as_str()));
+            assert_eq!(Some(XmlErrorPos { row: 2, col: 2 }), matching_start_tag);
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+pub fn test_end_of_file() {
+    let construct_of = |xml: &str| match Document::parse(xml).unwrap_err() {
+        UnexpectedEndOfFile { construct, .. } => construct,
+        e => panic!("unexpected error {:?}", e),
+    };
+    assert_eq!("start tag", construct_of("<root><a"));
+    assert_eq!("attribute", construct_of("<root a=\"1"));
+    assert_eq!("comment", construct_of("<root><!-- c"));
+    assert_eq!("CDATA section", construct_of("<root><![CDATA[c"));
+    assert_eq!("document type declaration", construct_of("<!DOCTYPE root ["));
+    assert_eq!("element", construct_of("<root><a/>"));
+    assert_eq!("document", construct_of("<!-- c -->"));
+
+    // the end of the input
+    assert!(matches!(
+        Document::parse("<root>\n<a>").unwrap_err(),
+        UnexpectedEndOfFile { pos: XmlErrorPos { row: 2, col: 4 }, .. }
+    ));
+
+    // the start and name of the construct
+    let start_of = |xml: &str| match Document::parse(xml).unwrap_err() {
+        UnexpectedEndOfFile { start, name, .. } => (start.map(|pos| (pos.row, pos.col)), name),
+        e => panic!("unexpected error {:?}", e),
+    };
+    assert_eq!((Some((3, 1)), Some("b".to_string())), start_of("<root>\n<a>\n<b>\n"));
+    assert_eq!((Some((2, 1)), Some("a".to_string())), start_of("<root>\n<a><b/>"));
+    assert_eq!((Some((1, 7)), Some("a".to_string())), start_of("<root><a x='1'"));
+    assert_eq!((Some((1, 13)), Some("x".to_string())), start_of("<root a='1' x='1"));
+    assert_eq!((Some((2, 1)), None), start_of("<root>\n<!-- c"));
+    assert_eq!((Some((1, 7)), None), start_of("<root><![CDATA[c"));
+    assert_eq!((Some((1, 7)), Some("pi".to_string())), start_of("<root><?pi x"));
+    assert_eq!((Some((1, 7)), Some("root".to_string())), start_of("<root></root"));
+    assert_eq!((None, None), start_of("<!-- c -->"));
+}
+
+#[test]
+pub fn test_error_context() {
+    let context_of = |xml: &str| Document::parse(xml).unwrap_err().context(xml);
+    assert_eq!(vec!["a", "b", "c"], context_of("<a>\n  <b>\n    <c x=\"1\" x=\"2\"/>\n  </b>\n</a>"));
+    assert_eq!(vec!["a", "b"], context_of("<a><b><c/>&unknown;</b></a>"));
+    assert_eq!(vec!["a", "b"], context_of("<a>\n<b>\n</c></b></a>"));
+    assert_eq!(vec!["a"], context_of("<a><b/>"));
+    assert!(context_of("<a></a><b/>").is_empty());
+}
+
+#[test]
+pub fn test_elements_named() {
+    let xml = "<table xmlns:x=\"urn:x\"><row n=\"1\"><cell/><row n=\"nested\"/></row>text<row n=\"2\"/><x:row n=\"3\"/></table>";
+    let doc = Document::parse(xml).unwrap();
+    fn numbers<'a>(nodes: impl Iterator<Item=Node<'a, 'a>>) -> Vec<&'a str> {
+        nodes.map(|n| n.attribute("n").unwrap()).collect()
+    }
+    assert_eq!(vec!["1", "nested", "2"], numbers(doc.elements_named("row")));
+    assert_eq!(vec!["3"], numbers(doc.elements_named("x:row")));
+    assert_eq!(vec!["3"], numbers(doc.elements_named_ns("urn:x", "row")));
+    assert_eq!(vec!["1", "nested", "2"], numbers(doc.elements_named_ns("", "row")));
+
+    let table = doc.root_element();
+    assert_eq!(vec!["1", "2"], numbers(table.children_named("row")));
+    assert_eq!(vec!["3"], numbers(table.children_named_ns("urn:x", "row")));
+    assert_eq!(0, table.children_named("cell").count());
+}
+
+#[test]
+pub fn test_element_children() {
+    let doc = Document::parse("<root>text<a/><!--c--><b/>tail<c/></root>").unwrap();
+    let root = doc.root_element();
+    assert_eq!(6, root.child_count());
+    assert_eq!("a", root.first_element_child().unwrap().tag_name());
+    assert_eq!("c", root.last_element_child().unwrap().tag_name());
+    assert_eq!("b", root.nth_element_child(1).unwrap().tag_name());
+    assert!(root.nth_element_child(3).is_none());
+
+    let a = root.first_element_child().unwrap();
+    assert_eq!(0, a.child_count());
+    assert!(a.first_element_child().is_none());
+    assert!(a.last_element_child().is_none());
+}
+
+#[test]
+pub fn test_paths() {
+    let xml = "<root><item>a</item><!--c--><x:item xmlns:x=\"urn:x\"/><item><name>n</name><![CDATA[d]]>text<?pi?></item></root>";
+    let doc = Document::parse(xml).unwrap();
+    let paths: Vec<String> = doc.descendants().map(|n| n.path()).collect();
+    assert_eq!(vec![
+        "/",
+        "/root[1]",
+        "/root[1]/item[1]",
+        "/root[1]/item[1]/text()[1]",
+        "/root[1]/comment()[1]",
+        "/root[1]/x:item[1]",
+        "/root[1]/item[2]",
+        "/root[1]/item[2]/name[1]",
+        "/root[1]/item[2]/name[1]/text()[1]",
+        "/root[1]/item[2]/text()[1]",
+        "/root[1]/item[2]/text()[2]",
+        "/root[1]/item[2]/processing-instruction()[1]",
+    ], paths);
+    // paths resolve in another parse of the same document
+    let other = Document::parse(xml).unwrap();
+    for node in doc.descendants() {
+        assert_eq!(node.id(), other.resolve_path(&node.path()).unwrap().id());
+    }
+    assert_eq!(Some("n"), other.resolve_path("/root/item[2]/name").and_then(|n| n.first_child()).and_then(|n| n.text()));
+    for invalid in ["", "root[1]", "/root[2]", "/root[0]", "/root[x]", "/root[1]/item[3]"] {
+        assert!(other.resolve_path(invalid).is_none(), "{}", invalid);
+    }
+}
+
+#[test]
+pub fn test_xpointer() {
+    let xml = "<book><intro xml:id=\"intro\"><p>a</p>text<p>b</p></intro><chapter id=\"c1\"><p>c</p></chapter></book>";
+    let doc = Document::parse(xml).unwrap();
+    let text = |pointer: &str| doc.resolve_xpointer(pointer).and_then(|n| n.first_child()).and_then(|n| n.text());
+    assert_eq!(Some("intro"), doc.resolve_xpointer("intro").map(|n| n.tag_name()));
+    assert_eq!(Some("b"), text("element(intro/2)"));
+    assert_eq!(Some("c"), text("element(/1/2/1)"));
+    assert_eq!(Some("book"), doc.resolve_xpointer("element(/1)").map(|n| n.tag_name()));
+    // the first part that identifies an element wins, unknown schemes are skipped
+    assert_eq!(Some("a"), text("xmlns(x=urn:x) element(/1/3) element(intro/1)"));
+    assert_eq!(Some("a"), text("foo(^(^)) element(intro/1)"));
+    // only xml:id is an ID without a DTD
+    for unresolved in ["c1", "element(c1/1)", "element(/)", "element(/2)", "element(/1/0)", "element(intro/x)", "element(intro/1", "foo(^x)"] {
+        assert!(doc.resolve_xpointer(unresolved).is_none(), "{}", unresolved);
+    }
+}
+
+#[test]
+pub fn test_language() {
+    let xml = "<root xml:lang=\"en\"><p>text</p><p xml:lang=\"de\">Text<b>fett</b></p><p xml:lang=\"\">none</p></root>";
+    let doc = Document::parse(xml).unwrap();
+    let languages: Vec<_> = doc.descendants().filter(|n| n.is_text()).map(|n| n.language()).collect();
+    assert_eq!(vec![Some("en"), Some("de"), Some("de"), None], languages);
+    assert_eq!(None, doc.root().language());
+}
+
+#[test]
+pub fn test_internal_subset_and_brackets() {
+    let xml = "<!DOCTYPE a [\n<!ELEMENT a (#PCDATA)>\n<!ATTLIST a x CDATA '>'>\n<!-- comment -->\n]>\n<a>[1] and ]]</a>";
+    let doc = Document::parse(xml).unwrap();
+    assert_eq!(Some("[1] and ]]"), doc.root_element().text());
+    assert!(Document::parse("<!DOCTYPE a [ x ]><a/>").is_err());
+}
+
+#[test]
+pub fn test_doctype_policies() {
+    let xml = "<!DOCTYPE a SYSTEM \"a>.dtd\" [<!-- ]> --><!ENTITY e '>]'>]><a/>";
+    assert!(Document::parse(xml).is_ok());
+    let error = ParseOptions { doctype: DoctypePolicy::Error, ..Default::default() };
+    assert!(matches!(Document::parse_with_options(xml, &error), Err(DisallowedConstruct { .. })));
+    assert!(matches!(Document::parse_with_options(xml, &ParseOptions::untrusted()), Err(DisallowedConstruct { .. })));
+    assert!(Document::parse_with_options("<a/>", &ParseOptions::untrusted()).is_ok());
+
+    // the invalid internal subset is skipped, and there is no warning about the declaration
+    let ignore = ParseOptions { doctype: DoctypePolicy::Ignore, ..Default::default() };
+    let (_, warnings) = Document::parse_with_warnings("<!DOCTYPE a [ x ]><a/>", &ignore).unwrap();
+    assert!(warnings.is_empty());
+    assert!(Document::parse_with_options(xml, &ignore).is_ok());
+    assert!(Document::parse_with_options("<!DOCTYPE a [ <a/>", &ignore).is_err());
+}
+
+#[test]
+pub fn test_doctype() {
+    let xml = "<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Strict//EN\" \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd\" [ <!ENTITY e 'e'> ]><html/>";
+    let doc = Document::parse(xml).unwrap();
+    let doctype = doc.doctype().unwrap();
+    assert_eq!("html", doctype.name);
+    assert_eq!(Some("-//W3C//DTD XHTML 1.0 Strict//EN"), doctype.public_id.as_deref());
+    assert_eq!(Some("http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"), doctype.system_id.as_deref());
+    assert_eq!(Some(" <!ENTITY e 'e'> "), doctype.internal_subset_span.clone().map(|span| &doc.input_text()[span]));
+    assert!(doctype.matches("-//W3C//DTD XHTML 1.0"));
+    assert!(!doctype.matches("-//W3C//DTD HTML"));
+    assert!(!doctype.is_html5());
+
+    for html5 in ["<!DOCTYPE html><html/>", "<!DOCTYPE HTML SYSTEM 'about:legacy-compat'><HTML/>"] {
+        let doc = Document::parse(html5).unwrap();
+        assert!(doc.doctype().unwrap().is_html5(), "{}", html5);
+        assert_eq!(None, doc.doctype().unwrap().internal_subset_span);
+    }
+    let doc = Document::parse("<!DOCTYPE a SYSTEM 'a.dtd' []><a/>").unwrap();
+    assert_eq!(Some(28..28), doc.doctype().unwrap().internal_subset_span);
+    assert!(!doc.doctype().unwrap().matches(""));
+
+    assert_eq!(None, Document::parse("<a/>").unwrap().doctype());
+    let ignore = ParseOptions { doctype: DoctypePolicy::Ignore, ..Default::default() };
+    assert_eq!(None, Document::parse_with_options(xml, &ignore).unwrap().doctype());
+}
+
+#[test]
+pub fn test_skip_large_doctype() {
+    let ignore = ParseOptions { doctype: DoctypePolicy::Ignore, ..Default::default() };
+    let subset = "<!ENTITY e \"]>\"><?pi ]> ?><!-- '>' -->\n".repeat(10_000);
+    let xml = format!("<!DOCTYPE a PUBLIC \"-//A//EN\" 'a.dtd' [{}]><!-- after --><a/>", subset);
+    let doc = Document::parse_with_options(&xml, &ignore).unwrap();
+    assert_eq!("a", doc.root_element().tag_name());
+    assert_eq!(2, doc.root().children().count());
+    assert!(matches!(Document::parse_with_options("<!DOCTYPE a [ '>' ", &ignore), Err(UnexpectedEndOfFile { .. })));
+}
+
+#[test]
+pub fn test_max_lookahead() {
+    let limited = ParseOptions { max_lookahead: Some(16), ..Default::default() };
+    assert!(Document::parse_with_options("<a><!-- short --><?pi short?><![CDATA[short]]></a>", &limited).is_ok());
+    assert!(matches!(
+        Document::parse_with_options("<a><!-- a comment that is too long --></a>", &limited),
+        Err(LimitExceeded { max: 16, .. })
+    ));
+    assert!(matches!(Document::parse_with_options("<a><?pi a value that is too long?></a>", &limited), Err(LimitExceeded { .. })));
+    assert!(matches!(Document::parse_with_options("<a><![CDATA[a section that is too long]]></a>", &limited), Err(LimitExceeded { .. })));
+
+    let ignore = ParseOptions { doctype: DoctypePolicy::Ignore, ..limited };
+    assert!(Document::parse_with_options("<!DOCTYPE a []><a/>", &ignore).is_ok());
+    assert!(matches!(Document::parse_with_options("<!DOCTYPE a [<!ENTITY e 'e'>]><a/>", &ignore), Err(LimitExceeded { .. })));
+}
+
+#[test]
+pub fn test_warnings() {
+    let xml = "<!DOCTYPE a>\n<a xmlAttr=\"\" xml:lang=\"en\" xmlns:x=\"urn:x\"><XMLish>\u{85}ok\u{7f}</XMLish><!--\u{fdd0}--></a>";
+    let (doc, warnings) = Document::parse_with_warnings(xml, &ParseOptions::default()).unwrap();
+    assert_eq!("a", doc.root_element().tag_name());
+    assert_eq!(vec![
+        XmlWarning::DoctypeIgnored { pos: XmlErrorPos { row: 1, col: 11 } },
+        XmlWarning::ReservedName { pos: XmlErrorPos { row: 2, col: 4 }, name: "xmlAttr".to_string() },
+        XmlWarning::EmptyAttributeValue { pos: XmlErrorPos { row: 2, col: 4 }, name: "xmlAttr".to_string() },
+        XmlWarning::ReservedName { pos: XmlErrorPos { row: 2, col: 46 }, name: "XMLish".to_string() },
+        XmlWarning::DiscouragedCharacter { pos: XmlErrorPos { row: 2, col: 56 }, character: '\u{7f}' },
+        XmlWarning::DiscouragedCharacter { pos: XmlErrorPos { row: 2, col: 70 }, character: '\u{fdd0}' },
+    ], warnings);
+    assert!(Document::parse_with_warnings("<a><b/></a>", &ParseOptions::default()).unwrap().1.is_empty());
+}
+
+#[test]
+pub fn test_multibyte_names() {
+    for xml in ["<abé/>", "<a abé='1'/>", "<é/>", "<xé xé='1'/>", "<a><€b/></a>"] {
+        let (_, warnings) = Document::parse_with_warnings(xml, &ParseOptions::default()).unwrap();
+        assert!(warnings.is_empty(), "{}", xml);
+    }
+    let (_, warnings) = Document::parse_with_warnings("<xmlé xmlé='1'/>", &ParseOptions::default()).unwrap();
+    let names: Vec<_> = warnings.iter().filter_map(|w| match w {
+        XmlWarning::ReservedName { name, .. } => Some(name.as_str()),
+        _ => None,
+    }).collect();
+    assert_eq!(vec!["xmlé", "xmlé"], names);
+}
+
+#[test]
+pub fn test_comment_and_pi_policies() {
+    let xml = "<a><!-- a -- b ---></a>";
+    assert!(Document::parse(xml).is_err());
+    let relaxed = ParseOptions { comments: CommentPolicy::AllowDoubleHyphen, ..Default::default() };
+    let doc = Document::parse_with_options(xml, &relaxed).unwrap();
+    assert_eq!(Some(" a -- b -"), doc.root_element().first_child().unwrap().text());
+
+    let no_comments = ParseOptions { comments: CommentPolicy::Deny, ..Default::default() };
+    assert!(matches!(Document::parse_with_options("<!--c--><a/>", &no_comments), Err(DisallowedConstruct { .. })));
+    let no_pis = ParseOptions { processing_instructions: ProcessingInstructionPolicy::Deny, ..Default::default() };
+    assert!(matches!(Document::parse_with_options("<a><?pi?></a>", &no_pis), Err(DisallowedConstruct { .. })));
+    assert!(Document::parse_with_options("<a><!--c--></a>", &no_pis).is_ok());
+}
+
+#[test]
+pub fn test_attribute_map() {
+    let mut doc = Document::parse("<a z=\"1\" y=\"2\" x=\"&amp;\"/>").unwrap();
+    let map = doc.root_element().attribute_map();
+    assert_eq!(3, map.len());
+    assert_eq!(Some(1), map.index_of("y"));
+    assert_eq!("x", map[2].name());
+    assert_eq!(Some("&amp;"), map.get("x").map(|a| a.value()));
+    assert!(map.get_index(3).is_none());
+
+    let id = doc.root_element().id();
+    let mut element = doc.node_mut(id).unwrap();
+    let mut attributes = element.attributes_mut().unwrap();
+    assert_eq!(Some("1"), attributes.insert("z", "<3").as_deref());
+    assert_eq!(None, attributes.insert("w", "4"));
+    assert_eq!("y", attributes.remove("y").unwrap().name());
+    assert_eq!("x", attributes.remove_index(1).unwrap().name());
+    assert!(attributes.remove_index(2).is_none());
+    assert_eq!("<a z=\"&lt;3\" w=\"4\"/>", serialize(doc.root()));
+    let root = doc.root().id();
+    assert!(doc.node_mut(root).unwrap().attributes_mut().is_none());
+}
+
+#[test]
+pub fn test_xml_declaration() {
+    let doc = Document::parse("<?xml version=\"1.0\" encoding='UTF-8' standalone=\"no\"?><a/>").unwrap();
+    let decl = doc.xml_declaration().unwrap();
+    assert_eq!(XmlDecl { version: XmlVersion::V1_0, encoding: Some("UTF-8".to_string()), standalone: Some(false) }, *decl);
+    let doc = Document::parse("<?xml version='1.17'?><a/>").unwrap();
+    assert_eq!(XmlVersion::Other("1.17".to_string()), doc.xml_declaration().unwrap().version);
+    assert_eq!("1.17", doc.xml_declaration().unwrap().version.as_str());
+    assert!(Document::parse("<a/>").unwrap().xml_declaration().is_none());
+
+    assert!(Document::parse("<?xml version=\"1.\"?><a/>").is_err());
+    assert!(Document::parse("<?xml version=\"2.0\"?><a/>").is_err());
+    assert!(Document::parse("<?xml version=\"1.0\" standalone=\"maybe\"?><a/>").is_err());
+    assert!(Document::parse("<?xml version=\"1.0\" encoding=\"8bit\"?><a/>").is_err());
+}
+
+#[test]
+pub fn test_standalone() {
+    let options = ParseOptions { entities: EntityTable::html(), ..Default::default() };
+    assert!(Document::parse_with_options("<?xml version=\"1.0\" standalone='no'?><a>&nbsp;</a>", &options).is_ok());
+    let standalone = "<?xml version=\"1.0\" standalone='yes'?><a>&nbsp;</a>";
+    assert!(matches!(Document::parse_with_options(standalone, &options), Err(UnknownReference { .. })));
+    // the flag does not outlive the document
+    let mut tokenizer = XmlTokenizer::with_options(&options);
+    assert!(tokenizer.tokenize(standalone).is_err());
+    assert!(tokenizer.tokenize("<a>&nbsp;</a>").is_ok());
+
+    let xml = "<?xml version=\"1.0\" standalone=\"yes\"?>\n<!DOCTYPE a SYSTEM \"a.dtd\">\n<a/>";
+    let (_, warnings) = Document::parse_with_warnings(xml, &ParseOptions::default()).unwrap();
+    assert_eq!(Some(&XmlWarning::StandaloneUnverified { pos: XmlErrorPos { row: 2, col: 11 } }), warnings.last());
+    let xml = "<?xml version=\"1.0\" standalone=\"yes\"?><!DOCTYPE a [%p; %q;]><a/>";
+    let (_, warnings) = Document::parse_with_warnings(xml, &ParseOptions::default()).unwrap();
+    assert_eq!(1, warnings.iter().filter(|w| matches!(w, XmlWarning::StandaloneUnverified { .. })).count());
+    let (_, warnings) = Document::parse_with_warnings("<!DOCTYPE a SYSTEM \"a.dtd\"><a/>", &ParseOptions::default()).unwrap();
+    assert!(!warnings.iter().any(|w| matches!(w, XmlWarning::StandaloneUnverified { .. })));
+}
+
+#[test]
+pub fn test_content_model() {
+    let xml = "<r><e/><t>text</t><c><![CDATA[ ]]></c><o>\n  <a/><!--c--> <b/>\n</o><m>a <b/> c</m><p><?pi?></p></r>";
+    let doc = Document::parse(xml).unwrap();
+    let models: Vec<_> = doc.root_element().child_elements().map(|e| e.content_model()).collect();
+    assert_eq!(vec![Empty, TextOnly, TextOnly, ElementOnly, Mixed, Empty], models);
+    let root = doc.root_element();
+    assert_eq!(6, root.child_elements().count());
+    assert_eq!(Some("p"), root.child_elements().next_back().map(|e| e.tag_name()));
+    assert!(root.child_elements().nth(1).unwrap().is_text_only());
+    assert!(root.child_elements().nth(4).unwrap().has_mixed_content());
+    assert!(!root.has_mixed_content());
+    assert_eq!(Empty, root.child_elements().nth(1).unwrap().first_child().unwrap().content_model());
+}
+
+#[test]
+pub fn test_name_eq() {
+    let doc = Document::parse("<ns:item1 id=\"a\" ns:id=\"b\" xmlns:ns=\"urn:ns\"><ns:item2/></ns:item1>").unwrap();
+    let root = doc.root_element();
+    assert!(root.name_eq("ns:item1"));
+    assert!(!root.name_eq("ns:item2"));
+    assert!(!root.name_eq("ns:item"));
+    assert!(!doc.root().name_eq("ns:item1"));
+    let attributes: Vec<_> = root.attributes().collect();
+    assert!(attributes[0].name_eq("id"));
+    assert!(!attributes[1].name_eq("id"));
+    assert_eq!(Some("b"), root.attribute("ns:id"));
+
+    let tokens = XmlTokenizer::default().tokenize("<a><bc/></a>").unwrap();
+    let names: Vec<_> = tokens.iter().filter_map(|t| match t { XmlToken::StartTag(range) => Some(range), _ => None }).collect();
+    assert!(names[0].name_eq("a"));
+    assert!(names[1].name_eq("bc"));
+    assert!(!names[1].name_eq("bd"));
+}
+
+#[test]
+pub fn test_sort_child_elements() {
+    let xml = "<list>\n  <c id='2'><k>b</k></c>\n  <!--first-->\n  <a id='10'><k>&#x61;</k></a>\n  <b><k>c</k><list><z/><y/></list></b>\n</list>";
+    let sorted = |key: SortKey, deep: bool| {
+        let mut doc = Document::parse(xml).unwrap();
+        let id = doc.root_element().id();
+        let mut list = doc.node_mut(id).unwrap();
+        if deep {
+            list.sort_descendant_elements(&key);
+        } else {
+            list.sort_child_elements(&key);
+        }
+        let names: Vec<_> = doc.descendants().filter(|n| n.is_element() && n.tag_name() != "k").map(|n| n.tag_name()).collect();
+        (names.join(" "), serialize(doc.root()))
+    };
+    assert_eq!("list a b list z y c", sorted(SortKey::TagName, false).0);
+    assert_eq!("list a b list y z c", sorted(SortKey::TagName, true).0);
+    // compared as strings, without the attribute first
+    assert_eq!("list b list z y a c", sorted(SortKey::Attribute("id".to_string()), false).0);
+    assert_eq!("list a c b list z y", sorted(SortKey::ChildText("k".to_string()), false).0);
+
+    // elements swap positions, other nodes stay in place
+    let expected = "<list>\n  <a id='10'><k>&#x61;</k></a>\n  <!--first-->\n  <b><k>c</k><list><z/><y/></list></b>\n  <c id='2'><k>b</k></c>\n</list>";
+    assert_eq!(expected.replace('\'', "\""), sorted(SortKey::TagName, false).1);
+
+    // a sorted document is left unchanged
+    let mut doc = Document::parse("<a><b/><c/></a>").unwrap();
+    let id = doc.root_element().id();
+    doc.node_mut(id).unwrap().sort_child_elements(&SortKey::TagName);
+    assert_eq!(Some("<a><b/><c/></a>"), doc.root_element().original_markup());
+}
+
+#[test]
+pub fn test_duplicate_child_elements() {
+    let xml = "<feed>\n  <item id='1'><t>a</t></item>\n  <item id='2'><t>a</t></item>\n  <item id='1'><t>a</t></item>\n  <item id='1'><t>a</t><!--c--></item>\n  <item id='1'><t>a</t></item>\n</feed>";
+    let other = Document::parse("<item id='1'><t>a</t></item>").unwrap();
+    let mut doc = Document::parse(xml).unwrap();
+    let items: Vec<_> = doc.root_element().child_elements().collect();
+    assert_eq!(items[0].subtree_hash(), items[2].subtree_hash());
+    assert_ne!(items[0].subtree_hash(), items[1].subtree_hash());
+    assert_ne!(items[0].subtree_hash(), items[3].subtree_hash());
+    assert!(items[0].subtree_eq(&other.root_element()));
+    assert_eq!(other.root_element().subtree_hash(), items[0].subtree_hash());
+    assert!(!items[0].subtree_eq(&items[3]));
+
+    let duplicates: Vec<_> = doc.root_element().duplicate_child_elements().iter().map(|(d, f)| (d.id(), f.id())).collect();
+    assert_eq!(vec![(items[2].id(), items[0].id()), (items[4].id(), items[0].id())], duplicates);
+
+    let id = doc.root_element().id();
+    assert_eq!(2, doc.node_mut(id).unwrap().remove_duplicate_child_elements());
+    let expected = "<feed>\n  <item id=\"1\"><t>a</t></item>\n  <item id=\"2\"><t>a</t></item>\n  <item id=\"1\"><t>a</t><!--c--></item>\n</feed>";
+    assert_eq!(expected, serialize(doc.root()));
+    assert_eq!(0, doc.node_mut(id).unwrap().remove_duplicate_child_elements());
+}
+
+#[test]
+pub fn test_siblings_after_edits() {
+    // walking the siblings in both directions visits the children in order
+    let check = |doc: &Document| {
+        for node in doc.descendants() {
+            let children: Vec<_> = node.children().collect();
+            let forward: Vec<_> = std::iter::successors(node.first_child(), |c| c.next_sibling()).collect();
+            let mut backward: Vec<_> = std::iter::successors(node.last_child(), |c| c.prev_sibling()).collect();
+            backward.reverse();
+            assert_eq!(children, forward);
+            assert_eq!(children, backward);
+        }
+    };
+    let mut doc = Document::parse("<r><c/>t<a/><!--x--><b><y/><z/></b></r>").unwrap();
+    let fragment = Document::parse("<n><m/>text</n>").unwrap();
+    let id = doc.root_element().id();
+    let a = doc.root_element().nth_element_child(1).unwrap().id();
+    doc.node_mut(id).unwrap().sort_descendant_elements(&SortKey::TagName);
+    check(&doc);
+    doc.node_mut(id).unwrap().insert_subtree(1, fragment.root()).unwrap();
+    check(&doc);
+    doc.node_mut(a).unwrap().detach();
+    check(&doc);
+    assert_eq!("<r><n><m/>text</n>t<b><y/><z/></b><!--x--><c/></r>", serialize(doc.root()));
+    assert!(doc.get_node(a).unwrap().prev_sibling().is_none());
+}
+
+#[test]
+pub fn test_parse_sample() {
+    let xml = "<feed><title>t</title>\n<entry id='1'><a/></entry>\n<entry id='2'/>\n<entry id='3'><b>unclosed</entry>";
+    let sample = SampleOptions { record_path: "/feed/entry".to_string(), max_records: 2 };
+    let (doc, is_truncated) = Document::parse_sample(xml, &ParseOptions::default(), &sample).unwrap();
+    assert!(is_truncated);
+    assert_eq!("<feed><title>t</title>\n<entry id=\"1\"><a/></entry>\n<entry id=\"2\"/>\n</feed>", serialize(doc.root()));
+    assert_eq!(None, doc.root_element().original_markup());
+    let entries: Vec<_> = doc.root_element().child_elements().skip(1).collect();
+    assert_eq!(Some("<entry id='2'/>"), entries[1].original_markup());
+
+    // the records end within the limit
+    let sample = SampleOptions { record_path: "feed/entry".to_string(), max_records: 3 };
+    let xml = "<feed><entry/><x><entry/></x></feed>";
+    let (doc, is_truncated) = Document::parse_sample(xml, &ParseOptions::default(), &sample).unwrap();
+    assert!(!is_truncated);
+    assert_eq!(Some(xml), doc.root_element().original_markup());
+
+    // nested ancestors are closed
+    let sample = SampleOptions { record_path: "/a/b/c".to_string(), max_records: 1 };
+    let (doc, is_truncated) = Document::parse_sample("<a><b><c>1</c><c>2</c></b></a>", &ParseOptions::default(), &sample).unwrap();
+    assert!(is_truncated);
+    assert_eq!("<a><b><c>1</c></b></a>", serialize(doc.root()));
+
+    // errors before the cut are reported
+    assert!(Document::parse_sample("<feed><entry></feed>", &ParseOptions::default(), &sample).is_err());
+}
+
+#[test]
+pub fn test_byte_order_mark() {
+    let xml = "\u{feff}<?xml version='1.0'?><a>text</a>";
+    let doc = Document::parse(xml).unwrap();
+    assert_eq!(Some(XmlVersion::V1_0), doc.xml_declaration().map(|d| d.version.clone()));
+    assert_eq!(Some("<a>text</a>"), doc.root_element().original_markup());
+    assert_eq!("<a>text</a>", serialize(doc.root()));
+    assert!(Document::parse("\u{feff}<a/>").is_ok());
+    let options = ParseOptions { strictness: Strictness::TagSoup, ..Default::default() };
+    assert_eq!("<a>text</a>", serialize(Document::parse_with_options("\u{feff}<a>text</a>", &options).unwrap().root()));
+
+    // leading garbage
+    assert!(matches!(Document::parse("HTTP/1.1 200 OK\n\n<a/>"), Err(LeadingGarbage { pos, len: 17 }) if pos.row == 1 && pos.col == 1));
+    assert!(matches!(Document::parse("\u{feff}\n  }<a/>"), Err(LeadingGarbage { pos, len: 1 }) if pos.row == 2 && pos.col == 3));
+    assert!(matches!(Document::parse("\u{feff}\u{feff}<a/>"), Err(LeadingGarbage { len: 3, .. })));
+    assert!(matches!(Document::parse("junk"), Err(LeadingGarbage { len: 4, .. })));
+}
+
+#[test]
+pub fn test_tree_display() {
+    let xml = "<?pi?>\n<root a='1'>\n<b>0123456789012345678901234567890123456789 and more</b><![CDATA[x]]></root>";
+    let mut doc = Document::parse(xml).unwrap();
+    let expected = "Document\n\
+        \x20 ProcessingInstruction pi @1:3\n\
+        \x20 Element root @2:2\n\
+        \x20   Attribute a=\"1\" @2:7\n\
+        \x20   Text \"\\n\" @2:13\n\
+        \x20   Element b @3:2\n\
+        \x20     Text \"0123456789012345678901234567890123456789\"... @3:4\n\
+        \x20   CdataSection \"x\" @3:66\n";
+    assert_eq!(expected, doc.to_string());
+    assert_eq!(expected, format!("{:#?}", doc));
+    assert!(format!("{:?}", doc).starts_with("Document { root: Element { name: \"root\""));
+
+    // edited nodes are not part of the input
+    let b = doc.root_element().first_element_child().unwrap().id();
+    doc.node_mut(b).unwrap().set_attribute("c", "2");
+    assert!(doc.root_element().to_string().contains("  Element b @3:2\n    Attribute c=\"2\"\n"));
+}
@@ -0,0 +1,31 @@
+use jaxp_rust::dump::dump_tokens;
+use jaxp_rust::options::ParseOptions;
+
+#[test]
+pub fn test_dump_json() {
+    let dump = dump_tokens("<?pi v?>\n<a x=\"&lt;\">t</a>", &ParseOptions::default());
+    assert!(dump.opt_error.is_none());
+    let expected = "{\"tokens\":[\
+        {\"kind\":\"processing-instruction\",\"ranges\":[{\"field\":\"target\",\"start\":2,\"end\":4,\"row\":1,\"col\":3,\"text\":\"pi\"},\
+        {\"field\":\"value\",\"start\":5,\"end\":6,\"row\":1,\"col\":6,\"text\":\"v\"}]},\
+        {\"kind\":\"start-tag\",\"ranges\":[{\"field\":\"name\",\"start\":10,\"end\":11,\"row\":2,\"col\":2,\"text\":\"a\"}]},\
+        {\"kind\":\"attribute\",\"ranges\":[{\"field\":\"name\",\"start\":12,\"end\":13,\"row\":2,\"col\":4,\"text\":\"x\"},\
+        {\"field\":\"value\",\"start\":15,\"end\":19,\"row\":2,\"col\":7,\"text\":\"&lt;\"}]},\
+        {\"kind\":\"text\",\"ranges\":[{\"field\":\"text\",\"start\":21,\"end\":22,\"row\":2,\"col\":13,\"text\":\"t\"}]},\
+        {\"kind\":\"end-tag\",\"ranges\":[{\"field\":\"name\",\"start\":24,\"end\":25,\"row\":2,\"col\":16,\"text\":\"a\"}]}],\
+        \"error\":null}";
+    assert_eq!(expected, dump.to_json());
+}
+
+#[test]
+pub fn test_dump_table() {
+    let dump = dump_tokens("<a>x\n<!-- c -- d --></a>", &ParseOptions::default());
+    assert_eq!(2, dump.tokens.len());
+    let table = dump.to_table();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(4, lines.len());
+    assert!(lines[0].starts_with("token kind"));
+    assert_eq!("1     text                       text            3..5        1:4     \"x\\n\"", lines[2]);
+    assert!(lines[3].starts_with("error: "));
+    assert!(dump.to_json().contains("],\"error\":\""));
+}
@@ -1,6 +1,7 @@
 extern crate core;
 
 use jaxp_rust::error::XmlError::*;
+use jaxp_rust::error::XmlErrorPos;
 use jaxp_rust::node::XmlNode;
 use jaxp_rust::parse::XmlParser;
 
@@ -101,13 +102,13 @@ pub fn test_random_spaces() {
 #[test]
 pub fn test_illegal_spaces() {
     let xml = "<root><   /root>";
-    let expected_err_target = " ".to_string();
-    let actual_err = XmlParser::default().parse(&xml).unwrap_err();
+    let _expected_err_target = " ".to_string();
+    let actual_err = XmlParser::default().parse(xml).unwrap_err();
     assert!(matches!(actual_err, IllegalToken{..})); // assert error type
 
     let xml = "<\nroot></root>";
-    let expected_err_target = "\n".to_string();
-    let actual_err = XmlParser::default().parse(&xml).unwrap_err();
+    let _expected_err_target = "\n".to_string();
+    let actual_err = XmlParser::default().parse(xml).unwrap_err();
     assert!(matches!(actual_err, IllegalToken{..})); // assert error type
 }
 
@@ -140,14 +141,14 @@ pub fn test_valid_unicode_names() {
 
 #[test]
 pub fn test_invalid_unicode_names() {
-    let mut start_chars_to_test = vec!["-", ".", "$", "\u{200E}"];
+    let start_chars_to_test = vec!["-", ".", "$", "\u{200E}"];
     // name chars
-    let mut name_chars_to_test = vec!["\u{B8}"];
+    let name_chars_to_test = vec!["\u{B8}"];
 
     for start_char in start_chars_to_test {
         let name = format!("{}abc", start_char);
         let xml = format!("<{}></{}>", name, name);
-        let expected_err_target = start_char;
+        let _expected_err_target = start_char;
         let actual_err = XmlParser::default().parse(&xml).unwrap_err();
         assert!(matches!(actual_err, IllegalToken{..})); // assert error type
     }
@@ -155,7 +156,7 @@ pub fn test_invalid_unicode_names() {
     for name_char in name_chars_to_test {
         let name = format!("ab{}c", name_char);
         let xml = format!("<{}></{}>", name, name);
-        let expected_err_target = name_char;
+        let _expected_err_target = name_char;
         let actual_err = XmlParser::default().parse(&xml).unwrap_err();
         assert!(matches!(actual_err, IllegalToken{..})); // assert error type
     }
@@ -165,7 +166,15 @@ pub fn test_invalid_unicode_names() {
 pub fn test_non_matching_tags() {
     // Opening tag "a" does not match closing tag "aa"
     let xml = "<root><a></b></aa></root>";
-    let expected_err_target = "aa".to_string();
+    let _expected_err_target = "aa".to_string();
     let actual_err = XmlParser::default().parse(xml).unwrap_err();
     assert!(matches!(actual_err, NonMatchingTags{ .. })); // assert error type
 }
+
+#[test]
+pub fn test_errors() {
+    assert!(matches!(XmlParser::default().parse("<root>\n<a>"), Err(UnexpectedEndOfFile { construct, .. }) if construct == "element"));
+    // prolog tokens are not part of the tree
+    let err = XmlParser::default().parse("<!DOCTYPE root>\n<root/>").unwrap_err();
+    assert!(matches!(err, UnexpectedXmlToken { pos: XmlErrorPos { row: 1, col: 11 } }), "{:?}", err);
+}
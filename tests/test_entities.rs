@@ -0,0 +1,39 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::entities::EntityTable;
+use jaxp_rust::error::XmlError::*;
+use jaxp_rust::options::{ParseOptions, Strictness};
+use jaxp_rust::writer::serialize;
+
+#[test]
+pub fn test_html_entities() {
+    let xml = "<p title=\"caf&eacute;\">a&nbsp;b &mdash; &amp;</p>";
+    assert!(matches!(Document::parse(xml), Err(UnknownReference { .. })));
+
+    let options = ParseOptions { entities: EntityTable::html(), ..Default::default() };
+    let doc = Document::parse_with_options(xml, &options).unwrap();
+    assert_eq!(Some("a&nbsp;b &mdash; &amp;"), doc.root_element().text());
+    assert_eq!(Some("caf&eacute;"), doc.root_element().attribute("title"));
+    assert!(matches!(Document::parse_with_options("<p>&bogus;</p>", &options), Err(UnknownReference { .. })));
+}
+
+#[test]
+pub fn test_custom_entities() {
+    let mut entities = EntityTable::new();
+    assert!(entities.is_empty());
+    entities.insert("product", "jaxp");
+    let html = EntityTable::html();
+    assert_eq!(Some("\u{a0}"), html.get("nbsp"));
+    assert_eq!(Some("\u{ff}"), html.get("yuml"));
+    assert!(!html.contains("product"));
+
+    let options = ParseOptions { entities, ..Default::default() };
+    assert!(Document::parse_with_options("<a>&product;</a>", &options).is_ok());
+    assert!(matches!(Document::parse_with_options("<a>&nbsp;</a>", &options), Err(UnknownReference { .. })));
+}
+
+#[test]
+pub fn test_tag_soup_entities() {
+    let options = ParseOptions { strictness: Strictness::TagSoup, entities: EntityTable::html(), ..Default::default() };
+    let doc = Document::parse_with_options("<p a=&lt;&eacute;>&#0; &nbsp;&copy &unknown;", &options).unwrap();
+    assert_eq!("<p a=\"&lt;\u{e9}\">&amp;#0; \u{a0}&amp;copy &amp;unknown;</p>", serialize(doc.root()));
+}
@@ -0,0 +1,44 @@
+use std::borrow::Cow;
+
+use jaxp_rust::document::Document;
+use jaxp_rust::escape::{escape_attribute, escape_attribute_with, escape_text, escape_text_with, unescape, EscapeStyle};
+
+#[test]
+pub fn test_escape_text() {
+    assert!(matches!(escape_text("plain text"), Cow::Borrowed(_)));
+    assert_eq!("a &lt;b&gt; &amp; \"c\" 'd'&#xD;\n", escape_text("a <b> & \"c\" 'd'\r\n"));
+}
+
+#[test]
+pub fn test_escape_attribute() {
+    assert_eq!("&quot;a&quot; 'b' &lt;&amp;&gt;", escape_attribute("\"a\" 'b' <&>", '"'));
+    assert_eq!("\"a\" &apos;b&apos;", escape_attribute("\"a\" 'b'", '\''));
+    assert_eq!("a&#x9;b&#xA;c&#xD;", escape_attribute("a\tb\nc\r", '"'));
+}
+
+#[test]
+pub fn test_escape_styles() {
+    let text = "a > b ]]> \"c\" 'd'";
+    assert_eq!("a > b ]]&gt; \"c\" 'd'", escape_text_with(text, EscapeStyle::Minimal));
+    assert_eq!(escape_text(text), escape_text_with(text, EscapeStyle::Standard));
+    assert_eq!("a &gt; b ]]&gt; &quot;c&quot; &apos;d&apos;", escape_text_with(text, EscapeStyle::Aggressive));
+    assert_eq!("]]]&gt;", escape_text_with("]]]>", EscapeStyle::Minimal));
+    assert!(matches!(escape_text_with("a > b", EscapeStyle::Minimal), Cow::Borrowed(_)));
+
+    let value = "<a> \"b\" 'c'";
+    assert_eq!("&lt;a> &quot;b&quot; 'c'", escape_attribute_with(value, '"', EscapeStyle::Minimal));
+    assert_eq!("&lt;a&gt; \"b\" &apos;c&apos;", escape_attribute_with(value, '\'', EscapeStyle::Standard));
+    assert_eq!("&lt;a&gt; &quot;b&quot; &apos;c&apos;", escape_attribute_with(value, '\'', EscapeStyle::Aggressive));
+}
+
+#[test]
+pub fn test_round_trip() {
+    let value = "x < y && \"z\"\n\t'w' \u{e9}";
+    let xml = format!("<a v=\"{}\" w='{}'>{}</a>", escape_attribute(value, '"'), escape_attribute(value, '\''), escape_text(value));
+    let doc = Document::parse(&xml).unwrap();
+    let root = doc.root_element();
+    assert_eq!(value, unescape(root.attribute("v").unwrap()));
+    assert_eq!(value, unescape(root.attribute("w").unwrap()));
+    assert_eq!(value, unescape(root.text().unwrap()));
+    assert_eq!("&bogus; &", unescape("&bogus; &amp;"));
+}
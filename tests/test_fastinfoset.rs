@@ -0,0 +1,67 @@
+#![cfg(feature = "fastinfoset")]
+
+use jaxp_rust::fastinfoset::{decode, decode_tokens, encode, FastInfosetError, HEADER};
+use jaxp_rust::token::XmlTokenOwned;
+
+fn document(body: &[u8]) -> Vec<u8> {
+    [&HEADER[..], body].concat()
+}
+
+#[test]
+pub fn test_round_trip() {
+    let xml = "<?xml version=\"1.0\" standalone=\"yes\"?><!DOCTYPE doc SYSTEM \"doc.dtd\"><?pi a?>\
+        <doc xmlns=\"urn:d\" xmlns:p=\"urn:p\" xml:lang=\"en\"><p:item p:n=\"1\" m=\"&quot;\">a &lt; b</p:item>\
+        <!--c--><p:item p:n=\"1\"><![CDATA[<x>]]></p:item><inner xmlns=\"\"><e/></inner></doc><!--after-->";
+    let expected = "<?xml version=\"1.0\" standalone=\"yes\"?><!DOCTYPE doc SYSTEM \"doc.dtd\"><?pi a?>\
+        <doc xmlns=\"urn:d\" xmlns:p=\"urn:p\" xml:lang=\"en\"><p:item p:n=\"1\" m=\"&quot;\">a &lt; b</p:item>\
+        <!--c--><p:item p:n=\"1\">&lt;x&gt;</p:item><inner xmlns=\"\"><e></e></inner></doc><!--after-->";
+    assert_eq!(expected, decode(&encode(xml).unwrap()).unwrap());
+}
+
+#[test]
+pub fn test_vocabulary_indices() {
+    // names, values and chunks are repeated after their tables grew past the small indices
+    let mut xml = "<r>".to_string();
+    for _ in 0..2 {
+        for i in 0..2100 {
+            xml.push_str(&format!("<e{0} a{1}=\"v{2}\" b{2}=\"\">t{3}</e{0}>", i, i % 100, i % 70, i % 1100));
+        }
+    }
+    xml.push_str("</r>");
+    let fi = encode(&xml).unwrap();
+    assert!(fi.len() < xml.len() / 2);
+    assert_eq!(xml, decode(&fi).unwrap());
+}
+
+#[test]
+pub fn test_decode() {
+    // a literal name and a chunk, terminated together with the document
+    assert_eq!("<a>b</a>", decode(&document(&[0x00, 0x3C, 0x00, b'a', 0x80, b'b', 0xFF])).unwrap());
+    // the second element by the index of its name
+    let fi = document(&[0x00, 0x3C, 0x00, b'r', 0x3C, 0x00, b'a', 0xF0, 0x01, 0xFF, 0xF0]);
+    assert_eq!(fi, encode("<r><a/><a/></r>").unwrap());
+    assert_eq!("<r><a></a><a></a></r>", decode(&fi).unwrap());
+    // a chunk in UTF-16, after an XML declaration that announces the encoding
+    let fi = [&b"<?xml encoding='finf'?>"[..], &document(&[0x00, 0x3C, 0x00, b'a', 0x85, 0x00, b'b', 0xFF])].concat();
+    assert_eq!("<a>b</a>", decode(&fi).unwrap());
+
+    let tokens = decode_tokens(&encode("<a x=\"1\"><?pi?>t</a>").unwrap()).unwrap();
+    assert_eq!(5, tokens.len());
+    assert!(matches!(tokens[1], XmlTokenOwned::Attribute { .. }));
+}
+
+#[test]
+pub fn test_errors() {
+    assert!(matches!(decode(b"<a/>"), Err(FastInfosetError::InvalidHeader)));
+    assert!(matches!(decode(&document(&[0x00, 0x3C, 0x00])), Err(FastInfosetError::UnexpectedEnd)));
+    assert!(matches!(decode(&document(&[0x00, 0x05])), Err(FastInfosetError::InvalidIndex { pos: 5, index: 6 })));
+    assert!(matches!(decode(&document(&[0x00, 0x3C, 0x00, b' ', 0xF0])), Err(FastInfosetError::InvalidString { pos: 6 })));
+    assert!(matches!(decode(&document(&[0x00, 0x3C, 0x00, b'a', 0xF0, 0xF0, 0xF0])), Err(FastInfosetError::IllegalOctet { pos: 10, .. })));
+    assert!(matches!(decode(&document(&[0x20])), Err(FastInfosetError::Unsupported(_))));
+    // a chunk in a restricted alphabet
+    assert!(matches!(decode(&document(&[0x00, 0x3C, 0x00, b'a', 0x88, 0x00])), Err(FastInfosetError::Unsupported(_))));
+
+    assert!(matches!(encode("<p:a/>"), Err(FastInfosetError::UndeclaredPrefix(prefix)) if prefix == "p"));
+    assert!(matches!(encode("<a><b></a>"), Err(FastInfosetError::UnbalancedTags(_))));
+    assert!(matches!(encode("<a"), Err(FastInfosetError::Xml(_))));
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "feeds")]
+
+use jaxp_rust::feeds::{parse, DateTime, FeedError, FeedKind, Link};
+
+const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:dc="http://purl.org/dc/elements/1.1/"
+     xmlns:atom="http://www.w3.org/2005/Atom">
+  <channel>
+    <title>Liftoff News</title>
+    <link>http://liftoff.msfc.nasa.gov/</link>
+    <description>Liftoff to Space Exploration.</description>
+    <atom:link href="http://liftoff.msfc.nasa.gov/rss.xml" rel="self" type="application/rss+xml"/>
+    <lastBuildDate>Tue, 10 Jun 2003 09:41:01 GMT</lastBuildDate>
+    <managingEditor>editor@example.com</managingEditor>
+    <docs>http://blogs.law.harvard.edu/tech/rss</docs>
+    <item>
+      <title>Star City</title>
+      <link>http://liftoff.msfc.nasa.gov/news/2003/news-starcity.asp</link>
+      <description>How do Americans get ready to work with Russians aboard the &lt;i&gt;ISS&lt;/i&gt;?</description>
+      <content:encoded><![CDATA[<p>How do <b>Americans</b> get ready?</p>]]></content:encoded>
+      <pubDate>Tue, 03 Jun 2003 09:39:21 +0200</pubDate>
+      <guid isPermaLink="false">http://liftoff.msfc.nasa.gov/2003/06/03.html#item573</guid>
+      <dc:creator>Jane Doe</dc:creator>
+      <category>Space</category>
+      <category>Russia</category>
+      <enclosure url="http://example.com/star.mp3" length="12216320" type="audio/mpeg"/>
+    </item>
+    <item>
+      <description>Sky watchers in Europe, Asia, and parts of Alaska and Canada will experience a partial eclipse.</description>
+      <pubDate>not a date</pubDate>
+    </item>
+  </channel>
+</rss>"#;
+
+const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title type="text">dive into mark</title>
+  <subtitle type="html">A &lt;em&gt;lot&lt;/em&gt; of effort went into making this effortless</subtitle>
+  <updated>2005-07-31T12:29:29Z</updated>
+  <id>tag:example.org,2003:3</id>
+  <link rel="alternate" type="text/html" hreflang="en" href="http://example.org/"/>
+  <link rel="self" type="application/atom+xml" href="http://example.org/feed.atom"/>
+  <entry>
+    <title>Atom draft-07 snapshot</title>
+    <link rel="enclosure" type="audio/mpeg" length="1337" href="http://example.org/audio/ph34r_my_podcast.mp3"/>
+    <link href="http://example.org/2005/04/02/atom"/>
+    <id>tag:example.org,2003:3.2397</id>
+    <updated>2005-07-31T12:29:29Z</updated>
+    <published>2003-12-13T08:29:29.123-04:00</published>
+    <author><name>Mark Pilgrim</name><uri>http://example.org/</uri></author>
+    <category term="atom" scheme="http://example.org/tags"/>
+    <ext:rating xmlns:ext="urn:ext"><ext:title>five</ext:title></ext:rating>
+    <content type="xhtml" xml:lang="en">
+      <div xmlns="http://www.w3.org/1999/xhtml"><p><i>[Update: The Atom draft is finished.]</i></p></div>
+    </content>
+  </entry>
+</feed>"#;
+
+#[test]
+pub fn test_rss() {
+    let feed = parse(RSS).unwrap();
+    assert_eq!(FeedKind::Rss, feed.kind);
+    assert_eq!(Some("Liftoff News"), feed.title.as_deref());
+    assert_eq!(Some("http://liftoff.msfc.nasa.gov/"), feed.link());
+    assert_eq!(Some("self"), feed.links[1].rel.as_deref());
+    assert_eq!(Some("Liftoff to Space Exploration."), feed.description.as_deref());
+    assert_eq!(vec!["editor@example.com"], feed.authors);
+    assert_eq!(Some(DateTime { year: 2003, month: 6, day: 10, hour: 9, minute: 41, second: 1, offset_minutes: 0 }), feed.updated);
+    assert_eq!(2, feed.entries.len());
+
+    let entry = &feed.entries[0];
+    assert_eq!(Some("Star City"), entry.title.as_deref());
+    assert_eq!(Some("http://liftoff.msfc.nasa.gov/news/2003/news-starcity.asp"), entry.link());
+    assert_eq!(Some("How do Americans get ready to work with Russians aboard the <i>ISS</i>?"), entry.summary.as_deref());
+    assert_eq!(Some("<p>How do <b>Americans</b> get ready?</p>"), entry.content.as_deref());
+    assert_eq!(Some("http://liftoff.msfc.nasa.gov/2003/06/03.html#item573"), entry.id.as_deref());
+    assert_eq!(vec!["Jane Doe"], entry.authors);
+    assert_eq!(vec!["Space", "Russia"], entry.categories);
+    assert_eq!(Some(1054625961), entry.published.map(|d| d.unix_timestamp()));
+    assert_eq!(
+        Link {
+            href: "http://example.com/star.mp3".to_string(),
+            rel: Some("enclosure".to_string()),
+            media_type: Some("audio/mpeg".to_string()),
+            title: None,
+            length: Some(12216320)
+        },
+        entry.links[1]
+    );
+
+    let entry = &feed.entries[1];
+    assert_eq!(None, entry.title);
+    assert_eq!(None, entry.link());
+    assert_eq!(None, entry.published);
+}
+
+#[test]
+pub fn test_atom() {
+    let feed = parse(ATOM).unwrap();
+    assert_eq!(FeedKind::Atom, feed.kind);
+    assert_eq!(Some("dive into mark"), feed.title.as_deref());
+    assert_eq!(Some("A <em>lot</em> of effort went into making this effortless"), feed.description.as_deref());
+    assert_eq!(Some("tag:example.org,2003:3"), feed.id.as_deref());
+    assert_eq!(Some("http://example.org/"), feed.link());
+    assert_eq!(Some(1122812969), feed.updated.map(|d| d.unix_timestamp()));
+
+    let entry = &feed.entries[0];
+    assert_eq!(Some("Atom draft-07 snapshot"), entry.title.as_deref());
+    assert_eq!(Some("http://example.org/2005/04/02/atom"), entry.link());
+    assert_eq!(Some(1337), entry.links[0].length);
+    assert_eq!(vec!["Mark Pilgrim"], entry.authors);
+    assert_eq!(vec!["atom"], entry.categories);
+    assert_eq!(Some(-4 * 60), entry.published.map(|d| d.offset_minutes));
+    assert_eq!(Some(1071318569), entry.published.map(|d| d.unix_timestamp()));
+    assert_eq!(Some("[Update: The Atom draft is finished.]"), entry.content.as_deref());
+}
+
+#[test]
+pub fn test_prefixed_atom() {
+    let xml = "<a:feed xmlns:a=\"http://www.w3.org/2005/Atom\"><a:entry><a:title>t</a:title><title>other</title></a:entry></a:feed>";
+    let feed = parse(xml).unwrap();
+    assert_eq!(Some("t"), feed.entries[0].title.as_deref());
+}
+
+#[test]
+pub fn test_dates() {
+    let utc = |s: &str| DateTime::parse_rfc822(s).map(|d| d.unix_timestamp());
+    assert_eq!(Some(0), utc("Thu, 01 Jan 1970 00:00:00 GMT"));
+    assert_eq!(Some(0), utc("1 Jan 70 01:00 +0100"));
+    assert_eq!(Some(951782400 + 5 * 3600), utc("29 Feb 2000 00:00:00 EST"));
+    assert_eq!(None, utc("29 Feb 1900 00:00:00 GMT"));
+    assert_eq!(None, utc("01 Foo 2000 00:00:00 GMT"));
+    assert_eq!(None, utc("01 Jan 2000 00:00:00"));
+
+    let utc = |s: &str| DateTime::parse_rfc3339(s).map(|d| d.unix_timestamp());
+    assert_eq!(Some(-1), utc("1969-12-31T23:59:59Z"));
+    assert_eq!(Some(0), utc("1970-01-01T05:30:00+05:30"));
+    assert_eq!(None, utc("1970-01-01T00:00:00"));
+    assert_eq!(None, utc("1970-13-01T00:00:00Z"));
+    assert_eq!(None, utc("1970-01-01"));
+}
+
+#[test]
+pub fn test_errors() {
+    assert!(matches!(parse("<rss><channel></rss>"), Err(FeedError::Xml(_))));
+    assert!(matches!(parse("<rss version=\"2.0\"/>"), Err(FeedError::InvalidFeed(_))));
+    assert!(matches!(parse("<feed/>"), Err(FeedError::UnknownFormat(name)) if name == "feed"));
+    assert!(matches!(
+        parse("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"/>"),
+        Err(FeedError::UnknownFormat(name)) if name == "{http://www.w3.org/1999/02/22-rdf-syntax-ns#}RDF"
+    ));
+}
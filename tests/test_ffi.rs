@@ -0,0 +1,54 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::{slice, str};
+
+use jaxp_rust::ffi::*;
+
+fn to_str<'a>(ptr: *const c_char, len: usize) -> Option<&'a str> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(str::from_utf8(unsafe { slice::from_raw_parts(ptr as *const u8, len) }).unwrap())
+    }
+}
+
+#[test]
+pub fn test_parse_and_navigate() {
+    let xml = "<root attr=\"value\"><a>text</a><b/></root>";
+    unsafe {
+        let doc = jaxp_parse(xml.as_ptr() as *const c_char, xml.len());
+        assert!(!doc.is_null());
+        let root = jaxp_root_element(doc);
+        assert_eq!(JaxpNodeType::Element, jaxp_node_type(doc, root));
+
+        let mut len = 0;
+        assert_eq!(Some("root"), to_str(jaxp_node_name(doc, root, &mut len), len));
+        let name = "attr";
+        assert_eq!(Some("value"), to_str(jaxp_node_attribute(doc, root, name.as_ptr() as *const c_char, name.len(), &mut len), len));
+        assert_eq!(1, jaxp_node_attribute_count(doc, root));
+
+        assert_eq!(2, jaxp_node_child_count(doc, root));
+        let a = jaxp_node_child(doc, root, 0);
+        assert_eq!(Some("text"), to_str(jaxp_node_text(doc, a, &mut len), len));
+        let b = jaxp_node_next_sibling(doc, a);
+        assert_eq!(Some("b"), to_str(jaxp_node_name(doc, b, &mut len), len));
+        assert_eq!(JAXP_NO_NODE, jaxp_node_next_sibling(doc, b));
+        assert_eq!(root, jaxp_node_parent(doc, b));
+        assert_eq!(JaxpNodeType::Invalid, jaxp_node_type(doc, 1000));
+
+        jaxp_document_free(doc);
+    }
+}
+
+#[test]
+pub fn test_error() {
+    let xml = "<root></a>";
+    unsafe {
+        let doc = jaxp_parse(xml.as_ptr() as *const c_char, xml.len());
+        assert!(doc.is_null());
+        let message = CStr::from_ptr(jaxp_last_error()).to_str().unwrap();
+        assert!(message.contains("NonMatchingTags"));
+    }
+}
@@ -0,0 +1,110 @@
+use std::io;
+
+use jaxp_rust::filter::{AddAttribute, DropNamespace, EventSink, FilterError, Pipeline, RenameElement};
+use jaxp_rust::pull::{Event, PullParser};
+use jaxp_rust::writer::XmlWriter;
+
+#[test]
+pub fn test_filters_in_order() {
+    let mut pipeline = Pipeline::new();
+    pipeline
+        .add(RenameElement::new("a", "b"))
+        .add(RenameElement::new("b", "c"))
+        .add(AddAttribute::new("c", "x", "<1>"))
+        .add(AddAttribute::new("c", "x", "\"2\""));
+    let xml = "<root><a x='0'>text &amp; <b/></a><?pi v?><![CDATA[<>]]></root>";
+    assert_eq!("<root><c x=\"&quot;2&quot;\">text &amp; <c x=\"&quot;2&quot;\"/></c><?pi v?><![CDATA[<>]]></root>", pipeline.transform(xml).unwrap());
+}
+
+#[test]
+pub fn test_closure_filters() {
+    // an element per text, and nothing else of a skipped element
+    let mut skipped_depth = None;
+    let mut pipeline = Pipeline::new();
+    pipeline
+        .add(move |event: Event, next: &mut dyn EventSink| match event {
+            Event::StartElement { name: "skip", depth, .. } if skipped_depth.is_none() => {
+                skipped_depth = Some(depth);
+                Ok(())
+            }
+            Event::EndElement { depth, .. } if skipped_depth == Some(depth) => {
+                skipped_depth = None;
+                Ok(())
+            }
+            _ if skipped_depth.is_some() => Ok(()),
+            event => next.event(event),
+        })
+        .add(|event: Event, next: &mut dyn EventSink| match event {
+            Event::Text(text) => {
+                next.event(Event::StartElement { name: "t", attributes: Default::default(), depth: 0 })?;
+                next.event(Event::Text(text))?;
+                next.event(Event::EndElement { name: "t", depth: 0 })
+            }
+            event => next.event(event),
+        });
+    let xml = "<r>a<skip>b<skip/></skip>c</r>";
+    assert_eq!("<r><t>a</t><t>c</t></r>", pipeline.transform(xml).unwrap());
+
+    // events into another sink
+    struct Names(Vec<String>);
+    impl EventSink for Names {
+        fn event(&mut self, event: Event) -> io::Result<()> {
+            if let Event::StartElement { name, .. } = event {
+                self.0.push(name.to_string());
+            }
+            Ok(())
+        }
+    }
+    let mut names = Names(vec![]);
+    Pipeline::new().add(RenameElement::new("a", "b")).run(PullParser::new("<a><c/><a/></a>").unwrap(), &mut names).unwrap();
+    assert_eq!(vec!["b", "c", "b"], names.0);
+}
+
+#[test]
+pub fn test_filter_errors() {
+    let mut pipeline = Pipeline::new();
+    pipeline.add(RenameElement::new("a", "1"));
+    assert!(matches!(pipeline.transform("<a/>"), Err(FilterError::Io(_))));
+    assert!(matches!(pipeline.transform("<b></c>"), Err(FilterError::Xml(_))));
+
+    // the writer of the caller is not checked for balance
+    let mut writer = XmlWriter::new(Vec::new());
+    let mut pipeline = Pipeline::new();
+    pipeline.add(|event: Event, next: &mut dyn EventSink| match event {
+        Event::EndElement { .. } => Ok(()),
+        event => next.event(event),
+    });
+    pipeline.run(PullParser::new("<a><b/></a>").unwrap(), &mut writer).unwrap();
+    assert!(writer.finish().is_err());
+}
+
+#[test]
+pub fn test_drop_namespace() {
+    let mut pipeline = Pipeline::new();
+    pipeline.add(DropNamespace::new("urn:x"));
+    let xml = "<x:a xmlns:x='urn:x' xmlns:y='urn:y' x:one='1' y:two='2'><x:b/><y:c/><d/></x:a>";
+    assert_eq!("<a xmlns:y=\"urn:y\" one=\"1\" y:two=\"2\"><b/><y:c/><d/></a>", pipeline.transform(xml).unwrap());
+
+    // elements of the dropped default namespace do not move into another one
+    let xml = "<a xmlns='urn:y'><b xmlns='urn:x'><c/><d xmlns='urn:y'/></b><x:e xmlns:x='urn:x'><f/></x:e></a>";
+    let expected = "<a xmlns=\"urn:y\"><b xmlns=\"\"><c/><d xmlns=\"urn:y\"/></b><e xmlns=\"\"><f xmlns=\"urn:y\"/></e></a>";
+    assert_eq!(expected, pipeline.transform(xml).unwrap());
+}
+
+#[test]
+pub fn test_strip_namespaces() {
+    use jaxp_rust::filter::StripNamespaces;
+
+    let xml = "<x:a xmlns:x='urn:x' xmlns='urn:d' x:one='1' one='2' xml:lang='en'><b xmlns:y='urn:y'><y:c y:two='2'/></b></x:a>";
+    let mut pipeline = Pipeline::new();
+    pipeline.add(StripNamespaces::new());
+    assert_eq!("<a one=\"1\" xml:lang=\"en\"><b><c two=\"2\"/></b></a>", pipeline.transform(xml).unwrap());
+
+    let mut pipeline = Pipeline::new();
+    pipeline.add(StripNamespaces::into_default("urn:new"));
+    let expected = "<a xmlns=\"urn:new\" one=\"1\" xml:lang=\"en\"><b><c two=\"2\"/></b></a>";
+    let result = pipeline.transform(xml).unwrap();
+    assert_eq!(expected, result);
+    let doc = jaxp_rust::document::Document::parse(&result).unwrap();
+    assert!(doc.descendants().filter(|n| n.is_element()).all(|n| n.namespace_uri() == Some("urn:new")));
+}
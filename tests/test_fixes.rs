@@ -0,0 +1,40 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::error::Fix;
+use jaxp_rust::parse::XmlParser;
+use jaxp_rust::stats::stats;
+
+fn fix_of(xml: &str) -> Fix {
+    Document::parse(xml).unwrap_err().fix().cloned().expect("a suggested fix")
+}
+
+#[test]
+pub fn test_fixes() {
+    let cases = [
+        ("<root>fish & chips</root>", "<root>fish &amp; chips</root>"),
+        ("<root>&</root>", "<root>&amp;</root>"),
+        ("<root a=1 b=\"2\"/>", "<root a=\"1\" b=\"2\"/>"),
+        ("<root a=x/>", "<root a=\"x\"/>"),
+        ("<root a='x' b=say\"hi\"></root>", "<root a='x' b='say\"hi\"'></root>"),
+        ("<root><Foo></foo></root>", "<root><Foo></Foo></root>"),
+        ("<root><br a=\"x>y\"></root>", "<root><br a=\"x>y\"/></root>"),
+    ];
+    for (xml, fixed) in cases {
+        assert_eq!(fixed, fix_of(xml).apply(xml), "{}", xml);
+        assert!(Document::parse(fixed).is_ok());
+    }
+}
+
+#[test]
+pub fn test_no_fixes() {
+    assert!(Document::parse("<root>&nbsp;</root>").unwrap_err().fix().is_none());
+    assert!(Document::parse("<a><b></c></a>").unwrap_err().fix().is_none());
+    assert!(Document::parse("<a").unwrap_err().fix().is_none());
+}
+
+#[test]
+pub fn test_fixes_of_parser_and_stats() {
+    let xml = "<root><br></root>";
+    let expected = Some(Fix { start: 9, end: 9, replacement: "/".to_string() });
+    assert_eq!(expected.as_ref(), XmlParser::default().parse(xml).unwrap_err().fix());
+    assert_eq!(expected.as_ref(), stats(xml).unwrap_err().fix());
+}
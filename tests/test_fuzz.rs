@@ -0,0 +1,91 @@
+use jaxp_rust::document::{parse_untrusted_bytes, Document, MAX_UNTRUSTED_DEPTH};
+use jaxp_rust::writer::serialize;
+use jaxp_rust::error::XmlError::*;
+
+/// Deterministic xorshift, the tests must be reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+#[test]
+pub fn test_untrusted_bytes_errors() {
+    assert!(matches!(parse_untrusted_bytes(b"<a>\n<b>\xff</b></a>"), Err(InvalidUtf8 { pos }) if pos.row == 2 && pos.col == 4));
+    assert!(parse_untrusted_bytes(b"<a>\xc3\xa9</a>").is_ok());
+}
+
+#[test]
+pub fn test_untrusted_depth() {
+    let nested = |depth: usize| format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+    let doc = parse_untrusted_bytes(nested(MAX_UNTRUSTED_DEPTH).as_bytes()).unwrap();
+    // what the fuzz target does with accepted input
+    let xml = serialize(doc.document().root());
+    assert!(parse_untrusted_bytes(xml.as_bytes()).is_ok());
+    for depth in [MAX_UNTRUSTED_DEPTH + 1, 100_000] {
+        assert!(matches!(parse_untrusted_bytes(nested(depth).as_bytes()), Err(LimitExceeded { max: MAX_UNTRUSTED_DEPTH, .. })));
+    }
+    // only start tags count, not the number of elements
+    let wide = format!("<r>{}</r>", "<a><b/></a>".repeat(MAX_UNTRUSTED_DEPTH));
+    assert!(parse_untrusted_bytes(wide.as_bytes()).is_ok());
+}
+
+#[test]
+pub fn test_mutated_input_does_not_panic() {
+    let seeds: [&[u8]; 5] = [
+        b"<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?><!DOCTYPE a SYSTEM \"a.dtd\"><a>",
+        b"<root xmlns:p=\"urn:p\" p:x=\"1\"><p:child y='&amp;&#x41;'>text<![CDATA[]]]]></p:child></root>",
+        b"<a><!-- comment --><?pi value?><b/>tail &lt; &#60;</a>\n",
+        b"<\xc3\xa9l\xc3\xa9ment attr=\"\xe2\x82\xac\"/>",
+        b"<!DOCTYPE a PUBLIC \"p\" \"s\" [<!ELEMENT a ANY> %pe; <!ATTLIST a x CDATA '>'><?pi?>]><a>]</a>",
+    ];
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..20_000 {
+        let mut input = seeds[rng.next() as usize % seeds.len()].to_vec();
+        for _ in 0..=rng.next() % 4 {
+            let pos = rng.next() as usize % input.len();
+            match rng.next() % 3 {
+                0 => input[pos] = rng.next() as u8,
+                1 => { input.remove(pos); }
+                _ => input.truncate(pos),
+            }
+            if input.is_empty() {
+                break;
+            }
+        }
+        let _ = parse_untrusted_bytes(&input);
+        // untrusted input skips document type declarations, which are processed by default
+        if let Ok(xml) = std::str::from_utf8(&input) {
+            let _ = Document::parse(xml);
+        }
+    }
+    for len in 0..2_000 {
+        let _ = parse_untrusted_bytes(&rng.bytes(len % 64));
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+pub fn test_arbitrary_round_trip() {
+    use arbitrary::{Arbitrary, Unstructured};
+    use jaxp_rust::testutil::ArbitraryDocument;
+    use jaxp_rust::writer::serialize;
+
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..500 {
+        let bytes = rng.bytes(4096);
+        let generated = ArbitraryDocument::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        let xml = generated.to_xml();
+        let doc = Document::parse(&xml).unwrap_or_else(|e| panic!("{} in {}", e, xml));
+        assert_eq!(xml, serialize(doc.root()));
+    }
+}
@@ -0,0 +1,40 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::generator::{generate, GeneratorOptions};
+
+#[test]
+pub fn test_reproducible() {
+    let options = GeneratorOptions { seed: 42, ..Default::default() };
+    assert_eq!(generate(&options), generate(&options));
+    assert_ne!(generate(&options), generate(&GeneratorOptions { seed: 43, ..Default::default() }));
+}
+
+#[test]
+pub fn test_shape() {
+    let options = GeneratorOptions {
+        seed: 7,
+        max_depth: 3,
+        fan_out: 2,
+        attribute_density: 2.0,
+        text_ratio: 0.0,
+        entity_ratio: 0.5,
+    };
+    let xml = generate(&options);
+    let doc = Document::parse(&xml).unwrap();
+    let elements: Vec<_> = doc.descendants().filter(|n| n.is_element()).collect();
+    // 1 + 2 + 4 + 8 elements
+    assert_eq!(15, elements.len());
+    assert!(elements.iter().all(|e| e.attributes().count() == 2));
+    let max_depth = elements.iter().map(|e| e.ancestors().filter(|a| a.is_element()).count() - 1).max();
+    assert_eq!(Some(3), max_depth);
+    assert!(xml.contains('&'));
+}
+
+#[test]
+pub fn test_text_ratio() {
+    let options = GeneratorOptions { seed: 1, text_ratio: 1.0, attribute_density: 0.0, ..Default::default() };
+    let xml = generate(&options);
+    let doc = Document::parse(&xml).unwrap();
+    let root = doc.root_element();
+    assert_eq!(0, root.attributes().count());
+    assert!(root.children().all(|c| c.is_text()));
+}
@@ -0,0 +1,52 @@
+use jaxp_rust::document::{Document, GraftError};
+use jaxp_rust::writer::serialize;
+
+#[test]
+pub fn test_append_subtree() {
+    let mut target = Document::parse("<page><header/><footer/></page>").unwrap();
+    let source = Document::parse("<lib><item n=\"1\">one<!--c--><b>two</b></item></lib>").unwrap();
+    let item = source.root_element().first_child().unwrap();
+
+    let root = target.root_element().id();
+    let copy = target.node_mut(root).unwrap().insert_subtree(1, item).unwrap();
+    target.node_mut(root).unwrap().append_subtree(item).unwrap();
+    assert_eq!(
+        "<page><header/><item n=\"1\">one<!--c--><b>two</b></item><footer/><item n=\"1\">one<!--c--><b>two</b></item></page>",
+        serialize(target.root())
+    );
+    assert_eq!(Some(root), target.get_node(copy).unwrap().parent().map(|p| p.id()));
+    drop(source);
+    assert_eq!("two", target.get_node(copy).unwrap().last_child().unwrap().first_child().unwrap().text().unwrap());
+}
+
+#[test]
+pub fn test_append_fragment_namespaces() {
+    let mut target = Document::parse("<doc xmlns=\"urn:doc\" xmlns:x=\"urn:x\" xmlns:y=\"urn:target\"><body/></doc>").unwrap();
+    let body = target.root_element().first_child().unwrap().id();
+    let mut body = target.node_mut(body).unwrap();
+    body.append_fragment("<p><x:a xmlns:x=\"urn:x\"/></p>").unwrap();
+
+    let source = Document::parse("<s xmlns:x=\"urn:x\" xmlns:y=\"urn:y\"><part xmlns=\"urn:part\"><y:b/></part></s>").unwrap();
+    body.append_subtree(source.root_element().first_child().unwrap()).unwrap();
+    assert_eq!(
+        "<body><p xmlns=\"\"><x:a xmlns:x=\"urn:x\"/></p><part xmlns=\"urn:part\" xmlns:y=\"urn:y\"><y:b/></part></body>",
+        serialize(target.root_element().first_child().unwrap())
+    );
+    let part = target.root_element().first_child().unwrap().last_child().unwrap();
+    assert_eq!(Some("urn:y"), part.first_child().unwrap().namespace_uri());
+}
+
+#[test]
+pub fn test_graft_errors() {
+    let mut target = Document::parse("<doc id=\"a\"><p xml:id=\"b\">text</p></doc>").unwrap();
+    let root = target.root_element().id();
+    let text = target.root_element().first_child().unwrap().first_child().unwrap().id();
+    let before = serialize(target.root());
+
+    assert!(matches!(target.node_mut(root).unwrap().append_fragment("<q><r id=\"b\"/></q>"), Err(GraftError::DuplicateId(id)) if id == "b"));
+    assert!(matches!(target.node_mut(root).unwrap().append_fragment("<q id=\"c\"><r xml:id=\"c\"/></q>"), Err(GraftError::DuplicateId(_))));
+    assert!(matches!(target.node_mut(root).unwrap().append_fragment("<q>"), Err(GraftError::Parse(_))));
+    assert!(matches!(target.node_mut(text).unwrap().append_fragment("<q/>"), Err(GraftError::NotAnElement)));
+    assert_eq!(before, serialize(target.root()));
+    assert!(target.node_mut(root).unwrap().append_fragment("<q id=\"c\"/>").is_ok());
+}
@@ -0,0 +1,61 @@
+use jaxp_rust::document::{Document, Node};
+use jaxp_rust::lint::{Linter, ReportSink};
+use jaxp_rust::report::{Severity, Source};
+
+const XML: &str = r#"<doc>
+<section id="a" title=""><font><b/></font></section>
+<section xml:id="a"><p id="b"><center/></p></section>
+</doc>"#;
+
+#[test]
+pub fn test_builtin_rules() {
+    let doc = Document::parse(XML).unwrap();
+    let report = Linter::with_builtin_rules(3, &["font", "center"]).run(&doc);
+    let codes: Vec<_> = report.diagnostics.iter().map(|d| (d.code.as_str(), d.severity)).collect();
+    assert_eq!(
+        vec![
+            ("empty-attribute", Severity::Warning),
+            ("deprecated-element", Severity::Warning),
+            ("max-depth", Severity::Error),
+            ("duplicate-id", Severity::Error),
+            ("max-depth", Severity::Error),
+            ("deprecated-element", Severity::Warning),
+        ],
+        codes
+    );
+    assert!(report.diagnostics.iter().all(|d| d.source == Source::Rule));
+    assert_eq!(
+        "2:17: warning[empty-attribute]: attribute title of section is empty\n\
+         2:27: warning[deprecated-element]: element font is deprecated\n\
+         2:33: error[max-depth]: element b is nested deeper than 3 levels\n\
+         3:18: error[duplicate-id]: duplicate id a, first used by section\n\
+         3:32: error[max-depth]: element center is nested deeper than 3 levels\n\
+         3:32: warning[deprecated-element]: element center is deprecated\n",
+        report.to_string()
+    );
+}
+
+#[test]
+pub fn test_custom_rules() {
+    let doc = Document::parse("<a><!-- TODO --><b>TODO</b><c/></a>").unwrap();
+    let mut linter = Linter::new();
+    linter
+        .add_rule("todo", |node: &Node, sink: &mut ReportSink| {
+            if (node.is_text() || node.is_comment()) && node.text().unwrap_or("").contains("TODO") {
+                sink.info(node, "unresolved TODO");
+            }
+        })
+        .add_rule("no-c", |node: &Node, sink: &mut ReportSink| {
+            if node.tag_name() == "c" {
+                sink.error(node, "c is not allowed");
+            }
+        });
+    let report = linter.run(&doc);
+    assert_eq!(3, report.diagnostics.len());
+    assert_eq!(2, report.count(Severity::Info));
+    assert_eq!(None, report.diagnostics[0].span);
+    assert_eq!(Some(29), report.diagnostics[2].span.as_ref().map(|s| s.start.col));
+    assert!(!report.is_valid());
+    // a linter is reusable
+    assert_eq!(report, linter.run(&doc));
+}
@@ -0,0 +1,140 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::error::XmlError::{DuplicateAttribute, ReservedNamespace};
+use jaxp_rust::error::XmlErrorPos;
+use jaxp_rust::namespace::XML_NAMESPACE;
+use jaxp_rust::options::{ParseOptions, Strictness};
+use jaxp_rust::writer::serialize;
+
+#[test]
+pub fn test_namespace_uri() {
+    let xml = "<root xmlns=\"urn:default\" xmlns:a=\"urn:a\"><a:child><inner xmlns=\"\"/></a:child><b:unbound/></root>";
+    // an undeclared prefix is not namespace-well-formed
+    let options = ParseOptions { strictness: Strictness::Lenient, ..Default::default() };
+    let doc = Document::parse_with_options(xml, &options).unwrap();
+    let uris: Vec<_> = doc.descendants().filter(|n| n.is_element()).map(|n| (n.local_name(), n.namespace_uri())).collect();
+    assert_eq!(vec![
+        ("root", Some("urn:default")),
+        ("child", Some("urn:a")),
+        ("inner", None),
+        ("unbound", None),
+    ], uris);
+}
+
+#[test]
+pub fn test_namespaces_in_scope() {
+    let xml = "<root xmlns=\"urn:default\" xmlns:a=\"urn:a\"><child xmlns:a=\"urn:other\" xmlns:b=\"urn:b\"><inner xmlns=\"\">text</inner></child></root>";
+    let doc = Document::parse(xml).unwrap();
+    let text = doc.descendants().find(|n| n.is_text()).unwrap();
+    let bindings: Vec<_> = text.namespaces_in_scope().iter().map(|ns| (ns.prefix(), ns.uri())).collect();
+    assert_eq!(vec![
+        (Some("a"), "urn:other"),
+        (Some("b"), "urn:b"),
+        (Some("xml"), XML_NAMESPACE),
+    ], bindings);
+
+    assert_eq!(Some("urn:b"), text.lookup_namespace(Some("b")));
+    assert_eq!(None, text.lookup_namespace(None));
+    assert_eq!(Some("urn:default"), doc.root_element().lookup_namespace(None));
+    assert_eq!(Some(XML_NAMESPACE), doc.root_element().lookup_namespace(Some("xml")));
+
+    assert_eq!(Some("a"), text.lookup_prefix("urn:other"));
+    // "a" is rebound, "urn:a" has no prefix anymore
+    assert_eq!(None, text.lookup_prefix("urn:a"));
+    assert_eq!(Some("a"), doc.root_element().lookup_prefix("urn:a"));
+    assert_eq!(Some(""), doc.root_element().lookup_prefix("urn:default"));
+}
+
+#[test]
+pub fn test_lookup_ns() {
+    let xsi = "http://www.w3.org/2001/XMLSchema-instance";
+    let xml = format!("<root xmlns:xsi=\"{}\" xmlns:s=\"{}\" xmlns=\"urn:default\" type=\"plain\"><item s:type=\"int\"/></root>", xsi, xsi);
+    let doc = Document::parse(&xml).unwrap();
+    let root = doc.root_element();
+    assert_eq!(Some("plain"), root.attribute_ns("", "type"));
+    assert!(!root.has_attribute_ns(xsi, "type"));
+
+    let item = root.child_element_ns("urn:default", "item").unwrap();
+    assert!(item.has_tag_name_ns("urn:default", "item"));
+    assert!(!item.has_tag_name_ns("", "item"));
+    assert_eq!(Some("int"), item.attribute_ns(xsi, "type"));
+    assert_eq!(None, root.child_element_ns("", "item"));
+}
+
+#[test]
+pub fn test_duplicate_attributes() {
+    assert!(matches!(Document::parse("<a x=\"1\" y=\"2\" x=\"3\"/>"), Err(DuplicateAttribute { pos: XmlErrorPos { row: 1, col: 16 } })));
+    // same expanded name, even though the declaration follows the attributes
+    assert!(matches!(
+        Document::parse("<a p:x=\"1\" q:x=\"2\" xmlns:p=\"urn:a\" xmlns:q=\"urn:a\"/>"),
+        Err(DuplicateAttribute { .. })
+    ));
+    assert!(matches!(
+        Document::parse("<root xmlns:p=\"urn:a\"><a xmlns:q=\"urn:a\"><b p:x=\"1\" q:x=\"2\">text</b></a></root>"),
+        Err(DuplicateAttribute { .. })
+    ));
+    // different namespaces, or an unprefixed attribute and a prefixed one
+    assert!(Document::parse("<a p:x=\"1\" q:x=\"2\" x=\"3\" xmlns:p=\"urn:a\" xmlns:q=\"urn:b\"/>").is_ok());
+}
+
+#[test]
+pub fn test_reserved_namespaces() {
+    assert!(Document::parse("<a xmlns:xml=\"http://www.w3.org/XML/1998/namespace\" xml:lang=\"en\"/>").is_ok());
+    for xml in [
+        "<a xmlns:xml=\"urn:a\"/>",
+        "<a xmlns:xmlns=\"http://www.w3.org/2000/xmlns/\"/>",
+        "<a xmlns:x=\"http://www.w3.org/XML/1998/namespace\"/>",
+        "<a xmlns=\"http://www.w3.org/2000/xmlns/\"/>",
+        "<a xmlns:x=\"\"/>",
+        "<root><xmlns:a/></root>",
+    ] {
+        assert!(matches!(Document::parse(xml), Err(ReservedNamespace { .. })), "{}", xml);
+    }
+}
+
+#[test]
+pub fn test_lenient() {
+    let options = ParseOptions { strictness: Strictness::Lenient, ..Default::default() };
+    let doc = Document::parse_with_options("<a x=\"1\" x=\"2\" xmlns:xml=\"urn:a\"><xmlns:b/></a>", &options).unwrap();
+    assert_eq!(Some("1"), doc.root_element().attribute("x"));
+    assert!(Document::parse_with_options("<a></b>", &options).is_err());
+}
+
+#[test]
+pub fn test_detach_with_namespaces() {
+    let xml = "<root xmlns=\"urn:d\" xmlns:a=\"urn:a\" xmlns:b=\"urn:b\"><a:child xmlns:b=\"urn:other\"><b:x/></a:child><plain xmlns=\"\"><y/></plain></root>";
+    let mut doc = Document::parse(xml).unwrap();
+    let child = doc.root_element().first_child().unwrap().id();
+    let plain = doc.root_element().last_child().unwrap().id();
+    doc.node_mut(child).unwrap().detach_with_namespaces();
+    doc.node_mut(plain).unwrap().detach_with_namespaces();
+
+    assert_eq!("<root xmlns=\"urn:d\" xmlns:a=\"urn:a\" xmlns:b=\"urn:b\"/>", serialize(doc.root()));
+    let child = doc.get_node(child).unwrap();
+    assert!(child.parent().is_none());
+    assert_eq!(Some("urn:other"), child.first_child().unwrap().namespace_uri());
+    assert_eq!(Some("urn:d"), child.lookup_namespace(None));
+    assert_eq!("<a:child xmlns:b=\"urn:other\" xmlns=\"urn:d\" xmlns:a=\"urn:a\"><b:x/></a:child>", serialize(child));
+    // the undeclared default namespace stays undeclared
+    assert_eq!("<plain xmlns=\"\" xmlns:a=\"urn:a\" xmlns:b=\"urn:b\"><y/></plain>", serialize(doc.get_node(plain).unwrap()));
+}
+
+#[test]
+pub fn test_name_validation() {
+    use jaxp_rust::namespace::{is_ncname, validate_qname};
+    use jaxp_rust::xmlchar::is_valid_xml_name;
+
+    for name in ["a", "_a-1.b", "a:b", ":a", "ä·b", "x:y:z"] {
+        assert!(is_valid_xml_name(name), "{}", name);
+    }
+    for name in ["", "1a", "-a", "a b", "a>", "a\u{FFFE}"] {
+        assert!(!is_valid_xml_name(name), "{}", name);
+    }
+    assert!(is_ncname("a.b") && !is_ncname("a:b") && !is_ncname(""));
+
+    assert_eq!(Ok((None, "a")), validate_qname("a"));
+    assert_eq!(Ok((Some("p"), "a")), validate_qname("p:a"));
+    assert_eq!(Err("the prefix is not an NCName"), validate_qname(":a"));
+    assert_eq!(Err("the prefix is not an NCName"), validate_qname("1:a"));
+    assert_eq!(Err("the local part is not an NCName"), validate_qname("p:"));
+    assert_eq!(Err("the local part is not an NCName"), validate_qname("x:y:z"));
+}
@@ -0,0 +1,50 @@
+use jaxp_rust::node::{NodeEq, XmlNode};
+use jaxp_rust::parse::XmlParser;
+
+fn parse(xml: &str) -> XmlNode<'_> {
+    XmlParser::default().parse(xml).unwrap()
+}
+
+#[test]
+pub fn test_strict() {
+    let strict = NodeEq::default();
+    assert!(strict.equal(&parse("<a x='1'>t<!--c--></a>"), &parse("<a x='1'>t<!--c--></a>")));
+    for (a, b) in [("<a x='1' y='2'/>", "<a y='2' x='1'/>"), ("<a>t<!--c--></a>", "<a>t</a>"), ("<a> t</a>", "<a>t</a>"), ("<a/>", "<A/>")] {
+        assert!(!strict.equal(&parse(a), &parse(b)), "{} {}", a, b);
+    }
+}
+
+#[test]
+pub fn test_flags() {
+    let eq = |flags: NodeEq, a: &str, b: &str| flags.equal(&parse(a), &parse(b));
+    let attribute_order = NodeEq { ignore_attribute_order: true, ..NodeEq::default() };
+    assert!(eq(attribute_order, "<a x='1' y='2'/>", "<a y='2' x='1'/>"));
+    assert!(!eq(attribute_order, "<a x='1' y='2'/>", "<a y='1' x='2'/>"));
+
+    let comments = NodeEq { ignore_comments: true, ..NodeEq::default() };
+    assert!(eq(comments, "<a>x<!--c-->y<b/></a>", "<a>xy<b/><!--d--></a>"));
+    assert!(!eq(comments, "<a>x<!--c-->y</a>", "<a>x<b/>y</a>"));
+
+    let whitespace = NodeEq { normalize_whitespace: true, ..NodeEq::default() };
+    assert!(eq(whitespace, "<a v=' 1  2'>\n  <b> t\n u </b>\n</a>", "<a v='1 2'><b>t u</b></a>"));
+    assert!(!eq(whitespace, "<a><b>tu</b></a>", "<a><b>t u</b></a>"));
+
+    let names = NodeEq { case_insensitive_names: true, ..NodeEq::default() };
+    assert!(eq(names, "<Html LANG='en'><?PHP x?></Html>", "<html lang='en'><?php x?></html>"));
+    assert!(!eq(names, "<a lang='EN'/>", "<a lang='en'/>"));
+    let names_and_order = NodeEq { ignore_attribute_order: true, ..names };
+    assert!(eq(names_and_order, "<a B='1' c='2'/>", "<a C='2' b='1'/>"));
+}
+
+#[test]
+pub fn test_references() {
+    let strict = NodeEq::default();
+    for (a, b) in [("<a>&amp;</a>", "<a>&#38;</a>"), ("<a v='&lt;&#x20;'/>", "<a v='&#60; '/>"), ("<a>x&amp;<!--c-->y</a>", "<a>x&#38;<!--c-->y</a>")] {
+        assert!(strict.equal(&parse(a), &parse(b)), "{} {}", a, b);
+        jaxp_rust::assert_xml_eq!(a, b);
+    }
+    let comments = NodeEq { ignore_comments: true, ..NodeEq::default() };
+    assert!(comments.equal(&parse("<a>&amp;<!--c-->&#x61;</a>"), &parse("<a>&amp;a</a>")));
+    assert!(!strict.equal(&parse("<a><![CDATA[&amp;]]></a>"), &parse("<a><![CDATA[&]]></a>")));
+    assert!(!strict.equal(&parse("<a>&amp;amp;</a>"), &parse("<a>&amp;</a>")));
+}
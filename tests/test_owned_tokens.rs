@@ -0,0 +1,76 @@
+use std::sync::mpsc;
+use std::thread;
+
+use jaxp_rust::error::XmlError::IllegalToken;
+use jaxp_rust::error::{Expected, XmlErrorPos};
+use jaxp_rust::options::ParseOptions;
+use jaxp_rust::textrange::InvalidChar;
+use jaxp_rust::token::{XmlToken, XmlTokenOwned};
+use jaxp_rust::tokenize::XmlTokenizer;
+
+#[test]
+pub fn test_owned_tokens() {
+    let xml = String::from("<?xml version=\"1.0\"?><a x=\"1\"><!--c--><?pi?>text</a>");
+    let tokens = XmlTokenizer::default().tokenize(&xml).unwrap();
+    let owned: Vec<XmlTokenOwned> = tokens.iter().map(XmlToken::to_owned).collect();
+    drop(tokens);
+    drop(xml);
+
+    assert_eq!(7, owned.len());
+    assert!(matches!(owned[0].borrowed(), XmlToken::XmlDeclaration { opt_encoding_range: None, .. }));
+    assert!(matches!(owned[4].borrowed(), XmlToken::ProcessingInstruction { opt_value_range: None, .. }));
+    // a round trip through the borrowed form
+    let copies: Vec<XmlTokenOwned> = owned.iter().map(|t| t.borrowed().to_owned()).collect();
+    assert_eq!(owned, copies);
+}
+
+#[test]
+pub fn test_owned_tokens_across_threads() {
+    let (sender, receiver) = mpsc::channel();
+    let producer = thread::spawn(move || {
+        let mut tokenizer = XmlTokenizer::default();
+        for i in 0..3 {
+            let xml = format!("<a n=\"{}\"/>", i);
+            sender.send(tokenizer.tokenize_owned(&xml).unwrap()).unwrap();
+        }
+    });
+    let batches: Vec<Vec<XmlTokenOwned>> = receiver.iter().collect();
+    producer.join().unwrap();
+    assert_eq!(3, batches.len());
+    assert_ne!(batches[0], batches[1]);
+    assert!(batches.iter().all(|tokens| matches!(tokens[0], XmlTokenOwned::StartTag(_))));
+}
+
+#[test]
+pub fn test_lazy_char_validation() {
+    let xml = "<a b='x\u{1}'>t\u{0}t<!--c\u{2}--><![CDATA[\u{3}]]><?pi \u{4}?>ok</a>";
+    assert!(XmlTokenizer::default().tokenize(xml).is_err());
+    let options = ParseOptions { lazy_char_validation: true, ..Default::default() };
+    let tokens = XmlTokenizer::with_options(&options).tokenize(xml).unwrap();
+    let invalid: Vec<_> = tokens
+        .iter()
+        .filter_map(|t| match t {
+            XmlToken::Attribute { value_range: range, .. } | XmlToken::Text(range) | XmlToken::Comment(range) | XmlToken::CdataSection(range) => Some(range),
+            XmlToken::ProcessingInstruction { opt_value_range: Some(range), .. } => Some(range),
+            _ => None,
+        })
+        .map(|r| r.as_str().map_err(|e| e.character))
+        .collect();
+    assert_eq!(vec![Err('\u{1}'), Err('\u{0}'), Err('\u{2}'), Err('\u{3}'), Err('\u{4}'), Ok("ok")], invalid);
+
+    let error = match &tokens[2] {
+        XmlToken::Text(range) => range.as_str().unwrap_err(),
+        token => panic!("unexpected token {:?}", token),
+    };
+    assert_eq!(InvalidChar { offset: 11, character: '\u{0}' }, error);
+    assert!(matches!(error.to_xml_error(xml), IllegalToken { pos: XmlErrorPos { row: 1, col: 12 }, .. }));
+
+    // the structure is still checked
+    let lazy = XmlTokenizer::with_options(&options).tokenize("<a>]]></a>");
+    assert!(matches!(lazy, Err(IllegalToken { expected: Some(Expected::NoCdataSectionEnd), .. })));
+    assert!(XmlTokenizer::with_options(&options).tokenize("<a b='<'/>").is_err());
+    assert!(XmlTokenizer::with_options(&options).tokenize("<a>&unknown;</a>").is_err());
+    assert!(XmlTokenizer::with_options(&options).tokenize("<a><!-- a -- b --></a>").is_err());
+    assert!(XmlTokenizer::with_options(&options).tokenize("<a>text").is_err());
+    assert!(XmlTokenizer::with_options(&options).tokenize("<a><!-- unclosed").is_err());
+}
@@ -0,0 +1,93 @@
+use jaxp_rust::plist::{from_xml, to_xml, PlistError, PlistValue};
+
+const INFO_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>CFBundleIdentifier</key>
+	<string>com.example.demo &amp; more</string>
+	<key>CFBundleSupportedPlatforms</key>
+	<array>
+		<string>iPhoneOS</string>
+		<string><![CDATA[<sim>]]></string>
+	</array>
+	<key>Icon</key>
+	<data>
+	iVBORw0KGgo=
+	</data>
+	<key>Build</key>
+	<integer>0x1F</integer>
+	<key>Scale</key>
+	<real>2.5</real>
+	<key>Created</key>
+	<date>2003-02-11T23:52:55Z</date>
+	<key>Debug</key>
+	<false/>
+	<key>Empty</key>
+	<dict/>
+</dict>
+</plist>"#;
+
+#[test]
+pub fn test_from_xml() {
+    let value = from_xml(INFO_PLIST).unwrap();
+    assert_eq!(Some("com.example.demo & more"), value.get("CFBundleIdentifier").and_then(|v| v.as_str()));
+    let platforms = value.get("CFBundleSupportedPlatforms").and_then(|v| v.as_array()).unwrap();
+    assert_eq!(vec![PlistValue::String("iPhoneOS".to_string()), PlistValue::String("<sim>".to_string())], platforms);
+    assert_eq!(Some(&PlistValue::Data(vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])), value.get("Icon"));
+    assert_eq!(Some(&PlistValue::Integer(31)), value.get("Build"));
+    assert_eq!(Some(&PlistValue::Real(2.5)), value.get("Scale"));
+    assert_eq!(Some(&PlistValue::Date("2003-02-11T23:52:55Z".to_string())), value.get("Created"));
+    assert_eq!(Some(&PlistValue::Boolean(false)), value.get("Debug"));
+    assert_eq!(Some(&PlistValue::Dictionary(vec![])), value.get("Empty"));
+    assert_eq!(None, value.get("Missing"));
+}
+
+#[test]
+pub fn test_to_xml() {
+    let value = PlistValue::Dictionary(vec![
+        ("name".to_string(), PlistValue::String("a < b".to_string())),
+        ("list".to_string(), PlistValue::Array(vec![PlistValue::Integer(-1), PlistValue::Boolean(true), PlistValue::Array(vec![])])),
+        ("bytes".to_string(), PlistValue::Data(vec![1, 2, 3, 4])),
+    ]);
+    let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
+<plist version=\"1.0\">
+<dict>
+\t<key>name</key>
+\t<string>a &lt; b</string>
+\t<key>list</key>
+\t<array>
+\t\t<integer>-1</integer>
+\t\t<true/>
+\t\t<array/>
+\t</array>
+\t<key>bytes</key>
+\t<data>AQIDBA==</data>
+</dict>
+</plist>
+";
+    assert_eq!(expected, to_xml(&value));
+    assert_eq!(value, from_xml(expected).unwrap());
+    let reals = PlistValue::Array(vec![PlistValue::Real(f64::INFINITY), PlistValue::Real(0.1), PlistValue::Real(-3.0)]);
+    assert_eq!(reals, from_xml(&to_xml(&reals)).unwrap());
+    for len in 0..8 {
+        let data = PlistValue::Data((0..len).map(|i| i * 37).collect());
+        assert_eq!(data, from_xml(&to_xml(&data)).unwrap());
+    }
+}
+
+#[test]
+pub fn test_errors() {
+    assert!(matches!(from_xml("<plist><dict>"), Err(PlistError::Xml(_))));
+    assert!(matches!(from_xml("<plist><string/><string/></plist>"), Err(PlistError::UnexpectedElement(name)) if name == "string"));
+    assert!(matches!(from_xml("<array><number>1</number></array>"), Err(PlistError::UnexpectedElement(name)) if name == "number"));
+    assert!(matches!(from_xml("<dict><key>a</key></dict>"), Err(PlistError::MissingKey(_))));
+    assert!(matches!(from_xml("<dict><string>a</string></dict>"), Err(PlistError::MissingKey(_))));
+    assert!(matches!(from_xml("<dict><key>a</key><true/><key>a</key><false/></dict>"), Err(PlistError::DuplicateKey(key)) if key == "a"));
+    assert!(matches!(from_xml("<array>text<true/></array>"), Err(PlistError::InvalidValue { .. })));
+    for xml in ["<integer>1.5</integer>", "<integer>--1</integer>", "<integer>9223372036854775808</integer>", "<real>x</real>", "<data>A</data>", "<data>A*==</data>", "<true>1</true>"].iter() {
+        assert!(matches!(from_xml(xml), Err(PlistError::InvalidValue { .. })), "{}", xml);
+    }
+    assert_eq!(PlistValue::Integer(i64::MIN), from_xml("<integer>-9223372036854775808</integer>").unwrap());
+}
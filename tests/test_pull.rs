@@ -0,0 +1,146 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::error::XmlError;
+use jaxp_rust::error::XmlError::*;
+use jaxp_rust::options::{ParseOptions, Strictness};
+use jaxp_rust::pull::{Event, PullParser};
+
+#[test]
+pub fn test_events() {
+    let xml = "<?xml version=\"1.0\"?>\n<a x=\"1\"><b/>text<!--c--><?pi v?><![CDATA[d]]></a>\n";
+    let events: Vec<Event> = PullParser::new(xml).unwrap().map(Result::unwrap).collect();
+    assert_eq!(8, events.len());
+    match &events[0] {
+        Event::StartElement { name, attributes, depth } => {
+            assert_eq!(("a", 0), (*name, *depth));
+            assert_eq!(Some("1"), attributes.get("x").map(|a| a.value()));
+        }
+        e => panic!("unexpected event {:?}", e),
+    }
+    assert!(matches!(events[1], Event::StartElement { name: "b", depth: 1, .. }));
+    assert_eq!(Event::EndElement { name: "b", depth: 1 }, events[2]);
+    assert_eq!(Event::Text("text"), events[3]);
+    assert_eq!(Event::Comment("c"), events[4]);
+    assert_eq!(Event::ProcessingInstruction { target: "pi", opt_value: Some("v") }, events[5]);
+    assert_eq!(Event::CdataSection("d"), events[6]);
+    assert_eq!(Event::EndElement { name: "a", depth: 0 }, events[7]);
+}
+
+#[test]
+pub fn test_skip_to_end_of_current() {
+    let xml = "<list><skip><deep><deeper/></deep></skip><item>1</item><item>2<x/></item></list>";
+    let mut parser = PullParser::new(xml).unwrap();
+    let mut items = vec![];
+    while let Some(event) = parser.next_event().unwrap() {
+        match event {
+            Event::StartElement { name: "item", .. } => {
+                if let Some(Event::Text(text)) = parser.next_event().unwrap() {
+                    items.push(text);
+                }
+                // the rest of the item
+                parser.skip_to_end_of_current().unwrap();
+                assert_eq!(1, parser.depth());
+            }
+            Event::StartElement { name: "skip", .. } => parser.skip_to_end_of_current().unwrap(),
+            Event::StartElement { name, .. } => assert_eq!("list", name),
+            Event::EndElement { name, depth } => assert_eq!(("list", 0), (name, depth)),
+            e => panic!("unexpected event {:?}", e),
+        }
+    }
+    assert_eq!(vec!["1", "2"], items);
+}
+
+#[test]
+pub fn test_errors() {
+    let errors_of = |xml: &str| PullParser::new(xml).unwrap().filter_map(Result::err).collect::<Vec<_>>();
+    assert!(matches!(errors_of("<a><b></a>")[..], [NonMatchingTags { .. }]));
+    assert!(matches!(errors_of("<a/><b/>")[..], [UnexpectedXmlToken { .. }]));
+    assert!(matches!(errors_of("<a>")[..], [UnexpectedEndOfFile { .. }]));
+    // errors in the prolog are reported when the parser is created, those in the content once they are reached
+    assert!(matches!(PullParser::new("garbage<a/>"), Err(LeadingGarbage { .. })));
+    // text is tokenized with the markup after it
+    let results: Vec<_> = PullParser::new("<a><b/>text<c").unwrap().collect();
+    assert_eq!(4, results.len());
+    assert!(results[..3].iter().all(Result::is_ok));
+    assert!(matches!(results[3], Err(UnexpectedEndOfFile { .. })));
+
+    // the error ends the iteration
+    let mut parser = PullParser::new("<a></b>").unwrap();
+    assert!(parser.next().unwrap().is_ok());
+    assert!(parser.next().unwrap().is_err());
+    assert!(parser.next().is_none());
+}
+
+fn pull_all(xml: &str, options: &ParseOptions) -> Result<(), XmlError> {
+    PullParser::with_options(xml, options)?.try_for_each(|result| result.map(drop))
+}
+
+#[test]
+pub fn test_namespace_constraints() {
+    let rejected = [
+        "<a x='1' x='2'/>",
+        "<a xmlns:p='u' p:x='1' p:x='2'/>",
+        "<a xmlns:p='u' xmlns:q='u' p:x='1' q:x='2'/>",
+        "<a xmlns:p='u'><b><c p:x='1' p:x='2'/></b></a>",
+        "<a xmlns:xml='x'/>",
+        "<a xmlns:p=''/>",
+        "<xmlns:a/>",
+        "<p:a/>",
+        "<a p:x='1'/>",
+        "<a:b:c/>",
+        "<a :x='1'/>",
+        "<a><b xmlns:p='u'/><p:c/></a>",
+    ];
+    for xml in rejected {
+        let document_error = Document::parse(xml).unwrap_err();
+        let pull_error = pull_all(xml, &ParseOptions::default()).unwrap_err();
+        assert_eq!(document_error.to_string(), pull_error.to_string(), "{}", xml);
+        let lenient = ParseOptions { strictness: Strictness::Lenient, ..Default::default() };
+        assert!(Document::parse_with_options(xml, &lenient).is_ok(), "{}", xml);
+        assert!(pull_all(xml, &lenient).is_ok(), "{}", xml);
+    }
+    // the declarations of an element go out of scope with its end tag
+    for xml in [
+        "<a xmlns:p='u' xmlns:q='v' p:x='1' q:x='2'/>",
+        "<a xmlns:p='v'><b xmlns:p='u' p:x='1'/><c xmlns:q='u' p:x='1' q:x='2'/></a>",
+        "<a xmlns='u' x='1' xmlns:p='u' p:y='1'/>",
+        "<p:a xmlns:p='u'/>",
+    ] {
+        assert!(Document::parse(xml).is_ok(), "{}", xml);
+        assert!(pull_all(xml, &ParseOptions::default()).is_ok(), "{}", xml);
+    }
+}
+
+#[test]
+pub fn test_chunks() {
+    fn chunks_of(xml: &str, max_len: usize) -> Vec<Event<'_>> {
+        let mut parser = PullParser::new(xml).unwrap();
+        parser.set_max_chunk_len(Some(max_len));
+        parser.map(Result::unwrap).filter(|e| matches!(e, Event::Text(_) | Event::CdataSection(_))).collect()
+    }
+    let xml = "<a>abcdefg<![CDATA[12345]]><b/>xy</a>";
+    let expected = vec![
+        Event::Text("abc"), Event::Text("def"), Event::Text("g"),
+        Event::CdataSection("123"), Event::CdataSection("45"),
+        Event::Text("xy"),
+    ];
+    assert_eq!(expected, chunks_of(xml, 3));
+
+    // references and characters are not split
+    assert_eq!(vec![Event::Text("ab"), Event::Text("&amp;"), Event::Text("cd")], chunks_of("<a>ab&amp;cd</a>", 4));
+    assert_eq!(vec![Event::Text("a"), Event::Text("&#x20;"), Event::Text("b")], chunks_of("<a>a&#x20;b</a>", 5));
+    assert_eq!(vec![Event::Text("ä"), Event::Text("öx")], chunks_of("<a>äöx</a>", 3));
+    assert_eq!(vec![Event::CdataSection("ä"), Event::CdataSection("&")], chunks_of("<a><![CDATA[ä&]]></a>", 1));
+
+    // skipping an element skips the rest of its chunks
+    let mut parser = PullParser::new("<a><b>0123456789</b>end</a>").unwrap();
+    parser.set_max_chunk_len(Some(4));
+    parser.next_event().unwrap();
+    parser.next_event().unwrap();
+    assert_eq!(Some(Event::Text("0123")), parser.next_event().unwrap());
+    parser.skip_to_end_of_current().unwrap();
+    assert_eq!(Some(Event::Text("end")), parser.next_event().unwrap());
+
+    // one event per text by default
+    let events: Vec<_> = PullParser::new(xml).unwrap().map(Result::unwrap).collect();
+    assert!(events.contains(&Event::Text("abcdefg")));
+}
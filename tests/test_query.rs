@@ -0,0 +1,51 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::query::for_each;
+use jaxp_rust::xpath::XPathError;
+
+const ORDERS: &str = "<orders>\
+    <order id='1' status='open'><customer>Ann</customer><total>20</total></order>\
+    <order id='2' status='closed'><customer>Bob</customer><total>5</total></order>\
+    <order id='3' status='open'><customer>Bob</customer><total>12.5</total></order>\
+    <order id='4' status='o&#x70;en'><customer><b>B</b>ob</customer><total>7</total></order>\
+</orders>";
+
+#[test]
+pub fn test_query() {
+    let doc = Document::parse(ORDERS).unwrap();
+    let total = |order: jaxp_rust::document::Node| order.children_named("total").next().and_then(|t| t.text()).unwrap().parse::<f64>().unwrap();
+    let open: Vec<(&str, f64)> = for_each("//order")
+        .where_attr("status", "open")
+        .select(|order| (order.attribute("id").unwrap(), total(order)))
+        .run(doc.root())
+        .unwrap()
+        .collect();
+    assert_eq!(vec![("1", 20.0), ("3", 12.5), ("4", 7.0)], open);
+
+    let bobs: Vec<&str> = for_each("order")
+        .where_child_text("customer", "Bob")
+        .filter(move |order| total(order) > 6.0)
+        .order_by(|order| std::cmp::Reverse(order.attribute("id")))
+        .select(|order| order.attribute("id").unwrap())
+        .run(doc.root_element())
+        .unwrap()
+        .collect();
+    assert_eq!(vec!["4", "3"], bobs);
+
+    // nodes with equal keys stay in document order, attributes are skipped
+    let statuses: Vec<_> = for_each("//order | //@id")
+        .order_by(|order| order.attribute("status").map(|s| s.len()))
+        .select(|order| order.id())
+        .run(doc.root())
+        .unwrap()
+        .collect();
+    let ids: Vec<_> = [1, 3, 2, 4].iter().map(|i| doc.root_element().nth_element_child(i - 1).unwrap().id()).collect();
+    assert_eq!(ids, statuses);
+}
+
+#[test]
+pub fn test_query_errors() {
+    let doc = Document::parse(ORDERS).unwrap();
+    assert!(matches!(for_each("//order[").select(|o| o).run(doc.root()), Err(XPathError::Syntax { .. })));
+    assert!(matches!(for_each("count(//order)").select(|o| o).run(doc.root()), Err(XPathError::Type(_))));
+    assert_eq!(0, for_each("//missing").select(|o| o).run(doc.root()).unwrap().count());
+}
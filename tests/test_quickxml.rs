@@ -0,0 +1,40 @@
+#![cfg(feature = "quick-xml")]
+
+use quick_xml::events::Event;
+use quick_xml::Writer;
+
+use jaxp_rust::quickxml::{to_events, write_tokens};
+use jaxp_rust::tokenize::XmlTokenizer;
+
+#[test]
+pub fn test_events() {
+    let xml = "<?xml version=\"1.0\"?><root a=\"1\" b='&amp;'>text<empty/><!--c--><![CDATA[<>]]><?pi value?></root>";
+    let tokens = XmlTokenizer::default().tokenize(xml).unwrap();
+    let events: Vec<_> = to_events(tokens).collect();
+    assert_eq!(9, events.len());
+    assert!(matches!(&events[0], Event::Decl(_)));
+    match &events[1] {
+        Event::Start(start) => {
+            assert_eq!(b"root", start.name().as_ref());
+            let values: Vec<_> = start.attributes().map(|a| a.unwrap().unescape_value().unwrap().into_owned()).collect();
+            assert_eq!(vec!["1", "&"], values);
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+    assert!(matches!(&events[2], Event::Text(t) if t.as_ref() == b"text"));
+    assert!(matches!(&events[3], Event::Empty(e) if e.name().as_ref() == b"empty"));
+    assert!(matches!(&events[4], Event::Comment(_)));
+    assert!(matches!(&events[5], Event::CData(c) if c.as_ref() == b"<>"));
+    assert!(matches!(&events[6], Event::PI(pi) if pi.target() == b"pi"));
+    assert!(matches!(&events[7], Event::End(e) if e.name().as_ref() == b"root"));
+    assert_eq!(Event::Eof, events[8]);
+}
+
+#[test]
+pub fn test_write_tokens() {
+    let xml = "<root a=\"&lt;\"><a>x &amp; y</a><b/></root>";
+    let tokens = XmlTokenizer::default().tokenize(xml).unwrap();
+    let mut writer = Writer::new(Vec::new());
+    write_tokens(tokens, &mut writer).unwrap();
+    assert_eq!("<root a=\"&lt;\"><a>x &amp; y</a><b/></root>", String::from_utf8(writer.into_inner()).unwrap());
+}
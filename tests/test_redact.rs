@@ -0,0 +1,31 @@
+use jaxp_rust::redact::{redact, RedactionRules};
+
+#[test]
+pub fn test_redact() {
+    let rules = RedactionRules {
+        elements: vec!["password".to_string(), "/order/card".to_string()],
+        attributes: vec!["token".to_string(), "user@ssn".to_string(), "/order@id".to_string()],
+        ..Default::default()
+    };
+    let xml = "<order id='42' token=\"abc\">\n  <user ssn=\"123\" name=\"x\"><password>hunter2</password></user>\n  \
+        <card>\n    <number>4111</number>\n    <cvc><![CDATA[123]]></cvc>\n  </card>\n  <item ssn=\"keep\"><card>keep</card></item>\n</order>";
+    let expected = "<order id='***' token=\"***\">\n  <user ssn=\"***\" name=\"x\"><password>***</password></user>\n  \
+        <card>\n    <number>***</number>\n    <cvc><![CDATA[***]]></cvc>\n  </card>\n  <item ssn=\"keep\"><card>keep</card></item>\n</order>";
+    assert_eq!(expected, redact(xml, &rules).unwrap());
+}
+
+#[test]
+pub fn test_redact_escapes_mask() {
+    let rules = RedactionRules {
+        elements: vec!["a".to_string()],
+        attributes: vec!["b".to_string(), "c".to_string()],
+        mask: "<\"'&>".to_string(),
+    };
+    let redacted = redact("<a b=\"1\" c='2'>x &amp; y</a>", &rules).unwrap();
+    assert_eq!("<a b=\"&lt;&quot;'&amp;&gt;\" c='&lt;\"&apos;&amp;&gt;'>&lt;\"'&amp;&gt;</a>", redacted);
+    // input without matches is copied as it is
+    let xml = "<?xml version=\"1.0\"?>\n<!-- c --><r  x = \"1\" >t</r>";
+    assert_eq!(xml, redact(xml, &rules).unwrap());
+    assert!(redact("<a>", &rules).is_ok());
+    assert!(redact("<a", &rules).is_err());
+}
@@ -1,9 +1,7 @@
 extern crate core;
 
-use jaxp_rust::error::XmlError;
 use jaxp_rust::node::XmlNode;
 use jaxp_rust::parse::XmlParser;
-use jaxp_rust::token::XmlToken;
 
 #[test]
 pub fn test_valid_char_references() {
@@ -22,6 +20,6 @@ pub fn test_invalid_char_references() {
     for reference in to_test {
         let expected_err_target = reference.to_string();
         let xml = format!("<root>&amp;{}</root>", expected_err_target);
-        let actual_err_ = XmlParser::default().parse(&xml).unwrap_err();
+        let _actual_err_ = XmlParser::default().parse(&xml).unwrap_err();
     }
 }
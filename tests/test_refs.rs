@@ -0,0 +1,67 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::entities::EntityTable;
+use jaxp_rust::error::XmlError::*;
+use jaxp_rust::options::ParseOptions;
+use jaxp_rust::refs::{decode_char_ref, decode_char_ref_with, decode_entities, decode_entities_with, CharRefPolicy, RefError};
+
+#[test]
+pub fn test_decode_char_ref() {
+    assert_eq!(Ok('A'), decode_char_ref("&#65;"));
+    assert_eq!(Ok('A'), decode_char_ref("&#x41;"));
+    assert_eq!(Ok('\u{100}'), decode_char_ref("&#x100;"));
+    assert_eq!(Ok('\u{1F600}'), decode_char_ref("&#x1f600;"));
+    assert_eq!(Ok('\n'), decode_char_ref("&#x000A;"));
+    assert_eq!(Err(RefError::Malformed), decode_char_ref("&#x;"));
+    assert_eq!(Err(RefError::Malformed), decode_char_ref("&#+65;"));
+    assert_eq!(Err(RefError::Malformed), decode_char_ref("&#X41;"));
+    assert_eq!(Err(RefError::Malformed), decode_char_ref("&amp;"));
+    assert_eq!(Err(RefError::OutOfRange), decode_char_ref("&#xD800;"));
+    assert_eq!(Err(RefError::OutOfRange), decode_char_ref("&#x110000;"));
+    assert_eq!(Err(RefError::OutOfRange), decode_char_ref("&#99999999999;"));
+    assert_eq!(Err(RefError::NotAnXmlChar('\0')), decode_char_ref("&#0;"));
+}
+
+#[test]
+pub fn test_decode_entities() {
+    assert_eq!("plain", decode_entities("plain"));
+    assert_eq!("<a & 'b' \"c\">", decode_entities("&lt;a &amp; &apos;b&apos; &quot;c&quot;&gt;"));
+    assert_eq!("\u{e9}t\u{e9}", decode_entities("&#233;t&#xE9;"));
+    assert_eq!("&nbsp; & &#0; &&", decode_entities("&nbsp; & &#0; &&amp;"));
+    assert_eq!("a\u{a0}b", decode_entities_with("a&nbsp;b", &EntityTable::html()));
+}
+
+#[test]
+pub fn test_char_refs_in_documents() {
+    assert!(Document::parse("<a b=\"&#x100;\">&#x10FFFF;&#65;</a>").is_ok());
+    assert!(matches!(Document::parse("<a>&#xD800;</a>"), Err(UnknownReference { .. })));
+    assert!(matches!(Document::parse("<a>&#x1;</a>"), Err(UnknownReference { .. })));
+    // a reference must not hide the reserved namespace
+    assert!(matches!(Document::parse("<a xmlns:p=\"http://www.w3.org/XML/1998/namespac&#x65;\"/>"), Err(ReservedNamespace { .. })));
+}
+
+#[test]
+pub fn test_char_ref_policies() {
+    assert_eq!(Err(RefError::NotAnXmlChar('\u{1}')), decode_char_ref_with("&#x1;", &CharRefPolicy::Xml10));
+    assert_eq!(Ok('\u{1}'), decode_char_ref_with("&#x1;", &CharRefPolicy::Xml11));
+    assert_eq!(Err(RefError::NotAnXmlChar('\0')), decode_char_ref_with("&#0;", &CharRefPolicy::Xml11));
+    assert_eq!(Err(RefError::OutOfRange), decode_char_ref_with("&#xD800;", &CharRefPolicy::Xml11));
+    assert_eq!(Ok('\u{FFFD}'), decode_char_ref_with("&#xFFFD;", &CharRefPolicy::Bmp));
+    assert_eq!(Err(RefError::Disallowed('\u{1F600}')), decode_char_ref_with("&#x1F600;", &CharRefPolicy::Bmp));
+    let latin_1 = CharRefPolicy::Custom(|c| c.is_ascii_graphic() || ('\u{A0}'..='\u{FF}').contains(&c));
+    assert_eq!(Ok('é'), decode_char_ref_with("&#233;", &latin_1));
+    assert_eq!(Err(RefError::Disallowed('\n')), decode_char_ref_with("&#10;", &latin_1));
+
+    let parse = |xml, char_refs| Document::parse_with_options(xml, &ParseOptions { char_refs, ..Default::default() });
+    assert!(parse("<a b='&#x1;'>&#x7F;</a>", CharRefPolicy::Xml11).is_ok());
+    // characters written literally are XML 1.0 Chars whatever the policy
+    assert!(parse("<a>\u{1}</a>", CharRefPolicy::Xml11).is_err());
+    assert!(parse("<a>\u{1F600}</a>", CharRefPolicy::Bmp).is_ok());
+    match parse("<a>&#x1F600;</a>", CharRefPolicy::Bmp) {
+        Err(UnknownReference { reason, .. }) => assert_eq!(Some(RefError::Disallowed('\u{1F600}')), reason),
+        r => panic!("unexpected result {:?}", r),
+    }
+    match Document::parse("<a>&bogus;</a>") {
+        Err(UnknownReference { reason, .. }) => assert_eq!(None, reason),
+        r => panic!("unexpected result {:?}", r),
+    }
+}
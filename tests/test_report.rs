@@ -0,0 +1,56 @@
+use jaxp_rust::error::{XmlErrorPos, XmlWarning};
+use jaxp_rust::options::ParseOptions;
+use jaxp_rust::report::{Diagnostic, Severity, Source, Span, ValidationReport};
+
+#[test]
+pub fn test_well_formedness_error() {
+    let report = ValidationReport::check_well_formedness("<a>\n  <b></c>\n</a>", &ParseOptions::default());
+    assert!(!report.is_valid());
+    assert_eq!(Some(Severity::Error), report.max_severity());
+    let error = report.errors().next().unwrap();
+    assert_eq!(Source::WellFormedness, error.source);
+    assert_eq!("non-matching-tags", error.code);
+    assert_eq!(Some(2), error.span.as_ref().map(|s| s.start.row));
+    assert_eq!("end tag c does not match start tag b", error.message);
+}
+
+#[test]
+pub fn test_multibyte_names() {
+    for xml in ["<abé/>", "<a abé='1'/>"] {
+        assert!(ValidationReport::check_well_formedness(xml, &ParseOptions::default()).diagnostics.is_empty(), "{}", xml);
+    }
+}
+
+#[test]
+pub fn test_merge_and_display() {
+    let mut report = ValidationReport::check_well_formedness("<a b=''/>", &ParseOptions::default());
+    assert!(report.is_valid());
+    assert_eq!(Some(Severity::Warning), report.max_severity());
+    let mut rules = ValidationReport::new();
+    rules.push(Diagnostic::new(Severity::Info, Source::Rule, "deprecated-element", "a is deprecated"));
+    report.merge(rules);
+    assert_eq!(1, report.warnings().count());
+    assert_eq!(1, report.count(Severity::Info));
+    assert_eq!("1:4: warning[empty-attribute-value]: attribute b is empty\ninfo[deprecated-element]: a is deprecated\n", report.to_string());
+
+    let warning = XmlWarning::ReservedName { pos: XmlErrorPos { row: 3, col: 1 }, name: "xmlfoo".to_string() };
+    assert_eq!(Some(Span::at(XmlErrorPos { row: 3, col: 1 })), Diagnostic::from(&warning).span);
+}
+
+#[test]
+pub fn test_json() {
+    let mut report = ValidationReport::check_well_formedness("<a>", &ParseOptions::default());
+    report.push(Diagnostic::new(Severity::Warning, Source::Schema, "custom", "quote \" and\nline"));
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(false, json["valid"]);
+    assert_eq!(1, json["error_count"]);
+    assert_eq!(1, json["warning_count"]);
+    let diagnostics = json["diagnostics"].as_array().unwrap();
+    assert_eq!("error", diagnostics[0]["severity"]);
+    assert_eq!("well-formedness", diagnostics[0]["source"]);
+    assert_eq!("unexpected-end-of-file", diagnostics[0]["code"]);
+    assert!(diagnostics[0]["span"]["end"]["col"].is_u64());
+    assert_eq!("schema", diagnostics[1]["source"]);
+    assert!(diagnostics[1]["span"].is_null());
+    assert_eq!("quote \" and\nline", diagnostics[1]["message"]);
+}
@@ -0,0 +1,39 @@
+use jaxp_rust::entities::EntityTable;
+use jaxp_rust::node::XmlNode::*;
+use jaxp_rust::options::ParseOptions;
+use jaxp_rust::parse::XmlParser;
+use jaxp_rust::tokenize::{recycle_tokens, XmlTokenizer};
+
+#[test]
+pub fn test_parser_reuse() {
+    let options = ParseOptions { entities: EntityTable::html(), ..Default::default() };
+    let mut parser = XmlParser::with_options(&options);
+    for i in 0..3 {
+        // every document borrows from its own buffer
+        let xml = format!("<a n=\"{}\">&nbsp;</a>", i);
+        let root = parser.parse(&xml).unwrap();
+        let n = i.to_string();
+        assert_eq!(ElementNode { name: "a", children: vec![AttributeNode { name: "n", value: &n }, TextNode("&nbsp;")] }, root);
+    }
+    // configuration and state after an error
+    assert!(parser.parse("<?xml version=\"1.0\" standalone=\"yes\"?><a>&nbsp;</a>").is_err());
+    assert!(parser.parse("<a>&nbsp;</a>").is_ok());
+    parser.reset();
+    assert!(parser.parse("<a/>").is_ok());
+}
+
+#[test]
+pub fn test_tokenizer_buffer_reuse() {
+    let mut tokenizer = XmlTokenizer::default();
+    let mut tokens = vec![];
+    tokenizer.tokenize_into("<a><b/><c x=\"1\"/></a>", &mut tokens).unwrap();
+    assert_eq!(tokenizer.tokenize("<a><b/><c x=\"1\"/></a>").unwrap().len(), tokens.len());
+    let capacity = tokens.capacity();
+
+    let xml = String::from("<a/>");
+    let mut tokens = recycle_tokens(tokens);
+    assert!(tokens.is_empty());
+    tokenizer.tokenize_into(&xml, &mut tokens).unwrap();
+    assert_eq!(2, tokens.len());
+    assert_eq!(capacity, tokens.capacity());
+}
@@ -0,0 +1,46 @@
+use jaxp_rust::error::Expected;
+use jaxp_rust::error::XmlError::*;
+use jaxp_rust::scan::Scanner;
+
+#[test]
+pub fn test_scanner() {
+    // a template tag like <%@ include file="a &amp; b.jsp" %>
+    let mut scanner = Scanner::new("<%@ include file=\"a &amp; b.jsp\" %>rest");
+    scanner.expect("<%@").unwrap();
+    assert!(scanner.skip_spaces());
+    assert_eq!("include", scanner.name().unwrap().slice());
+    scanner.expect_spaces().unwrap();
+    let (name, value) = scanner.attribute().unwrap();
+    assert_eq!(("file", "a &amp; b.jsp"), (name.slice(), value.slice()));
+    assert!(!scanner.test("%>"));
+    scanner.skip_spaces();
+    assert!(scanner.skip("%>"));
+    assert_eq!("rest", scanner.rest());
+    assert_eq!(35, scanner.pos());
+    assert_eq!('r', scanner.peek().unwrap());
+    assert_eq!('r', scanner.next_char().unwrap());
+    assert_eq!("es", scanner.until("t").unwrap().slice());
+    assert!(scanner.skip("t"));
+    assert!(scanner.is_at_end());
+
+    let mut scanner = Scanner::new("'a < b' \"c & d\"");
+    assert_eq!("a < b", scanner.quoted().unwrap().slice());
+    scanner.skip_spaces();
+    assert!(matches!(scanner.attribute_value(), Err(UnknownReference { .. })));
+    let mut scanner = Scanner::new("'e' \"f < g\"");
+    assert_eq!("e", scanner.attribute_value().unwrap().slice());
+    scanner.skip_spaces();
+    assert!(matches!(scanner.attribute_value(), Err(IllegalToken { expected: Some(Expected::NoLessThan), .. })));
+
+    // failed names and quotes do not move the scanner
+    let mut scanner = Scanner::new("1x y");
+    assert!(scanner.name().is_err());
+    assert!(matches!(scanner.quoted(), Err(IllegalToken { expected: Some(Expected::Quote), .. })));
+    assert_eq!(0, scanner.pos());
+    assert!(!scanner.skip_spaces());
+    assert!(scanner.expect_spaces().is_err());
+    assert!(!scanner.skip("1y"));
+    assert!(matches!(scanner.expect("1y"), Err(IllegalToken { .. })));
+    assert!(matches!(scanner.error(None), IllegalToken { expected: None, .. }));
+    assert!(matches!(scanner.until("%>"), Err(UnexpectedEndOfFile { .. })));
+}
@@ -0,0 +1,40 @@
+#![cfg(feature = "serde")]
+
+use jaxp_rust::error::XmlError;
+use jaxp_rust::parse::XmlParser;
+use jaxp_rust::tokenize::XmlTokenizer;
+use serde_json::json;
+
+#[test]
+pub fn test_serialize_tokens() {
+    let tokens = XmlTokenizer::default().tokenize("<a x=\"1\">b</a>").unwrap();
+    assert_eq!(
+        json!([
+            { "StartTag": { "start": 1, "end": 2 } },
+            { "Attribute": { "name_range": { "start": 3, "end": 4 }, "value_range": { "start": 6, "end": 7 } } },
+            { "Text": { "start": 9, "end": 10 } },
+            { "EndTag": { "start": 12, "end": 13 } },
+        ]),
+        serde_json::to_value(&tokens).unwrap()
+    );
+}
+
+#[test]
+pub fn test_serialize_nodes() {
+    let root = XmlParser::default().parse("<a x=\"1\">b</a>").unwrap();
+    assert_eq!(
+        json!({ "ElementNode": { "name": "a", "children": [
+            { "AttributeNode": { "name": "x", "value": "1" } },
+            { "TextNode": "b" },
+        ] } }),
+        serde_json::to_value(&root).unwrap()
+    );
+}
+
+#[test]
+pub fn test_error_round_trip() {
+    let err = XmlParser::default().parse("<a></b>").unwrap_err();
+    let json = serde_json::to_string(&err).unwrap();
+    let deserialized: XmlError = serde_json::from_str(&json).unwrap();
+    assert_eq!(format!("{:?}", err), format!("{:?}", deserialized));
+}
@@ -0,0 +1,66 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::shard::{merge, split};
+use jaxp_rust::writer::XmlWriter;
+
+#[test]
+pub fn test_split() {
+    let xml = "<?xml version='1.0'?>\n<feed xmlns='urn:feed' xmlns:x='urn:x' id='f'>\n  <entry n='1'/>\n  <x:entry>2</x:entry>\n  <!-- three -->\n  <entry><a>3</a></entry>\n  <entry/>\n  <entry/>\n</feed>\n";
+    let shards = split(xml, 2).unwrap();
+    let expected = vec![
+        "<feed xmlns='urn:feed' xmlns:x='urn:x' id='f'>\n  <entry n='1'/>\n  <x:entry>2</x:entry>\n  <!-- three -->\n  <entry><a>3</a></entry></feed>",
+        "<feed xmlns='urn:feed' xmlns:x='urn:x' id='f'>\n  <entry/>\n  <entry/>\n</feed>",
+    ];
+    assert_eq!(expected, shards);
+    for shard in &shards {
+        let doc = Document::parse(shard).unwrap();
+        assert_eq!(Some("urn:x"), doc.root_element().lookup_namespace(Some("x")));
+    }
+
+    assert_eq!(5, split(xml, 5).unwrap().len());
+    assert_eq!(5, split(xml, 9).unwrap().len());
+    let whole = split(xml, 1).unwrap();
+    assert_eq!(1, whole.len());
+    assert_eq!(xml[xml.find("<feed").unwrap()..].trim_end(), whole[0]);
+
+    // the content between records goes with the record after it, the content after the last record with the last shard
+    let xml = "<feed>a<!--b--><r>1</r>c<?pi d?><r>2</r>e<r>3</r><!--f-->g</feed>";
+    assert_eq!(
+        vec!["<feed>a<!--b--><r>1</r>c<?pi d?><r>2</r></feed>", "<feed>e<r>3</r><!--f-->g</feed>"],
+        split(xml, 2).unwrap(),
+    );
+    assert_eq!(vec![xml], split(xml, 1).unwrap());
+
+    // documents without records
+    assert_eq!(vec!["<feed a='1'/>"], split("<feed a='1'/>", 3).unwrap());
+    assert_eq!(vec!["<feed>text</feed>"], split(" <feed>text</feed>", 3).unwrap());
+
+    assert!(split("<feed><a></b></feed>", 2).is_err());
+    assert!(split("<feed><a/>", 2).is_err());
+    assert!(split("", 2).is_err());
+}
+
+#[test]
+#[should_panic(expected = "a document is split into at least one shard")]
+pub fn test_split_into_no_shards() {
+    let _ = split("<feed><a/></feed>", 0);
+}
+
+#[test]
+pub fn test_merge() {
+    let first = "<?xml version='1.0'?><!-- feed 1 --><feed xmlns='urn:feed' xmlns:x='urn:x' id='1'><entry>a</entry><x:entry/></feed>";
+    let second = "<feed xmlns:x='urn:y'>\n<entry x:n='1'>b &amp; c</entry><!-- c --></feed>";
+    let third = "<feed xmlns='urn:feed'><entry xmlns:x='urn:z'/></feed>";
+    let mut writer = XmlWriter::new(Vec::new());
+    merge([first, second, third], "all", &mut writer).unwrap();
+    let merged = String::from_utf8(writer.finish().unwrap()).unwrap();
+    let expected = "<all xmlns=\"urn:feed\" xmlns:x=\"urn:x\"><entry>a</entry><x:entry/>\n<entry x:n=\"1\" xmlns:x=\"urn:y\" xmlns=\"\">b &amp; c</entry><!-- c --><entry xmlns:x=\"urn:z\"/></all>";
+    assert_eq!(expected, merged);
+    Document::parse(&merged).unwrap();
+
+    let mut writer = XmlWriter::new(Vec::new());
+    merge([], "all", &mut writer).unwrap();
+    assert_eq!(b"<all/>".to_vec(), writer.finish().unwrap());
+
+    let mut writer = XmlWriter::new(Vec::new());
+    assert!(merge(["<a/>", "<a><b></a>"], "all", &mut writer).is_err());
+}
@@ -0,0 +1,75 @@
+use std::thread;
+
+use jaxp_rust::document::{Document, OwnedDocument, SharedDocument};
+use jaxp_rust::writer::serialize;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+pub fn test_send_sync() {
+    assert_send_sync::<Document>();
+    assert_send_sync::<OwnedDocument>();
+    assert_send_sync::<SharedDocument>();
+}
+
+#[test]
+pub fn test_query_from_threads() {
+    let shared = OwnedDocument::parse("<root><a>1</a><a>2</a><a>3</a></root>".to_string()).unwrap().into_shared();
+    let handles: Vec<_> = (0..3).map(|i| {
+        let shared = shared.clone();
+        thread::spawn(move || {
+            let doc = shared.document();
+            doc.root_element().children().nth(i).unwrap().text().unwrap().to_string()
+        })
+    }).collect();
+    let texts: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert_eq!(vec!["1", "2", "3"], texts);
+}
+
+#[test]
+pub fn test_copy_on_write() {
+    let original = OwnedDocument::parse("<root attr=\"old\"><a>text</a></root>".to_string()).unwrap().into_shared();
+    let mut edited = original.clone();
+    assert!(edited.is_shared());
+
+    let root_id = edited.document().root_element().id();
+    let a_id = edited.document().root_element().first_child().unwrap().id();
+    {
+        let doc = edited.make_mut();
+        let mut root = doc.node_mut(root_id).unwrap();
+        assert!(root.set_attribute("attr", "<new>"));
+        assert!(root.set_attribute("other", "value"));
+        let b = root.append_element("b");
+        doc.node_mut(b).unwrap().append_text("a & b");
+        doc.node_mut(a_id).unwrap().set_text("changed");
+    }
+    assert!(!edited.is_shared());
+
+    assert_eq!("<root attr=\"old\"><a>text</a></root>", serialize(original.document().root()));
+    assert_eq!(
+        "<root attr=\"&lt;new&gt;\" other=\"value\"><a>changed</a><b>a &amp; b</b></root>",
+        serialize(edited.document().root())
+    );
+
+    edited.make_mut().node_mut(a_id).unwrap().detach();
+    let names: Vec<_> = edited.document().descendants().map(|n| n.tag_name()).collect();
+    assert_eq!(vec!["", "root", "b", ""], names);
+}
+
+#[test]
+pub fn test_owned_attribute_edits() {
+    let mut doc = OwnedDocument::parse("<root attr=\"value\" other=\"old\"/>".to_string()).unwrap();
+    let id = doc.document().root_element().id();
+    let (removed, previous) = {
+        let mut root = doc.node_mut(id).unwrap();
+        let mut attributes = root.attributes_mut().unwrap();
+        let removed = attributes.remove("attr").unwrap();
+        let previous = attributes.insert("other", "new").unwrap();
+        assert_eq!(Some("new"), attributes.get("other").map(|a| a.value()));
+        // values taken out borrow from the document, copies outlive it
+        (removed.value().to_string(), previous.into_owned())
+    };
+    drop(doc);
+    assert_eq!("value", removed);
+    assert_eq!("old", previous);
+}
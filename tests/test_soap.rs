@@ -0,0 +1,145 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::soap::{Envelope, EnvelopeBuilder, FaultCode, SoapError, SoapVersion, SOAP_11_NAMESPACE};
+
+const REQUEST_12: &str = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+  <env:Header>
+    <t:transaction xmlns:t="urn:tx" env:mustUnderstand="true">5</t:transaction>
+    <n:notify xmlns:n="urn:notify" env:role="http://example.org/logger" env:mustUnderstand="1"/>
+    <n:trace xmlns:n="urn:notify" env:role="http://www.w3.org/2003/05/soap-envelope/role/none" env:mustUnderstand="1" env:relay="true"/>
+  </env:Header>
+  <env:Body>
+    <m:GetPrice xmlns:m="urn:shop"><m:Item>Apples</m:Item></m:GetPrice>
+  </env:Body>
+</env:Envelope>"#;
+
+#[test]
+pub fn test_header_blocks() {
+    let doc = Document::parse(REQUEST_12).unwrap();
+    let envelope = Envelope::from_document(&doc).unwrap();
+    assert_eq!(SoapVersion::Soap12, envelope.version());
+    let blocks: Vec<_> = envelope.header_blocks().collect();
+    assert_eq!(3, blocks.len());
+    assert_eq!("transaction", blocks[0].node().local_name());
+    assert!(blocks[0].must_understand());
+    assert_eq!(None, blocks[0].role());
+    assert_eq!(Some("http://example.org/logger"), blocks[1].role());
+    assert!(!blocks[1].is_targeted_at(&[]));
+    assert!(blocks[1].is_targeted_at(&["http://example.org/logger"]));
+    assert!(blocks[2].relay());
+    assert!(!blocks[2].is_targeted_at(&[]));
+    assert_eq!(Some("GetPrice"), envelope.body_content().map(|c| c.local_name()));
+    assert!(envelope.fault().is_none());
+}
+
+#[test]
+pub fn test_must_understand() {
+    let doc = Document::parse(REQUEST_12).unwrap();
+    let envelope = Envelope::from_document(&doc).unwrap();
+    assert_eq!(Err(SoapError::NotUnderstood(vec!["{urn:tx}transaction".to_string()])), envelope.check_must_understand(&[], |_| false));
+    assert_eq!(Ok(()), envelope.check_must_understand(&[], |b| b.node().has_tag_name_ns("urn:tx", "transaction")));
+    assert_eq!(
+        Err(SoapError::NotUnderstood(vec!["{urn:notify}notify".to_string()])),
+        envelope.check_must_understand(&["http://example.org/logger"], |b| b.node().local_name() == "transaction")
+    );
+}
+
+#[test]
+pub fn test_soap_11() {
+    let xml = r#"<SOAP-ENV:Envelope xmlns:SOAP-ENV="http://schemas.xmlsoap.org/soap/envelope/">
+  <SOAP-ENV:Header>
+    <t:Transaction xmlns:t="urn:tx" SOAP-ENV:mustUnderstand="true" SOAP-ENV:actor="http://schemas.xmlsoap.org/soap/actor/next">5</t:Transaction>
+  </SOAP-ENV:Header>
+  <SOAP-ENV:Body>
+    <SOAP-ENV:Fault>
+      <faultcode>SOAP-ENV:Client</faultcode>
+      <faultstring>Price &amp; item unknown</faultstring>
+      <faultactor>urn:shop</faultactor>
+      <detail><e:code xmlns:e="urn:errors">42</e:code></detail>
+    </SOAP-ENV:Fault>
+  </SOAP-ENV:Body>
+  <x:trailer xmlns:x="urn:x"/>
+</SOAP-ENV:Envelope>"#;
+    let doc = Document::parse(xml).unwrap();
+    let envelope = Envelope::from_document(&doc).unwrap();
+    assert_eq!(SoapVersion::Soap11, envelope.version());
+    assert_eq!("text/xml", envelope.version().content_type());
+    let block = envelope.header_blocks().next().unwrap();
+    // only "1" is true in SOAP 1.1
+    assert!(!block.must_understand());
+    assert!(block.is_targeted_at(&[]));
+    let fault = envelope.fault().unwrap();
+    assert_eq!(Some(FaultCode::Sender), fault.code());
+    assert_eq!(Some("Price & item unknown".to_string()), fault.reason());
+    assert_eq!(Some("urn:shop".to_string()), fault.role());
+    assert_eq!(Some("code"), fault.detail().and_then(|d| d.first_element_child()).map(|c| c.local_name()));
+}
+
+#[test]
+pub fn test_fault_12() {
+    let xml = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope" xmlns:app="urn:app">
+  <env:Body>
+    <env:Fault>
+      <env:Code><env:Value>app:OutOfStock</env:Value></env:Code>
+      <env:Reason><env:Text xml:lang="en">Out of stock</env:Text><env:Text xml:lang="de">Ausverkauft</env:Text></env:Reason>
+      <env:Detail><app:item>Apples</app:item></env:Detail>
+    </env:Fault>
+  </env:Body>
+</env:Envelope>"#;
+    let doc = Document::parse(xml).unwrap();
+    let fault = Envelope::from_document(&doc).unwrap().fault().unwrap();
+    assert_eq!(Some(FaultCode::Other { namespace: Some("urn:app".to_string()), local_name: "OutOfStock".to_string() }), fault.code());
+    assert_eq!(Some("Out of stock".to_string()), fault.reason());
+    assert_eq!(None, fault.role());
+    assert!(fault.detail().is_some());
+}
+
+#[test]
+pub fn test_invalid_envelopes() {
+    let cases = [
+        ("<Envelope/>", SoapError::VersionMismatch(None)),
+        ("<e:Envelope xmlns:e=\"urn:other\"/>", SoapError::VersionMismatch(Some("urn:other".to_string()))),
+        ("<e:Message xmlns:e=\"http://schemas.xmlsoap.org/soap/envelope/\"/>", SoapError::NotAnEnvelope("e:Message".to_string())),
+        (
+            "<e:Envelope xmlns:e=\"http://schemas.xmlsoap.org/soap/envelope/\"><e:Header/></e:Envelope>",
+            SoapError::InvalidEnvelope("expected a Body after the optional Header".to_string()),
+        ),
+        (
+            "<e:Envelope xmlns:e=\"http://www.w3.org/2003/05/soap-envelope\"><e:Body/><e:Header/></e:Envelope>",
+            SoapError::InvalidEnvelope("unexpected element e:Header after the Body".to_string()),
+        ),
+    ];
+    for (xml, expected) in cases.iter() {
+        let doc = Document::parse(xml).unwrap();
+        assert_eq!(expected, &Envelope::from_document(&doc).unwrap_err(), "{}", xml);
+    }
+}
+
+#[test]
+pub fn test_build() {
+    let blocks = Document::parse("<t:transaction xmlns:t=\"urn:tx\">5</t:transaction>").unwrap();
+    let request = Document::parse("<r xmlns:m=\"urn:shop\"><m:GetPrice><m:Item>Apples &amp; pears</m:Item></m:GetPrice></r>").unwrap();
+    let content = request.root_element().first_element_child().unwrap();
+    let message = EnvelopeBuilder::new(SoapVersion::Soap11).header_block(blocks.root_element()).body_content(content).build();
+    let doc = Document::parse(&message).unwrap();
+    let envelope = Envelope::from_document(&doc).unwrap();
+    assert_eq!(SOAP_11_NAMESPACE, envelope.node().namespace_uri().unwrap());
+    assert!(envelope.header_blocks().next().unwrap().node().has_tag_name_ns("urn:tx", "transaction"));
+    let content = envelope.body_content().unwrap();
+    assert!(content.has_tag_name_ns("urn:shop", "GetPrice"));
+    assert_eq!(Some("Apples &amp; pears"), content.child_element_ns("urn:shop", "Item").and_then(|i| i.text()));
+}
+
+#[test]
+pub fn test_build_fault() {
+    let detail = Document::parse("<e:code xmlns:e=\"urn:errors\">42</e:code>").unwrap();
+    for version in [SoapVersion::Soap11, SoapVersion::Soap12].iter() {
+        for code in [FaultCode::MustUnderstand, FaultCode::Receiver, FaultCode::Other { namespace: Some("urn:app".to_string()), local_name: "Busy".to_string() }].iter() {
+            let message = EnvelopeBuilder::new(*version).fault(code, "try <later>", Some(detail.root_element())).build();
+            let doc = Document::parse(&message).unwrap();
+            let fault = Envelope::from_document(&doc).unwrap().fault().unwrap();
+            assert_eq!(Some(code), fault.code().as_ref(), "{}", message);
+            assert_eq!(Some("try <later>".to_string()), fault.reason());
+            assert!(fault.detail().unwrap().child_element_ns("urn:errors", "code").is_some());
+        }
+    }
+}
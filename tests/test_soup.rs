@@ -0,0 +1,78 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::error::XmlError::*;
+use jaxp_rust::options::{ParseOptions, Strictness};
+use jaxp_rust::profile::Profile;
+use jaxp_rust::writer::serialize;
+
+fn soup(html: &str) -> String {
+    let options = ParseOptions { strictness: Strictness::TagSoup, ..Default::default() };
+    let doc = Document::parse_with_options(html, &options).unwrap_or_else(|e| panic!("{} in {}", e, html));
+    let xml = serialize(doc.root());
+    // the tree of tag soup is always well-formed
+    assert!(Document::parse(&xml).is_ok(), "{}", xml);
+    xml
+}
+
+#[test]
+pub fn test_soup_attributes() {
+    assert_eq!("<a href=\"x.html\" checked=\"\" id=\"1\"/>", soup("<a href=x.html checked id='1'/>"));
+    assert_eq!("<a x=\"1\"/>", soup("<a x=1 x=2></a>"));
+    assert_eq!("<a title=\"&quot;q&quot; &amp; &lt;\" v=\"a/b\"/>", soup("<a title='\"q\" & <' v=a/b>"));
+}
+
+#[test]
+pub fn test_soup_tag_matching() {
+    assert_eq!("<DIV><p>a<BR/>b<img src=\"i\"/></p></DIV>", soup("<DIV><p>a<BR>b<img src=i></P></div>"));
+    assert_eq!("<ul><li>one</li><li>two<b>bold</b></li></ul>", soup("<ul><li>one<li>two<b>bold</ul></i>"));
+    assert_eq!("<body><p>a</p><div>b</div><p>c</p></body>", soup("<body><p>a<div>b</div><p>c"));
+    assert_eq!("<table><tr><td>1</td><td>2</td></tr><tr><th>3</th></tr></table>", soup("<table><tr><td>1<td>2<tr><th>3</table>"));
+}
+
+#[test]
+pub fn test_soup_text() {
+    assert_eq!("<p>1 &lt; 2 &amp;&amp; &amp;nbsp; &#160; &gt;</p>", soup("<p>1 < 2 && &nbsp; &#160; ></p>"));
+    assert_eq!("<p><!-- a - - - b --></p><?pi v?>", soup("<!DOCTYPE html><?xml version=\"1.0\"?><p><!-- a --- b --></p><?pi v?>"));
+    assert!(matches!(Document::parse_with_options("just text", &ParseOptions { strictness: Strictness::TagSoup, ..Default::default() }), Err(UnexpectedEndOfFile { .. })));
+}
+
+#[test]
+pub fn test_soup_top_level() {
+    // the first element is the root element and takes in the text and elements around it
+    assert_eq!("<a><b/></a>", soup("<a/><b/>"));
+    assert_eq!("<a>trailing</a>", soup("<a/>trailing"));
+    assert_eq!("<a>lead</a>", soup("lead<a/>"));
+    assert_eq!("<!--c--><a>lead x<!--d--><b/>tail</a><!--e--><?pi?>", soup("<!--c-->lead <a>x</a><!--d--><b/>tail<!--e--><?pi?>"));
+    assert_eq!("<html><body>a</body><script/></html>", soup("<html><body>a</body></html>\n<script></script>\n"));
+}
+
+#[test]
+pub fn test_soup_truncated_input() {
+    let html = "<html><head><title>t</title></head><body class=main><p id=\"x\">a &amp; b<br><!-- c --><![CDATA[d]]></body></html>";
+    for end in (1..=html.len()).filter(|end| html.is_char_boundary(*end)) {
+        let options = ParseOptions { strictness: Strictness::TagSoup, ..Default::default() };
+        if let Ok(doc) = Document::parse_with_options(&html[..end], &options) {
+            let xml = serialize(doc.root());
+            assert!(Document::parse(&xml).is_ok(), "{}", xml);
+        }
+    }
+}
+
+#[test]
+pub fn test_soup_profiles() {
+    let with_profile = |markup: &str, profile: Profile| serialize(Document::parse_with_options(markup, &profile.parse_options()).unwrap().root());
+    assert_eq!("<p>caf\u{e9}<br/>&#160;</p>", with_profile("<p>caf&eacute;<br>&#160;", Profile::xhtml()));
+    // case-sensitive names and no void elements
+    assert_eq!(
+        "<svg><linearGradient><stop/></linearGradient><g><image>&amp;nbsp;</image></g></svg>",
+        with_profile("<svg><linearGradient><stop/></LinearGradient></linearGradient><g><image>&nbsp;</svg>", Profile::svg())
+    );
+
+    let mut profile = Profile::svg();
+    profile.void_elements.push("stop".to_string());
+    profile.implied_end_tags.push(("text".to_string(), vec!["text".to_string()]));
+    profile.entities.insert("ns_svg", "http://www.w3.org/2000/svg");
+    assert_eq!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\"><stop/><text>a</text><text>b</text></svg>",
+        with_profile("<svg xmlns=\"&ns_svg;\"><stop><text>a<text>b</svg>", profile)
+    );
+}
@@ -0,0 +1,55 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::error::XmlError::*;
+use jaxp_rust::generator::{generate, GeneratorOptions};
+use jaxp_rust::stats::{stats, DocumentStats};
+
+#[test]
+pub fn test_stats() {
+    let xml = "<?xml version=\"1.0\"?>\n<root xmlns=\"urn:a\" xmlns:b=\"urn:b\"><b:x y=\"1\">text<![CDATA[cdata]]></b:x><c xmlns:d=\"urn:a\"><d/></c></root>\n";
+    assert_eq!(DocumentStats {
+        element_count: 4,
+        max_depth: 3,
+        attribute_count: 4,
+        text_bytes: 9,
+        namespace_count: 2,
+    }, stats(xml).unwrap());
+}
+
+#[test]
+pub fn test_stats_of_generated() {
+    let xml = generate(&GeneratorOptions { seed: 3, ..Default::default() });
+    let doc = Document::parse(&xml).unwrap();
+    let stats = stats(&xml).unwrap();
+    assert_eq!(doc.descendants().filter(|n| n.is_element()).count(), stats.element_count);
+    assert_eq!(doc.descendants().map(|n| n.attributes().count()).sum::<usize>(), stats.attribute_count);
+}
+
+#[test]
+pub fn test_stats_errors() {
+    assert!(matches!(stats("<a></b>"), Err(NonMatchingTags { .. })));
+    assert!(matches!(stats("<a><b></b>"), Err(UnexpectedEndOfFile { .. })));
+}
+
+#[test]
+pub fn test_metrics() {
+    let xml = "<root xmlns=\"urn:a\"><x y=\"1\" z=\"2\">text<![CDATA[cdata]]></x><!--c--><x y=\"3\"><d/></x></root>";
+    let metrics = Document::parse(xml).unwrap().metrics();
+    assert_eq!(vec![1, 2, 1], metrics.depth_histogram);
+    assert_eq!(vec![("d", 1), ("root", 1), ("x", 2)], metrics.tag_counts.iter().map(|(k, v)| (k.as_str(), *v)).collect::<Vec<_>>());
+    assert_eq!(vec![1, 2, 1], metrics.attribute_histogram);
+    assert_eq!(vec![("xmlns", 1), ("y", 2), ("z", 1)], metrics.attribute_counts.iter().map(|(k, v)| (k.as_str(), *v)).collect::<Vec<_>>());
+    assert_eq!(9, metrics.text_bytes);
+    assert_eq!(xml.len() - 9, metrics.markup_bytes);
+    assert_eq!(4, metrics.element_count());
+    assert_eq!(3, metrics.max_depth());
+    assert_eq!(2, metrics.max_attributes());
+    assert!((metrics.text_ratio() - 9.0 / xml.len() as f64).abs() < 1e-9);
+
+    let xml = generate(&GeneratorOptions { seed: 5, ..Default::default() });
+    let metrics = Document::parse(&xml).unwrap().metrics();
+    let stats = stats(&xml).unwrap();
+    assert_eq!(stats.element_count, metrics.element_count());
+    assert_eq!(stats.max_depth, metrics.max_depth());
+    assert_eq!(stats.text_bytes, metrics.text_bytes);
+    assert_eq!(stats.attribute_count, metrics.attribute_counts.values().sum::<usize>());
+}
@@ -0,0 +1,89 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::pull::PullParser;
+use jaxp_rust::streaming::{Match, StreamingPath};
+use jaxp_rust::xpath::{Item, XPath, XPathError};
+
+const XML: &str = "<shop xmlns:x=\"urn:x\">\
+    <order id=\"1\" status=\"open\"><item sku=\"a\">A</item><item sku=\"b\">B<![CDATA[!]]></item><note><item sku=\"c\">C</item></note></order>\
+    <order id=\"2\" status=\"closed\" x:flag=\"y\"><item sku=\"d\">D</item></order>\
+    <order id=\"3\" status=\"op&#101;n\"><item>E</item><item sku=\"f\">F</item></order>\
+</shop>";
+
+/// The matches as they would be selected from a tree: element names, attribute values and text.
+fn stream(path: &str) -> Vec<String> {
+    StreamingPath::compile(path).unwrap()
+        .matches(PullParser::new(XML).unwrap())
+        .map(|m| match m.unwrap() {
+            Match::Element { name, .. } => name.to_string(),
+            Match::Attribute(attribute) => attribute.value().to_string(),
+            Match::Text(text) => text.to_string(),
+        })
+        .collect()
+}
+
+fn select(doc: &Document, path: &str) -> Vec<String> {
+    XPath::compile(path).unwrap().select(doc.root()).unwrap().iter()
+        .map(|item| match item {
+            Item::Node(node) if node.is_element() => node.tag_name().to_string(),
+            Item::Node(node) => node.text().unwrap().to_string(),
+            Item::Attribute(..) => item.attribute().unwrap().value().to_string(),
+        })
+        .collect()
+}
+
+#[test]
+pub fn test_streaming_matches_tree() {
+    let doc = Document::parse(XML).unwrap();
+    for path in [
+        "/shop/order",
+        "shop/order/item",
+        "//item",
+        "/shop//note/item",
+        "/shop/*/*[1]",
+        "//order[@status = 'open']/item[2]",
+        "//order[@status != 'open' or not(@id)]/@id",
+        "//order[@id and not(@x:flag)][2]/item/text()",
+        "//item/@sku",
+        "//@*",
+        "//order/@x:*",
+        "//order[1]//text()",
+        "//item[1]",
+        "/shop/order[@status='open']/descendant::item/@sku",
+        "//missing",
+    ] {
+        assert_eq!(select(&doc, path), stream(path), "{}", path);
+    }
+}
+
+#[test]
+pub fn test_streaming_match_values() {
+    let path = StreamingPath::compile("//order[3]").unwrap();
+    let matches: Vec<Match> = path.matches(PullParser::new(XML).unwrap()).collect::<Result<_, _>>().unwrap();
+    assert_eq!(1, matches.len());
+    match &matches[0] {
+        Match::Element { name, attributes, depth } => {
+            assert_eq!(("order", 1), (*name, *depth));
+            assert_eq!(Some("op&#101;n"), attributes.get("status").map(|a| a.value()));
+        }
+        m => panic!("{:?}", m),
+    }
+    // errors of the document are reported in the stream
+    let mut matches = path.matches(PullParser::new("<shop><order></shop>").unwrap());
+    assert!(matches.next().unwrap().is_err());
+    assert!(matches.next().is_none());
+    // matches before the error are reported first
+    let path = StreamingPath::compile("//order/@id").unwrap();
+    let mut matches = path.matches(PullParser::new("<shop><order id='1'/><order id='2'/><order id='3'></shop>").unwrap());
+    assert!(matches!(matches.next(), Some(Ok(Match::Attribute { .. }))));
+    assert!(matches!(matches.next(), Some(Ok(Match::Attribute { .. }))));
+    assert!(matches!(matches.next(), Some(Ok(Match::Attribute { .. }))));
+    assert!(matches.next().unwrap().is_err());
+}
+
+#[test]
+pub fn test_streaming_unsupported() {
+    for path in ["//item/..", "//item/ancestor::order", "//order[last()]", "/shop/descendant::item[1]", "//item[. = 'A']", "//comment()", "/", "//@id/x", "count(//a)", "//order[@id = @status]"] {
+        assert!(matches!(StreamingPath::compile(path), Err(XPathError::Unsupported(_))), "{}", path);
+    }
+    assert!(matches!(StreamingPath::compile("//item["), Err(XPathError::Syntax { .. })));
+}
@@ -0,0 +1,47 @@
+use jaxp_rust::assert_xml_eq;
+use jaxp_rust::diff::DiffOptions;
+use jaxp_rust::testutil::{assert_golden, assert_xml_eq_with};
+
+#[test]
+pub fn test_assert_xml_eq() {
+    assert_xml_eq!("<a y='2' x='1'>\n  <b>some  text</b>\n</a>", "<a x=\"1\" y=\"2\"><b>some text</b></a>");
+    assert_xml_eq!(String::from("<a/>"), "<a></a>",);
+}
+
+#[test]
+pub fn test_failure_message() {
+    let message = std::panic::catch_unwind(|| assert_xml_eq!("<a><b/><c x='1'/></a>", "<a><c x='2'/></a>"))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap();
+    assert!(message.starts_with("XML documents differ (- expected, + actual):\n"), "{}", message);
+    assert!(message.contains("+ /a[1]/b[1]: <b/>\n~ /a[1]/c[1]/@x: \"2\" -> \"1\""), "{}", message);
+    assert!(message.contains("actual:\n<a>\n  <b/>"), "{}", message);
+}
+
+#[test]
+#[should_panic(expected = "the actual XML is not well-formed")]
+pub fn test_not_well_formed() {
+    assert_xml_eq!("<a>", "<a/>");
+}
+
+#[test]
+#[should_panic(expected = "XML documents differ")]
+pub fn test_strict_options() {
+    assert_xml_eq_with("<a y='2' x='1'/>", "<a x='1' y='2'/>", &DiffOptions::default());
+}
+
+#[test]
+pub fn test_golden() {
+    let path = format!("{}/golden/test_golden.xml", env!("CARGO_TARGET_TMPDIR"));
+    let _ = std::fs::remove_file(&path);
+    let message = std::panic::catch_unwind(|| assert_golden("<a><b/></a>", &path)).unwrap_err().downcast::<String>().unwrap();
+    assert!(message.ends_with("does not exist, run with JAXP_UPDATE_GOLDEN=1 to create it"), "{}", message);
+    assert!(!std::path::Path::new(&path).exists());
+
+    std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap()).unwrap();
+    std::fs::write(&path, "<a><b/></a>").unwrap();
+    assert_golden("<a><b/></a>", &path);
+    assert_golden("<a>\n  <b/>\n</a>", &path);
+    assert!(std::panic::catch_unwind(|| assert_golden("<a><c/></a>", &path)).is_err());
+}
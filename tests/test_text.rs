@@ -1,6 +1,5 @@
 extern crate core;
 
-use jaxp_rust::error::XmlError::*;
 use jaxp_rust::node::XmlNode;
 use jaxp_rust::node::XmlNode::TextNode;
 use jaxp_rust::parse::XmlParser;
@@ -72,8 +71,18 @@ pub fn test_invalid_unicode() {
     for illegal_text in illegal_texts {
         let xml = format!("<root>{}</root>", illegal_text);
         // error type can vary
-        let actual_err = XmlParser::default().parse(&xml).unwrap_err();
+        let _actual_err = XmlParser::default().parse(&xml).unwrap_err();
     }
 }
 
 //TODO test text before and after root element
+
+#[test]
+pub fn test_tree_display() {
+    let xml = "<root a='1'>some text<b><!--c--></b><?pi v?></root>";
+    let root = XmlParser::default().parse(xml).unwrap();
+    let expected = "Element root\n  Attribute a=\"1\"\n  Text \"some text\"\n  Element b\n    Comment \"c\"\n  ProcessingInstruction pi \"v\"\n";
+    assert_eq!(expected, root.to_string());
+    assert_eq!(expected, format!("{:#?}", root));
+    assert_eq!("TextNode(\"x\")", format!("{:?}", TextNode("x")));
+}
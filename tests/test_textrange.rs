@@ -0,0 +1,71 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::error::XmlErrorPos;
+use jaxp_rust::textrange::{LineIndex, TextRange};
+use jaxp_rust::token::XmlToken;
+use jaxp_rust::tokenize::XmlTokenizer;
+
+fn texts<'a>(tokens: &[XmlToken<'a>]) -> Vec<TextRange<'a>> {
+    tokens.iter().filter_map(|t| match t { XmlToken::Text(range) => Some(*range), _ => None }).collect()
+}
+
+#[test]
+pub fn test_accessors() {
+    let xml = "<a>\n  héllo \n<b/></a>";
+    let tokens = XmlTokenizer::default().tokenize(xml).unwrap();
+    let text = texts(&tokens)[0];
+    assert_eq!(3, text.start());
+    assert_eq!(14, text.end());
+    assert_eq!(11, text.len());
+    assert_eq!("\n  héllo \n", text.slice());
+    assert_eq!("\n  héllo \n", text.to_string());
+    assert_eq!(Ok("\n  héllo \n"), text.as_str());
+
+    let trimmed = text.trim();
+    assert_eq!("héllo", trimmed.slice());
+    assert_eq!((6, 12), (trimmed.start(), trimmed.end()));
+    assert_eq!(&xml[trimmed.start()..trimmed.end()], trimmed.slice());
+    assert!(!trimmed.is_empty());
+
+    let spaces = XmlTokenizer::default().tokenize("<a> \t </a>").unwrap();
+    assert!(texts(&spaces)[0].trim().is_empty());
+}
+
+#[test]
+pub fn test_line_index() {
+    let xml = "<a>\n  <b>x</b>\n\n<c></a>";
+    let index = LineIndex::new(xml);
+    assert_eq!(XmlErrorPos { row: 1, col: 1 }, index.pos(0));
+    // a line feed ends its row
+    assert_eq!(XmlErrorPos { row: 1, col: 4 }, index.pos(3));
+    assert_eq!(XmlErrorPos { row: 2, col: 1 }, index.pos(4));
+    assert_eq!(XmlErrorPos { row: 2, col: 3 }, index.pos(6));
+    assert_eq!(XmlErrorPos { row: 4, col: 3 }, index.pos(18));
+    assert_eq!(index.pos(xml.len()), index.pos(xml.len() + 10));
+
+    // positions agree with those of errors
+    let error = Document::parse(xml).unwrap_err();
+    let c = xml.find("</a>").unwrap() + 2;
+    assert_eq!(error.pos(), Some(&index.pos(c)));
+
+    let xml = "<a>\n  <b>x</b></a>";
+    let x = texts(&XmlTokenizer::default().tokenize(xml).unwrap())[1];
+    assert_eq!((XmlErrorPos { row: 2, col: 6 }, XmlErrorPos { row: 2, col: 7 }), x.line_col(&LineIndex::new(xml)));
+}
+
+#[test]
+pub fn test_line_index_multibyte() {
+    // columns count characters, on the first row and on later ones
+    let xml = "<é a='€'>
+<ü>𝄞x</a></é>";
+    let index = LineIndex::new(xml);
+    assert_eq!(XmlErrorPos { row: 1, col: 4 }, index.pos(xml.find('a').unwrap()));
+    assert_eq!(XmlErrorPos { row: 1, col: 10 }, index.pos(xml.find('\n').unwrap()));
+    assert_eq!(XmlErrorPos { row: 2, col: 5 }, index.pos(xml.find('x').unwrap()));
+
+    let error = Document::parse(xml).unwrap_err();
+    let end_tag = xml.find("</a>").unwrap() + 2;
+    assert_eq!(Some(&XmlErrorPos { row: 2, col: 8 }), error.pos());
+    assert_eq!(error.pos(), Some(&index.pos(end_tag)));
+    let error = Document::parse("<é>€</a>").unwrap_err();
+    assert_eq!(Some(&XmlErrorPos { row: 1, col: 7 }), error.pos());
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "tracing")]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+use jaxp_rust::document::Document;
+
+/// Records the names of spans and the messages of events
+#[derive(Clone, Default)]
+struct Recorder {
+    names: Arc<Mutex<Vec<String>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.names.lock().unwrap().push(span.metadata().name().to_string());
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        self.names.lock().unwrap().push(format!("event {}", event.metadata().target()));
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+pub fn test_spans() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        Document::parse("<!DOCTYPE a [<!ELEMENT a ANY>]><a/>").unwrap();
+        assert!(Document::parse("<a></b>").is_err());
+    });
+    let names = recorder.names.lock().unwrap();
+    for name in ["tokenize", "prolog", "doctype", "internal_subset", "content", "build_tree"] {
+        assert!(names.iter().any(|n| n == name), "{} missing in {:?}", name, names);
+    }
+    assert!(names.iter().filter(|n| n.starts_with("event")).count() >= 3);
+}
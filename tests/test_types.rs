@@ -0,0 +1,88 @@
+use jaxp_rust::binding::{attribute, value};
+use jaxp_rust::document::Document;
+use jaxp_rust::types::{parse_boolean, Date, DateTime, Decimal, Duration};
+
+#[test]
+pub fn test_boolean() {
+    assert_eq!(Some(true), parse_boolean(" 1 "));
+    assert_eq!(Some(false), parse_boolean("false"));
+    assert_eq!(None, parse_boolean("True"));
+    assert_eq!(None, parse_boolean("yes"));
+}
+
+#[test]
+pub fn test_decimal() {
+    let parse = |text| Decimal::parse(text).map(|d| d.to_string());
+    assert_eq!(Some("12.5".to_string()), parse("012.50"));
+    assert_eq!(Some("-0.05".to_string()), parse("-.050"));
+    assert_eq!(Some("7".to_string()), parse("+7."));
+    assert_eq!(Some("0".to_string()), parse("-0.0"));
+    for invalid in ["", ".", "1e3", "1.2.3", "--1", "NaN", " 1 2"] {
+        assert_eq!(None, parse(invalid), "{}", invalid);
+    }
+
+    let d = Decimal::parse("-0.05").unwrap();
+    assert_eq!((-5, 2), (d.unscaled(), d.scale()));
+    assert_eq!((2, 2), (d.total_digits(), d.fraction_digits()));
+    assert_eq!(3, Decimal::parse("123.000").unwrap().total_digits());
+    assert_eq!(Decimal::new(125, 1), Decimal::new(1250, 2));
+    assert_eq!(-0.05, d.to_f64());
+
+    assert!(Decimal::parse("1.5").unwrap() > Decimal::parse("1.49").unwrap());
+    assert!(Decimal::parse("-2").unwrap() < Decimal::parse("-1.999").unwrap());
+    let huge = Decimal::parse("99999999999999999999999999999999999999").unwrap();
+    assert!(huge > Decimal::parse("0.5").unwrap());
+    assert!(Decimal::parse("-0.5").unwrap() > Decimal::new(-huge.unscaled(), 0));
+}
+
+#[test]
+pub fn test_date() {
+    let date = Date::parse("2024-02-29+05:30").unwrap();
+    assert_eq!((2024, 2, 29, Some(330)), (date.year, date.month, date.day, date.opt_timezone));
+    assert_eq!("2024-02-29+05:30", date.to_string());
+    assert_eq!("-0044-03-15", Date::parse("-0044-03-15").unwrap().to_string());
+    assert_eq!("12024-01-01Z", Date::parse("12024-01-01Z").unwrap().to_string());
+    assert_eq!(Some(0), Date::parse("2024-01-01-00:00").unwrap().opt_timezone);
+    for invalid in ["2023-02-29", "2024-13-01", "2024-1-01", "24-01-01", "02024-01-01", "2024-01-01+15:00", "2024-01-01T00:00:00"] {
+        assert_eq!(None, Date::parse(invalid), "{}", invalid);
+    }
+}
+
+#[test]
+pub fn test_date_time() {
+    let time = DateTime::parse(" 2002-10-10T12:00:00.1250-05:00 ").unwrap();
+    assert_eq!((12, 0, 0, 125_000_000, Some(-300)), (time.hour, time.minute, time.second, time.nanosecond, time.opt_timezone));
+    assert_eq!("2002-10-10T12:00:00.125-05:00", time.to_string());
+    assert_eq!(Date::parse("2002-10-10-05:00").unwrap(), time.date());
+    assert_eq!("2000-01-01T00:00:00", DateTime::parse("1999-12-31T24:00:00").unwrap().to_string());
+    for invalid in ["2002-10-10T24:00:01", "2002-10-10T12:60:00", "2002-10-10T12:00", "2002-10-10 12:00:00", "2002-10-10T12:00:00."] {
+        assert_eq!(None, DateTime::parse(invalid), "{}", invalid);
+    }
+}
+
+#[test]
+pub fn test_duration() {
+    let duration = Duration::parse("-P1Y2M3DT4H5M6.5S").unwrap();
+    assert!(duration.negative);
+    assert_eq!((1, 2, 3, 4, 5, 6, 500_000_000), (duration.years, duration.months, duration.days, duration.hours, duration.minutes, duration.seconds, duration.nanoseconds));
+    assert_eq!("-P1Y2M3DT4H5M6.5S", duration.to_string());
+    assert_eq!("P1M", Duration::parse("P1M").unwrap().to_string());
+    assert_eq!("PT1M", Duration::parse("PT1M").unwrap().to_string());
+    assert_eq!("PT0S", Duration::parse("P0D").unwrap().to_string());
+    for invalid in ["P", "PT", "P1DT", "P1D1Y", "P1.5Y", "PT1.5M", "1Y", "P-1D", "P1H"] {
+        assert_eq!(None, Duration::parse(invalid), "{}", invalid);
+    }
+}
+
+#[test]
+pub fn test_binding() {
+    let doc = Document::parse("<order at='2024-05-01T08:30:00Z'><total>19.90</total><ttl>PT1H</ttl></order>").unwrap();
+    let order = doc.root_element();
+    let at: DateTime = attribute(order, "at").unwrap();
+    assert_eq!((2024, 5, 1, 8, 30), (at.year, at.month, at.day, at.hour, at.minute));
+    let total: Decimal = value(order.children_named("total").next().unwrap()).unwrap();
+    assert_eq!("19.9", total.to_string());
+    let ttl: Duration = value(order.children_named("ttl").next().unwrap()).unwrap();
+    assert_eq!(1, ttl.hours);
+    assert!(attribute::<Date>(order, "at").is_err());
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "wbxml")]
+
+use jaxp_rust::token::XmlTokenOwned;
+use jaxp_rust::wbxml::{decode, decode_tokens, CodePageRegistry, CodePages, WbxmlError};
+
+const WML: &str = "-//WAPFORUM//DTD WML 1.1//EN";
+
+fn registry() -> CodePageRegistry {
+    // parts of the Exchange ActiveSync code pages AirSync and FolderHierarchy
+    let mut active_sync = CodePages::default();
+    active_sync.add_tags(0, &[(0x05, "Sync")]);
+    active_sync.add_tags(7, &[(0x07, "DisplayName"), (0x0C, "Status"), (0x12, "SyncKey"), (0x16, "FolderSync")]);
+    // a part of WML 1.1
+    let mut wml = CodePages::default();
+    wml.add_tags(0, &[(0x1C, "a"), (0x20, "p"), (0x27, "wml")]);
+    wml.attribute_starts.insert((0, 0x4A), ("href".to_string(), String::new()));
+    wml.attribute_starts.insert((0, 0x4B), ("href".to_string(), "http://".to_string()));
+    wml.attribute_values.insert((0, 0x85), ".com/".to_string());
+    let mut registry = CodePageRegistry::default();
+    registry.by_id.insert(0x01, active_sync);
+    registry.by_name.insert(WML.to_string(), wml);
+    registry
+}
+
+/// A WML document of version 1.1 in ISO-8859-1, whose public identifier and literal names are in the string table.
+fn wml(body: &[u8]) -> Vec<u8> {
+    let strings = format!("{}\0x\0title\0", WML);
+    [&[0x01, 0x00, 0x00, 0x04, strings.len() as u8][..], strings.as_bytes(), body].concat()
+}
+
+#[test]
+pub fn test_code_pages() {
+    let registry = registry();
+    // version 1.3, public identifier 1, UTF-8, an empty string table
+    let wbxml = [
+        0x03, 0x01, 0x6A, 0x00, //
+        0x00, 0x07, 0x56, 0x52, 0x03, b'0', 0x00, 0x01, 0x47, 0x03, 0xC3, 0xA4, 0x00, 0x01, //
+        0x4C, 0x02, 0x3C, 0x01, 0x00, 0x00, 0x05, 0x01,
+    ];
+    let expected = "<FolderSync><SyncKey>0</SyncKey><DisplayName>ä</DisplayName><Status>&lt;</Status><Sync/></FolderSync>";
+    assert_eq!(expected, decode(&wbxml, &registry).unwrap());
+
+    let (x, title) = (WML.len() as u8 + 1, WML.len() as u8 + 3);
+    let wbxml = wml(&[
+        0x43, 0x04, x, 0x03, b'v', 0x00, 0x01, // a processing instruction with a literal target
+        0x67, 0x60, 0x83, x, // wml and p, with text of the string table
+        0x9C, 0x4B, 0x03, b'a', 0x00, 0x85, 0x04, title, 0x03, 0xE9, 0x00, 0x01, // a, with attributes
+        0x04, title, 0x01, 0x01, // a literal tag
+    ]);
+    let expected = "<?x v?><wml><p>x<a href=\"http://a.com/\" title=\"é\"/><title/></p></wml>";
+    assert_eq!(expected, decode(&wbxml, &registry).unwrap());
+}
+
+#[test]
+pub fn test_decode_tokens() {
+    let wbxml = [0x03, 0x01, 0x6A, 0x00, 0x45, 0x00, 0x07, 0x52, 0x03, b'1', 0x00, 0x01, 0x01];
+    let tokens = decode_tokens(&wbxml, &registry()).unwrap();
+    assert_eq!(5, tokens.len());
+    assert!(matches!(tokens[2], XmlTokenOwned::Text(_)));
+}
+
+#[test]
+pub fn test_errors() {
+    let registry = registry();
+    let header = [0x03, 0x01, 0x6A, 0x00];
+    let document = |body: &[u8]| [&header[..], body].concat();
+    // without registered code pages, only literal tags are known
+    assert!(matches!(decode(&document(&[0x05]), &CodePageRegistry::default()), Err(WbxmlError::UnknownToken { pos: 4, page: 0, token: 0x05 })));
+    assert!(matches!(decode(&document(&[0x06]), &registry), Err(WbxmlError::UnknownToken { pos: 4, page: 0, token: 0x06 })));
+    assert!(matches!(decode(&document(&[0x45]), &registry), Err(WbxmlError::UnexpectedEnd)));
+    assert!(matches!(decode(&document(&[0x05, 0x05]), &registry), Err(WbxmlError::IllegalToken { pos: 5, token: 0x05 })));
+    assert!(matches!(decode(&document(&[0x01]), &registry), Err(WbxmlError::IllegalToken { pos: 4, token: 0x01 })));
+    assert!(matches!(decode(&document(&[0x45, 0xC0, 0x01]), &registry), Err(WbxmlError::Unsupported(_))));
+    assert!(matches!(decode(&document(&[0x45, 0x03, 0xFF, 0x00, 0x01]), &registry), Err(WbxmlError::InvalidString { pos: 6 })));
+    assert!(matches!(decode(&document(&[0x04, 0x00]), &registry), Err(WbxmlError::InvalidStringTableIndex { pos: 5, index: 0 })));
+    assert!(matches!(decode(&document(&[0x45, 0x02, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0x01]), &registry), Err(WbxmlError::IllegalToken { pos: 10, .. })));
+    assert!(matches!(decode(&[0x04, 0x01, 0x6A, 0x00], &registry), Err(WbxmlError::UnsupportedVersion(0x04))));
+    assert!(matches!(decode(&[0x03, 0x01, 0x87, 0x77, 0x00], &registry), Err(WbxmlError::Unsupported(_))));
+    // a literal name that is not a name, and an attribute value without an attribute
+    assert!(matches!(decode(&wml(&[0x04, 0x00]), &registry), Err(WbxmlError::InvalidString { pos: 43 })));
+    assert!(matches!(decode(&wml(&[0x9C, 0x85, 0x01]), &registry), Err(WbxmlError::IllegalToken { token: 0x85, .. })));
+}
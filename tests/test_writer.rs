@@ -0,0 +1,341 @@
+use std::collections::HashSet;
+
+use jaxp_rust::document::{Document, XmlVersion};
+use jaxp_rust::escape::EscapeStyle;
+use jaxp_rust::writer::{serialize, serialize_minified, serialize_pretty, serialize_with_options, write_node_with_options, InlineElements, LineEnding, NamespaceOptions, OutputEncoding, PrettyPrintOptions, QuoteStyle, SerializeOptions, TrailingComments, XmlWriter};
+
+#[test]
+pub fn test_round_trip() {
+    let xml = "<root a=\"1\" b='\"q\"'>text &amp; more<a/><!--comment--><![CDATA[<cdata>]]><?pi value?><b>x</b></root>";
+    let doc = Document::parse(xml).unwrap();
+    assert_eq!(xml, serialize(doc.root()));
+    assert_eq!("<b>x</b>", serialize(doc.root_element().last_child().unwrap()));
+}
+
+#[test]
+pub fn test_writer_escapes() {
+    let mut writer = XmlWriter::new(Vec::new());
+    writer.start_element("root").unwrap();
+    writer.attribute("attr", "\"<&>\"").unwrap();
+    writer.text("a < b & c").unwrap();
+    writer.start_element("empty").unwrap();
+    writer.end_element().unwrap();
+    writer.end_element().unwrap();
+    assert!(writer.end_element().is_err());
+    let actual = String::from_utf8(writer.into_inner()).unwrap();
+    assert_eq!("<root attr=\"&quot;&lt;&amp;&gt;&quot;\">a &lt; b &amp; c<empty/></root>", actual);
+}
+
+#[test]
+pub fn test_pretty_print() {
+    let xml = "<root>  <a><b/><c>text</c></a>\n<p>mixed <b>content</b></p><!--comment--></root>";
+    let doc = Document::parse(xml).unwrap();
+    let expected = "<root>\n  <a>\n    <b/>\n    <c>text</c>\n  </a>\n  <p>mixed <b>content</b></p>\n  <!--comment-->\n</root>";
+    assert_eq!(expected, serialize_pretty(doc.root(), &PrettyPrintOptions::default()));
+
+    let doc = Document::parse("<!--c--><a><b>x\ny</b></a>").unwrap();
+    let options = PrettyPrintOptions { line_ending: LineEnding::CrLf, ..PrettyPrintOptions::spaces(1) };
+    assert_eq!("<!--c-->\r\n<a>\r\n <b>x\ny</b>\r\n</a>", serialize_pretty(doc.root(), &options));
+    let options = PrettyPrintOptions { line_ending: LineEnding::CrLf, ..PrettyPrintOptions::tabs() };
+    assert_eq!("<a>\r\n\t<b>x\ny</b>\r\n</a>", serialize_pretty(doc.root_element(), &options));
+}
+
+#[test]
+pub fn test_pretty_print_inline_elements() {
+    let xml = "<div><p><b>bold</b><i>italic</i></p><ul><li><a><img/></a></li></ul></div>";
+    let doc = Document::parse(xml).unwrap();
+    let names: HashSet<String> = ["a", "b", "i"].iter().map(|n| n.to_string()).collect();
+    let options = PrettyPrintOptions { inline_elements: InlineElements::Names(names), ..PrettyPrintOptions::default() };
+    let expected = "<div>\n  <p><b>bold</b><i>italic</i></p>\n  <ul>\n    <li><a><img/></a></li>\n  </ul>\n</div>";
+    assert_eq!(expected, serialize_pretty(doc.root(), &options));
+
+    fn is_inline(node: jaxp_rust::document::Node) -> bool {
+        !["div", "p", "ul"].contains(&node.tag_name())
+    }
+    let options = PrettyPrintOptions { inline_elements: InlineElements::Predicate(is_inline), ..PrettyPrintOptions::default() };
+    assert_eq!("<div>\n  <p><b>bold</b><i>italic</i></p>\n  <ul><li><a><img/></a></li></ul>\n</div>", serialize_pretty(doc.root(), &options));
+}
+
+#[test]
+pub fn test_pretty_print_trailing_comments() {
+    let xml = "<!--config--><config>\n<!--server-->\n<host>localhost</host> <!--name-->\n<port>80</port><!--default--><!--own line-->\n<tls><on/></tls> <!--tls--></config>";
+    let doc = Document::parse(xml).unwrap();
+    let with_trailing = |trailing_comments| PrettyPrintOptions { trailing_comments, ..PrettyPrintOptions::default() };
+    let expected = "<!--config-->\n<config>\n  <!--server-->\n  <host>localhost</host>\n  <!--name-->\n  <port>80</port>\n  <!--default-->\n  <!--own line-->\n  <tls>\n    <on/>\n  </tls>\n  <!--tls-->\n</config>";
+    assert_eq!(expected, serialize_pretty(doc.root(), &with_trailing(TrailingComments::OwnLine)));
+    let expected = "<!--config-->\n<config>\n  <!--server-->\n  <host>localhost</host> <!--name-->\n  <port>80</port> <!--default-->\n  <!--own line-->\n  <tls>\n    <on/>\n  </tls> <!--tls-->\n</config>";
+    assert_eq!(expected, serialize_pretty(doc.root(), &with_trailing(TrailingComments::Keep)));
+    let expected = "<!--config-->\n<config>\n  <!--server-->\n  <host>localhost</host> <!--name-->\n  <port>80</port>        <!--default-->\n  <!--own line-->\n  <tls>\n    <on/>\n  </tls>                 <!--tls-->\n</config>";
+    assert_eq!(expected, serialize_pretty(doc.root(), &with_trailing(TrailingComments::Align)));
+
+    // aligned lines are rendered before they are written, in the encoding of the writer
+    let doc = Document::parse("<a><b x='€'>€</b><!--c--></a>").unwrap();
+    let mut writer = XmlWriter::new(Vec::new());
+    writer.set_encoding(OutputEncoding::Latin1);
+    let options = SerializeOptions { pretty: Some(with_trailing(TrailingComments::Align)), ..SerializeOptions::default() };
+    write_node_with_options(doc.root(), &mut writer, &options).unwrap();
+    assert_eq!(b"<a>\n  <b x=\"&#x20AC;\">&#x20AC;</b> <!--c-->\n</a>".to_vec(), writer.finish().unwrap());
+}
+
+#[test]
+pub fn test_pretty_print_xml_space() {
+    let xml = "<root><pre xml:space=\"preserve\">  <a> <b/></a>  <c xml:space=\"default\"><d/></c></pre></root>";
+    let doc = Document::parse(xml).unwrap();
+    let expected = "<root>\n\t<pre xml:space=\"preserve\">  <a> <b/></a>  <c xml:space=\"default\">\n\t\t\t<d/>\n\t\t</c></pre>\n</root>";
+    let options = PrettyPrintOptions::tabs();
+    assert_eq!(expected, serialize_pretty(doc.root(), &options));
+
+    // preserved regions are detected from ancestors as well
+    let a = doc.descendants().find(|n| n.tag_name() == "a").unwrap();
+    assert_eq!("<a> <b/></a>", serialize_pretty(a, &options));
+}
+
+fn namespace_options(preferred: &[(&str, &str)], hoist: bool, drop_redundant: bool) -> SerializeOptions {
+    SerializeOptions {
+        namespaces: Some(NamespaceOptions {
+            preferred_prefixes: preferred.iter().map(|(u, p)| (u.to_string(), p.to_string())).collect(),
+            hoist_declarations: hoist,
+            drop_redundant_declarations: drop_redundant,
+        }),
+        ..SerializeOptions::default()
+    }
+}
+
+#[test]
+pub fn test_rewrite_prefixes() {
+    let xml = "<a:root xmlns:a=\"urn:x\"><a:child b:attr=\"1\" xmlns:b=\"urn:y\"/></a:root>";
+    let doc = Document::parse(xml).unwrap();
+
+    let options = namespace_options(&[("urn:x", "x")], false, true);
+    let expected = "<x:root xmlns:x=\"urn:x\"><x:child xmlns:b=\"urn:y\" b:attr=\"1\"/></x:root>";
+    assert_eq!(expected, serialize_with_options(doc.root(), &options));
+
+    let options = namespace_options(&[("urn:x", "x")], true, true);
+    let expected = "<x:root xmlns:b=\"urn:y\" xmlns:x=\"urn:x\"><x:child b:attr=\"1\"/></x:root>";
+    assert_eq!(expected, serialize_with_options(doc.root(), &options));
+
+    // attributes cannot use the default namespace
+    let options = namespace_options(&[("urn:x", ""), ("urn:y", "")], false, true);
+    let expected = "<root xmlns=\"urn:x\"><child xmlns:ns1=\"urn:y\" ns1:attr=\"1\"/></root>";
+    assert_eq!(expected, serialize_with_options(doc.root(), &options));
+}
+
+#[test]
+pub fn test_redundant_declarations() {
+    let xml = "<root xmlns=\"urn:d\"><child xmlns=\"urn:d\"/><other xmlns:unused=\"urn:u\"/><none xmlns=\"\"/></root>";
+    let doc = Document::parse(xml).unwrap();
+
+    let options = namespace_options(&[], false, true);
+    let expected = "<root xmlns=\"urn:d\"><child/><other/><none xmlns=\"\"/></root>";
+    assert_eq!(expected, serialize_with_options(doc.root(), &options));
+
+    let options = namespace_options(&[], false, false);
+    let expected = "<root xmlns=\"urn:d\"><child xmlns=\"urn:d\"/><other xmlns:unused=\"urn:u\"/><none xmlns=\"\"/></root>";
+    assert_eq!(expected, serialize_with_options(doc.root(), &options));
+}
+
+#[test]
+pub fn test_prefix_clashes() {
+    let xml = "<r><p:a xmlns:p=\"urn:1\"/><p:b xmlns:p=\"urn:2\"/></r>";
+    let doc = Document::parse(xml).unwrap();
+    let options = namespace_options(&[], true, true);
+    let expected = "<r xmlns:ns1=\"urn:2\" xmlns:p=\"urn:1\"><p:a/><ns1:b/></r>";
+    assert_eq!(expected, serialize_with_options(doc.root(), &options));
+}
+
+#[test]
+pub fn test_minify() {
+    let xml = "<root>\n  <!-- comment -->\n  <a x=\"1\">\n    <b/>\n  </a>\n  <p>mixed <b>content</b> kept</p>\n  <pre xml:space=\"preserve\">\n    <c/>\n  </pre>\n</root>";
+    let doc = Document::parse(xml).unwrap();
+    assert_eq!(
+        "<root><a x=\"1\"><b/></a><p>mixed <b>content</b> kept</p><pre xml:space=\"preserve\">\n    <c/>\n  </pre></root>",
+        serialize_minified(doc.root())
+    );
+}
+
+#[test]
+pub fn test_sorted_attributes() {
+    let xml = "<a z=\"1\" xmlns:q=\"urn:b\" q:y=\"2\" xmlns=\"urn:d\" b=\"3\" xmlns:p=\"urn:a\" p:y=\"4\" xml:lang=\"en\"/>";
+    let doc = Document::parse(xml).unwrap();
+    let sorted = SerializeOptions { sort_attributes: true, ..Default::default() };
+    let expected = "<a xmlns=\"urn:d\" xmlns:p=\"urn:a\" xmlns:q=\"urn:b\" b=\"3\" z=\"1\" xml:lang=\"en\" p:y=\"4\" q:y=\"2\"/>";
+    assert_eq!(expected, serialize_with_options(doc.root(), &sorted));
+    // the order of the input does not matter
+    let shuffled = "<a xml:lang=\"en\" p:y=\"4\" b=\"3\" xmlns:p=\"urn:a\" q:y=\"2\" xmlns=\"urn:d\" z=\"1\" xmlns:q=\"urn:b\"/>";
+    assert_eq!(expected, serialize_with_options(Document::parse(shuffled).unwrap().root(), &sorted));
+
+    let rewritten = SerializeOptions { sort_attributes: true, namespaces: Some(NamespaceOptions::default()), ..Default::default() };
+    assert_eq!(expected, serialize_with_options(doc.root(), &rewritten));
+}
+
+#[test]
+pub fn test_copy_unchanged() {
+    let xml = "<root>\n  <a x='1' y = \"&lt;&gt;\" z=\">\" ></a >\n  <b><c   /><d x='2'/></b>\n</root>";
+    let mut doc = Document::parse(xml).unwrap();
+    let copy = SerializeOptions { copy_unchanged: true, ..SerializeOptions::default() };
+    assert_eq!(xml, serialize_with_options(doc.root(), &copy));
+    let a = doc.descendants().find(|n| n.tag_name() == "a").unwrap();
+    assert_eq!(Some("<a x='1' y = \"&lt;&gt;\" z=\">\" ></a >"), a.original_markup());
+    let c = doc.descendants().find(|n| n.tag_name() == "c").unwrap();
+    assert_eq!(Some("<c   />"), c.original_markup());
+
+    // the edited element and its ancestors are written node by node, other subtrees are copied
+    let d = doc.descendants().find(|n| n.tag_name() == "d").unwrap().id();
+    doc.node_mut(d).unwrap().set_attribute("x", "3");
+    assert_eq!(None, doc.root_element().original_markup());
+    assert_eq!(
+        "<root>\n  <a x='1' y = \"&lt;&gt;\" z=\">\" ></a >\n  <b><c   /><d x=\"3\"/></b>\n</root>",
+        serialize_with_options(doc.root(), &copy)
+    );
+    // added elements have no markup to copy
+    let b = doc.descendants().find(|n| n.tag_name() == "b").unwrap().id();
+    let e = doc.node_mut(b).unwrap().append_element("e");
+    assert_eq!(None, doc.get_node(e).unwrap().original_markup());
+
+    // other options rewrite the markup
+    let sorted = SerializeOptions { sort_attributes: true, ..copy };
+    assert!(serialize_with_options(doc.root(), &sorted).contains("<a x=\"1\" y=\"&lt;&gt;\" z=\">\"/>"));
+    let quoted = SerializeOptions { copy_unchanged: true, quote_style: Some(QuoteStyle::Double), ..SerializeOptions::default() };
+    assert!(serialize_with_options(doc.root(), &quoted).contains("<a x=\"1\" y=\"&lt;&gt;\" z=\">\"/>"));
+    let doc = Document::parse("<a x=\"1\"><b y=\"2\"/></a>").unwrap();
+    let single = SerializeOptions { quote_style: Some(QuoteStyle::Single), ..quoted };
+    assert_eq!("<a x='1'><b y='2'/></a>", serialize_with_options(doc.root(), &single));
+}
+
+#[test]
+pub fn test_quote_and_escape_styles() {
+    let mut writer = XmlWriter::new(Vec::new());
+    writer.set_quote_style(QuoteStyle::Single);
+    writer.set_escape_style(EscapeStyle::Minimal);
+    writer.start_element("a").unwrap();
+    writer.attribute("x", "it's > \"1\"").unwrap();
+    writer.attribute_quoted("y", "it's", '"').unwrap();
+    writer.attribute_raw("z", "it's").unwrap();
+    assert!(writer.attribute_quoted("w", "1", '`').is_err());
+    writer.text("a > b ]]>").unwrap();
+    writer.end_element().unwrap();
+    assert_eq!("<a x='it&apos;s > \"1\"' y=\"it's\" z=\"it's\">a > b ]]&gt;</a>", String::from_utf8(writer.finish().unwrap()).unwrap());
+
+    let xml = "<a x='1' y=\"2\" z='\"3\"'><b v='4'/></a>";
+    let mut doc = Document::parse(xml).unwrap();
+    let with_quotes = |quote_style| SerializeOptions { quote_style: Some(quote_style), ..SerializeOptions::default() };
+    assert_eq!("<a x='1' y='2' z='\"3\"'><b v='4'/></a>", serialize_with_options(doc.root(), &with_quotes(QuoteStyle::Single)));
+    assert_eq!("<a x=\"1\" y=\"2\" z='\"3\"'><b v=\"4\"/></a>", serialize_with_options(doc.root(), &with_quotes(QuoteStyle::Double)));
+    assert_eq!(xml, serialize_with_options(doc.root(), &with_quotes(QuoteStyle::Preserve)));
+    // edited values have no quotes to preserve
+    let b = doc.root_element().first_child().unwrap().id();
+    doc.node_mut(b).unwrap().set_attribute("v", "5");
+    assert_eq!("<a x='1' y=\"2\" z='\"3\"'><b v=\"5\"/></a>", serialize_with_options(doc.root(), &with_quotes(QuoteStyle::Preserve)));
+}
+
+#[test]
+pub fn test_xml_declaration_and_encoding() {
+    let mut writer = XmlWriter::new(Vec::new());
+    assert!(writer.xml_declaration(&XmlVersion::Other("2.0".to_string()), None).is_err());
+    writer.xml_declaration(&XmlVersion::V1_0, Some(true)).unwrap();
+    assert!(writer.xml_declaration(&XmlVersion::V1_0, None).is_err());
+    writer.start_element("a").unwrap();
+    writer.end_element().unwrap();
+    assert_eq!("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?><a/>", String::from_utf8(writer.finish().unwrap()).unwrap());
+
+    let mut writer = XmlWriter::new(Vec::new());
+    writer.set_encoding(OutputEncoding::Utf16Be);
+    writer.xml_declaration(&XmlVersion::V1_1, None).unwrap();
+    writer.start_element("a").unwrap();
+    writer.text("\u{1F600}").unwrap();
+    writer.end_element().unwrap();
+    let expected: Vec<u8> = std::iter::once(0xFEFF)
+        .chain("<?xml version=\"1.1\" encoding=\"UTF-16\"?><a>\u{1F600}</a>".encode_utf16())
+        .flat_map(u16::to_be_bytes)
+        .collect();
+    assert_eq!(expected, writer.finish().unwrap());
+
+    let mut writer = XmlWriter::new(Vec::new());
+    writer.set_encoding(OutputEncoding::Utf16Le);
+    writer.start_element("é").unwrap();
+    writer.end_element().unwrap();
+    assert_eq!(vec![0xFF, 0xFE, b'<', 0, 0xE9, 0, b'/', 0, b'>', 0], writer.finish().unwrap());
+
+    // characters beyond ISO-8859-1 are escaped where references are allowed
+    let mut writer = XmlWriter::new(Vec::new());
+    writer.set_encoding(OutputEncoding::Latin1);
+    writer.xml_declaration(&XmlVersion::V1_0, None).unwrap();
+    writer.start_element("a").unwrap();
+    writer.attribute("x", "é€").unwrap();
+    writer.text("ü\u{1F600}").unwrap();
+    let error = writer.comment("€").unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+    writer.end_element().unwrap();
+    let expected = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a x=\"\xE9&#x20AC;\">\xFC&#x1F600;</a>";
+    assert_eq!(expected.to_vec(), writer.finish().unwrap());
+
+    // copied markup would bypass the escaping
+    let doc = Document::parse("<a><b x='€'>€</b></a>").unwrap();
+    let mut writer = XmlWriter::new(Vec::new());
+    writer.set_encoding(OutputEncoding::Latin1);
+    let copy = SerializeOptions { copy_unchanged: true, ..SerializeOptions::default() };
+    write_node_with_options(doc.root(), &mut writer, &copy).unwrap();
+    assert_eq!(b"<a><b x=\"&#x20AC;\">&#x20AC;</b></a>".to_vec(), writer.finish().unwrap());
+
+    let doc = Document::parse("<?xml version='1.1' standalone='no'?>\n<a>\n</a>").unwrap();
+    let options = SerializeOptions { xml_declaration: true, ..SerializeOptions::default() };
+    assert_eq!("<?xml version=\"1.1\" encoding=\"UTF-8\" standalone=\"no\"?><a>\n</a>", serialize_with_options(doc.root(), &options));
+    let doc = Document::parse("<a><b/></a>").unwrap();
+    let options = SerializeOptions { xml_declaration: true, pretty: Some(PrettyPrintOptions::default()), ..SerializeOptions::default() };
+    assert_eq!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<a>\n  <b/>\n</a>", serialize_with_options(doc.root(), &options));
+}
+
+#[test]
+pub fn test_writer_checks() {
+    let mut writer = XmlWriter::new(Vec::new());
+    assert!(writer.start_element("1a").is_err());
+    writer.start_element("root").unwrap();
+    writer.attribute("a", "1").unwrap();
+    assert!(writer.attribute("a", "2").is_err());
+    assert!(writer.attribute("a b", "2").is_err());
+    assert!(writer.attribute_raw("b", "<").is_err());
+    assert!(writer.attribute_raw("b", "\"'").is_err());
+    assert!(writer.attribute("b", "\u{0}").is_err());
+    writer.attribute_raw("b", "&amp;&#x41;&custom;").unwrap();
+    for raw in ["a & b", "&#0;", "&1a;", "]]>", "<b/>"] {
+        assert!(writer.text_raw(raw).is_err(), "{}", raw);
+    }
+    assert!(writer.cdata_section("]]>").is_err());
+    assert!(writer.comment("a--b").is_err());
+    assert!(writer.comment("a-").is_err());
+    assert!(writer.processing_instruction("xml", Some("version=\"1.0\"")).is_err());
+    assert!(writer.processing_instruction("pi", Some("?>")).is_err());
+    writer.start_element("child").unwrap();
+    // the attributes of another element
+    writer.attribute("a", "1").unwrap();
+    writer.end_element().unwrap();
+    writer.markup_raw("<copied/>").unwrap();
+    let error = writer.finish().unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidInput, error.kind());
+
+    let mut writer = XmlWriter::new(Vec::new());
+    writer.start_element("root").unwrap();
+    writer.end_element().unwrap();
+    let xml = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!("<root/>", xml);
+
+    // nothing is written for rejected input
+    let mut writer = XmlWriter::new(Vec::new());
+    writer.start_element("root").unwrap();
+    writer.attribute("a", "1").unwrap();
+    assert!(writer.text_raw("<").is_err());
+    writer.end_element().unwrap();
+    assert_eq!(b"<root a=\"1\"/>".to_vec(), writer.finish().unwrap());
+}
+
+#[test]
+pub fn test_unchecked_writer() {
+    let mut writer = XmlWriter::unchecked(Vec::new());
+    writer.start_element("1a").unwrap();
+    writer.attribute("a", "1").unwrap();
+    writer.attribute("a", "2").unwrap();
+    writer.comment("--").unwrap();
+    writer.end_element().unwrap();
+    assert!(writer.end_element().is_err());
+    assert_eq!("<1a a=\"1\" a=\"2\"><!------></1a>", String::from_utf8(writer.into_inner()).unwrap());
+}
@@ -0,0 +1,27 @@
+use jaxp_rust::xmlchar::{XmlByte, XmlChar};
+
+#[test]
+pub fn test_classes_of_all_chars() {
+    for c in (0..=0x10FFFF).filter_map(char::from_u32) {
+        let is_char = matches!(c, '\u{9}' | '\u{A}' | '\u{D}' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..='\u{10FFFF}');
+        let is_name_start_char = matches!(c,
+            ':' | 'A'..='Z' | '_' | 'a'..='z' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}' |
+            '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}' |
+            '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' |
+            '\u{10000}'..='\u{EFFFF}'
+        );
+        let is_name_char = is_name_start_char || matches!(c, '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}');
+        assert_eq!(is_char, c.is_xml_char(), "{:?}", c);
+        assert_eq!(is_name_start_char, c.is_xml_name_start_char(), "{:?}", c);
+        assert_eq!(is_name_char, c.is_xml_name_char(), "{:?}", c);
+    }
+}
+
+#[test]
+pub fn test_classes_of_bytes() {
+    for b in 0..=u8::MAX {
+        assert_eq!(matches!(b, b' ' | b'\n' | b'\t' | b'\r'), b.is_xml_whitespace());
+        assert_eq!(matches!(b, b'"' | b'\''), b.is_xml_quote());
+        assert_eq!(b.is_ascii_alphanumeric() || b" \r\n-'()+,./:=?;!*#@$_%".contains(&b), b.is_xml_pubid_char(), "{}", b);
+    }
+}
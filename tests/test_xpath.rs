@@ -0,0 +1,82 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::xpath::{Item, Value, XPath, XPathError};
+
+fn evaluate(doc: &Document, expression: &str) -> String {
+    XPath::compile(expression).unwrap().evaluate(doc.root()).unwrap().string()
+}
+
+fn names(doc: &Document, expression: &str) -> Vec<String> {
+    XPath::compile(expression).unwrap().select(doc.root()).unwrap().iter().map(|i| i.name().to_string()).collect()
+}
+
+#[test]
+pub fn test_paths() {
+    let doc = Document::parse("<a x=\"1\" xmlns:p=\"urn:p\"><b y=\"2\"><c/><p:c/></b><!--n--><d>t<![CDATA[u]]></d><?pi v?></a>").unwrap();
+    assert_eq!(vec!["a"], names(&doc, "/a"));
+    assert_eq!(vec!["c", "p:c"], names(&doc, "/a/b/*"));
+    assert_eq!(vec!["c"], names(&doc, "//c"));
+    assert_eq!(vec!["p:c"], names(&doc, "//p:*"));
+    assert_eq!(vec!["x", "y"], names(&doc, "//@*"));
+    assert_eq!(vec!["b", "d"], names(&doc, "a/*[1] | /a/d"));
+    assert_eq!(vec!["a", "b"], names(&doc, "//c/ancestor::*"));
+    assert_eq!(vec!["b"], names(&doc, "//c/.."));
+    assert_eq!(vec!["d"], names(&doc, "/a/b/following-sibling::*"));
+    assert_eq!(vec!["pi"], names(&doc, "//processing-instruction('pi')"));
+    // positions on reverse axes count from the context node
+    assert_eq!(vec!["b"], names(&doc, "//p:c/ancestor::*[1]"));
+    assert_eq!(vec!["c"], names(&doc, "//p:c/preceding-sibling::node()[1]"));
+    assert_eq!("tu", evaluate(&doc, "/a/d"));
+    assert_eq!("u", evaluate(&doc, "/a/d/text()[2]"));
+    assert_eq!("n", evaluate(&doc, "//comment()"));
+    assert_eq!("v", evaluate(&doc, "//processing-instruction()"));
+    assert_eq!("2", evaluate(&doc, "//b/@y"));
+}
+
+#[test]
+pub fn test_operators_and_functions() {
+    let doc = Document::parse("<r><i n=\"3\">a &amp; b</i><i n=\"4\"> x  y </i><i n=\"x\"/></r>").unwrap();
+    for (expression, expected) in [
+        ("1 + 2 * 3 - 4 div 2", "5"),
+        ("7 mod 3", "1"),
+        ("-(1 + 1)", "-2"),
+        ("1 div 0", "Infinity"),
+        ("0.5 + 0.25", "0.75"),
+        ("sum(//i[position() < 3]/@n)", "7"),
+        ("sum(//@n)", "NaN"),
+        ("count(//i[@n > 3])", "1"),
+        ("count(//i[@n = 'x' or @n = 3])", "2"),
+        ("//i[@n = 4] = ' x  y '", "true"),
+        ("//i != 'a & b'", "true"),
+        ("//i[last()]/@n", "x"),
+        ("normalize-space(//i[2])", "x y"),
+        ("concat(name(/r), '-', local-name(//i), '-', string(1 = 1))", "r-i-true"),
+        ("substring('12345', 1.5, 2.6)", "234"),
+        ("substring('12345', 0, 3)", "12"),
+        ("substring-before('a=b', '=')", "a"),
+        ("substring-after('a=b', '=')", "b"),
+        ("translate('abc', 'abc', 'AB')", "AB"),
+        ("string-length(//i)", "5"),
+        ("starts-with(//i, 'a') and contains(//i, '&')", "true"),
+        ("not(//missing)", "true"),
+        ("round(2.5) + floor(-1.5) + ceiling(1.2)", "3"),
+        ("number(' 12 ') + number('1e2')", "NaN"),
+    ] {
+        assert_eq!(expected, evaluate(&doc, expression), "{}", expression);
+    }
+    let i = XPath::compile("//i[2]").unwrap().select(doc.root()).unwrap()[0];
+    assert_eq!(Value::Number(4.0), XPath::compile("number(@n)").unwrap().evaluate(i.node()).unwrap());
+    assert_eq!(Value::Nodes(vec![Item::Attribute(i.node(), 0)]), XPath::compile("@n").unwrap().evaluate(i.node()).unwrap());
+}
+
+#[test]
+pub fn test_errors() {
+    assert!(matches!(XPath::compile("/a["), Err(XPathError::Syntax { pos: 3, .. })));
+    assert!(matches!(XPath::compile("a b"), Err(XPathError::Syntax { pos: 2, .. })));
+    assert!(matches!(XPath::compile("'a"), Err(XPathError::Syntax { pos: 0, .. })));
+    assert!(matches!(XPath::compile("following::a"), Err(XPathError::Syntax { .. })));
+    assert_eq!(Err(XPathError::UnknownFunction("count#0".to_string())), XPath::compile("count()"));
+    assert_eq!(Err(XPathError::UnknownFunction("key#2".to_string())), XPath::compile("key('k', 1)"));
+    let doc = Document::parse("<a/>").unwrap();
+    assert!(matches!(XPath::compile("'a'/b").unwrap().evaluate(doc.root()), Err(XPathError::Type(_))));
+    assert!(matches!(XPath::compile("1 + 1").unwrap().select(doc.root()), Err(XPathError::Type(_))));
+}
@@ -0,0 +1,94 @@
+use jaxp_rust::document::Document;
+use jaxp_rust::xslt::{Stylesheet, XsltError};
+
+fn transform(templates: &str, xml: &str) -> Result<String, XsltError> {
+    let xsl = format!("<xsl:stylesheet version=\"1.0\" xmlns:xsl=\"http://www.w3.org/1999/XSL/Transform\">{}</xsl:stylesheet>", templates);
+    Stylesheet::parse(&xsl)?.transform(&Document::parse(xml).unwrap())
+}
+
+#[test]
+pub fn test_templates() {
+    let templates = r#"
+        <xsl:template match="/">
+            <html><xsl:apply-templates select="catalog/book"/></html>
+        </xsl:template>
+        <xsl:template match="book">
+            <p class="book-{position()}">
+                <xsl:attribute name="title"><xsl:value-of select="title"/></xsl:attribute>
+                <xsl:apply-templates select="author" mode="short"/>
+            </p>
+        </xsl:template>
+        <xsl:template match="book[@lang = 'de']" priority="1">
+            <xsl:element name="{@lang}-book"><xsl:value-of select="title"/></xsl:element>
+        </xsl:template>
+        <xsl:template match="author" mode="short"><xsl:text>by </xsl:text><xsl:value-of select="."/></xsl:template>
+        <xsl:template match="author"><ignored/></xsl:template>
+    "#;
+    let xml = "<catalog><book><title>A &amp; B</title><author>X</author></book><book lang='de'><title>C</title></book></catalog>";
+    assert_eq!("<html><p class=\"book-1\" title=\"A &amp; B\">by X</p><de-book>C</de-book></html>", transform(templates, xml).unwrap());
+}
+
+#[test]
+pub fn test_built_in_rules() {
+    // text is copied, comments and processing instructions are dropped
+    let templates = "<xsl:template match=\"b\">[<xsl:apply-templates/>]</xsl:template>";
+    assert_eq!("x[y&lt;]z", transform(templates, "<a>x<!--c--><b>y&lt;<?pi?></b>z</a>").unwrap());
+    // the last of the matching rules with the same priority wins
+    let templates = "<xsl:template match=\"*\">1</xsl:template><xsl:template match=\"b|c\">2</xsl:template><xsl:template match=\"c\">3</xsl:template>";
+    assert_eq!("3", transform(templates, "<c/>").unwrap());
+    assert_eq!("1", transform(templates, "<d/>").unwrap());
+    let templates = "<xsl:template match=\"a//c/text()\">T</xsl:template><xsl:template match=\"/a/@x\">X</xsl:template>\
+        <xsl:template match=\"a\"><xsl:apply-templates select=\"@*|node()\"/></xsl:template>";
+    assert_eq!("X2T", transform(templates, "<a x=\"1\" y=\"2\"><b><c>t</c></b></a>").unwrap());
+}
+
+#[test]
+pub fn test_instructions() {
+    let templates = r#"
+        <xsl:template match="/list">
+            <ul>
+                <xsl:for-each select="item">
+                    <xsl:if test="position() != last()"><li><xsl:value-of select="position()"/>. <xsl:value-of select="."/></li></xsl:if>
+                    <xsl:choose>
+                        <xsl:when test="@n &gt; 5"><big/></xsl:when>
+                        <xsl:when test="@n &gt; 1"><medium/></xsl:when>
+                        <xsl:otherwise><xsl:call-template name="small"/></xsl:otherwise>
+                    </xsl:choose>
+                </xsl:for-each>
+                <xsl:copy-of select="item[1]"/>
+                <xsl:copy-of select="count(item)"/>
+                <xsl:comment>a--b-</xsl:comment>
+            </ul>
+        </xsl:template>
+        <xsl:template name="small"><small><xsl:copy><xsl:attribute name="n">0</xsl:attribute></xsl:copy></small></xsl:template>
+    "#;
+    let xml = "<list><item n=\"1\">a<!--c--></item><item n=\"7\">b</item></list>";
+    assert_eq!(
+        "<ul><li>1. a</li><small><item n=\"0\"/></small><big/><item n=\"1\">a<!--c--></item>2<!--a- -b- --></ul>",
+        transform(templates, xml).unwrap()
+    );
+}
+
+#[test]
+pub fn test_literal_result_element_stylesheet() {
+    let xsl = "<html xsl:version=\"1.0\" xmlns:xsl=\"http://www.w3.org/1999/XSL/Transform\" xmlns:h=\"urn:h\">\
+        <h:title><xsl:value-of select=\"/doc/title\"/></h:title></html>";
+    let doc = Document::parse("<doc><title>T</title></doc>").unwrap();
+    assert_eq!("<html xmlns:h=\"urn:h\"><h:title>T</h:title></html>", Stylesheet::parse(xsl).unwrap().transform(&doc).unwrap());
+}
+
+#[test]
+pub fn test_errors() {
+    assert!(matches!(transform("<xsl:variable name=\"v\"/>", "<a/>"), Err(XsltError::Unsupported(name)) if name == "xsl:variable"));
+    let unsupported = "<xsl:template match=\"a\"><xsl:apply-templates><xsl:sort/></xsl:apply-templates></xsl:template>";
+    assert!(matches!(transform(unsupported, "<a/>"), Err(XsltError::Unsupported(name)) if name == "xsl:sort"));
+    assert!(matches!(transform("<xsl:template/>", "<a/>"), Err(XsltError::InvalidStylesheet(_))));
+    assert!(matches!(transform("<xsl:template match=\"count(a)\"/>", "<a/>"), Err(XsltError::InvalidStylesheet(_))));
+    assert!(matches!(transform("<xsl:template match=\"a\"><b c=\"{@d\"/></xsl:template>", "<a/>"), Err(XsltError::InvalidStylesheet(_))));
+    assert!(matches!(transform("<xsl:template match=\"a[\"/>", "<a/>"), Err(XsltError::XPath(_))));
+    assert!(matches!(transform("<xsl:template match=\"a\">", "<a/>"), Err(XsltError::Parse(_))));
+    let late_attribute = "<xsl:template match=\"a\"><b>t<xsl:attribute name=\"c\">d</xsl:attribute></b></xsl:template>";
+    assert!(matches!(transform(late_attribute, "<a/>"), Err(XsltError::Output(_))));
+    let endless = "<xsl:template match=\"a\"><xsl:apply-templates select=\".\"/></xsl:template>";
+    assert!(matches!(transform(endless, "<a/>"), Err(XsltError::RecursionLimitExceeded)));
+}